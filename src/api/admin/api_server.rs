@@ -16,6 +16,8 @@ use prometheus::{Encoder, TextEncoder};
 
 use garage_model::garage::Garage;
 use garage_rpc::system::ClusterHealthStatus;
+use garage_util::background::BackgroundRunner;
+use garage_util::config::AdminScope;
 use garage_util::error::Error as GarageError;
 use garage_util::socket_address::UnixOrTCPSocketAddress;
 
@@ -23,23 +25,34 @@ use crate::generic_server::*;
 
 use crate::admin::bucket::*;
 use crate::admin::cluster::*;
+use crate::admin::deletion::{jwt_identity, token_identity};
 use crate::admin::error::*;
+use crate::admin::group::*;
+use crate::admin::job::*;
 use crate::admin::key::*;
+use crate::admin::oidc::OidcValidator;
+use crate::admin::repair::*;
 use crate::admin::router_v0;
 use crate::admin::router_v1::{Authorization, Endpoint};
-use crate::helpers::host_to_bucket;
+use crate::admin::spec::handle_get_spec;
+use crate::helpers::{host_id, host_to_bucket, host_to_bucket_multi};
 
 pub struct AdminApiServer {
 	garage: Arc<Garage>,
+	background: Arc<BackgroundRunner>,
 	#[cfg(feature = "metrics")]
 	exporter: PrometheusExporter,
 	metrics_token: Option<String>,
-	admin_token: Option<String>,
+	/// Expected authorization headers for the admin tokens,
+	/// with the identities of the tokens (see `token_identity`)
+	admin_tokens: Vec<(String, String)>,
+	oidc: Option<OidcValidator>,
 }
 
 impl AdminApiServer {
 	pub fn new(
 		garage: Arc<Garage>,
+		background: Arc<BackgroundRunner>,
 		#[cfg(feature = "metrics")] exporter: PrometheusExporter,
 	) -> Self {
 		let cfg = &garage.config.admin;
@@ -47,16 +60,21 @@ impl AdminApiServer {
 			.metrics_token
 			.as_ref()
 			.map(|tok| format!("Bearer {}", tok));
-		let admin_token = cfg
+		let admin_tokens = cfg
 			.admin_token
-			.as_ref()
-			.map(|tok| format!("Bearer {}", tok));
+			.iter()
+			.chain(cfg.additional_admin_tokens.iter())
+			.map(|tok| (format!("Bearer {}", tok), token_identity(tok)))
+			.collect();
+		let oidc = cfg.oidc.clone().map(OidcValidator::new);
 		Self {
 			garage,
+			background,
 			#[cfg(feature = "metrics")]
 			exporter,
 			metrics_token,
-			admin_token,
+			admin_tokens,
+			oidc,
 		}
 	}
 
@@ -66,9 +84,16 @@ impl AdminApiServer {
 		shutdown_signal: impl Future<Output = ()>,
 	) -> Result<(), GarageError> {
 		let region = self.garage.config.s3_api.s3_region.clone();
-		ApiServer::new(region, self)
-			.run_server(bind_addr, Some(0o220), shutdown_signal)
-			.await
+		let in_flight_requests = self.garage.in_flight_requests.clone();
+		ApiServer::new(
+			region,
+			host_id(&self.garage),
+			self,
+			in_flight_requests,
+			false,
+		)
+		.run_server(bind_addr, Some(0o220), shutdown_signal)
+		.await
 	}
 
 	fn handle_options(&self, _req: &Request<Body>) -> Result<Response<Body>, Error> {
@@ -117,13 +142,8 @@ impl AdminApiServer {
 	async fn check_domain(&self, domain: &str) -> Result<bool, Error> {
 		// Resolve bucket from domain name, inferring if the website must be activated for the
 		// domain to be valid.
-		let (bucket_name, must_check_website) = if let Some(bname) = self
-			.garage
-			.config
-			.s3_api
-			.root_domain
-			.as_ref()
-			.and_then(|rd| host_to_bucket(domain, rd))
+		let (bucket_name, must_check_website) = if let Some(bname) =
+			host_to_bucket_multi(domain, &self.garage.config.s3_api.all_root_domains())
 		{
 			(bname.to_string(), false)
 		} else if let Some(bname) = self
@@ -219,6 +239,39 @@ impl AdminApiServer {
 			"Garage was built without the metrics feature".to_string(),
 		))
 	}
+
+	/// Validate a JWT given as bearer token, and check that it gives at least
+	/// the `required` scope. Returns an identifier of the bearer of the token.
+	async fn check_jwt(&self, authorization: &str, required: AdminScope) -> Result<String, Error> {
+		let (oidc, token) = match (&self.oidc, authorization.strip_prefix("Bearer ")) {
+			(Some(oidc), Some(token)) => (oidc, token.trim()),
+			_ => return Err(Error::forbidden("Invalid authorization token provided")),
+		};
+		let identity = oidc.validate(token).await?;
+		match identity.scope {
+			Some(scope) if scope >= required => Ok(jwt_identity(&identity.subject)),
+			_ => Err(Error::forbidden(
+				"The provided token does not give access to this endpoint",
+			)),
+		}
+	}
+
+	/// The web UI is a static page, that asks for the admin token and
+	/// calls the JSON endpoints of the admin API from the browser
+	fn handle_web_ui(&self) -> Result<Response<Body>, Error> {
+		#[cfg(feature = "web-ui")]
+		{
+			Ok(Response::builder()
+				.status(StatusCode::OK)
+				.header(http::header::CONTENT_TYPE, "text/html; charset=utf-8")
+				.header(http::header::X_FRAME_OPTIONS, "DENY")
+				.body(Body::from(include_str!("web_ui/index.html")))?)
+		}
+		#[cfg(not(feature = "web-ui"))]
+		Err(Error::bad_request(
+			"Garage was built without the web-ui feature".to_string(),
+		))
+	}
 }
 
 #[async_trait]
@@ -243,28 +296,31 @@ impl ApiHandler for AdminApiServer {
 		req: Request<Body>,
 		endpoint: Endpoint,
 	) -> Result<Response<Body>, Error> {
-		let expected_auth_header =
-			match endpoint.authorization_type() {
-				Authorization::None => None,
-				Authorization::MetricsToken => self.metrics_token.as_ref(),
-				Authorization::AdminToken => match &self.admin_token {
-					None => return Err(Error::forbidden(
-						"Admin token isn't configured, admin API access is disabled for security.",
-					)),
-					Some(t) => Some(t),
-				},
-			};
-
-		if let Some(h) = expected_auth_header {
-			match req.headers().get("Authorization") {
-				None => return Err(Error::forbidden("Authorization token must be provided")),
-				Some(v) => {
-					let authorized = v.to_str().map(|hv| hv.trim() == h).unwrap_or(false);
-					if !authorized {
-						return Err(Error::forbidden("Invalid authorization token provided"));
+		// Identity of the admin token with which the request was made,
+		// or of the subject of the JWT
+		let mut admin_token = String::new();
+		match endpoint.authorization_type() {
+			Authorization::None => (),
+			Authorization::MetricsToken => {
+				if let Some(h) = &self.metrics_token {
+					let provided = authorization_header(&req)?;
+					if provided != h.as_str() {
+						self.check_jwt(provided, AdminScope::Metrics).await?;
 					}
 				}
 			}
+			Authorization::AdminToken => {
+				if self.admin_tokens.is_empty() && self.oidc.is_none() {
+					return Err(Error::forbidden(
+						"Admin token isn't configured, admin API access is disabled for security.",
+					));
+				}
+				let provided = authorization_header(&req)?;
+				admin_token = match self.admin_tokens.iter().find(|(h, _)| h == provided) {
+					Some((_, identity)) => identity.clone(),
+					None => self.check_jwt(provided, required_scope(&endpoint)).await?,
+				};
+			}
 		}
 
 		match endpoint {
@@ -272,14 +328,22 @@ impl ApiHandler for AdminApiServer {
 			Endpoint::CheckDomain => self.handle_check_domain(req).await,
 			Endpoint::Health => self.handle_health(),
 			Endpoint::Metrics => self.handle_metrics(),
+			Endpoint::WebUi => self.handle_web_ui(),
+			Endpoint::GetSpec => handle_get_spec(&self.garage).await,
 			Endpoint::GetClusterStatus => handle_get_cluster_status(&self.garage).await,
 			Endpoint::GetClusterHealth => handle_get_cluster_health(&self.garage).await,
 			Endpoint::ConnectClusterNodes => handle_connect_cluster_nodes(&self.garage, req).await,
+			Endpoint::GetLogFilter => handle_get_log_filter(&self.garage).await,
+			Endpoint::SetLogFilter => handle_set_log_filter(&self.garage, req).await,
+			Endpoint::ReloadConfig => handle_reload_config(&self.garage).await,
+			Endpoint::GetFaultInjection => handle_get_fault_injection(&self.garage).await,
+			Endpoint::SetFaultInjection => handle_set_fault_injection(&self.garage, req).await,
 			// Layout
 			Endpoint::GetClusterLayout => handle_get_cluster_layout(&self.garage).await,
 			Endpoint::UpdateClusterLayout => handle_update_cluster_layout(&self.garage, req).await,
 			Endpoint::ApplyClusterLayout => handle_apply_cluster_layout(&self.garage, req).await,
 			Endpoint::RevertClusterLayout => handle_revert_cluster_layout(&self.garage, req).await,
+			Endpoint::GetLayoutRebalance => handle_get_layout_rebalance(&self.garage).await,
 			// Keys
 			Endpoint::ListKeys => handle_list_keys(&self.garage).await,
 			Endpoint::GetKeyInfo {
@@ -293,15 +357,62 @@ impl ApiHandler for AdminApiServer {
 			Endpoint::CreateKey => handle_create_key(&self.garage, req).await,
 			Endpoint::ImportKey => handle_import_key(&self.garage, req).await,
 			Endpoint::UpdateKey { id } => handle_update_key(&self.garage, id, req).await,
-			Endpoint::DeleteKey { id } => handle_delete_key(&self.garage, id).await,
+			Endpoint::ClaimKeySecret { id } => handle_claim_key_secret(&self.garage, id).await,
+			Endpoint::DeleteKey { id } => {
+				handle_delete_key(&self.garage, id, req.headers(), &admin_token).await
+			}
+			Endpoint::GetKeyUsage {
+				id,
+				start,
+				end,
+				granularity,
+			} => handle_get_key_usage(&self.garage, id, start, end, granularity).await,
 			// Buckets
 			Endpoint::ListBuckets => handle_list_buckets(&self.garage).await,
-			Endpoint::GetBucketInfo { id, global_alias } => {
-				handle_get_bucket_info(&self.garage, id, global_alias).await
+			Endpoint::GetBucketInfo {
+				id,
+				global_alias,
+				show_secret,
+			} => {
+				let show_secret = show_secret.map(|x| x == "true").unwrap_or(false);
+				handle_get_bucket_info(&self.garage, id, global_alias, show_secret).await
 			}
 			Endpoint::CreateBucket => handle_create_bucket(&self.garage, req).await,
-			Endpoint::DeleteBucket { id } => handle_delete_bucket(&self.garage, id).await,
+			Endpoint::DeleteBucket { id } => {
+				handle_delete_bucket(&self.garage, id, req.headers(), &admin_token).await
+			}
 			Endpoint::UpdateBucket { id } => handle_update_bucket(&self.garage, id, req).await,
+			Endpoint::ImportBucket { id } => {
+				handle_import_bucket(&self.garage, &self.background, id, req).await
+			}
+			Endpoint::GetBucketDedupStats { id } => {
+				handle_get_bucket_dedup_stats(&self.garage, id).await
+			}
+			Endpoint::EmptyBucket { id } => {
+				handle_empty_bucket(&self.garage, &self.background, id, req).await
+			}
+			Endpoint::GetEmptyBucketStatus { id } => {
+				handle_get_empty_bucket_status(&self.garage, id).await
+			}
+			Endpoint::CloneBucket { id } => {
+				handle_clone_bucket(&self.garage, &self.background, id, req).await
+			}
+			Endpoint::UpdateBucketObjectsMetadata { id } => {
+				handle_update_bucket_objects_metadata(&self.garage, &self.background, id, req).await
+			}
+			Endpoint::ListBucketTrash { id, prefix, start } => {
+				handle_list_bucket_trash(&self.garage, id, prefix, start).await
+			}
+			Endpoint::RestoreBucketTrash { id } => {
+				handle_restore_bucket_trash(&self.garage, id, req).await
+			}
+			Endpoint::PresignBucketObjects { id } => {
+				handle_presign_bucket_objects(&self.garage, id, req).await
+			}
+			Endpoint::GetBucketWebUsage { start, end, limit } => {
+				handle_get_bucket_web_usage(&self.garage, start, end, limit).await
+			}
+			Endpoint::GetBucketUsage => handle_get_bucket_usage(&self.garage).await,
 			// Bucket-key permissions
 			Endpoint::BucketAllowKey => {
 				handle_bucket_change_key_perm(&self.garage, req, true).await
@@ -309,23 +420,51 @@ impl ApiHandler for AdminApiServer {
 			Endpoint::BucketDenyKey => {
 				handle_bucket_change_key_perm(&self.garage, req, false).await
 			}
+			// Permission groups
+			Endpoint::ListGroups => handle_list_groups(&self.garage).await,
+			Endpoint::GetGroupInfo { id } => handle_get_group_info(&self.garage, id).await,
+			Endpoint::CreateGroup => handle_create_group(&self.garage, req).await,
+			Endpoint::UpdateGroup { id } => handle_update_group(&self.garage, id, req).await,
+			Endpoint::DeleteGroup { id } => {
+				handle_delete_group(&self.garage, id, req.headers()).await
+			}
+			Endpoint::GroupAllowBucket => {
+				handle_group_change_bucket_perm(&self.garage, req, true).await
+			}
+			Endpoint::GroupDenyBucket => {
+				handle_group_change_bucket_perm(&self.garage, req, false).await
+			}
 			// Bucket aliasing
 			Endpoint::GlobalAliasBucket { id, alias } => {
-				handle_global_alias_bucket(&self.garage, id, alias).await
+				handle_global_alias_bucket(&self.garage, id, alias, req.headers()).await
 			}
 			Endpoint::GlobalUnaliasBucket { id, alias } => {
-				handle_global_unalias_bucket(&self.garage, id, alias).await
+				handle_global_unalias_bucket(&self.garage, id, alias, req.headers()).await
 			}
 			Endpoint::LocalAliasBucket {
 				id,
 				access_key_id,
 				alias,
-			} => handle_local_alias_bucket(&self.garage, id, access_key_id, alias).await,
+			} => {
+				handle_local_alias_bucket(&self.garage, id, access_key_id, alias, req.headers())
+					.await
+			}
 			Endpoint::LocalUnaliasBucket {
 				id,
 				access_key_id,
 				alias,
-			} => handle_local_unalias_bucket(&self.garage, id, access_key_id, alias).await,
+			} => {
+				handle_local_unalias_bucket(&self.garage, id, access_key_id, alias, req.headers())
+					.await
+			}
+			// Repairs
+			Endpoint::LaunchRepair => {
+				handle_launch_repair(&self.garage, &self.background, req).await
+			}
+			// Jobs
+			Endpoint::ListJobs => handle_list_jobs(&self.garage).await,
+			Endpoint::GetJob { id } => handle_get_job(&self.garage, id).await,
+			Endpoint::CancelJob { id } => handle_cancel_job(&self.garage, id).await,
 		}
 	}
 }
@@ -337,3 +476,50 @@ impl ApiEndpoint for Endpoint {
 
 	fn add_span_attributes(&self, _span: SpanRef<'_>) {}
 }
+
+/// Scope that a JWT must give to call an endpoint that requires the admin
+/// token: the endpoints listed here that don't modify the cluster only
+/// require the read scope, unless they are asked to reveal secrets
+fn required_scope(endpoint: &Endpoint) -> AdminScope {
+	match endpoint {
+		Endpoint::GetKeyInfo {
+			show_secret_key: Some(show),
+			..
+		}
+		| Endpoint::GetBucketInfo {
+			show_secret: Some(show),
+			..
+		} if show == "true" => AdminScope::Admin,
+		Endpoint::GetClusterStatus
+		| Endpoint::GetClusterHealth
+		| Endpoint::GetLogFilter
+		| Endpoint::GetFaultInjection
+		| Endpoint::GetClusterLayout
+		| Endpoint::GetLayoutRebalance
+		| Endpoint::ListKeys
+		| Endpoint::GetKeyInfo { .. }
+		| Endpoint::GetKeyUsage { .. }
+		| Endpoint::ListBuckets
+		| Endpoint::GetBucketInfo { .. }
+		| Endpoint::GetBucketDedupStats { .. }
+		| Endpoint::GetEmptyBucketStatus { .. }
+		| Endpoint::ListBucketTrash { .. }
+		| Endpoint::GetBucketWebUsage { .. }
+		| Endpoint::GetBucketUsage
+		| Endpoint::ListGroups
+		| Endpoint::GetGroupInfo { .. }
+		| Endpoint::ListJobs
+		| Endpoint::GetJob { .. } => AdminScope::Read,
+		_ => AdminScope::Admin,
+	}
+}
+
+fn authorization_header(req: &Request<Body>) -> Result<&str, Error> {
+	match req.headers().get("Authorization") {
+		None => Err(Error::forbidden("Authorization token must be provided")),
+		Some(v) => v
+			.to_str()
+			.map(str::trim)
+			.map_err(|_| Error::forbidden("Invalid authorization token provided")),
+	}
+}
@@ -1,26 +1,46 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use hyper::{Body, Request, Response, StatusCode};
+use hyper::{header::HeaderValue, Body, HeaderMap, Request, Response, StatusCode};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use garage_sigv4::{presign_request, SigningParams, SERVICE_S3};
+
+use garage_util::background::BackgroundRunner;
 use garage_util::crdt::*;
 use garage_util::data::*;
 use garage_util::time::*;
 
+use garage_rpc::ring::Ring;
+
+use garage_table::replication::QuorumPolicy;
 use garage_table::*;
 
 use garage_model::bucket_alias_table::*;
 use garage_model::bucket_table::*;
 use garage_model::garage::Garage;
+use garage_model::helper::object::current_version;
+use garage_model::key_usage::{total_usage, window_sort_key, USAGE_WINDOW_MSEC};
 use garage_model::permission::*;
+use garage_model::s3::clone_bucket_worker::CloneBucketWorker;
+use garage_model::s3::empty_bucket_worker::EmptyBucketWorker;
 use garage_model::s3::mpu_table;
 use garage_model::s3::object_table::*;
+use garage_model::s3::trash::*;
+use garage_model::web_usage;
 
+use crate::admin::deletion::*;
 use crate::admin::error::*;
-use crate::admin::key::ApiBucketKeyPerm;
+use crate::admin::key::{parse_usage_date, ApiBucketKeyPerm};
+use crate::admin::precondition::*;
+use crate::admin::spec::ApiSpec;
 use crate::common_error::CommonError;
-use crate::helpers::{json_ok_response, parse_json_body};
+use crate::encoding::uri_encode;
+use crate::helpers::{is_default, json_ok_response, parse_json_body};
+use crate::s3::import::{start_import, ImportParams};
+use crate::s3::update_metadata::{start_metadata_update, MetadataUpdateParams};
 
 pub async fn handle_list_buckets(garage: &Arc<Garage>) -> Result<Response<Body>, Error> {
 	let buckets = garage
@@ -64,7 +84,7 @@ pub async fn handle_list_buckets(garage: &Arc<Garage>) -> Result<Response<Body>,
 	Ok(json_ok_response(&res)?)
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 struct ListBucketResultItem {
 	id: String,
@@ -72,24 +92,48 @@ struct ListBucketResultItem {
 	local_aliases: Vec<BucketLocalAlias>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 struct BucketLocalAlias {
 	access_key_id: String,
 	alias: String,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 struct ApiBucketQuotas {
 	max_size: Option<u64>,
 	max_objects: Option<u64>,
+	#[serde(default)]
+	soft: bool,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct ApiBucketAlarms {
+	max_size: Option<u64>,
+	max_objects: Option<u64>,
+	quota_percent: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct ApiObjectDefaults {
+	cache_control: Option<String>,
+	content_disposition: Option<String>,
+	#[serde(default)]
+	content_type_from_extension: bool,
+	#[serde(default)]
+	sniff_content_type: bool,
+	#[serde(default)]
+	strip_meta: Vec<String>,
 }
 
 pub async fn handle_get_bucket_info(
 	garage: &Arc<Garage>,
 	id: Option<String>,
 	global_alias: Option<String>,
+	show_secret: bool,
 ) -> Result<Response<Body>, Error> {
 	let bucket_id = match (id, global_alias) {
 		(Some(id), None) => parse_bucket_id(&id)?,
@@ -105,17 +149,21 @@ pub async fn handle_get_bucket_info(
 		}
 	};
 
-	bucket_info_results(garage, bucket_id).await
+	bucket_info_results(garage, bucket_id, show_secret).await
 }
 
+/// Information about a bucket, including the secret with which website URLs
+/// are signed only if `show_secret` is set
 async fn bucket_info_results(
 	garage: &Arc<Garage>,
 	bucket_id: Uuid,
+	show_secret: bool,
 ) -> Result<Response<Body>, Error> {
 	let bucket = garage
 		.bucket_helper()
 		.get_existing_bucket(bucket_id)
 		.await?;
+	let version = resource_version(&bucket)?;
 
 	let counters = garage
 		.object_counter_table
@@ -133,6 +181,14 @@ async fn bucket_info_results(
 		.map(|x| x.filtered_values(&garage.system.ring.borrow()))
 		.unwrap_or_default();
 
+	let trash_counters = garage
+		.object_counter_table
+		.table
+		.get(&trash_partition(&bucket_id), &EmptyKey)
+		.await?
+		.map(|x| x.filtered_values(&garage.system.ring.borrow()))
+		.unwrap_or_default();
+
 	let mut relevant_keys = HashMap::new();
 	for (k, _) in bucket
 		.state
@@ -171,9 +227,24 @@ async fn bucket_info_results(
 		}
 	}
 
+	let groups = garage
+		.permission_group_helper()
+		.list_groups()
+		.await?
+		.into_iter()
+		.filter(|g| g.bucket_permissions(&bucket_id).is_any())
+		.map(|g| GetBucketInfoGroup {
+			id: hex::encode(g.id),
+			name: g.params().unwrap().name.get().clone(),
+			permissions: ApiBucketKeyPerm::from(&g.bucket_permissions(&bucket_id)),
+		})
+		.collect::<Vec<_>>();
+
 	let state = bucket.state.as_option().unwrap();
 
 	let quotas = state.quotas.get();
+	let alarms = state.alarms.get();
+	let object_defaults = state.object_defaults.get();
 	let res =
 		GetBucketInfoResult {
 			id: hex::encode(bucket.id),
@@ -185,10 +256,23 @@ async fn bucket_info_results(
 				.map(|(n, _, _)| n.to_string())
 				.collect::<Vec<_>>(),
 			website_access: state.website_config.get().is_some(),
+			public_read: *state.public_read.get(),
+			website_signing: state
+				.website_signing
+				.get()
+				.clone()
+				.map(|s| ApiWebsiteSigning {
+					secret: Some(s.secret).filter(|_| show_secret),
+					prefixes: s.prefixes,
+				}),
 			website_config: state.website_config.get().clone().map(|wsc| {
 				GetBucketInfoWebsiteResult {
 					index_document: wsc.index_document,
 					error_document: wsc.error_document,
+					directory_listing: wsc.directory_listing,
+					error_documents: wsc.error_documents,
+					cache_control: wsc.cache_control,
+					expires_after: wsc.expires_after,
 				}
 			}),
 			keys: relevant_keys
@@ -201,11 +285,7 @@ async fn bucket_info_results(
 						permissions: p
 							.authorized_buckets
 							.get(&bucket.id)
-							.map(|p| ApiBucketKeyPerm {
-								read: p.allow_read,
-								write: p.allow_write,
-								owner: p.allow_owner,
-							})
+							.map(ApiBucketKeyPerm::from)
 							.unwrap_or_default(),
 						bucket_local_aliases: p
 							.local_aliases
@@ -217,8 +297,14 @@ async fn bucket_info_results(
 					}
 				})
 				.collect::<Vec<_>>(),
+			groups,
 			objects: *counters.get(OBJECTS).unwrap_or(&0),
 			bytes: *counters.get(BYTES).unwrap_or(&0),
+			storage_classes: storage_class_usage(&counters),
+			zones: zone_usage(
+				*counters.get(BYTES).unwrap_or(&0),
+				&garage.system.ring.borrow(),
+			),
 			unfinished_uploads: *counters.get(UNFINISHED_UPLOADS).unwrap_or(&0),
 			unfinished_multipart_uploads: *mpu_counters.get(mpu_table::UPLOADS).unwrap_or(&0),
 			unfinished_multipart_upload_parts: *mpu_counters.get(mpu_table::PARTS).unwrap_or(&0),
@@ -226,13 +312,33 @@ async fn bucket_info_results(
 			quotas: ApiBucketQuotas {
 				max_size: quotas.max_size,
 				max_objects: quotas.max_objects,
+				soft: quotas.soft,
+			},
+			alarms: ApiBucketAlarms {
+				max_size: alarms.max_size,
+				max_objects: alarms.max_objects,
+				quota_percent: alarms.quota_percent,
 			},
+			trash_retention_days: *state.trash_retention_days.get(),
+			trash_objects: *trash_counters.get(OBJECTS).unwrap_or(&0),
+			trash_bytes: *trash_counters.get(BYTES).unwrap_or(&0),
+			quorum_policy: state.quorum_policy.get().as_str().to_string(),
+			first_block_sizes: state.first_block_sizes.get().clone().unwrap_or_default(),
+			object_defaults: ApiObjectDefaults {
+				cache_control: object_defaults.cache_control.clone(),
+				content_disposition: object_defaults.content_disposition.clone(),
+				content_type_from_extension: object_defaults.content_type_from_extension,
+				sniff_content_type: object_defaults.sniff_content_type,
+				strip_meta: object_defaults.strip_meta.clone(),
+			},
+			deletion_protection: *state.deletion_protection.get(),
+			pending_deletion: pending_deletion(&state.pending_deletion),
 		};
 
-	Ok(json_ok_response(&res)?)
+	with_version(json_ok_response(&res)?, &version)
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 struct GetBucketInfoResult {
 	id: String,
@@ -240,24 +346,50 @@ struct GetBucketInfoResult {
 	website_access: bool,
 	#[serde(default)]
 	website_config: Option<GetBucketInfoWebsiteResult>,
+	website_signing: Option<ApiWebsiteSigning>,
+	public_read: bool,
 	keys: Vec<GetBucketInfoKey>,
+	groups: Vec<GetBucketInfoGroup>,
 	objects: i64,
 	bytes: i64,
+	storage_classes: Vec<ApiStorageClassUsage>,
+	zones: Vec<ApiZoneUsage>,
 	unfinished_uploads: i64,
 	unfinished_multipart_uploads: i64,
 	unfinished_multipart_upload_parts: i64,
 	unfinished_multipart_upload_bytes: i64,
 	quotas: ApiBucketQuotas,
+	alarms: ApiBucketAlarms,
+	trash_retention_days: Option<u64>,
+	trash_objects: i64,
+	trash_bytes: i64,
+	quorum_policy: String,
+	first_block_sizes: Vec<u64>,
+	object_defaults: ApiObjectDefaults,
+	deletion_protection: bool,
+	pending_deletion: Option<ApiPendingDeletion>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 struct GetBucketInfoWebsiteResult {
 	index_document: String,
 	error_document: Option<String>,
+	directory_listing: bool,
+	error_documents: BTreeMap<u16, String>,
+	cache_control: Option<String>,
+	expires_after: Option<u64>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct ApiWebsiteSigning {
+	#[serde(skip_serializing_if = "is_default")]
+	secret: Option<String>,
+	prefixes: Vec<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 struct GetBucketInfoKey {
 	access_key_id: String,
@@ -266,6 +398,14 @@ struct GetBucketInfoKey {
 	bucket_local_aliases: Vec<String>,
 }
 
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct GetBucketInfoGroup {
+	id: String,
+	name: String,
+	permissions: ApiBucketKeyPerm,
+}
+
 pub async fn handle_create_bucket(
 	garage: &Arc<Garage>,
 	req: Request<Body>,
@@ -338,17 +478,17 @@ pub async fn handle_create_bucket(
 		}
 	}
 
-	bucket_info_results(garage, bucket.id).await
+	bucket_info_results(garage, bucket.id, false).await
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 struct CreateBucketRequest {
 	global_alias: Option<String>,
 	local_alias: Option<CreateBucketLocalAlias>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 struct CreateBucketLocalAlias {
 	access_key_id: String,
@@ -360,19 +500,36 @@ struct CreateBucketLocalAlias {
 pub async fn handle_delete_bucket(
 	garage: &Arc<Garage>,
 	id: String,
+	headers: &HeaderMap,
+	admin_token: &str,
 ) -> Result<Response<Body>, Error> {
 	let helper = garage.bucket_helper();
 
 	let bucket_id = parse_bucket_id(&id)?;
 
 	let mut bucket = helper.get_existing_bucket(bucket_id).await?;
-	let state = bucket.state.as_option().unwrap();
+	check_if_match(garage, headers, &resource_version(&bucket)?)?;
 
 	// Check bucket is empty
 	if !helper.is_bucket_empty(bucket_id).await? {
 		return Err(CommonError::BucketNotEmpty.into());
 	}
 
+	// Protected buckets are only deleted when the deletion is confirmed
+	// with a second admin token
+	if bucket.deletion_protection() {
+		let state = bucket.state.as_option_mut().unwrap();
+		if !request_deletion(garage, &mut state.pending_deletion, admin_token)? {
+			garage.bucket_table.insert(&bucket).await?;
+			let state = bucket.state.as_option().unwrap();
+			return deletion_requested_response(
+				&state.pending_deletion,
+				&resource_version(&bucket)?,
+			);
+		}
+	}
+	let state = bucket.state.as_option().unwrap();
+
 	// --- done checking, now commit ---
 	// 1. delete authorization from keys that had access
 	for (key_id, perm) in bucket.authorized_keys() {
@@ -411,59 +568,862 @@ pub async fn handle_update_bucket(
 	id: String,
 	req: Request<Body>,
 ) -> Result<Response<Body>, Error> {
-	let req = parse_json_body::<UpdateBucketRequest>(req).await?;
 	let bucket_id = parse_bucket_id(&id)?;
 
 	let mut bucket = garage
 		.bucket_helper()
 		.get_existing_bucket(bucket_id)
 		.await?;
+	check_if_match(garage, req.headers(), &resource_version(&bucket)?)?;
+
+	let req = parse_json_body::<UpdateBucketRequest>(req).await?;
 
 	let state = bucket.state.as_option_mut().unwrap();
 
 	if let Some(wa) = req.website_access {
 		if wa.enabled {
+			let error_documents = wa.error_documents.unwrap_or_default();
+			if error_documents.keys().any(|c| !(400..600).contains(c)) {
+				return Err(Error::bad_request(
+					"Keys of errorDocuments must be 4xx or 5xx HTTP status codes.",
+				));
+			}
+			if let Some(cc) = &wa.cache_control {
+				HeaderValue::from_str(cc)
+					.ok_or_bad_request("cacheControl is not a valid header value.")?;
+			}
+			if wa.expires_after.unwrap_or(0) > MAX_WEBSITE_EXPIRES_AFTER {
+				return Err(Error::bad_request(format!(
+					"expiresAfter cannot be more than {} seconds.",
+					MAX_WEBSITE_EXPIRES_AFTER
+				)));
+			}
+			// Routing rules can only be set through the S3 API, keep them
+			let routing_rules = state
+				.website_config
+				.get()
+				.as_ref()
+				.map(|c| c.routing_rules.clone())
+				.unwrap_or_default();
 			state.website_config.update(Some(WebsiteConfig {
 				index_document: wa.index_document.ok_or_bad_request(
 					"Please specify indexDocument when enabling website access.",
 				)?,
 				error_document: wa.error_document,
+				directory_listing: wa.directory_listing.unwrap_or(false),
+				routing_rules,
+				error_documents,
+				cache_control: wa.cache_control,
+				expires_after: wa.expires_after,
 			}));
 		} else {
-			if wa.index_document.is_some() || wa.error_document.is_some() {
+			if wa.index_document.is_some()
+				|| wa.error_document.is_some()
+				|| wa.directory_listing.is_some()
+				|| wa.error_documents.is_some()
+				|| wa.cache_control.is_some()
+				|| wa.expires_after.is_some()
+			{
 				return Err(Error::bad_request(
-					"Cannot specify indexDocument or errorDocument when disabling website access.",
+					"Cannot specify indexDocument, errorDocument, errorDocuments, directoryListing, cacheControl or expiresAfter when disabling website access.",
 				));
 			}
 			state.website_config.update(None);
 		}
 	}
 
+	if let Some(pr) = req.public_read {
+		state.public_read.update(pr);
+	}
+
 	if let Some(q) = req.quotas {
 		state.quotas.update(BucketQuotas {
 			max_size: q.max_size,
 			max_objects: q.max_objects,
+			soft: q.soft,
+		});
+	}
+
+	if let Some(a) = req.alarms {
+		if matches!(a.quota_percent, Some(p) if !(1..=100).contains(&p)) {
+			return Err(Error::bad_request(
+				"quotaPercent must be between 1 and 100.",
+			));
+		}
+		state.alarms.update(BucketAlarms {
+			max_size: a.max_size,
+			max_objects: a.max_objects,
+			quota_percent: a.quota_percent,
+		});
+	}
+
+	// The new secret is returned to the caller that configured signing
+	let show_secret = req.website_signing.is_some();
+	if let Some(ws) = req.website_signing {
+		if ws.enabled {
+			let prefixes = ws.prefixes.unwrap_or_default();
+			// The secret is kept unless a new one is requested,
+			// so that URLs that were already signed remain valid
+			let signing = match state.website_signing.get() {
+				Some(s) if !ws.new_secret => WebsiteSigning {
+					secret: s.secret.clone(),
+					prefixes,
+				},
+				_ => WebsiteSigning::new(prefixes),
+			};
+			state.website_signing.update(Some(signing));
+		} else {
+			state.website_signing.update(None);
+		}
+	}
+
+	if let Some(days) = req.trash_retention_days {
+		// 0 disables the trash
+		state
+			.trash_retention_days
+			.update(Some(days).filter(|d| *d > 0));
+	}
+
+	if let Some(qp) = req.quorum_policy {
+		let qp = QuorumPolicy::parse(&qp)
+			.ok_or_bad_request("Invalid quorumPolicy, expected one of: quorum, all, one.")?;
+		state.quorum_policy.update(qp);
+	}
+
+	if let Some(fbs) = req.first_block_sizes {
+		if fbs.contains(&0) {
+			return Err(Error::bad_request("firstBlockSizes cannot contain 0."));
+		}
+		state
+			.first_block_sizes
+			.update(Some(fbs).filter(|s| !s.is_empty()));
+	}
+
+	if let Some(od) = req.object_defaults {
+		for (name, value) in [
+			("cacheControl", &od.cache_control),
+			("contentDisposition", &od.content_disposition),
+		] {
+			if let Some(v) = value {
+				HeaderValue::from_str(v)
+					.ok_or_bad_request(format!("{} is not a valid header value.", name))?;
+			}
+		}
+		state.object_defaults.update(ObjectDefaults {
+			cache_control: od.cache_control,
+			content_disposition: od.content_disposition,
+			content_type_from_extension: od.content_type_from_extension,
+			sniff_content_type: od.sniff_content_type,
+			strip_meta: od
+				.strip_meta
+				.iter()
+				.map(|m| {
+					let m = m.to_lowercase();
+					m.strip_prefix("x-amz-meta-").unwrap_or(&m).to_string()
+				})
+				.collect(),
 		});
 	}
 
+	update_deletion_protection(
+		&mut state.deletion_protection,
+		&mut state.pending_deletion,
+		req.deletion_protection,
+		req.cancel_deletion,
+	)?;
+
 	garage.bucket_table.insert(&bucket).await?;
 
-	bucket_info_results(garage, bucket_id).await
+	bucket_info_results(garage, bucket_id, show_secret).await
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 struct UpdateBucketRequest {
 	website_access: Option<UpdateBucketWebsiteAccess>,
+	public_read: Option<bool>,
+	website_signing: Option<UpdateBucketWebsiteSigning>,
 	quotas: Option<ApiBucketQuotas>,
+	alarms: Option<ApiBucketAlarms>,
+	trash_retention_days: Option<u64>,
+	quorum_policy: Option<String>,
+	first_block_sizes: Option<Vec<u64>>,
+	object_defaults: Option<ApiObjectDefaults>,
+	/// Require a second admin token to delete the bucket. Can only be
+	/// disabled with the garage CLI.
+	deletion_protection: Option<bool>,
+	/// Cancel a pending deletion of the bucket
+	#[serde(default)]
+	cancel_deletion: bool,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 struct UpdateBucketWebsiteAccess {
 	enabled: bool,
 	index_document: Option<String>,
 	error_document: Option<String>,
+	directory_listing: Option<bool>,
+	error_documents: Option<BTreeMap<u16, String>>,
+	cache_control: Option<String>,
+	expires_after: Option<u64>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct UpdateBucketWebsiteSigning {
+	enabled: bool,
+	prefixes: Option<Vec<String>>,
+	#[serde(default)]
+	new_secret: bool,
+}
+
+pub async fn handle_import_bucket(
+	garage: &Arc<Garage>,
+	background: &BackgroundRunner,
+	id: String,
+	req: Request<Body>,
+) -> Result<Response<Body>, Error> {
+	let req = parse_json_body::<ImportBucketRequest>(req).await?;
+	let bucket_id = parse_bucket_id(&id)?;
+
+	let bucket = garage
+		.bucket_helper()
+		.get_existing_bucket(bucket_id)
+		.await?;
+	let bucket_name = bucket
+		.aliases()
+		.iter()
+		.find(|(_, _, active)| *active)
+		.map(|(name, _, _)| name.clone())
+		.unwrap_or_else(|| hex::encode(bucket_id));
+
+	let params = ImportParams {
+		endpoint: req.endpoint,
+		region: req.region.unwrap_or_else(|| "us-east-1".into()),
+		access_key_id: req.access_key_id,
+		secret_access_key: req.secret_access_key,
+		remote_bucket: req.remote_bucket,
+		prefix: req.prefix.unwrap_or_default(),
+		concurrency: req.concurrency.unwrap_or(4),
+	};
+	let job = start_import(garage, background, bucket_id, bucket_name, params)?;
+
+	Ok(json_ok_response(&ImportBucketResponse {
+		job_id: hex::encode(job.id),
+	})?)
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct ImportBucketRequest {
+	endpoint: String,
+	region: Option<String>,
+	access_key_id: String,
+	secret_access_key: String,
+	remote_bucket: String,
+	prefix: Option<String>,
+	concurrency: Option<usize>,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct ImportBucketResponse {
+	job_id: String,
+}
+
+pub async fn handle_get_bucket_dedup_stats(
+	garage: &Arc<Garage>,
+	id: String,
+) -> Result<Response<Body>, Error> {
+	let bucket_id = parse_bucket_id(&id)?;
+	garage
+		.bucket_helper()
+		.get_existing_bucket(bucket_id)
+		.await?;
+
+	let stats = garage.bucket_helper().dedup_stats(&bucket_id).await?;
+
+	let res = GetBucketDedupStatsResult {
+		logical_bytes: stats.logical_bytes,
+		physical_bytes: stats.physical_bytes,
+		saved_bytes: stats.logical_bytes - stats.physical_bytes,
+		blocks: stats.blocks,
+		unique_blocks: stats.unique_blocks,
+	};
+	Ok(json_ok_response(&res)?)
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct GetBucketDedupStatsResult {
+	logical_bytes: u64,
+	physical_bytes: u64,
+	saved_bytes: u64,
+	blocks: u64,
+	unique_blocks: u64,
+}
+
+pub async fn handle_empty_bucket(
+	garage: &Arc<Garage>,
+	background: &BackgroundRunner,
+	id: String,
+	req: Request<Body>,
+) -> Result<Response<Body>, Error> {
+	let req = parse_json_body::<EmptyBucketRequest>(req).await?;
+	let bucket_id = parse_bucket_id(&id)?;
+	garage
+		.bucket_helper()
+		.get_existing_bucket(bucket_id)
+		.await?;
+
+	let rate = req.rate.unwrap_or(1000);
+	if rate == 0 {
+		return Err(Error::bad_request("rate must be at least 1"));
+	}
+	match EmptyBucketWorker::start(garage, background, bucket_id, rate)? {
+		Some(_) => (),
+		None => {
+			return Err(Error::bad_request(
+				"Bucket is already being emptied by this node",
+			))
+		}
+	}
+
+	handle_get_empty_bucket_status(garage, id).await
+}
+
+pub async fn handle_get_empty_bucket_status(
+	garage: &Arc<Garage>,
+	id: String,
+) -> Result<Response<Body>, Error> {
+	let bucket_id = parse_bucket_id(&id)?;
+
+	let res = match EmptyBucketWorker::job_status(garage, bucket_id)? {
+		Some(job) => GetEmptyBucketStatusResult {
+			running: true,
+			rate: Some(job.rate),
+			started: Some(msec_to_rfc3339(job.started)),
+			last_key: job.last_key,
+			objects_deleted: Some(job.objects_deleted),
+		},
+		None => GetEmptyBucketStatusResult {
+			running: false,
+			rate: None,
+			started: None,
+			last_key: None,
+			objects_deleted: None,
+		},
+	};
+	Ok(json_ok_response(&res)?)
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct EmptyBucketRequest {
+	rate: Option<u64>,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct GetEmptyBucketStatusResult {
+	running: bool,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	rate: Option<u64>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	started: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	last_key: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	objects_deleted: Option<u64>,
+}
+
+pub async fn handle_clone_bucket(
+	garage: &Arc<Garage>,
+	background: &BackgroundRunner,
+	id: String,
+	req: Request<Body>,
+) -> Result<Response<Body>, Error> {
+	let req = parse_json_body::<CloneBucketRequest>(req).await?;
+	let source_id = parse_bucket_id(&id)?;
+	let dest_id = parse_bucket_id(&req.destination_bucket_id)?;
+	if source_id == dest_id {
+		return Err(Error::bad_request(
+			"Source and destination buckets must be different",
+		));
+	}
+
+	let helper = garage.bucket_helper();
+	helper.get_existing_bucket(source_id).await?;
+	helper.get_existing_bucket(dest_id).await?;
+
+	let prefix = req.prefix.unwrap_or_default();
+	match CloneBucketWorker::start(garage.clone(), source_id, dest_id, prefix)? {
+		Some(worker) => background.spawn_worker(worker),
+		None => {
+			return Err(Error::bad_request(
+				"Bucket is already being copied into this destination by this node",
+			))
+		}
+	}
+
+	Ok(Response::builder()
+		.status(StatusCode::NO_CONTENT)
+		.body(Body::empty())?)
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct CloneBucketRequest {
+	destination_bucket_id: String,
+	prefix: Option<String>,
+}
+
+pub async fn handle_update_bucket_objects_metadata(
+	garage: &Arc<Garage>,
+	background: &BackgroundRunner,
+	id: String,
+	req: Request<Body>,
+) -> Result<Response<Body>, Error> {
+	let req = parse_json_body::<UpdateBucketObjectsMetadataRequest>(req).await?;
+	let bucket_id = parse_bucket_id(&id)?;
+	garage
+		.bucket_helper()
+		.get_existing_bucket(bucket_id)
+		.await?;
+
+	let params = MetadataUpdateParams {
+		prefix: req.prefix.unwrap_or_default(),
+		content_type: req.content_type,
+		content_type_from_extension: req.content_type_from_extension,
+		set_headers: req.set_headers.into_iter().collect(),
+		remove_headers: req.remove_headers,
+		rate: req.rate.unwrap_or(100),
+	}
+	.validate()?;
+	if params.content_type.is_none()
+		&& !params.content_type_from_extension
+		&& params.set_headers.is_empty()
+		&& params.remove_headers.is_empty()
+	{
+		return Err(Error::bad_request("No change to the metadata of objects"));
+	}
+	let job = start_metadata_update(garage, background, bucket_id, params)?;
+
+	Ok(json_ok_response(&UpdateBucketObjectsMetadataResponse {
+		job_id: hex::encode(job.id),
+	})?)
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct UpdateBucketObjectsMetadataRequest {
+	prefix: Option<String>,
+	content_type: Option<String>,
+	#[serde(default)]
+	content_type_from_extension: bool,
+	#[serde(default)]
+	set_headers: HashMap<String, String>,
+	#[serde(default)]
+	remove_headers: Vec<String>,
+	rate: Option<u64>,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct UpdateBucketObjectsMetadataResponse {
+	job_id: String,
+}
+
+pub async fn handle_list_bucket_trash(
+	garage: &Arc<Garage>,
+	id: String,
+	prefix: Option<String>,
+	start: Option<String>,
+) -> Result<Response<Body>, Error> {
+	const LIMIT: usize = 1000;
+
+	let bucket_id = parse_bucket_id(&id)?;
+	garage
+		.bucket_helper()
+		.get_existing_bucket(bucket_id)
+		.await?;
+
+	let prefix = prefix.unwrap_or_default();
+	let objects = list_trash(garage, bucket_id, &prefix, start, LIMIT + 1).await?;
+	let next_start = objects.get(LIMIT).map(|o| o.key.clone());
+
+	let res = ListBucketTrashResult {
+		objects: objects
+			.into_iter()
+			.take(LIMIT)
+			.map(|o| ListBucketTrashItem {
+				key: o.key,
+				size: o.size,
+				etag: o.etag,
+				deleted: msec_to_rfc3339(o.deleted),
+			})
+			.collect(),
+		next_start,
+	};
+	Ok(json_ok_response(&res)?)
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct ListBucketTrashResult {
+	objects: Vec<ListBucketTrashItem>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	next_start: Option<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct ListBucketTrashItem {
+	key: String,
+	size: u64,
+	etag: String,
+	deleted: String,
+}
+
+pub async fn handle_restore_bucket_trash(
+	garage: &Arc<Garage>,
+	id: String,
+	req: Request<Body>,
+) -> Result<Response<Body>, Error> {
+	let req = parse_json_body::<RestoreBucketTrashRequest>(req).await?;
+	let bucket_id = parse_bucket_id(&id)?;
+	garage
+		.bucket_helper()
+		.get_existing_bucket(bucket_id)
+		.await?;
+
+	let (restored, skipped) = match (req.keys, req.prefix) {
+		(Some(keys), None) => {
+			let mut restored = 0;
+			for key in keys.iter() {
+				if restore_from_trash(garage, bucket_id, key).await? {
+					restored += 1;
+				}
+			}
+			(restored, keys.len() as u64 - restored)
+		}
+		(None, Some(prefix)) => restore_prefix_from_trash(garage, bucket_id, &prefix).await?,
+		_ => {
+			return Err(Error::bad_request(
+				"Either keys or prefix must be provided (but not both)",
+			));
+		}
+	};
+
+	let res = RestoreBucketTrashResult { restored, skipped };
+	Ok(json_ok_response(&res)?)
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct RestoreBucketTrashRequest {
+	keys: Option<Vec<String>>,
+	prefix: Option<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct RestoreBucketTrashResult {
+	restored: u64,
+	skipped: u64,
+}
+
+pub async fn handle_presign_bucket_objects(
+	garage: &Arc<Garage>,
+	id: String,
+	req: Request<Body>,
+) -> Result<Response<Body>, Error> {
+	const LIMIT: usize = 1000;
+
+	let req = parse_json_body::<PresignBucketObjectsRequest>(req).await?;
+	let bucket_id = parse_bucket_id(&id)?;
+	let bucket = garage
+		.bucket_helper()
+		.get_existing_bucket(bucket_id)
+		.await?;
+	let key = garage
+		.key_helper()
+		.get_existing_key(&req.access_key_id)
+		.await?;
+	if !key.allow_read(&bucket_id) {
+		return Err(Error::bad_request(format!(
+			"Key {} is not allowed to read bucket {:?}",
+			key.key_id, bucket_id
+		)));
+	}
+
+	if !(1..=7 * 24 * 3600).contains(&req.expires_in) {
+		return Err(Error::bad_request(
+			"expiresIn must be between 1 second and 7 days",
+		));
+	}
+
+	// Objects are addressed path-style, using a global alias of the bucket
+	// or a local alias of the key
+	let key_params = key.state.as_option().unwrap();
+	let bucket_name = bucket
+		.aliases()
+		.iter()
+		.find(|(_, _, active)| *active)
+		.map(|(name, _, _)| name.clone())
+		.or_else(|| {
+			key_params
+				.local_aliases
+				.items()
+				.iter()
+				.find(|(_, _, b)| *b == Some(bucket_id))
+				.map(|(name, _, _)| name.clone())
+		})
+		.ok_or_bad_request(
+			"Bucket has no global alias nor local alias for this key, it cannot be accessed path-style",
+		)?;
+
+	let prefix = req.prefix.unwrap_or_default();
+	let start = match req.start {
+		Some(s) if s > prefix => s,
+		_ => prefix.clone(),
+	};
+	let objects = garage
+		.object_table
+		.get_range(
+			&bucket_id,
+			Some(start),
+			Some(ObjectFilter::IsData),
+			LIMIT + 1,
+			EnumerationOrder::Forward,
+		)
+		.await?
+		.into_iter()
+		.take_while(|o| o.key.starts_with(&prefix))
+		.collect::<Vec<_>>();
+	let next_start = objects.get(LIMIT).map(|o| o.key.clone());
+
+	let now = SystemTime::now();
+	let expires_in = Duration::from_secs(req.expires_in);
+	let mut items = Vec::with_capacity(LIMIT);
+	for object in objects.iter().take(LIMIT) {
+		let size = match current_version(object) {
+			Some((_, meta)) => meta.size,
+			None => continue,
+		};
+		let url = presign_get_url(
+			&req.endpoint,
+			&bucket_name,
+			&object.key,
+			&key.key_id,
+			&key_params.secret_key,
+			&garage.config.s3_api.s3_region,
+			now,
+			expires_in,
+		)?;
+		items.push(PresignedObject {
+			key: object.key.clone(),
+			size,
+			url,
+		});
+	}
+
+	let res = PresignBucketObjectsResult {
+		expires: msec_to_rfc3339(
+			(now + expires_in)
+				.duration_since(UNIX_EPOCH)
+				.unwrap_or_default()
+				.as_millis() as u64,
+		),
+		objects: items,
+		next_start,
+	};
+	Ok(json_ok_response(&res)?)
+}
+
+/// Build a presigned URL to GET an object with the S3 API, which is valid
+/// for `expires_in` from `time`
+#[allow(clippy::too_many_arguments)]
+fn presign_get_url(
+	endpoint: &str,
+	bucket_name: &str,
+	key: &str,
+	access_key_id: &str,
+	secret_key: &str,
+	region: &str,
+	time: SystemTime,
+	expires_in: Duration,
+) -> Result<String, Error> {
+	let uri = format!(
+		"{}/{}/{}",
+		endpoint.trim_end_matches('/'),
+		uri_encode(bucket_name, true),
+		uri_encode(key, false)
+	);
+	let mut req = Request::get(&uri).body(Body::empty())?;
+
+	let signing_params = SigningParams {
+		access_key_id,
+		secret_access_key: secret_key,
+		region,
+		service: SERVICE_S3,
+		time: time.into(),
+	};
+	presign_request(&mut req, &signing_params, expires_in)
+		.ok_or_internal_error("Could not sign request")?;
+
+	Ok(req.uri().to_string())
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct PresignBucketObjectsRequest {
+	access_key_id: String,
+	endpoint: String,
+	prefix: Option<String>,
+	start: Option<String>,
+	expires_in: u64,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct PresignBucketObjectsResult {
+	expires: String,
+	objects: Vec<PresignedObject>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	next_start: Option<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct PresignedObject {
+	key: String,
+	size: u64,
+	url: String,
+}
+
+// ---- WEB USAGE ----
+
+/// Period for which the usage of websites is returned by default
+const DEFAULT_WEB_USAGE_PERIOD_MSEC: u64 = 24 * 3600 * 1000;
+
+/// Number of buckets returned by default
+const DEFAULT_WEB_USAGE_LIMIT: usize = 10;
+
+pub async fn handle_get_bucket_web_usage(
+	garage: &Arc<Garage>,
+	start: Option<String>,
+	end: Option<String>,
+	limit: Option<String>,
+) -> Result<Response<Body>, Error> {
+	let end = match end {
+		Some(d) => parse_usage_date(&d)?,
+		None => now_msec(),
+	};
+	let start = match start {
+		Some(d) => parse_usage_date(&d)?,
+		None => end.saturating_sub(DEFAULT_WEB_USAGE_PERIOD_MSEC),
+	};
+	let limit = match limit {
+		Some(l) => l.parse::<usize>().ok_or_bad_request("Invalid limit")?,
+		None => DEFAULT_WEB_USAGE_LIMIT,
+	};
+
+	// Usage is stored with one partition per time window
+	let mut usage = HashMap::<Uuid, WebUsageResult>::new();
+	let mut window = start - start % USAGE_WINDOW_MSEC;
+	while window < end {
+		let window_key = window_sort_key(window);
+		let mut pos = None;
+		loop {
+			let entries = garage
+				.web_usage
+				.counter
+				.table
+				.get_range(&window_key, pos, None, 1000, EnumerationOrder::Forward)
+				.await?;
+			let n_entries = entries.len();
+			for entry in entries.iter() {
+				// Pages after the first one start with the last entry of the previous page
+				if pos == Some(entry.sk) {
+					continue;
+				}
+				usage.entry(entry.sk).or_default().add(&total_usage(entry));
+			}
+			match entries.last() {
+				Some(last) if n_entries == 1000 => pos = Some(last.sk),
+				_ => break,
+			}
+		}
+		window += USAGE_WINDOW_MSEC;
+	}
+
+	let mut usage = usage.into_iter().collect::<Vec<_>>();
+	usage.sort_by_key(|(_, u)| std::cmp::Reverse(u.bytes_sent));
+	usage.truncate(limit);
+
+	let mut buckets = vec![];
+	for (bucket_id, usage) in usage {
+		let global_aliases = garage
+			.bucket_table
+			.get(&EmptyKey, &bucket_id)
+			.await?
+			.map(|b| {
+				b.aliases()
+					.iter()
+					.filter(|(_, _, active)| *active)
+					.map(|(n, _, _)| n.to_string())
+					.collect::<Vec<_>>()
+			})
+			.unwrap_or_default();
+		buckets.push(BucketWebUsageResult {
+			id: hex::encode(bucket_id),
+			global_aliases,
+			usage,
+		});
+	}
+
+	let res = GetBucketWebUsageResult {
+		start: msec_to_rfc3339(start),
+		end: msec_to_rfc3339(end),
+		buckets,
+	};
+	Ok(json_ok_response(&res)?)
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct GetBucketWebUsageResult {
+	start: String,
+	end: String,
+	buckets: Vec<BucketWebUsageResult>,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct BucketWebUsageResult {
+	id: String,
+	global_aliases: Vec<String>,
+	#[serde(flatten)]
+	usage: WebUsageResult,
+}
+
+#[derive(Serialize, Default, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct WebUsageResult {
+	requests: i64,
+	errors: i64,
+	bytes_sent: i64,
+}
+
+impl WebUsageResult {
+	fn add(&mut self, usage: &HashMap<String, i64>) {
+		let get = |name: &str| usage.get(name).copied().unwrap_or(0);
+		self.requests += get(web_usage::REQUESTS);
+		self.errors += get(web_usage::ERRORS);
+		self.bytes_sent += get(web_usage::BYTES_SENT);
+	}
 }
 
 // ---- BUCKET/KEY PERMISSIONS ----
@@ -473,6 +1433,7 @@ pub async fn handle_bucket_change_key_perm(
 	req: Request<Body>,
 	new_perm_flag: bool,
 ) -> Result<Response<Body>, Error> {
+	let headers = req.headers().clone();
 	let req = parse_json_body::<BucketKeyPermChangeRequest>(req).await?;
 
 	let bucket_id = parse_bucket_id(&req.bucket_id)?;
@@ -481,6 +1442,7 @@ pub async fn handle_bucket_change_key_perm(
 		.bucket_helper()
 		.get_existing_bucket(bucket_id)
 		.await?;
+	check_if_match(garage, &headers, &resource_version(&bucket)?)?;
 	let state = bucket.state.as_option().unwrap();
 
 	let key = garage
@@ -509,10 +1471,10 @@ pub async fn handle_bucket_change_key_perm(
 		.set_bucket_key_permissions(bucket.id, &key.key_id, perm)
 		.await?;
 
-	bucket_info_results(garage, bucket.id).await
+	bucket_info_results(garage, bucket.id, false).await
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 struct BucketKeyPermChangeRequest {
 	bucket_id: String,
@@ -526,30 +1488,34 @@ pub async fn handle_global_alias_bucket(
 	garage: &Arc<Garage>,
 	bucket_id: String,
 	alias: String,
+	headers: &HeaderMap,
 ) -> Result<Response<Body>, Error> {
 	let bucket_id = parse_bucket_id(&bucket_id)?;
+	check_bucket_version(garage, bucket_id, headers).await?;
 
 	garage
 		.bucket_helper()
 		.set_global_bucket_alias(bucket_id, &alias)
 		.await?;
 
-	bucket_info_results(garage, bucket_id).await
+	bucket_info_results(garage, bucket_id, false).await
 }
 
 pub async fn handle_global_unalias_bucket(
 	garage: &Arc<Garage>,
 	bucket_id: String,
 	alias: String,
+	headers: &HeaderMap,
 ) -> Result<Response<Body>, Error> {
 	let bucket_id = parse_bucket_id(&bucket_id)?;
+	check_bucket_version(garage, bucket_id, headers).await?;
 
 	garage
 		.bucket_helper()
 		.unset_global_bucket_alias(bucket_id, &alias)
 		.await?;
 
-	bucket_info_results(garage, bucket_id).await
+	bucket_info_results(garage, bucket_id, false).await
 }
 
 pub async fn handle_local_alias_bucket(
@@ -557,15 +1523,17 @@ pub async fn handle_local_alias_bucket(
 	bucket_id: String,
 	access_key_id: String,
 	alias: String,
+	headers: &HeaderMap,
 ) -> Result<Response<Body>, Error> {
 	let bucket_id = parse_bucket_id(&bucket_id)?;
+	check_bucket_version(garage, bucket_id, headers).await?;
 
 	garage
 		.bucket_helper()
 		.set_local_bucket_alias(bucket_id, &access_key_id, &alias)
 		.await?;
 
-	bucket_info_results(garage, bucket_id).await
+	bucket_info_results(garage, bucket_id, false).await
 }
 
 pub async fn handle_local_unalias_bucket(
@@ -573,15 +1541,17 @@ pub async fn handle_local_unalias_bucket(
 	bucket_id: String,
 	access_key_id: String,
 	alias: String,
+	headers: &HeaderMap,
 ) -> Result<Response<Body>, Error> {
 	let bucket_id = parse_bucket_id(&bucket_id)?;
+	check_bucket_version(garage, bucket_id, headers).await?;
 
 	garage
 		.bucket_helper()
 		.unset_local_bucket_alias(bucket_id, &access_key_id, &alias)
 		.await?;
 
-	bucket_info_results(garage, bucket_id).await
+	bucket_info_results(garage, bucket_id, false).await
 }
 
 // ---- HELPER ----
@@ -590,3 +1560,282 @@ fn parse_bucket_id(id: &str) -> Result<Uuid, Error> {
 	let id_hex = hex::decode(id).ok_or_bad_request("Invalid bucket id")?;
 	Ok(Uuid::try_from(&id_hex).ok_or_bad_request("Invalid bucket id")?)
 }
+
+/// Check the If-Match header of an update against the current version of a bucket
+async fn check_bucket_version(
+	garage: &Arc<Garage>,
+	bucket_id: Uuid,
+	headers: &HeaderMap,
+) -> Result<(), Error> {
+	let bucket = garage
+		.bucket_helper()
+		.get_existing_bucket(bucket_id)
+		.await?;
+	check_if_match(garage, headers, &resource_version(&bucket)?)
+}
+
+// ---- usage per storage class and zone ----
+
+pub async fn handle_get_bucket_usage(garage: &Arc<Garage>) -> Result<Response<Body>, Error> {
+	let buckets = garage
+		.bucket_table
+		.get_range(
+			&EmptyKey,
+			None,
+			Some(DeletedFilter::NotDeleted),
+			10000,
+			EnumerationOrder::Forward,
+		)
+		.await?;
+
+	let mut total = HashMap::new();
+	let mut per_bucket = vec![];
+	for bucket in buckets {
+		let counters = garage
+			.object_counter_table
+			.table
+			.get(&bucket.id, &EmptyKey)
+			.await?
+			.map(|x| x.filtered_values(&garage.system.ring.borrow()))
+			.unwrap_or_default();
+		for (name, value) in counters.iter() {
+			*total.entry(name.clone()).or_insert(0) += *value;
+		}
+		per_bucket.push(GetBucketUsageBucket {
+			id: hex::encode(bucket.id),
+			usage: usage_result(garage, &counters),
+		});
+	}
+
+	let res = GetBucketUsageResult {
+		total: usage_result(garage, &total),
+		buckets: per_bucket,
+	};
+	Ok(json_ok_response(&res)?)
+}
+
+fn usage_result(garage: &Garage, counters: &HashMap<String, i64>) -> ApiUsage {
+	let bytes = *counters.get(BYTES).unwrap_or(&0);
+	ApiUsage {
+		objects: *counters.get(OBJECTS).unwrap_or(&0),
+		bytes,
+		storage_classes: storage_class_usage(counters),
+		zones: zone_usage(bytes, &garage.system.ring.borrow()),
+	}
+}
+
+/// Objects and bytes of each storage class. The default class gets
+/// everything that is not in another class.
+fn storage_class_usage(counters: &HashMap<String, i64>) -> Vec<ApiStorageClassUsage> {
+	let get = |name: &str| *counters.get(name).unwrap_or(&0);
+	let mut ret = STORAGE_CLASSES
+		.iter()
+		.map(|(class, objects, bytes)| ApiStorageClassUsage {
+			storage_class: class.to_string(),
+			objects: get(objects),
+			bytes: get(bytes),
+		})
+		.filter(|u| u.objects != 0 || u.bytes != 0)
+		.collect::<Vec<_>>();
+	ret.insert(
+		0,
+		ApiStorageClassUsage {
+			storage_class: DEFAULT_STORAGE_CLASS.to_string(),
+			objects: get(OBJECTS) - ret.iter().map(|u| u.objects).sum::<i64>(),
+			bytes: get(BYTES) - ret.iter().map(|u| u.bytes).sum::<i64>(),
+		},
+	);
+	ret
+}
+
+/// Bytes stored in each zone for a given amount of object data, according
+/// to the placement of partitions in the current layout
+fn zone_usage(bytes: i64, ring: &Ring) -> Vec<ApiZoneUsage> {
+	let mut ret = ring
+		.zone_replication()
+		.into_iter()
+		.map(|(zone, copies)| ApiZoneUsage {
+			zone,
+			bytes: (bytes as f64 * copies).round() as i64,
+		})
+		.collect::<Vec<_>>();
+	ret.sort_by(|a, b| a.zone.cmp(&b.zone));
+	ret
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct GetBucketUsageResult {
+	total: ApiUsage,
+	buckets: Vec<GetBucketUsageBucket>,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct GetBucketUsageBucket {
+	id: String,
+	#[serde(flatten)]
+	usage: ApiUsage,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct ApiUsage {
+	objects: i64,
+	bytes: i64,
+	storage_classes: Vec<ApiStorageClassUsage>,
+	zones: Vec<ApiZoneUsage>,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct ApiStorageClassUsage {
+	storage_class: String,
+	objects: i64,
+	bytes: i64,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct ApiZoneUsage {
+	zone: String,
+	bytes: i64,
+}
+
+// ---- API description ----
+
+pub(crate) fn api_spec(spec: &mut ApiSpec) {
+	spec.op("GET", "/v1/bucket", "GetBucketInfo")
+		.summary(
+			"List all buckets, or get information about a bucket when `id` or `globalAlias` is given",
+		)
+		.query("id", false)
+		.query("globalAlias", false)
+		.query("showSecret", false)
+		.response::<Vec<ListBucketResultItem>>()
+		.response::<GetBucketInfoResult>()
+		.add();
+	spec.op("POST", "/v1/bucket", "CreateBucket")
+		.summary("Create a bucket")
+		.request::<CreateBucketRequest>()
+		.response::<GetBucketInfoResult>()
+		.add();
+	spec.op("PUT", "/v1/bucket", "UpdateBucket")
+		.summary("Update the configuration of a bucket")
+		.query("id", true)
+		.request::<UpdateBucketRequest>()
+		.response::<GetBucketInfoResult>()
+		.add();
+	spec.op("DELETE", "/v1/bucket", "DeleteBucket")
+		.summary("Delete an empty bucket")
+		.query("id", true)
+		.no_content()
+		.accepted::<ApiPendingDeletion>()
+		.add();
+	spec.op("POST", "/v1/bucket/import", "ImportBucket")
+		.summary("Import the objects of a bucket of another S3 service")
+		.query("id", true)
+		.request::<ImportBucketRequest>()
+		.response::<ImportBucketResponse>()
+		.add();
+	spec.op("GET", "/v1/bucket/dedup", "GetBucketDedupStats")
+		.summary("Deduplication of the blocks of a bucket")
+		.query("id", true)
+		.response::<GetBucketDedupStatsResult>()
+		.add();
+	spec.op("POST", "/v1/bucket/empty", "EmptyBucket")
+		.summary("Start deleting all objects of a bucket")
+		.query("id", true)
+		.request::<EmptyBucketRequest>()
+		.response::<GetEmptyBucketStatusResult>()
+		.add();
+	spec.op("GET", "/v1/bucket/empty", "GetEmptyBucketStatus")
+		.summary("Progress of the deletion of all objects of a bucket")
+		.query("id", true)
+		.response::<GetEmptyBucketStatusResult>()
+		.add();
+	spec.op("POST", "/v1/bucket/clone", "CloneBucket")
+		.summary("Copy the objects of a bucket to another bucket")
+		.query("id", true)
+		.request::<CloneBucketRequest>()
+		.no_content()
+		.add();
+	spec.op(
+		"POST",
+		"/v1/bucket/update-metadata",
+		"UpdateBucketObjectsMetadata",
+	)
+	.summary("Start updating the metadata of objects of a bucket")
+	.query("id", true)
+	.request::<UpdateBucketObjectsMetadataRequest>()
+	.response::<UpdateBucketObjectsMetadataResponse>()
+	.add();
+	spec.op("GET", "/v1/bucket/trash", "ListBucketTrash")
+		.summary("Deleted objects of a bucket that can still be restored")
+		.query("id", true)
+		.query("prefix", false)
+		.query("start", false)
+		.response::<ListBucketTrashResult>()
+		.add();
+	spec.op("POST", "/v1/bucket/trash/restore", "RestoreBucketTrash")
+		.summary("Restore deleted objects of a bucket")
+		.query("id", true)
+		.request::<RestoreBucketTrashRequest>()
+		.response::<RestoreBucketTrashResult>()
+		.add();
+	spec.op("POST", "/v1/bucket/presign", "PresignBucketObjects")
+		.summary("Presigned URLs for the objects of a bucket")
+		.query("id", true)
+		.request::<PresignBucketObjectsRequest>()
+		.response::<PresignBucketObjectsResult>()
+		.add();
+	spec.op("GET", "/v1/bucket/web-usage", "GetBucketWebUsage")
+		.summary("Website traffic of buckets")
+		.query("start", false)
+		.query("end", false)
+		.query("limit", false)
+		.response::<GetBucketWebUsageResult>()
+		.add();
+	spec.op("GET", "/v1/bucket/usage", "GetBucketUsage")
+		.summary("Objects and bytes stored by buckets, by storage class and by zone")
+		.response::<GetBucketUsageResult>()
+		.add();
+
+	spec.op("POST", "/v1/bucket/allow", "BucketAllowKey")
+		.summary("Give permissions on a bucket to a key")
+		.request::<BucketKeyPermChangeRequest>()
+		.response::<GetBucketInfoResult>()
+		.add();
+	spec.op("POST", "/v1/bucket/deny", "BucketDenyKey")
+		.summary("Remove permissions on a bucket from a key")
+		.request::<BucketKeyPermChangeRequest>()
+		.response::<GetBucketInfoResult>()
+		.add();
+
+	spec.op("PUT", "/v1/bucket/alias/global", "GlobalAliasBucket")
+		.summary("Add a global alias to a bucket")
+		.query("id", true)
+		.query("alias", true)
+		.response::<GetBucketInfoResult>()
+		.add();
+	spec.op("DELETE", "/v1/bucket/alias/global", "GlobalUnaliasBucket")
+		.summary("Remove a global alias of a bucket")
+		.query("id", true)
+		.query("alias", true)
+		.response::<GetBucketInfoResult>()
+		.add();
+	spec.op("PUT", "/v1/bucket/alias/local", "LocalAliasBucket")
+		.summary("Add an alias to a bucket in the namespace of a key")
+		.query("id", true)
+		.query("accessKeyId", true)
+		.query("alias", true)
+		.response::<GetBucketInfoResult>()
+		.add();
+	spec.op("DELETE", "/v1/bucket/alias/local", "LocalUnaliasBucket")
+		.summary("Remove an alias of a bucket in the namespace of a key")
+		.query("id", true)
+		.query("accessKeyId", true)
+		.query("alias", true)
+		.response::<GetBucketInfoResult>()
+		.add();
+}
@@ -2,16 +2,20 @@ use std::net::SocketAddr;
 use std::sync::Arc;
 
 use hyper::{Body, Request, Response};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use garage_util::crdt::*;
 use garage_util::data::*;
+use garage_util::log_filter;
 
+use garage_rpc::fault_injection::{FaultConfig, FaultStats, FAULT_INJECTION_ENABLED};
 use garage_rpc::layout;
 
 use garage_model::garage::Garage;
 
 use crate::admin::error::*;
+use crate::admin::spec::ApiSpec;
 use crate::helpers::{json_ok_response, parse_json_body};
 
 pub async fn handle_get_cluster_status(garage: &Arc<Garage>) -> Result<Response<Body>, Error> {
@@ -31,6 +35,12 @@ pub async fn handle_get_cluster_status(garage: &Arc<Garage>) -> Result<Response<
 				is_up: i.is_up,
 				last_seen_secs_ago: i.last_seen_secs_ago,
 				hostname: i.status.hostname,
+				drain: i.status.drain.map(|d| DrainResp {
+					complete: d.is_complete(),
+					progress: d.progress(),
+					partitions_remaining: d.partitions_remaining,
+					blocks_remaining: d.blocks_remaining,
+				}),
 			})
 			.collect(),
 		layout: format_cluster_layout(&garage.system.get_cluster_layout()),
@@ -89,6 +99,34 @@ pub async fn handle_get_cluster_layout(garage: &Arc<Garage>) -> Result<Response<
 	Ok(json_ok_response(&res)?)
 }
 
+pub async fn handle_get_layout_rebalance(garage: &Arc<Garage>) -> Result<Response<Body>, Error> {
+	let res = match garage.system.rebalance_suggestion() {
+		Some(s) => GetLayoutRebalanceResponse {
+			threshold: s.threshold,
+			average_usage: Some(s.average_usage),
+			imbalance: Some(s.imbalance),
+			changes: s
+				.changes
+				.iter()
+				.map(|c| CapacityChangeResp {
+					id: hex::encode(c.node),
+					usage: c.usage,
+					capacity: c.capacity,
+					new_capacity: c.new_capacity,
+				})
+				.collect(),
+		},
+		None => GetLayoutRebalanceResponse {
+			threshold: garage.live_config.load().rebalance.threshold,
+			average_usage: None,
+			imbalance: None,
+			changes: vec![],
+		},
+	};
+
+	Ok(json_ok_response(&res)?)
+}
+
 fn format_cluster_layout(layout: &layout::ClusterLayout) -> GetClusterLayoutResponse {
 	let roles = layout
 		.roles
@@ -133,7 +171,7 @@ fn format_cluster_layout(layout: &layout::ClusterLayout) -> GetClusterLayoutResp
 
 // ----
 
-#[derive(Debug, Clone, Copy, Serialize)]
+#[derive(Debug, Clone, Copy, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ClusterHealth {
 	status: &'static str,
@@ -146,7 +184,7 @@ pub struct ClusterHealth {
 	partitions_all_ok: usize,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 struct GetClusterStatusResponse {
 	node: String,
@@ -158,21 +196,21 @@ struct GetClusterStatusResponse {
 	layout: GetClusterLayoutResponse,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 struct ApplyClusterLayoutResponse {
 	message: Vec<String>,
 	layout: GetClusterLayoutResponse,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 struct ConnectClusterNodesResponse {
 	success: bool,
 	error: Option<String>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 struct GetClusterLayoutResponse {
 	version: u64,
@@ -180,7 +218,7 @@ struct GetClusterLayoutResponse {
 	staged_role_changes: Vec<NodeRoleChange>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 struct NodeRoleResp {
 	id: String,
@@ -189,7 +227,7 @@ struct NodeRoleResp {
 	tags: Vec<String>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 struct KnownNodeResp {
 	id: String,
@@ -197,6 +235,35 @@ struct KnownNodeResp {
 	is_up: bool,
 	last_seen_secs_ago: Option<u64>,
 	hostname: String,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	drain: Option<DrainResp>,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct DrainResp {
+	complete: bool,
+	progress: f64,
+	partitions_remaining: u64,
+	blocks_remaining: u64,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct GetLayoutRebalanceResponse {
+	threshold: f64,
+	average_usage: Option<f64>,
+	imbalance: Option<f64>,
+	changes: Vec<CapacityChangeResp>,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct CapacityChangeResp {
+	id: String,
+	usage: f64,
+	capacity: u64,
+	new_capacity: u64,
 }
 
 // ---- update functions ----
@@ -277,7 +344,7 @@ pub async fn handle_revert_cluster_layout(
 
 type UpdateClusterLayoutRequest = Vec<NodeRoleChange>;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 struct ApplyRevertLayoutRequest {
 	version: u64,
@@ -285,7 +352,7 @@ struct ApplyRevertLayoutRequest {
 
 // ----
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 struct NodeRoleChange {
 	id: String,
@@ -293,7 +360,7 @@ struct NodeRoleChange {
 	action: NodeRoleChangeEnum,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, JsonSchema)]
 #[serde(untagged)]
 enum NodeRoleChangeEnum {
 	#[serde(rename_all = "camelCase")]
@@ -305,3 +372,172 @@ enum NodeRoleChangeEnum {
 		tags: Vec<String>,
 	},
 }
+
+// ---- log filter ----
+
+pub async fn handle_get_log_filter(garage: &Arc<Garage>) -> Result<Response<Body>, Error> {
+	let res = LogFilterResponse {
+		node: hex::encode(garage.system.id),
+		filter: log_filter::get_log_filter()?,
+	};
+	Ok(json_ok_response(&res)?)
+}
+
+pub async fn handle_set_log_filter(
+	garage: &Arc<Garage>,
+	req: Request<Body>,
+) -> Result<Response<Body>, Error> {
+	let param = parse_json_body::<SetLogFilterRequest>(req).await?;
+
+	log_filter::set_log_filter(&param.filter).map_err(|e| Error::bad_request(e.to_string()))?;
+	info!(
+		"Log filter changed to `{}` through the admin API",
+		param.filter
+	);
+
+	handle_get_log_filter(garage).await
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct SetLogFilterRequest {
+	filter: String,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct LogFilterResponse {
+	node: String,
+	filter: String,
+}
+
+// ---- configuration reload ----
+
+pub async fn handle_reload_config(garage: &Arc<Garage>) -> Result<Response<Body>, Error> {
+	let reload = garage
+		.reload_config()
+		.map_err(|e| Error::bad_request(format!("Unable to reload configuration: {}", e)))?;
+
+	let res = ReloadConfigResponse {
+		node: hex::encode(garage.system.id),
+		applied: reload.applied,
+		requires_restart: reload.requires_restart,
+	};
+	Ok(json_ok_response(&res)?)
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct ReloadConfigResponse {
+	node: String,
+	applied: Vec<String>,
+	requires_restart: Vec<String>,
+}
+
+// ---- fault injection ----
+
+pub async fn handle_get_fault_injection(garage: &Arc<Garage>) -> Result<Response<Body>, Error> {
+	let fault_injection = &garage.system.fault_injection;
+	let res = FaultInjectionResponse {
+		node: hex::encode(garage.system.id),
+		enabled: FAULT_INJECTION_ENABLED,
+		config: fault_injection.config(),
+		stats: fault_injection.stats(),
+	};
+	Ok(json_ok_response(&res)?)
+}
+
+pub async fn handle_set_fault_injection(
+	garage: &Arc<Garage>,
+	req: Request<Body>,
+) -> Result<Response<Body>, Error> {
+	if !FAULT_INJECTION_ENABLED {
+		return Err(Error::bad_request(
+			"Fault injection is not enabled in this build of Garage",
+		));
+	}
+
+	let config = parse_json_body::<FaultConfig>(req).await?;
+	garage
+		.system
+		.fault_injection
+		.set_config(config)
+		.map_err(|e| Error::bad_request(e.to_string()))?;
+
+	handle_get_fault_injection(garage).await
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct FaultInjectionResponse {
+	node: String,
+	enabled: bool,
+	#[schemars(with = "serde_json::Value")]
+	config: FaultConfig,
+	#[schemars(with = "serde_json::Value")]
+	stats: FaultStats,
+}
+
+// ---- API description ----
+
+pub(crate) fn api_spec(spec: &mut ApiSpec) {
+	spec.op("GET", "/v1/status", "GetClusterStatus")
+		.summary("Status of the cluster and of its nodes")
+		.response::<GetClusterStatusResponse>()
+		.add();
+	spec.op("GET", "/v1/health", "GetClusterHealth")
+		.summary("Health of the cluster")
+		.response::<ClusterHealth>()
+		.add();
+	spec.op("POST", "/v1/connect", "ConnectClusterNodes")
+		.summary("Connect to other nodes, given as `<node id>@<address>`")
+		.request::<Vec<String>>()
+		.response::<Vec<ConnectClusterNodesResponse>>()
+		.add();
+	spec.op("GET", "/v1/log-filter", "GetLogFilter")
+		.summary("Log filter of this node")
+		.response::<LogFilterResponse>()
+		.add();
+	spec.op("POST", "/v1/log-filter", "SetLogFilter")
+		.summary("Change the log filter of this node")
+		.request::<SetLogFilterRequest>()
+		.response::<LogFilterResponse>()
+		.add();
+	spec.op("POST", "/v1/config/reload", "ReloadConfig")
+		.summary("Reload the configuration file of this node")
+		.response::<ReloadConfigResponse>()
+		.add();
+	spec.op("GET", "/v1/debug/faults", "GetFaultInjection")
+		.summary("Faults injected in the RPC of this node")
+		.response::<FaultInjectionResponse>()
+		.add();
+	spec.op("POST", "/v1/debug/faults", "SetFaultInjection")
+		.summary("Change the faults injected in the RPC of this node")
+		.request::<serde_json::Value>()
+		.response::<FaultInjectionResponse>()
+		.add();
+
+	spec.op("GET", "/v1/layout", "GetClusterLayout")
+		.summary("Current cluster layout and staged changes")
+		.response::<GetClusterLayoutResponse>()
+		.add();
+	spec.op("POST", "/v1/layout", "UpdateClusterLayout")
+		.summary("Stage changes to the roles of nodes")
+		.request::<UpdateClusterLayoutRequest>()
+		.response::<GetClusterLayoutResponse>()
+		.add();
+	spec.op("POST", "/v1/layout/apply", "ApplyClusterLayout")
+		.summary("Apply the staged layout changes")
+		.request::<ApplyRevertLayoutRequest>()
+		.response::<ApplyClusterLayoutResponse>()
+		.add();
+	spec.op("POST", "/v1/layout/revert", "RevertClusterLayout")
+		.summary("Discard the staged layout changes")
+		.request::<ApplyRevertLayoutRequest>()
+		.response::<GetClusterLayoutResponse>()
+		.add();
+	spec.op("GET", "/v1/layout/rebalance", "GetLayoutRebalance")
+		.summary("Capacity changes that would even out the usage of nodes")
+		.response::<GetLayoutRebalanceResponse>()
+		.add();
+}
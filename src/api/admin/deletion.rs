@@ -0,0 +1,221 @@
+//! Protection of buckets and keys against deletion with a single admin token.
+//! Deleting a bucket or key that is protected against deletion only registers
+//! a pending deletion, which is carried out when the deletion is requested
+//! again with a different admin token within `deletion_confirmation_secs`.
+//! A single leaked admin token is thus not enough to destroy data.
+//!
+//! Requesters are identified by `token:<fingerprint>` for the admin tokens of
+//! the configuration and by `jwt:<subject>` for JWTs, so that a JWT subject
+//! can never be taken for the fingerprint of an admin token.
+use hyper::{Body, Response, StatusCode};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use garage_util::crdt::Lww;
+use garage_util::data::blake2sum;
+use garage_util::time::*;
+
+use garage_model::garage::Garage;
+use garage_model::permission::PendingDeletion;
+
+use crate::admin::error::*;
+use crate::admin::precondition::with_version;
+use crate::helpers::json_ok_response;
+
+const DEFAULT_DELETION_CONFIRMATION_SECS: u64 = 3600;
+
+/// Identity of an admin token in pending deletions, made of its
+/// fingerprint so as not to reveal it
+pub(crate) fn token_identity(token: &str) -> String {
+	format!(
+		"token:{}",
+		hex::encode(&blake2sum(token.as_bytes()).as_slice()[..8])
+	)
+}
+
+/// Identity of the subject of a JWT in pending deletions
+pub(crate) fn jwt_identity(subject: &str) -> String {
+	format!("jwt:{}", subject)
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ApiPendingDeletion {
+	/// Date at which the deletion was requested
+	requested: String,
+	/// Identity of the admin token (`token:<fingerprint>`) or of the subject
+	/// of the JWT (`jwt:<subject>`) with which the deletion was requested
+	requested_by: String,
+	/// Date until which the deletion can be confirmed with another admin token
+	expires: String,
+}
+
+/// The pending deletion of a bucket or key, if it can still be confirmed
+pub(crate) fn pending_deletion(
+	pending: &Lww<Option<PendingDeletion>>,
+) -> Option<ApiPendingDeletion> {
+	pending
+		.get()
+		.as_ref()
+		.filter(|p| now_msec() < p.expires)
+		.map(|p| ApiPendingDeletion {
+			requested: msec_to_rfc3339(p.requested),
+			requested_by: p.requested_by.clone(),
+			expires: msec_to_rfc3339(p.expires),
+		})
+}
+
+/// Handle a request to delete a protected bucket or key, made by `requester`
+/// (see `token_identity` and `jwt_identity`). Returns true if the request
+/// confirms a deletion requested by someone else, and the deletion must be
+/// carried out. Otherwise, the deletion is registered in `pending` and false
+/// is returned.
+pub(crate) fn request_deletion(
+	garage: &Garage,
+	pending: &mut Lww<Option<PendingDeletion>>,
+	requester: &str,
+) -> Result<bool, Error> {
+	let window = confirmation_window(garage);
+	match deletion_decision(pending.get().as_ref(), requester, now_msec(), window) {
+		Decision::Confirmed => Ok(true),
+		Decision::SameToken => Err(Error::DeletionNotConfirmed),
+		Decision::Requested(p) => {
+			pending.update(Some(p));
+			Ok(false)
+		}
+	}
+}
+
+/// Apply the `deletionProtection` and `cancelDeletion` fields of an update of
+/// a bucket or key. Protection can only be disabled with the CLI of Garage,
+/// so that it cannot be removed with a single admin token either.
+pub(crate) fn update_deletion_protection(
+	protection: &mut Lww<bool>,
+	pending: &mut Lww<Option<PendingDeletion>>,
+	enable: Option<bool>,
+	cancel_deletion: bool,
+) -> Result<(), Error> {
+	match enable {
+		Some(true) => protection.update(true),
+		Some(false) if *protection.get() => {
+			return Err(Error::bad_request(
+				"Deletion protection can only be disabled with the garage CLI.",
+			))
+		}
+		_ => (),
+	}
+	if cancel_deletion && pending.get().is_some() {
+		pending.update(None);
+	}
+	Ok(())
+}
+
+/// Response to a request that registered a pending deletion
+pub(crate) fn deletion_requested_response(
+	pending: &Lww<Option<PendingDeletion>>,
+	version: &str,
+) -> Result<Response<Body>, Error> {
+	let mut resp = json_ok_response(&pending_deletion(pending))?;
+	*resp.status_mut() = StatusCode::ACCEPTED;
+	with_version(resp, version)
+}
+
+/// Delay to confirm new deletion requests, the expiry of pending deletions
+/// being stored with them
+fn confirmation_window(garage: &Garage) -> u64 {
+	garage
+		.config
+		.admin
+		.deletion_confirmation_secs
+		.unwrap_or(DEFAULT_DELETION_CONFIRMATION_SECS)
+		* 1000
+}
+
+#[derive(Debug, PartialEq)]
+enum Decision {
+	Confirmed,
+	SameToken,
+	Requested(PendingDeletion),
+}
+
+fn deletion_decision(
+	pending: Option<&PendingDeletion>,
+	requester: &str,
+	now: u64,
+	window: u64,
+) -> Decision {
+	match pending {
+		Some(p) if now < p.expires && p.requested_by != requester => Decision::Confirmed,
+		Some(p) if now < p.expires => Decision::SameToken,
+		_ => Decision::Requested(PendingDeletion {
+			requested: now,
+			expires: now + window,
+			requested_by: requester.to_string(),
+		}),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_deletion_decision() {
+		let a = token_identity("a");
+		let b = token_identity("b");
+		let pending = PendingDeletion {
+			requested: 1000,
+			expires: 1500,
+			requested_by: a.clone(),
+		};
+		assert_eq!(
+			deletion_decision(None, &a, 1000, 500),
+			Decision::Requested(pending.clone())
+		);
+		assert_eq!(
+			deletion_decision(Some(&pending), &b, 1200, 500),
+			Decision::Confirmed
+		);
+		assert_eq!(
+			deletion_decision(Some(&pending), &a, 1200, 500),
+			Decision::SameToken
+		);
+		assert_eq!(
+			deletion_decision(Some(&pending), &b, 1500, 500),
+			Decision::Requested(PendingDeletion {
+				requested: 1500,
+				expires: 2000,
+				requested_by: b.clone(),
+			})
+		);
+
+		// A shorter confirmation delay does not apply to pending deletions
+		assert_eq!(
+			deletion_decision(Some(&pending), &b, 1200, 100),
+			Decision::Confirmed
+		);
+	}
+
+	#[test]
+	fn test_requester_identities() {
+		// A JWT whose subject is the fingerprint of an admin token does not
+		// count as that token
+		let token = token_identity("secret");
+		let fingerprint = token.strip_prefix("token:").unwrap();
+		assert_ne!(jwt_identity(fingerprint), token);
+
+		let pending = PendingDeletion {
+			requested: 1000,
+			expires: 1500,
+			requested_by: token.clone(),
+		};
+		assert_eq!(
+			deletion_decision(Some(&pending), &jwt_identity(fingerprint), 1200, 500),
+			Decision::Confirmed
+		);
+		assert_eq!(
+			deletion_decision(Some(&pending), &token_identity("secret"), 1200, 500),
+			Decision::SameToken
+		);
+	}
+}
@@ -27,6 +27,32 @@ pub enum Error {
 		_0
 	)]
 	KeyAlreadyExists(String),
+
+	/// The job does not exist
+	#[error(display = "Job not found: {}", _0)]
+	NoSuchJob(String),
+
+	/// The permission group does not exist
+	#[error(display = "Permission group not found: {}", _0)]
+	NoSuchPermissionGroup(String),
+
+	/// The resource was modified since the version given in If-Match
+	#[error(
+		display = "Precondition failed: the resource was modified, its current version is {}",
+		_0
+	)]
+	PreconditionFailed(String),
+
+	/// Updates must give the version they apply to in If-Match
+	#[error(display = "Precondition required: an If-Match header must be given")]
+	PreconditionRequired,
+
+	/// The deletion of a protected bucket or key was confirmed with the
+	/// admin token that requested it
+	#[error(
+		display = "This deletion was requested with the same admin token, it must be confirmed with a different one"
+	)]
+	DeletionNotConfirmed,
 }
 
 impl<T> From<T> for Error
@@ -48,6 +74,7 @@ impl From<HelperError> for Error {
 			HelperError::InvalidBucketName(n) => Self::Common(CommonError::InvalidBucketName(n)),
 			HelperError::NoSuchBucket(n) => Self::Common(CommonError::NoSuchBucket(n)),
 			HelperError::NoSuchAccessKey(n) => Self::NoSuchAccessKey(n),
+			HelperError::NoSuchPermissionGroup(n) => Self::NoSuchPermissionGroup(n),
 		}
 	}
 }
@@ -58,6 +85,11 @@ impl Error {
 			Error::Common(c) => c.aws_code(),
 			Error::NoSuchAccessKey(_) => "NoSuchAccessKey",
 			Error::KeyAlreadyExists(_) => "KeyAlreadyExists",
+			Error::NoSuchJob(_) => "NoSuchJob",
+			Error::NoSuchPermissionGroup(_) => "NoSuchPermissionGroup",
+			Error::PreconditionFailed(_) => "PreconditionFailed",
+			Error::PreconditionRequired => "PreconditionRequired",
+			Error::DeletionNotConfirmed => "DeletionNotConfirmed",
 		}
 	}
 }
@@ -69,6 +101,11 @@ impl ApiError for Error {
 			Error::Common(c) => c.http_status_code(),
 			Error::NoSuchAccessKey(_) => StatusCode::NOT_FOUND,
 			Error::KeyAlreadyExists(_) => StatusCode::CONFLICT,
+			Error::NoSuchJob(_) => StatusCode::NOT_FOUND,
+			Error::NoSuchPermissionGroup(_) => StatusCode::NOT_FOUND,
+			Error::PreconditionFailed(_) => StatusCode::PRECONDITION_FAILED,
+			Error::PreconditionRequired => StatusCode::PRECONDITION_REQUIRED,
+			Error::DeletionNotConfirmed => StatusCode::CONFLICT,
 		}
 	}
 
@@ -77,7 +114,7 @@ impl ApiError for Error {
 		header_map.append(header::CONTENT_TYPE, "application/json".parse().unwrap());
 	}
 
-	fn http_body(&self, garage_region: &str, path: &str) -> Body {
+	fn http_body(&self, garage_region: &str, path: &str, _request_id: &str) -> Body {
 		let error = CustomApiErrorBody {
 			code: self.code().to_string(),
 			message: format!("{}", self),
@@ -0,0 +1,309 @@
+use std::sync::Arc;
+
+use hyper::{Body, HeaderMap, Request, Response, StatusCode};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use garage_util::data::*;
+use garage_util::time::*;
+
+use garage_table::*;
+
+use garage_model::garage::Garage;
+use garage_model::permission_group_table::*;
+
+use crate::admin::error::*;
+use crate::admin::key::ApiBucketKeyPerm;
+use crate::admin::precondition::*;
+use crate::admin::spec::ApiSpec;
+use crate::helpers::{json_ok_response, parse_json_body};
+
+pub async fn handle_list_groups(garage: &Arc<Garage>) -> Result<Response<Body>, Error> {
+	let res = garage
+		.permission_group_helper()
+		.list_groups()
+		.await?
+		.iter()
+		.map(|g| ListGroupResultItem {
+			id: hex::encode(g.id),
+			name: g.params().unwrap().name.get().clone(),
+		})
+		.collect::<Vec<_>>();
+
+	Ok(json_ok_response(&res)?)
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct ListGroupResultItem {
+	id: String,
+	name: String,
+}
+
+pub async fn handle_get_group_info(
+	garage: &Arc<Garage>,
+	id: String,
+) -> Result<Response<Body>, Error> {
+	let group_id = parse_group_id(&id)?;
+	group_info_results(garage, group_id).await
+}
+
+pub async fn handle_create_group(
+	garage: &Arc<Garage>,
+	req: Request<Body>,
+) -> Result<Response<Body>, Error> {
+	let req = parse_json_body::<CreateGroupRequest>(req).await?;
+
+	let group = PermissionGroup::new(req.name);
+	garage.permission_group_table.insert(&group).await?;
+
+	group_info_results(garage, group.id).await
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct CreateGroupRequest {
+	name: String,
+}
+
+pub async fn handle_update_group(
+	garage: &Arc<Garage>,
+	id: String,
+	req: Request<Body>,
+) -> Result<Response<Body>, Error> {
+	let group_id = parse_group_id(&id)?;
+	let mut group = garage
+		.permission_group_helper()
+		.get_existing_group(group_id)
+		.await?;
+	check_if_match(garage, req.headers(), &resource_version(&group)?)?;
+
+	let req = parse_json_body::<UpdateGroupRequest>(req).await?;
+
+	if let Some(new_name) = req.name {
+		group.params_mut().unwrap().name.update(new_name);
+		garage.permission_group_table.insert(&group).await?;
+	}
+	if !req.add_keys.is_empty() || !req.remove_keys.is_empty() {
+		garage
+			.permission_group_helper()
+			.set_group_members(group_id, &req.add_keys, &req.remove_keys)
+			.await?;
+	}
+
+	group_info_results(garage, group_id).await
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct UpdateGroupRequest {
+	name: Option<String>,
+	/// Access keys to add to the members of the group
+	#[serde(default)]
+	add_keys: Vec<String>,
+	/// Access keys to remove from the members of the group
+	#[serde(default)]
+	remove_keys: Vec<String>,
+}
+
+pub async fn handle_delete_group(
+	garage: &Arc<Garage>,
+	id: String,
+	headers: &HeaderMap,
+) -> Result<Response<Body>, Error> {
+	let group_id = parse_group_id(&id)?;
+	let mut group = garage
+		.permission_group_helper()
+		.get_existing_group(group_id)
+		.await?;
+	check_if_match(garage, headers, &resource_version(&group)?)?;
+
+	garage
+		.permission_group_helper()
+		.delete_group(&mut group)
+		.await?;
+
+	Ok(Response::builder()
+		.status(StatusCode::NO_CONTENT)
+		.body(Body::empty())?)
+}
+
+pub async fn handle_group_change_bucket_perm(
+	garage: &Arc<Garage>,
+	req: Request<Body>,
+	new_perm_flag: bool,
+) -> Result<Response<Body>, Error> {
+	let headers = req.headers().clone();
+	let req = parse_json_body::<GroupBucketPermChangeRequest>(req).await?;
+
+	let group_id = parse_group_id(&req.group_id)?;
+	let group = garage
+		.permission_group_helper()
+		.get_existing_group(group_id)
+		.await?;
+	check_if_match(garage, &headers, &resource_version(&group)?)?;
+
+	let bucket_id = parse_bucket_id(&req.bucket_id)?;
+	garage
+		.bucket_helper()
+		.get_existing_bucket(bucket_id)
+		.await?;
+
+	let mut perm = group.bucket_permissions(&bucket_id);
+	if req.permissions.read {
+		perm.allow_read = new_perm_flag;
+	}
+	if req.permissions.write {
+		perm.allow_write = new_perm_flag;
+	}
+	if req.permissions.owner {
+		perm.allow_owner = new_perm_flag;
+	}
+	perm.timestamp = now_msec();
+
+	garage
+		.permission_group_helper()
+		.set_group_bucket_permissions(group_id, bucket_id, perm)
+		.await?;
+
+	group_info_results(garage, group_id).await
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct GroupBucketPermChangeRequest {
+	group_id: String,
+	bucket_id: String,
+	permissions: ApiBucketKeyPerm,
+}
+
+async fn group_info_results(garage: &Arc<Garage>, group_id: Uuid) -> Result<Response<Body>, Error> {
+	let group = garage
+		.permission_group_helper()
+		.get_existing_group(group_id)
+		.await?;
+	let params = group.params().unwrap();
+
+	let mut keys = vec![];
+	for key_id in params.member_keys() {
+		if let Some(key) = garage
+			.key_table
+			.get(&EmptyKey, key_id)
+			.await?
+			.filter(|k| !k.is_deleted())
+		{
+			keys.push(GroupInfoKey {
+				access_key_id: key.key_id.clone(),
+				name: key.params().unwrap().name.get().clone(),
+			});
+		}
+	}
+
+	let mut buckets = vec![];
+	for (bucket_id, perm) in params.bucket_permissions.items().iter() {
+		if !perm.is_any() {
+			continue;
+		}
+		if let Some(bucket) = garage
+			.bucket_table
+			.get(&EmptyKey, bucket_id)
+			.await?
+			.filter(|b| !b.is_deleted())
+		{
+			buckets.push(GroupInfoBucket {
+				id: hex::encode(bucket_id),
+				global_aliases: bucket
+					.state
+					.as_option()
+					.unwrap()
+					.aliases
+					.items()
+					.iter()
+					.filter(|(_, _, a)| *a)
+					.map(|(n, _, _)| n.to_string())
+					.collect::<Vec<_>>(),
+				permissions: ApiBucketKeyPerm::from(perm),
+			});
+		}
+	}
+
+	let res = GetGroupInfoResult {
+		id: hex::encode(group.id),
+		name: params.name.get().clone(),
+		created: msec_to_rfc3339(params.creation_date),
+		keys,
+		buckets,
+	};
+
+	with_version(json_ok_response(&res)?, &resource_version(&group)?)
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct GetGroupInfoResult {
+	id: String,
+	name: String,
+	created: String,
+	/// Access keys that are members of the group
+	keys: Vec<GroupInfoKey>,
+	/// Buckets on which the group gives permissions to its members
+	buckets: Vec<GroupInfoBucket>,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct GroupInfoKey {
+	access_key_id: String,
+	name: String,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct GroupInfoBucket {
+	id: String,
+	global_aliases: Vec<String>,
+	permissions: ApiBucketKeyPerm,
+}
+
+fn parse_group_id(id: &str) -> Result<Uuid, Error> {
+	let id_hex = hex::decode(id).ok_or_bad_request("Invalid group id")?;
+	Ok(Uuid::try_from(&id_hex).ok_or_bad_request("Invalid group id")?)
+}
+
+fn parse_bucket_id(id: &str) -> Result<Uuid, Error> {
+	let id_hex = hex::decode(id).ok_or_bad_request("Invalid bucket id")?;
+	Ok(Uuid::try_from(&id_hex).ok_or_bad_request("Invalid bucket id")?)
+}
+
+// ---- API description ----
+
+pub(crate) fn api_spec(spec: &mut ApiSpec) {
+	spec.op("GET", "/v1/group", "GetGroupInfo")
+		.summary("List all permission groups, or get information about a group when `id` is given")
+		.query("id", false)
+		.response::<Vec<ListGroupResultItem>>()
+		.response::<GetGroupInfoResult>()
+		.add();
+	spec.op("POST", "/v1/group", "UpdateGroup")
+		.summary("Create a permission group, or update the group given by `id`")
+		.query("id", false)
+		.request::<CreateGroupRequest>()
+		.request::<UpdateGroupRequest>()
+		.response::<GetGroupInfoResult>()
+		.add();
+	spec.op("DELETE", "/v1/group", "DeleteGroup")
+		.summary("Delete a permission group")
+		.query("id", true)
+		.no_content()
+		.add();
+	spec.op("POST", "/v1/group/allow", "GroupAllowBucket")
+		.summary("Give permissions on a bucket to the members of a group")
+		.request::<GroupBucketPermChangeRequest>()
+		.response::<GetGroupInfoResult>()
+		.add();
+	spec.op("POST", "/v1/group/deny", "GroupDenyBucket")
+		.summary("Remove permissions on a bucket from the members of a group")
+		.request::<GroupBucketPermChangeRequest>()
+		.response::<GetGroupInfoResult>()
+		.add();
+}
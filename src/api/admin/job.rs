@@ -0,0 +1,105 @@
+use std::sync::Arc;
+
+use hyper::{Body, Response};
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use garage_util::data::*;
+use garage_util::time::*;
+
+use garage_model::garage::Garage;
+use garage_model::job::*;
+
+use crate::admin::error::*;
+use crate::admin::spec::ApiSpec;
+use crate::helpers::json_ok_response;
+
+pub async fn handle_list_jobs(garage: &Arc<Garage>) -> Result<Response<Body>, Error> {
+	let res = garage
+		.jobs
+		.list()?
+		.into_iter()
+		.map(|job| JobResponse::new(garage, job))
+		.collect::<Vec<_>>();
+	Ok(json_ok_response(&res)?)
+}
+
+pub async fn handle_get_job(garage: &Arc<Garage>, id: String) -> Result<Response<Body>, Error> {
+	let job_id = parse_job_id(&id)?;
+	let job = garage.jobs.get(job_id)?.ok_or(Error::NoSuchJob(id))?;
+	Ok(json_ok_response(&JobResponse::new(garage, job))?)
+}
+
+pub async fn handle_cancel_job(garage: &Arc<Garage>, id: String) -> Result<Response<Body>, Error> {
+	let job_id = parse_job_id(&id)?;
+	if garage.jobs.get(job_id)?.is_none() {
+		return Err(Error::NoSuchJob(id));
+	}
+	let job = garage.jobs.cancel(job_id)?;
+	Ok(json_ok_response(&JobResponse::new(garage, job))?)
+}
+
+fn parse_job_id(id: &str) -> Result<Uuid, Error> {
+	let id_hex = hex::decode(id).ok_or_bad_request("Invalid job id")?;
+	Ok(Uuid::try_from(&id_hex).ok_or_bad_request("Invalid job id")?)
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct JobResponse {
+	id: String,
+	node: String,
+	kind: String,
+	description: String,
+	state: &'static str,
+	cancel_requested: bool,
+	created: String,
+	updated: String,
+	finished: Option<String>,
+	/// Percentage of the work done, if known
+	progress: Option<f32>,
+	status: Option<String>,
+	errors: u64,
+	last_error: Option<String>,
+}
+
+impl JobResponse {
+	pub(crate) fn new(garage: &Garage, job: JobRecord) -> Self {
+		Self {
+			id: hex::encode(job.id),
+			node: hex::encode(garage.system.id),
+			kind: job.kind,
+			description: job.description,
+			state: match job.state {
+				JobState::Running => "running",
+				JobState::Completed => "completed",
+				JobState::Cancelled => "cancelled",
+				JobState::Failed => "failed",
+			},
+			cancel_requested: job.cancel_requested,
+			created: msec_to_rfc3339(job.created),
+			updated: msec_to_rfc3339(job.updated),
+			finished: job.finished.map(msec_to_rfc3339),
+			progress: job.progress.map(|p| (p * 10000.).round() / 100.),
+			status: job.status,
+			errors: job.errors,
+			last_error: job.last_error,
+		}
+	}
+}
+
+// ---- API description ----
+
+pub(crate) fn api_spec(spec: &mut ApiSpec) {
+	spec.op("GET", "/v1/job", "GetJob")
+		.summary("List the jobs of this node, or get a job when `id` is given")
+		.query("id", false)
+		.response::<Vec<JobResponse>>()
+		.response::<JobResponse>()
+		.add();
+	spec.op("POST", "/v1/job/cancel", "CancelJob")
+		.summary("Cancel a job")
+		.query("id", true)
+		.response::<JobResponse>()
+		.add();
+}
@@ -1,15 +1,23 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
 
-use hyper::{Body, Request, Response, StatusCode};
+use chrono::{DateTime, TimeZone, Utc};
+use hyper::{Body, HeaderMap, Request, Response, StatusCode};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use garage_table::*;
 
 use garage_model::garage::Garage;
 use garage_model::key_table::*;
+use garage_model::key_usage::*;
+use garage_model::permission::BucketKeyPerm;
+use garage_util::time::*;
 
+use crate::admin::deletion::*;
 use crate::admin::error::*;
+use crate::admin::precondition::*;
+use crate::admin::spec::ApiSpec;
 use crate::helpers::{is_default, json_ok_response, parse_json_body};
 
 pub async fn handle_list_keys(garage: &Arc<Garage>) -> Result<Response<Body>, Error> {
@@ -27,17 +35,20 @@ pub async fn handle_list_keys(garage: &Arc<Garage>) -> Result<Response<Body>, Er
 		.map(|k| ListKeyResultItem {
 			id: k.key_id.to_string(),
 			name: k.params().unwrap().name.get().clone(),
+			expiration: k.expiration().map(msec_to_rfc3339),
 		})
 		.collect::<Vec<_>>();
 
 	Ok(json_ok_response(&res)?)
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 struct ListKeyResultItem {
 	id: String,
 	name: String,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	expiration: Option<String>,
 }
 
 pub async fn handle_get_key_info(
@@ -66,16 +77,25 @@ pub async fn handle_create_key(
 ) -> Result<Response<Body>, Error> {
 	let req = parse_json_body::<CreateKeyRequest>(req).await?;
 
-	let key = Key::new(req.name.as_deref().unwrap_or("Unnamed key"));
+	let mut key = Key::new(req.name.as_deref().unwrap_or("Unnamed key"));
+	if let Some(expiration) = req.expiration {
+		let expiration = parse_expiration(&expiration)?;
+		key.params_mut()
+			.unwrap()
+			.expiration
+			.update(Some(expiration));
+	}
 	garage.key_table.insert(&key).await?;
 
 	key_info_results(garage, key, true).await
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 struct CreateKeyRequest {
 	name: Option<String>,
+	/// Date after which the key can no longer be used, in RFC 3339 format
+	expiration: Option<String>,
 }
 
 pub async fn handle_import_key(
@@ -89,23 +109,33 @@ pub async fn handle_import_key(
 		return Err(Error::KeyAlreadyExists(req.access_key_id.to_string()));
 	}
 
-	let imported_key = Key::import(
+	let mut imported_key = Key::import(
 		&req.access_key_id,
 		&req.secret_access_key,
 		req.name.as_deref().unwrap_or("Imported key"),
 	)
 	.ok_or_bad_request("Invalid key format")?;
+	if let Some(expiration) = req.expiration {
+		let expiration = parse_expiration(&expiration)?;
+		imported_key
+			.params_mut()
+			.unwrap()
+			.expiration
+			.update(Some(expiration));
+	}
 	garage.key_table.insert(&imported_key).await?;
 
 	key_info_results(garage, imported_key, false).await
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 struct ImportKeyRequest {
 	access_key_id: String,
 	secret_access_key: String,
 	name: Option<String>,
+	/// Date after which the key can no longer be used, in RFC 3339 format
+	expiration: Option<String>,
 }
 
 pub async fn handle_update_key(
@@ -113,9 +143,10 @@ pub async fn handle_update_key(
 	id: String,
 	req: Request<Body>,
 ) -> Result<Response<Body>, Error> {
-	let req = parse_json_body::<UpdateKeyRequest>(req).await?;
-
 	let mut key = garage.key_helper().get_existing_key(&id).await?;
+	check_if_match(garage, req.headers(), &resource_version(&key)?)?;
+
+	let req = parse_json_body::<UpdateKeyRequest>(req).await?;
 
 	let key_state = key.state.as_option_mut().unwrap();
 
@@ -132,24 +163,74 @@ pub async fn handle_update_key(
 			key_state.allow_create_bucket.update(false);
 		}
 	}
+	if req.never_expires {
+		key_state.expiration.update(None);
+	} else if let Some(expiration) = req.expiration {
+		key_state
+			.expiration
+			.update(Some(parse_expiration(&expiration)?));
+	}
+	update_deletion_protection(
+		&mut key_state.deletion_protection,
+		&mut key_state.pending_deletion,
+		req.deletion_protection,
+		req.cancel_deletion,
+	)?;
 
 	garage.key_table.insert(&key).await?;
 
 	key_info_results(garage, key, false).await
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 struct UpdateKeyRequest {
 	name: Option<String>,
 	allow: Option<KeyPerm>,
 	deny: Option<KeyPerm>,
+	/// Date after which the key can no longer be used, in RFC 3339 format
+	expiration: Option<String>,
+	/// Remove the expiration date of the key
+	#[serde(default)]
+	never_expires: bool,
+	/// Require a second admin token to delete the key. Can only be
+	/// disabled with the garage CLI.
+	deletion_protection: Option<bool>,
+	/// Cancel a pending deletion of the key
+	#[serde(default)]
+	cancel_deletion: bool,
 }
 
-pub async fn handle_delete_key(garage: &Arc<Garage>, id: String) -> Result<Response<Body>, Error> {
-	let mut key = garage.key_helper().get_existing_key(&id).await?;
+fn parse_expiration(date: &str) -> Result<u64, Error> {
+	let expiration = parse_usage_date(date)?;
+	if expiration <= now_msec() {
+		return Err(Error::bad_request("The expiration date is in the past"));
+	}
+	Ok(expiration)
+}
 
-	key.state.as_option().unwrap();
+pub async fn handle_delete_key(
+	garage: &Arc<Garage>,
+	id: String,
+	headers: &HeaderMap,
+	admin_token: &str,
+) -> Result<Response<Body>, Error> {
+	let mut key = garage.key_helper().get_existing_key(&id).await?;
+	check_if_match(garage, headers, &resource_version(&key)?)?;
+
+	// Protected keys are only deleted when the deletion is confirmed
+	// with a second admin token
+	if key.deletion_protection() {
+		let key_state = key.state.as_option_mut().unwrap();
+		if !request_deletion(garage, &mut key_state.pending_deletion, admin_token)? {
+			garage.key_table.insert(&key).await?;
+			let key_state = key.state.as_option().unwrap();
+			return deletion_requested_response(
+				&key_state.pending_deletion,
+				&resource_version(&key)?,
+			);
+		}
+	}
 
 	garage.key_helper().delete_key(&mut key).await?;
 
@@ -158,6 +239,177 @@ pub async fn handle_delete_key(garage: &Arc<Garage>, id: String) -> Result<Respo
 		.body(Body::empty())?)
 }
 
+pub async fn handle_claim_key_secret(
+	garage: &Arc<Garage>,
+	id: String,
+) -> Result<Response<Body>, Error> {
+	let mut key = garage.key_helper().get_existing_key(&id).await?;
+
+	let key_state = key.state.as_option_mut().unwrap();
+	if !*key_state.secret_pending.get() {
+		return Err(Error::bad_request(
+			"The secret of this key was already retrieved, or the key was not provisioned from LDAP",
+		));
+	}
+	key_state.secret_pending.update(false);
+	garage.key_table.insert(&key).await?;
+
+	let key_state = key.state.as_option().unwrap();
+	Ok(json_ok_response(&ClaimKeySecretResult {
+		access_key_id: key.key_id.clone(),
+		secret_access_key: key_state.secret_key.clone(),
+		ldap_user: key_state.ldap_user.get().clone(),
+	})?)
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct ClaimKeySecretResult {
+	access_key_id: String,
+	secret_access_key: String,
+	ldap_user: Option<String>,
+}
+
+/// Default period for which key usage is returned, if no start date is given
+const DEFAULT_USAGE_PERIOD_MSEC: u64 = 30 * 24 * 3600 * 1000;
+
+pub async fn handle_get_key_usage(
+	garage: &Arc<Garage>,
+	id: String,
+	start: Option<String>,
+	end: Option<String>,
+	granularity: Option<String>,
+) -> Result<Response<Body>, Error> {
+	let key = garage.key_helper().get_existing_key(&id).await?;
+
+	let end = match end {
+		Some(d) => parse_usage_date(&d)?,
+		None => now_msec(),
+	};
+	let start = match start {
+		Some(d) => parse_usage_date(&d)?,
+		None => end.saturating_sub(DEFAULT_USAGE_PERIOD_MSEC),
+	};
+	let granularity = match granularity.as_deref() {
+		None | Some("hour") => USAGE_WINDOW_MSEC,
+		Some("day") => 24 * 3600 * 1000,
+		Some(g) => {
+			return Err(Error::bad_request(format!(
+				"Invalid granularity: {} (expected hour or day)",
+				g
+			)))
+		}
+	};
+
+	let mut windows = BTreeMap::<u64, KeyUsageResult>::new();
+	let mut pos = window_sort_key(start);
+	let mut first_page = true;
+	'pages: loop {
+		let entries = garage
+			.key_usage
+			.counter
+			.table
+			.get_range(
+				&key.key_id,
+				Some(pos.clone()),
+				None,
+				1000,
+				EnumerationOrder::Forward,
+			)
+			.await?;
+		let n_entries = entries.len();
+		for entry in entries {
+			// Pages after the first one start with the last entry of the previous page
+			if !first_page && entry.sk == pos {
+				continue;
+			}
+			let window_start = match parse_window_sort_key(&entry.sk) {
+				Some(t) => t,
+				None => continue,
+			};
+			if window_start >= end {
+				break 'pages;
+			}
+			windows
+				.entry(window_start - window_start % granularity)
+				.or_default()
+				.add(&total_usage(&entry));
+			pos = entry.sk;
+		}
+		if n_entries < 1000 {
+			break;
+		}
+		first_page = false;
+	}
+
+	let mut total = KeyUsageResult::default();
+	for w in windows.values() {
+		total.requests += w.requests;
+		total.bytes_received += w.bytes_received;
+		total.bytes_sent += w.bytes_sent;
+	}
+
+	let res = GetKeyUsageResult {
+		access_key_id: key.key_id,
+		start: msec_to_rfc3339(start),
+		end: msec_to_rfc3339(end),
+		total,
+		windows: windows
+			.into_iter()
+			.map(|(t, usage)| KeyUsageWindowResult {
+				start: msec_to_rfc3339(t),
+				usage,
+			})
+			.collect(),
+	};
+
+	Ok(json_ok_response(&res)?)
+}
+
+pub(crate) fn parse_usage_date(date: &str) -> Result<u64, Error> {
+	let date = DateTime::parse_from_rfc3339(date)
+		.ok_or_bad_request(format!("Invalid date: {} (expected RFC 3339 format)", date))?;
+	Ok(Utc
+		.from_utc_datetime(&date.naive_utc())
+		.timestamp_millis()
+		.max(0) as u64)
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct GetKeyUsageResult {
+	access_key_id: String,
+	start: String,
+	end: String,
+	total: KeyUsageResult,
+	windows: Vec<KeyUsageWindowResult>,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct KeyUsageWindowResult {
+	start: String,
+	#[serde(flatten)]
+	usage: KeyUsageResult,
+}
+
+#[derive(Serialize, Default, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct KeyUsageResult {
+	requests: i64,
+	bytes_received: i64,
+	bytes_sent: i64,
+}
+
+impl KeyUsageResult {
+	fn add(&mut self, usage: &HashMap<String, i64>) {
+		let get = |name: &str| usage.get(name).copied().unwrap_or(0);
+		self.requests += get(REQUESTS);
+		self.bytes_received += get(BYTES_RECEIVED);
+		self.bytes_sent += get(BYTES_SENT);
+	}
+}
+
 async fn key_info_results(
 	garage: &Arc<Garage>,
 	key: Key,
@@ -172,6 +424,7 @@ async fn key_info_results(
 		.items()
 		.iter()
 		.map(|(id, _)| id)
+		.chain(key_state.group_permissions.items().iter().map(|(id, _)| id))
 		.chain(
 			key_state
 				.local_aliases
@@ -188,6 +441,18 @@ async fn key_info_results(
 		}
 	}
 
+	let groups = garage
+		.permission_group_helper()
+		.list_groups()
+		.await?
+		.into_iter()
+		.filter(|g| g.has_member(&key.key_id))
+		.map(|g| KeyInfoGroupResult {
+			id: hex::encode(g.id),
+			name: g.params().unwrap().name.get().clone(),
+		})
+		.collect::<Vec<_>>();
+
 	let res = GetKeyInfoResult {
 		name: key_state.name.get().clone(),
 		access_key_id: key.key_id.clone(),
@@ -199,6 +464,13 @@ async fn key_info_results(
 		permissions: KeyPerm {
 			create_bucket: *key_state.allow_create_bucket.get(),
 		},
+		expiration: key.expiration().map(msec_to_rfc3339),
+		expired: key.is_expired(now_msec()),
+		deletion_protection: *key_state.deletion_protection.get(),
+		pending_deletion: pending_deletion(&key_state.pending_deletion),
+		ldap_user: key_state.ldap_user.get().clone(),
+		disabled: *key_state.disabled.get(),
+		secret_pending: *key_state.secret_pending.get(),
 		buckets: relevant_buckets
 			.into_values()
 			.map(|bucket| {
@@ -222,21 +494,23 @@ async fn key_info_results(
 					permissions: key_state
 						.authorized_buckets
 						.get(&bucket.id)
-						.map(|p| ApiBucketKeyPerm {
-							read: p.allow_read,
-							write: p.allow_write,
-							owner: p.allow_owner,
-						})
+						.map(ApiBucketKeyPerm::from)
+						.unwrap_or_default(),
+					group_permissions: key_state
+						.group_permissions
+						.get(&bucket.id)
+						.map(ApiBucketKeyPerm::from)
 						.unwrap_or_default(),
 				}
 			})
 			.collect::<Vec<_>>(),
+		groups,
 	};
 
-	Ok(json_ok_response(&res)?)
+	with_version(json_ok_response(&res)?, &resource_version(&key)?)
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 struct GetKeyInfoResult {
 	name: String,
@@ -244,26 +518,49 @@ struct GetKeyInfoResult {
 	#[serde(skip_serializing_if = "is_default")]
 	secret_access_key: Option<String>,
 	permissions: KeyPerm,
+	expiration: Option<String>,
+	expired: bool,
+	deletion_protection: bool,
+	pending_deletion: Option<ApiPendingDeletion>,
+	/// User of the LDAP directory for which the key was provisioned
+	ldap_user: Option<String>,
+	/// Whether requests signed with the key are refused, because its
+	/// LDAP user left the synchronized group
+	disabled: bool,
+	/// Whether the secret of the key can still be retrieved once with
+	/// ClaimKeySecret
+	secret_pending: bool,
 	buckets: Vec<KeyInfoBucketResult>,
+	groups: Vec<KeyInfoGroupResult>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 struct KeyPerm {
 	#[serde(default)]
 	create_bucket: bool,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 struct KeyInfoBucketResult {
 	id: String,
 	global_aliases: Vec<String>,
 	local_aliases: Vec<String>,
+	/// Permissions given directly to the key
 	permissions: ApiBucketKeyPerm,
+	/// Permissions given to the key by its permission groups
+	group_permissions: ApiBucketKeyPerm,
 }
 
-#[derive(Serialize, Deserialize, Default)]
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct KeyInfoGroupResult {
+	id: String,
+	name: String,
+}
+
+#[derive(Serialize, Deserialize, Default, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct ApiBucketKeyPerm {
 	#[serde(default)]
@@ -273,3 +570,57 @@ pub(crate) struct ApiBucketKeyPerm {
 	#[serde(default)]
 	pub(crate) owner: bool,
 }
+
+impl From<&BucketKeyPerm> for ApiBucketKeyPerm {
+	fn from(p: &BucketKeyPerm) -> Self {
+		ApiBucketKeyPerm {
+			read: p.allow_read,
+			write: p.allow_write,
+			owner: p.allow_owner,
+		}
+	}
+}
+
+// ---- API description ----
+
+pub(crate) fn api_spec(spec: &mut ApiSpec) {
+	spec.op("GET", "/v1/key", "GetKeyInfo")
+		.summary("List all keys, or get information about a key when `id` or `search` is given")
+		.query("id", false)
+		.query("search", false)
+		.query("showSecretKey", false)
+		.response::<Vec<ListKeyResultItem>>()
+		.response::<GetKeyInfoResult>()
+		.add();
+	spec.op("POST", "/v1/key", "UpdateKey")
+		.summary("Create a key, or update the key given by `id`")
+		.query("id", false)
+		.request::<CreateKeyRequest>()
+		.request::<UpdateKeyRequest>()
+		.response::<GetKeyInfoResult>()
+		.add();
+	spec.op("DELETE", "/v1/key", "DeleteKey")
+		.summary("Delete a key")
+		.query("id", true)
+		.no_content()
+		.accepted::<ApiPendingDeletion>()
+		.add();
+	spec.op("POST", "/v1/key/import", "ImportKey")
+		.summary("Import an existing key")
+		.request::<ImportKeyRequest>()
+		.response::<GetKeyInfoResult>()
+		.add();
+	spec.op("POST", "/v1/key/claim-secret", "ClaimKeySecret")
+		.summary("Retrieve once the secret of a key provisioned from LDAP")
+		.query("id", true)
+		.response::<ClaimKeySecretResult>()
+		.add();
+	spec.op("GET", "/v1/key/usage", "GetKeyUsage")
+		.summary("Requests and traffic of a key")
+		.query("id", true)
+		.query("start", false)
+		.query("end", false)
+		.query("granularity", false)
+		.response::<GetKeyUsageResult>()
+		.add();
+}
@@ -5,4 +5,11 @@ mod router_v1;
 
 mod bucket;
 mod cluster;
+mod deletion;
+mod group;
+mod job;
 mod key;
+mod oidc;
+mod precondition;
+mod repair;
+mod spec;
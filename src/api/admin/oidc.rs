@@ -0,0 +1,317 @@
+//! Authentication to the admin API with JWTs issued by an OpenID Connect
+//! provider. Tokens are validated locally with the keys of the JSON Web Key Set
+//! of the provider, which is fetched when it is first needed and refreshed
+//! periodically. The values of one of the claims of a token are mapped to
+//! admin scopes with the `scopes` table of the configuration.
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use bytes::Bytes;
+use hyper::client::{Client as HttpClient, HttpConnector};
+use hyper::{Body, Request};
+use hyper_rustls::HttpsConnector;
+use jsonwebtoken::jwk::{AlgorithmParameters, EllipticCurve, Jwk, JwkSet};
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use garage_util::config::{AdminOidcConfig, AdminScope};
+
+use crate::admin::error::*;
+
+/// Minimum delay between two refreshes of the key set that are caused
+/// by tokens signed with an unknown key
+const MIN_JWKS_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Timeout of the requests to the provider
+const OIDC_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub(crate) struct OidcValidator {
+	config: AdminOidcConfig,
+	client: HttpClient<HttpsConnector<HttpConnector>>,
+	/// Key set of the provider, and when it was fetched
+	jwks: Mutex<Option<(Instant, Arc<JwkSet>)>>,
+	/// Held while the key set is fetched, so that it is fetched once at a time
+	refresh: tokio::sync::Mutex<()>,
+}
+
+/// The bearer of a valid token
+pub(crate) struct OidcIdentity {
+	/// The `sub` claim of the token
+	pub(crate) subject: String,
+	/// The highest scope given by the claims of the token
+	pub(crate) scope: Option<AdminScope>,
+}
+
+#[derive(Deserialize)]
+struct Claims {
+	sub: String,
+	#[serde(flatten)]
+	other: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct OpenIdConfiguration {
+	jwks_uri: String,
+}
+
+impl OidcValidator {
+	pub(crate) fn new(config: AdminOidcConfig) -> Self {
+		let connector = hyper_rustls::HttpsConnectorBuilder::new().with_native_roots();
+		let connector = if config.allow_insecure_http {
+			connector.https_or_http().enable_http1().build()
+		} else {
+			connector.https_only().enable_http1().build()
+		};
+		Self {
+			config,
+			client: HttpClient::builder().build(connector),
+			jwks: Mutex::new(None),
+			refresh: tokio::sync::Mutex::new(()),
+		}
+	}
+
+	/// Validate a token, and return the identity and scope of its bearer
+	pub(crate) async fn validate(&self, token: &str) -> Result<OidcIdentity, Error> {
+		let header = decode_header(token).map_err(|_| Error::forbidden("Invalid JWT"))?;
+		let kid = header
+			.kid
+			.ok_or_else(|| Error::forbidden("JWT has no key ID"))?;
+		let (key, algorithms) = self.decoding_key(&kid).await?;
+
+		// The algorithm is given by the key, not chosen by the token
+		if !algorithms.contains(&header.alg) {
+			return Err(Error::forbidden(format!(
+				"JWT is signed with algorithm {:?}, which is not that of its key",
+				header.alg
+			)));
+		}
+		let mut validation = Validation::new(header.alg);
+		validation.algorithms = algorithms;
+		validation.set_issuer(&[&self.config.issuer]);
+		validation.set_audience(&[&self.config.audience]);
+		validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+
+		let claims = decode::<Claims>(token, &key, &validation)
+			.map_err(|e| Error::forbidden(format!("Invalid JWT: {}", e)))?
+			.claims;
+
+		Ok(OidcIdentity {
+			scope: claims_scope(&self.config, &claims.other),
+			subject: claims.sub,
+		})
+	}
+
+	/// The key with which tokens of a key ID are signed, and the algorithms
+	/// with which it can be used
+	async fn decoding_key(&self, kid: &str) -> Result<(DecodingKey, Vec<Algorithm>), Error> {
+		let jwks = self.key_set(kid).await?;
+		let jwk = jwks
+			.find(kid)
+			.ok_or_else(|| Error::forbidden("JWT is signed with an unknown key"))?;
+		let algorithms = key_algorithms(jwk);
+		if algorithms.is_empty() {
+			return Err(Error::forbidden(
+				"JWT is signed with a key of an unsupported type",
+			));
+		}
+		let key = DecodingKey::from_jwk(jwk)
+			.map_err(|e| Error::internal_error(format!("Invalid key in OIDC key set: {}", e)))?;
+		Ok((key, algorithms))
+	}
+
+	/// The key set with which a token signed with a key ID is validated,
+	/// refreshed if it is too old or does not have this key ID. The key set
+	/// is fetched without blocking the validation of other tokens, which
+	/// use the previous key set meanwhile.
+	async fn key_set(&self, kid: &str) -> Result<Arc<JwkSet>, Error> {
+		let current = self.jwks.lock().unwrap().clone();
+		if !self.must_refresh(current.as_ref(), kid) {
+			return Ok(current.unwrap().1);
+		}
+
+		let _refresh = match self.refresh.try_lock() {
+			Ok(guard) => guard,
+			Err(_) => match current {
+				Some((_, set)) => return Ok(set),
+				None => self.refresh.lock().await,
+			},
+		};
+		// The key set may have been fetched while waiting for the lock
+		let current = self.jwks.lock().unwrap().clone();
+		if !self.must_refresh(current.as_ref(), kid) {
+			return Ok(current.unwrap().1);
+		}
+
+		let set = match self.fetch_jwks().await {
+			Ok(set) => Arc::new(set),
+			// Keep using the previous key set until the provider is reachable again
+			Err(e) => match current {
+				Some((_, set)) => {
+					warn!("Could not refresh the OIDC key set: {}", e);
+					set
+				}
+				None => return Err(e),
+			},
+		};
+		*self.jwks.lock().unwrap() = Some((Instant::now(), set.clone()));
+		Ok(set)
+	}
+
+	fn must_refresh(&self, current: Option<&(Instant, Arc<JwkSet>)>, kid: &str) -> bool {
+		match current {
+			None => true,
+			Some((fetched, set)) => {
+				fetched.elapsed() > Duration::from_secs(self.config.jwks_refresh_secs)
+					|| (set.find(kid).is_none() && fetched.elapsed() > MIN_JWKS_REFRESH_INTERVAL)
+			}
+		}
+	}
+
+	async fn fetch_jwks(&self) -> Result<JwkSet, Error> {
+		let url = match &self.config.jwks_url {
+			Some(url) => url.clone(),
+			None => {
+				let discovery = format!(
+					"{}/.well-known/openid-configuration",
+					self.config.issuer.trim_end_matches('/')
+				);
+				self.get_json::<OpenIdConfiguration>(&discovery)
+					.await?
+					.jwks_uri
+			}
+		};
+		self.get_json(&url).await
+	}
+
+	async fn get_json<T: DeserializeOwned>(&self, url: &str) -> Result<T, Error> {
+		let body = tokio::time::timeout(OIDC_REQUEST_TIMEOUT, self.get(url))
+			.await
+			.map_err(|_| Error::internal_error(format!("Request to {} timed out", url)))??;
+		serde_json::from_slice(&body)
+			.map_err(|e| Error::internal_error(format!("Invalid response from {}: {}", url, e)))
+	}
+
+	async fn get(&self, url: &str) -> Result<Bytes, Error> {
+		let req = Request::get(url).body(Body::empty())?;
+		let resp = self.client.request(req).await?;
+		if !resp.status().is_success() {
+			return Err(Error::internal_error(format!(
+				"Request to {} failed with status {}",
+				url,
+				resp.status()
+			)));
+		}
+		Ok(hyper::body::to_bytes(resp.into_body()).await?)
+	}
+}
+
+/// The algorithms with which a key of the key set can be used: the one given
+/// by its `alg` parameter if it has one, or those of its key type. Symmetric
+/// keys are never accepted.
+fn key_algorithms(jwk: &Jwk) -> Vec<Algorithm> {
+	let algorithms = match &jwk.algorithm {
+		AlgorithmParameters::RSA(_) => vec![
+			Algorithm::RS256,
+			Algorithm::RS384,
+			Algorithm::RS512,
+			Algorithm::PS256,
+			Algorithm::PS384,
+			Algorithm::PS512,
+		],
+		AlgorithmParameters::EllipticCurve(ec) => match ec.curve {
+			EllipticCurve::P256 => vec![Algorithm::ES256],
+			EllipticCurve::P384 => vec![Algorithm::ES384],
+			_ => vec![],
+		},
+		AlgorithmParameters::OctetKeyPair(okp) => match okp.curve {
+			EllipticCurve::Ed25519 => vec![Algorithm::EdDSA],
+			_ => vec![],
+		},
+		AlgorithmParameters::OctetKey(_) => vec![],
+	};
+	match jwk.common.algorithm {
+		Some(alg) => algorithms.into_iter().filter(|a| *a == alg).collect(),
+		None => algorithms,
+	}
+}
+
+/// The highest scope given by the values of the configured claim, which can
+/// be a list of strings or a string of space-separated values
+fn claims_scope(
+	config: &AdminOidcConfig,
+	claims: &HashMap<String, serde_json::Value>,
+) -> Option<AdminScope> {
+	let values = match claims.get(&config.scopes_claim) {
+		Some(serde_json::Value::String(s)) => s.split_whitespace().collect::<Vec<_>>(),
+		Some(serde_json::Value::Array(a)) => a.iter().filter_map(|v| v.as_str()).collect(),
+		_ => vec![],
+	};
+	values
+		.into_iter()
+		.filter_map(|v| config.scopes.get(v).copied())
+		.max()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_claims_scope() {
+		let config = AdminOidcConfig {
+			issuer: "https://sso.example.com".into(),
+			audience: "garage".into(),
+			jwks_url: None,
+			jwks_refresh_secs: 3600,
+			allow_insecure_http: false,
+			scopes_claim: "groups".into(),
+			scopes: vec![
+				("ops".to_string(), AdminScope::Read),
+				("admins".to_string(), AdminScope::Admin),
+			]
+			.into_iter()
+			.collect(),
+		};
+		let claims = |v: serde_json::Value| -> HashMap<String, serde_json::Value> {
+			vec![("groups".to_string(), v)].into_iter().collect()
+		};
+
+		assert_eq!(
+			claims_scope(&config, &claims(serde_json::json!(["users", "ops"]))),
+			Some(AdminScope::Read)
+		);
+		assert_eq!(
+			claims_scope(&config, &claims(serde_json::json!("ops admins"))),
+			Some(AdminScope::Admin)
+		);
+		assert_eq!(
+			claims_scope(&config, &claims(serde_json::json!(["users"]))),
+			None
+		);
+		assert_eq!(claims_scope(&config, &HashMap::new()), None);
+	}
+
+	#[test]
+	fn test_key_algorithms() {
+		let jwk = |v: serde_json::Value| -> Jwk { serde_json::from_value(v).unwrap() };
+		let n = "sXchDaQebHnPiGvyDOAT4saGEUetSyo9MKLOoWFsueri23bOdgWp4Dy1WlUzewbgBHod5pcM9H95GQRV3JDXboIRROSBigeC5yjU1hGzHHyXss8UDprecbAYxknTcQkhslANGRUZmdTOQ5qTRsLAt6BTYuyvVRdhS8exSZEy_c4gs_7svlJJQ4H9_NxsiIoLwAEk7-Q3UXERGYw_75IDrGA84-lA_-Ct4eTlXHBIY2EaV7t7LjJaynVJCpkv4LKjTTAumiGUIuQhrNhZLuF_RJLqHpM2kgWFLU7-VTdL1VbC2tejvcI2BlMkEpk1BzBZI0KQB0GaDWFLN-aEAw3vRw";
+
+		// Without `alg`, all the algorithms of the key type are accepted
+		let rsa = jwk(serde_json::json!({"kty": "RSA", "kid": "a", "n": n, "e": "AQAB"}));
+		assert!(key_algorithms(&rsa).contains(&Algorithm::RS256));
+		assert!(key_algorithms(&rsa).contains(&Algorithm::PS512));
+		assert!(!key_algorithms(&rsa).contains(&Algorithm::ES256));
+
+		// With `alg`, only this algorithm is accepted
+		let rsa =
+			jwk(serde_json::json!({"kty": "RSA", "kid": "a", "alg": "RS384", "n": n, "e": "AQAB"}));
+		assert_eq!(key_algorithms(&rsa), vec![Algorithm::RS384]);
+
+		// Symmetric keys are refused, so that HMAC tokens are never accepted
+		let oct =
+			jwk(serde_json::json!({"kty": "oct", "kid": "a", "alg": "HS256", "k": "c2VjcmV0"}));
+		assert!(key_algorithms(&oct).is_empty());
+	}
+}
@@ -0,0 +1,79 @@
+//! Optimistic concurrency control for the updates of buckets and keys through
+//! the admin API. Responses that describe a bucket or a key have an `ETag`
+//! header with the version of the resource, and updates that give a version in
+//! an `If-Match` header are refused if the resource was modified since then.
+//! With `require_if_match` in the `[admin]` section of the configuration,
+//! updates that don't give a version are refused.
+use hyper::header::{HeaderMap, HeaderValue, ETAG, IF_MATCH};
+use hyper::{Body, Response};
+use serde::Serialize;
+
+use garage_util::data::blake2sum;
+use garage_util::encode::nonversioned_encode;
+
+use garage_model::garage::Garage;
+
+use crate::admin::error::*;
+
+/// Version of a bucket or key, as a quoted hash of its table entry, which
+/// changes with any update of the entry
+pub(crate) fn resource_version<T: Serialize>(entry: &T) -> Result<String, Error> {
+	let bytes = nonversioned_encode(entry).ok_or_internal_error("Could not encode resource")?;
+	Ok(format!(
+		"\"{}\"",
+		hex::encode(&blake2sum(&bytes).as_slice()[..16])
+	))
+}
+
+/// Add the `ETag` header with the version of the resource to a response
+pub(crate) fn with_version(
+	mut resp: Response<Body>,
+	version: &str,
+) -> Result<Response<Body>, Error> {
+	resp.headers_mut().insert(
+		ETAG,
+		HeaderValue::from_str(version).ok_or_internal_error("Invalid version")?,
+	);
+	Ok(resp)
+}
+
+/// Check the `If-Match` header of an update against the current version of
+/// the resource it applies to
+pub(crate) fn check_if_match(
+	garage: &Garage,
+	headers: &HeaderMap,
+	version: &str,
+) -> Result<(), Error> {
+	let if_match = match headers.get(IF_MATCH) {
+		Some(v) => v.to_str().ok_or_bad_request("Invalid If-Match header")?,
+		None if garage.config.admin.require_if_match => return Err(Error::PreconditionRequired),
+		None => return Ok(()),
+	};
+	if if_match_matches(if_match, version) {
+		Ok(())
+	} else {
+		Err(Error::PreconditionFailed(version.to_string()))
+	}
+}
+
+fn if_match_matches(if_match: &str, version: &str) -> bool {
+	if_match
+		.split(',')
+		.map(str::trim)
+		.any(|etag| etag == "*" || etag.trim_start_matches("W/") == version)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_if_match_matches() {
+		let version = "\"0123abcd\"";
+		assert!(if_match_matches("\"0123abcd\"", version));
+		assert!(if_match_matches("*", version));
+		assert!(if_match_matches("\"ffff\", W/\"0123abcd\"", version));
+		assert!(!if_match_matches("\"ffff\"", version));
+		assert!(!if_match_matches("0123abcd", version));
+	}
+}
@@ -0,0 +1,87 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use hyper::{Body, Request, Response};
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+use garage_util::background::BackgroundRunner;
+
+use garage_rpc::ring::{Partition, PARTITION_BITS};
+
+use garage_model::garage::Garage;
+use garage_model::repair::*;
+
+use crate::admin::error::*;
+use crate::admin::job::JobResponse;
+use crate::admin::spec::ApiSpec;
+use crate::helpers::{json_ok_response, parse_json_body};
+
+pub async fn handle_launch_repair(
+	garage: &Arc<Garage>,
+	background: &BackgroundRunner,
+	req: Request<Body>,
+) -> Result<Response<Body>, Error> {
+	let req = parse_json_body::<LaunchRepairRequest>(req).await?;
+
+	let partitions = match req.partitions {
+		Some(PartitionRangeRequest { first, last }) => {
+			if first > last || last as usize >= (1 << PARTITION_BITS) {
+				return Err(Error::bad_request(format!(
+					"Invalid partition range {}-{}: partitions are numbered from 0 to {}",
+					first,
+					last,
+					(1 << PARTITION_BITS) - 1
+				)));
+			}
+			Some((first, last))
+		}
+		None => None,
+	};
+	let concurrency = req.concurrency.unwrap_or(1);
+	if concurrency == 0 {
+		return Err(Error::bad_request("concurrency must be at least 1"));
+	}
+
+	let params = RepairParams {
+		partitions,
+		concurrency,
+		data_dir: req.data_dir,
+	};
+	let job = launch_repair(garage, background, req.repair, params)
+		.await
+		.map_err(|e| Error::bad_request(format!("Unable to launch repair: {}", e)))?;
+	info!(
+		"Repair job {:?} ({:?}) launched through the admin API",
+		job.id, req.repair
+	);
+
+	Ok(json_ok_response(&JobResponse::new(garage, job))?)
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct LaunchRepairRequest {
+	#[schemars(with = "String")]
+	repair: RepairType,
+	partitions: Option<PartitionRangeRequest>,
+	concurrency: Option<usize>,
+	data_dir: Option<PathBuf>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+struct PartitionRangeRequest {
+	first: Partition,
+	last: Partition,
+}
+
+// ---- API description ----
+
+pub(crate) fn api_spec(spec: &mut ApiSpec) {
+	spec.op("POST", "/v1/repair", "LaunchRepair")
+		.summary("Launch a repair on this node, as a job")
+		.request::<LaunchRepairRequest>()
+		.response::<JobResponse>()
+		.add();
+}
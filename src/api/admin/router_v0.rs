@@ -14,6 +14,7 @@ pub enum Endpoint {
 	CheckDomain,
 	Health,
 	Metrics,
+	GetSpec,
 	GetClusterStatus,
 	GetClusterHealth,
 	ConnectClusterNodes,
@@ -89,6 +90,7 @@ impl Endpoint {
 			GET "/check" => CheckDomain,
 			GET "/health" => Health,
 			GET "/metrics" => Metrics,
+			GET "/v0/spec" => GetSpec,
 			GET "/v0/status" => GetClusterStatus,
 			GET "/v0/health" => GetClusterHealth,
 			POST "/v0/connect" => ConnectClusterNodes,
@@ -21,14 +21,22 @@ pub enum Endpoint {
 	CheckDomain,
 	Health,
 	Metrics,
+	WebUi,
+	GetSpec,
 	GetClusterStatus,
 	GetClusterHealth,
 	ConnectClusterNodes,
+	GetLogFilter,
+	SetLogFilter,
+	ReloadConfig,
+	GetFaultInjection,
+	SetFaultInjection,
 	// Layout
 	GetClusterLayout,
 	UpdateClusterLayout,
 	ApplyClusterLayout,
 	RevertClusterLayout,
+	GetLayoutRebalance,
 	// Keys
 	ListKeys,
 	CreateKey,
@@ -44,12 +52,22 @@ pub enum Endpoint {
 	UpdateKey {
 		id: String,
 	},
+	ClaimKeySecret {
+		id: String,
+	},
+	GetKeyUsage {
+		id: String,
+		start: Option<String>,
+		end: Option<String>,
+		granularity: Option<String>,
+	},
 	// Buckets
 	ListBuckets,
 	CreateBucket,
 	GetBucketInfo {
 		id: Option<String>,
 		global_alias: Option<String>,
+		show_secret: Option<String>,
 	},
 	DeleteBucket {
 		id: String,
@@ -57,6 +75,41 @@ pub enum Endpoint {
 	UpdateBucket {
 		id: String,
 	},
+	ImportBucket {
+		id: String,
+	},
+	GetBucketDedupStats {
+		id: String,
+	},
+	EmptyBucket {
+		id: String,
+	},
+	GetEmptyBucketStatus {
+		id: String,
+	},
+	CloneBucket {
+		id: String,
+	},
+	UpdateBucketObjectsMetadata {
+		id: String,
+	},
+	ListBucketTrash {
+		id: String,
+		prefix: Option<String>,
+		start: Option<String>,
+	},
+	RestoreBucketTrash {
+		id: String,
+	},
+	PresignBucketObjects {
+		id: String,
+	},
+	GetBucketWebUsage {
+		start: Option<String>,
+		end: Option<String>,
+		limit: Option<String>,
+	},
+	GetBucketUsage,
 	// Bucket-Key Permissions
 	BucketAllowKey,
 	BucketDenyKey,
@@ -79,6 +132,30 @@ pub enum Endpoint {
 		access_key_id: String,
 		alias: String,
 	},
+	// Permission groups
+	ListGroups,
+	GetGroupInfo {
+		id: String,
+	},
+	CreateGroup,
+	UpdateGroup {
+		id: String,
+	},
+	DeleteGroup {
+		id: String,
+	},
+	GroupAllowBucket,
+	GroupDenyBucket,
+	// Repairs
+	LaunchRepair,
+	// Jobs
+	ListJobs,
+	GetJob {
+		id: String,
+	},
+	CancelJob {
+		id: String,
+	},
 }}
 
 impl Endpoint {
@@ -97,29 +174,50 @@ impl Endpoint {
 			GET "/check" => CheckDomain,
 			GET "/health" => Health,
 			GET "/metrics" => Metrics,
+			GET "/ui" => WebUi,
+			GET "/v1/spec" => GetSpec,
 			GET "/v1/status" => GetClusterStatus,
 			GET "/v1/health" => GetClusterHealth,
 			POST "/v1/connect" => ConnectClusterNodes,
+			GET "/v1/log-filter" => GetLogFilter,
+			POST "/v1/log-filter" => SetLogFilter,
+			POST "/v1/config/reload" => ReloadConfig,
+			GET "/v1/debug/faults" => GetFaultInjection,
+			POST "/v1/debug/faults" => SetFaultInjection,
 			// Layout endpoints
 			GET "/v1/layout" => GetClusterLayout,
 			POST "/v1/layout" => UpdateClusterLayout,
 			POST "/v1/layout/apply" => ApplyClusterLayout,
 			POST "/v1/layout/revert" => RevertClusterLayout,
+			GET "/v1/layout/rebalance" => GetLayoutRebalance,
 			// API key endpoints
 			GET "/v1/key" if id => GetKeyInfo (query_opt::id, query_opt::search, query_opt::show_secret_key),
 			GET "/v1/key" if search => GetKeyInfo (query_opt::id, query_opt::search, query_opt::show_secret_key),
 			POST "/v1/key" if id => UpdateKey (query::id),
 			POST "/v1/key" => CreateKey,
 			POST "/v1/key/import" => ImportKey,
+			POST "/v1/key/claim-secret" => ClaimKeySecret (query::id),
+			GET "/v1/key/usage" => GetKeyUsage (query::id, query_opt::start, query_opt::end, query_opt::granularity),
 			DELETE "/v1/key" if id => DeleteKey (query::id),
 			GET "/v1/key" => ListKeys,
 			// Bucket endpoints
-			GET "/v1/bucket" if id => GetBucketInfo (query_opt::id, query_opt::global_alias),
-			GET "/v1/bucket" if global_alias => GetBucketInfo (query_opt::id, query_opt::global_alias),
+			GET "/v1/bucket" if id => GetBucketInfo (query_opt::id, query_opt::global_alias, query_opt::show_secret),
+			GET "/v1/bucket" if global_alias => GetBucketInfo (query_opt::id, query_opt::global_alias, query_opt::show_secret),
 			GET "/v1/bucket" => ListBuckets,
 			POST "/v1/bucket" => CreateBucket,
 			DELETE "/v1/bucket" if id => DeleteBucket (query::id),
 			PUT "/v1/bucket" if id => UpdateBucket (query::id),
+			POST "/v1/bucket/import" => ImportBucket (query::id),
+			GET "/v1/bucket/dedup" => GetBucketDedupStats (query::id),
+			POST "/v1/bucket/empty" => EmptyBucket (query::id),
+			GET "/v1/bucket/empty" => GetEmptyBucketStatus (query::id),
+			POST "/v1/bucket/clone" => CloneBucket (query::id),
+			POST "/v1/bucket/update-metadata" => UpdateBucketObjectsMetadata (query::id),
+			GET "/v1/bucket/trash" => ListBucketTrash (query::id, query_opt::prefix, query_opt::start),
+			POST "/v1/bucket/trash/restore" => RestoreBucketTrash (query::id),
+			POST "/v1/bucket/presign" => PresignBucketObjects (query::id),
+			GET "/v1/bucket/web-usage" => GetBucketWebUsage (query_opt::start, query_opt::end, query_opt::limit),
+			GET "/v1/bucket/usage" => GetBucketUsage,
 			// Bucket-key permissions
 			POST "/v1/bucket/allow" => BucketAllowKey,
 			POST "/v1/bucket/deny" => BucketDenyKey,
@@ -128,6 +226,20 @@ impl Endpoint {
 			DELETE "/v1/bucket/alias/global" => GlobalUnaliasBucket (query::id, query::alias),
 			PUT "/v1/bucket/alias/local" => LocalAliasBucket (query::id, query::access_key_id, query::alias),
 			DELETE "/v1/bucket/alias/local" => LocalUnaliasBucket (query::id, query::access_key_id, query::alias),
+			// Permission groups
+			GET "/v1/group" if id => GetGroupInfo (query::id),
+			GET "/v1/group" => ListGroups,
+			POST "/v1/group" if id => UpdateGroup (query::id),
+			POST "/v1/group" => CreateGroup,
+			DELETE "/v1/group" if id => DeleteGroup (query::id),
+			POST "/v1/group/allow" => GroupAllowBucket,
+			POST "/v1/group/deny" => GroupDenyBucket,
+			// Repair endpoints
+			POST "/v1/repair" => LaunchRepair,
+			// Job endpoints
+			GET "/v1/job" if id => GetJob (query::id),
+			GET "/v1/job" => ListJobs,
+			POST "/v1/job/cancel" => CancelJob (query::id),
 		]);
 
 		if let Some(message) = query.nonempty_message() {
@@ -141,6 +253,8 @@ impl Endpoint {
 	/// its v1/ URL.
 	pub fn from_v0(v0_endpoint: router_v0::Endpoint) -> Result<Self, Error> {
 		match v0_endpoint {
+			router_v0::Endpoint::GetSpec => Ok(Self::GetSpec),
+
 			// Cluster endpoints
 			router_v0::Endpoint::ConnectClusterNodes => Ok(Self::ConnectClusterNodes),
 			// - GetClusterStatus: response format changed
@@ -164,9 +278,11 @@ impl Endpoint {
 			// - UpdateKey: response format changed (secret key no longer returned)
 
 			// Bucket endpoints
-			router_v0::Endpoint::GetBucketInfo { id, global_alias } => {
-				Ok(Self::GetBucketInfo { id, global_alias })
-			}
+			router_v0::Endpoint::GetBucketInfo { id, global_alias } => Ok(Self::GetBucketInfo {
+				id,
+				global_alias,
+				show_secret: None,
+			}),
 			router_v0::Endpoint::ListBuckets => Ok(Self::ListBuckets),
 			router_v0::Endpoint::CreateBucket => Ok(Self::CreateBucket),
 			router_v0::Endpoint::DeleteBucket { id } => Ok(Self::DeleteBucket { id }),
@@ -215,6 +331,8 @@ impl Endpoint {
 		match self {
 			Self::Health => Authorization::None,
 			Self::CheckDomain => Authorization::None,
+			Self::WebUi => Authorization::None,
+			Self::GetSpec => Authorization::None,
 			Self::Metrics => Authorization::MetricsToken,
 			_ => Authorization::AdminToken,
 		}
@@ -230,6 +348,12 @@ generateQueryParameters! {
 		"globalAlias" => global_alias,
 		"alias" => alias,
 		"accessKeyId" => access_key_id,
-		"showSecretKey" => show_secret_key
+		"showSecretKey" => show_secret_key,
+		"showSecret" => show_secret,
+		"start" => start,
+		"end" => end,
+		"granularity" => granularity,
+		"prefix" => prefix,
+		"limit" => limit
 	]
 }
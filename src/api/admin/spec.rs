@@ -0,0 +1,229 @@
+//! Machine-readable description of the admin API in the OpenAPI 3 format,
+//! served on `/v1/spec`. The schemas of request and response bodies are
+//! derived from the types that the handlers parse and return, so that client
+//! generators always see the API that is actually implemented.
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use hyper::{Body, Response};
+use schemars::gen::{SchemaGenerator, SchemaSettings};
+use schemars::schema::Schema;
+use schemars::JsonSchema;
+use serde_json::{json, Value};
+
+use garage_model::garage::Garage;
+
+use crate::admin::error::*;
+use crate::helpers::{json_ok_response, CustomApiErrorBody};
+
+lazy_static::lazy_static! {
+	static ref SPEC: Value = build_spec();
+}
+
+pub async fn handle_get_spec(_garage: &Arc<Garage>) -> Result<Response<Body>, Error> {
+	Ok(json_ok_response(&*SPEC)?)
+}
+
+fn build_spec() -> Value {
+	let mut spec = ApiSpec::new();
+
+	spec.op("GET", "/health", "Health")
+		.summary("Check that the node can serve requests")
+		.public()
+		.text_response()
+		.add();
+	spec.op("GET", "/check", "CheckDomain")
+		.summary("Check that a domain is served by the website endpoint of Garage")
+		.public()
+		.query("domain", true)
+		.text_response()
+		.add();
+	spec.op("GET", "/metrics", "Metrics")
+		.summary("Metrics of the node in the Prometheus format")
+		.text_response()
+		.add();
+	spec.op("GET", "/v1/spec", "GetSpec")
+		.summary("This description of the admin API")
+		.public()
+		.response_any()
+		.add();
+
+	crate::admin::cluster::api_spec(&mut spec);
+	crate::admin::key::api_spec(&mut spec);
+	crate::admin::bucket::api_spec(&mut spec);
+	crate::admin::group::api_spec(&mut spec);
+	crate::admin::repair::api_spec(&mut spec);
+	crate::admin::job::api_spec(&mut spec);
+
+	spec.finish()
+}
+
+/// Description of the admin API being built
+pub(crate) struct ApiSpec {
+	gen: SchemaGenerator,
+	paths: BTreeMap<String, BTreeMap<String, Value>>,
+}
+
+impl ApiSpec {
+	fn new() -> Self {
+		Self {
+			gen: SchemaSettings::openapi3().into_generator(),
+			paths: BTreeMap::new(),
+		}
+	}
+
+	/// Start the description of an endpoint
+	pub(crate) fn op(&mut self, method: &str, path: &str, name: &str) -> Operation<'_> {
+		let error = self.gen.subschema_for::<CustomApiErrorBody>();
+		Operation {
+			spec: self,
+			method: method.to_lowercase(),
+			path: path.to_string(),
+			op: json!({
+				"operationId": name,
+				"parameters": [],
+				"responses": {
+					"default": {
+						"description": "Error",
+						"content": { "application/json": { "schema": error } },
+					},
+				},
+			}),
+		}
+	}
+
+	fn finish(mut self) -> Value {
+		json!({
+			"openapi": "3.0.3",
+			"info": {
+				"title": "Garage administration API",
+				"version": garage_util::version::garage_version(),
+			},
+			"components": {
+				"schemas": self.gen.take_definitions(),
+				"securitySchemes": {
+					"adminToken": {
+						"type": "http",
+						"scheme": "bearer",
+					},
+				},
+			},
+			"security": [{ "adminToken": [] }],
+			"paths": self.paths,
+		})
+	}
+}
+
+/// Description of an endpoint, added to the API description by `add`
+pub(crate) struct Operation<'a> {
+	spec: &'a mut ApiSpec,
+	method: String,
+	path: String,
+	op: Value,
+}
+
+impl<'a> Operation<'a> {
+	pub(crate) fn summary(mut self, summary: &str) -> Self {
+		self.op["summary"] = json!(summary);
+		self
+	}
+
+	/// The endpoint does not require the admin token
+	pub(crate) fn public(mut self) -> Self {
+		self.op["security"] = json!([]);
+		self
+	}
+
+	/// A query parameter, given as a string
+	pub(crate) fn query(mut self, name: &str, required: bool) -> Self {
+		self.op["parameters"].as_array_mut().unwrap().push(json!({
+			"name": name,
+			"in": "query",
+			"required": required,
+			"schema": { "type": "string" },
+		}));
+		self
+	}
+
+	/// The JSON body of the request. When called several times, the body
+	/// can be any of the given types.
+	pub(crate) fn request<T: JsonSchema>(mut self) -> Self {
+		let schema = self.spec.gen.subschema_for::<T>();
+		let prev = self.op["requestBody"]["content"]["application/json"]["schema"].take();
+		self.op["requestBody"] = json!({
+			"required": true,
+			"content": { "application/json": { "schema": one_of(prev, schema) } },
+		});
+		self
+	}
+
+	/// The JSON body of successful responses. When called several times,
+	/// the body can be any of the given types, e.g. for endpoints that return
+	/// a list or a single item depending on the query parameters.
+	pub(crate) fn response<T: JsonSchema>(mut self) -> Self {
+		let schema = self.spec.gen.subschema_for::<T>();
+		let prev = self.op["responses"]["200"]["content"]["application/json"]["schema"].take();
+		self.op["responses"]["200"] = json!({
+			"description": "Success",
+			"content": { "application/json": { "schema": one_of(prev, schema) } },
+		});
+		self
+	}
+
+	/// Successful responses have a JSON body without a fixed schema
+	pub(crate) fn response_any(mut self) -> Self {
+		self.op["responses"]["200"] = json!({
+			"description": "Success",
+			"content": { "application/json": { "schema": {} } },
+		});
+		self
+	}
+
+	/// Successful responses have a plain text body
+	pub(crate) fn text_response(mut self) -> Self {
+		self.op["responses"]["200"] = json!({
+			"description": "Success",
+			"content": { "text/plain": { "schema": { "type": "string" } } },
+		});
+		self
+	}
+
+	/// Successful responses have no body
+	pub(crate) fn no_content(mut self) -> Self {
+		self.op["responses"]["204"] = json!({ "description": "Success" });
+		self
+	}
+
+	/// The request can also be accepted without being fully carried out,
+	/// with a JSON body of this type
+	pub(crate) fn accepted<T: JsonSchema>(mut self) -> Self {
+		let schema = self.spec.gen.subschema_for::<T>();
+		self.op["responses"]["202"] = json!({
+			"description": "Accepted",
+			"content": { "application/json": { "schema": schema } },
+		});
+		self
+	}
+
+	pub(crate) fn add(self) {
+		let prev = self
+			.spec
+			.paths
+			.entry(self.path)
+			.or_default()
+			.insert(self.method, self.op);
+		debug_assert!(prev.is_none(), "endpoint described twice");
+	}
+}
+
+fn one_of(prev: Value, schema: Schema) -> Value {
+	let schema = json!(schema);
+	match prev {
+		Value::Null => schema,
+		Value::Object(mut o) if o.contains_key("oneOf") => {
+			o["oneOf"].as_array_mut().unwrap().push(schema);
+			Value::Object(o)
+		}
+		prev => json!({ "oneOf": [prev, schema] }),
+	}
+}
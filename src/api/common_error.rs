@@ -28,6 +28,10 @@ pub enum CommonError {
 	#[error(display = "Bad request: {}", _0)]
 	BadRequest(String),
 
+	/// Too many requests of this kind are being processed
+	#[error(display = "Slow down: {}", _0)]
+	SlowDown(String),
+
 	// ---- SPECIFIC ERROR CONDITIONS ----
 	// These have to be error codes referenced in the S3 spec here:
 	// https://docs.aws.amazon.com/AmazonS3/latest/API/ErrorResponses.html#ErrorCodeList
@@ -43,6 +47,13 @@ pub enum CommonError {
 	#[error(display = "Tried to delete a non-empty bucket")]
 	BucketNotEmpty,
 
+	/// The request body is larger than the configured maximum size
+	#[error(
+		display = "Request body too large (maximum allowed size is {} bytes)",
+		_0
+	)]
+	EntityTooLarge(u64),
+
 	// Category: bad request
 	/// Bucket name is not valid according to AWS S3 specs
 	#[error(display = "Invalid bucket name: {}", _0)]
@@ -52,15 +63,18 @@ pub enum CommonError {
 impl CommonError {
 	pub fn http_status_code(&self) -> StatusCode {
 		match self {
+			CommonError::InternalError(e) if e.is_insufficient_storage() => {
+				StatusCode::INSUFFICIENT_STORAGE
+			}
 			CommonError::InternalError(
-				GarageError::Timeout
-				| GarageError::RemoteError(_)
-				| GarageError::Quorum(_, _, _, _),
+				GarageError::Timeout | GarageError::RemoteError(_) | GarageError::Quorum(..),
 			) => StatusCode::SERVICE_UNAVAILABLE,
 			CommonError::InternalError(_) | CommonError::Hyper(_) | CommonError::Http(_) => {
 				StatusCode::INTERNAL_SERVER_ERROR
 			}
 			CommonError::BadRequest(_) => StatusCode::BAD_REQUEST,
+			CommonError::SlowDown(_) => StatusCode::SERVICE_UNAVAILABLE,
+			CommonError::EntityTooLarge(_) => StatusCode::BAD_REQUEST,
 			CommonError::Forbidden(_) => StatusCode::FORBIDDEN,
 			CommonError::NoSuchBucket(_) => StatusCode::NOT_FOUND,
 			CommonError::BucketNotEmpty | CommonError::BucketAlreadyExists => StatusCode::CONFLICT,
@@ -71,15 +85,16 @@ impl CommonError {
 	pub fn aws_code(&self) -> &'static str {
 		match self {
 			CommonError::Forbidden(_) => "AccessDenied",
+			CommonError::InternalError(e) if e.is_insufficient_storage() => "InsufficientStorage",
 			CommonError::InternalError(
-				GarageError::Timeout
-				| GarageError::RemoteError(_)
-				| GarageError::Quorum(_, _, _, _),
+				GarageError::Timeout | GarageError::RemoteError(_) | GarageError::Quorum(..),
 			) => "ServiceUnavailable",
 			CommonError::InternalError(_) | CommonError::Hyper(_) | CommonError::Http(_) => {
 				"InternalError"
 			}
 			CommonError::BadRequest(_) => "InvalidRequest",
+			CommonError::SlowDown(_) => "SlowDown",
+			CommonError::EntityTooLarge(_) => "EntityTooLarge",
 			CommonError::NoSuchBucket(_) => "NoSuchBucket",
 			CommonError::BucketAlreadyExists => "BucketAlreadyExists",
 			CommonError::BucketNotEmpty => "BucketNotEmpty",
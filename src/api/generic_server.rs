@@ -1,42 +1,98 @@
 use std::fs::{self, Permissions};
 use std::os::unix::fs::PermissionsExt;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Instant;
 
 use async_trait::async_trait;
 
 use futures::future::Future;
+use futures::TryStreamExt;
 
-use hyper::header::HeaderValue;
+use hyper::body::HttpBody;
+use hyper::header::{HeaderValue, CONTENT_LENGTH};
 use hyper::server::conn::AddrStream;
 use hyper::service::{make_service_fn, service_fn};
-use hyper::{Body, Request, Response, Server};
+use hyper::{Body, Method, Request, Response, Server};
 use hyper::{HeaderMap, StatusCode};
 
 use hyperlocal::UnixServerExt;
 
 use tokio::net::UnixStream;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 
 use opentelemetry::{
 	global,
 	metrics::{Counter, ValueRecorder},
-	trace::{FutureExt, SpanRef, TraceContextExt, Tracer},
+	trace::{FutureExt, SpanRef, TraceContextExt, TraceId, Tracer},
 	Context, KeyValue,
 };
 
+use garage_util::config::{ApiLimitsConfig, TlsConfig};
+use garage_util::data::Uuid;
 use garage_util::error::Error as GarageError;
 use garage_util::forwarded_headers;
-use garage_util::metrics::{gen_trace_id, RecordDuration};
-use garage_util::socket_address::UnixOrTCPSocketAddress;
+use garage_util::in_flight::{InFlightGuard, InFlightRequests};
+use garage_util::metrics::gen_trace_id;
+use garage_util::socket_address::{bind_tcp_listener, UnixOrTCPSocketAddress};
+
+use tracing::Instrument;
+
+use crate::common_error::CommonError;
+use crate::https::{load_tls_config, serve_https, set_host_header};
+
+/// Unique ID of the request, returned in all responses of the API servers
+pub const X_AMZ_REQUEST_ID: &str = "x-amz-request-id";
+/// ID of the node that processed the request
+pub const X_AMZ_ID_2: &str = "x-amz-id-2";
 
 pub(crate) trait ApiEndpoint: Send + Sync + 'static {
 	fn name(&self) -> &'static str;
 	fn add_span_attributes(&self, span: SpanRef<'_>);
+	fn request_kind(&self) -> RequestKind {
+		RequestKind::Other
+	}
+}
+
+/// Bucket of a request, used to label the metrics of the request when
+/// per-bucket metrics are enabled. It is stored in the extensions of the
+/// request, and set by the API handler only once the bucket has been
+/// resolved and the request authorized, so that unauthenticated clients
+/// cannot create metric series for arbitrary bucket names.
+#[derive(Clone, Default)]
+pub(crate) struct MetricsBucket(Arc<Mutex<Option<Uuid>>>);
+
+impl MetricsBucket {
+	/// Set the bucket of a request, if per-bucket metrics are enabled
+	pub(crate) fn set<B>(req: &Request<B>, bucket_id: Uuid) {
+		if let Some(bucket) = req.extensions().get::<MetricsBucket>() {
+			*bucket.0.lock().unwrap() = Some(bucket_id);
+		}
+	}
+
+	fn tags(&self, tags: &[KeyValue]) -> Vec<KeyValue> {
+		let mut tags = tags.to_vec();
+		if let Some(bucket_id) = *self.0.lock().unwrap() {
+			tags.push(KeyValue::new("bucket", hex::encode(bucket_id)));
+		}
+		tags
+	}
+}
+
+/// Kinds of requests for which the number of requests processed
+/// concurrently can be limited
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RequestKind {
+	/// Requests that stream data to be stored
+	Put,
+	/// Requests that scan a range of the metadata tables
+	ListScan,
+	Other,
 }
 
 pub trait ApiError: std::error::Error + Send + Sync + 'static {
 	fn http_status_code(&self) -> StatusCode;
 	fn add_http_headers(&self, header_map: &mut HeaderMap<HeaderValue>);
-	fn http_body(&self, garage_region: &str, path: &str) -> Body;
+	fn http_body(&self, garage_region: &str, path: &str, request_id: &str) -> Body;
 }
 
 #[async_trait]
@@ -45,7 +101,7 @@ pub(crate) trait ApiHandler: Send + Sync + 'static {
 	const API_NAME_DISPLAY: &'static str;
 
 	type Endpoint: ApiEndpoint;
-	type Error: ApiError;
+	type Error: ApiError + From<CommonError>;
 
 	fn parse_endpoint(&self, r: &Request<Body>) -> Result<Self::Endpoint, Self::Error>;
 	async fn handle(
@@ -53,24 +109,72 @@ pub(crate) trait ApiHandler: Send + Sync + 'static {
 		req: Request<Body>,
 		endpoint: Self::Endpoint,
 	) -> Result<Response<Body>, Self::Error>;
+
+	/// Limits on the requests processed by the API server, which are read
+	/// for each request as they change when the configuration is reloaded
+	fn limits(&self) -> ApiLimitsConfig {
+		ApiLimitsConfig::default()
+	}
+}
+
+/// Limits in effect on an API server, built from an `ApiLimitsConfig`
+struct ApiLimits {
+	config: ApiLimitsConfig,
+	max_request_body_size: Option<u64>,
+	put_semaphore: Option<Arc<Semaphore>>,
+	list_scan_semaphore: Option<Arc<Semaphore>>,
+}
+
+impl ApiLimits {
+	fn new(config: ApiLimitsConfig) -> Self {
+		Self {
+			max_request_body_size: config.max_request_body_size.map(|s| s as u64),
+			put_semaphore: config
+				.max_concurrent_puts
+				.map(|n| Arc::new(Semaphore::new(n))),
+			list_scan_semaphore: config
+				.max_concurrent_list_scans
+				.map(|n| Arc::new(Semaphore::new(n))),
+			config,
+		}
+	}
 }
 
 pub(crate) struct ApiServer<A: ApiHandler> {
 	region: String,
+	host_id: String,
 	api_handler: A,
+	in_flight_requests: Arc<InFlightRequests>,
+
+	// Limits
+	limits: RwLock<Arc<ApiLimits>>,
 
 	// Metrics
+	per_bucket_metrics: bool,
 	request_counter: Counter<u64>,
 	error_counter: Counter<u64>,
 	request_duration: ValueRecorder<f64>,
+	request_body_bytes: Counter<u64>,
+	response_body_bytes: Counter<u64>,
 }
 
 impl<A: ApiHandler> ApiServer<A> {
-	pub fn new(region: String, api_handler: A) -> Arc<Self> {
+	pub fn new(
+		region: String,
+		host_id: String,
+		api_handler: A,
+		in_flight_requests: Arc<InFlightRequests>,
+		per_bucket_metrics: bool,
+	) -> Arc<Self> {
 		let meter = global::meter("garage/api");
+		let limits = RwLock::new(Arc::new(ApiLimits::new(api_handler.limits())));
 		Arc::new(Self {
 			region,
+			host_id,
 			api_handler,
+			in_flight_requests,
+			limits,
+			per_bucket_metrics,
 			request_counter: meter
 				.u64_counter(format!("api.{}.request_counter", A::API_NAME))
 				.with_description(format!(
@@ -92,6 +196,20 @@ impl<A: ApiHandler> ApiServer<A> {
 					A::API_NAME_DISPLAY
 				))
 				.init(),
+			request_body_bytes: meter
+				.u64_counter(format!("api.{}.request_body_bytes", A::API_NAME))
+				.with_description(format!(
+					"Number of bytes received in the body of calls to the various {} API endpoints",
+					A::API_NAME_DISPLAY
+				))
+				.init(),
+			response_body_bytes: meter
+				.u64_counter(format!("api.{}.response_body_bytes", A::API_NAME))
+				.with_description(format!(
+					"Number of bytes sent in the body of responses to calls to the various {} API endpoints",
+					A::API_NAME_DISPLAY
+				))
+				.init(),
 		})
 	}
 
@@ -135,7 +253,7 @@ impl<A: ApiHandler> ApiServer<A> {
 
 		match bind_addr {
 			UnixOrTCPSocketAddress::TCPSocket(addr) => {
-				Server::bind(&addr)
+				Server::from_tcp(bind_tcp_listener(addr)?)?
 					.serve(tcp_service)
 					.with_graceful_shutdown(shutdown_signal)
 					.await?
@@ -162,11 +280,63 @@ impl<A: ApiHandler> ApiServer<A> {
 		Ok(())
 	}
 
+	pub async fn run_https_server(
+		self: Arc<Self>,
+		tls_config: &TlsConfig,
+		shutdown_signal: impl Future<Output = ()>,
+	) -> Result<(), GarageError> {
+		let server_config = Arc::new(load_tls_config(tls_config)?);
+
+		info!(
+			"{} API server listening on {} (HTTPS)",
+			A::API_NAME_DISPLAY,
+			tls_config.bind_addr
+		);
+
+		serve_https(
+			tls_config.bind_addr,
+			server_config,
+			tls_config.http3,
+			move |req, addr| self.clone().handler(req, addr),
+			shutdown_signal,
+		)
+		.await
+	}
+
 	async fn handler(
 		self: Arc<Self>,
 		req: Request<Body>,
 		addr: String,
 	) -> Result<Response<Body>, GarageError> {
+		// The request ID is the ID of the trace of the request, so that
+		// the request can easily be found in traces from its ID
+		let trace_id = gen_trace_id();
+		let request_id = trace_id.to_string();
+
+		let span = info_span!("request", id = %request_id);
+		let mut resp = self
+			.handler_stage1(req, addr, trace_id, &request_id)
+			.instrument(span)
+			.await?;
+
+		// Both IDs are hex strings, which are valid header values
+		let headers = resp.headers_mut();
+		headers.insert(
+			X_AMZ_REQUEST_ID,
+			HeaderValue::from_str(&request_id).unwrap(),
+		);
+		headers.insert(X_AMZ_ID_2, HeaderValue::from_str(&self.host_id).unwrap());
+		Ok(resp)
+	}
+
+	async fn handler_stage1(
+		&self,
+		mut req: Request<Body>,
+		addr: String,
+		trace_id: TraceId,
+		request_id: &str,
+	) -> Result<Response<Body>, GarageError> {
+		set_host_header(&mut req);
 		let uri = req.uri().clone();
 
 		if let Ok(forwarded_for_ip_addr) =
@@ -184,13 +354,21 @@ impl<A: ApiHandler> ApiServer<A> {
 		}
 		debug!("{:?}", req);
 
+		let in_flight = self.in_flight_requests.start(
+			A::API_NAME_DISPLAY,
+			req.method().to_string(),
+			uri.to_string(),
+			addr,
+		);
+
 		let tracer = opentelemetry::global::tracer("garage");
 		let span = tracer
 			.span_builder(format!("{} API call (unknown)", A::API_NAME_DISPLAY))
-			.with_trace_id(gen_trace_id())
+			.with_trace_id(trace_id)
 			.with_attributes(vec![
 				KeyValue::new("method", format!("{}", req.method())),
 				KeyValue::new("uri", req.uri().to_string()),
+				KeyValue::new("request_id", request_id.to_string()),
 			])
 			.start(&tracer);
 
@@ -202,10 +380,10 @@ impl<A: ApiHandler> ApiServer<A> {
 		match res {
 			Ok(x) => {
 				debug!("{} {:?}", x.status(), x.headers());
-				Ok(x)
+				Ok(hold_in_flight_until_sent(x, in_flight))
 			}
 			Err(e) => {
-				let body: Body = e.http_body(&self.region, uri.path());
+				let body: Body = e.http_body(&self.region, uri.path(), request_id);
 				let mut http_error_builder = Response::builder().status(e.http_status_code());
 
 				if let Some(header_map) = http_error_builder.headers_mut() {
@@ -238,14 +416,38 @@ impl<A: ApiHandler> ApiServer<A> {
 		current_span.set_attribute(KeyValue::new("endpoint", endpoint.name()));
 		endpoint.add_span_attributes(current_span);
 
-		let metrics_tags = &[KeyValue::new("api_endpoint", endpoint.name())];
+		let endpoint_tags = [KeyValue::new("api_endpoint", endpoint.name())];
+		let metrics_bucket = MetricsBucket::default();
+		let mut req = req;
+		if self.per_bucket_metrics {
+			req.extensions_mut().insert(metrics_bucket.clone());
+		}
 
-		let res = self
-			.api_handler
-			.handle(req, endpoint)
-			.record_duration(&self.request_duration, &metrics_tags[..])
-			.await;
+		let is_head = req.method() == Method::HEAD;
+		let req = count_request_body(
+			req,
+			self.request_body_bytes.clone(),
+			endpoint_tags.to_vec(),
+			metrics_bucket.clone(),
+		);
 
+		let request_start = Instant::now();
+		let res = async {
+			let limits = self.current_limits();
+			let req = self.limit_body_size(&limits, req)?;
+			let _permit = self.acquire_permit(&limits, endpoint.request_kind())?;
+			self.api_handler.handle(req, endpoint).await
+		}
+		.await;
+
+		// The bucket label is known only once the request has been handled
+		let metrics_tags = metrics_bucket.tags(&endpoint_tags);
+		self.request_duration.record(
+			Instant::now()
+				.saturating_duration_since(request_start)
+				.as_secs_f64(),
+			&metrics_tags[..],
+		);
 		self.request_counter.add(1, &metrics_tags[..]);
 
 		let status_code = match &res {
@@ -253,15 +455,169 @@ impl<A: ApiHandler> ApiServer<A> {
 			Err(e) => e.http_status_code(),
 		};
 		if status_code.is_client_error() || status_code.is_server_error() {
-			self.error_counter.add(
-				1,
-				&[
-					metrics_tags[0].clone(),
-					KeyValue::new("status_code", status_code.as_str().to_string()),
-				],
+			let error_class = if status_code.is_server_error() {
+				"server_error"
+			} else {
+				"client_error"
+			};
+			let mut error_tags = metrics_tags.clone();
+			error_tags.push(KeyValue::new(
+				"status_code",
+				status_code.as_str().to_string(),
+			));
+			error_tags.push(KeyValue::new("error_class", error_class));
+			self.error_counter.add(1, &error_tags[..]);
+		}
+
+		match res {
+			Ok(resp) if !is_head => Ok(count_response_body(
+				resp,
+				self.response_body_bytes.clone(),
+				metrics_tags,
+			)),
+			res => res,
+		}
+	}
+
+	/// Get the limits in effect, rebuilding them if the configured limits
+	/// have changed. Requests that are already being processed keep the
+	/// permits they hold on the previous limits.
+	fn current_limits(&self) -> Arc<ApiLimits> {
+		let config = self.api_handler.limits();
+		let limits = self.limits.read().unwrap().clone();
+		if limits.config == config {
+			return limits;
+		}
+
+		let mut current = self.limits.write().unwrap();
+		if current.config != config {
+			info!(
+				"{} API server: applying new request limits {:?}",
+				A::API_NAME_DISPLAY,
+				config
 			);
+			*current = Arc::new(ApiLimits::new(config));
 		}
+		current.clone()
+	}
 
-		res
+	/// Reject requests whose body is larger than the configured maximum:
+	/// directly if they announce their size in the Content-Length header,
+	/// or as soon as the maximum size is reached otherwise.
+	fn limit_body_size(
+		&self,
+		limits: &ApiLimits,
+		req: Request<Body>,
+	) -> Result<Request<Body>, A::Error> {
+		let max_size = match limits.max_request_body_size {
+			Some(m) => m,
+			None => return Ok(req),
+		};
+
+		let content_length = req
+			.headers()
+			.get(CONTENT_LENGTH)
+			.and_then(|v| v.to_str().ok())
+			.and_then(|v| v.parse::<u64>().ok());
+		match content_length {
+			Some(len) if len > max_size => Err(CommonError::EntityTooLarge(max_size).into()),
+			Some(_) => Ok(req),
+			None => {
+				let (parts, body) = req.into_parts();
+				let mut size = 0u64;
+				let body = TryStreamExt::map_err(body, |e| {
+					std::io::Error::new(std::io::ErrorKind::Other, e)
+				})
+				.and_then(move |chunk| {
+					size += chunk.len() as u64;
+					let res = if size > max_size {
+						Err(std::io::Error::new(
+							std::io::ErrorKind::InvalidData,
+							CommonError::EntityTooLarge(max_size).to_string(),
+						))
+					} else {
+						Ok(chunk)
+					};
+					futures::future::ready(res)
+				});
+				Ok(Request::from_parts(parts, Body::wrap_stream(body)))
+			}
+		}
+	}
+
+	fn acquire_permit(
+		&self,
+		limits: &ApiLimits,
+		kind: RequestKind,
+	) -> Result<Option<OwnedSemaphorePermit>, A::Error> {
+		let semaphore = match kind {
+			RequestKind::Put => limits.put_semaphore.as_ref(),
+			RequestKind::ListScan => limits.list_scan_semaphore.as_ref(),
+			RequestKind::Other => None,
+		};
+		match semaphore.map(|s| s.clone().try_acquire_owned()) {
+			None => Ok(None),
+			Some(Ok(permit)) => Ok(Some(permit)),
+			Some(Err(_)) => Err(CommonError::SlowDown(format!(
+				"too many concurrent requests of this kind ({:?})",
+				kind
+			))
+			.into()),
+		}
+	}
+}
+
+/// Count the bytes of a request body as they are received
+fn count_request_body(
+	req: Request<Body>,
+	counter: Counter<u64>,
+	tags: Vec<KeyValue>,
+	bucket: MetricsBucket,
+) -> Request<Body> {
+	let (parts, body) = req.into_parts();
+	let body =
+		body.inspect_ok(move |chunk| counter.add(chunk.len() as u64, &bucket.tags(&tags)[..]));
+	Request::from_parts(parts, Body::wrap_stream(body))
+}
+
+/// Keep a request counted as in flight until its response body has been
+/// entirely sent or dropped, and not only until the handler returns,
+/// so that streamed bodies are not interrupted when draining on shutdown.
+/// Bodies whose size is known in advance are left untouched.
+fn hold_in_flight_until_sent(resp: Response<Body>, guard: InFlightGuard) -> Response<Body> {
+	if HttpBody::size_hint(resp.body()).exact().is_some() {
+		return resp;
+	}
+	let (parts, body) = resp.into_parts();
+	let body = body.map_ok(move |chunk| {
+		let _ = &guard;
+		chunk
+	});
+	Response::from_parts(parts, Body::wrap_stream(body))
+}
+
+/// Count the bytes of a response body. If its size is known in advance,
+/// it is counted directly, so that the response body is left untouched.
+fn count_response_body(
+	resp: Response<Body>,
+	counter: Counter<u64>,
+	tags: Vec<KeyValue>,
+) -> Response<Body> {
+	let known_size = resp
+		.headers()
+		.get(CONTENT_LENGTH)
+		.and_then(|v| v.to_str().ok())
+		.and_then(|v| v.parse::<u64>().ok())
+		.or_else(|| HttpBody::size_hint(resp.body()).exact());
+	match known_size {
+		Some(size) => {
+			counter.add(size, &tags[..]);
+			resp
+		}
+		None => {
+			let (parts, body) = resp.into_parts();
+			let body = body.inspect_ok(move |chunk| counter.add(chunk.len() as u64, &tags[..]));
+			Response::from_parts(parts, Body::wrap_stream(body))
+		}
 	}
 }
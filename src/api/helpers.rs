@@ -1,7 +1,14 @@
+use std::sync::Arc;
+
+use futures::TryStreamExt;
+use hyper::body::HttpBody;
 use hyper::{Body, Request, Response};
 use idna::domain_to_unicode;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use garage_model::garage::Garage;
+
 use crate::common_error::{CommonError as Error, *};
 
 /// What kind of authorization is required to perform a given action
@@ -17,12 +24,19 @@ pub enum Authorization {
 	Owner,
 }
 
+/// Identifier of this node returned in the x-amz-id-2 header of API responses,
+/// in the short form displayed by `garage status`
+pub fn host_id(garage: &Garage) -> String {
+	hex::encode(&garage.system.id.as_slice()[..8])
+}
+
 /// Host to bucket
 ///
 /// Convert a host, like "bucket.garage-site.tld" to the corresponding bucket "bucket",
 /// considering that ".garage-site.tld" is the "root domain". For domains not matching
 /// the provided root domain, no bucket is returned
 /// This behavior has been chosen to follow AWS S3 semantic.
+/// A `*` label in the root domain matches any label of the host.
 pub fn host_to_bucket<'a>(host: &'a str, root: &str) -> Option<&'a str> {
 	let root = root.trim_start_matches('.');
 	let label_root = root.chars().filter(|c| c == &'.').count() + 1;
@@ -30,13 +44,24 @@ pub fn host_to_bucket<'a>(host: &'a str, root: &str) -> Option<&'a str> {
 	let mut host = host.rsplitn(label_root + 1, '.');
 	for root_part in root {
 		let host_part = host.next()?;
-		if root_part != host_part {
+		if root_part != "*" && root_part != host_part {
 			return None;
 		}
 	}
 	host.next()
 }
 
+/// Host to bucket, with several possible root domains
+///
+/// When several root domains match the host, the one with the most labels is
+/// used, e.g. with root domains "garage.tld" and "s3.garage.tld", the host
+/// "bucket.s3.garage.tld" gives the bucket "bucket".
+pub fn host_to_bucket_multi<'a>(host: &'a str, roots: &[&str]) -> Option<&'a str> {
+	let mut roots = roots.to_vec();
+	roots.sort_by_key(|r| std::cmp::Reverse(r.trim_start_matches('.').split('.').count()));
+	roots.into_iter().find_map(|r| host_to_bucket(host, r))
+}
+
 /// Extract host from the authority section given by the HTTP host header
 ///
 /// The HTTP host contains both a host and a port.
@@ -156,6 +181,41 @@ pub fn is_default<T: Default + PartialEq>(v: &T) -> bool {
 	*v == T::default()
 }
 
+/// Account a request made with an access key in the key usage counters,
+/// counting the bytes of its body as they are received
+pub fn count_key_usage_request(
+	garage: &Arc<Garage>,
+	key_id: &str,
+	req: Request<Body>,
+) -> Request<Body> {
+	garage.key_usage.record(key_id, 1, 0, 0);
+
+	let key_usage = garage.key_usage.clone();
+	let key_id = key_id.to_string();
+	let (parts, body) = req.into_parts();
+	let body = body.inspect_ok(move |chunk| key_usage.record(&key_id, 0, chunk.len() as u64, 0));
+	Request::from_parts(parts, Body::wrap_stream(body))
+}
+
+/// Account the bytes of the body of a response to a request made with
+/// an access key in the key usage counters
+pub fn count_key_usage_response(
+	garage: &Arc<Garage>,
+	key_id: &str,
+	resp: Response<Body>,
+) -> Response<Body> {
+	if let Some(size) = HttpBody::size_hint(resp.body()).exact() {
+		garage.key_usage.record(key_id, 0, 0, size);
+		return resp;
+	}
+
+	let key_usage = garage.key_usage.clone();
+	let key_id = key_id.to_string();
+	let (parts, body) = resp.into_parts();
+	let body = body.inspect_ok(move |chunk| key_usage.record(&key_id, 0, 0, chunk.len() as u64));
+	Response::from_parts(parts, Body::wrap_stream(body))
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -254,6 +314,33 @@ mod tests {
 
 		assert_eq!(host_to_bucket("not-garage.tld", "garage.tld"), None);
 		assert_eq!(host_to_bucket("not-garage.tld", ".garage.tld"), None);
+
+		assert_eq!(
+			host_to_bucket("john.s3.eu-west.garage.tld", "s3.*.garage.tld"),
+			Some("john")
+		);
+		assert_eq!(
+			host_to_bucket("john.s3.garage.tld", "s3.*.garage.tld"),
+			None
+		);
+	}
+
+	#[test]
+	fn host_to_bucket_multi_test() {
+		let roots = ["garage.tld", "s3.garage.tld", "*.garage.net"];
+		assert_eq!(
+			host_to_bucket_multi("john.s3.garage.tld", &roots),
+			Some("john")
+		);
+		assert_eq!(
+			host_to_bucket_multi("john.web.garage.tld", &roots),
+			Some("john.web")
+		);
+		assert_eq!(
+			host_to_bucket_multi("john.s3.garage.net", &roots),
+			Some("john")
+		);
+		assert_eq!(host_to_bucket_multi("john.garage.org", &roots), None);
 	}
 
 	#[test]
@@ -292,7 +379,7 @@ mod tests {
 	}
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, JsonSchema)]
 pub(crate) struct CustomApiErrorBody {
 	pub(crate) code: String,
 	pub(crate) message: String,
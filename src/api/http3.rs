@@ -0,0 +1,133 @@
+//! Serving requests using HTTP/3 over QUIC
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use bytes::{Buf, Bytes};
+use futures::future::Future;
+
+use hyper::body::HttpBody;
+use hyper::{Body, Request, Response, StatusCode};
+
+use garage_util::error::Error as GarageError;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+pub(crate) async fn serve_http3<F, Fut, E>(
+	bind_addr: SocketAddr,
+	tls_config: Arc<rustls::ServerConfig>,
+	handler: F,
+	shutdown_signal: impl Future<Output = ()>,
+) -> Result<(), GarageError>
+where
+	F: Fn(Request<Body>, String) -> Fut + Clone + Send + Sync + 'static,
+	Fut: Future<Output = Result<Response<Body>, E>> + Send + 'static,
+	E: Into<Box<dyn std::error::Error + Send + Sync>> + 'static,
+{
+	let mut tls_config = (*tls_config).clone();
+	tls_config.alpn_protocols = vec![b"h3".to_vec()];
+	// 0-RTT data can be replayed by an attacker, which would execute signed
+	// requests such as PutObject or DeleteObject a second time: keep it disabled
+	tls_config.max_early_data_size = 0;
+	let server_config = quinn::ServerConfig::with_crypto(Arc::new(tls_config));
+	let endpoint = quinn::Endpoint::server(server_config, bind_addr)?;
+
+	info!("Also serving HTTP/3 on {} (UDP)", bind_addr);
+
+	tokio::pin!(shutdown_signal);
+	loop {
+		let connecting = tokio::select! {
+			_ = &mut shutdown_signal => break,
+			incoming = endpoint.accept() => match incoming {
+				Some(c) => c,
+				None => break,
+			},
+		};
+
+		let handler = handler.clone();
+		tokio::spawn(async move {
+			let client_addr = connecting.remote_address();
+			if let Err(e) = serve_connection(connecting, handler).await {
+				debug!(
+					"Error serving HTTP/3 connection from {}: {}",
+					client_addr, e
+				);
+			}
+		});
+	}
+
+	endpoint.close(0u32.into(), b"shutdown");
+	Ok(())
+}
+
+async fn serve_connection<F, Fut, E>(
+	connecting: quinn::Connecting,
+	handler: F,
+) -> Result<(), BoxError>
+where
+	F: Fn(Request<Body>, String) -> Fut + Clone + Send + Sync + 'static,
+	Fut: Future<Output = Result<Response<Body>, E>> + Send + 'static,
+	E: Into<Box<dyn std::error::Error + Send + Sync>> + 'static,
+{
+	let conn = connecting.await?;
+	let client_addr = conn.remote_address().to_string();
+	let mut conn = h3::server::Connection::<_, Bytes>::new(h3_quinn::Connection::new(conn)).await?;
+
+	while let Some((req, stream)) = conn.accept().await? {
+		let handler = handler.clone();
+		let client_addr = client_addr.clone();
+		tokio::spawn(async move {
+			if let Err(e) = serve_request(req, stream, handler, client_addr.clone()).await {
+				debug!("Error serving HTTP/3 request from {}: {}", client_addr, e);
+			}
+		});
+	}
+
+	Ok(())
+}
+
+async fn serve_request<F, Fut, E, S>(
+	req: Request<()>,
+	stream: h3::server::RequestStream<S, Bytes>,
+	handler: F,
+	client_addr: String,
+) -> Result<(), BoxError>
+where
+	F: Fn(Request<Body>, String) -> Fut,
+	Fut: Future<Output = Result<Response<Body>, E>>,
+	E: Into<Box<dyn std::error::Error + Send + Sync>>,
+	S: h3::quic::BidiStream<Bytes> + Send + 'static,
+	S::RecvStream: Send,
+{
+	let (mut send, mut recv) = stream.split();
+
+	// Stream the request body from the QUIC stream to the handler
+	let (mut body_tx, body) = Body::channel();
+	tokio::spawn(async move {
+		while let Ok(Some(mut chunk)) = recv.recv_data().await {
+			let bytes = chunk.copy_to_bytes(chunk.remaining());
+			if body_tx.send_data(bytes).await.is_err() {
+				break;
+			}
+		}
+	});
+
+	let (parts, ()) = req.into_parts();
+	let resp = match handler(Request::from_parts(parts, body), client_addr).await {
+		Ok(resp) => resp,
+		Err(e) => {
+			warn!("Error handling HTTP/3 request: {}", e.into());
+			Response::builder()
+				.status(StatusCode::INTERNAL_SERVER_ERROR)
+				.body(Body::empty())?
+		}
+	};
+
+	let (parts, mut body) = resp.into_parts();
+	send.send_response(Response::from_parts(parts, ())).await?;
+	while let Some(chunk) = body.data().await {
+		send.send_data(chunk?).await?;
+	}
+	send.finish().await?;
+
+	Ok(())
+}
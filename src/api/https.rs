@@ -0,0 +1,229 @@
+//! Helpers for serving API and web endpoints over HTTPS, using HTTP/1.1 or
+//! HTTP/2 over TLS, and optionally HTTP/3 over QUIC.
+use std::fs::File;
+use std::io::BufReader;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use futures::future::Future;
+
+use hyper::header::{HeaderValue, HOST};
+use hyper::server::conn::Http;
+use hyper::service::service_fn;
+use hyper::{Body, Request, Response};
+
+use rustls::server::AllowAnyAuthenticatedClient;
+use tokio::net::TcpListener;
+use tokio_rustls::TlsAcceptor;
+
+use garage_util::config::TlsConfig;
+use garage_util::error::Error as GarageError;
+use garage_util::socket_address::bind_tcp_listener;
+
+/// Build the TLS configuration of a server from the certificate and private
+/// key files given in the configuration. HTTP/2 is preferred over HTTP/1.1
+/// when the client supports it. If a client CA file is given, clients
+/// must present a certificate signed by one of its CAs.
+pub fn load_tls_config(config: &TlsConfig) -> Result<rustls::ServerConfig, GarageError> {
+	let certs = rustls_pemfile::certs(&mut BufReader::new(File::open(&config.cert_file)?))?
+		.into_iter()
+		.map(rustls::Certificate)
+		.collect::<Vec<_>>();
+	if certs.is_empty() {
+		return Err(GarageError::Message(format!(
+			"No certificate found in {}",
+			config.cert_file.display()
+		)));
+	}
+
+	let key = rustls_pemfile::read_all(&mut BufReader::new(File::open(&config.key_file)?))?
+		.into_iter()
+		.find_map(|item| match item {
+			rustls_pemfile::Item::PKCS8Key(k)
+			| rustls_pemfile::Item::RSAKey(k)
+			| rustls_pemfile::Item::ECKey(k) => Some(rustls::PrivateKey(k)),
+			_ => None,
+		})
+		.ok_or_else(|| {
+			GarageError::Message(format!(
+				"No private key found in {}",
+				config.key_file.display()
+			))
+		})?;
+
+	let builder = rustls::ServerConfig::builder().with_safe_defaults();
+	let builder = match &config.client_ca_file {
+		Some(ca_file) => {
+			let mut roots = rustls::RootCertStore::empty();
+			for cert in rustls_pemfile::certs(&mut BufReader::new(File::open(ca_file)?))? {
+				roots.add(&rustls::Certificate(cert)).map_err(|e| {
+					GarageError::Message(format!(
+						"Invalid CA certificate in {}: {}",
+						ca_file.display(),
+						e
+					))
+				})?;
+			}
+			if roots.is_empty() {
+				return Err(GarageError::Message(format!(
+					"No certificate found in {}",
+					ca_file.display()
+				)));
+			}
+			builder.with_client_cert_verifier(AllowAnyAuthenticatedClient::new(roots).boxed())
+		}
+		None => builder.with_no_client_auth(),
+	};
+
+	let mut tls_config = builder
+		.with_single_cert(certs, key)
+		.map_err(|e| GarageError::Message(format!("Invalid TLS certificate or key: {}", e)))?;
+	tls_config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+	Ok(tls_config)
+}
+
+/// Identity of a client that presented a certificate during the TLS
+/// handshake, added to the extensions of the requests of its connection
+#[derive(Debug, Clone)]
+pub struct ClientCertificate {
+	/// Common name of the subject of the certificate
+	pub common_name: String,
+}
+
+/// Common name of the subject of a DER-encoded certificate
+fn certificate_common_name(der: &[u8]) -> Option<String> {
+	let (_, cert) = x509_parser::parse_x509_certificate(der).ok()?;
+	let cn = cert.subject().iter_common_name().next()?;
+	cn.as_str().ok().map(str::to_string)
+}
+
+/// HTTP/2 and HTTP/3 requests carry the requested host in the URI
+/// (`:authority` pseudo-header) instead of the `Host` header. As the
+/// request handlers (bucket name from vhost, signature checking) rely
+/// on the `Host` header, add it back from the URI if it is missing.
+pub fn set_host_header(req: &mut Request<Body>) {
+	if req.headers().contains_key(HOST) {
+		return;
+	}
+	let host = req
+		.uri()
+		.authority()
+		.and_then(|a| HeaderValue::from_str(a.as_str()).ok());
+	if let Some(host) = host {
+		req.headers_mut().insert(HOST, host);
+	}
+}
+
+/// Serve requests over HTTPS with the given TLS configuration, until the
+/// shutdown signal is received. If `http3` is set, requests are also
+/// served using HTTP/3 over QUIC on the same UDP port, and clients are
+/// told about it using the `Alt-Svc` header.
+pub async fn serve_https<F, Fut, E>(
+	bind_addr: SocketAddr,
+	tls_config: Arc<rustls::ServerConfig>,
+	http3: bool,
+	handler: F,
+	shutdown_signal: impl Future<Output = ()>,
+) -> Result<(), GarageError>
+where
+	F: Fn(Request<Body>, String) -> Fut + Clone + Send + Sync + 'static,
+	Fut: Future<Output = Result<Response<Body>, E>> + Send + 'static,
+	E: Into<Box<dyn std::error::Error + Send + Sync>> + 'static,
+{
+	if http3 {
+		#[cfg(feature = "http3")]
+		{
+			let (stop_tx, stop_rx) = tokio::sync::watch::channel(false);
+			let alt_svc = HeaderValue::from_str(&format!("h3=\":{}\"; ma=86400", bind_addr.port()))
+				.map_err(|e| GarageError::Message(e.to_string()))?;
+			let tcp_handler = move |req, addr| {
+				let fut = handler(req, addr);
+				let alt_svc = alt_svc.clone();
+				async move {
+					let mut resp = fut.await?;
+					resp.headers_mut().insert(hyper::header::ALT_SVC, alt_svc);
+					Ok::<_, E>(resp)
+				}
+			};
+			let h3_handler = tcp_handler.clone();
+
+			let tcp = serve_tcp(bind_addr, tls_config.clone(), tcp_handler, async move {
+				shutdown_signal.await;
+				let _ = stop_tx.send(true);
+			});
+			let quic = crate::http3::serve_http3(bind_addr, tls_config, h3_handler, async move {
+				let mut stop_rx = stop_rx;
+				while !*stop_rx.borrow() {
+					if stop_rx.changed().await.is_err() {
+						break;
+					}
+				}
+			});
+			let (tcp_res, quic_res) = futures::join!(tcp, quic);
+			return tcp_res.and(quic_res);
+		}
+		#[cfg(not(feature = "http3"))]
+		error!("Garage was built without HTTP/3 support, http3 = true is ignored.");
+	}
+
+	serve_tcp(bind_addr, tls_config, handler, shutdown_signal).await
+}
+
+async fn serve_tcp<F, Fut, E>(
+	bind_addr: SocketAddr,
+	tls_config: Arc<rustls::ServerConfig>,
+	handler: F,
+	shutdown_signal: impl Future<Output = ()>,
+) -> Result<(), GarageError>
+where
+	F: Fn(Request<Body>, String) -> Fut + Clone + Send + Sync + 'static,
+	Fut: Future<Output = Result<Response<Body>, E>> + Send + 'static,
+	E: Into<Box<dyn std::error::Error + Send + Sync>> + 'static,
+{
+	let acceptor = TlsAcceptor::from(tls_config);
+	let listener = TcpListener::from_std(bind_tcp_listener(bind_addr)?)?;
+
+	tokio::pin!(shutdown_signal);
+	loop {
+		let (stream, client_addr) = tokio::select! {
+			_ = &mut shutdown_signal => break,
+			accepted = listener.accept() => match accepted {
+				Ok(x) => x,
+				Err(e) => {
+					warn!("Could not accept connection: {}", e);
+					continue;
+				}
+			},
+		};
+
+		let acceptor = acceptor.clone();
+		let handler = handler.clone();
+		tokio::spawn(async move {
+			let stream = match acceptor.accept(stream).await {
+				Ok(s) => s,
+				Err(e) => {
+					debug!("TLS handshake with {} failed: {}", client_addr, e);
+					return;
+				}
+			};
+			let client_cert = stream
+				.get_ref()
+				.1
+				.peer_certificates()
+				.and_then(|certs| certs.first())
+				.and_then(|cert| certificate_common_name(&cert.0))
+				.map(|common_name| ClientCertificate { common_name });
+			let service = service_fn(move |mut req: Request<Body>| {
+				if let Some(cert) = &client_cert {
+					req.extensions_mut().insert(cert.clone());
+				}
+				handler(req, client_addr.to_string())
+			});
+			if let Err(e) = Http::new().serve_connection(stream, service).await {
+				debug!("Error serving HTTPS connection from {}: {}", client_addr, e);
+			}
+		});
+	}
+
+	Ok(())
+}
@@ -7,6 +7,7 @@ use hyper::{Body, Method, Request, Response};
 
 use opentelemetry::{trace::SpanRef, KeyValue};
 
+use garage_util::config::ApiLimitsConfig;
 use garage_util::error::Error as GarageError;
 use garage_util::socket_address::UnixOrTCPSocketAddress;
 
@@ -41,9 +42,17 @@ impl K2VApiServer {
 		s3_region: String,
 		shutdown_signal: impl Future<Output = ()>,
 	) -> Result<(), GarageError> {
-		ApiServer::new(s3_region, K2VApiServer { garage })
-			.run_server(bind_addr, None, shutdown_signal)
-			.await
+		let in_flight_requests = garage.in_flight_requests.clone();
+		let per_bucket_metrics = garage.config.admin.metrics_per_bucket;
+		ApiServer::new(
+			s3_region,
+			host_id(&garage),
+			K2VApiServer { garage },
+			in_flight_requests,
+			per_bucket_metrics,
+		)
+		.run_server(bind_addr, None, shutdown_signal)
+		.await
 	}
 }
 
@@ -55,6 +64,16 @@ impl ApiHandler for K2VApiServer {
 	type Endpoint = K2VApiEndpoint;
 	type Error = Error;
 
+	fn limits(&self) -> ApiLimitsConfig {
+		self.garage
+			.live_config
+			.load()
+			.k2v_api
+			.as_ref()
+			.map(|k2v| k2v.limits.clone())
+			.unwrap_or_default()
+	}
+
 	fn parse_endpoint(&self, req: &Request<Body>) -> Result<K2VApiEndpoint, Error> {
 		let (endpoint, bucket_name) = Endpoint::from_request(req)?;
 
@@ -85,6 +104,7 @@ impl ApiHandler for K2VApiServer {
 		let (api_key, mut content_sha256) = check_payload_signature(&garage, "k2v", &req).await?;
 		let api_key = api_key
 			.ok_or_else(|| Error::forbidden("Garage does not support anonymous access yet"))?;
+		let req = count_key_usage_request(&garage, &api_key.key_id, req);
 
 		let req = parse_streaming_body(
 			&api_key,
@@ -113,6 +133,7 @@ impl ApiHandler for K2VApiServer {
 		if !allowed {
 			return Err(Error::forbidden("Operation is not allowed for this key."));
 		}
+		MetricsBucket::set(&req, bucket_id);
 
 		// Look up what CORS rule might apply to response.
 		// Requests for methods different than GET, HEAD or POST
@@ -174,11 +195,14 @@ impl ApiHandler for K2VApiServer {
 		// add the corresponding CORS headers to the response
 		let mut resp_ok = resp?;
 		if let Some(rule) = matching_cors_rule {
-			add_cors_headers(&mut resp_ok, rule)
-				.ok_or_internal_error("Invalid bucket CORS configuration")?;
+			add_cors_headers(&mut resp_ok, &rule);
 		}
 
-		Ok(resp_ok)
+		Ok(count_key_usage_response(
+			&self.garage,
+			&api_key.key_id,
+			resp_ok,
+		))
 	}
 }
 
@@ -190,4 +214,12 @@ impl ApiEndpoint for K2VApiEndpoint {
 	fn add_span_attributes(&self, span: SpanRef<'_>) {
 		span.set_attribute(KeyValue::new("bucket", self.bucket_name.clone()));
 	}
+
+	fn request_kind(&self) -> RequestKind {
+		match self.endpoint {
+			Endpoint::InsertItem { .. } | Endpoint::InsertBatch { .. } => RequestKind::Put,
+			Endpoint::ReadIndex { .. } | Endpoint::ReadBatch { .. } => RequestKind::ListScan,
+			_ => RequestKind::Other,
+		}
+	}
 }
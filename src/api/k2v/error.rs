@@ -73,6 +73,15 @@ impl From<SignatureError> for Error {
 			SignatureError::AuthorizationHeaderMalformed(c) => {
 				Self::AuthorizationHeaderMalformed(c)
 			}
+			SignatureError::InvalidAccessKeyId(k) => {
+				Self::Common(CommonError::Forbidden(format!("No such key: {}", k)))
+			}
+			SignatureError::ExpiredAccessKey(k) => {
+				Self::Common(CommonError::Forbidden(format!("Key has expired: {}", k)))
+			}
+			SignatureError::SignatureDoesNotMatch(m) | SignatureError::RequestTimeTooSkewed(m) => {
+				Self::Common(CommonError::Forbidden(m))
+			}
 			SignatureError::InvalidUtf8Str(i) => Self::InvalidUtf8Str(i),
 			SignatureError::InvalidHeader(h) => Self::InvalidHeader(h),
 		}
@@ -115,7 +124,7 @@ impl ApiError for Error {
 		header_map.append(header::CONTENT_TYPE, "application/json".parse().unwrap());
 	}
 
-	fn http_body(&self, garage_region: &str, path: &str) -> Body {
+	fn http_body(&self, garage_region: &str, path: &str, _request_id: &str) -> Body {
 		let error = CustomApiErrorBody {
 			code: self.code().to_string(),
 			message: format!("{}", self),
@@ -7,6 +7,11 @@ pub mod common_error;
 mod encoding;
 pub mod generic_server;
 pub mod helpers;
+#[cfg(feature = "http3")]
+mod http3;
+pub mod https;
+#[cfg(any(feature = "webdav", feature = "sftp"))]
+mod object_tree;
 mod router_macros;
 /// This mode is public only to help testing. Don't expect stability here
 pub mod signature;
@@ -15,3 +20,7 @@ pub mod admin;
 #[cfg(feature = "k2v")]
 pub mod k2v;
 pub mod s3;
+#[cfg(feature = "sftp")]
+pub mod sftp;
+#[cfg(feature = "webdav")]
+pub mod webdav;
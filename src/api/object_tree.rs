@@ -0,0 +1,263 @@
+//! Mapping of the objects of a bucket onto a tree of files and directories,
+//! for the gateways that expose buckets as filesystems: keys are split on
+//! `/`, a directory existing as soon as some object has its path as a prefix,
+//! or when its marker object (the path of the directory followed by `/`)
+//! exists.
+use garage_table::*;
+
+use garage_model::bucket_table::Bucket;
+use garage_model::garage::Garage;
+use garage_model::helper::object::current_version;
+use garage_model::s3::object_table::*;
+
+use crate::common_error::CommonError as Error;
+use crate::helpers::key_after_prefix;
+
+/// Number of objects read at once when listing a directory
+const LIST_BATCH: usize = 1000;
+
+pub(crate) enum Node {
+	File(FileInfo),
+	/// Prefix of the objects of the directory, ending with `/` unless it is
+	/// the root of the bucket
+	Dir(String),
+}
+
+/// Properties of the current version of an object
+pub(crate) struct FileInfo {
+	pub size: u64,
+	pub etag: String,
+	pub content_type: String,
+	/// Time at which the version was created, in msec
+	pub timestamp: u64,
+}
+
+/// An entry of a directory
+pub(crate) struct DirEntry {
+	/// Name of the entry, relative to the directory
+	pub name: String,
+	/// Properties of the object, None for a directory
+	pub file: Option<FileInfo>,
+}
+
+/// Find the file or the directory at a path of a bucket, a path ending
+/// with `/` always designating a directory
+pub(crate) async fn resolve_path(
+	garage: &Garage,
+	bucket: &Bucket,
+	path: &str,
+) -> Result<Option<Node>, Error> {
+	if path.is_empty() {
+		return Ok(Some(Node::Dir(String::new())));
+	}
+
+	if !path.ends_with('/') {
+		let object = garage
+			.object_table
+			.get(&bucket.id, &path.to_string())
+			.await?;
+		if let Some(file) = object.as_ref().and_then(current_file) {
+			return Ok(Some(Node::File(file)));
+		}
+	}
+
+	let prefix = dir_prefix(path);
+	let first = garage
+		.object_table
+		.get_range(
+			&bucket.id,
+			Some(prefix.clone()),
+			Some(ObjectFilter::IsData),
+			1,
+			EnumerationOrder::Forward,
+		)
+		.await?;
+	match first.first() {
+		Some(o) if o.key.starts_with(&prefix) => Ok(Some(Node::Dir(prefix))),
+		_ => Ok(None),
+	}
+}
+
+/// List the files and the directories directly in a directory
+pub(crate) async fn list_dir(
+	garage: &Garage,
+	bucket: &Bucket,
+	prefix: &str,
+) -> Result<Vec<DirEntry>, Error> {
+	let mut entries = vec![];
+	let mut start = Some(prefix.to_string());
+	while let Some(cursor) = start.take() {
+		let objects = garage
+			.object_table
+			.get_range(
+				&bucket.id,
+				Some(cursor),
+				Some(ObjectFilter::IsData),
+				LIST_BATCH,
+				EnumerationOrder::Forward,
+			)
+			.await?;
+		let mut next = objects
+			.last()
+			.filter(|_| objects.len() == LIST_BATCH)
+			.map(|o| format!("{}\0", o.key));
+
+		for object in objects.iter() {
+			let rest = match object.key.strip_prefix(prefix) {
+				Some(rest) => rest,
+				None => return Ok(entries),
+			};
+			if let Some(i) = rest.find('/') {
+				// Skip all the objects of the sub-directory at once
+				next = key_after_prefix(&format!("{}{}", prefix, &rest[..=i]));
+				if i > 0 {
+					entries.push(DirEntry {
+						name: rest[..i].to_string(),
+						file: None,
+					});
+				}
+				break;
+			}
+			if rest.is_empty() {
+				// Marker object of the directory itself
+				continue;
+			}
+			if let Some(file) = current_file(object) {
+				entries.push(DirEntry {
+					name: rest.to_string(),
+					file: Some(file),
+				});
+			}
+		}
+
+		start = next;
+	}
+	Ok(entries)
+}
+
+/// List the keys of all the objects in a directory and its sub-directories,
+/// including its marker object
+pub(crate) async fn list_dir_keys(
+	garage: &Garage,
+	bucket: &Bucket,
+	prefix: &str,
+) -> Result<Vec<String>, Error> {
+	let mut keys = vec![];
+	let mut start = Some(prefix.to_string());
+	while let Some(cursor) = start.take() {
+		let objects = garage
+			.object_table
+			.get_range(
+				&bucket.id,
+				Some(cursor),
+				Some(ObjectFilter::IsData),
+				LIST_BATCH,
+				EnumerationOrder::Forward,
+			)
+			.await?;
+		if objects.len() == LIST_BATCH {
+			start = objects.last().map(|o| format!("{}\0", o.key));
+		}
+		for object in objects {
+			if !object.key.starts_with(prefix) {
+				return Ok(keys);
+			}
+			keys.push(object.key);
+		}
+	}
+	Ok(keys)
+}
+
+/// Prefix of the objects of the directory at a path
+pub(crate) fn dir_prefix(path: &str) -> String {
+	if path.is_empty() || path.ends_with('/') {
+		path.to_string()
+	} else {
+		format!("{}/", path)
+	}
+}
+
+/// Path of the parent directory of a directory prefix or of a file
+pub(crate) fn parent_dir(path: &str) -> &str {
+	match path.trim_end_matches('/').rfind('/') {
+		Some(i) => &path[..=i],
+		None => "",
+	}
+}
+
+/// The last complete version of an object, None if it is a delete marker
+fn current_file(object: &Object) -> Option<FileInfo> {
+	let (version, meta) = current_version(object)?;
+	Some(FileInfo {
+		size: meta.size,
+		etag: meta.etag.clone(),
+		content_type: meta.headers.content_type.clone(),
+		timestamp: version.timestamp,
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use garage_util::data::*;
+
+	fn version(timestamp: u64, state: ObjectVersionState) -> ObjectVersion {
+		ObjectVersion {
+			uuid: gen_uuid(),
+			timestamp,
+			state,
+		}
+	}
+
+	fn data(size: u64) -> ObjectVersionState {
+		let meta = ObjectVersionMeta {
+			headers: ObjectVersionHeaders {
+				content_type: "text/plain".into(),
+				other: Default::default(),
+			},
+			size,
+			etag: format!("etag{}", size),
+		};
+		ObjectVersionState::Complete(ObjectVersionData::Inline(meta, vec![]))
+	}
+
+	#[test]
+	fn test_paths() {
+		assert_eq!(dir_prefix(""), "");
+		assert_eq!(dir_prefix("a/b"), "a/b/");
+		assert_eq!(dir_prefix("a/b/"), "a/b/");
+
+		assert_eq!(parent_dir("a/b/c.txt"), "a/b/");
+		assert_eq!(parent_dir("a/b/"), "a/");
+		assert_eq!(parent_dir("a/"), "");
+		assert_eq!(parent_dir("c.txt"), "");
+	}
+
+	#[test]
+	fn test_current_file() {
+		let uploading = ObjectVersionState::Uploading {
+			multipart: false,
+			headers: ObjectVersionHeaders {
+				content_type: "text/plain".into(),
+				other: Default::default(),
+			},
+			initiator: None,
+		};
+		let object = |versions| Object::new(gen_uuid(), "a/b".into(), versions);
+
+		// A version being uploaded does not hide the previous one
+		let file = current_file(&object(vec![version(1, data(10)), version(2, uploading)]));
+		let file = file.unwrap();
+		assert_eq!(file.size, 10);
+		assert_eq!(file.etag, "etag10");
+		assert_eq!(file.timestamp, 1);
+
+		let file = current_file(&object(vec![version(1, data(10)), version(2, data(20))]));
+		assert_eq!(file.unwrap().size, 20);
+
+		// A deleted object is not a file
+		let deleted = ObjectVersionState::Complete(ObjectVersionData::DeleteMarker);
+		assert!(current_file(&object(vec![version(1, data(10)), version(2, deleted)])).is_none());
+		assert!(current_file(&object(vec![version(1, ObjectVersionState::Aborted)])).is_none());
+	}
+}
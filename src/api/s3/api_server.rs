@@ -6,9 +6,12 @@ use futures::future::Future;
 use hyper::header;
 use hyper::{Body, Request, Response};
 
-use opentelemetry::{trace::SpanRef, KeyValue};
+use opentelemetry::{global, metrics::Counter, trace::SpanRef, KeyValue};
 
+use garage_util::config::{ApiLimitsConfig, TlsConfig};
+use garage_util::data::blake2sum;
 use garage_util::error::Error as GarageError;
+use garage_util::forwarded_headers::handle_forwarded_host_header;
 use garage_util::socket_address::UnixOrTCPSocketAddress;
 
 use garage_model::garage::Garage;
@@ -17,13 +20,14 @@ use garage_model::key_table::Key;
 use crate::generic_server::*;
 use crate::s3::error::*;
 
-use crate::signature::payload::check_payload_signature;
+use crate::signature::payload::{check_client_certificate, check_payload_signature};
 use crate::signature::streaming::*;
 
 use crate::helpers::*;
 use crate::s3::bucket::*;
 use crate::s3::copy::*;
 use crate::s3::cors::*;
+use crate::s3::dedup::*;
 use crate::s3::delete::*;
 use crate::s3::get::*;
 use crate::s3::lifecycle::*;
@@ -31,11 +35,16 @@ use crate::s3::list::*;
 use crate::s3::multipart::*;
 use crate::s3::post_object::handle_post_object;
 use crate::s3::put::*;
+use crate::s3::resumable::*;
 use crate::s3::router::Endpoint;
+use crate::s3::verify::*;
 use crate::s3::website::*;
 
 pub struct S3ApiServer {
 	garage: Arc<Garage>,
+	/// Only serve unauthenticated read requests on public buckets
+	anonymous: bool,
+	soft_quota_exceeded_counter: Counter<u64>,
 }
 
 pub(crate) struct S3ApiEndpoint {
@@ -44,15 +53,75 @@ pub(crate) struct S3ApiEndpoint {
 }
 
 impl S3ApiServer {
+	fn new(garage: Arc<Garage>, anonymous: bool) -> Self {
+		let meter = global::meter("garage/api");
+		Self {
+			garage,
+			anonymous,
+			soft_quota_exceeded_counter: meter
+				.u64_counter("api.s3.soft_quota_exceeded_counter")
+				.with_description(
+					"Number of writes that were accepted although they exceed soft quotas of their bucket",
+				)
+				.init(),
+		}
+	}
+
 	pub async fn run(
 		garage: Arc<Garage>,
 		addr: UnixOrTCPSocketAddress,
 		s3_region: String,
 		shutdown_signal: impl Future<Output = ()>,
 	) -> Result<(), GarageError> {
-		ApiServer::new(s3_region, S3ApiServer { garage })
-			.run_server(addr, None, shutdown_signal)
-			.await
+		let in_flight_requests = garage.in_flight_requests.clone();
+		let per_bucket_metrics = garage.config.admin.metrics_per_bucket;
+		ApiServer::new(
+			s3_region,
+			host_id(&garage),
+			S3ApiServer::new(garage, false),
+			in_flight_requests,
+			per_bucket_metrics,
+		)
+		.run_server(addr, None, shutdown_signal)
+		.await
+	}
+
+	pub async fn run_https(
+		garage: Arc<Garage>,
+		tls_config: TlsConfig,
+		s3_region: String,
+		shutdown_signal: impl Future<Output = ()>,
+	) -> Result<(), GarageError> {
+		let in_flight_requests = garage.in_flight_requests.clone();
+		let per_bucket_metrics = garage.config.admin.metrics_per_bucket;
+		ApiServer::new(
+			s3_region,
+			host_id(&garage),
+			S3ApiServer::new(garage, false),
+			in_flight_requests,
+			per_bucket_metrics,
+		)
+		.run_https_server(&tls_config, shutdown_signal)
+		.await
+	}
+
+	pub async fn run_anonymous(
+		garage: Arc<Garage>,
+		addr: UnixOrTCPSocketAddress,
+		s3_region: String,
+		shutdown_signal: impl Future<Output = ()>,
+	) -> Result<(), GarageError> {
+		let in_flight_requests = garage.in_flight_requests.clone();
+		let per_bucket_metrics = garage.config.admin.metrics_per_bucket;
+		ApiServer::new(
+			s3_region,
+			host_id(&garage),
+			S3ApiServer::new(garage, true),
+			in_flight_requests,
+			per_bucket_metrics,
+		)
+		.run_server(addr, None, shutdown_signal)
+		.await
 	}
 
 	async fn handle_request_without_bucket(
@@ -66,6 +135,145 @@ impl S3ApiServer {
 			endpoint => Err(Error::NotImplemented(endpoint.name().to_owned())),
 		}
 	}
+
+	/// Handle a request received on the anonymous endpoint: signatures are
+	/// ignored, and only read operations on public buckets are allowed
+	async fn handle_anonymous_request(
+		&self,
+		req: Request<Body>,
+		bucket_name: Option<String>,
+		endpoint: Endpoint,
+	) -> Result<Response<Body>, Error> {
+		let garage = self.garage.clone();
+
+		if let Endpoint::Options = endpoint {
+			return handle_options_s3api(garage, &req, bucket_name).await;
+		}
+
+		let allowed = matches!(
+			endpoint,
+			Endpoint::GetObject { .. }
+				| Endpoint::HeadObject { .. }
+				| Endpoint::HeadBucket {}
+				| Endpoint::ListObjects { .. }
+				| Endpoint::ListObjectsV2 { .. }
+		);
+		let bucket_name = match bucket_name {
+			Some(b) if allowed => b,
+			_ => {
+				return Err(Error::forbidden(format!(
+					"{} is not allowed without authentication",
+					endpoint.name()
+				)))
+			}
+		};
+
+		// Buckets that do not exist and buckets that are not public
+		// get the same answer, so that bucket names cannot be probed
+		let bucket_id = garage
+			.bucket_helper()
+			.resolve_global_bucket_name(&bucket_name)
+			.await?
+			.ok_or_else(|| Error::forbidden("Anonymous access is not allowed"))?;
+		let bucket = garage
+			.bucket_helper()
+			.get_existing_bucket(bucket_id)
+			.await?;
+		if !bucket.public_read() {
+			return Err(Error::forbidden("Anonymous access is not allowed"));
+		}
+		MetricsBucket::set(&req, bucket_id);
+
+		let matching_cors_rule = find_matching_cors_rule(&bucket, &req)?;
+
+		let resp = match endpoint {
+			Endpoint::HeadObject {
+				key, part_number, ..
+			} => handle_head(garage, &req, &bucket, &key, part_number).await,
+			Endpoint::GetObject {
+				key, part_number, ..
+			} => handle_get(garage, &req, &bucket, &key, part_number).await,
+			Endpoint::HeadBucket {} => Ok(Response::new(Body::empty())),
+			Endpoint::ListObjects {
+				delimiter,
+				encoding_type,
+				marker,
+				max_keys,
+				prefix,
+			} => {
+				handle_list(
+					garage,
+					&ListObjectsQuery {
+						common: ListQueryCommon {
+							bucket_name,
+							bucket_id,
+							delimiter: delimiter.map(|d| d.to_string()),
+							page_size: max_keys.unwrap_or(1000).clamp(1, 1000),
+							prefix: prefix.unwrap_or_default(),
+							urlencode_resp: encoding_type.map(|e| e == "url").unwrap_or(false),
+						},
+						is_v2: false,
+						marker,
+						continuation_token: None,
+						start_after: None,
+					},
+				)
+				.await
+			}
+			Endpoint::ListObjectsV2 {
+				delimiter,
+				encoding_type,
+				max_keys,
+				prefix,
+				continuation_token,
+				start_after,
+				list_type,
+				..
+			} if list_type == "2" => {
+				handle_list(
+					garage,
+					&ListObjectsQuery {
+						common: ListQueryCommon {
+							bucket_name,
+							bucket_id,
+							delimiter: delimiter.map(|d| d.to_string()),
+							page_size: max_keys.unwrap_or(1000).clamp(1, 1000),
+							urlencode_resp: encoding_type.map(|e| e == "url").unwrap_or(false),
+							prefix: prefix.unwrap_or_default(),
+						},
+						is_v2: true,
+						marker: None,
+						continuation_token,
+						start_after,
+					},
+				)
+				.await
+			}
+			Endpoint::ListObjectsV2 { list_type, .. } => Err(Error::bad_request(format!(
+				"Invalid endpoint: list-type={}",
+				list_type
+			))),
+			_ => unreachable!(),
+		};
+
+		let mut resp_ok = resp?;
+		if let Some(rule) = matching_cors_rule {
+			add_cors_headers(&mut resp_ok, &rule);
+		}
+		Ok(resp_ok)
+	}
+
+	fn count_soft_quota_exceeded(&self, resp: &Response<Body>, bucket_name: &str) {
+		if let Some(quotas) = resp.headers().get(X_GARAGE_QUOTA_EXCEEDED) {
+			self.soft_quota_exceeded_counter.add(
+				1,
+				&[
+					KeyValue::new("bucket", bucket_name.to_string()),
+					KeyValue::new("quota", quotas.to_str().unwrap_or_default().to_string()),
+				],
+			);
+		}
+	}
 }
 
 #[async_trait]
@@ -76,22 +284,28 @@ impl ApiHandler for S3ApiServer {
 	type Endpoint = S3ApiEndpoint;
 	type Error = Error;
 
+	fn limits(&self) -> ApiLimitsConfig {
+		self.garage.live_config.load().s3_api.limits.clone()
+	}
+
 	fn parse_endpoint(&self, req: &Request<Body>) -> Result<S3ApiEndpoint, Error> {
-		let authority = req
-			.headers()
-			.get(header::HOST)
-			.ok_or_bad_request("Host header required")?
-			.to_str()?;
+		let forwarded_host = match self.garage.config.s3_api.trust_forwarded_host {
+			true => handle_forwarded_host_header(req.headers()),
+			false => None,
+		};
+		let authority = match forwarded_host {
+			Some(h) => h,
+			None => req
+				.headers()
+				.get(header::HOST)
+				.ok_or_bad_request("Host header required")?
+				.to_str()?,
+		};
 
 		let host = authority_to_host(authority)?;
 
-		let bucket_name = self
-			.garage
-			.config
-			.s3_api
-			.root_domain
-			.as_ref()
-			.and_then(|root_domain| host_to_bucket(&host, root_domain));
+		let bucket_name =
+			host_to_bucket_multi(&host, &self.garage.config.s3_api.all_root_domains());
 
 		let (endpoint, bucket_name) =
 			Endpoint::from_request(req, bucket_name.map(ToOwned::to_owned))?;
@@ -104,7 +318,7 @@ impl ApiHandler for S3ApiServer {
 
 	async fn handle(
 		&self,
-		req: Request<Body>,
+		mut req: Request<Body>,
 		endpoint: S3ApiEndpoint,
 	) -> Result<Response<Body>, Error> {
 		let S3ApiEndpoint {
@@ -113,17 +327,44 @@ impl ApiHandler for S3ApiServer {
 		} = endpoint;
 		let garage = self.garage.clone();
 
+		// Signatures are computed by clients with the host they requested,
+		// which might have been replaced by a reverse proxy
+		if garage.config.s3_api.trust_forwarded_host {
+			if let Some(host) = handle_forwarded_host_header(req.headers()) {
+				let host = header::HeaderValue::from_str(host)
+					.ok_or_bad_request("Invalid X-Forwarded-Host header")?;
+				req.headers_mut().insert(header::HOST, host);
+			}
+		}
+
+		if self.anonymous {
+			return self
+				.handle_anonymous_request(req, bucket_name, endpoint)
+				.await;
+		}
+
 		// Some endpoints are processed early, before we even check for an API key
 		if let Endpoint::PostObject = endpoint {
-			return handle_post_object(garage, req, bucket_name.unwrap()).await;
+			let bucket_name = bucket_name.unwrap();
+			let resp = handle_post_object(garage, req, bucket_name.clone()).await?;
+			self.count_soft_quota_exceeded(&resp, &bucket_name);
+			return Ok(resp);
 		}
 		if let Endpoint::Options = endpoint {
 			return handle_options_s3api(garage, &req, bucket_name).await;
 		}
 
 		let (api_key, mut content_sha256) = check_payload_signature(&garage, "s3", &req).await?;
-		let api_key = api_key
-			.ok_or_else(|| Error::forbidden("Garage does not support anonymous access yet"))?;
+		let api_key = match api_key {
+			Some(key) => key,
+			// Requests without signature are only accepted from clients
+			// authenticated with a certificate mapped to an access key
+			None => check_client_certificate(&garage, &req)
+				.await?
+				.ok_or_else(|| Error::forbidden("Garage does not support anonymous access yet"))?,
+		};
+		let req = count_key_usage_request(&garage, &api_key.key_id, req);
+		let key_id = api_key.key_id.clone();
 
 		let req = parse_streaming_body(
 			&api_key,
@@ -135,47 +376,47 @@ impl ApiHandler for S3ApiServer {
 
 		let bucket_name = match bucket_name {
 			None => {
-				return self
+				let resp = self
 					.handle_request_without_bucket(req, api_key, endpoint)
-					.await
+					.await?;
+				return Ok(count_key_usage_response(&garage, &key_id, resp));
 			}
 			Some(bucket) => bucket.to_string(),
 		};
 
 		// Special code path for CreateBucket API endpoint
 		if let Endpoint::CreateBucket {} = endpoint {
-			return handle_create_bucket(&garage, req, content_sha256, api_key, bucket_name).await;
+			let resp =
+				handle_create_bucket(&garage, req, content_sha256, api_key, bucket_name).await?;
+			return Ok(count_key_usage_response(&garage, &key_id, resp));
 		}
 
-		let bucket_id = garage
-			.bucket_helper()
-			.resolve_bucket(&bucket_name, &api_key)
-			.await?;
-		let bucket = garage
-			.bucket_helper()
-			.get_existing_bucket(bucket_id)
-			.await?;
-
-		let allowed = match endpoint.authorization_type() {
-			Authorization::Read => api_key.allow_read(&bucket_id),
-			Authorization::Write => api_key.allow_write(&bucket_id),
-			Authorization::Owner => api_key.allow_owner(&bucket_id),
-			_ => unreachable!(),
-		};
-
-		if !allowed {
-			return Err(Error::forbidden("Operation is not allowed for this key."));
-		}
+		let bucket = get_authorized_bucket(
+			&garage,
+			&bucket_name,
+			&api_key,
+			endpoint.authorization_type(),
+		)
+		.await?;
+		let bucket_id = bucket.id;
+		MetricsBucket::set(&req, bucket_id);
 
 		let matching_cors_rule = find_matching_cors_rule(&bucket, &req)?;
 
+		let quota_bucket_name = bucket_name.clone();
 		let resp = match endpoint {
 			Endpoint::HeadObject {
 				key, part_number, ..
-			} => handle_head(garage, &req, bucket_id, &key, part_number).await,
+			} => match parse_verify_header(&req)? {
+				Some(full) => handle_verify_object(garage, &bucket, &key, full, true).await,
+				None => handle_head(garage, &req, &bucket, &key, part_number).await,
+			},
 			Endpoint::GetObject {
 				key, part_number, ..
-			} => handle_get(garage, &req, bucket_id, &key, part_number).await,
+			} => match parse_verify_header(&req)? {
+				Some(full) => handle_verify_object(garage, &bucket, &key, full, false).await,
+				None => handle_get(garage, &req, &bucket, &key, part_number).await,
+			},
 			Endpoint::UploadPart {
 				key,
 				part_number,
@@ -184,7 +425,7 @@ impl ApiHandler for S3ApiServer {
 				handle_put_part(
 					garage,
 					req,
-					bucket_id,
+					&bucket,
 					&key,
 					part_number,
 					&upload_id,
@@ -193,7 +434,7 @@ impl ApiHandler for S3ApiServer {
 				.await
 			}
 			Endpoint::CopyObject { key } => {
-				handle_copy(garage, &api_key, &req, bucket_id, &key).await
+				handle_copy(garage, &api_key, &req, &bucket, &key).await
 			}
 			Endpoint::UploadPartCopy {
 				key,
@@ -212,14 +453,25 @@ impl ApiHandler for S3ApiServer {
 				.await
 			}
 			Endpoint::PutObject { key } => {
-				handle_put(garage, req, &bucket, &key, content_sha256).await
+				if let Some(mode) = parse_if_block_exists_header(&req)? {
+					handle_put_if_block_exists(garage, req, &bucket, &key, mode, content_sha256)
+						.await
+				} else if let Some(offset) = parse_upload_offset_header(&req)? {
+					handle_put_resumable(garage, req, &bucket, &key, offset, content_sha256).await
+				} else {
+					handle_put(garage, req, &bucket, &key, content_sha256).await
+				}
+			}
+			Endpoint::AppendObject { key, position } => {
+				handle_append(garage, req, &bucket, &key, position, content_sha256).await
 			}
 			Endpoint::AbortMultipartUpload { key, upload_id } => {
 				handle_abort_multipart_upload(garage, bucket_id, &key, &upload_id).await
 			}
-			Endpoint::DeleteObject { key, .. } => handle_delete(garage, bucket_id, &key).await,
+			Endpoint::DeleteObject { key, .. } => handle_delete(garage, &bucket, &key).await,
 			Endpoint::CreateMultipartUpload { key } => {
-				handle_create_multipart_upload(garage, &req, &bucket_name, bucket_id, &key).await
+				handle_create_multipart_upload(garage, &api_key, &req, &bucket_name, &bucket, &key)
+					.await
 			}
 			Endpoint::CompleteMultipartUpload { key, upload_id } => {
 				handle_complete_multipart_upload(
@@ -351,7 +603,7 @@ impl ApiHandler for S3ApiServer {
 				.await
 			}
 			Endpoint::DeleteObjects {} => {
-				handle_delete_objects(garage, bucket_id, req, content_sha256).await
+				handle_delete_objects(garage, &bucket, req, content_sha256).await
 			}
 			Endpoint::GetBucketWebsite {} => handle_get_website(&bucket).await,
 			Endpoint::PutBucketWebsite {} => {
@@ -377,11 +629,11 @@ impl ApiHandler for S3ApiServer {
 		// add the corresponding CORS headers to the response
 		let mut resp_ok = resp?;
 		if let Some(rule) = matching_cors_rule {
-			add_cors_headers(&mut resp_ok, rule)
-				.ok_or_internal_error("Invalid bucket CORS configuration")?;
+			add_cors_headers(&mut resp_ok, &rule);
 		}
+		self.count_soft_quota_exceeded(&resp_ok, &quota_bucket_name);
 
-		Ok(resp_ok)
+		Ok(count_key_usage_response(&self.garage, &key_id, resp_ok))
 	}
 }
 
@@ -395,5 +647,29 @@ impl ApiEndpoint for S3ApiEndpoint {
 			"bucket",
 			self.bucket_name.clone().unwrap_or_default(),
 		));
+		// The hash of the key is used instead of the key itself, so that
+		// object names don't leak into the tracing system
+		if let Some(key) = self.endpoint.get_key() {
+			span.set_attribute(KeyValue::new(
+				"key_hash",
+				hex::encode(&blake2sum(key.as_bytes()).as_slice()[..8]),
+			));
+		}
+	}
+
+	fn request_kind(&self) -> RequestKind {
+		match self.endpoint {
+			Endpoint::PutObject { .. }
+			| Endpoint::AppendObject { .. }
+			| Endpoint::UploadPart { .. }
+			| Endpoint::CopyObject { .. }
+			| Endpoint::UploadPartCopy { .. }
+			| Endpoint::PostObject => RequestKind::Put,
+			Endpoint::ListObjects { .. }
+			| Endpoint::ListObjectsV2 { .. }
+			| Endpoint::ListObjectVersions { .. }
+			| Endpoint::ListMultipartUploads { .. } => RequestKind::ListScan,
+			_ => RequestKind::Other,
+		}
 	}
 }
@@ -6,6 +6,7 @@ use hyper::{Body, Request, Response, StatusCode};
 use garage_model::bucket_alias_table::*;
 use garage_model::bucket_table::Bucket;
 use garage_model::garage::Garage;
+use garage_model::helper::error::Error as HelperError;
 use garage_model::key_table::Key;
 use garage_model::permission::BucketKeyPerm;
 use garage_table::util::*;
@@ -14,10 +15,52 @@ use garage_util::data::*;
 use garage_util::time::*;
 
 use crate::common_error::CommonError;
+use crate::helpers::Authorization;
 use crate::s3::error::*;
 use crate::s3::xml as s3_xml;
 use crate::signature::verify_signed_content;
 
+/// Find the bucket targeted by a request, and check that the access key
+/// has the permission required by the request on this bucket.
+///
+/// As in AWS S3, a bucket that does not exist gives a NoSuchBucket error,
+/// which lets anyone holding a valid key find which bucket names are used.
+/// If `hide_bucket_existence` is set, AccessDenied is returned instead,
+/// so that it cannot be told apart from a bucket the key has no access to.
+pub async fn get_authorized_bucket(
+	garage: &Garage,
+	bucket_name: &str,
+	api_key: &Key,
+	authorization: Authorization,
+) -> Result<Bucket, Error> {
+	let hide_existence = garage.config.s3_api.hide_bucket_existence;
+	let denied = || Error::forbidden("Operation is not allowed for this key.");
+
+	let bucket_id = match garage
+		.bucket_helper()
+		.resolve_bucket(&bucket_name.to_string(), api_key)
+		.await
+	{
+		Err(HelperError::NoSuchBucket(_)) if hide_existence => return Err(denied()),
+		res => res?,
+	};
+
+	let allowed = match authorization {
+		Authorization::None => true,
+		Authorization::Read => api_key.allow_read(&bucket_id),
+		Authorization::Write => api_key.allow_write(&bucket_id),
+		Authorization::Owner => api_key.allow_owner(&bucket_id),
+	};
+	if !allowed {
+		return Err(denied());
+	}
+
+	match garage.bucket_helper().get_existing_bucket(bucket_id).await {
+		Err(HelperError::NoSuchBucket(_)) if hide_existence => Err(denied()),
+		res => Ok(res?),
+	}
+}
+
 pub fn handle_get_bucket_location(garage: Arc<Garage>) -> Result<Response<Body>, Error> {
 	let loc = s3_xml::LocationConstraint {
 		xmlns: (),
@@ -49,16 +92,7 @@ pub async fn handle_list_buckets(garage: &Garage, api_key: &Key) -> Result<Respo
 	)?;
 
 	// Collect buckets user has access to
-	let ids = api_key
-		.state
-		.as_option()
-		.unwrap()
-		.authorized_buckets
-		.items()
-		.iter()
-		.filter(|(_, perms)| perms.is_any())
-		.map(|(id, _)| *id)
-		.collect::<Vec<_>>();
+	let ids = api_key.authorized_bucket_ids();
 
 	let mut buckets_by_id = HashMap::new();
 	let mut aliases = HashMap::new();
@@ -130,7 +164,7 @@ pub async fn handle_create_bucket(
 
 	if let Some(location_constraint) = cmd {
 		if location_constraint != garage.config.s3_api.s3_region {
-			return Err(Error::bad_request(format!(
+			return Err(Error::InvalidLocationConstraint(format!(
 				"Cannot satisfy location constraint `{}`: buckets can only be created in region `{}`",
 				location_constraint,
 				garage.config.s3_api.s3_region
@@ -236,6 +270,12 @@ pub async fn handle_delete_bucket(
 	if !has_other_global_aliases && !has_other_local_aliases {
 		// Delete bucket
 
+		if bucket.deletion_protection() {
+			return Err(Error::forbidden(
+				"The bucket is protected against deletion, it can only be deleted through the admin API",
+			));
+		}
+
 		// Check bucket is empty
 		if !garage.bucket_helper().is_bucket_empty(bucket_id).await? {
 			return Err(CommonError::BucketNotEmpty.into());
@@ -289,6 +329,7 @@ fn parse_create_bucket_xml(xml_bytes: &[u8]) -> Option<Option<String>> {
 	// Returns None if invalid data
 	// Returns Some(None) if no location constraint is given
 	// Returns Some(Some("xxxx")) where xxxx is the given location constraint
+	// An empty location constraint is the same as no location constraint
 
 	let xml_str = std::str::from_utf8(xml_bytes).ok()?;
 	if xml_str.trim_matches(char::is_whitespace).is_empty() {
@@ -308,13 +349,13 @@ fn parse_create_bucket_xml(xml_bytes: &[u8]) -> Option<Option<String>> {
 			if ret.is_some() {
 				return None;
 			}
-			ret = Some(item.text()?.to_string());
+			ret = Some(item.text().unwrap_or_default().trim().to_string());
 		} else if !item.is_text() {
 			return None;
 		}
 	}
 
-	Some(ret)
+	Some(ret.filter(|x| !x.is_empty()))
 }
 
 #[cfg(test)]
@@ -346,6 +387,16 @@ mod tests {
 		assert_eq!(
 			parse_create_bucket_xml(
 				br#"
+            <CreateBucketConfiguration xmlns="http://s3.amazonaws.com/doc/2006-03-01/">
+             <LocationConstraint/>
+            </CreateBucketConfiguration >
+		"#
+			),
+			Some(None)
+		);
+		assert_eq!(
+			parse_create_bucket_xml(
+				br#"
             <CreateBucketConfiguration xmlns="http://s3.amazonaws.com/doc/2006-03-01/">
             </Crea >
 		"#
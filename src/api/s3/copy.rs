@@ -15,6 +15,7 @@ use garage_table::*;
 use garage_util::data::*;
 use garage_util::time::*;
 
+use garage_model::bucket_table::Bucket;
 use garage_model::garage::Garage;
 use garage_model::key_table::Key;
 use garage_model::s3::block_ref_table::*;
@@ -22,37 +23,60 @@ use garage_model::s3::mpu_table::*;
 use garage_model::s3::object_table::*;
 use garage_model::s3::version_table::*;
 
-use crate::helpers::parse_bucket_key;
+use crate::helpers::{parse_bucket_key, Authorization};
+use crate::s3::bucket::get_authorized_bucket;
 use crate::s3::error::*;
 use crate::s3::multipart;
 use crate::s3::put::get_headers;
 use crate::s3::xml::{self as s3_xml, xmlns_tag};
 
+pub const X_GARAGE_RENAME: &str = "x-garage-rename";
+
 pub async fn handle_copy(
 	garage: Arc<Garage>,
 	api_key: &Key,
 	req: &Request<Body>,
-	dest_bucket_id: Uuid,
+	dest_bucket: &Bucket,
 	dest_key: &str,
 ) -> Result<Response<Body>, Error> {
+	let dest_bucket_id = dest_bucket.id;
 	let copy_precondition = CopyPreconditionHeaders::parse(req)?;
 
 	let source_object = get_copy_source(&garage, api_key, req).await?;
 
-	let (source_version, source_version_data, source_version_meta) =
-		extract_source_info(&source_object)?;
+	let (source_version, _, source_version_meta) = extract_source_info(&source_object)?;
 
 	// Check precondition, e.g. x-amz-copy-source-if-match
 	copy_precondition.check(source_version, &source_version_meta.etag)?;
 
-	// Generate parameters for copied object
-	let new_uuid = gen_uuid();
-	let new_timestamp = now_msec();
+	// Garage extension: x-garage-rename: true turns the copy into a move,
+	// the source object being deleted in the same write as the destination
+	// object is created.
+	let rename = match req.headers().get(X_GARAGE_RENAME) {
+		None => false,
+		Some(v) => match v.to_str()? {
+			"true" => true,
+			"false" => false,
+			_ => return Err(Error::bad_request("Invalid x-garage-rename header")),
+		},
+	};
+	if rename {
+		if source_object.bucket_id != dest_bucket_id {
+			return Err(Error::bad_request(
+				"x-garage-rename requires source and destination to be in the same bucket",
+			));
+		}
+		if source_object.key == dest_key {
+			return Err(Error::bad_request(
+				"x-garage-rename requires source and destination keys to be different",
+			));
+		}
+	}
 
 	// Implement x-amz-metadata-directive: REPLACE
 	let new_meta = match req.headers().get("x-amz-metadata-directive") {
 		Some(v) if v == hyper::header::HeaderValue::from_static("REPLACE") => ObjectVersionMeta {
-			headers: get_headers(req.headers())?,
+			headers: get_headers(req.headers(), dest_key, &dest_bucket.object_defaults())?,
 			size: source_version_meta.size,
 			etag: source_version_meta.etag.clone(),
 		},
@@ -61,7 +85,57 @@ pub async fn handle_copy(
 
 	let etag = new_meta.etag.to_string();
 
-	// Save object copy
+	let (new_uuid, new_timestamp) = copy_object(
+		&garage,
+		&source_object,
+		new_meta,
+		dest_bucket_id,
+		dest_key,
+		rename,
+	)
+	.await?;
+
+	let last_modified = msec_to_rfc3339(new_timestamp);
+	let result = CopyObjectResult {
+		last_modified: s3_xml::Value(last_modified),
+		etag: s3_xml::Value(format!("\"{}\"", etag)),
+	};
+	let xml = s3_xml::to_xml_with_header(&result)?;
+
+	Ok(Response::builder()
+		.header("Content-Type", "application/xml")
+		.header("x-amz-version-id", hex::encode(new_uuid))
+		.header(
+			"x-amz-copy-source-version-id",
+			hex::encode(source_version.uuid),
+		)
+		.body(Body::from(xml))?)
+}
+
+/// Write a new version of an object, with the data of the current version of
+/// a source object and the given metadata. Returns the id and timestamp of the
+/// new version. When renaming, the source object is deleted at the same time.
+pub(crate) async fn copy_object(
+	garage: &Garage,
+	source_object: &Object,
+	new_meta: ObjectVersionMeta,
+	dest_bucket_id: Uuid,
+	dest_key: &str,
+	rename: bool,
+) -> Result<(Uuid, u64), Error> {
+	let (source_version, source_version_data, _) = extract_source_info(source_object)?;
+
+	// Generate parameters for copied object. When copying an object onto
+	// itself, the new version must supersede the source version even if
+	// the clock of this node is behind.
+	let new_uuid = gen_uuid();
+	let new_timestamp =
+		if source_object.bucket_id == dest_bucket_id && source_object.key == dest_key {
+			std::cmp::max(now_msec(), source_version.timestamp + 1)
+		} else {
+			now_msec()
+		};
+
 	match source_version_data {
 		ObjectVersionData::DeleteMarker => unreachable!(),
 		ObjectVersionData::Inline(_meta, bytes) => {
@@ -78,9 +152,16 @@ pub async fn handle_copy(
 				dest_key.to_string(),
 				vec![dest_object_version],
 			);
-			garage.object_table.insert(&dest_object).await?;
+			insert_dest_object(garage, dest_object, rename.then_some(source_object)).await?;
 		}
 		ObjectVersionData::FirstBlock(_meta, first_block_hash) => {
+			// Data blocks are never read or rewritten: the copy is a new version
+			// that references the same blocks, so copying an object onto itself
+			// to replace its metadata only writes metadata, whatever its size.
+			// Reusing the source version instead is not possible: it is marked
+			// as deleted when the object is overwritten, and a concurrent write
+			// seen first by some nodes would make them delete it for good.
+
 			// Get block list from source version
 			let source_version = garage
 				.version_table
@@ -97,6 +178,7 @@ pub async fn handle_copy(
 				state: ObjectVersionState::Uploading {
 					headers: new_meta.headers.clone(),
 					multipart: false,
+					initiator: None,
 				},
 			};
 			let tmp_dest_object = Object::new(
@@ -158,25 +240,50 @@ pub async fn handle_copy(
 				dest_key.to_string(),
 				vec![dest_object_version],
 			);
-			garage.object_table.insert(&dest_object).await?;
+			insert_dest_object(garage, dest_object, rename.then_some(source_object)).await?;
 		}
 	}
 
-	let last_modified = msec_to_rfc3339(new_timestamp);
-	let result = CopyObjectResult {
-		last_modified: s3_xml::Value(last_modified),
-		etag: s3_xml::Value(format!("\"{}\"", etag)),
+	Ok((new_uuid, new_timestamp))
+}
+
+/// Insert the final destination object of a copy. When renaming, a delete
+/// marker for the source object is written in the same call: as both objects
+/// live in the same bucket they belong to the same partition, so they are
+/// sent together to the same set of nodes.
+async fn insert_dest_object(
+	garage: &Garage,
+	dest_object: Object,
+	rename_source: Option<&Object>,
+) -> Result<(), Error> {
+	let source_object = match rename_source {
+		None => {
+			garage.object_table.insert(&dest_object).await?;
+			return Ok(());
+		}
+		Some(o) => o,
 	};
-	let xml = s3_xml::to_xml_with_header(&result)?;
 
-	Ok(Response::builder()
-		.header("Content-Type", "application/xml")
-		.header("x-amz-version-id", hex::encode(new_uuid))
-		.header(
-			"x-amz-copy-source-version-id",
-			hex::encode(source_version.uuid),
-		)
-		.body(Body::from(xml))?)
+	let timestamp = source_object
+		.versions()
+		.iter()
+		.map(|v| v.timestamp + 1)
+		.fold(now_msec(), std::cmp::max);
+	let delete_marker = Object::new(
+		source_object.bucket_id,
+		source_object.key.clone(),
+		vec![ObjectVersion {
+			uuid: gen_uuid(),
+			timestamp,
+			state: ObjectVersionState::Complete(ObjectVersionData::DeleteMarker),
+		}],
+	);
+
+	garage
+		.object_table
+		.insert_many(&[dest_object, delete_marker])
+		.await?;
+	Ok(())
 }
 
 pub async fn handle_upload_part_copy(
@@ -444,17 +551,10 @@ async fn get_copy_source(
 	let copy_source = percent_encoding::percent_decode_str(copy_source).decode_utf8()?;
 
 	let (source_bucket, source_key) = parse_bucket_key(&copy_source, None)?;
-	let source_bucket_id = garage
-		.bucket_helper()
-		.resolve_bucket(&source_bucket.to_string(), api_key)
-		.await?;
-
-	if !api_key.allow_read(&source_bucket_id) {
-		return Err(Error::forbidden(format!(
-			"Reading from bucket {} not allowed for this key",
-			source_bucket
-		)));
-	}
+	let source_bucket_id =
+		get_authorized_bucket(garage, source_bucket, api_key, Authorization::Read)
+			.await?
+			.id;
 
 	let source_key = source_key.ok_or_bad_request("No source key specified")?;
 
@@ -1,9 +1,11 @@
 use quick_xml::de::from_reader;
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 
 use http::header::{
-	ACCESS_CONTROL_ALLOW_HEADERS, ACCESS_CONTROL_ALLOW_METHODS, ACCESS_CONTROL_ALLOW_ORIGIN,
-	ACCESS_CONTROL_EXPOSE_HEADERS, ACCESS_CONTROL_REQUEST_HEADERS, ACCESS_CONTROL_REQUEST_METHOD,
+	HeaderValue, ACCESS_CONTROL_ALLOW_CREDENTIALS, ACCESS_CONTROL_ALLOW_HEADERS,
+	ACCESS_CONTROL_ALLOW_METHODS, ACCESS_CONTROL_ALLOW_ORIGIN, ACCESS_CONTROL_EXPOSE_HEADERS,
+	ACCESS_CONTROL_MAX_AGE, ACCESS_CONTROL_REQUEST_HEADERS, ACCESS_CONTROL_REQUEST_METHOD, VARY,
 };
 use hyper::{header::HeaderName, Body, Method, Request, Response, StatusCode};
 
@@ -142,8 +144,7 @@ pub fn handle_options_for_bucket(
 	let origin = req
 		.headers()
 		.get("Origin")
-		.ok_or_bad_request("Missing Origin header")?
-		.to_str()?;
+		.ok_or_bad_request("Missing Origin header")?;
 	let request_method = req
 		.headers()
 		.get(ACCESS_CONTROL_REQUEST_METHOD)
@@ -154,82 +155,249 @@ pub fn handle_options_for_bucket(
 		None => vec![],
 	};
 
-	if let Some(cors_config) = bucket.params().unwrap().cors_config.get() {
-		let matching_rule = cors_config
-			.iter()
-			.find(|rule| cors_rule_matches(rule, origin, request_method, request_headers.iter()));
-		if let Some(rule) = matching_rule {
-			let mut resp = Response::builder()
-				.status(StatusCode::OK)
-				.body(Body::empty())?;
-			add_cors_headers(&mut resp, rule).ok_or_internal_error("Invalid CORS configuration")?;
-			return Ok(resp);
+	if let Some(rule) = find_cors_rule(bucket, origin, request_method, &request_headers)? {
+		let mut resp = Response::builder()
+			.status(StatusCode::OK)
+			.body(Body::empty())?;
+		add_cors_headers(&mut resp, &rule);
+		// Allowed headers can be patterns, answer with the headers of the
+		// request, which are all allowed
+		if let Some(h) = req.headers().get(ACCESS_CONTROL_REQUEST_HEADERS) {
+			resp.headers_mut()
+				.insert(ACCESS_CONTROL_ALLOW_HEADERS, h.clone());
+		}
+		if let Some(max_age) = &rule.rule().max_age_header {
+			resp.headers_mut()
+				.insert(ACCESS_CONTROL_MAX_AGE, max_age.clone());
 		}
+		return Ok(resp);
 	}
 
 	Err(Error::forbidden("This CORS request is not allowed."))
 }
 
-pub fn find_matching_cors_rule<'a>(
-	bucket: &'a Bucket,
+pub fn find_matching_cors_rule(
+	bucket: &Bucket,
 	req: &Request<Body>,
-) -> Result<Option<&'a GarageCorsRule>, Error> {
-	if let Some(cors_config) = bucket.params().unwrap().cors_config.get() {
-		if let Some(origin) = req.headers().get("Origin") {
-			let origin = origin.to_str()?;
-			let request_headers = match req.headers().get(ACCESS_CONTROL_REQUEST_HEADERS) {
-				Some(h) => h.to_str()?.split(',').map(|h| h.trim()).collect::<Vec<_>>(),
-				None => vec![],
-			};
-			return Ok(cors_config.iter().find(|rule| {
-				cors_rule_matches(rule, origin, req.method().as_ref(), request_headers.iter())
-			}));
-		}
+) -> Result<Option<MatchingCorsRule>, Error> {
+	if let Some(origin) = req.headers().get("Origin") {
+		let request_headers = match req.headers().get(ACCESS_CONTROL_REQUEST_HEADERS) {
+			Some(h) => h.to_str()?.split(',').map(|h| h.trim()).collect::<Vec<_>>(),
+			None => vec![],
+		};
+		return find_cors_rule(bucket, origin, req.method().as_ref(), &request_headers);
 	}
 	Ok(None)
 }
 
-fn cors_rule_matches<'a, HI, S>(
-	rule: &GarageCorsRule,
-	origin: &'a str,
-	method: &'a str,
-	mut request_headers: HI,
-) -> bool
-where
-	HI: Iterator<Item = S>,
-	S: AsRef<str>,
-{
-	rule.allow_origins.iter().any(|x| x == "*" || x == origin)
-		&& rule.allow_methods.iter().any(|x| x == "*" || x == method)
-		&& request_headers.all(|h| {
-			rule.allow_headers
-				.iter()
-				.any(|x| x == "*" || x == h.as_ref())
-		})
+fn find_cors_rule(
+	bucket: &Bucket,
+	origin: &HeaderValue,
+	method: &str,
+	request_headers: &[&str],
+) -> Result<Option<MatchingCorsRule>, Error> {
+	let config = match compiled_cors_config(bucket)? {
+		Some(c) => c,
+		None => return Ok(None),
+	};
+	let origin_str = origin.to_str()?;
+	let matching = config.rules.iter().enumerate().find_map(|(i, rule)| {
+		let pattern = rule.matching_origin(origin_str, method, request_headers)?;
+		// Browsers reject credentialed responses that allow any origin
+		Some((i, pattern.is_any() && !rule.allow_credentials))
+	});
+	Ok(matching.map(|(index, any_origin)| MatchingCorsRule {
+		config: config.clone(),
+		index,
+		allow_origin: if any_origin {
+			HeaderValue::from_static("*")
+		} else {
+			origin.clone()
+		},
+	}))
+}
+
+/// Compiled CORS rules of a bucket, obtained from a cache that is updated
+/// when the CORS configuration of the bucket changes
+fn compiled_cors_config(bucket: &Bucket) -> Result<Option<Arc<CompiledCorsConfig>>, Error> {
+	let cors_config = match bucket.params().unwrap().cors_config.get() {
+		Some(c) => c,
+		None => return Ok(None),
+	};
+
+	let mut cache = COMPILED_CORS_CONFIGS.lock().unwrap();
+	if let Some(compiled) = cache.get(&bucket.id) {
+		if compiled.source == *cors_config {
+			return Ok(Some(compiled.clone()));
+		}
+	}
+
+	let compiled = Arc::new(CompiledCorsConfig {
+		source: cors_config.clone(),
+		rules: cors_config
+			.iter()
+			.map(CompiledCorsRule::new)
+			.collect::<Result<Vec<_>, _>>()
+			.ok_or_internal_error("Invalid bucket CORS configuration")?,
+	});
+	if cache.len() >= COMPILED_CORS_CACHE_SIZE {
+		cache.clear();
+	}
+	cache.insert(bucket.id, compiled.clone());
+	Ok(Some(compiled))
 }
 
-pub fn add_cors_headers(
-	resp: &mut Response<Body>,
-	rule: &GarageCorsRule,
-) -> Result<(), http::header::InvalidHeaderValue> {
+pub fn add_cors_headers(resp: &mut Response<Body>, rule: &MatchingCorsRule) {
+	let compiled = rule.rule();
 	let h = resp.headers_mut();
-	h.insert(
-		ACCESS_CONTROL_ALLOW_ORIGIN,
-		rule.allow_origins.join(", ").parse()?,
-	);
+	h.insert(ACCESS_CONTROL_ALLOW_ORIGIN, rule.allow_origin.clone());
 	h.insert(
 		ACCESS_CONTROL_ALLOW_METHODS,
-		rule.allow_methods.join(", ").parse()?,
+		compiled.allow_methods_header.clone(),
 	);
 	h.insert(
 		ACCESS_CONTROL_ALLOW_HEADERS,
-		rule.allow_headers.join(", ").parse()?,
+		compiled.allow_headers_header.clone(),
 	);
 	h.insert(
 		ACCESS_CONTROL_EXPOSE_HEADERS,
-		rule.expose_headers.join(", ").parse()?,
+		compiled.expose_headers_header.clone(),
 	);
-	Ok(())
+	if compiled.allow_credentials {
+		h.insert(
+			ACCESS_CONTROL_ALLOW_CREDENTIALS,
+			HeaderValue::from_static("true"),
+		);
+	}
+	// Whether a rule matches, and the headers it sets, depend on the origin
+	// of the request
+	h.append(VARY, HeaderValue::from_static("Origin"));
+}
+
+// ---- COMPILED CORS RULES ----
+
+lazy_static::lazy_static! {
+	static ref COMPILED_CORS_CONFIGS: Mutex<HashMap<Uuid, Arc<CompiledCorsConfig>>> =
+		Mutex::new(HashMap::new());
+}
+
+/// Maximum number of buckets whose compiled CORS rules are kept in the cache
+const COMPILED_CORS_CACHE_SIZE: usize = 10000;
+
+/// CORS rules of a bucket, prepared to be matched against requests
+struct CompiledCorsConfig {
+	/// Rules of the configuration of the bucket they were compiled from
+	source: Vec<GarageCorsRule>,
+	rules: Vec<CompiledCorsRule>,
+}
+
+/// A CORS rule, with its patterns and the values of the response headers
+/// it sets computed once
+struct CompiledCorsRule {
+	allow_origins: Vec<WildcardPattern>,
+	allow_methods: Vec<String>,
+	/// Lowercase patterns of header names
+	allow_headers: Vec<WildcardPattern>,
+	allow_methods_header: HeaderValue,
+	allow_headers_header: HeaderValue,
+	expose_headers_header: HeaderValue,
+	max_age_header: Option<HeaderValue>,
+	allow_credentials: bool,
+}
+
+impl CompiledCorsRule {
+	fn new(rule: &GarageCorsRule) -> Result<Self, http::header::InvalidHeaderValue> {
+		Ok(Self {
+			allow_origins: rule
+				.allow_origins
+				.iter()
+				.map(|o| WildcardPattern::new(o))
+				.collect(),
+			allow_methods: rule.allow_methods.clone(),
+			allow_headers: rule
+				.allow_headers
+				.iter()
+				.map(|h| WildcardPattern::new(&h.to_lowercase()))
+				.collect(),
+			allow_methods_header: rule.allow_methods.join(", ").parse()?,
+			allow_headers_header: rule.allow_headers.join(", ").parse()?,
+			expose_headers_header: rule.expose_headers.join(", ").parse()?,
+			max_age_header: rule.max_age_seconds.map(HeaderValue::from),
+			allow_credentials: rule.allow_credentials,
+		})
+	}
+
+	/// If the rule allows a request, returns the pattern of its allowed
+	/// origins that matches the origin of the request
+	fn matching_origin(
+		&self,
+		origin: &str,
+		method: &str,
+		request_headers: &[&str],
+	) -> Option<&WildcardPattern> {
+		let pattern = self.allow_origins.iter().find(|p| p.matches(origin))?;
+		let allowed = self.allow_methods.iter().any(|x| x == "*" || x == method)
+			&& request_headers.iter().all(|h| {
+				let h = h.to_lowercase();
+				self.allow_headers.iter().any(|p| p.matches(&h))
+			});
+		allowed.then_some(pattern)
+	}
+}
+
+/// Allowed origin or header name of a CORS rule, in which a `*` matches
+/// any sequence of characters, e.g. `https://*.example.com`
+#[derive(Debug)]
+struct WildcardPattern {
+	prefix: String,
+	/// Part after the `*`, if the pattern contains one
+	suffix: Option<String>,
+}
+
+impl WildcardPattern {
+	fn new(pattern: &str) -> Self {
+		match pattern.split_once('*') {
+			Some((prefix, suffix)) => Self {
+				prefix: prefix.to_string(),
+				suffix: Some(suffix.to_string()),
+			},
+			None => Self {
+				prefix: pattern.to_string(),
+				suffix: None,
+			},
+		}
+	}
+
+	fn is_any(&self) -> bool {
+		self.prefix.is_empty() && self.suffix.as_deref() == Some("")
+	}
+
+	fn matches(&self, value: &str) -> bool {
+		match &self.suffix {
+			None => value == self.prefix,
+			Some(suffix) => {
+				value.len() >= self.prefix.len() + suffix.len()
+					&& value.starts_with(&self.prefix)
+					&& value.ends_with(suffix.as_str())
+			}
+		}
+	}
+}
+
+/// A CORS rule of a bucket that allows a request
+pub struct MatchingCorsRule {
+	config: Arc<CompiledCorsConfig>,
+	index: usize,
+	/// Value of the Access-Control-Allow-Origin header: `*`, or the origin
+	/// of the request if the rule does not allow all origins or allows
+	/// credentials
+	allow_origin: HeaderValue,
+}
+
+impl MatchingCorsRule {
+	fn rule(&self) -> &CompiledCorsRule {
+		&self.config.rules[self.index]
+	}
 }
 
 // ---- SERIALIZATION AND DESERIALIZATION TO/FROM S3 XML ----
@@ -257,6 +425,9 @@ pub struct CorsRule {
 	pub allowed_headers: Vec<Value>,
 	#[serde(rename = "ExposeHeader", default)]
 	pub expose_headers: Vec<Value>,
+	/// Garage extension: `true` to allow requests with credentials
+	#[serde(rename = "AllowCredentials", skip_serializing_if = "Option::is_none")]
+	pub allow_credentials: Option<Value>,
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
@@ -312,6 +483,22 @@ impl CorsRule {
 				.parse::<HeaderName>()
 				.ok_or_bad_request("Invalid HTTP header name")?;
 		}
+		if let Some(ac) = &self.allow_credentials {
+			if ac.0 != "true" && ac.0 != "false" {
+				return Err(Error::bad_request("AllowCredentials must be true or false"));
+			}
+		}
+		for pattern in self
+			.allowed_origins
+			.iter()
+			.chain(self.allowed_headers.iter())
+		{
+			if pattern.0.matches('*').count() > 1 {
+				return Err(Error::bad_request(
+					"AllowedOrigin and AllowedHeader can contain at most one wildcard",
+				));
+			}
+		}
 		Ok(())
 	}
 
@@ -325,6 +512,7 @@ impl CorsRule {
 			allow_methods: convert_vec(&self.allowed_methods),
 			allow_headers: convert_vec(&self.allowed_headers),
 			expose_headers: convert_vec(&self.expose_headers),
+			allow_credentials: matches!(&self.allow_credentials, Some(v) if v.0 == "true"),
 		}
 	}
 
@@ -341,6 +529,7 @@ impl CorsRule {
 			allowed_methods: convert_vec(&rule.allow_methods),
 			allowed_headers: convert_vec(&rule.allow_headers),
 			expose_headers: convert_vec(&rule.expose_headers),
+			allow_credentials: rule.allow_credentials.then(|| Value("true".into())),
 		}
 	}
 }
@@ -377,6 +566,7 @@ mod tests {
    <AllowedMethod>DELETE</AllowedMethod>
    <AllowedHeader>*</AllowedHeader>
    <ExposeHeader>*</ExposeHeader>
+   <AllowCredentials>true</AllowCredentials>
  </CORSRule>
 </CORSConfiguration>"#;
 		let conf: CorsConfiguration = from_str(message).unwrap();
@@ -390,6 +580,7 @@ mod tests {
 					allowed_methods: vec!["PUT".into(), "POST".into(), "DELETE".into()],
 					allowed_headers: vec!["*".into()],
 					expose_headers: vec![],
+					allow_credentials: None,
 				},
 				CorsRule {
 					id: None,
@@ -398,6 +589,7 @@ mod tests {
 					allowed_methods: vec!["GET".into()],
 					allowed_headers: vec![],
 					expose_headers: vec![],
+					allow_credentials: None,
 				},
 				CorsRule {
 					id: Some("qsdfjklm".into()),
@@ -406,6 +598,7 @@ mod tests {
 					allowed_methods: vec!["GET".into(), "DELETE".into()],
 					allowed_headers: vec!["*".into()],
 					expose_headers: vec!["*".into()],
+					allow_credentials: Some("true".into()),
 				},
 			],
 		};
@@ -421,4 +614,49 @@ mod tests {
 
 		Ok(())
 	}
+
+	#[test]
+	fn test_wildcard_rules() {
+		let rule = CompiledCorsRule::new(&GarageCorsRule {
+			id: None,
+			max_age_seconds: Some(3600),
+			allow_origins: vec![
+				"https://*.example.com".to_string(),
+				"http://localhost:*".to_string(),
+			],
+			allow_methods: vec!["GET".to_string(), "PUT".to_string()],
+			allow_headers: vec!["X-Amz-*".to_string(), "Content-Type".to_string()],
+			expose_headers: vec![],
+			allow_credentials: false,
+		})
+		.unwrap();
+
+		let allowed = |origin, method, headers: &[&str]| {
+			rule.matching_origin(origin, method, headers)
+				.map(|p| p.is_any())
+		};
+		assert_eq!(allowed("https://app.example.com", "GET", &[]), Some(false));
+		assert_eq!(allowed("https://a.b.example.com", "PUT", &[]), Some(false));
+		assert_eq!(allowed("http://localhost:8080", "GET", &[]), Some(false));
+		assert_eq!(allowed("https://example.com", "GET", &[]), None);
+		assert_eq!(allowed("https://app.example.com.evil", "GET", &[]), None);
+		assert_eq!(allowed("http://app.example.com", "GET", &[]), None);
+		assert_eq!(allowed("https://app.example.com", "DELETE", &[]), None);
+		assert_eq!(
+			allowed(
+				"https://app.example.com",
+				"PUT",
+				&["x-amz-date", "content-type"]
+			),
+			Some(false)
+		);
+		assert_eq!(
+			allowed("https://app.example.com", "PUT", &["authorization"]),
+			None
+		);
+
+		assert!(WildcardPattern::new("*").is_any());
+		assert!(WildcardPattern::new("*").matches("https://example.com"));
+		assert!(!WildcardPattern::new("https://*").is_any());
+	}
 }
@@ -0,0 +1,540 @@
+//! Garage extension to upload objects without sending the blocks that are
+//! already stored in their bucket
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use futures::prelude::*;
+use hyper::body::Bytes;
+use hyper::{Body, Request, Response, StatusCode};
+use md5::{Digest as Md5Digest, Md5};
+use serde::Serialize;
+use sha2::Sha256;
+
+use garage_rpc::netapp::bytes_buf::BytesBuf;
+use garage_table::*;
+use garage_util::async_hash::*;
+use garage_util::data::*;
+use garage_util::time::*;
+
+use garage_model::bucket_table::Bucket;
+use garage_model::garage::Garage;
+use garage_model::s3::block_ref_table::*;
+use garage_model::s3::object_table::*;
+use garage_model::s3::version_table::*;
+
+use crate::s3::error::*;
+use crate::s3::put::*;
+
+pub const X_GARAGE_IF_BLOCK_EXISTS: &str = "x-garage-if-block-exists";
+
+/// Number of blocks whose presence is checked at the same time
+const CHECK_CONCURRENCY: usize = 16;
+
+/// Maximum size of the manifest at the beginning of a request body
+const MAX_MANIFEST_SIZE: usize = 16 << 20;
+
+/// Number of block references read at once when checking a block
+const BLOCK_REF_PAGE_SIZE: usize = 100;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IfBlockExistsMode {
+	/// Return the blocks of the manifest that are not stored in the bucket
+	Check,
+	/// Store an object made of the blocks of the manifest
+	Commit,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CheckResponse {
+	block_size: usize,
+	missing: Vec<String>,
+}
+
+/// A line of a manifest: the hash and size of a block of the object, and
+/// whether its data is sent in the request
+#[derive(Debug, PartialEq, Eq)]
+struct ManifestEntry {
+	hash: Hash,
+	size: u64,
+	with_data: bool,
+}
+
+/// Parse the value of the x-garage-if-block-exists header, if present
+pub fn parse_if_block_exists_header(
+	req: &Request<Body>,
+) -> Result<Option<IfBlockExistsMode>, Error> {
+	match req.headers().get(X_GARAGE_IF_BLOCK_EXISTS) {
+		None => Ok(None),
+		Some(v) => match v.to_str()? {
+			"check" => Ok(Some(IfBlockExistsMode::Check)),
+			"commit" => Ok(Some(IfBlockExistsMode::Commit)),
+			_ => Err(Error::bad_request(
+				"Invalid x-garage-if-block-exists header",
+			)),
+		},
+	}
+}
+
+/// Handle a PutObject request with the x-garage-if-block-exists header.
+///
+/// The body starts with a manifest of the blocks of the object, one per line,
+/// given by their BLAKE2 hash in hex and their size. Blocks must be cut by the
+/// client using at most the block size of the cluster.
+///
+/// With `check`, the body only contains the manifest, and the blocks that are
+/// not stored in the bucket are returned in a JSON response; nothing is written.
+///
+/// With `commit`, the lines of the blocks whose data is sent end with ` +`, and
+/// the manifest is followed by an empty line and by the data of these blocks,
+/// in the order of the manifest. All other blocks must already be stored in the
+/// bucket, otherwise the request fails with NoSuchBlock.
+///
+/// Only blocks referenced by objects of the same bucket are considered present,
+/// so that the content of other buckets cannot be probed.
+pub async fn handle_put_if_block_exists(
+	garage: Arc<Garage>,
+	req: Request<Body>,
+	bucket: &Bucket,
+	key: &str,
+	mode: IfBlockExistsMode,
+	content_sha256: Option<Hash>,
+) -> Result<Response<Body>, Error> {
+	let content_md5 = match req.headers().get("content-md5") {
+		Some(x) => Some(x.to_str()?.to_string()),
+		None => None,
+	};
+	let headers = get_headers(req.headers(), key, &bucket.object_defaults())?;
+
+	let mut body = HashingBody::new(req.into_body().map_err(Error::from));
+	let manifest = parse_manifest(&body.read_manifest().await?)?;
+
+	match mode {
+		IfBlockExistsMode::Check => {
+			body.read_to_end().await?;
+			body.ensure_checksum_matches(content_md5, content_sha256)?;
+
+			let mut seen = HashSet::new();
+			let hashes = manifest
+				.iter()
+				.map(|e| e.hash)
+				.filter(|h| seen.insert(*h))
+				.collect::<Vec<_>>();
+			let stored = blocks_in_bucket(&garage, bucket, &hashes).await?;
+			let resp = CheckResponse {
+				block_size: garage.config.block_size,
+				missing: hashes
+					.iter()
+					.filter(|h| !stored.contains_key(h))
+					.map(hex::encode)
+					.collect(),
+			};
+
+			Ok(Response::builder()
+				.status(StatusCode::OK)
+				.header(hyper::header::CONTENT_TYPE, "application/json")
+				.body(Body::from(
+					serde_json::to_vec_pretty(&resp)
+						.ok_or_internal_error("Could not serialize block check response")?,
+				))?)
+		}
+		IfBlockExistsMode::Commit => {
+			commit(
+				garage,
+				headers,
+				body,
+				bucket,
+				key,
+				manifest,
+				content_md5,
+				content_sha256,
+			)
+			.await
+		}
+	}
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn commit<S: Stream<Item = Result<Bytes, Error>> + Unpin>(
+	garage: Arc<Garage>,
+	headers: ObjectVersionHeaders,
+	mut body: HashingBody<S>,
+	bucket: &Bucket,
+	key: &str,
+	manifest: Vec<ManifestEntry>,
+	content_md5: Option<String>,
+	content_sha256: Option<Hash>,
+) -> Result<Response<Body>, Error> {
+	let quorum = bucket.quorum_policy();
+
+	if manifest.is_empty() {
+		return Err(Error::bad_request("Empty block manifest"));
+	}
+	if let Some(e) = manifest
+		.iter()
+		.find(|e| e.size == 0 || e.size > garage.config.block_size as u64)
+	{
+		return Err(Error::bad_request(format!(
+			"Invalid size for block {}: {}",
+			hex::encode(e.hash),
+			e.size
+		)));
+	}
+
+	// Check that the blocks that are not sent are stored in the bucket,
+	// with the size given in the manifest
+	let referenced = manifest
+		.iter()
+		.filter(|e| !e.with_data)
+		.map(|e| e.hash)
+		.collect::<HashSet<_>>()
+		.into_iter()
+		.collect::<Vec<_>>();
+	let stored = blocks_in_bucket(&garage, bucket, &referenced).await?;
+	for e in manifest.iter().filter(|e| !e.with_data) {
+		if stored.get(&e.hash) != Some(&e.size) {
+			return Err(Error::NoSuchBlock(hex::encode(e.hash)));
+		}
+	}
+
+	let total_size = manifest.iter().map(|e| e.size).sum::<u64>();
+	let quota = check_quotas(&garage, bucket, key, total_size).await?;
+
+	let version_uuid = gen_uuid();
+	let version_timestamp = now_msec();
+	let mut interrupted_cleanup = InterruptedCleanup::new(
+		garage.clone(),
+		bucket.id,
+		key.into(),
+		version_uuid,
+		version_timestamp,
+	);
+
+	let mut object_version = ObjectVersion {
+		uuid: version_uuid,
+		timestamp: version_timestamp,
+		state: ObjectVersionState::Uploading {
+			headers: headers.clone(),
+			multipart: false,
+			initiator: None,
+		},
+	};
+	let object = Object::new(bucket.id, key.into(), vec![object_version.clone()]);
+	garage
+		.object_table
+		.insert_with_quorum(&object, quorum)
+		.await?;
+
+	// Reference the blocks that are already stored, then store the others
+	let mut version = Version::new(
+		version_uuid,
+		VersionBacklink::Object {
+			bucket_id: bucket.id,
+			key: key.into(),
+		},
+		false,
+	);
+	let mut offsets = Vec::with_capacity(manifest.len());
+	let mut offset = 0;
+	for e in manifest.iter() {
+		offsets.push(offset);
+		if !e.with_data {
+			version.blocks.put(
+				VersionBlockKey {
+					part_number: 1,
+					offset,
+				},
+				VersionBlock {
+					hash: e.hash,
+					size: e.size,
+				},
+			);
+		}
+		offset += e.size;
+	}
+	let block_refs = version
+		.blocks
+		.items()
+		.iter()
+		.map(|(_, vb)| BlockRef {
+			block: vb.hash,
+			version: version_uuid,
+			deleted: false.into(),
+		})
+		.collect::<Vec<_>>();
+	futures::try_join!(
+		garage.version_table.insert_with_quorum(&version, quorum),
+		garage.block_ref_table.insert_many(&block_refs[..]),
+	)?;
+
+	for (e, offset) in manifest.iter().zip(offsets.iter()) {
+		if !e.with_data {
+			continue;
+		}
+		let block = body.read_exact(e.size as usize).await?;
+		if async_blake2sum(block.clone()).await != e.hash {
+			return Err(Error::bad_request(format!(
+				"Data of block {} does not match its hash",
+				hex::encode(e.hash)
+			)));
+		}
+		put_block_and_meta(&garage, &version, 1, *offset, e.hash, block, quorum).await?;
+	}
+	body.read_to_end().await?;
+	body.ensure_checksum_matches(content_md5, content_sha256)?;
+
+	let hashes = manifest.iter().map(|e| e.hash).collect::<Vec<_>>();
+	let etag = blocks_etag(&hashes);
+	object_version.state = ObjectVersionState::Complete(ObjectVersionData::FirstBlock(
+		ObjectVersionMeta {
+			headers,
+			size: total_size,
+			etag: etag.clone(),
+		},
+		manifest[0].hash,
+	));
+	let object = Object::new(bucket.id, key.into(), vec![object_version]);
+	garage
+		.object_table
+		.insert_with_quorum(&object, quorum)
+		.await?;
+
+	interrupted_cleanup.cancel();
+
+	let mut resp = put_response(version_uuid, etag);
+	quota.add_headers(resp.headers_mut());
+	Ok(resp)
+}
+
+fn parse_manifest(manifest: &[u8]) -> Result<Vec<ManifestEntry>, Error> {
+	let manifest = std::str::from_utf8(manifest)?;
+	manifest
+		.lines()
+		.map(str::trim)
+		.filter(|l| !l.is_empty())
+		.map(|line| {
+			let mut fields = line.split_whitespace();
+			let hash = fields.next().and_then(|h| hex::decode(h).ok());
+			let size = fields.next().and_then(|s| s.parse::<u64>().ok());
+			let with_data = match fields.next() {
+				None => Some(false),
+				Some("+") => Some(true),
+				Some(_) => None,
+			};
+			match (hash, size, with_data, fields.next()) {
+				(Some(hash), Some(size), Some(with_data), None) if hash.len() == 32 => {
+					Ok(ManifestEntry {
+						hash: Hash::try_from(&hash[..]).unwrap(),
+						size,
+						with_data,
+					})
+				}
+				_ => Err(Error::bad_request(format!(
+					"Invalid block manifest line: {}",
+					line
+				))),
+			}
+		})
+		.collect()
+}
+
+/// Find which of the given blocks are stored in a bucket, with their size.
+/// A block is considered stored if it is referenced by a version of an object
+/// of the bucket that is not deleted.
+async fn blocks_in_bucket(
+	garage: &Arc<Garage>,
+	bucket: &Bucket,
+	hashes: &[Hash],
+) -> Result<HashMap<Hash, u64>, Error> {
+	let sizes = stream::iter(hashes.to_vec())
+		.map(|hash| {
+			let garage = garage.clone();
+			let bucket_id = bucket.id;
+			async move {
+				let size = block_size_in_bucket(&garage, bucket_id, &hash).await?;
+				Ok::<_, Error>((hash, size))
+			}
+		})
+		.buffer_unordered(CHECK_CONCURRENCY)
+		.try_collect::<Vec<_>>()
+		.await?;
+	Ok(sizes
+		.into_iter()
+		.filter_map(|(hash, size)| Some((hash, size?)))
+		.collect())
+}
+
+async fn block_size_in_bucket(
+	garage: &Arc<Garage>,
+	bucket_id: Uuid,
+	hash: &Hash,
+) -> Result<Option<u64>, Error> {
+	let mut start = None;
+	loop {
+		let refs = garage
+			.block_ref_table
+			.get_range(
+				hash,
+				start,
+				Some(DeletedFilter::NotDeleted),
+				BLOCK_REF_PAGE_SIZE,
+				EnumerationOrder::Forward,
+			)
+			.await?;
+		for block_ref in refs.iter() {
+			if Some(block_ref.version) == start {
+				continue;
+			}
+			let version = match garage
+				.version_table
+				.get(&block_ref.version, &EmptyKey)
+				.await?
+			{
+				Some(v) if !v.deleted.get() => v,
+				_ => continue,
+			};
+			let version_bucket_id = match &version.backlink {
+				VersionBacklink::Object { bucket_id, .. } => Some(*bucket_id),
+				VersionBacklink::MultipartUpload { upload_id } => garage
+					.mpu_table
+					.get(upload_id, &EmptyKey)
+					.await?
+					.map(|mpu| mpu.bucket_id),
+			};
+			if version_bucket_id != Some(bucket_id) {
+				continue;
+			}
+			let size = version
+				.blocks
+				.items()
+				.iter()
+				.find(|(_, vb)| vb.hash == *hash)
+				.map(|(_, vb)| vb.size);
+			if size.is_some() {
+				return Ok(size);
+			}
+		}
+		if refs.len() < BLOCK_REF_PAGE_SIZE {
+			return Ok(None);
+		}
+		start = refs.last().map(|r| r.version);
+	}
+}
+
+/// Request body whose MD5 and SHA256 are computed while it is read
+struct HashingBody<S> {
+	stream: S,
+	buf: BytesBuf,
+	md5: Md5,
+	sha256: Sha256,
+}
+
+impl<S: Stream<Item = Result<Bytes, Error>> + Unpin> HashingBody<S> {
+	fn new(stream: S) -> Self {
+		Self {
+			stream,
+			buf: BytesBuf::new(),
+			md5: Md5::new(),
+			sha256: Sha256::new(),
+		}
+	}
+
+	async fn next_chunk(&mut self) -> Result<Option<Bytes>, Error> {
+		match self.stream.next().await {
+			Some(chunk) => {
+				let chunk = chunk?;
+				self.md5.update(&chunk[..]);
+				self.sha256.update(&chunk[..]);
+				Ok(Some(chunk))
+			}
+			None => Ok(None),
+		}
+	}
+
+	/// Read the manifest, up to the first empty line or to the end of the body
+	async fn read_manifest(&mut self) -> Result<Vec<u8>, Error> {
+		let mut manifest = Vec::new();
+		loop {
+			if let Some(pos) = manifest.windows(2).position(|w| w == b"\n\n") {
+				self.buf.extend(Bytes::from(manifest.split_off(pos + 2)));
+				manifest.truncate(pos);
+				return Ok(manifest);
+			}
+			if manifest.len() > MAX_MANIFEST_SIZE {
+				return Err(Error::bad_request("Block manifest is too large"));
+			}
+			match self.next_chunk().await? {
+				Some(chunk) => manifest.extend_from_slice(&chunk[..]),
+				None => return Ok(manifest),
+			}
+		}
+	}
+
+	async fn read_exact(&mut self, len: usize) -> Result<Bytes, Error> {
+		while self.buf.len() < len {
+			match self.next_chunk().await? {
+				Some(chunk) => self.buf.extend(chunk),
+				None => return Err(Error::bad_request("Missing block data in request body")),
+			}
+		}
+		Ok(self.buf.take_exact(len).unwrap())
+	}
+
+	/// Check that the body has no data left
+	async fn read_to_end(&mut self) -> Result<(), Error> {
+		while self.buf.is_empty() {
+			match self.next_chunk().await? {
+				Some(chunk) => self.buf.extend(chunk),
+				None => return Ok(()),
+			}
+		}
+		Err(Error::bad_request(
+			"Unexpected data at the end of request body",
+		))
+	}
+
+	fn ensure_checksum_matches(
+		self,
+		content_md5: Option<String>,
+		content_sha256: Option<Hash>,
+	) -> Result<(), Error> {
+		let data_sha256sum = Hash::try_from(&self.sha256.finalize()[..]).unwrap();
+		ensure_checksum_matches(
+			self.md5.finalize().as_slice(),
+			data_sha256sum,
+			content_md5.as_deref(),
+			content_sha256,
+		)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_parse_manifest() {
+		let h1 = blake2sum(b"block 1");
+		let h2 = blake2sum(b"block 2");
+		let manifest = format!("{} 7\n\n{} 1024 +\n", hex::encode(h1), hex::encode(h2));
+		assert_eq!(
+			parse_manifest(manifest.as_bytes()).unwrap(),
+			vec![
+				ManifestEntry {
+					hash: h1,
+					size: 7,
+					with_data: false,
+				},
+				ManifestEntry {
+					hash: h2,
+					size: 1024,
+					with_data: true,
+				},
+			]
+		);
+
+		assert!(parse_manifest(b"abcd 7").is_err());
+		assert!(parse_manifest(format!("{} x", hex::encode(h1)).as_bytes()).is_err());
+		assert!(parse_manifest(format!("{} 7 -", hex::encode(h1)).as_bytes()).is_err());
+	}
+}
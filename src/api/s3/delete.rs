@@ -5,8 +5,10 @@ use hyper::{Body, Request, Response, StatusCode};
 use garage_util::data::*;
 use garage_util::time::*;
 
+use garage_model::bucket_table::Bucket;
 use garage_model::garage::Garage;
 use garage_model::s3::object_table::*;
+use garage_model::s3::trash::move_to_trash;
 
 use crate::s3::error::*;
 use crate::s3::xml as s3_xml;
@@ -14,9 +16,10 @@ use crate::signature::verify_signed_content;
 
 async fn handle_delete_internal(
 	garage: &Garage,
-	bucket_id: Uuid,
+	bucket: &Bucket,
 	key: &str,
 ) -> Result<(Uuid, Uuid), Error> {
+	let bucket_id = bucket.id;
 	let object = garage
 		.object_table
 		.get(&bucket_id, &key.to_string())
@@ -42,6 +45,10 @@ async fn handle_delete_internal(
 
 	let deleted_version = version_to_delete.ok_or(Error::NoSuchKey)?;
 
+	if bucket.trash_retention_days().is_some() {
+		move_to_trash(garage, bucket_id, &object).await?;
+	}
+
 	let version_uuid = gen_uuid();
 
 	let object = Object::new(
@@ -61,10 +68,10 @@ async fn handle_delete_internal(
 
 pub async fn handle_delete(
 	garage: Arc<Garage>,
-	bucket_id: Uuid,
+	bucket: &Bucket,
 	key: &str,
 ) -> Result<Response<Body>, Error> {
-	match handle_delete_internal(&garage, bucket_id, key).await {
+	match handle_delete_internal(&garage, bucket, key).await {
 		Ok(_) | Err(Error::NoSuchKey) => Ok(Response::builder()
 			.status(StatusCode::NO_CONTENT)
 			.body(Body::from(vec![]))
@@ -75,7 +82,7 @@ pub async fn handle_delete(
 
 pub async fn handle_delete_objects(
 	garage: Arc<Garage>,
-	bucket_id: Uuid,
+	bucket: &Bucket,
 	req: Request<Body>,
 	content_sha256: Option<Hash>,
 ) -> Result<Response<Body>, Error> {
@@ -92,7 +99,7 @@ pub async fn handle_delete_objects(
 	let mut ret_errors = Vec::new();
 
 	for obj in cmd.objects.iter() {
-		match handle_delete_internal(&garage, bucket_id, &obj.key).await {
+		match handle_delete_internal(&garage, bucket, &obj.key).await {
 			Ok((deleted_version, delete_marker_version)) => {
 				if cmd.quiet {
 					continue;
@@ -1,5 +1,6 @@
 use std::convert::TryInto;
 
+use bytesize::ByteSize;
 use err_derive::Error;
 use hyper::header::HeaderValue;
 use hyper::{Body, HeaderMap, StatusCode};
@@ -24,6 +25,22 @@ pub enum Error {
 	#[error(display = "Authorization header malformed, unexpected scope: {}", _0)]
 	AuthorizationHeaderMalformed(String),
 
+	/// The access key given in the request does not exist
+	#[error(display = "Forbidden: No such key: {}", _0)]
+	InvalidAccessKeyId(String),
+
+	/// The access key given in the request has expired
+	#[error(display = "Forbidden: The access key has expired: {}", _0)]
+	ExpiredToken(String),
+
+	/// The signature of the request does not match the one computed by Garage
+	#[error(display = "Forbidden: {}", _0)]
+	SignatureDoesNotMatch(String),
+
+	/// The date of the request is too far from the current time
+	#[error(display = "Forbidden: {}", _0)]
+	RequestTimeTooSkewed(String),
+
 	/// The object requested don't exists
 	#[error(display = "Key not found")]
 	NoSuchKey,
@@ -44,12 +61,38 @@ pub enum Error {
 	#[error(display = "Parts given to CompleteMultipartUpload were not in ascending order")]
 	InvalidPartOrder,
 
-	/// In CompleteMultipartUpload: not enough data
-	/// (here we are more lenient than AWS S3)
+	/// In AppendObject: the position given is not the current size of the object
+	#[error(display = "Position is not equal to the length of the object ({})", _0)]
+	PositionNotEqualToLength(u64),
+
+	/// In a resumable PutObject: the offset given is not the amount of data
+	/// already stored for the upload
+	#[error(display = "Offset is not the size of the uploaded data ({})", _0)]
+	InvalidUploadOffset(u64),
+
+	/// In a deduplicated PutObject: a block whose data is not sent is not
+	/// stored in the bucket
+	#[error(display = "Block {} is not stored in this bucket", _0)]
+	NoSuchBlock(String),
+
+	/// Writing an object would exceed a quota of the bucket
+	#[error(display = "{}", _0)]
+	QuotaExceeded(QuotaExceeded),
+
+	/// In CompleteMultipartUpload: no parts given, or parts smaller than
+	/// the minimum part size
 	#[error(display = "Proposed upload is smaller than the minimum allowed object size")]
 	EntityTooSmall,
 
 	// Category: bad request
+	/// The Content-MD5 given does not match the MD5 of the data received
+	#[error(display = "The Content-MD5 you specified did not match what we received")]
+	BadDigest,
+
+	/// The x-amz-content-sha256 given does not match the SHA256 of the data received
+	#[error(display = "The x-amz-content-sha256 you specified did not match what we received")]
+	ContentSha256Mismatch,
+
 	/// The request contained an invalid UTF-8 sequence in its path or in other parameters
 	#[error(display = "Invalid UTF-8: {}", _0)]
 	InvalidUtf8Str(#[error(source)] std::str::Utf8Error),
@@ -58,6 +101,10 @@ pub enum Error {
 	#[error(display = "Invalid UTF-8: {}", _0)]
 	InvalidUtf8String(#[error(source)] std::string::FromUtf8Error),
 
+	/// The region given in CreateBucket is not one where buckets can be created
+	#[error(display = "Invalid location constraint: {}", _0)]
+	InvalidLocationConstraint(String),
+
 	/// The client sent invalid XML data
 	#[error(display = "Invalid XML: {}", _0)]
 	InvalidXml(String),
@@ -75,6 +122,53 @@ pub enum Error {
 	NotImplemented(String),
 }
 
+/// A bucket quota that is exceeded by a write
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quota {
+	Size,
+	Objects,
+}
+
+impl Quota {
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			Quota::Size => "size",
+			Quota::Objects => "objects",
+		}
+	}
+}
+
+/// Details of an exceeded bucket quota, given in the body of QuotaExceeded errors
+#[derive(Debug, Clone, Copy)]
+pub struct QuotaExceeded {
+	pub quota: Quota,
+	/// Current usage of the bucket: total size of objects in bytes,
+	/// or number of objects
+	pub current: u64,
+	pub limit: u64,
+	/// Usage that the write would add
+	pub requested: u64,
+}
+
+impl std::fmt::Display for QuotaExceeded {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self.quota {
+			Quota::Size => write!(
+				f,
+				"Bucket size quota exceeded: the bucket already contains {} out of a maximum of {}, and this object would add {}",
+				ByteSize::b(self.current).to_string_as(true),
+				ByteSize::b(self.limit).to_string_as(true),
+				ByteSize::b(self.requested).to_string_as(true),
+			),
+			Quota::Objects => write!(
+				f,
+				"Object quota exceeded: the bucket already contains {} objects out of a maximum of {}",
+				self.current, self.limit
+			),
+		}
+	}
+}
+
 impl<T> From<T> for Error
 where
 	CommonError: From<T>,
@@ -117,6 +211,10 @@ impl From<SignatureError> for Error {
 			SignatureError::AuthorizationHeaderMalformed(c) => {
 				Self::AuthorizationHeaderMalformed(c)
 			}
+			SignatureError::InvalidAccessKeyId(k) => Self::InvalidAccessKeyId(k),
+			SignatureError::ExpiredAccessKey(k) => Self::ExpiredToken(k),
+			SignatureError::SignatureDoesNotMatch(m) => Self::SignatureDoesNotMatch(m),
+			SignatureError::RequestTimeTooSkewed(m) => Self::RequestTimeTooSkewed(m),
 			SignatureError::InvalidUtf8Str(i) => Self::InvalidUtf8Str(i),
 			SignatureError::InvalidHeader(h) => Self::InvalidHeader(h),
 		}
@@ -139,13 +237,23 @@ impl Error {
 			Error::InvalidPart => "InvalidPart",
 			Error::InvalidPartOrder => "InvalidPartOrder",
 			Error::EntityTooSmall => "EntityTooSmall",
+			Error::PositionNotEqualToLength(_) => "PositionNotEqualToLength",
+			Error::InvalidUploadOffset(_) => "InvalidUploadOffset",
+			Error::NoSuchBlock(_) => "NoSuchBlock",
+			Error::QuotaExceeded(_) => "QuotaExceeded",
 			Error::AuthorizationHeaderMalformed(_) => "AuthorizationHeaderMalformed",
+			Error::InvalidAccessKeyId(_) => "InvalidAccessKeyId",
+			Error::ExpiredToken(_) => "ExpiredToken",
+			Error::SignatureDoesNotMatch(_) => "SignatureDoesNotMatch",
+			Error::RequestTimeTooSkewed(_) => "RequestTimeTooSkewed",
+			Error::BadDigest => "BadDigest",
+			Error::ContentSha256Mismatch => "XAmzContentSHA256Mismatch",
 			Error::NotImplemented(_) => "NotImplemented",
+			Error::InvalidLocationConstraint(_) => "InvalidLocationConstraint",
 			Error::InvalidXml(_) => "MalformedXML",
 			Error::InvalidRange(_) => "InvalidRange",
-			Error::InvalidUtf8Str(_) | Error::InvalidUtf8String(_) | Error::InvalidHeader(_) => {
-				"InvalidRequest"
-			}
+			Error::InvalidHeader(_) => "InvalidArgument",
+			Error::InvalidUtf8Str(_) | Error::InvalidUtf8String(_) => "InvalidRequest",
 		}
 	}
 }
@@ -157,12 +265,23 @@ impl ApiError for Error {
 			Error::Common(c) => c.http_status_code(),
 			Error::NoSuchKey | Error::NoSuchUpload => StatusCode::NOT_FOUND,
 			Error::PreconditionFailed => StatusCode::PRECONDITION_FAILED,
+			Error::PositionNotEqualToLength(_)
+			| Error::InvalidUploadOffset(_)
+			| Error::NoSuchBlock(_) => StatusCode::CONFLICT,
+			Error::QuotaExceeded(_)
+			| Error::InvalidAccessKeyId(_)
+			| Error::ExpiredToken(_)
+			| Error::SignatureDoesNotMatch(_)
+			| Error::RequestTimeTooSkewed(_) => StatusCode::FORBIDDEN,
 			Error::InvalidRange(_) => StatusCode::RANGE_NOT_SATISFIABLE,
 			Error::NotImplemented(_) => StatusCode::NOT_IMPLEMENTED,
 			Error::AuthorizationHeaderMalformed(_)
 			| Error::InvalidPart
 			| Error::InvalidPartOrder
 			| Error::EntityTooSmall
+			| Error::BadDigest
+			| Error::ContentSha256Mismatch
+			| Error::InvalidLocationConstraint(_)
 			| Error::InvalidXml(_)
 			| Error::InvalidUtf8Str(_)
 			| Error::InvalidUtf8String(_)
@@ -175,7 +294,6 @@ impl ApiError for Error {
 
 		header_map.append(header::CONTENT_TYPE, "application/xml".parse().unwrap());
 
-		#[allow(clippy::single_match)]
 		match self {
 			Error::InvalidRange((_, len)) => {
 				header_map.append(
@@ -185,26 +303,89 @@ impl ApiError for Error {
 						.expect("header value only contain ascii"),
 				);
 			}
+			Error::PositionNotEqualToLength(len) => {
+				header_map.append(
+					"x-garage-next-append-position",
+					len.to_string()
+						.try_into()
+						.expect("header value only contain ascii"),
+				);
+			}
+			Error::InvalidUploadOffset(offset) => {
+				header_map.append(
+					"x-garage-upload-offset",
+					offset
+						.to_string()
+						.try_into()
+						.expect("header value only contain ascii"),
+				);
+			}
 			_ => (),
 		}
 	}
 
-	fn http_body(&self, garage_region: &str, path: &str) -> Body {
+	fn http_body(&self, garage_region: &str, path: &str, request_id: &str) -> Body {
 		let error = s3_xml::Error {
 			code: s3_xml::Value(self.aws_code().to_string()),
 			message: s3_xml::Value(format!("{}", self)),
 			resource: Some(s3_xml::Value(path.to_string())),
 			region: Some(s3_xml::Value(garage_region.to_string())),
+			request_id: Some(s3_xml::Value(request_id.to_string())),
+			quota: None,
+			current_usage: None,
+			limit: None,
+			requested: None,
+		};
+		let error = match self {
+			Error::QuotaExceeded(q) => s3_xml::Error {
+				quota: Some(s3_xml::Value(q.quota.as_str().to_string())),
+				current_usage: Some(s3_xml::IntValue(q.current as i64)),
+				limit: Some(s3_xml::IntValue(q.limit as i64)),
+				requested: Some(s3_xml::IntValue(q.requested as i64)),
+				..error
+			},
+			_ => error,
 		};
 		Body::from(s3_xml::to_xml_with_header(&error).unwrap_or_else(|_| {
-			r#"
-<?xml version="1.0" encoding="UTF-8"?>
+			r#"<?xml version="1.0" encoding="UTF-8"?>
 <Error>
 	<Code>InternalError</Code>
 	<Message>XML encoding of error failed</Message>
 </Error>
-			"#
+"#
 			.into()
 		}))
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_signature_error_codes() {
+		let err = Error::from(SignatureError::SignatureDoesNotMatch(
+			"Invalid signature".into(),
+		));
+		assert_eq!(err.aws_code(), "SignatureDoesNotMatch");
+		assert_eq!(err.http_status_code(), StatusCode::FORBIDDEN);
+
+		let err = Error::from(SignatureError::InvalidAccessKeyId("GK123".into()));
+		assert_eq!(err.aws_code(), "InvalidAccessKeyId");
+		assert_eq!(err.http_status_code(), StatusCode::FORBIDDEN);
+
+		let err = Error::from(SignatureError::ExpiredAccessKey("GK123".into()));
+		assert_eq!(err.aws_code(), "ExpiredToken");
+		assert_eq!(err.http_status_code(), StatusCode::FORBIDDEN);
+	}
+
+	#[tokio::test]
+	async fn test_error_body() {
+		let body = Error::BadDigest.http_body("garage", "/bucket/key", "0123");
+		let body = hyper::body::to_bytes(body).await.unwrap();
+		let body = std::str::from_utf8(&body).unwrap();
+		assert!(body.starts_with("<?xml"));
+		assert!(body.contains("<Code>BadDigest</Code>"));
+		assert!(body.contains("<RequestId>0123</RequestId>"));
+	}
+}
@@ -1,29 +1,42 @@
 //! Function related to GET and HEAD requests
+use std::collections::VecDeque;
 use std::sync::Arc;
 use std::time::{Duration, UNIX_EPOCH};
 
+use bytes::Bytes;
 use futures::future;
-use futures::stream::{self, StreamExt};
+use futures::stream::{self, Stream, StreamExt};
 use http::header::{
-	ACCEPT_RANGES, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE, ETAG, IF_MODIFIED_SINCE,
-	IF_NONE_MATCH, LAST_MODIFIED, RANGE,
+	ACCEPT_RANGES, CACHE_CONTROL, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE, ETAG, EXPIRES,
+	IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED, RANGE,
 };
 use hyper::{Body, Request, Response, StatusCode};
+use md5::{Digest as Md5Digest, Md5};
 use tokio::sync::mpsc;
 
 use garage_rpc::rpc_helper::{netapp::stream::ByteStream, OrderTag};
 use garage_table::EmptyKey;
-use garage_util::data::*;
 use garage_util::error::OkOrMessage;
 
+use garage_model::bucket_table::Bucket;
 use garage_model::garage::Garage;
 use garage_model::s3::object_table::*;
 use garage_model::s3::version_table::*;
 
 use crate::s3::error::*;
+use crate::s3::put::blocks_etag;
 
 const X_AMZ_MP_PARTS_COUNT: &str = "x-amz-mp-parts-count";
 
+/// Garage extension: `x-garage-verify-etag: true` on the GET of a whole
+/// object makes the gateway compute the ETag of the data it sends, and abort
+/// the response before its last bytes if it does not match the stored ETag
+pub const X_GARAGE_VERIFY_ETAG: &str = "x-garage-verify-etag";
+
+/// Number of times a GET request reads the object again when the version it
+/// found was replaced by a concurrent overwrite before its blocks were listed
+const MAX_GET_ATTEMPTS: usize = 3;
+
 fn object_headers(
 	version: &ObjectVersion,
 	version_meta: &ObjectVersionMeta,
@@ -61,44 +74,62 @@ fn try_answer_cached(
 	// for instance (in case of very fast updates)
 	let cached = if let Some(none_match) = req.headers().get(IF_NONE_MATCH) {
 		let none_match = none_match.to_str().ok()?;
-		let expected = format!("\"{}\"", version_meta.etag);
-		let found = none_match
-			.split(',')
-			.map(str::trim)
-			.any(|etag| etag == expected || etag == "\"*\"");
-		found
+		etag_list_matches(none_match, &version_meta.etag)
 	} else if let Some(modified_since) = req.headers().get(IF_MODIFIED_SINCE) {
 		let modified_since = modified_since.to_str().ok()?;
 		let client_date = httpdate::parse_http_date(modified_since).ok()?;
-		let server_date = UNIX_EPOCH + Duration::from_millis(version.timestamp);
+		// Last-Modified is sent with a precision of one second,
+		// so compare dates with that same precision
+		let server_date = UNIX_EPOCH + Duration::from_secs(version.timestamp / 1000);
 		client_date >= server_date
 	} else {
 		false
 	};
 
 	if cached {
-		Some(
-			Response::builder()
-				.status(StatusCode::NOT_MODIFIED)
-				.body(Body::empty())
-				.unwrap(),
-		)
+		// A 304 response must contain the headers that would have been sent
+		// in a 200 response and that are used for caching (RFC 7232 section 4.1)
+		let date = UNIX_EPOCH + Duration::from_millis(version.timestamp);
+		let mut resp = Response::builder()
+			.status(StatusCode::NOT_MODIFIED)
+			.header(LAST_MODIFIED, httpdate::fmt_http_date(date))
+			.header(ACCEPT_RANGES, "bytes");
+		if !version_meta.etag.is_empty() {
+			resp = resp.header(ETAG, format!("\"{}\"", version_meta.etag));
+		}
+		for (k, v) in version_meta.headers.other.iter() {
+			if k.eq_ignore_ascii_case(CACHE_CONTROL.as_str())
+				|| k.eq_ignore_ascii_case(EXPIRES.as_str())
+			{
+				resp = resp.header(k, v.to_string());
+			}
+		}
+		Some(resp.body(Body::empty()).unwrap())
 	} else {
 		None
 	}
 }
 
+/// Checks whether an etag is matched by the value of an If-None-Match header,
+/// using the weak comparison function (RFC 7232 section 2.3.2)
+fn etag_list_matches(etag_list: &str, etag: &str) -> bool {
+	etag_list
+		.split(',')
+		.map(str::trim)
+		.any(|x| x == "*" || x == "\"*\"" || x.trim_start_matches("W/").trim_matches('"') == etag)
+}
+
 /// Handle HEAD request
 pub async fn handle_head(
 	garage: Arc<Garage>,
 	req: &Request<Body>,
-	bucket_id: Uuid,
+	bucket: &Bucket,
 	key: &str,
 	part_number: Option<u64>,
 ) -> Result<Response<Body>, Error> {
 	let object = garage
 		.object_table
-		.get(&bucket_id, &key.to_string())
+		.get_with_quorum(&bucket.id, &key.to_string(), bucket.quorum_policy())
 		.await?
 		.ok_or(Error::NoSuchKey)?;
 
@@ -130,20 +161,13 @@ pub async fn handle_head(
 				if pn != 1 {
 					return Err(Error::InvalidPart);
 				}
-				Ok(object_headers(object_version, version_meta)
-					.header(CONTENT_LENGTH, format!("{}", bytes.len()))
-					.header(
-						CONTENT_RANGE,
-						format!("bytes 0-{}/{}", bytes.len() - 1, bytes.len()),
-					)
-					.header(X_AMZ_MP_PARTS_COUNT, "1")
-					.status(StatusCode::PARTIAL_CONTENT)
-					.body(Body::empty())?)
+				Ok(
+					inline_part_headers(object_headers(object_version, version_meta), bytes.len())
+						.body(Body::empty())?,
+				)
 			}
 			ObjectVersionData::FirstBlock(_, _) => {
-				let version = garage
-					.version_table
-					.get(&object_version.uuid, &EmptyKey)
+				let version = get_version_blocks(&garage, object_version)
 					.await?
 					.ok_or(Error::NoSuchKey)?;
 
@@ -167,6 +191,18 @@ pub async fn handle_head(
 			}
 			_ => unreachable!(),
 		}
+	} else if let Some(range) = parse_range_header(req, version_meta.size)? {
+		// Answer with the headers that a GET request with the same range
+		// would get
+		let end = range.start + range.length;
+		Ok(object_headers(object_version, version_meta)
+			.header(CONTENT_LENGTH, format!("{}", range.length))
+			.header(
+				CONTENT_RANGE,
+				format!("bytes {}-{}/{}", range.start, end - 1, version_meta.size),
+			)
+			.status(StatusCode::PARTIAL_CONTENT)
+			.body(Body::empty())?)
 	} else {
 		Ok(object_headers(object_version, version_meta)
 			.header(CONTENT_LENGTH, format!("{}", version_meta.size))
@@ -179,13 +215,35 @@ pub async fn handle_head(
 pub async fn handle_get(
 	garage: Arc<Garage>,
 	req: &Request<Body>,
-	bucket_id: Uuid,
+	bucket: &Bucket,
 	key: &str,
 	part_number: Option<u64>,
 ) -> Result<Response<Body>, Error> {
+	for _ in 0..MAX_GET_ATTEMPTS {
+		if let Some(resp) = try_handle_get(garage.clone(), req, bucket, key, part_number).await? {
+			return Ok(resp);
+		}
+	}
+	Err(Error::internal_error(
+		"Object was overwritten several times while it was being read, please retry",
+	))
+}
+
+/// Answer a GET request with the latest version of the object. The list of
+/// blocks of this version is read before the response is started, so that
+/// all blocks sent belong to it even if a newer version is written in the
+/// meantime. Returns None if the version was deleted, i.e. replaced by a
+/// newer one, before its blocks could be listed.
+async fn try_handle_get(
+	garage: Arc<Garage>,
+	req: &Request<Body>,
+	bucket: &Bucket,
+	key: &str,
+	part_number: Option<u64>,
+) -> Result<Option<Response<Body>>, Error> {
 	let object = garage
 		.object_table
-		.get(&bucket_id, &key.to_string())
+		.get_with_quorum(&bucket.id, &key.to_string(), bucket.quorum_policy())
 		.await?
 		.ok_or(Error::NoSuchKey)?;
 
@@ -207,17 +265,50 @@ pub async fn handle_get(
 	};
 
 	if let Some(cached) = try_answer_cached(last_v, last_v_meta, req) {
-		return Ok(cached);
+		return Ok(Some(cached));
 	}
 
-	match (part_number, parse_range_header(req, last_v_meta.size)?) {
-		(Some(_), Some(_)) => {
-			return Err(Error::bad_request(
-				"Cannot specify both partNumber and Range header",
-			));
-		}
-		(Some(pn), None) => {
-			return handle_get_part(garage, last_v, last_v_data, last_v_meta, pn).await;
+	let range = parse_range_header(req, last_v_meta.size)?;
+	if part_number.is_some() && range.is_some() {
+		return Err(Error::bad_request(
+			"Cannot specify both partNumber and Range header",
+		));
+	}
+
+	let verify_etag = match req.headers().get(X_GARAGE_VERIFY_ETAG) {
+		None => false,
+		Some(v) => match v.to_str()? {
+			"true" => true,
+			"false" => false,
+			_ => return Err(Error::bad_request("Invalid x-garage-verify-etag header")),
+		},
+	};
+	if verify_etag && (part_number.is_some() || range.is_some()) {
+		return Err(Error::bad_request(
+			"x-garage-verify-etag can only be used to get a whole object",
+		));
+	}
+
+	let version = match last_v_data {
+		ObjectVersionData::FirstBlock(_, _) => match get_version_blocks(&garage, last_v).await? {
+			Some(v) => Some(v),
+			None => return Ok(None),
+		},
+		_ => None,
+	};
+
+	match (part_number, range) {
+		(Some(pn), _) => {
+			return handle_get_part(
+				garage,
+				last_v,
+				last_v_data,
+				last_v_meta,
+				version.as_ref(),
+				pn,
+			)
+			.await
+			.map(Some);
 		}
 		(None, Some(range)) => {
 			return handle_get_range(
@@ -225,38 +316,69 @@ pub async fn handle_get(
 				last_v,
 				last_v_data,
 				last_v_meta,
+				version.as_ref(),
 				range.start,
 				range.start + range.length,
 			)
-			.await;
+			.await
+			.map(Some);
 		}
 		(None, None) => (),
 	}
 
-	let resp_builder = object_headers(last_v, last_v_meta)
+	let verify_etag = verify_etag && etag_is_verifiable(last_v_meta, version.as_ref());
+
+	let mut resp_builder = object_headers(last_v, last_v_meta)
 		.header(CONTENT_LENGTH, format!("{}", last_v_meta.size))
 		.status(StatusCode::OK);
+	if verify_etag {
+		resp_builder = resp_builder.header(X_GARAGE_VERIFY_ETAG, "true");
+	}
 
-	match &last_v_data {
-		ObjectVersionData::DeleteMarker => unreachable!(),
-		ObjectVersionData::Inline(_, bytes) => {
+	match (last_v_data, version) {
+		(ObjectVersionData::Inline(_, bytes), _) => {
+			if verify_etag {
+				let mut verifier =
+					EtagVerifier::new(&last_v_meta.etag, vec![(1, bytes.len() as u64)]);
+				verifier.update(bytes);
+				if !verifier.matches() {
+					error!(
+						"ETag mismatch for object {:?} in bucket {:?}",
+						key, bucket.id
+					);
+					return Err(Error::internal_error(
+						"The data of the object does not match its ETag",
+					));
+				}
+			}
 			let body: Body = Body::from(bytes.to_vec());
-			Ok(resp_builder.body(body)?)
+			Ok(Some(resp_builder.body(body)?))
 		}
-		ObjectVersionData::FirstBlock(_, first_block_hash) => {
+		(ObjectVersionData::FirstBlock(_, first_block_hash), Some(version)) => {
 			let (tx, rx) = mpsc::channel(2);
 
 			let order_stream = OrderTag::stream();
 			let first_block_hash = *first_block_hash;
-			let version_uuid = last_v.uuid;
+
+			let verifier = match verify_etag {
+				true => Some(EtagVerifier::new(&last_v_meta.etag, part_sizes(&version))),
+				false => None,
+			};
+
+			// Keep the blocks from being deleted until they have all been
+			// fetched, even if the object is deleted in the meantime
+			let hold = garage.block_manager.hold_blocks(
+				version
+					.blocks
+					.items()
+					.iter()
+					.map(|(_, vb)| vb.hash)
+					.collect(),
+			);
 
 			tokio::spawn(async move {
+				let _hold = hold;
 				match async {
-					let garage2 = garage.clone();
-					let version_fut = tokio::spawn(async move {
-						garage2.version_table.get(&version_uuid, &EmptyKey).await
-					});
-
 					let stream_block_0 = garage
 						.block_manager
 						.rpc_get_block_streaming(&first_block_hash, Some(order_stream.order(0)))
@@ -265,7 +387,6 @@ pub async fn handle_get(
 						.await
 						.ok_or_message("channel closed")?;
 
-					let version = version_fut.await.unwrap()?.ok_or(Error::NoSuchKey)?;
 					for (i, (_, vb)) in version.blocks.items().iter().enumerate().skip(1) {
 						let stream_block_i = garage
 							.block_manager
@@ -295,9 +416,16 @@ pub async fn handle_get(
 
 			let body_stream = tokio_stream::wrappers::ReceiverStream::new(rx).flatten();
 
-			let body = hyper::body::Body::wrap_stream(body_stream);
-			Ok(resp_builder.body(body)?)
+			let body = match verifier {
+				Some(verifier) => {
+					let what = format!("object {:?} in bucket {:?}", key, bucket.id);
+					hyper::body::Body::wrap_stream(verify_etag_stream(body_stream, verifier, what))
+				}
+				None => hyper::body::Body::wrap_stream(body_stream),
+			};
+			Ok(Some(resp_builder.body(body)?))
 		}
+		_ => unreachable!(),
 	}
 }
 
@@ -306,6 +434,7 @@ async fn handle_get_range(
 	version: &ObjectVersion,
 	version_data: &ObjectVersionData,
 	version_meta: &ObjectVersionMeta,
+	blocks: Option<&Version>,
 	begin: u64,
 	end: u64,
 ) -> Result<Response<Body>, Error> {
@@ -317,28 +446,16 @@ async fn handle_get_range(
 		)
 		.status(StatusCode::PARTIAL_CONTENT);
 
-	match &version_data {
-		ObjectVersionData::DeleteMarker => unreachable!(),
-		ObjectVersionData::Inline(_meta, bytes) => {
-			if end as usize <= bytes.len() {
-				let body: Body = Body::from(bytes[begin as usize..end as usize].to_vec());
-				Ok(resp_builder.body(body)?)
-			} else {
-				Err(Error::internal_error(
-					"Requested range not present in inline bytes when it should have been",
-				))
-			}
+	match (version_data, blocks) {
+		(ObjectVersionData::Inline(_meta, bytes), _) => {
+			let body: Body = Body::from(inline_range(bytes, begin, end)?.to_vec());
+			Ok(resp_builder.body(body)?)
 		}
-		ObjectVersionData::FirstBlock(_meta, _first_block_hash) => {
-			let version = garage
-				.version_table
-				.get(&version.uuid, &EmptyKey)
-				.await?
-				.ok_or(Error::NoSuchKey)?;
-
+		(ObjectVersionData::FirstBlock(_meta, _first_block_hash), Some(version)) => {
 			let body = body_from_blocks_range(garage, version.blocks.items(), begin, end);
 			Ok(resp_builder.body(body)?)
 		}
+		_ => unreachable!(),
 	}
 }
 
@@ -347,34 +464,22 @@ async fn handle_get_part(
 	object_version: &ObjectVersion,
 	version_data: &ObjectVersionData,
 	version_meta: &ObjectVersionMeta,
+	blocks: Option<&Version>,
 	part_number: u64,
 ) -> Result<Response<Body>, Error> {
 	let resp_builder =
 		object_headers(object_version, version_meta).status(StatusCode::PARTIAL_CONTENT);
 
-	match version_data {
-		ObjectVersionData::Inline(_, bytes) => {
+	match (version_data, blocks) {
+		(ObjectVersionData::Inline(_, bytes), _) => {
 			if part_number != 1 {
 				return Err(Error::InvalidPart);
 			}
-			Ok(resp_builder
-				.header(CONTENT_LENGTH, format!("{}", bytes.len()))
-				.header(
-					CONTENT_RANGE,
-					format!("bytes {}-{}/{}", 0, bytes.len() - 1, bytes.len()),
-				)
-				.header(X_AMZ_MP_PARTS_COUNT, "1")
-				.body(Body::from(bytes.to_vec()))?)
+			Ok(inline_part_headers(resp_builder, bytes.len()).body(Body::from(bytes.to_vec()))?)
 		}
-		ObjectVersionData::FirstBlock(_, _) => {
-			let version = garage
-				.version_table
-				.get(&object_version.uuid, &EmptyKey)
-				.await?
-				.ok_or(Error::NoSuchKey)?;
-
+		(ObjectVersionData::FirstBlock(_, _), Some(version)) => {
 			let (begin, end) =
-				calculate_part_bounds(&version, part_number).ok_or(Error::InvalidPart)?;
+				calculate_part_bounds(version, part_number).ok_or(Error::InvalidPart)?;
 
 			let body = body_from_blocks_range(garage, version.blocks.items(), begin, end);
 
@@ -391,6 +496,57 @@ async fn handle_get_part(
 	}
 }
 
+/// Slice of the data of an inline object for a byte range, which is checked
+/// against the size of the object like ranges of objects stored in blocks
+fn inline_range(bytes: &[u8], begin: u64, end: u64) -> Result<&[u8], Error> {
+	if begin <= end && end <= bytes.len() as u64 {
+		Ok(&bytes[begin as usize..end as usize])
+	} else {
+		Err(Error::internal_error(
+			"Requested range not present in inline bytes when it should have been",
+		))
+	}
+}
+
+/// Headers of the response to a request for the single part of an inline
+/// object. Empty objects have no byte range to return, and are returned whole.
+fn inline_part_headers(
+	resp_builder: http::response::Builder,
+	size: usize,
+) -> http::response::Builder {
+	let resp_builder = resp_builder
+		.header(CONTENT_LENGTH, format!("{}", size))
+		.header(X_AMZ_MP_PARTS_COUNT, "1");
+	if size == 0 {
+		resp_builder.status(StatusCode::OK)
+	} else {
+		resp_builder
+			.header(CONTENT_RANGE, format!("bytes 0-{}/{}", size - 1, size))
+			.status(StatusCode::PARTIAL_CONTENT)
+	}
+}
+
+/// Read the list of blocks of an object version. Returns None if the version
+/// was deleted, which happens when it is replaced by a newer version of the
+/// object: its blocks are then no longer referenced and might be deleted.
+/// Blocks of versions deleted after this point are kept for a while by the
+/// block manager, so that transfers already started can still read them.
+async fn get_version_blocks(
+	garage: &Garage,
+	object_version: &ObjectVersion,
+) -> Result<Option<Version>, Error> {
+	let version = garage
+		.version_table
+		.get(&object_version.uuid, &EmptyKey)
+		.await?
+		.ok_or(Error::NoSuchKey)?;
+	if version.deleted.get() {
+		Ok(None)
+	} else {
+		Ok(Some(version))
+	}
+}
+
 fn parse_range_header(
 	req: &Request<Body>,
 	total_size: u64,
@@ -455,10 +611,17 @@ fn body_from_blocks_range(
 		block_offset += b.size;
 	}
 
+	// Keep the blocks from being deleted for as long as the body is read,
+	// even if the object is deleted in the meantime
+	let hold = garage
+		.block_manager
+		.hold_blocks(blocks.iter().map(|(b, _)| b.hash).collect());
+
 	let order_stream = OrderTag::stream();
 	let body_stream = futures::stream::iter(blocks)
 		.enumerate()
 		.map(move |(i, (block, block_offset))| {
+			let _hold = &hold;
 			let garage = garage.clone();
 			async move {
 				garage
@@ -511,6 +674,162 @@ fn body_from_blocks_range(
 	hyper::body::Body::wrap_stream(body_stream)
 }
 
+/// Whether the ETag of an object is derived from its data. That of objects
+/// uploaded by their blocks is derived from the hashes of the blocks.
+fn etag_is_verifiable(meta: &ObjectVersionMeta, version: Option<&Version>) -> bool {
+	match version {
+		Some(v) => {
+			let hashes = v
+				.blocks
+				.items()
+				.iter()
+				.map(|(_, vb)| vb.hash)
+				.collect::<Vec<_>>();
+			meta.etag != blocks_etag(&hashes)
+		}
+		None => true,
+	}
+}
+
+/// Sizes of the parts of an object version, with their part numbers
+fn part_sizes(version: &Version) -> Vec<(u64, u64)> {
+	let mut parts: Vec<(u64, u64)> = vec![];
+	for (bk, bv) in version.blocks.items().iter() {
+		match parts.last_mut() {
+			Some((pn, size)) if *pn == bk.part_number => *size += bv.size,
+			_ => parts.push((bk.part_number, bv.size)),
+		}
+	}
+	parts
+}
+
+/// Computation of the ETag of the data of an object as it is sent
+struct EtagVerifier {
+	etag: String,
+	/// Part numbers and sizes of the parts not started yet
+	parts: VecDeque<(u64, u64)>,
+	/// Part number and remaining size of the current part
+	current: Option<(u64, u64)>,
+	hasher: Md5,
+	/// Part numbers and MD5 of the parts already read
+	md5s: Vec<(u64, Vec<u8>)>,
+	/// More data was read than the size of the object
+	overflow: bool,
+}
+
+impl EtagVerifier {
+	fn new(etag: &str, parts: Vec<(u64, u64)>) -> Self {
+		Self {
+			etag: etag.to_string(),
+			parts: parts.into_iter().filter(|(_, size)| *size > 0).collect(),
+			current: None,
+			hasher: Md5::new(),
+			md5s: vec![],
+			overflow: false,
+		}
+	}
+
+	fn update(&mut self, mut data: &[u8]) {
+		while !data.is_empty() {
+			let (part_number, remaining) =
+				match self.current.take().or_else(|| self.parts.pop_front()) {
+					Some(part) => part,
+					None => {
+						self.overflow = true;
+						return;
+					}
+				};
+			let n = std::cmp::min(remaining, data.len() as u64) as usize;
+			self.hasher.update(&data[..n]);
+			data = &data[n..];
+			if n as u64 == remaining {
+				let md5 = self.hasher.finalize_reset().to_vec();
+				self.md5s.push((part_number, md5));
+			} else {
+				self.current = Some((part_number, remaining - n as u64));
+			}
+		}
+	}
+
+	/// Whether all the data was read and matches the ETag. The ETag of an
+	/// object can be the MD5 of its data, that of a multipart upload (the
+	/// MD5 of the hex MD5 of its parts), or that of an object to which data
+	/// was appended (the MD5 of the ETag before the append and of the MD5
+	/// of the appended data), possibly starting from a multipart upload:
+	/// all the ways of splitting the parts between these cases are tried.
+	fn matches(&self) -> bool {
+		if self.overflow || self.current.is_some() || !self.parts.is_empty() {
+			return false;
+		}
+		let md5s = &self.md5s;
+		if md5s.is_empty() {
+			return self.etag == hex::encode(Md5::digest(b""));
+		}
+		(1..=md5s.len()).any(|j| {
+			let mut etag = if j == 1 {
+				hex::encode(&md5s[0].1)
+			} else {
+				let mut hasher = Md5::new();
+				for (_, md5) in md5s[..j].iter() {
+					hasher.update(hex::encode(md5).as_bytes());
+				}
+				format!("{}-{}", hex::encode(hasher.finalize()), j)
+			};
+			for (part_number, md5) in md5s[j..].iter() {
+				let mut hasher = Md5::new();
+				hasher.update(etag.as_bytes());
+				hasher.update(md5);
+				etag = format!("{}-{}", hex::encode(hasher.finalize()), part_number);
+			}
+			etag == self.etag
+		})
+	}
+}
+
+/// Pass the data of an object through an ETag verifier, holding back the
+/// last chunk until the ETag is checked, so that on a mismatch the response
+/// is cut before its end and clients see it as failed
+fn verify_etag_stream<S>(
+	stream: S,
+	verifier: EtagVerifier,
+	what: String,
+) -> impl Stream<Item = Result<Bytes, std::io::Error>> + Send
+where
+	S: Stream<Item = Result<Bytes, std::io::Error>> + Send + Unpin + 'static,
+{
+	stream::unfold(
+		(stream, Some(verifier), None::<Bytes>),
+		move |(mut stream, mut verifier, mut held)| {
+			let what = what.clone();
+			async move {
+				let v = verifier.as_mut()?;
+				loop {
+					match stream.next().await {
+						Some(Ok(bytes)) => {
+							v.update(&bytes);
+							if let Some(prev) = held.replace(bytes) {
+								return Some((Ok(prev), (stream, verifier, held)));
+							}
+						}
+						Some(Err(e)) => return Some((Err(e), (stream, None, None))),
+						None if v.matches() => {
+							return held.map(|last| (Ok(last), (stream, None, None)));
+						}
+						None => {
+							error!("ETag mismatch for {}", what);
+							let err = std::io::Error::new(
+								std::io::ErrorKind::Other,
+								"The data of the object does not match its ETag",
+							);
+							return Some((Err(err), (stream, None, None)));
+						}
+					}
+				}
+			}
+		},
+	)
+}
+
 fn error_stream(i: usize, e: garage_util::error::Error) -> ByteStream {
 	Box::pin(futures::stream::once(async move {
 		Err(std::io::Error::new(
@@ -519,3 +838,100 @@ fn error_stream(i: usize, e: garage_util::error::Error) -> ByteStream {
 		))
 	}))
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_etag_list_matches() {
+		assert!(etag_list_matches("\"abc\"", "abc"));
+		assert!(etag_list_matches("\"xyz\", W/\"abc\"", "abc"));
+		assert!(etag_list_matches("*", "abc"));
+		assert!(!etag_list_matches("\"abcd\", \"ab\"", "abc"));
+	}
+
+	#[test]
+	fn test_object_headers_round_trip() {
+		let req = Request::builder()
+			.header(CONTENT_TYPE, "text/html")
+			.header(CACHE_CONTROL, "max-age=3600")
+			.header(http::header::CONTENT_DISPOSITION, "inline")
+			.header(http::header::CONTENT_ENCODING, "gzip")
+			.header(http::header::CONTENT_LANGUAGE, "fr-FR")
+			.header(EXPIRES, "Wed, 21 Oct 2015 07:28:00 GMT")
+			.header("x-amz-meta-zeta", "z")
+			.header("x-amz-meta-alpha", "a1")
+			.header("x-amz-meta-alpha", "a2")
+			.body(Body::empty())
+			.unwrap();
+		let headers = crate::s3::put::get_headers(
+			req.headers(),
+			"index.html",
+			&garage_model::bucket_table::ObjectDefaults::default(),
+		)
+		.unwrap();
+
+		let meta = ObjectVersionMeta {
+			headers,
+			size: 0,
+			etag: "d41d8cd98f00b204e9800998ecf8427e".into(),
+		};
+		let version = ObjectVersion {
+			uuid: garage_util::data::gen_uuid(),
+			timestamp: 1445412480000,
+			state: ObjectVersionState::Complete(ObjectVersionData::Inline(meta.clone(), vec![])),
+		};
+		let resp = object_headers(&version, &meta).body(()).unwrap();
+		let resp = resp.headers();
+
+		assert_eq!(resp[CONTENT_TYPE], "text/html");
+		assert_eq!(resp[ACCEPT_RANGES], "bytes");
+		assert_eq!(resp[ETAG], "\"d41d8cd98f00b204e9800998ecf8427e\"");
+		assert_eq!(resp[LAST_MODIFIED], "Wed, 21 Oct 2015 07:28:00 GMT");
+		assert_eq!(resp[CACHE_CONTROL], "max-age=3600");
+		assert_eq!(resp[http::header::CONTENT_DISPOSITION], "inline");
+		assert_eq!(resp[http::header::CONTENT_ENCODING], "gzip");
+		assert_eq!(resp[http::header::CONTENT_LANGUAGE], "fr-FR");
+		assert_eq!(resp[EXPIRES], "Wed, 21 Oct 2015 07:28:00 GMT");
+		assert_eq!(resp["x-amz-meta-alpha"], "a1,a2");
+
+		let meta_names = resp
+			.keys()
+			.filter(|k| k.as_str().starts_with("x-amz-meta-"))
+			.map(|k| k.as_str())
+			.collect::<Vec<_>>();
+		assert_eq!(meta_names, vec!["x-amz-meta-alpha", "x-amz-meta-zeta"]);
+	}
+
+	fn range_of(range: &str, data: &[u8]) -> Option<Vec<u8>> {
+		let req = Request::builder()
+			.header(RANGE, range)
+			.body(Body::empty())
+			.unwrap();
+		let range = parse_range_header(&req, data.len() as u64).ok()??;
+		let slice = inline_range(data, range.start, range.start + range.length).unwrap();
+		Some(slice.to_vec())
+	}
+
+	#[test]
+	fn test_inline_range() {
+		let data = b"0123456789";
+		assert_eq!(range_of("bytes=0-0", data).unwrap(), b"0");
+		assert_eq!(range_of("bytes=2-5", data).unwrap(), b"2345");
+		assert_eq!(range_of("bytes=7-", data).unwrap(), b"789");
+		assert_eq!(range_of("bytes=-3", data).unwrap(), b"789");
+		assert_eq!(range_of("bytes=5-100", data).unwrap(), b"56789");
+		// Multi-range requests get the whole object
+		assert_eq!(range_of("bytes=0-1,4-5", data), None);
+		// Unsatisfiable ranges are rejected, as for objects stored in blocks
+		let req = Request::builder()
+			.header(RANGE, "bytes=10-20")
+			.body(Body::empty())
+			.unwrap();
+		assert!(parse_range_header(&req, data.len() as u64).is_err());
+
+		assert!(inline_range(data, 5, 11).is_err());
+		assert!(inline_range(data, 6, 5).is_err());
+	}
+}
@@ -0,0 +1,502 @@
+//! Import of the objects of a bucket of another S3-compatible service
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use base64::prelude::*;
+use bytesize::ByteSize;
+use chrono::Utc;
+use futures::future::join_all;
+use futures::TryStreamExt;
+use hyper::client::connect::HttpConnector;
+use hyper::{Body, Client as HttpClient, Request, Response};
+use hyper_rustls::HttpsConnector;
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+
+use garage_sigv4::{sign_request, Payload, SigningParams, SERVICE_S3};
+
+use garage_util::background::*;
+use garage_util::data::*;
+use garage_util::error::Error as GarageError;
+
+use garage_model::bucket_table::Bucket;
+use garage_model::garage::Garage;
+use garage_model::job::*;
+use garage_model::s3::object_table::*;
+
+use crate::encoding::uri_encode;
+use crate::s3::error::*;
+use crate::s3::put::{get_headers, save_stream};
+
+/// Maximum number of objects listed in each request to the remote service
+const LIST_PAGE_SIZE: usize = 1000;
+
+/// Kind of the import jobs
+pub const JOB_KIND: &str = "import";
+
+/// Parameters of an import from a remote S3-compatible service. They are
+/// saved in the job record of the import, to resume it after a restart.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportParams {
+	/// URL of the remote service, e.g. `https://s3.eu-west-1.amazonaws.com`;
+	/// requests are made using path-style addressing
+	pub endpoint: String,
+	/// Region used to sign requests to the remote service
+	pub region: String,
+	pub access_key_id: String,
+	pub secret_access_key: String,
+	/// Bucket of the remote service the objects are imported from
+	pub remote_bucket: String,
+	/// Only import objects whose key starts with this prefix
+	pub prefix: String,
+	/// Number of objects imported at the same time
+	pub concurrency: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ImportJobParams {
+	bucket_id: Uuid,
+	bucket_name: String,
+	params: ImportParams,
+}
+
+/// Saved state of an import: it is resumed by listing again the page
+/// of objects it was importing, whose objects that were already imported
+/// are then skipped
+#[derive(Serialize, Deserialize)]
+struct ImportCheckpoint {
+	page_token: Option<String>,
+	imported: u64,
+	imported_bytes: u64,
+	skipped: u64,
+	failed: u64,
+}
+
+/// Start an import into a bucket, as a job of the job queue
+pub fn start_import(
+	garage: &Arc<Garage>,
+	bg: &BackgroundRunner,
+	bucket_id: Uuid,
+	bucket_name: String,
+	params: ImportParams,
+) -> Result<JobRecord, GarageError> {
+	let description = format!(
+		"Import of {}/{} into {}",
+		params.endpoint, params.remote_bucket, bucket_name
+	);
+	let job_params = ImportJobParams {
+		bucket_id,
+		bucket_name: bucket_name.clone(),
+		params: params.clone(),
+	};
+	let worker = ImportWorker::new(garage.clone(), bucket_id, bucket_name, params);
+	garage
+		.jobs
+		.start(bg, JOB_KIND, description, &job_params, Box::new(worker))
+}
+
+/// Register the import jobs in the job queue, so that they are resumed
+/// after a restart
+pub fn register_job_kind(jobs: &JobQueue) {
+	jobs.register_kind(JOB_KIND, resume_import);
+}
+
+fn resume_import(
+	garage: &Arc<Garage>,
+	record: &JobRecord,
+) -> Result<Box<dyn JobWorker>, GarageError> {
+	let job_params = record.params::<ImportJobParams>()?;
+	let mut worker = ImportWorker::new(
+		garage.clone(),
+		job_params.bucket_id,
+		job_params.bucket_name,
+		job_params.params,
+	);
+	if let Some(checkpoint) = record.checkpoint::<ImportCheckpoint>()? {
+		worker.continuation_token = checkpoint.page_token;
+		worker.imported = checkpoint.imported;
+		worker.imported_bytes = checkpoint.imported_bytes;
+		worker.skipped = checkpoint.skipped;
+		worker.failed = checkpoint.failed;
+	}
+	Ok(Box::new(worker))
+}
+
+/// An object listed in the remote bucket
+#[derive(Debug, PartialEq, Eq)]
+struct RemoteObject {
+	key: String,
+	size: u64,
+	etag: String,
+}
+
+impl RemoteObject {
+	/// MD5 sum of the object's content, if it is given by its ETag,
+	/// which is not the case for objects uploaded using multipart uploads
+	fn md5(&self) -> Option<Vec<u8>> {
+		if self.etag.len() == 32 {
+			hex::decode(&self.etag).ok()
+		} else {
+			None
+		}
+	}
+}
+
+/// Worker that imports all the objects of a remote bucket into a bucket.
+///
+/// Objects that are already present with the same size and ETag are skipped,
+/// so that an import that was interrupted can be resumed by starting it again.
+pub struct ImportWorker {
+	garage: Arc<Garage>,
+	bucket_id: Uuid,
+	bucket_name: String,
+	params: ImportParams,
+	client: HttpClient<HttpsConnector<HttpConnector>>,
+
+	/// Token with which the page of objects in the queue was listed
+	page_token: Option<String>,
+	continuation_token: Option<String>,
+	listing_done: bool,
+	queue: VecDeque<RemoteObject>,
+
+	imported: u64,
+	imported_bytes: u64,
+	skipped: u64,
+	failed: u64,
+	last_failure: Option<String>,
+}
+
+impl ImportWorker {
+	pub fn new(
+		garage: Arc<Garage>,
+		bucket_id: Uuid,
+		bucket_name: String,
+		params: ImportParams,
+	) -> Self {
+		let connector = hyper_rustls::HttpsConnectorBuilder::new()
+			.with_native_roots()
+			.https_or_http()
+			.enable_http1()
+			.enable_http2()
+			.build();
+		Self {
+			garage,
+			bucket_id,
+			bucket_name,
+			params,
+			client: HttpClient::builder().build(connector),
+			page_token: None,
+			continuation_token: None,
+			listing_done: false,
+			queue: VecDeque::new(),
+			imported: 0,
+			imported_bytes: 0,
+			skipped: 0,
+			failed: 0,
+			last_failure: None,
+		}
+	}
+
+	/// Send a signed GET request to the remote bucket
+	async fn remote_get(&self, key: &str, query: &[(&str, &str)]) -> Result<Response<Body>, Error> {
+		let mut uri = format!(
+			"{}/{}/{}",
+			self.params.endpoint.trim_end_matches('/'),
+			uri_encode(&self.params.remote_bucket, true),
+			uri_encode(key, false)
+		);
+		for (i, (name, value)) in query.iter().enumerate() {
+			uri.push(if i == 0 { '?' } else { '&' });
+			uri.push_str(&format!("{}={}", name, uri_encode(value, true)));
+		}
+		let mut req = Request::get(&uri).body(Body::empty())?;
+
+		let signing_params = SigningParams {
+			access_key_id: &self.params.access_key_id,
+			secret_access_key: &self.params.secret_access_key,
+			region: &self.params.region,
+			service: SERVICE_S3,
+			time: Utc::now(),
+		};
+		sign_request(&mut req, &signing_params, Payload::Unsigned)
+			.ok_or_internal_error("Could not sign request")?;
+
+		let resp = self.client.request(req).await?;
+		if !resp.status().is_success() {
+			let status = resp.status();
+			let body = hyper::body::to_bytes(resp.into_body())
+				.await
+				.unwrap_or_default();
+			return Err(Error::internal_error(format!(
+				"Request to {} failed with status {}: {}",
+				uri,
+				status,
+				String::from_utf8_lossy(&body)
+			)));
+		}
+		Ok(resp)
+	}
+
+	/// List the next page of objects of the remote bucket
+	async fn list_next_page(&mut self) -> Result<(), Error> {
+		let max_keys = LIST_PAGE_SIZE.to_string();
+		let mut query = vec![
+			("list-type", "2"),
+			("encoding-type", "url"),
+			("max-keys", max_keys.as_str()),
+			("prefix", self.params.prefix.as_str()),
+		];
+		if let Some(token) = &self.continuation_token {
+			query.push(("continuation-token", token.as_str()));
+		}
+		query.sort();
+
+		let resp = self.remote_get("", &query).await?;
+		let body = hyper::body::to_bytes(resp.into_body()).await?;
+		let (objects, next_token) = parse_list_objects(&body)?;
+
+		self.queue.extend(objects);
+		self.listing_done = next_token.is_none();
+		self.page_token = std::mem::replace(&mut self.continuation_token, next_token);
+		Ok(())
+	}
+
+	/// Import an object, if it is not already present in the bucket.
+	/// Returns whether the object was imported.
+	async fn import_object(&self, bucket: &Bucket, object: &RemoteObject) -> Result<bool, Error> {
+		let existing = self
+			.garage
+			.object_table
+			.get(&bucket.id, &object.key)
+			.await?;
+		let existing_meta = existing.as_ref().and_then(|o| {
+			o.versions()
+				.iter()
+				.rev()
+				.find(|v| v.is_data())
+				.and_then(|v| match &v.state {
+					ObjectVersionState::Complete(ObjectVersionData::Inline(meta, _))
+					| ObjectVersionState::Complete(ObjectVersionData::FirstBlock(meta, _)) => Some(meta),
+					_ => None,
+				})
+		});
+		if let Some(meta) = existing_meta {
+			if meta.size == object.size && (object.md5().is_none() || meta.etag == object.etag) {
+				return Ok(false);
+			}
+		}
+
+		let resp = self.remote_get(&object.key, &[]).await?;
+		let headers = get_headers(resp.headers(), &object.key, &bucket.object_defaults())?;
+		// The content is checked against the MD5 sum given by the ETag
+		let content_md5 = object.md5().map(|md5| BASE64_STANDARD.encode(md5));
+		let body = resp.into_body().map_err(Error::from);
+
+		save_stream(
+			self.garage.clone(),
+			headers,
+			body,
+			bucket,
+			&object.key,
+			content_md5,
+			None,
+		)
+		.await?;
+		Ok(true)
+	}
+}
+
+#[async_trait]
+impl Worker for ImportWorker {
+	fn name(&self) -> String {
+		format!(
+			"Import of {}/{} into {}",
+			self.params.endpoint, self.params.remote_bucket, self.bucket_name
+		)
+	}
+
+	fn status(&self) -> WorkerStatus {
+		let mut freeform = vec![];
+		if let Some(failure) = &self.last_failure {
+			freeform.push(format!("Last failure: {}", failure));
+		}
+		WorkerStatus {
+			progress: Some(format!(
+				"{} imported ({}), {} skipped, {} failed",
+				self.imported,
+				ByteSize::b(self.imported_bytes).to_string_as(true),
+				self.skipped,
+				self.failed
+			)),
+			queue_length: Some(self.queue.len() as u64),
+			freeform,
+			..Default::default()
+		}
+	}
+
+	async fn work(
+		&mut self,
+		_must_exit: &mut watch::Receiver<bool>,
+	) -> Result<WorkerState, GarageError> {
+		if self.queue.is_empty() {
+			if self.listing_done {
+				info!(
+					"{}: finished, {} imported, {} skipped, {} failed",
+					self.name(),
+					self.imported,
+					self.skipped,
+					self.failed
+				);
+				return Ok(WorkerState::Done);
+			}
+			self.list_next_page()
+				.await
+				.map_err(|e| GarageError::Message(e.to_string()))?;
+			return Ok(WorkerState::Busy);
+		}
+
+		let bucket = match self
+			.garage
+			.bucket_helper()
+			.get_existing_bucket(self.bucket_id)
+			.await
+		{
+			Ok(bucket) => bucket,
+			Err(e) => {
+				error!("{}: stopping, bucket is not available: {}", self.name(), e);
+				return Ok(WorkerState::Done);
+			}
+		};
+
+		let n = std::cmp::min(std::cmp::max(self.params.concurrency, 1), self.queue.len());
+		let batch = self.queue.drain(..n).collect::<Vec<_>>();
+		let results = join_all(batch.iter().map(|o| self.import_object(&bucket, o))).await;
+
+		for (object, res) in batch.iter().zip(results) {
+			match res {
+				Ok(true) => {
+					self.imported += 1;
+					self.imported_bytes += object.size;
+				}
+				Ok(false) => self.skipped += 1,
+				Err(e) => {
+					warn!("{}: could not import {}: {}", self.name(), object.key, e);
+					self.failed += 1;
+					self.last_failure = Some(format!("{}: {}", object.key, e));
+				}
+			}
+		}
+		Ok(WorkerState::Busy)
+	}
+
+	async fn wait_for_work(&mut self) -> WorkerState {
+		unreachable!()
+	}
+}
+
+impl JobWorker for ImportWorker {
+	fn checkpoint(&self) -> Vec<u8> {
+		encode_checkpoint(&ImportCheckpoint {
+			page_token: self.page_token.clone(),
+			imported: self.imported,
+			imported_bytes: self.imported_bytes,
+			skipped: self.skipped,
+			failed: self.failed,
+		})
+	}
+}
+
+/// Parse the body of a ListObjectsV2 response made with `encoding-type=url`,
+/// returning the objects listed and the token to list the next ones, if any
+fn parse_list_objects(xml_bytes: &[u8]) -> Result<(Vec<RemoteObject>, Option<String>), Error> {
+	let xml_str = std::str::from_utf8(xml_bytes)?;
+	let xml = roxmltree::Document::parse(xml_str)?;
+
+	let root = xml.root_element();
+	if !root.has_tag_name("ListBucketResult") {
+		return Err(Error::InvalidXml("Expected ListBucketResult".into()));
+	}
+	let child_text = |node: roxmltree::Node, name: &str| {
+		node.children()
+			.find(|c| c.has_tag_name(name))
+			.map(|c| c.text().unwrap_or_default().to_string())
+	};
+
+	let mut objects = vec![];
+	for item in root.children().filter(|c| c.has_tag_name("Contents")) {
+		let key = child_text(item, "Key").ok_or_bad_request("Object without key")?;
+		let key = percent_encoding::percent_decode_str(&key)
+			.decode_utf8()?
+			.into_owned();
+		let size = child_text(item, "Size")
+			.and_then(|s| s.parse().ok())
+			.ok_or_bad_request("Object without valid size")?;
+		let etag = child_text(item, "ETag")
+			.unwrap_or_default()
+			.trim_matches('"')
+			.to_string();
+		objects.push(RemoteObject { key, size, etag });
+	}
+
+	let next_token = match child_text(root, "IsTruncated").as_deref() {
+		Some("true") => Some(
+			child_text(root, "NextContinuationToken")
+				.ok_or_bad_request("Truncated listing without continuation token")?,
+		),
+		_ => None,
+	};
+
+	Ok((objects, next_token))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_parse_list_objects() {
+		let xml = br#"<?xml version="1.0" encoding="UTF-8"?>
+<ListBucketResult xmlns="http://s3.amazonaws.com/doc/2006-03-01/">
+	<Name>bucket</Name>
+	<Prefix></Prefix>
+	<KeyCount>2</KeyCount>
+	<MaxKeys>2</MaxKeys>
+	<EncodingType>url</EncodingType>
+	<IsTruncated>true</IsTruncated>
+	<Contents>
+		<Key>photos/a%20b.jpg</Key>
+		<LastModified>2023-01-01T00:00:00.000Z</LastModified>
+		<ETag>&quot;9b2cf535f27731c974343645a3985328&quot;</ETag>
+		<Size>1024</Size>
+		<StorageClass>STANDARD</StorageClass>
+	</Contents>
+	<Contents>
+		<Key>video.mp4</Key>
+		<ETag>"d41d8cd98f00b204e9800998ecf8427e-3"</ETag>
+		<Size>20000000</Size>
+	</Contents>
+	<NextContinuationToken>dmlkZW8ubXA0</NextContinuationToken>
+</ListBucketResult>"#;
+
+		let (objects, next_token) = parse_list_objects(xml).unwrap();
+		assert_eq!(
+			objects,
+			vec![
+				RemoteObject {
+					key: "photos/a b.jpg".into(),
+					size: 1024,
+					etag: "9b2cf535f27731c974343645a3985328".into(),
+				},
+				RemoteObject {
+					key: "video.mp4".into(),
+					size: 20000000,
+					etag: "d41d8cd98f00b204e9800998ecf8427e-3".into(),
+				},
+			]
+		);
+		assert!(objects[0].md5().is_some());
+		assert!(objects[1].md5().is_none());
+		assert_eq!(next_token.as_deref(), Some("dmlkZW8ubXA0"));
+	}
+}
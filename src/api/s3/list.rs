@@ -1,4 +1,4 @@
-use std::collections::{BTreeMap, BTreeSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::iter::{Iterator, Peekable};
 use std::sync::Arc;
 
@@ -13,7 +13,7 @@ use garage_model::garage::Garage;
 use garage_model::s3::mpu_table::*;
 use garage_model::s3::object_table::*;
 
-use garage_table::EnumerationOrder;
+use garage_table::{EmptyKey, EnumerationOrder};
 
 use crate::encoding::*;
 use crate::helpers::key_after_prefix;
@@ -147,7 +147,7 @@ pub async fn handle_list(
 				last_modified: s3_xml::Value(msec_to_rfc3339(info.last_modified)),
 				size: s3_xml::IntValue(info.size as i64),
 				etag: s3_xml::Value(format!("\"{}\"", info.etag)),
-				storage_class: s3_xml::Value("STANDARD".to_string()),
+				storage_class: s3_xml::Value(info.storage_class.clone()),
 			})
 			.collect(),
 		common_prefixes: acc
@@ -189,6 +189,12 @@ pub async fn handle_list_multipart_upload(
 	let mut acc = query.build_accumulator();
 	let pagination = fetch_list_entries(&query.common, query.begin()?, &mut acc, &io).await?;
 
+	// Uploads are returned sorted by key, then by upload id
+	let mut uploads = acc.keys.iter().collect::<Vec<_>>();
+	uploads.sort_by(|(u1, i1), (u2, i2)| (&i1.key, u1).cmp(&(&i2.key, u2)));
+
+	let key_names = initiator_names(&garage, uploads.iter().map(|(_, i)| &i.initiator)).await?;
+
 	let result = s3_xml::ListMultipartUploadsResult {
 		xmlns: (),
 
@@ -233,18 +239,14 @@ pub async fn handle_list_multipart_upload(
 		},
 
 		// Result body
-		upload: acc
-			.keys
-			.iter()
+		upload: uploads
+			.into_iter()
 			.map(|(uuid, info)| s3_xml::ListMultipartItem {
 				initiated: s3_xml::Value(msec_to_rfc3339(info.timestamp)),
 				key: uriencode_maybe(&info.key, query.common.urlencode_resp),
 				upload_id: s3_xml::Value(hex::encode(uuid)),
 				storage_class: s3_xml::Value("STANDARD".to_string()),
-				initiator: s3_xml::Initiator {
-					display_name: s3_xml::Value(DUMMY_NAME.to_string()),
-					id: s3_xml::Value(DUMMY_KEY.to_string()),
-				},
+				initiator: initiator_xml(info.initiator.as_deref(), &key_names),
 				owner: s3_xml::Owner {
 					display_name: s3_xml::Value(DUMMY_NAME.to_string()),
 					id: s3_xml::Value(DUMMY_KEY.to_string()),
@@ -275,8 +277,10 @@ pub async fn handle_list_parts(
 
 	let upload_id = s3_multipart::decode_upload_id(&query.upload_id)?;
 
-	let (_, _, mpu) =
+	let (_, object_version, mpu) =
 		s3_multipart::get_upload(&garage, &query.bucket_id, &query.key, &upload_id).await?;
+	let initiator = object_version.initiator().map(str::to_string);
+	let key_names = initiator_names(&garage, std::iter::once(&initiator)).await?;
 
 	let (info, next) = fetch_part_info(query, &mpu)?;
 
@@ -303,11 +307,9 @@ pub async fn handle_list_parts(
 			})
 			.collect(),
 
+		initiator: initiator_xml(initiator.as_deref(), &key_names),
+
 		// Dummy result values (unsupported features)
-		initiator: s3_xml::Initiator {
-			display_name: s3_xml::Value(DUMMY_NAME.to_string()),
-			id: s3_xml::Value(DUMMY_KEY.to_string()),
-		},
 		owner: s3_xml::Owner {
 			display_name: s3_xml::Value(DUMMY_NAME.to_string()),
 			id: s3_xml::Value(DUMMY_KEY.to_string()),
@@ -322,6 +324,47 @@ pub async fn handle_list_parts(
 		.body(Body::from(xml.into_bytes()))?)
 }
 
+/// Names of the access keys that started multipart uploads
+async fn initiator_names<'a>(
+	garage: &Garage,
+	initiators: impl Iterator<Item = &'a Option<String>>,
+) -> Result<HashMap<String, String>, Error> {
+	let key_ids = initiators.flatten().collect::<BTreeSet<_>>();
+	let mut names = HashMap::new();
+	for key_id in key_ids {
+		if let Some(key) = garage.key_table.get(&EmptyKey, key_id).await? {
+			if let Some(params) = key.params() {
+				names.insert(key_id.clone(), params.name.get().clone());
+			}
+		}
+	}
+	Ok(names)
+}
+
+/// Initiator of a multipart upload, given by its access key. Uploads started
+/// before initiators were recorded get placeholder values.
+fn initiator_xml(
+	initiator: Option<&str>,
+	key_names: &HashMap<String, String>,
+) -> s3_xml::Initiator {
+	match initiator {
+		Some(key_id) => s3_xml::Initiator {
+			display_name: s3_xml::Value(
+				key_names
+					.get(key_id)
+					.filter(|name| !name.is_empty())
+					.cloned()
+					.unwrap_or_else(|| key_id.to_string()),
+			),
+			id: s3_xml::Value(key_id.to_string()),
+		},
+		None => s3_xml::Initiator {
+			display_name: s3_xml::Value(DUMMY_NAME.to_string()),
+			id: s3_xml::Value(DUMMY_KEY.to_string()),
+		},
+	}
+}
+
 /*
  * Private enums and structs
  */
@@ -331,12 +374,14 @@ struct ObjectInfo {
 	last_modified: u64,
 	size: u64,
 	etag: String,
+	storage_class: String,
 }
 
 #[derive(Debug, PartialEq)]
 struct UploadInfo {
 	key: String,
 	timestamp: u64,
+	initiator: Option<String>,
 }
 
 #[derive(Debug, PartialEq)]
@@ -760,6 +805,7 @@ impl ExtractAccumulator for ObjectAccumulator {
 			last_modified: version.timestamp,
 			size: meta.size,
 			etag: meta.etag.to_string(),
+			storage_class: meta.headers.storage_class().to_string(),
 		};
 
 		match self.try_insert_entry(object.key.clone(), info) {
@@ -831,6 +877,7 @@ impl ExtractAccumulator for UploadAccumulator {
 		let first_up_info = UploadInfo {
 			key: object.key.to_string(),
 			timestamp: first_upload.timestamp,
+			initiator: first_upload.initiator().map(str::to_string),
 		};
 		if !self.try_insert_entry(first_upload.uuid, first_up_info) {
 			return ExtractionResult::Filled;
@@ -842,6 +889,7 @@ impl ExtractAccumulator for UploadAccumulator {
 			let up_info = UploadInfo {
 				key: object.key.to_string(),
 				timestamp: upload.timestamp,
+				initiator: upload.initiator().map(str::to_string),
 			};
 
 			// Insert data in our accumulator
@@ -937,6 +985,7 @@ mod tests {
 					content_type: "text/plain".to_string(),
 					other: BTreeMap::<String, String>::new(),
 				},
+				initiator: None,
 			},
 		}
 	}
@@ -1010,14 +1059,16 @@ mod tests {
 			acc.keys.get(&Uuid::from([0x80; 32])).unwrap(),
 			&UploadInfo {
 				timestamp: TS,
-				key: "b".to_string()
+				key: "b".to_string(),
+				initiator: None,
 			}
 		);
 		assert_eq!(
 			acc.keys.get(&Uuid::from([0x8f; 32])).unwrap(),
 			&UploadInfo {
 				timestamp: TS,
-				key: "b".to_string()
+				key: "b".to_string(),
+				initiator: None,
 			}
 		);
 
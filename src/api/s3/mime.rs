@@ -0,0 +1,231 @@
+//! Determination of the Content-Type of objects from their key or their content
+
+/// Content types of common file extensions
+const EXTENSION_CONTENT_TYPES: &[(&str, &str)] = &[
+	("7z", "application/x-7z-compressed"),
+	("avif", "image/avif"),
+	("bmp", "image/bmp"),
+	("bz2", "application/x-bzip2"),
+	("css", "text/css"),
+	("csv", "text/csv"),
+	("doc", "application/msword"),
+	(
+		"docx",
+		"application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+	),
+	("eot", "application/vnd.ms-fontobject"),
+	("epub", "application/epub+zip"),
+	("flac", "audio/flac"),
+	("gif", "image/gif"),
+	("gz", "application/gzip"),
+	("htm", "text/html"),
+	("html", "text/html"),
+	("ico", "image/vnd.microsoft.icon"),
+	("ics", "text/calendar"),
+	("jpeg", "image/jpeg"),
+	("jpg", "image/jpeg"),
+	("js", "text/javascript"),
+	("json", "application/json"),
+	("jsonld", "application/ld+json"),
+	("m4a", "audio/mp4"),
+	("md", "text/markdown"),
+	("mjs", "text/javascript"),
+	("mkv", "video/x-matroska"),
+	("mov", "video/quicktime"),
+	("mp3", "audio/mpeg"),
+	("mp4", "video/mp4"),
+	("mpeg", "video/mpeg"),
+	("oga", "audio/ogg"),
+	("ogg", "audio/ogg"),
+	("ogv", "video/ogg"),
+	("opus", "audio/opus"),
+	("otf", "font/otf"),
+	("pdf", "application/pdf"),
+	("png", "image/png"),
+	("ppt", "application/vnd.ms-powerpoint"),
+	(
+		"pptx",
+		"application/vnd.openxmlformats-officedocument.presentationml.presentation",
+	),
+	("rar", "application/vnd.rar"),
+	("rss", "application/rss+xml"),
+	("rtf", "application/rtf"),
+	("svg", "image/svg+xml"),
+	("tar", "application/x-tar"),
+	("tif", "image/tiff"),
+	("tiff", "image/tiff"),
+	("ttf", "font/ttf"),
+	("txt", "text/plain"),
+	("wasm", "application/wasm"),
+	("wav", "audio/wav"),
+	("weba", "audio/webm"),
+	("webm", "video/webm"),
+	("webmanifest", "application/manifest+json"),
+	("webp", "image/webp"),
+	("woff", "font/woff"),
+	("woff2", "font/woff2"),
+	("xhtml", "application/xhtml+xml"),
+	("xls", "application/vnd.ms-excel"),
+	(
+		"xlsx",
+		"application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+	),
+	("xml", "application/xml"),
+	("xz", "application/x-xz"),
+	("yaml", "application/yaml"),
+	("yml", "application/yaml"),
+	("zip", "application/zip"),
+	("zst", "application/zstd"),
+];
+
+/// Content type corresponding to the extension of an object key, if it is known
+pub fn content_type_from_extension(key: &str) -> Option<&'static str> {
+	let file_name = key.rsplit('/').next().unwrap_or(key);
+	let (_, ext) = file_name.rsplit_once('.')?;
+	let ext = ext.to_ascii_lowercase();
+	EXTENSION_CONTENT_TYPES
+		.binary_search_by(|(e, _)| (*e).cmp(ext.as_str()))
+		.ok()
+		.map(|i| EXTENSION_CONTENT_TYPES[i].1)
+}
+
+/// Whether a content type gives no information on the content of an object,
+/// as sent by upload tools that do not determine it (or set when no
+/// Content-Type header was given)
+pub fn is_generic_content_type(content_type: &str) -> bool {
+	let mime = content_type.split(';').next().unwrap_or_default().trim();
+	mime.is_empty()
+		|| mime.eq_ignore_ascii_case("application/octet-stream")
+		|| mime.eq_ignore_ascii_case("binary/octet-stream")
+		|| mime == "blob"
+}
+
+/// Signatures found at the beginning of files of common formats
+const MAGIC_BYTES: &[(&[u8], &str)] = &[
+	(b"\x89PNG\r\n\x1a\n", "image/png"),
+	(b"\xff\xd8\xff", "image/jpeg"),
+	(b"GIF87a", "image/gif"),
+	(b"GIF89a", "image/gif"),
+	(b"II*\x00", "image/tiff"),
+	(b"MM\x00*", "image/tiff"),
+	(b"\x00\x00\x01\x00", "image/vnd.microsoft.icon"),
+	(b"%PDF-", "application/pdf"),
+	(b"PK\x03\x04", "application/zip"),
+	(b"\x1f\x8b", "application/gzip"),
+	(b"BZh", "application/x-bzip2"),
+	(b"\xfd7zXZ\x00", "application/x-xz"),
+	(b"7z\xbc\xaf\x27\x1c", "application/x-7z-compressed"),
+	(b"\x28\xb5\x2f\xfd", "application/zstd"),
+	(b"Rar!\x1a\x07", "application/vnd.rar"),
+	(b"\x00asm", "application/wasm"),
+	(b"OggS", "audio/ogg"),
+	(b"fLaC", "audio/flac"),
+	(b"ID3", "audio/mpeg"),
+	(b"\x1a\x45\xdf\xa3", "video/webm"),
+	(b"wOFF", "font/woff"),
+	(b"wOF2", "font/woff2"),
+	(b"{\\rtf", "application/rtf"),
+];
+
+/// Content type of an object determined from the first bytes of its content,
+/// if it is of a known format
+pub fn content_type_from_magic(data: &[u8]) -> Option<&'static str> {
+	if let Some((_, ct)) = MAGIC_BYTES.iter().find(|(m, _)| data.starts_with(m)) {
+		return Some(ct);
+	}
+
+	// RIFF containers and ISO base media files have their type after a size field
+	if data.len() >= 12 && &data[0..4] == b"RIFF" {
+		match &data[8..12] {
+			b"WEBP" => return Some("image/webp"),
+			b"WAVE" => return Some("audio/wav"),
+			b"AVI " => return Some("video/x-msvideo"),
+			_ => (),
+		}
+	}
+	if data.len() >= 12 && &data[4..8] == b"ftyp" {
+		return Some(match &data[8..12] {
+			b"avif" => "image/avif",
+			b"heic" => "image/heic",
+			b"qt  " => "video/quicktime",
+			b"M4A " => "audio/mp4",
+			_ => "video/mp4",
+		});
+	}
+	if data.len() > 262 && &data[257..262] == b"ustar" {
+		return Some("application/x-tar");
+	}
+
+	// Text formats, possibly preceded by a byte order mark and whitespace
+	let text = data.strip_prefix(b"\xef\xbb\xbf").unwrap_or(data);
+	let start = text.iter().position(|c| !c.is_ascii_whitespace())?;
+	let text = &text[start..std::cmp::min(text.len(), start + 256)];
+	let text = String::from_utf8_lossy(text).to_ascii_lowercase();
+	if text.starts_with("<!doctype html") || text.starts_with("<html") {
+		Some("text/html")
+	} else if text.starts_with("<svg") || (text.starts_with("<?xml") && text.contains("<svg")) {
+		Some("image/svg+xml")
+	} else if text.starts_with("<?xml") {
+		Some("application/xml")
+	} else {
+		None
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_extension_table_sorted() {
+		assert!(EXTENSION_CONTENT_TYPES.windows(2).all(|w| w[0].0 < w[1].0));
+	}
+
+	#[test]
+	fn test_content_type_from_extension() {
+		assert_eq!(content_type_from_extension("index.html"), Some("text/html"));
+		assert_eq!(
+			content_type_from_extension("a/b/IMG.JPG"),
+			Some("image/jpeg")
+		);
+		assert_eq!(content_type_from_extension("a.b/c"), None);
+		assert_eq!(content_type_from_extension("archive.unknown"), None);
+		assert_eq!(content_type_from_extension("noext"), None);
+	}
+
+	#[test]
+	fn test_content_type_from_magic() {
+		assert_eq!(
+			content_type_from_magic(b"\x89PNG\r\n\x1a\n\x00\x00"),
+			Some("image/png")
+		);
+		assert_eq!(
+			content_type_from_magic(b"RIFF\x00\x00\x00\x00WEBPVP8 "),
+			Some("image/webp")
+		);
+		assert_eq!(
+			content_type_from_magic(b"\x00\x00\x00\x20ftypisom"),
+			Some("video/mp4")
+		);
+		assert_eq!(
+			content_type_from_magic(b"\n  <!DOCTYPE HTML><html>"),
+			Some("text/html")
+		);
+		assert_eq!(
+			content_type_from_magic(b"<?xml version=\"1.0\"?>\n<svg xmlns=\"\">"),
+			Some("image/svg+xml")
+		);
+		assert_eq!(content_type_from_magic(b"hello world"), None);
+		assert_eq!(content_type_from_magic(b""), None);
+	}
+
+	#[test]
+	fn test_is_generic_content_type() {
+		assert!(is_generic_content_type("blob"));
+		assert!(is_generic_content_type("application/octet-stream"));
+		assert!(is_generic_content_type(
+			"binary/octet-stream; charset=binary"
+		));
+		assert!(!is_generic_content_type("text/plain"));
+	}
+}
@@ -4,14 +4,20 @@ pub mod error;
 mod bucket;
 mod copy;
 pub mod cors;
-mod delete;
+mod dedup;
+pub(crate) mod delete;
 pub mod get;
+pub mod import;
 mod lifecycle;
 mod list;
+mod mime;
 mod multipart;
 mod post_object;
-mod put;
-mod website;
+pub(crate) mod put;
+mod resumable;
+pub mod update_metadata;
+mod verify;
+pub mod website;
 
 mod router;
 pub mod xml;
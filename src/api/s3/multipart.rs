@@ -13,6 +13,7 @@ use garage_util::time::*;
 
 use garage_model::bucket_table::Bucket;
 use garage_model::garage::Garage;
+use garage_model::key_table::Key;
 use garage_model::s3::block_ref_table::*;
 use garage_model::s3::mpu_table::*;
 use garage_model::s3::object_table::*;
@@ -27,14 +28,16 @@ use crate::signature::verify_signed_content;
 
 pub async fn handle_create_multipart_upload(
 	garage: Arc<Garage>,
+	api_key: &Key,
 	req: &Request<Body>,
 	bucket_name: &str,
-	bucket_id: Uuid,
+	bucket: &Bucket,
 	key: &str,
 ) -> Result<Response<Body>, Error> {
+	let bucket_id = bucket.id;
 	let upload_id = gen_uuid();
 	let timestamp = now_msec();
-	let headers = get_headers(req.headers())?;
+	let headers = get_headers(req.headers(), key, &bucket.object_defaults())?;
 
 	// Create object in object table
 	let object_version = ObjectVersion {
@@ -43,6 +46,7 @@ pub async fn handle_create_multipart_upload(
 		state: ObjectVersionState::Uploading {
 			multipart: true,
 			headers,
+			initiator: Some(api_key.key_id.clone()),
 		},
 	};
 	let object = Object::new(bucket_id, key.to_string(), vec![object_version]);
@@ -69,7 +73,7 @@ pub async fn handle_create_multipart_upload(
 pub async fn handle_put_part(
 	garage: Arc<Garage>,
 	req: Request<Body>,
-	bucket_id: Uuid,
+	bucket: &Bucket,
 	key: &str,
 	part_number: u64,
 	upload_id: &str,
@@ -89,7 +93,7 @@ pub async fn handle_put_part(
 	let mut chunker = StreamChunker::new(body, garage.config.block_size);
 
 	let ((_, _, mut mpu), first_block) = futures::try_join!(
-		get_upload(&garage, &bucket_id, &key, &upload_id),
+		get_upload(&garage, &bucket.id, &key, &upload_id),
 		chunker.next(),
 	)?;
 
@@ -138,9 +142,11 @@ pub async fn handle_put_part(
 		&garage,
 		&version,
 		part_number,
+		0,
 		first_block,
 		first_block_hash,
 		&mut chunker,
+		bucket.quorum_policy(),
 	)
 	.await?;
 
@@ -274,6 +280,15 @@ pub async fn handle_complete_multipart_upload(
 		}
 	}
 
+	// Check that all parts but the last one have the minimum part size
+	let min_part_size = garage.config.s3_api.min_part_size as u64;
+	if parts[..parts.len() - 1]
+		.iter()
+		.any(|p| p.size.unwrap() < min_part_size)
+	{
+		return Err(Error::EntityTooSmall);
+	}
+
 	let grg = &garage;
 	let parts_versions = futures::future::try_join_all(parts.iter().map(|p| async move {
 		grg.version_table
@@ -331,13 +346,16 @@ pub async fn handle_complete_multipart_upload(
 	// Calculate total size of final object
 	let total_size = parts.iter().map(|x| x.size.unwrap()).sum();
 
-	if let Err(e) = check_quotas(&garage, bucket, &key, total_size).await {
-		object_version.state = ObjectVersionState::Aborted;
-		let final_object = Object::new(bucket.id, key.clone(), vec![object_version]);
-		garage.object_table.insert(&final_object).await?;
+	let quota = match check_quotas(&garage, bucket, &key, total_size).await {
+		Ok(quota) => quota,
+		Err(e) => {
+			object_version.state = ObjectVersionState::Aborted;
+			let final_object = Object::new(bucket.id, key.clone(), vec![object_version]);
+			garage.object_table.insert(&final_object).await?;
 
-		return Err(e);
-	}
+			return Err(e);
+		}
+	};
 
 	// Write final object version
 	object_version.state = ObjectVersionState::Complete(ObjectVersionData::FirstBlock(
@@ -362,7 +380,9 @@ pub async fn handle_complete_multipart_upload(
 	};
 	let xml = s3_xml::to_xml_with_header(&result)?;
 
-	Ok(Response::new(Body::from(xml.into_bytes())))
+	let mut resp = Response::new(Body::from(xml.into_bytes()));
+	quota.add_headers(resp.headers_mut());
+	Ok(resp)
 }
 
 pub async fn handle_abort_multipart_upload(
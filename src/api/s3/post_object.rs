@@ -15,6 +15,8 @@ use serde::Deserialize;
 
 use garage_model::garage::Garage;
 
+use crate::helpers::Authorization;
+use crate::s3::bucket::get_authorized_bucket;
 use crate::s3::error::*;
 use crate::s3::put::{get_headers, save_stream};
 use crate::s3::xml as s3_xml;
@@ -122,20 +124,10 @@ pub async fn handle_post_object(
 		policy.as_bytes(),
 	)
 	.await?;
+	garage.key_usage.record(&api_key.key_id, 1, 0, 0);
 
-	let bucket_id = garage
-		.bucket_helper()
-		.resolve_bucket(&bucket_name, &api_key)
-		.await?;
-
-	if !api_key.allow_write(&bucket_id) {
-		return Err(Error::forbidden("Operation is not allowed for this key."));
-	}
-
-	let bucket = garage
-		.bucket_helper()
-		.get_existing_bucket(bucket_id)
-		.await?;
+	let bucket =
+		get_authorized_bucket(&garage, &bucket_name, &api_key, Authorization::Write).await?;
 
 	let decoded_policy = BASE64_STANDARD
 		.decode(policy)
@@ -226,10 +218,17 @@ pub async fn handle_post_object(
 		)));
 	}
 
-	let headers = get_headers(&params)?;
+	let headers = get_headers(&params, &key, &bucket.object_defaults())?;
 
-	let stream = field.map(|r| r.map_err(Into::into));
-	let (_, md5) = save_stream(
+	let key_usage = garage.key_usage.clone();
+	let key_id = api_key.key_id.clone();
+	let stream = field.map(move |r| {
+		if let Ok(chunk) = &r {
+			key_usage.record(&key_id, 0, chunk.len() as u64, 0);
+		}
+		r.map_err(Into::into)
+	});
+	let (_, md5, quota) = save_stream(
 		garage,
 		headers,
 		StreamLimiter::new(stream, conditions.content_length),
@@ -242,7 +241,7 @@ pub async fn handle_post_object(
 
 	let etag = format!("\"{}\"", md5);
 
-	let resp = if let Some(mut target) = params
+	let mut resp = if let Some(mut target) = params
 		.get("success_action_redirect")
 		.and_then(|h| h.to_str().ok())
 		.and_then(|u| url::Url::parse(u).ok())
@@ -307,6 +306,7 @@ pub async fn handle_post_object(
 			_ => builder.status(StatusCode::NO_CONTENT).body(Body::empty())?,
 		}
 	};
+	quota.add_headers(resp.headers_mut());
 
 	Ok(resp)
 }
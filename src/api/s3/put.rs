@@ -1,10 +1,12 @@
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 
 use base64::prelude::*;
 use futures::prelude::*;
+use futures::stream::FuturesUnordered;
 use hyper::body::{Body, Bytes};
-use hyper::header::{HeaderMap, HeaderValue};
+use hyper::header::{HeaderMap, HeaderValue, CONTENT_LENGTH};
 use hyper::{Request, Response};
 use md5::{digest::generic_array::*, Digest as Md5Digest, Md5};
 use sha2::Sha256;
@@ -15,6 +17,7 @@ use opentelemetry::{
 };
 
 use garage_rpc::netapp::bytes_buf::BytesBuf;
+use garage_table::replication::QuorumPolicy;
 use garage_table::*;
 use garage_util::async_hash::*;
 use garage_util::data::*;
@@ -22,7 +25,7 @@ use garage_util::error::Error as GarageError;
 use garage_util::time::*;
 
 use garage_block::manager::INLINE_THRESHOLD;
-use garage_model::bucket_table::Bucket;
+use garage_model::bucket_table::{Bucket, ObjectDefaults};
 use garage_model::garage::Garage;
 use garage_model::index_counter::CountedItem;
 use garage_model::s3::block_ref_table::*;
@@ -30,6 +33,12 @@ use garage_model::s3::object_table::*;
 use garage_model::s3::version_table::*;
 
 use crate::s3::error::*;
+use crate::s3::mime::*;
+
+pub const X_GARAGE_NEXT_APPEND_POSITION: &str = "x-garage-next-append-position";
+pub const X_GARAGE_QUOTA_REMAINING_SIZE: &str = "x-garage-quota-remaining-size";
+pub const X_GARAGE_QUOTA_REMAINING_OBJECTS: &str = "x-garage-quota-remaining-objects";
+pub const X_GARAGE_QUOTA_EXCEEDED: &str = "x-garage-quota-exceeded";
 
 pub async fn handle_put(
 	garage: Arc<Garage>,
@@ -39,7 +48,7 @@ pub async fn handle_put(
 	content_sha256: Option<Hash>,
 ) -> Result<Response<Body>, Error> {
 	// Retrieve interesting headers from request
-	let headers = get_headers(req.headers())?;
+	let headers = get_headers(req.headers(), key, &bucket.object_defaults())?;
 	debug!("Object headers: {:?}", headers);
 
 	let content_md5 = match req.headers().get("content-md5") {
@@ -60,25 +69,354 @@ pub async fn handle_put(
 		content_sha256,
 	)
 	.await
-	.map(|(uuid, md5)| put_response(uuid, md5))
+	.map(|(uuid, md5, quota)| {
+		let mut resp = put_response(uuid, md5);
+		quota.add_headers(resp.headers_mut());
+		resp
+	})
+}
+
+/// Garage extension: append data at the end of an existing object.
+///
+/// The appended data is stored as a new part of the object, the blocks of the
+/// previous version being referenced by the new version without being copied.
+/// `position` must be equal to the current size of the object, which allows
+/// clients to detect concurrent appends: when several appends to an object
+/// are made at the same time, at most one of them succeeds, the others
+/// failing with `PositionNotEqualToLength`. If the object does not exist yet,
+/// this behaves like PutObject (with `position` = 0).
+pub async fn handle_append(
+	garage: Arc<Garage>,
+	req: Request<Body>,
+	bucket: &Bucket,
+	key: &str,
+	position: u64,
+	content_sha256: Option<Hash>,
+) -> Result<Response<Body>, Error> {
+	let quorum = bucket.quorum_policy();
+	// The check for concurrent appends could read from a single node that
+	// has not seen the other append, so that the data of one of them is lost
+	if quorum == QuorumPolicy::One {
+		return Err(Error::bad_request(
+			"Appends are not allowed in buckets whose quorum policy is `one`",
+		));
+	}
+
+	let content_md5 = match req.headers().get("content-md5") {
+		Some(x) => Some(x.to_str()?.to_string()),
+		None => None,
+	};
+
+	let prev_object = garage
+		.object_table
+		.get_with_quorum(&bucket.id, &key.to_string(), quorum)
+		.await?;
+	let prev = prev_object
+		.as_ref()
+		.and_then(|o| o.versions().iter().rev().find(|v| v.is_complete()))
+		.and_then(|v| match &v.state {
+			ObjectVersionState::Complete(
+				d @ (ObjectVersionData::Inline(m, _) | ObjectVersionData::FirstBlock(m, _)),
+			) => Some((v, d, m)),
+			_ => None,
+		});
+	let (prev_version, prev_data, prev_meta) = match prev {
+		Some(x) => x,
+		None => {
+			if position != 0 {
+				return Err(Error::PositionNotEqualToLength(0));
+			}
+			let headers = get_headers(req.headers(), key, &bucket.object_defaults())?;
+			let size = Arc::new(AtomicU64::new(0));
+			let size2 = size.clone();
+			let body = req.into_body().map_err(Error::from).inspect_ok(move |b| {
+				size2.fetch_add(b.len() as u64, Ordering::Relaxed);
+			});
+			let (uuid, etag, quota) = save_stream(
+				garage,
+				headers,
+				body,
+				bucket,
+				key,
+				content_md5,
+				content_sha256,
+			)
+			.await?;
+			let mut resp = append_response(uuid, etag, size.load(Ordering::Relaxed));
+			quota.add_headers(resp.headers_mut());
+			return Ok(resp);
+		}
+	};
+
+	if position != prev_meta.size {
+		return Err(Error::PositionNotEqualToLength(prev_meta.size));
+	}
+
+	// Check quotas with the size announced by the client before storing
+	// anything, and again once the data is stored if the size was not known
+	let announced_size = req
+		.headers()
+		.get("x-amz-decoded-content-length")
+		.or_else(|| req.headers().get(CONTENT_LENGTH))
+		.and_then(|v| v.to_str().ok()?.parse::<u64>().ok());
+	let mut quota = check_quotas(
+		&garage,
+		bucket,
+		key,
+		prev_meta.size + announced_size.unwrap_or(0),
+	)
+	.await?;
+
+	let mut chunker = StreamChunker::new(
+		req.into_body().map_err(Error::from),
+		garage.config.block_size,
+	);
+	let first_block = match chunker.next().await? {
+		Some(b) => b,
+		None => {
+			// Nothing to append
+			return Ok(append_response(
+				prev_version.uuid,
+				prev_meta.etag.clone(),
+				prev_meta.size,
+			));
+		}
+	};
+
+	let version_uuid = gen_uuid();
+	let version_timestamp = std::cmp::max(now_msec(), prev_version.timestamp + 1);
+
+	let mut interrupted_cleanup = InterruptedCleanup::new(
+		garage.clone(),
+		bucket.id,
+		key.into(),
+		version_uuid,
+		version_timestamp,
+	);
+
+	// Mark the new version as uploading, this protects its entry
+	// in the version table until we are done
+	let mut object_version = ObjectVersion {
+		uuid: version_uuid,
+		timestamp: version_timestamp,
+		state: ObjectVersionState::Uploading {
+			headers: prev_meta.headers.clone(),
+			multipart: false,
+			initiator: None,
+		},
+	};
+	let object = Object::new(bucket.id, key.into(), vec![object_version.clone()]);
+	garage
+		.object_table
+		.insert_with_quorum(&object, quorum)
+		.await?;
+
+	let mut version = Version::new(
+		version_uuid,
+		VersionBacklink::Object {
+			bucket_id: bucket.id,
+			key: key.into(),
+		},
+		false,
+	);
+
+	// Reference the data of the previous version in the new one
+	let (first_block_hash, next_part_number) = match prev_data {
+		ObjectVersionData::Inline(_, bytes) => {
+			let bytes = Bytes::from(bytes.clone());
+			let hash = async_blake2sum(bytes.clone()).await;
+			garage
+				.version_table
+				.insert_with_quorum(&version, quorum)
+				.await?;
+			futures::try_join!(
+				garage
+					.block_manager
+					.rpc_put_block_with_quorum(hash, bytes.clone(), quorum)
+					.map_err(Error::from),
+				put_block_meta(&garage, &version, 1, 0, hash, bytes.len() as u64, quorum)
+					.map_err(Error::from),
+			)?;
+			version.blocks.put(
+				VersionBlockKey {
+					part_number: 1,
+					offset: 0,
+				},
+				VersionBlock {
+					hash,
+					size: bytes.len() as u64,
+				},
+			);
+			(hash, 2)
+		}
+		ObjectVersionData::FirstBlock(_, first_block_hash) => {
+			let prev_version = garage
+				.version_table
+				.get_with_quorum(&prev_version.uuid, &EmptyKey, quorum)
+				.await?
+				.ok_or(Error::NoSuchKey)?;
+			for (bk, bv) in prev_version.blocks.items().iter() {
+				version.blocks.put(*bk, *bv);
+			}
+			let block_refs = version
+				.blocks
+				.items()
+				.iter()
+				.map(|(_, vb)| BlockRef {
+					block: vb.hash,
+					version: version_uuid,
+					deleted: false.into(),
+				})
+				.collect::<Vec<_>>();
+			futures::try_join!(
+				garage.version_table.insert_with_quorum(&version, quorum),
+				garage.block_ref_table.insert_many(&block_refs[..]),
+			)?;
+			let last_part_number = version
+				.blocks
+				.items()
+				.iter()
+				.map(|(bk, _)| bk.part_number)
+				.max()
+				.unwrap_or(0);
+			(*first_block_hash, last_part_number + 1)
+		}
+		ObjectVersionData::DeleteMarker => unreachable!(),
+	};
+
+	// Transfer appended data and verify checksum
+	let appended_first_block_hash = async_blake2sum(first_block.clone()).await;
+	let (appended_size, data_md5sum, data_sha256sum) = read_and_put_blocks(
+		&garage,
+		&version,
+		next_part_number,
+		0,
+		first_block,
+		appended_first_block_hash,
+		&mut chunker,
+		quorum,
+	)
+	.await?;
+
+	ensure_checksum_matches(
+		data_md5sum.as_slice(),
+		data_sha256sum,
+		content_md5.as_deref(),
+		content_sha256,
+	)?;
+
+	let total_size = prev_meta.size + appended_size;
+	if announced_size != Some(appended_size) {
+		quota = check_quotas(&garage, bucket, key, total_size).await?;
+	}
+
+	check_no_concurrent_append(&garage, bucket, key, prev_version, version_uuid).await?;
+
+	// The ETag of an appended object is derived from the ETag of the previous
+	// version and the MD5 of the appended data, in the style of multipart ETags
+	let mut etag_md5 = Md5::new();
+	etag_md5.update(prev_meta.etag.as_bytes());
+	etag_md5.update(&data_md5sum[..]);
+	let etag = format!("{}-{}", hex::encode(etag_md5.finalize()), next_part_number);
+
+	object_version.state = ObjectVersionState::Complete(ObjectVersionData::FirstBlock(
+		ObjectVersionMeta {
+			headers: prev_meta.headers.clone(),
+			size: total_size,
+			etag: etag.clone(),
+		},
+		first_block_hash,
+	));
+	let object = Object::new(bucket.id, key.into(), vec![object_version]);
+	garage
+		.object_table
+		.insert_with_quorum(&object, quorum)
+		.await?;
+
+	interrupted_cleanup.cancel();
+
+	let mut resp = append_response(version_uuid, etag, total_size);
+	quota.add_headers(resp.headers_mut());
+	Ok(resp)
+}
+
+/// Check, just before completing an append, that no other version of the
+/// object was stored or started being stored since the version that is
+/// appended to. As each append marks its version as being uploaded before
+/// storing its data, of two concurrent appends, the one that checks last
+/// sees the other, so that they can't both succeed and lose the data of one
+/// of them. Non-multipart versions left uploading by a node that crashed
+/// block appends until they are removed with `cleanup-incomplete-uploads`.
+async fn check_no_concurrent_append(
+	garage: &Garage,
+	bucket: &Bucket,
+	key: &str,
+	prev_version: &ObjectVersion,
+	version_uuid: Uuid,
+) -> Result<(), Error> {
+	let object = garage
+		.object_table
+		.get_with_quorum(&bucket.id, &key.to_string(), bucket.quorum_policy())
+		.await?;
+	let versions = object.as_ref().map(|o| o.versions()).unwrap_or_default();
+
+	let concurrent = versions.iter().any(|v| {
+		v.uuid != version_uuid
+			&& (v.timestamp, v.uuid) > (prev_version.timestamp, prev_version.uuid)
+			&& (v.is_complete() || v.is_uploading(Some(false)))
+	});
+	if concurrent {
+		let size = match versions.iter().rev().find(|v| v.is_complete()) {
+			Some(ObjectVersion {
+				state:
+					ObjectVersionState::Complete(
+						ObjectVersionData::Inline(m, _) | ObjectVersionData::FirstBlock(m, _),
+					),
+				..
+			}) => m.size,
+			_ => 0,
+		};
+		return Err(Error::PositionNotEqualToLength(size));
+	}
+	Ok(())
+}
+
+fn append_response(version_uuid: Uuid, etag: String, next_position: u64) -> Response<Body> {
+	Response::builder()
+		.header("x-amz-version-id", hex::encode(version_uuid))
+		.header("ETag", format!("\"{}\"", etag))
+		.header(X_GARAGE_NEXT_APPEND_POSITION, next_position.to_string())
+		.body(Body::from(vec![]))
+		.unwrap()
 }
 
 pub(crate) async fn save_stream<S: Stream<Item = Result<Bytes, Error>> + Unpin>(
 	garage: Arc<Garage>,
-	headers: ObjectVersionHeaders,
+	mut headers: ObjectVersionHeaders,
 	body: S,
 	bucket: &Bucket,
 	key: &str,
 	content_md5: Option<String>,
 	content_sha256: Option<FixedBytes32>,
-) -> Result<(Uuid, String), Error> {
+) -> Result<(Uuid, String, QuotaStatus), Error> {
 	// Generate identity of new version
 	let version_uuid = gen_uuid();
 	let version_timestamp = now_msec();
+	let quorum = bucket.quorum_policy();
 
-	let mut chunker = StreamChunker::new(body, garage.config.block_size);
+	let mut chunker = StreamChunker::new(body, garage.config.block_size)
+		.with_first_block_sizes(&bucket.first_block_sizes());
 	let first_block = chunker.next().await?.unwrap_or_default();
 
+	// If the content type could not be determined from the request
+	// or the object's key, try to guess it from the object's first bytes
+	if bucket.object_defaults().sniff_content_type && is_generic_content_type(&headers.content_type)
+	{
+		if let Some(ct) = content_type_from_magic(&first_block[..]) {
+			headers.content_type = ct.to_string();
+		}
+	}
+
 	// If body is small enough, store it directly in the object table
 	// as "inline data". We can then return immediately.
 	if first_block.len() < INLINE_THRESHOLD {
@@ -97,7 +435,7 @@ pub(crate) async fn save_stream<S: Stream<Item = Result<Bytes, Error>> + Unpin>(
 			content_sha256,
 		)?;
 
-		check_quotas(&garage, bucket, key, size).await?;
+		let quota = check_quotas(&garage, bucket, key, size).await?;
 
 		let object_version = ObjectVersion {
 			uuid: version_uuid,
@@ -113,21 +451,24 @@ pub(crate) async fn save_stream<S: Stream<Item = Result<Bytes, Error>> + Unpin>(
 		};
 
 		let object = Object::new(bucket.id, key.into(), vec![object_version]);
-		garage.object_table.insert(&object).await?;
+		garage
+			.object_table
+			.insert_with_quorum(&object, quorum)
+			.await?;
 
-		return Ok((version_uuid, data_md5sum_hex));
+		return Ok((version_uuid, data_md5sum_hex, quota));
 	}
 
 	// The following consists in many steps that can each fail.
 	// Keep track that some cleanup will be needed if things fail
 	// before everything is finished (cleanup is done using the Drop trait).
-	let mut interrupted_cleanup = InterruptedCleanup(Some(InterruptedCleanupInner {
-		garage: garage.clone(),
-		bucket_id: bucket.id,
-		key: key.into(),
+	let mut interrupted_cleanup = InterruptedCleanup::new(
+		garage.clone(),
+		bucket.id,
+		key.into(),
 		version_uuid,
 		version_timestamp,
-	}));
+	);
 
 	// Write version identifier in object table so that we have a trace
 	// that we are uploading something
@@ -137,10 +478,14 @@ pub(crate) async fn save_stream<S: Stream<Item = Result<Bytes, Error>> + Unpin>(
 		state: ObjectVersionState::Uploading {
 			headers: headers.clone(),
 			multipart: false,
+			initiator: None,
 		},
 	};
 	let object = Object::new(bucket.id, key.into(), vec![object_version.clone()]);
-	garage.object_table.insert(&object).await?;
+	garage
+		.object_table
+		.insert_with_quorum(&object, quorum)
+		.await?;
 
 	// Initialize corresponding entry in version table
 	// Write this entry now, even with empty block list,
@@ -154,7 +499,10 @@ pub(crate) async fn save_stream<S: Stream<Item = Result<Bytes, Error>> + Unpin>(
 		},
 		false,
 	);
-	garage.version_table.insert(&version).await?;
+	garage
+		.version_table
+		.insert_with_quorum(&version, quorum)
+		.await?;
 
 	// Transfer data and verify checksum
 	let first_block_hash = async_blake2sum(first_block.clone()).await;
@@ -163,9 +511,11 @@ pub(crate) async fn save_stream<S: Stream<Item = Result<Bytes, Error>> + Unpin>(
 		&garage,
 		&version,
 		1,
+		0,
 		first_block,
 		first_block_hash,
 		&mut chunker,
+		quorum,
 	)
 	.await?;
 
@@ -176,7 +526,7 @@ pub(crate) async fn save_stream<S: Stream<Item = Result<Bytes, Error>> + Unpin>(
 		content_sha256,
 	)?;
 
-	check_quotas(&garage, bucket, key, total_size).await?;
+	let quota = check_quotas(&garage, bucket, key, total_size).await?;
 
 	// Save final object state, marked as Complete
 	let md5sum_hex = hex::encode(data_md5sum);
@@ -189,13 +539,16 @@ pub(crate) async fn save_stream<S: Stream<Item = Result<Bytes, Error>> + Unpin>(
 		first_block_hash,
 	));
 	let object = Object::new(bucket.id, key.into(), vec![object_version]);
-	garage.object_table.insert(&object).await?;
+	garage
+		.object_table
+		.insert_with_quorum(&object, quorum)
+		.await?;
 
 	// We were not interrupted, everything went fine.
 	// We won't have to clean up on drop.
 	interrupted_cleanup.cancel();
 
-	Ok((version_uuid, md5sum_hex))
+	Ok((version_uuid, md5sum_hex, quota))
 }
 
 /// Validate MD5 sum against content-md5 header
@@ -208,16 +561,14 @@ pub(crate) fn ensure_checksum_matches(
 ) -> Result<(), Error> {
 	if let Some(expected_sha256) = content_sha256 {
 		if expected_sha256 != data_sha256sum {
-			return Err(Error::bad_request(
-				"Unable to validate x-amz-content-sha256",
-			));
+			return Err(Error::ContentSha256Mismatch);
 		} else {
 			trace!("Successfully validated x-amz-content-sha256");
 		}
 	}
 	if let Some(expected_md5) = content_md5 {
 		if expected_md5.trim_matches('"') != BASE64_STANDARD.encode(data_md5sum) {
-			return Err(Error::bad_request("Unable to validate content-md5"));
+			return Err(Error::BadDigest);
 		} else {
 			trace!("Successfully validated content-md5");
 		}
@@ -225,16 +576,51 @@ pub(crate) fn ensure_checksum_matches(
 	Ok(())
 }
 
-/// Check that inserting this object with this size doesn't exceed bucket quotas
+/// Room left under the bucket quotas once an object has been written,
+/// returned to clients in the x-garage-quota-* headers
+#[derive(Default)]
+pub(crate) struct QuotaStatus {
+	remaining_size: Option<u64>,
+	remaining_objects: Option<u64>,
+	/// Quotas exceeded by the write, that was accepted because quotas are soft
+	exceeded: Vec<Quota>,
+}
+
+impl QuotaStatus {
+	pub(crate) fn add_headers(&self, headers: &mut HeaderMap<HeaderValue>) {
+		if let Some(rs) = self.remaining_size {
+			headers.insert(X_GARAGE_QUOTA_REMAINING_SIZE, rs.into());
+		}
+		if let Some(ro) = self.remaining_objects {
+			headers.insert(X_GARAGE_QUOTA_REMAINING_OBJECTS, ro.into());
+		}
+		if !self.exceeded.is_empty() {
+			let exceeded = self
+				.exceeded
+				.iter()
+				.map(Quota::as_str)
+				.collect::<Vec<_>>()
+				.join(",");
+			headers.insert(
+				X_GARAGE_QUOTA_EXCEEDED,
+				HeaderValue::from_str(&exceeded).unwrap(),
+			);
+		}
+	}
+}
+
+/// Check that inserting this object with this size doesn't exceed bucket quotas.
+/// If the bucket has soft quotas, exceeding them is not an error and is only
+/// reported in the returned status.
 pub(crate) async fn check_quotas(
 	garage: &Arc<Garage>,
 	bucket: &Bucket,
 	key: &str,
 	size: u64,
-) -> Result<(), Error> {
+) -> Result<QuotaStatus, Error> {
 	let quotas = bucket.state.as_option().unwrap().quotas.get();
 	if quotas.max_objects.is_none() && quotas.max_size.is_none() {
-		return Ok(());
+		return Ok(QuotaStatus::default());
 	};
 
 	let key = key.to_string();
@@ -260,36 +646,57 @@ pub(crate) async fn check_quotas(
 	let cnt_obj_diff = 1 - prev_cnt_obj;
 	let cnt_size_diff = size as i64 - prev_cnt_size;
 
+	let mut status = QuotaStatus::default();
+
 	if let Some(mo) = quotas.max_objects {
 		let current_objects = counters.get(OBJECTS).cloned().unwrap_or_default();
 		if cnt_obj_diff > 0 && current_objects + cnt_obj_diff > mo as i64 {
-			return Err(Error::forbidden(format!(
-				"Object quota is reached, maximum objects for this bucket: {}",
-				mo
-			)));
+			let exceeded = QuotaExceeded {
+				quota: Quota::Objects,
+				current: std::cmp::max(current_objects, 0) as u64,
+				limit: mo,
+				requested: cnt_obj_diff as u64,
+			};
+			if !quotas.soft {
+				return Err(Error::QuotaExceeded(exceeded));
+			}
+			status.exceeded.push(Quota::Objects);
 		}
+		status.remaining_objects =
+			Some(std::cmp::max(mo as i64 - current_objects - cnt_obj_diff, 0) as u64);
 	}
 
 	if let Some(ms) = quotas.max_size {
 		let current_size = counters.get(BYTES).cloned().unwrap_or_default();
 		if cnt_size_diff > 0 && current_size + cnt_size_diff > ms as i64 {
-			return Err(Error::forbidden(format!(
-				"Bucket size quota is reached, maximum total size of objects for this bucket: {}. The bucket is already {} bytes, and this object would add {} bytes.",
-				ms, current_size, size
-			)));
+			let exceeded = QuotaExceeded {
+				quota: Quota::Size,
+				current: std::cmp::max(current_size, 0) as u64,
+				limit: ms,
+				requested: size,
+			};
+			if !quotas.soft {
+				return Err(Error::QuotaExceeded(exceeded));
+			}
+			status.exceeded.push(Quota::Size);
 		}
+		status.remaining_size =
+			Some(std::cmp::max(ms as i64 - current_size - cnt_size_diff, 0) as u64);
 	}
 
-	Ok(())
+	Ok(status)
 }
 
+#[allow(clippy::too_many_arguments)]
 pub(crate) async fn read_and_put_blocks<S: Stream<Item = Result<Bytes, Error>> + Unpin>(
 	garage: &Garage,
 	version: &Version,
 	part_number: u64,
+	offset: u64,
 	first_block: Bytes,
 	first_block_hash: Hash,
 	chunker: &mut StreamChunker<S>,
+	quorum: QuorumPolicy,
 ) -> Result<(u64, GenericArray<u8, typenum::U16>, Hash), Error> {
 	let tracer = opentelemetry::global::tracer("garage");
 
@@ -305,25 +712,35 @@ pub(crate) async fn read_and_put_blocks<S: Stream<Item = Result<Bytes, Error>> +
 	))
 	.await;
 
+	// Up to put_blocks_max_parallel blocks are written at the same time,
+	// while the next block is being read from the request body
+	let max_parallel = std::cmp::max(1, garage.config.s3_api.put_blocks_max_parallel);
+	let mut put_blocks = FuturesUnordered::new();
+
 	let mut next_offset = first_block.len();
-	let mut put_curr_version_block = put_block_meta(
+	put_blocks.push(put_block_and_meta(
 		garage,
 		version,
 		part_number,
-		0,
+		offset,
 		first_block_hash,
-		first_block.len() as u64,
-	);
-	let mut put_curr_block = garage
-		.block_manager
-		.rpc_put_block(first_block_hash, first_block);
+		first_block,
+		quorum,
+	));
 
 	loop {
-		let (_, _, next_block) = futures::try_join!(
-			put_curr_block.map_err(Error::from),
-			put_curr_version_block.map_err(Error::from),
-			chunker.next(),
-		)?;
+		let next_block = loop {
+			if put_blocks.len() >= max_parallel {
+				if let Some(res) = put_blocks.next().await {
+					res?;
+				}
+				continue;
+			}
+			tokio::select! {
+				Some(res) = put_blocks.next() => res?,
+				block = chunker.next() => break block?,
+			}
+		};
 		if let Some(block) = next_block {
 			let (_, _, block_hash) = futures::future::join3(
 				md5hasher.update(block.clone()),
@@ -335,20 +752,23 @@ pub(crate) async fn read_and_put_blocks<S: Stream<Item = Result<Bytes, Error>> +
 			))
 			.await;
 			let block_len = block.len();
-			put_curr_version_block = put_block_meta(
+			put_blocks.push(put_block_and_meta(
 				garage,
 				version,
 				part_number,
-				next_offset as u64,
+				offset + next_offset as u64,
 				block_hash,
-				block_len as u64,
-			);
-			put_curr_block = garage.block_manager.rpc_put_block(block_hash, block);
+				block,
+				quorum,
+			));
 			next_offset += block_len;
 		} else {
 			break;
 		}
 	}
+	while let Some(res) = put_blocks.next().await {
+		res?;
+	}
 
 	let total_size = next_offset as u64;
 	let data_md5sum = md5hasher.finalize().await;
@@ -359,6 +779,26 @@ pub(crate) async fn read_and_put_blocks<S: Stream<Item = Result<Bytes, Error>> +
 	Ok((total_size, data_md5sum, data_sha256sum))
 }
 
+/// Store a block, then add it to the version: blocks referenced by a version
+/// are thus always fully stored, even when the upload is interrupted
+pub(crate) async fn put_block_and_meta(
+	garage: &Garage,
+	version: &Version,
+	part_number: u64,
+	offset: u64,
+	hash: Hash,
+	block: Bytes,
+	quorum: QuorumPolicy,
+) -> Result<(), Error> {
+	let size = block.len() as u64;
+	garage
+		.block_manager
+		.rpc_put_block_with_quorum(hash, block, quorum)
+		.await?;
+	put_block_meta(garage, version, part_number, offset, hash, size, quorum).await?;
+	Ok(())
+}
+
 async fn put_block_meta(
 	garage: &Garage,
 	version: &Version,
@@ -366,6 +806,7 @@ async fn put_block_meta(
 	offset: u64,
 	hash: Hash,
 	size: u64,
+	quorum: QuorumPolicy,
 ) -> Result<(), GarageError> {
 	let mut version = version.clone();
 	version.blocks.put(
@@ -383,8 +824,10 @@ async fn put_block_meta(
 	};
 
 	futures::try_join!(
-		garage.version_table.insert(&version),
-		garage.block_ref_table.insert(&block_ref),
+		garage.version_table.insert_with_quorum(&version, quorum),
+		garage
+			.block_ref_table
+			.insert_with_quorum(&block_ref, quorum),
 	)?;
 	Ok(())
 }
@@ -393,6 +836,7 @@ pub(crate) struct StreamChunker<S: Stream<Item = Result<Bytes, Error>>> {
 	stream: S,
 	read_all: bool,
 	block_size: usize,
+	first_block_sizes: VecDeque<usize>,
 	buf: BytesBuf,
 }
 
@@ -402,12 +846,29 @@ impl<S: Stream<Item = Result<Bytes, Error>> + Unpin> StreamChunker<S> {
 			stream,
 			read_all: false,
 			block_size,
+			first_block_sizes: VecDeque::new(),
 			buf: BytesBuf::new(),
 		}
 	}
 
+	/// Use smaller sizes for the first blocks of the stream, so that the
+	/// first blocks of large objects are stored sooner. Sizes are bounded
+	/// by the block size, and by the threshold under which data is inlined.
+	pub(crate) fn with_first_block_sizes(mut self, sizes: &[u64]) -> Self {
+		self.first_block_sizes = sizes
+			.iter()
+			.map(|s| (*s as usize).max(INLINE_THRESHOLD).min(self.block_size))
+			.collect();
+		self
+	}
+
 	pub(crate) async fn next(&mut self) -> Result<Option<Bytes>, Error> {
-		while !self.read_all && self.buf.len() < self.block_size {
+		let block_size = self
+			.first_block_sizes
+			.front()
+			.copied()
+			.unwrap_or(self.block_size);
+		while !self.read_all && self.buf.len() < block_size {
 			if let Some(block) = self.stream.next().await {
 				let bytes = block?;
 				trace!("Body next: {} bytes", bytes.len());
@@ -420,7 +881,8 @@ impl<S: Stream<Item = Result<Bytes, Error>> + Unpin> StreamChunker<S> {
 		if self.buf.is_empty() {
 			Ok(None)
 		} else {
-			Ok(Some(self.buf.take_max(self.block_size)))
+			self.first_block_sizes.pop_front();
+			Ok(Some(self.buf.take_max(block_size)))
 		}
 	}
 }
@@ -433,7 +895,17 @@ pub fn put_response(version_uuid: Uuid, md5sum_hex: String) -> Response<Body> {
 		.unwrap()
 }
 
-struct InterruptedCleanup(Option<InterruptedCleanupInner>);
+/// ETag of an object whose content was not received in a single request,
+/// derived from the hashes of its blocks in the style of multipart ETags
+pub(crate) fn blocks_etag(hashes: &[Hash]) -> String {
+	let mut etag_md5 = Md5::new();
+	for hash in hashes.iter() {
+		etag_md5.update(hash.as_slice());
+	}
+	format!("{}-{}", hex::encode(etag_md5.finalize()), hashes.len())
+}
+
+pub(crate) struct InterruptedCleanup(Option<InterruptedCleanupInner>);
 struct InterruptedCleanupInner {
 	garage: Arc<Garage>,
 	bucket_id: Uuid,
@@ -443,7 +915,24 @@ struct InterruptedCleanupInner {
 }
 
 impl InterruptedCleanup {
-	fn cancel(&mut self) {
+	/// Mark the given object version as aborted when dropped, unless cancelled
+	pub(crate) fn new(
+		garage: Arc<Garage>,
+		bucket_id: Uuid,
+		key: String,
+		version_uuid: Uuid,
+		version_timestamp: u64,
+	) -> Self {
+		Self(Some(InterruptedCleanupInner {
+			garage,
+			bucket_id,
+			key,
+			version_uuid,
+			version_timestamp,
+		}))
+	}
+
+	pub(crate) fn cancel(&mut self) {
 		drop(self.0.take());
 	}
 }
@@ -475,8 +964,22 @@ pub(crate) fn get_mime_type(headers: &HeaderMap<HeaderValue>) -> Result<String,
 		.to_string())
 }
 
-pub(crate) fn get_headers(headers: &HeaderMap<HeaderValue>) -> Result<ObjectVersionHeaders, Error> {
-	let content_type = get_mime_type(headers)?;
+/// Headers of an object uploaded with the given request headers, after applying
+/// the object defaults of its bucket
+pub(crate) fn get_headers(
+	headers: &HeaderMap<HeaderValue>,
+	key: &str,
+	defaults: &ObjectDefaults,
+) -> Result<ObjectVersionHeaders, Error> {
+	let mut content_type = get_mime_type(headers)?;
+	if defaults.content_type_from_extension
+		|| (defaults.sniff_content_type && is_generic_content_type(&content_type))
+	{
+		if let Some(ct) = content_type_from_extension(key) {
+			content_type = ct.to_string();
+		}
+	}
+
 	let mut other = BTreeMap::new();
 
 	// Preserve standard headers
@@ -500,12 +1003,49 @@ pub(crate) fn get_headers(headers: &HeaderMap<HeaderValue>) -> Result<ObjectVers
 		}
 	}
 
-	// Preserve x-amz-meta- headers
+	// Apply default headers of the bucket
+	let default_headers = [
+		(hyper::header::CACHE_CONTROL, &defaults.cache_control),
+		(
+			hyper::header::CONTENT_DISPOSITION,
+			&defaults.content_disposition,
+		),
+	];
+	for (h, v) in default_headers {
+		if let Some(v) = v {
+			other.entry(h.to_string()).or_insert_with(|| v.to_string());
+		}
+	}
+
+	// Record the storage class, if it is not the default one
+	if let Some(class) = headers.get(STORAGE_CLASS_HEADER) {
+		let class = class.to_str()?;
+		if STORAGE_CLASSES.iter().any(|(c, _, _)| *c == class) {
+			other.insert(STORAGE_CLASS_HEADER.to_string(), class.to_string());
+		} else if class != DEFAULT_STORAGE_CLASS {
+			return Err(Error::bad_request(format!(
+				"Invalid storage class: {}",
+				class
+			)));
+		}
+	}
+
+	// Preserve x-amz-meta- headers, except those stripped by the bucket.
+	// Values of headers given several times are joined, as in S3.
 	for (k, v) in headers.iter() {
-		if k.as_str().starts_with("x-amz-meta-") {
+		if let Some(meta_name) = k.as_str().strip_prefix("x-amz-meta-") {
+			if defaults.strip_meta.iter().any(|m| m == meta_name) {
+				continue;
+			}
 			match v.to_str() {
 				Ok(v_str) => {
-					other.insert(k.to_string(), v_str.to_string());
+					other
+						.entry(k.to_string())
+						.and_modify(|prev: &mut String| {
+							prev.push(',');
+							prev.push_str(v_str);
+						})
+						.or_insert_with(|| v_str.to_string());
 				}
 				Err(e) => {
 					warn!("Discarding header {}, error in .to_str(): {}", k, e);
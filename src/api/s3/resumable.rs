@@ -0,0 +1,363 @@
+//! Garage extension to resume interrupted PutObject requests
+use std::sync::Arc;
+
+use futures::prelude::*;
+use hyper::{Body, Request, Response};
+use md5::{Digest as Md5Digest, Md5};
+
+use garage_table::replication::QuorumPolicy;
+use garage_table::*;
+use garage_util::async_hash::*;
+use garage_util::data::*;
+use garage_util::time::*;
+
+use garage_model::bucket_table::Bucket;
+use garage_model::garage::Garage;
+use garage_model::s3::block_ref_table::*;
+use garage_model::s3::object_table::*;
+use garage_model::s3::version_table::*;
+
+use crate::s3::error::*;
+use crate::s3::put::*;
+
+pub const X_GARAGE_UPLOAD_OFFSET: &str = "x-garage-upload-offset";
+
+pub fn parse_upload_offset_header(req: &Request<Body>) -> Result<Option<u64>, Error> {
+	match req.headers().get(X_GARAGE_UPLOAD_OFFSET) {
+		None => Ok(None),
+		Some(v) => {
+			Ok(Some(v.to_str()?.parse().ok_or_bad_request(
+				"Invalid x-garage-upload-offset header",
+			)?))
+		}
+	}
+}
+
+/// Handle a PutObject request with the x-garage-upload-offset header.
+///
+/// With an offset of 0, a new resumable upload of the object is started.
+/// If the request is interrupted, the data that was stored is kept, and the
+/// upload can be resumed by sending the rest of the object in another request
+/// whose offset is the size of the data kept. When the offset does not match,
+/// an InvalidUploadOffset error is returned with the size of the data kept
+/// in its x-garage-upload-offset header. The object is complete once a request
+/// ends normally.
+///
+/// Each request is stored in a new version of the object, that references the
+/// blocks kept from the previous one: the blocks stored at the beginning of
+/// the interrupted request, up to the first block that is missing.
+pub async fn handle_put_resumable(
+	garage: Arc<Garage>,
+	req: Request<Body>,
+	bucket: &Bucket,
+	key: &str,
+	offset: u64,
+	content_sha256: Option<Hash>,
+) -> Result<Response<Body>, Error> {
+	let quorum = bucket.quorum_policy();
+
+	let content_md5 = match req.headers().get("content-md5") {
+		Some(x) => Some(x.to_str()?.to_string()),
+		None => None,
+	};
+
+	let object = garage
+		.object_table
+		.get_with_quorum(&bucket.id, &key.to_string(), quorum)
+		.await?;
+	let prev = find_resumable_upload(&garage, object.as_ref(), quorum).await?;
+
+	let (headers, kept_blocks) = match (offset, &prev) {
+		(0, _) => (
+			get_headers(req.headers(), key, &bucket.object_defaults())?,
+			vec![],
+		),
+		(_, Some((prev_version, prev_blocks))) => {
+			let kept_blocks = complete_blocks(prev_blocks);
+			let kept_size = kept_blocks.iter().map(|(_, vb)| vb.size).sum::<u64>();
+			if offset != kept_size {
+				return Err(Error::InvalidUploadOffset(kept_size));
+			}
+			let headers = match &prev_version.state {
+				ObjectVersionState::Uploading { headers, .. } => headers.clone(),
+				_ => unreachable!(),
+			};
+			(headers, kept_blocks)
+		}
+		(_, None) => return Err(Error::InvalidUploadOffset(0)),
+	};
+
+	// Create a new version for this request, that references the blocks
+	// kept from the previous request, and abort the previous version
+	let version_uuid = gen_uuid();
+	let version_timestamp = match &prev {
+		Some((pv, _)) => std::cmp::max(now_msec(), pv.timestamp + 1),
+		None => now_msec(),
+	};
+
+	let mut version = Version::new(
+		version_uuid,
+		VersionBacklink::Object {
+			bucket_id: bucket.id,
+			key: key.into(),
+		},
+		false,
+	);
+	version.resumable.set();
+	for (bk, bv) in kept_blocks.iter() {
+		version.blocks.put(*bk, *bv);
+	}
+	let block_refs = kept_blocks
+		.iter()
+		.map(|(_, vb)| BlockRef {
+			block: vb.hash,
+			version: version_uuid,
+			deleted: false.into(),
+		})
+		.collect::<Vec<_>>();
+	futures::try_join!(
+		garage.version_table.insert_with_quorum(&version, quorum),
+		garage.block_ref_table.insert_many(&block_refs[..]),
+	)?;
+
+	let mut object_version = ObjectVersion {
+		uuid: version_uuid,
+		timestamp: version_timestamp,
+		state: ObjectVersionState::Uploading {
+			headers: headers.clone(),
+			multipart: false,
+			initiator: None,
+		},
+	};
+	let mut versions = vec![object_version.clone()];
+	if let Some((pv, _)) = &prev {
+		versions.push(ObjectVersion {
+			uuid: pv.uuid,
+			timestamp: pv.timestamp,
+			state: ObjectVersionState::Aborted,
+		});
+	}
+	let object = Object::new(bucket.id, key.into(), versions);
+	garage
+		.object_table
+		.insert_with_quorum(&object, quorum)
+		.await?;
+
+	// Store the data of this request. If it is interrupted, the version stays
+	// in the uploading state so that the upload can be resumed.
+	let mut chunker = StreamChunker::new(
+		req.into_body().map_err(Error::from),
+		garage.config.block_size,
+	);
+	if offset == 0 {
+		chunker = chunker.with_first_block_sizes(&bucket.first_block_sizes());
+	}
+	let (size, data_md5sum) = match chunker.next().await? {
+		Some(first_block) => {
+			let first_block_hash = async_blake2sum(first_block.clone()).await;
+			let (size, data_md5sum, data_sha256sum) = read_and_put_blocks(
+				&garage,
+				&version,
+				1,
+				offset,
+				first_block,
+				first_block_hash,
+				&mut chunker,
+				quorum,
+			)
+			.await?;
+
+			// The data of a request that does not match its checksums has
+			// already been stored: the upload cannot be resumed after it.
+			if let Err(e) = ensure_checksum_matches(
+				data_md5sum.as_slice(),
+				data_sha256sum,
+				content_md5.as_deref(),
+				content_sha256,
+			) {
+				abort_version(&garage, bucket, key, &object_version).await?;
+				return Err(e);
+			}
+			(size, data_md5sum)
+		}
+		None => (0, Md5::new().finalize()),
+	};
+
+	let total_size = offset + size;
+	let quota = match check_quotas(&garage, bucket, key, total_size).await {
+		Ok(q) => q,
+		Err(e) => {
+			abort_version(&garage, bucket, key, &object_version).await?;
+			return Err(e);
+		}
+	};
+
+	let version = garage
+		.version_table
+		.get_with_quorum(&version_uuid, &EmptyKey, quorum)
+		.await?
+		.ok_or_internal_error("Version of resumable upload not found")?;
+
+	let (data, etag) = match version.blocks.items().first() {
+		None => {
+			let deleted_version = Version::new(version_uuid, version.backlink.clone(), true);
+			garage
+				.version_table
+				.insert_with_quorum(&deleted_version, quorum)
+				.await?;
+			let etag = hex::encode(data_md5sum);
+			let meta = ObjectVersionMeta {
+				headers,
+				size: 0,
+				etag: etag.clone(),
+			};
+			(ObjectVersionData::Inline(meta, vec![]), etag)
+		}
+		Some((_, first_block)) => {
+			// When the object was uploaded in several requests, the MD5 of its
+			// whole content is not known, and the ETag is derived from the
+			// hashes of its blocks in the style of multipart ETags
+			let etag = if offset == 0 {
+				hex::encode(data_md5sum)
+			} else {
+				let hashes = version
+					.blocks
+					.items()
+					.iter()
+					.map(|(_, vb)| vb.hash)
+					.collect::<Vec<_>>();
+				blocks_etag(&hashes)
+			};
+			let meta = ObjectVersionMeta {
+				headers,
+				size: total_size,
+				etag: etag.clone(),
+			};
+			(ObjectVersionData::FirstBlock(meta, first_block.hash), etag)
+		}
+	};
+
+	object_version.state = ObjectVersionState::Complete(data);
+	let object = Object::new(bucket.id, key.into(), vec![object_version]);
+	garage
+		.object_table
+		.insert_with_quorum(&object, quorum)
+		.await?;
+
+	let mut resp = put_response(version_uuid, etag);
+	quota.add_headers(resp.headers_mut());
+	Ok(resp)
+}
+
+/// Find the latest resumable upload of an object that is still in progress,
+/// with its version
+async fn find_resumable_upload(
+	garage: &Garage,
+	object: Option<&Object>,
+	quorum: QuorumPolicy,
+) -> Result<Option<(ObjectVersion, Version)>, Error> {
+	let uploading = object
+		.map(|o| o.versions())
+		.unwrap_or_default()
+		.iter()
+		.rev()
+		.filter(|v| v.is_uploading(Some(false)));
+	for object_version in uploading {
+		let version = garage
+			.version_table
+			.get_with_quorum(&object_version.uuid, &EmptyKey, quorum)
+			.await?;
+		if let Some(v) = version {
+			if v.resumable.get() && !v.deleted.get() {
+				return Ok(Some((object_version.clone(), v)));
+			}
+		}
+	}
+	Ok(None)
+}
+
+/// Blocks at the beginning of a version that were all stored.
+///
+/// Blocks are only added to a version once they are stored, but several blocks
+/// are written at the same time: when a request is interrupted, the blocks
+/// that were stored are not necessarily contiguous.
+fn complete_blocks(version: &Version) -> Vec<(VersionBlockKey, VersionBlock)> {
+	let mut blocks = vec![];
+	let mut offset = 0;
+	for (bk, bv) in version.blocks.items().iter() {
+		if bk.part_number != 1 || bk.offset != offset {
+			break;
+		}
+		offset += bv.size;
+		blocks.push((*bk, *bv));
+	}
+	blocks
+}
+
+async fn abort_version(
+	garage: &Garage,
+	bucket: &Bucket,
+	key: &str,
+	object_version: &ObjectVersion,
+) -> Result<(), Error> {
+	let object_version = ObjectVersion {
+		uuid: object_version.uuid,
+		timestamp: object_version.timestamp,
+		state: ObjectVersionState::Aborted,
+	};
+	let object = Object::new(bucket.id, key.into(), vec![object_version]);
+	garage.object_table.insert(&object).await?;
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn version_with_blocks(blocks: &[(u64, u64)]) -> Version {
+		let mut version = Version::new(
+			gen_uuid(),
+			VersionBacklink::Object {
+				bucket_id: gen_uuid(),
+				key: "test".into(),
+			},
+			false,
+		);
+		for (offset, size) in blocks {
+			version.blocks.put(
+				VersionBlockKey {
+					part_number: 1,
+					offset: *offset,
+				},
+				VersionBlock {
+					hash: gen_uuid(),
+					size: *size,
+				},
+			);
+		}
+		version
+	}
+
+	#[test]
+	fn test_complete_blocks() {
+		let offsets = |v: &Version| {
+			complete_blocks(v)
+				.iter()
+				.map(|(bk, _)| bk.offset)
+				.collect::<Vec<_>>()
+		};
+
+		assert!(offsets(&version_with_blocks(&[])).is_empty());
+		assert_eq!(offsets(&version_with_blocks(&[(0, 10)])), vec![0]);
+		assert_eq!(
+			offsets(&version_with_blocks(&[(0, 10), (10, 10), (20, 10)])),
+			vec![0, 10, 20]
+		);
+		// Blocks after a gap are ignored
+		assert_eq!(
+			offsets(&version_with_blocks(&[(0, 10), (10, 10), (30, 10)])),
+			vec![0, 10]
+		);
+		assert!(offsets(&version_with_blocks(&[(10, 10)])).is_empty());
+	}
+}
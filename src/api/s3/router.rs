@@ -21,6 +21,11 @@ pub enum Endpoint {
 		key: String,
 		upload_id: String,
 	},
+	// Garage extension, modeled after Alibaba OSS's AppendObject
+	AppendObject {
+		key: String,
+		position: u64,
+	},
 	CompleteMultipartUpload {
 		key: String,
 		upload_id: String,
@@ -429,6 +434,7 @@ impl Endpoint {
 			(query.keyword.take().unwrap_or_default(), key, query, None),
 			key: [
 				EMPTY if upload_id  => CompleteMultipartUpload (query::upload_id),
+				APPEND => AppendObject (parse::position),
 				RESTORE => RestoreObject (query_opt::version_id),
 				SELECT => SelectObjectContent (query::select_type),
 				UPLOADS => CreateMultipartUpload,
@@ -524,6 +530,7 @@ impl Endpoint {
 			key,
 			[
 				AbortMultipartUpload,
+				AppendObject,
 				CompleteMultipartUpload,
 				CopyObject,
 				CreateMultipartUpload,
@@ -628,6 +635,7 @@ generateQueryParameters! {
 		"accelerate" => ACCELERATE,
 		"acl" => ACL,
 		"analytics" => ANALYTICS,
+		"append" => APPEND,
 		"cors" => CORS,
 		"delete" => DELETE,
 		"encryption" => ENCRYPTION,
@@ -670,6 +678,7 @@ generateQueryParameters! {
 		"max-uploads" => max_uploads,
 		"partNumber" => part_number,
 		"part-number-marker" => part_number_marker,
+		"position" => position,
 		"prefix" => prefix,
 		"select-type" => select_type,
 		"start-after" => start_after,
@@ -1073,4 +1082,21 @@ mod tests {
 			Authorization::Read
 		));
 	}
+
+	#[test]
+	fn test_garage_extensions() {
+		assert_eq!(
+			parse("POST", "/my_bucket/my/log?append&position=42", None, None).0,
+			Endpoint::AppendObject {
+				key: "my/log".to_string(),
+				position: 42
+			}
+		);
+		assert!(matches!(
+			parse("POST", "/my_bucket/my/log?append&position=0", None, None)
+				.0
+				.authorization_type(),
+			Authorization::Write
+		));
+	}
 }
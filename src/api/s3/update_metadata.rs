@@ -0,0 +1,448 @@
+//! Update of the metadata of all the objects of a bucket whose key starts
+//! with a prefix, e.g. to set a Cache-Control header or fix Content-Types
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Instant;
+
+use async_trait::async_trait;
+use futures::future::join_all;
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+
+use garage_table::*;
+use garage_util::background::*;
+use garage_util::data::*;
+use garage_util::error::Error as GarageError;
+
+use garage_model::garage::Garage;
+use garage_model::job::*;
+use garage_model::s3::object_table::*;
+
+use crate::common_error::CommonError;
+use crate::s3::copy::copy_object;
+use crate::s3::error::*;
+use crate::s3::mime::content_type_from_extension;
+
+/// Maximum number of objects listed in each batch
+const BATCH_SIZE: usize = 100;
+
+/// Number of objects updated at the same time
+const CONCURRENCY: usize = 8;
+
+/// Kind of the jobs updating the metadata of objects
+pub const JOB_KIND: &str = "update-metadata";
+
+/// Headers of objects that can be set or removed, besides x-amz-meta-*
+const UPDATABLE_HEADERS: &[&str] = &[
+	"cache-control",
+	"content-disposition",
+	"content-encoding",
+	"content-language",
+	"expires",
+];
+
+/// Changes made to the metadata of objects. They are saved in the job record
+/// of the update, to resume it after a restart.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetadataUpdateParams {
+	/// Only update objects whose key starts with this prefix
+	pub prefix: String,
+	/// Content-Type set on the objects
+	pub content_type: Option<String>,
+	/// Whether the Content-Type of the objects is determined from the
+	/// extension of their key; objects whose extension is not known keep
+	/// their Content-Type
+	pub content_type_from_extension: bool,
+	/// Headers set on the objects, by lowercase name
+	pub set_headers: BTreeMap<String, String>,
+	/// Headers removed from the objects, by lowercase name
+	pub remove_headers: Vec<String>,
+	/// Maximum number of objects processed per second
+	pub rate: u64,
+}
+
+impl MetadataUpdateParams {
+	/// Check that only headers that are stored with objects are changed,
+	/// and normalize their names
+	pub fn validate(mut self) -> Result<Self, CommonError> {
+		let check = |name: &str| {
+			let name = name.to_ascii_lowercase();
+			let valid = UPDATABLE_HEADERS.contains(&name.as_str())
+				|| (name.len() > "x-amz-meta-".len() && name.starts_with("x-amz-meta-"));
+			if valid && http::header::HeaderName::from_bytes(name.as_bytes()).is_ok() {
+				Ok(name)
+			} else {
+				Err(CommonError::bad_request(format!(
+					"Header {} cannot be changed",
+					name
+				)))
+			}
+		};
+		let mut set_headers = BTreeMap::new();
+		for (name, value) in self.set_headers.into_iter() {
+			if http::header::HeaderValue::from_str(&value).is_err() {
+				return Err(CommonError::bad_request(format!(
+					"Invalid value for header {}",
+					name
+				)));
+			}
+			set_headers.insert(check(&name)?, value);
+		}
+		self.set_headers = set_headers;
+		self.remove_headers = self
+			.remove_headers
+			.iter()
+			.map(|name| check(name))
+			.collect::<Result<_, _>>()?;
+
+		if let Some(ct) = &self.content_type {
+			if http::header::HeaderValue::from_str(ct).is_err() {
+				return Err(CommonError::bad_request("Invalid Content-Type"));
+			}
+		}
+		if self.content_type.is_some() && self.content_type_from_extension {
+			return Err(CommonError::bad_request(
+				"The Content-Type cannot be both given and determined from the extension",
+			));
+		}
+		if self.rate == 0 {
+			return Err(CommonError::bad_request("rate must be at least 1"));
+		}
+		Ok(self)
+	}
+
+	/// Headers of an object after the update
+	fn apply(&self, key: &str, headers: &ObjectVersionHeaders) -> ObjectVersionHeaders {
+		let mut headers = headers.clone();
+		if let Some(ct) = &self.content_type {
+			headers.content_type = ct.clone();
+		} else if self.content_type_from_extension {
+			if let Some(ct) = content_type_from_extension(key) {
+				headers.content_type = ct.to_string();
+			}
+		}
+		for name in self.remove_headers.iter() {
+			headers.other.remove(name);
+		}
+		for (name, value) in self.set_headers.iter() {
+			headers.other.insert(name.clone(), value.clone());
+		}
+		headers
+	}
+}
+
+#[derive(Serialize, Deserialize)]
+struct MetadataUpdateJobParams {
+	bucket_id: Uuid,
+	params: MetadataUpdateParams,
+}
+
+#[derive(Serialize, Deserialize)]
+struct MetadataUpdateCheckpoint {
+	last_key: Option<String>,
+	updated: u64,
+	unchanged: u64,
+	failed: u64,
+}
+
+/// Start an update of the metadata of the objects of a bucket, as a job of
+/// the job queue
+pub fn start_metadata_update(
+	garage: &Arc<Garage>,
+	bg: &BackgroundRunner,
+	bucket_id: Uuid,
+	params: MetadataUpdateParams,
+) -> Result<JobRecord, GarageError> {
+	let description = format!(
+		"Update of the metadata of objects {:?} of bucket {:?}",
+		params.prefix, bucket_id
+	);
+	let job_params = MetadataUpdateJobParams {
+		bucket_id,
+		params: params.clone(),
+	};
+	let worker = MetadataUpdateWorker::new(garage.clone(), bucket_id, params);
+	garage
+		.jobs
+		.start(bg, JOB_KIND, description, &job_params, Box::new(worker))
+}
+
+/// Register the metadata update jobs in the job queue, so that they are
+/// resumed after a restart
+pub fn register_job_kind(jobs: &JobQueue) {
+	jobs.register_kind(JOB_KIND, resume_metadata_update);
+}
+
+fn resume_metadata_update(
+	garage: &Arc<Garage>,
+	record: &JobRecord,
+) -> Result<Box<dyn JobWorker>, GarageError> {
+	let job_params = record.params::<MetadataUpdateJobParams>()?;
+	let mut worker =
+		MetadataUpdateWorker::new(garage.clone(), job_params.bucket_id, job_params.params);
+	if let Some(checkpoint) = record.checkpoint::<MetadataUpdateCheckpoint>()? {
+		worker.last_key = checkpoint.last_key;
+		worker.updated = checkpoint.updated;
+		worker.unchanged = checkpoint.unchanged;
+		worker.failed = checkpoint.failed;
+	}
+	Ok(Box::new(worker))
+}
+
+/// Worker that updates the metadata of the objects of a bucket.
+///
+/// Each object is copied onto itself with its new metadata, as a CopyObject
+/// call with the REPLACE metadata directive would: only metadata is written,
+/// the new version referencing the data blocks of the previous one. Objects
+/// whose metadata would not change are left as they are.
+pub struct MetadataUpdateWorker {
+	garage: Arc<Garage>,
+	bucket_id: Uuid,
+	params: MetadataUpdateParams,
+
+	/// Key of the last object processed
+	last_key: Option<String>,
+	updated: u64,
+	unchanged: u64,
+	failed: u64,
+	last_failure: Option<String>,
+}
+
+impl MetadataUpdateWorker {
+	pub fn new(garage: Arc<Garage>, bucket_id: Uuid, params: MetadataUpdateParams) -> Self {
+		Self {
+			garage,
+			bucket_id,
+			params,
+			last_key: None,
+			updated: 0,
+			unchanged: 0,
+			failed: 0,
+			last_failure: None,
+		}
+	}
+
+	/// Update the metadata of an object, if it changes.
+	/// Returns whether the object was updated.
+	async fn update_object(&self, key: &str) -> Result<bool, Error> {
+		// Read the object again, as a new version might have been written
+		// since it was listed
+		let object = match self
+			.garage
+			.object_table
+			.get(&self.bucket_id, &key.to_string())
+			.await?
+		{
+			Some(o) => o,
+			None => return Ok(false),
+		};
+		let meta = match object.versions().iter().rev().find(|v| v.is_complete()) {
+			Some(ObjectVersion {
+				state:
+					ObjectVersionState::Complete(
+						ObjectVersionData::Inline(meta, _) | ObjectVersionData::FirstBlock(meta, _),
+					),
+				..
+			}) => meta,
+			_ => return Ok(false),
+		};
+
+		let headers = self.params.apply(key, &meta.headers);
+		if headers == meta.headers {
+			return Ok(false);
+		}
+		let new_meta = ObjectVersionMeta {
+			headers,
+			..meta.clone()
+		};
+		copy_object(&self.garage, &object, new_meta, self.bucket_id, key, false).await?;
+		Ok(true)
+	}
+
+	/// Update the objects of the next batch, returning the number of
+	/// objects processed and whether the end of the prefix was reached
+	async fn update_batch(&mut self) -> Result<(usize, bool), GarageError> {
+		let limit = (self.params.rate as usize).clamp(1, BATCH_SIZE);
+		// Start after the last key processed, i.e. at the key that follows it
+		// in lexicographic order
+		let start = match &self.last_key {
+			Some(k) => format!("{}\0", k),
+			None => self.params.prefix.clone(),
+		};
+		let objects = self
+			.garage
+			.object_table
+			.get_range(
+				&self.bucket_id,
+				Some(start),
+				Some(ObjectFilter::IsData),
+				limit,
+				EnumerationOrder::Forward,
+			)
+			.await?;
+
+		let keys = objects
+			.iter()
+			.map(|o| o.key.clone())
+			.take_while(|k| k.starts_with(&self.params.prefix))
+			.collect::<Vec<_>>();
+		let finished = objects.len() < limit || keys.len() < objects.len();
+
+		let mut results = Vec::with_capacity(keys.len());
+		for chunk in keys.chunks(CONCURRENCY) {
+			results.extend(join_all(chunk.iter().map(|key| self.update_object(key))).await);
+		}
+		for (key, res) in keys.iter().zip(results) {
+			match res {
+				Ok(true) => self.updated += 1,
+				Ok(false) => self.unchanged += 1,
+				Err(e) => {
+					warn!("{}: could not update {}: {}", self.name(), key, e);
+					self.failed += 1;
+					self.last_failure = Some(format!("{}: {}", key, e));
+				}
+			}
+		}
+		if let Some(last) = keys.last() {
+			self.last_key = Some(last.clone());
+		}
+
+		Ok((keys.len(), finished))
+	}
+}
+
+#[async_trait]
+impl Worker for MetadataUpdateWorker {
+	fn name(&self) -> String {
+		format!(
+			"Update of the metadata of objects {:?} of bucket {:?}",
+			self.params.prefix, self.bucket_id
+		)
+	}
+
+	fn status(&self) -> WorkerStatus {
+		let mut freeform = vec![
+			format!("Rate limit: {} objects/s", self.params.rate),
+			format!("Last key: {}", self.last_key.as_deref().unwrap_or("-")),
+		];
+		if let Some(failure) = &self.last_failure {
+			freeform.push(format!("Last failure: {}", failure));
+		}
+		WorkerStatus {
+			progress: Some(format!(
+				"{} updated, {} unchanged, {} failed",
+				self.updated, self.unchanged, self.failed
+			)),
+			freeform,
+			..Default::default()
+		}
+	}
+
+	async fn work(
+		&mut self,
+		_must_exit: &mut watch::Receiver<bool>,
+	) -> Result<WorkerState, GarageError> {
+		let start = Instant::now();
+		let (count, finished) = self.update_batch().await?;
+		if finished {
+			info!(
+				"{}: finished, {} updated, {} unchanged, {} failed",
+				self.name(),
+				self.updated,
+				self.unchanged,
+				self.failed
+			);
+			return Ok(WorkerState::Done);
+		}
+
+		let target = count as f32 / self.params.rate as f32;
+		let elapsed = start.elapsed().as_secs_f32();
+		if elapsed < target {
+			Ok(WorkerState::Throttled(target - elapsed))
+		} else {
+			Ok(WorkerState::Busy)
+		}
+	}
+
+	async fn wait_for_work(&mut self) -> WorkerState {
+		unreachable!()
+	}
+}
+
+impl JobWorker for MetadataUpdateWorker {
+	fn checkpoint(&self) -> Vec<u8> {
+		encode_checkpoint(&MetadataUpdateCheckpoint {
+			last_key: self.last_key.clone(),
+			updated: self.updated,
+			unchanged: self.unchanged,
+			failed: self.failed,
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn params() -> MetadataUpdateParams {
+		MetadataUpdateParams {
+			prefix: "assets/".into(),
+			content_type: None,
+			content_type_from_extension: false,
+			set_headers: BTreeMap::new(),
+			remove_headers: vec![],
+			rate: 100,
+		}
+	}
+
+	#[test]
+	fn test_validate() {
+		let mut p = params();
+		p.set_headers
+			.insert("Cache-Control".into(), "max-age=3600".into());
+		p.remove_headers.push("X-Amz-Meta-Old".into());
+		let p = p.validate().unwrap();
+		assert_eq!(p.set_headers["cache-control"], "max-age=3600");
+		assert_eq!(p.remove_headers, vec!["x-amz-meta-old".to_string()]);
+
+		let mut p = params();
+		p.set_headers.insert("authorization".into(), "x".into());
+		assert!(p.validate().is_err());
+
+		let mut p = params();
+		p.remove_headers.push("x-amz-meta-".into());
+		assert!(p.validate().is_err());
+
+		let mut p = params();
+		p.content_type = Some("text/css".into());
+		p.content_type_from_extension = true;
+		assert!(p.validate().is_err());
+	}
+
+	#[test]
+	fn test_apply() {
+		let headers = ObjectVersionHeaders {
+			content_type: "application/octet-stream".into(),
+			other: vec![
+				("cache-control".to_string(), "no-cache".to_string()),
+				("x-amz-meta-old".to_string(), "1".to_string()),
+			]
+			.into_iter()
+			.collect(),
+		};
+
+		let mut p = params();
+		p.content_type_from_extension = true;
+		p.set_headers
+			.insert("cache-control".into(), "max-age=3600".into());
+		p.remove_headers.push("x-amz-meta-old".into());
+		let new = p.apply("assets/style.css", &headers);
+		assert_eq!(new.content_type, "text/css");
+		assert_eq!(new.other.len(), 1);
+		assert_eq!(new.other["cache-control"], "max-age=3600");
+
+		// Unknown extensions keep their Content-Type
+		let new = p.apply("assets/data.unknownext", &headers);
+		assert_eq!(new.content_type, "application/octet-stream");
+	}
+}
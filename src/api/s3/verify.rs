@@ -0,0 +1,198 @@
+//! Garage extension to verify the integrity of the data of an object
+use std::sync::Arc;
+
+use futures::stream::{self, StreamExt, TryStreamExt};
+use hyper::{Body, Request, Response, StatusCode};
+use serde::Serialize;
+
+use garage_table::EmptyKey;
+
+use garage_model::bucket_table::Bucket;
+use garage_model::garage::Garage;
+use garage_model::s3::object_table::*;
+
+use crate::s3::error::*;
+
+pub const X_GARAGE_VERIFY: &str = "x-garage-verify";
+pub const X_GARAGE_VERIFY_STATUS: &str = "x-garage-verify-status";
+
+/// Number of blocks that are checked at the same time
+const VERIFY_CONCURRENCY: usize = 8;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct IntegrityReport {
+	key: String,
+	version_id: String,
+	size: u64,
+	/// `exists` if the presence of blocks was checked, `full` if their
+	/// content was also checked
+	mode: &'static str,
+	/// `ok` if all blocks have all their copies, `degraded` if some blocks
+	/// are missing some copies, `damaged` if some blocks have no valid copy
+	status: &'static str,
+	degraded_blocks: usize,
+	damaged_blocks: usize,
+	blocks: Vec<BlockReport>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BlockReport {
+	part_number: u64,
+	offset: u64,
+	hash: String,
+	size: u64,
+	expected_copies: usize,
+	valid_copies: usize,
+	nodes: Vec<NodeReport>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct NodeReport {
+	node: String,
+	stored: Option<bool>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	valid: Option<bool>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	error: Option<String>,
+}
+
+/// Parse the value of the x-garage-verify header, if present:
+/// `exists` only checks that blocks are stored on their nodes,
+/// `full` also reads them on each node to check their content.
+pub fn parse_verify_header(req: &Request<Body>) -> Result<Option<bool>, Error> {
+	match req.headers().get(X_GARAGE_VERIFY) {
+		None => Ok(None),
+		Some(v) => match v.to_str()? {
+			"exists" => Ok(Some(false)),
+			"full" => Ok(Some(true)),
+			_ => Err(Error::bad_request("Invalid x-garage-verify header")),
+		},
+	}
+}
+
+/// Handle a HEAD or GET request with the x-garage-verify header: check that
+/// the blocks of the last version of an object are stored on all the nodes that
+/// should store them, and answer with a JSON report instead of the object's data.
+/// The overall result is also given in the x-garage-verify-status header,
+/// which is the only information returned to HEAD requests.
+pub async fn handle_verify_object(
+	garage: Arc<Garage>,
+	bucket: &Bucket,
+	key: &str,
+	full: bool,
+	head: bool,
+) -> Result<Response<Body>, Error> {
+	let object = garage
+		.object_table
+		.get_with_quorum(&bucket.id, &key.to_string(), bucket.quorum_policy())
+		.await?
+		.ok_or(Error::NoSuchKey)?;
+
+	let object_version = object
+		.versions()
+		.iter()
+		.rev()
+		.find(|v| v.is_data())
+		.ok_or(Error::NoSuchKey)?;
+
+	let (size, blocks) = match &object_version.state {
+		ObjectVersionState::Complete(ObjectVersionData::Inline(meta, _)) => (meta.size, vec![]),
+		ObjectVersionState::Complete(ObjectVersionData::FirstBlock(meta, _)) => {
+			let version = garage
+				.version_table
+				.get(&object_version.uuid, &EmptyKey)
+				.await?
+				.ok_or(Error::NoSuchKey)?;
+			let blocks = version
+				.blocks
+				.items()
+				.iter()
+				.map(|(k, vb)| (*k, *vb))
+				.collect::<Vec<_>>();
+			(meta.size, blocks)
+		}
+		_ => unreachable!(),
+	};
+
+	let blocks = stream::iter(blocks)
+		.map(|(k, vb)| {
+			let garage = &garage;
+			async move {
+				let status = if full {
+					garage.block_manager.rpc_verify_block(&vb.hash).await?
+				} else {
+					garage.block_manager.rpc_get_block_status(&vb.hash).await?
+				};
+				let nodes = status
+					.into_iter()
+					.map(|(node, st)| match st {
+						Ok(st) => NodeReport {
+							node: hex::encode(node),
+							stored: Some(st.stored),
+							valid: st.valid,
+							error: None,
+						},
+						Err(e) => NodeReport {
+							node: hex::encode(node),
+							stored: None,
+							valid: None,
+							error: Some(e.to_string()),
+						},
+					})
+					.collect::<Vec<_>>();
+				Ok::<_, Error>(BlockReport {
+					part_number: k.part_number,
+					offset: k.offset,
+					hash: hex::encode(vb.hash),
+					size: vb.size,
+					expected_copies: nodes.len(),
+					valid_copies: nodes
+						.iter()
+						.filter(|n| n.stored == Some(true) && n.valid != Some(false))
+						.count(),
+					nodes,
+				})
+			}
+		})
+		.buffered(VERIFY_CONCURRENCY)
+		.try_collect::<Vec<_>>()
+		.await?;
+
+	let damaged_blocks = blocks.iter().filter(|b| b.valid_copies == 0).count();
+	let degraded_blocks = blocks
+		.iter()
+		.filter(|b| b.valid_copies > 0 && b.valid_copies < b.expected_copies)
+		.count();
+	let status = if damaged_blocks > 0 {
+		"damaged"
+	} else if degraded_blocks > 0 {
+		"degraded"
+	} else {
+		"ok"
+	};
+
+	let report = IntegrityReport {
+		key: key.to_string(),
+		version_id: hex::encode(object_version.uuid),
+		size,
+		mode: if full { "full" } else { "exists" },
+		status,
+		degraded_blocks,
+		damaged_blocks,
+		blocks,
+	};
+
+	let body = if head {
+		Body::empty()
+	} else {
+		Body::from(serde_json::to_vec_pretty(&report).ok_or_internal_error("JSON encoding")?)
+	};
+	Ok(Response::builder()
+		.status(StatusCode::OK)
+		.header(http::header::CONTENT_TYPE, "application/json")
+		.header(X_GARAGE_VERIFY_STATUS, status)
+		.body(body)?)
+}
@@ -1,16 +1,25 @@
 use quick_xml::de::from_reader;
 use std::sync::Arc;
 
+use hmac::{Hmac, Mac};
 use hyper::{Body, Request, Response, StatusCode};
 use serde::{Deserialize, Serialize};
+use sha2::Sha256;
 
+use crate::encoding::uri_encode;
 use crate::s3::error::*;
 use crate::s3::xml::{to_xml_with_header, xmlns_tag, IntValue, Value};
 use crate::signature::verify_signed_content;
 
-use garage_model::bucket_table::*;
+use garage_model::bucket_table::{
+	Bucket, Redirect as GarageRedirect, RedirectReplaceKey, RoutingRule as GarageRoutingRule,
+	WebsiteConfig, WebsiteSigning,
+};
 use garage_model::garage::Garage;
 use garage_util::data::*;
+use garage_util::time::now_msec;
+
+type HmacSha256 = Hmac<Sha256>;
 
 pub async fn handle_get_website(bucket: &Bucket) -> Result<Response<Body>, Error> {
 	let param = bucket
@@ -27,7 +36,17 @@ pub async fn handle_get_website(bucket: &Bucket) -> Result<Response<Body>, Error
 				suffix: Value(website.index_document.to_string()),
 			}),
 			redirect_all_requests_to: None,
-			routing_rules: None,
+			routing_rules: if website.routing_rules.is_empty() {
+				None
+			} else {
+				Some(
+					website
+						.routing_rules
+						.iter()
+						.map(RoutingRule::from_garage_routing_rule)
+						.collect(),
+				)
+			},
 		};
 		let xml = to_xml_with_header(&wc)?;
 		Ok(Response::builder()
@@ -76,9 +95,17 @@ pub async fn handle_put_website(
 	let conf: WebsiteConfiguration = from_reader(&body as &[u8])?;
 	conf.validate()?;
 
-	param
-		.website_config
-		.update(Some(conf.into_garage_website_config()?));
+	// Directory listing, per-status error documents and default cache headers
+	// can't be configured through the S3 API, keep their current values
+	let mut website_config = conf.into_garage_website_config()?;
+	if let Some(prev) = param.website_config.get() {
+		website_config.directory_listing = prev.directory_listing;
+		website_config.error_documents = prev.error_documents.clone();
+		website_config.cache_control = prev.cache_control.clone();
+		website_config.expires_after = prev.expires_after;
+	}
+
+	param.website_config.update(Some(website_config));
 	garage.bucket_table.insert(&bucket).await?;
 
 	Ok(Response::builder()
@@ -190,10 +217,6 @@ impl WebsiteConfiguration {
 			Err(Error::NotImplemented(
 				"S3 website redirects are not currently implemented in Garage.".into(),
 			))
-		} else if self.routing_rules.map(|x| !x.is_empty()).unwrap_or(false) {
-			Err(Error::NotImplemented(
-				"S3 routing rules are not currently implemented in Garage.".into(),
-			))
 		} else {
 			Ok(WebsiteConfig {
 				index_document: self
@@ -201,6 +224,16 @@ impl WebsiteConfiguration {
 					.map(|x| x.suffix.0)
 					.unwrap_or_else(|| "index.html".to_string()),
 				error_document: self.error_document.map(|x| x.key.0),
+				directory_listing: false,
+				error_documents: Default::default(),
+				cache_control: None,
+				expires_after: None,
+				routing_rules: self
+					.routing_rules
+					.unwrap_or_default()
+					.into_iter()
+					.map(|rr| rr.into_garage_routing_rule())
+					.collect(),
 			})
 		}
 	}
@@ -243,6 +276,9 @@ impl Target {
 
 impl RoutingRuleInner {
 	pub fn validate(&self) -> Result<(), Error> {
+		if let Some(ref condition) = self.condition {
+			condition.validate()?;
+		}
 		let has_prefix = self
 			.condition
 			.as_ref()
@@ -252,6 +288,77 @@ impl RoutingRuleInner {
 	}
 }
 
+impl RoutingRule {
+	pub fn into_garage_routing_rule(self) -> GarageRoutingRule {
+		let RoutingRuleInner {
+			condition,
+			redirect,
+		} = self.inner;
+		let (prefix, http_error_code) = match condition {
+			Some(c) => (c.prefix.map(|x| x.0), c.http_error_code.map(|x| x.0 as u16)),
+			None => (None, None),
+		};
+		let replace_key = match (redirect.replace_full, redirect.replace_prefix) {
+			(Some(k), _) => Some(RedirectReplaceKey::Key(k.0)),
+			(None, Some(p)) => Some(RedirectReplaceKey::Prefix(p.0)),
+			(None, None) => None,
+		};
+		GarageRoutingRule {
+			prefix,
+			http_error_code,
+			redirect: GarageRedirect {
+				hostname: redirect.hostname.map(|x| x.0),
+				protocol: redirect.protocol.map(|x| x.0),
+				http_redirect_code: redirect.http_redirect_code.map(|x| x.0 as u16),
+				replace_key,
+			},
+		}
+	}
+
+	pub fn from_garage_routing_rule(rule: &GarageRoutingRule) -> Self {
+		let condition = match (&rule.prefix, rule.http_error_code) {
+			(None, None) => None,
+			(prefix, http_error_code) => Some(Condition {
+				http_error_code: http_error_code.map(|x| IntValue(x as i64)),
+				prefix: prefix.clone().map(Value),
+			}),
+		};
+		let (replace_prefix, replace_full) = match &rule.redirect.replace_key {
+			Some(RedirectReplaceKey::Key(k)) => (None, Some(Value(k.clone()))),
+			Some(RedirectReplaceKey::Prefix(p)) => (Some(Value(p.clone())), None),
+			None => (None, None),
+		};
+		RoutingRule {
+			inner: RoutingRuleInner {
+				condition,
+				redirect: Redirect {
+					hostname: rule.redirect.hostname.clone().map(Value),
+					protocol: rule.redirect.protocol.clone().map(Value),
+					http_redirect_code: rule
+						.redirect
+						.http_redirect_code
+						.map(|x| IntValue(x as i64)),
+					replace_prefix,
+					replace_full,
+				},
+			},
+		}
+	}
+}
+
+impl Condition {
+	pub fn validate(&self) -> Result<(), Error> {
+		if let Some(ref code) = self.http_error_code {
+			if !(400..600).contains(&code.0) {
+				return Err(Error::bad_request(
+					"Bad XML: HttpErrorCodeReturnedEquals must be a 4xx or 5xx code",
+				));
+			}
+		}
+		Ok(())
+	}
+}
+
 impl Redirect {
 	pub fn validate(&self, has_prefix: bool) -> Result<(), Error> {
 		if self.replace_prefix.is_some() {
@@ -271,11 +378,91 @@ impl Redirect {
 				return Err(Error::bad_request("Bad XML: invalid protocol"));
 			}
 		}
+		if let Some(ref code) = self.http_redirect_code {
+			if !(300..400).contains(&code.0) {
+				return Err(Error::bad_request(
+					"Bad XML: HttpRedirectCode must be a 3xx code",
+				));
+			}
+		}
 		// TODO there are probably more invalide cases, but which ones?
 		Ok(())
 	}
 }
 
+/// Path and query string of a URL of the website of a bucket that gives
+/// access to the object with the given key until `expires`, a Unix timestamp
+/// in seconds
+pub fn sign_website_url(secret: &str, key: &str, expires: u64) -> String {
+	let signature = website_url_hmac(secret, key, expires).finalize();
+	format!(
+		"/{}?expires={}&signature={}",
+		uri_encode(key, false),
+		expires,
+		hex::encode(signature.into_bytes())
+	)
+}
+
+/// Check that a request to the website of a bucket for the given key is
+/// allowed by the configuration of its signed URLs: if the object requires
+/// a signed URL, the query string must contain a valid signature that
+/// has not expired.
+///
+/// `key` is the requested key, on which the signature is computed, and
+/// `served_key` is the key of the object that is actually returned, which
+/// differs from `key` when the index document is appended to a directory.
+/// A signature is required if either of them requires one.
+pub fn check_website_url_signature(
+	signing: &WebsiteSigning,
+	key: &str,
+	served_key: &str,
+	query: Option<&str>,
+) -> Result<(), Error> {
+	if !signing.requires_signature(key) && !signing.requires_signature(served_key) {
+		return Ok(());
+	}
+
+	let mut expires = None;
+	let mut signature = None;
+	for (k, v) in url::form_urlencoded::parse(query.unwrap_or_default().as_bytes()) {
+		match k.as_ref() {
+			"expires" => expires = Some(v),
+			"signature" => signature = Some(v),
+			_ => (),
+		}
+	}
+	let (expires, signature) = match (expires, signature) {
+		(Some(e), Some(s)) => (e, s),
+		_ => {
+			return Err(Error::forbidden(
+				"This object can only be accessed with a signed URL",
+			))
+		}
+	};
+	let expires = expires
+		.parse::<u64>()
+		.ok_or_bad_request("Invalid expires parameter")?;
+	let signature = hex::decode(signature.as_ref()).ok_or_bad_request("Invalid signature")?;
+
+	if website_url_hmac(&signing.secret, key, expires)
+		.verify_slice(&signature)
+		.is_err()
+	{
+		return Err(Error::forbidden("Invalid signature"));
+	}
+	if expires < now_msec() / 1000 {
+		return Err(Error::forbidden("Signed URL has expired"));
+	}
+	Ok(())
+}
+
+fn website_url_hmac(secret: &str, key: &str, expires: u64) -> HmacSha256 {
+	let mut hmac =
+		HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC can take key of any size");
+	hmac.update(format!("{}\n{}", expires, key).as_bytes());
+	hmac
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -353,4 +540,83 @@ mod tests {
 
 		Ok(())
 	}
+
+	#[test]
+	fn test_routing_rule_conversion() {
+		let rule = RoutingRule {
+			inner: RoutingRuleInner {
+				condition: Some(Condition {
+					http_error_code: Some(IntValue(404)),
+					prefix: Some(Value("docs/".to_owned())),
+				}),
+				redirect: Redirect {
+					hostname: Some(Value("example.com".to_owned())),
+					protocol: Some(Value("https".to_owned())),
+					http_redirect_code: Some(IntValue(302)),
+					replace_prefix: Some(Value("documents/".to_owned())),
+					replace_full: None,
+				},
+			},
+		};
+		rule.inner.validate().unwrap();
+
+		let garage_rule = RoutingRule::from_garage_routing_rule(&GarageRoutingRule {
+			prefix: Some("docs/".to_owned()),
+			http_error_code: Some(404),
+			redirect: GarageRedirect {
+				hostname: Some("example.com".to_owned()),
+				protocol: Some("https".to_owned()),
+				http_redirect_code: Some(302),
+				replace_key: Some(RedirectReplaceKey::Prefix("documents/".to_owned())),
+			},
+		});
+		assert_eq!(garage_rule, rule);
+		assert_eq!(
+			garage_rule.into_garage_routing_rule(),
+			rule.into_garage_routing_rule()
+		);
+
+		let bad_code = Redirect {
+			hostname: None,
+			protocol: None,
+			http_redirect_code: Some(IntValue(200)),
+			replace_prefix: None,
+			replace_full: None,
+		};
+		assert!(bad_code.validate(false).is_err());
+	}
+
+	#[test]
+	fn test_website_url_signature() {
+		let signing = WebsiteSigning {
+			secret: "secret".to_owned(),
+			prefixes: vec!["private/".to_owned()],
+		};
+		let expires = now_msec() / 1000 + 3600;
+		let url = sign_website_url(&signing.secret, "private/my file", expires);
+		let (path, query) = url.split_once('?').unwrap();
+		assert_eq!(path, "/private/my%20file");
+
+		let check = |key, query| check_website_url_signature(&signing, key, key, query).is_ok();
+		assert!(check("private/my file", Some(query)));
+		assert!(check("public/file", None));
+		assert!(!check("private/my file", None));
+		assert!(!check("private/other", Some(query)));
+
+		let expired = sign_website_url(&signing.secret, "private/my file", 1000);
+		let (_, expired_query) = expired.split_once('?').unwrap();
+		assert!(!check("private/my file", Some(expired_query)));
+
+		// The index document of a directory is protected by its own prefix
+		let signing = WebsiteSigning {
+			secret: "secret".to_owned(),
+			prefixes: vec!["private/index".to_owned()],
+		};
+		let check_index =
+			|query| check_website_url_signature(&signing, "private/", "private/index.html", query);
+		assert!(check_index(None).is_err());
+		let url = sign_website_url(&signing.secret, "private/", expires);
+		let (_, query) = url.split_once('?').unwrap();
+		assert!(check_index(Some(query)).is_ok());
+	}
 }
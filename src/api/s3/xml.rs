@@ -83,6 +83,17 @@ pub struct Error {
 	pub resource: Option<Value>,
 	#[serde(rename = "Region")]
 	pub region: Option<Value>,
+	#[serde(rename = "RequestId")]
+	pub request_id: Option<Value>,
+	/// For QuotaExceeded errors: the name of the quota (size or objects)
+	#[serde(rename = "Quota")]
+	pub quota: Option<Value>,
+	#[serde(rename = "CurrentUsage")]
+	pub current_usage: Option<IntValue>,
+	#[serde(rename = "Limit")]
+	pub limit: Option<IntValue>,
+	#[serde(rename = "Requested")]
+	pub requested: Option<IntValue>,
 }
 
 #[derive(Debug, Serialize, PartialEq, Eq)]
@@ -316,6 +327,11 @@ mod tests {
 			message: Value("A dummy error message".to_string()),
 			resource: Some(Value("/bucket/a/plop".to_string())),
 			region: Some(Value("garage".to_string())),
+			request_id: Some(Value("4f2c5e8a1b3d9c7e".to_string())),
+			quota: None,
+			current_usage: None,
+			limit: None,
+			requested: None,
 		};
 		assert_eq!(
 			to_xml_with_header(&error)?,
@@ -325,6 +341,37 @@ mod tests {
 	<Message>A dummy error message</Message>\
 	<Resource>/bucket/a/plop</Resource>\
 	<Region>garage</Region>\
+	<RequestId>4f2c5e8a1b3d9c7e</RequestId>\
+</Error>"
+		);
+		Ok(())
+	}
+
+	#[test]
+	fn quota_error_message() -> Result<(), ApiError> {
+		let error = Error {
+			code: Value("QuotaExceeded".to_string()),
+			message: Value("Object quota exceeded".to_string()),
+			resource: Some(Value("/bucket/a".to_string())),
+			region: Some(Value("garage".to_string())),
+			request_id: None,
+			quota: Some(Value("objects".to_string())),
+			current_usage: Some(IntValue(10)),
+			limit: Some(IntValue(10)),
+			requested: Some(IntValue(1)),
+		};
+		assert_eq!(
+			to_xml_with_header(&error)?,
+			"<?xml version=\"1.0\" encoding=\"UTF-8\"?>\
+<Error>\
+	<Code>QuotaExceeded</Code>\
+	<Message>Object quota exceeded</Message>\
+	<Resource>/bucket/a</Resource>\
+	<Region>garage</Region>\
+	<Quota>objects</Quota>\
+	<CurrentUsage>10</CurrentUsage>\
+	<Limit>10</Limit>\
+	<Requested>1</Requested>\
 </Error>"
 		);
 		Ok(())
@@ -0,0 +1,222 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures::future::Future;
+use russh::server::{Auth, Msg, Session};
+use russh::{Channel, ChannelId, MethodSet};
+use russh_keys::key::PublicKey;
+
+use garage_util::config::SftpUserConfig;
+use garage_util::error::{Error as GarageError, OkOrMessage};
+
+use garage_model::bucket_table::Bucket;
+use garage_model::garage::Garage;
+use garage_model::key_table::Key;
+
+use crate::sftp::session::SftpSession;
+use crate::signature::payload::get_usable_key;
+
+pub struct SftpServer {
+	garage: Arc<Garage>,
+}
+
+/// Access key of a user who logged in, and the bucket and the prefix of
+/// their home directory
+#[derive(Clone)]
+pub(crate) struct Login {
+	pub api_key: Key,
+	pub bucket: Bucket,
+	/// Empty, or ending with `/`
+	pub prefix: String,
+}
+
+impl SftpServer {
+	pub async fn run(
+		garage: Arc<Garage>,
+		shutdown_signal: impl Future<Output = ()>,
+	) -> Result<(), GarageError> {
+		let config = garage
+			.config
+			.sftp
+			.clone()
+			.ok_or_message("The SFTP server is not configured")?;
+		let host_key = russh_keys::load_secret_key(&config.host_key_file, None).map_err(|e| {
+			GarageError::Message(format!(
+				"Unable to load SFTP host key {}: {}",
+				config.host_key_file.display(),
+				e
+			))
+		})?;
+		let ssh_config = russh::server::Config {
+			keys: vec![host_key],
+			auth_rejection_time: Duration::from_secs(1),
+			..Default::default()
+		};
+
+		info!("SFTP server listening on {}", config.bind_addr);
+		tokio::select! {
+			res = russh::server::run(Arc::new(ssh_config), config.bind_addr, SftpServer { garage }) => res?,
+			_ = shutdown_signal => (),
+		}
+		Ok(())
+	}
+}
+
+impl russh::server::Server for SftpServer {
+	type Handler = SshSession;
+
+	fn new_client(&mut self, peer_addr: Option<SocketAddr>) -> SshSession {
+		SshSession {
+			garage: self.garage.clone(),
+			peer_addr,
+			login: None,
+			channels: HashMap::new(),
+		}
+	}
+}
+
+pub struct SshSession {
+	garage: Arc<Garage>,
+	peer_addr: Option<SocketAddr>,
+	login: Option<Login>,
+	channels: HashMap<ChannelId, Channel<Msg>>,
+}
+
+impl SshSession {
+	/// Log a user in, if they exist in the configuration and pass `check`
+	async fn log_in<F>(&mut self, user: &str, check: F) -> Auth
+	where
+		F: FnOnce(&SftpUserConfig, &Key) -> bool,
+	{
+		match self.try_log_in(user, check).await {
+			Ok(Some(login)) => {
+				info!("SFTP user {} logged in from {:?}", user, self.peer_addr);
+				self.login = Some(login);
+				Auth::Accept
+			}
+			Ok(None) => reject(),
+			Err(e) => {
+				warn!("SFTP user {} cannot log in: {}", user, e);
+				reject()
+			}
+		}
+	}
+
+	async fn try_log_in<F>(&self, user: &str, check: F) -> Result<Option<Login>, GarageError>
+	where
+		F: FnOnce(&SftpUserConfig, &Key) -> bool,
+	{
+		let user_config = match self
+			.garage
+			.config
+			.sftp
+			.as_ref()
+			.and_then(|c| c.users.get(user))
+		{
+			Some(u) => u,
+			None => return Ok(None),
+		};
+
+		let api_key = get_usable_key(&self.garage, &user_config.access_key_id)
+			.await
+			.map_err(|e| GarageError::Message(e.to_string()))?;
+		if !check(user_config, &api_key) {
+			return Ok(None);
+		}
+
+		let home = user_config.home.trim_matches('/');
+		let (bucket_name, prefix) = match home.split_once('/') {
+			Some((bucket, prefix)) => (bucket, format!("{}/", prefix.trim_end_matches('/'))),
+			None => (home, String::new()),
+		};
+		let bucket_id = self
+			.garage
+			.bucket_helper()
+			.resolve_bucket(&bucket_name.to_string(), &api_key)
+			.await
+			.map_err(|e| GarageError::Message(e.to_string()))?;
+		let bucket = self
+			.garage
+			.bucket_helper()
+			.get_existing_bucket(bucket_id)
+			.await
+			.map_err(|e| GarageError::Message(e.to_string()))?;
+
+		Ok(Some(Login {
+			api_key,
+			bucket,
+			prefix,
+		}))
+	}
+}
+
+#[async_trait]
+impl russh::server::Handler for SshSession {
+	type Error = russh::Error;
+
+	async fn auth_password(&mut self, user: &str, password: &str) -> Result<Auth, Self::Error> {
+		Ok(self
+			.log_in(user, |_, key| {
+				key.params()
+					.map(|p| p.secret_key == password)
+					.unwrap_or(false)
+			})
+			.await)
+	}
+
+	async fn auth_publickey(
+		&mut self,
+		user: &str,
+		public_key: &PublicKey,
+	) -> Result<Auth, Self::Error> {
+		let fingerprint = public_key.fingerprint();
+		Ok(self
+			.log_in(user, |user_config, _| {
+				user_config
+					.authorized_keys
+					.iter()
+					.filter_map(|k| k.split_whitespace().nth(1))
+					.filter_map(|k| russh_keys::parse_public_key_base64(k).ok())
+					.any(|k| k.fingerprint() == fingerprint)
+			})
+			.await)
+	}
+
+	async fn channel_open_session(
+		&mut self,
+		channel: Channel<Msg>,
+		_session: &mut Session,
+	) -> Result<bool, Self::Error> {
+		if self.login.is_none() {
+			return Ok(false);
+		}
+		self.channels.insert(channel.id(), channel);
+		Ok(true)
+	}
+
+	async fn subsystem_request(
+		&mut self,
+		channel_id: ChannelId,
+		name: &str,
+		session: &mut Session,
+	) -> Result<(), Self::Error> {
+		match (name, self.login.clone(), self.channels.remove(&channel_id)) {
+			("sftp", Some(login), Some(channel)) => {
+				session.channel_success(channel_id);
+				let sftp = SftpSession::new(self.garage.clone(), login);
+				russh_sftp::server::run(channel.into_stream(), sftp).await;
+			}
+			_ => session.channel_failure(channel_id),
+		}
+		Ok(())
+	}
+}
+
+fn reject() -> Auth {
+	Auth::Reject {
+		proceed_with_methods: Some(MethodSet::PASSWORD | MethodSet::PUBLICKEY),
+	}
+}
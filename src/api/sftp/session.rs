@@ -0,0 +1,529 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use hyper::header::RANGE;
+use hyper::{Body, Request, Response};
+use russh_sftp::protocol::{
+	Attrs, Data, File, FileAttributes, Handle, Name, OpenFlags, Status, StatusCode, Version,
+};
+use tokio::task::JoinHandle;
+
+use garage_model::garage::Garage;
+
+use crate::object_tree::*;
+use crate::s3::delete::handle_delete;
+use crate::s3::error::Error as S3Error;
+use crate::s3::get::handle_get;
+use crate::s3::put::handle_put;
+use crate::sftp::server::Login;
+
+/// Size of the chunks in which files are read from the bucket
+const READ_CHUNK: u64 = 1024 * 1024;
+
+/// Handler of the SFTP requests of a logged in user, whose home directory
+/// is presented as the root of the filesystem
+pub(crate) struct SftpSession {
+	garage: Arc<Garage>,
+	login: Login,
+	handles: HashMap<String, OpenHandle>,
+	next_handle: u64,
+}
+
+enum OpenHandle {
+	Read {
+		key: String,
+		size: u64,
+		/// Last chunk read: offset and data
+		cache: Option<(u64, Bytes)>,
+	},
+	Write(Upload),
+	Dir {
+		prefix: String,
+		listed: bool,
+	},
+}
+
+/// Object being written, whose data is streamed to a PutObject request
+/// as it is received
+struct Upload {
+	written: u64,
+	body: Option<hyper::body::Sender>,
+	task: Option<JoinHandle<Result<Response<Body>, S3Error>>>,
+}
+
+impl SftpSession {
+	pub(crate) fn new(garage: Arc<Garage>, login: Login) -> Self {
+		Self {
+			garage,
+			login,
+			handles: HashMap::new(),
+			next_handle: 0,
+		}
+	}
+
+	fn key(&self, path: &str) -> String {
+		object_key(&self.login.prefix, path)
+	}
+
+	async fn resolve(&self, key: &str) -> Result<Node, StatusCode> {
+		if key == self.login.prefix {
+			// The home directory exists even if it contains no object
+			return Ok(Node::Dir(key.to_string()));
+		}
+		resolve_path(&self.garage, &self.login.bucket, key)
+			.await
+			.map_err(failure)?
+			.ok_or(StatusCode::NoSuchFile)
+	}
+
+	fn check_read(&self) -> Result<(), StatusCode> {
+		match self.login.api_key.allow_read(&self.login.bucket.id) {
+			true => Ok(()),
+			false => Err(StatusCode::PermissionDenied),
+		}
+	}
+
+	fn check_write(&self) -> Result<(), StatusCode> {
+		match self.login.api_key.allow_write(&self.login.bucket.id) {
+			true => Ok(()),
+			false => Err(StatusCode::PermissionDenied),
+		}
+	}
+
+	fn add_handle(&mut self, id: u32, handle: OpenHandle) -> Handle {
+		self.next_handle += 1;
+		let name = self.next_handle.to_string();
+		self.handles.insert(name.clone(), handle);
+		Handle { id, handle: name }
+	}
+
+	fn start_upload(&self, key: String) -> Upload {
+		let (body, req_body) = Body::channel();
+		let req = Request::new(req_body);
+		let garage = self.garage.clone();
+		let bucket = self.login.bucket.clone();
+		let task = tokio::spawn(async move { handle_put(garage, req, &bucket, &key, None).await });
+		Upload {
+			written: 0,
+			body: Some(body),
+			task: Some(task),
+		}
+	}
+
+	/// Read a chunk of an object from the bucket
+	async fn read_chunk(&self, key: &str, offset: u64, size: u64) -> Result<Bytes, StatusCode> {
+		let end = std::cmp::min(offset + READ_CHUNK, size);
+		let req = Request::builder()
+			.header(RANGE, format!("bytes={}-{}", offset, end - 1))
+			.body(Body::empty())
+			.map_err(failure)?;
+		let resp = handle_get(self.garage.clone(), &req, &self.login.bucket, key, None)
+			.await
+			.map_err(s3_failure)?;
+		let data = hyper::body::to_bytes(resp.into_body())
+			.await
+			.map_err(failure)?;
+		self.garage
+			.key_usage
+			.record(&self.login.api_key.key_id, 1, 0, data.len() as u64);
+		Ok(data)
+	}
+}
+
+impl Upload {
+	async fn finish(&mut self) -> Result<(), StatusCode> {
+		// Dropping the sender ends the body of the request
+		drop(self.body.take());
+		match self.task.take() {
+			Some(task) => match task.await {
+				Ok(Ok(_)) => Ok(()),
+				Ok(Err(e)) => Err(s3_failure(e)),
+				Err(e) => Err(failure(e)),
+			},
+			None => Ok(()),
+		}
+	}
+}
+
+impl Drop for Upload {
+	fn drop(&mut self) {
+		// An upload that was not closed is interrupted, so that the object
+		// is not replaced by a truncated version
+		if let Some(body) = self.body.take() {
+			body.abort();
+		}
+	}
+}
+
+#[async_trait]
+impl russh_sftp::server::Handler for SftpSession {
+	type Error = StatusCode;
+
+	fn unimplemented(&self) -> Self::Error {
+		StatusCode::OpUnsupported
+	}
+
+	async fn init(
+		&mut self,
+		_version: u32,
+		_extensions: HashMap<String, String>,
+	) -> Result<Version, Self::Error> {
+		Ok(Version::new())
+	}
+
+	async fn realpath(&mut self, id: u32, path: String) -> Result<Name, Self::Error> {
+		let key = self.key(&path);
+		let path = format!("/{}", &key[self.login.prefix.len()..]);
+		Ok(Name {
+			id,
+			files: vec![File {
+				filename: path.clone(),
+				longname: path,
+				attrs: FileAttributes::default(),
+			}],
+		})
+	}
+
+	async fn stat(&mut self, id: u32, path: String) -> Result<Attrs, Self::Error> {
+		self.check_read()?;
+		let attrs = match self.resolve(&self.key(&path)).await? {
+			Node::File(file) => file_attrs(&file),
+			Node::Dir(_) => dir_attrs(),
+		};
+		Ok(Attrs { id, attrs })
+	}
+
+	async fn lstat(&mut self, id: u32, path: String) -> Result<Attrs, Self::Error> {
+		self.stat(id, path).await
+	}
+
+	async fn fstat(&mut self, id: u32, handle: String) -> Result<Attrs, Self::Error> {
+		let attrs = match self.handles.get(&handle).ok_or(StatusCode::Failure)? {
+			OpenHandle::Read { size, .. } => FileAttributes {
+				size: Some(*size),
+				permissions: Some(0o100644),
+				..Default::default()
+			},
+			OpenHandle::Write(upload) => FileAttributes {
+				size: Some(upload.written),
+				permissions: Some(0o100644),
+				..Default::default()
+			},
+			OpenHandle::Dir { .. } => dir_attrs(),
+		};
+		Ok(Attrs { id, attrs })
+	}
+
+	async fn opendir(&mut self, id: u32, path: String) -> Result<Handle, Self::Error> {
+		self.check_read()?;
+		match self.resolve(&self.key(&path)).await? {
+			Node::Dir(prefix) => Ok(self.add_handle(
+				id,
+				OpenHandle::Dir {
+					prefix,
+					listed: false,
+				},
+			)),
+			Node::File(_) => Err(StatusCode::Failure),
+		}
+	}
+
+	async fn readdir(&mut self, id: u32, handle: String) -> Result<Name, Self::Error> {
+		let prefix = match self.handles.get_mut(&handle) {
+			Some(OpenHandle::Dir { listed: true, .. }) => return Err(StatusCode::Eof),
+			Some(OpenHandle::Dir { prefix, listed }) => {
+				*listed = true;
+				prefix.clone()
+			}
+			_ => return Err(StatusCode::Failure),
+		};
+
+		let entries = list_dir(&self.garage, &self.login.bucket, &prefix)
+			.await
+			.map_err(failure)?;
+		let files = entries
+			.into_iter()
+			.map(|entry| {
+				let attrs = match &entry.file {
+					Some(file) => file_attrs(file),
+					None => dir_attrs(),
+				};
+				File {
+					longname: entry.name.clone(),
+					filename: entry.name,
+					attrs,
+				}
+			})
+			.collect();
+		Ok(Name { id, files })
+	}
+
+	async fn open(
+		&mut self,
+		id: u32,
+		filename: String,
+		pflags: OpenFlags,
+		_attrs: FileAttributes,
+	) -> Result<Handle, Self::Error> {
+		let key = self.key(&filename);
+		if key.is_empty() || key == self.login.prefix {
+			return Err(StatusCode::Failure);
+		}
+
+		if pflags.contains(OpenFlags::APPEND) {
+			// Objects cannot be modified, only replaced
+			return Err(StatusCode::OpUnsupported);
+		}
+		if pflags.intersects(OpenFlags::WRITE | OpenFlags::CREATE | OpenFlags::TRUNCATE) {
+			self.check_write()?;
+			if pflags.contains(OpenFlags::EXCLUDE) && self.resolve(&key).await.is_ok() {
+				return Err(StatusCode::Failure);
+			}
+			let upload = self.start_upload(key);
+			return Ok(self.add_handle(id, OpenHandle::Write(upload)));
+		}
+
+		self.check_read()?;
+		match self.resolve(&key).await? {
+			Node::File(file) => Ok(self.add_handle(
+				id,
+				OpenHandle::Read {
+					key,
+					size: file.size,
+					cache: None,
+				},
+			)),
+			Node::Dir(_) => Err(StatusCode::Failure),
+		}
+	}
+
+	async fn read(
+		&mut self,
+		id: u32,
+		handle: String,
+		offset: u64,
+		len: u32,
+	) -> Result<Data, Self::Error> {
+		let (key, size, cached) = match self.handles.get(&handle) {
+			Some(OpenHandle::Read { key, size, cache }) => (key.clone(), *size, cache.clone()),
+			_ => return Err(StatusCode::Failure),
+		};
+		if offset >= size {
+			return Err(StatusCode::Eof);
+		}
+
+		let (start, chunk) = match cached {
+			Some((start, chunk)) if start <= offset && offset < start + chunk.len() as u64 => {
+				(start, chunk)
+			}
+			_ => {
+				let start = offset - offset % READ_CHUNK;
+				let chunk = self.read_chunk(&key, start, size).await?;
+				if let Some(OpenHandle::Read { cache, .. }) = self.handles.get_mut(&handle) {
+					*cache = Some((start, chunk.clone()));
+				}
+				(start, chunk)
+			}
+		};
+
+		let from = std::cmp::min((offset - start) as usize, chunk.len());
+		let to = std::cmp::min(from + len as usize, chunk.len());
+		Ok(Data {
+			id,
+			data: chunk[from..to].to_vec(),
+		})
+	}
+
+	async fn write(
+		&mut self,
+		id: u32,
+		handle: String,
+		offset: u64,
+		data: Vec<u8>,
+	) -> Result<Status, Self::Error> {
+		let upload = match self.handles.get_mut(&handle) {
+			Some(OpenHandle::Write(upload)) => upload,
+			_ => return Err(StatusCode::Failure),
+		};
+		if offset != upload.written {
+			// The data is streamed to the bucket, it must be written in order
+			return Err(StatusCode::OpUnsupported);
+		}
+
+		let len = data.len() as u64;
+		let body = upload.body.as_mut().ok_or(StatusCode::Failure)?;
+		if body.send_data(Bytes::from(data)).await.is_err() {
+			// The request failed, its error is returned when the file is closed
+			return Err(StatusCode::Failure);
+		}
+		upload.written += len;
+		self.garage
+			.key_usage
+			.record(&self.login.api_key.key_id, 1, len, 0);
+		Ok(ok(id))
+	}
+
+	async fn close(&mut self, id: u32, handle: String) -> Result<Status, Self::Error> {
+		match self.handles.remove(&handle) {
+			Some(OpenHandle::Write(mut upload)) => upload.finish().await?,
+			Some(_) => (),
+			None => return Err(StatusCode::Failure),
+		}
+		Ok(ok(id))
+	}
+
+	async fn remove(&mut self, id: u32, filename: String) -> Result<Status, Self::Error> {
+		self.check_write()?;
+		let key = self.key(&filename);
+		match self.resolve(&key).await? {
+			Node::File(_) => {
+				handle_delete(self.garage.clone(), &self.login.bucket, &key)
+					.await
+					.map_err(s3_failure)?;
+				Ok(ok(id))
+			}
+			Node::Dir(_) => Err(StatusCode::Failure),
+		}
+	}
+
+	async fn mkdir(
+		&mut self,
+		id: u32,
+		path: String,
+		_attrs: FileAttributes,
+	) -> Result<Status, Self::Error> {
+		self.check_write()?;
+		let key = self.key(&path);
+		if self.resolve(&key).await.is_ok() {
+			return Err(StatusCode::Failure);
+		}
+
+		let prefix = dir_prefix(&key);
+		handle_put(
+			self.garage.clone(),
+			Request::new(Body::empty()),
+			&self.login.bucket,
+			&prefix,
+			None,
+		)
+		.await
+		.map_err(s3_failure)?;
+		Ok(ok(id))
+	}
+
+	async fn rmdir(&mut self, id: u32, path: String) -> Result<Status, Self::Error> {
+		self.check_write()?;
+		let key = self.key(&path);
+		let prefix = match self.resolve(&key).await? {
+			Node::Dir(prefix) if prefix != self.login.prefix => prefix,
+			_ => return Err(StatusCode::Failure),
+		};
+
+		let entries = list_dir(&self.garage, &self.login.bucket, &prefix)
+			.await
+			.map_err(failure)?;
+		if !entries.is_empty() {
+			return Err(StatusCode::Failure);
+		}
+		handle_delete(self.garage.clone(), &self.login.bucket, &prefix)
+			.await
+			.map_err(s3_failure)?;
+		Ok(ok(id))
+	}
+}
+
+/// Key of the object at a path sent by the client, the paths being
+/// relative to the home directory, above which `..` does not go
+fn object_key(home: &str, path: &str) -> String {
+	let mut parts = vec![];
+	for part in path.split('/') {
+		match part {
+			"" | "." => (),
+			".." => {
+				parts.pop();
+			}
+			p => parts.push(p),
+		}
+	}
+	format!("{}{}", home, parts.join("/"))
+}
+
+fn ok(id: u32) -> Status {
+	Status {
+		id,
+		status_code: StatusCode::Ok,
+		error_message: "Ok".into(),
+		language_tag: "en-US".into(),
+	}
+}
+
+fn file_attrs(file: &FileInfo) -> FileAttributes {
+	let mtime = (file.timestamp / 1000) as u32;
+	FileAttributes {
+		size: Some(file.size),
+		permissions: Some(0o100644),
+		atime: Some(mtime),
+		mtime: Some(mtime),
+		..Default::default()
+	}
+}
+
+fn dir_attrs() -> FileAttributes {
+	FileAttributes {
+		permissions: Some(0o040755),
+		..Default::default()
+	}
+}
+
+fn failure<E: std::fmt::Display>(e: E) -> StatusCode {
+	warn!("SFTP request failed: {}", e);
+	StatusCode::Failure
+}
+
+fn s3_failure(e: S3Error) -> StatusCode {
+	match e {
+		S3Error::NoSuchKey => StatusCode::NoSuchFile,
+		e => failure(e),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_object_key() {
+		assert_eq!(object_key("home/", ""), "home/");
+		assert_eq!(object_key("home/", "/"), "home/");
+		assert_eq!(object_key("home/", "/a/./b.txt"), "home/a/b.txt");
+		assert_eq!(object_key("home/", "a//b/../c.txt"), "home/a/c.txt");
+
+		// Paths cannot go above the home directory
+		assert_eq!(object_key("home/", "../../other/x"), "home/other/x");
+		assert_eq!(object_key("home/", "/a/../../x"), "home/x");
+		assert_eq!(object_key("", "../x"), "x");
+	}
+
+	#[test]
+	fn test_file_attrs() {
+		let attrs = file_attrs(&FileInfo {
+			size: 42,
+			etag: String::new(),
+			content_type: String::new(),
+			timestamp: 1_700_000_000_999,
+		});
+		assert_eq!(attrs.size, Some(42));
+		assert_eq!(attrs.mtime, Some(1_700_000_000));
+		assert_eq!(attrs.permissions.map(|p| p & 0o170000), Some(0o100000));
+		assert_eq!(
+			dir_attrs().permissions.map(|p| p & 0o170000),
+			Some(0o040000)
+		);
+		assert!(matches!(
+			s3_failure(S3Error::NoSuchKey),
+			StatusCode::NoSuchFile
+		));
+	}
+}
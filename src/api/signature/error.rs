@@ -14,6 +14,22 @@ pub enum Error {
 	#[error(display = "Authorization header malformed, unexpected scope: {}", _0)]
 	AuthorizationHeaderMalformed(String),
 
+	/// The access key given in the request does not exist
+	#[error(display = "Forbidden: No such key: {}", _0)]
+	InvalidAccessKeyId(String),
+
+	/// The access key given in the request has expired
+	#[error(display = "Forbidden: The access key has expired: {}", _0)]
+	ExpiredAccessKey(String),
+
+	/// The signature of the request does not match the one computed by Garage
+	#[error(display = "Forbidden: {}", _0)]
+	SignatureDoesNotMatch(String),
+
+	/// The date of the request is too far from the current time
+	#[error(display = "Forbidden: {}", _0)]
+	RequestTimeTooSkewed(String),
+
 	// Category: bad request
 	/// The request contained an invalid UTF-8 sequence in its path or in other parameters
 	#[error(display = "Invalid UTF-8: {}", _0)]
@@ -1,20 +1,24 @@
 use std::collections::HashMap;
 
+use base64::prelude::*;
 use chrono::{DateTime, Duration, NaiveDateTime, Utc};
 use hmac::Mac;
-use hyper::{Body, Method, Request};
-use sha2::{Digest, Sha256};
+use hyper::header::AUTHORIZATION;
+use hyper::{Body, Request};
 
 use garage_table::*;
 use garage_util::data::Hash;
+use garage_util::time::now_msec;
 
 use garage_model::garage::Garage;
 use garage_model::key_table::*;
 
+pub use garage_sigv4::{canonical_request, string_to_sign};
+
 use super::LONG_DATETIME;
 use super::{compute_scope, signing_hmac};
 
-use crate::encoding::uri_encode;
+use crate::https::ClientCertificate;
 use crate::signature::error::*;
 
 pub async fn check_payload_signature(
@@ -134,7 +138,7 @@ fn parse_authorization(
 		.and_then(|d| parse_date(d))?;
 
 	if Utc::now() - date > Duration::hours(24) {
-		return Err(Error::bad_request("Date is too old".to_string()));
+		return Err(Error::RequestTimeTooSkewed("Date is too old".to_string()));
 	}
 
 	let auth = Authorization {
@@ -196,7 +200,7 @@ fn parse_query_authorization(
 		.and_then(|d| parse_date(d))?;
 
 	if Utc::now() - date > Duration::seconds(duration) {
-		return Err(Error::bad_request("Date is too old".to_string()));
+		return Err(Error::forbidden("Request has expired".to_string()));
 	}
 
 	Ok(Authorization {
@@ -219,104 +223,81 @@ fn parse_credential(cred: &str) -> Result<(String, String), Error> {
 	))
 }
 
-pub fn string_to_sign(datetime: &DateTime<Utc>, scope_string: &str, canonical_req: &str) -> String {
-	let mut hasher = Sha256::default();
-	hasher.update(canonical_req.as_bytes());
-	[
-		"AWS4-HMAC-SHA256",
-		&datetime.format(LONG_DATETIME).to_string(),
-		scope_string,
-		&hex::encode(hasher.finalize().as_slice()),
-	]
-	.join("\n")
+pub fn parse_date(date: &str) -> Result<DateTime<Utc>, Error> {
+	let date: NaiveDateTime =
+		NaiveDateTime::parse_from_str(date, LONG_DATETIME).ok_or_bad_request("Invalid date")?;
+	Ok(DateTime::from_utc(date, Utc))
 }
 
-pub fn canonical_request(
-	service: &'static str,
-	method: &Method,
-	uri: &hyper::Uri,
-	headers: &HashMap<String, String>,
-	signed_headers: &str,
-	content_sha256: &str,
-) -> String {
-	// There seems to be evidence that in AWSv4 signatures, the path component is url-encoded
-	// a second time when building the canonical request, as specified in this documentation page:
-	// -> https://docs.aws.amazon.com/rolesanywhere/latest/userguide/authentication-sign-process.html
-	// However this documentation page is for a specific service ("roles anywhere"), and
-	// in the S3 service we know for a fact that there is no double-urlencoding, because all of
-	// the tests we made with external software work without it.
-	//
-	// The theory is that double-urlencoding occurs for all services except S3,
-	// which is what is implemented in rusoto_signature:
-	// -> https://docs.rs/rusoto_signature/latest/src/rusoto_signature/signature.rs.html#464
-	//
-	// Digging into the code of the official AWS Rust SDK, we learn that double-URI-encoding can
-	// be set or unset on a per-request basis (the signature crates, aws-sigv4 and aws-sig-auth,
-	// are agnostic to this). Grepping the codebase confirms that S3 is the only API for which
-	// double_uri_encode is set to false, meaning it is true (its default value) for all other
-	// AWS services. We will therefore implement this behavior in Garage as well.
-	//
-	// Note that this documentation page, which is touted as the "authoritative reference" on
-	// AWSv4 signatures, makes no mention of either single- or double-urlencoding:
-	// -> https://docs.aws.amazon.com/IAM/latest/UserGuide/create-signed-request.html
-	// This page of the S3 documentation does also not mention anything specific:
-	// -> https://docs.aws.amazon.com/AmazonS3/latest/API/sig-v4-header-based-auth.html
-	//
-	// Note that there is also the issue of path normalization, which I hope is unrelated to the
-	// one of URI-encoding. At least in aws-sigv4 both parameters can be set independently,
-	// and rusoto_signature does not seem to do any effective path normalization, even though
-	// it mentions it in the comments (same link to the souce code as above).
-	// We make the explicit choice of NOT normalizing paths in the K2V API because doing so
-	// would make non-normalized paths invalid K2V partition keys, and we don't want that.
-	let path: std::borrow::Cow<str> = if service != "s3" {
-		uri_encode(uri.path(), false).into()
-	} else {
-		uri.path().into()
+/// Get the access key used for a request that carries no signature, if it
+/// was received on a connection authenticated with a client certificate
+/// whose common name is mapped to a key in the configuration
+pub async fn check_client_certificate(
+	garage: &Garage,
+	request: &Request<Body>,
+) -> Result<Option<Key>, Error> {
+	let cert = match request.extensions().get::<ClientCertificate>() {
+		Some(c) => c,
+		None => return Ok(None),
 	};
-	[
-		method.as_str(),
-		&path,
-		&canonical_query_string(uri),
-		&canonical_header_string(headers, signed_headers),
-		"",
-		signed_headers,
-		content_sha256,
-	]
-	.join("\n")
-}
-
-fn canonical_header_string(headers: &HashMap<String, String>, signed_headers: &str) -> String {
-	let signed_headers_vec = signed_headers.split(';').collect::<Vec<_>>();
-	let mut items = headers
-		.iter()
-		.filter(|(key, _)| signed_headers_vec.contains(&key.as_str()))
-		.collect::<Vec<_>>();
-	items.sort_by(|(k1, _), (k2, _)| k1.cmp(k2));
-	items
-		.iter()
-		.map(|(key, value)| key.to_lowercase() + ":" + value.trim())
-		.collect::<Vec<_>>()
-		.join("\n")
+	let key_id = garage
+		.config
+		.s3_api
+		.tls
+		.as_ref()
+		.and_then(|tls| tls.client_cert_keys.get(&cert.common_name))
+		.ok_or_else(|| {
+			Error::forbidden(format!(
+				"No access key for client certificate {}",
+				cert.common_name
+			))
+		})?;
+	Ok(Some(get_usable_key(garage, key_id).await?))
 }
 
-fn canonical_query_string(uri: &hyper::Uri) -> String {
-	if let Some(query) = uri.query() {
-		let query_pairs = url::form_urlencoded::parse(query.as_bytes());
-		let mut items = query_pairs
-			.filter(|(key, _)| key != "X-Amz-Signature")
-			.map(|(key, value)| uri_encode(&key, true) + "=" + &uri_encode(&value, true))
-			.collect::<Vec<_>>();
-		items.sort();
-		items.join("&")
-	} else {
-		"".to_string()
+/// Get the access key of a request that carries HTTP Basic credentials,
+/// the user name being the ID of the key and the password its secret
+pub async fn check_basic_auth(
+	garage: &Garage,
+	request: &Request<Body>,
+) -> Result<Option<Key>, Error> {
+	let authorization = match request.headers().get(AUTHORIZATION) {
+		Some(a) => a.to_str()?,
+		None => return Ok(None),
+	};
+	let credentials = match authorization.strip_prefix("Basic ") {
+		Some(c) => c.trim(),
+		None => return Ok(None),
+	};
+	let credentials = BASE64_STANDARD
+		.decode(credentials)
+		.ok()
+		.and_then(|c| String::from_utf8(c).ok())
+		.ok_or_bad_request("Invalid Basic authorization header")?;
+	let (key_id, secret) = credentials
+		.split_once(':')
+		.ok_or_bad_request("Invalid Basic authorization header")?;
+
+	let key = get_usable_key(garage, key_id).await?;
+	if key.params().unwrap().secret_key != secret {
+		return Err(Error::SignatureDoesNotMatch(
+			"Invalid secret key for this access key".into(),
+		));
 	}
+	Ok(Some(key))
 }
 
-pub fn parse_date(date: &str) -> Result<DateTime<Utc>, Error> {
-	let date: NaiveDateTime =
-		NaiveDateTime::parse_from_str(date, LONG_DATETIME).ok_or_bad_request("Invalid date")?;
-	Ok(DateTime::from_utc(date, Utc))
+pub(crate) async fn get_usable_key(garage: &Garage, key_id: &str) -> Result<Key, Error> {
+	let key = garage
+		.key_table
+		.get(&EmptyKey, &key_id.to_string())
+		.await?
+		.filter(|k| !k.state.is_deleted() && !k.is_disabled())
+		.ok_or_else(|| Error::InvalidAccessKeyId(key_id.to_string()))?;
+	if key.is_expired(now_msec()) {
+		return Err(Error::ExpiredAccessKey(key_id.to_string()));
+	}
+	Ok(key)
 }
 
 pub async fn verify_v4(
@@ -334,12 +315,7 @@ pub async fn verify_v4(
 		return Err(Error::AuthorizationHeaderMalformed(scope.to_string()));
 	}
 
-	let key = garage
-		.key_table
-		.get(&EmptyKey, &key_id)
-		.await?
-		.filter(|k| !k.state.is_deleted())
-		.ok_or_else(|| Error::forbidden(format!("No such key: {}", &key_id)))?;
+	let key = get_usable_key(garage, &key_id).await?;
 	let key_p = key.params().unwrap();
 
 	let mut hmac = signing_hmac(
@@ -352,7 +328,9 @@ pub async fn verify_v4(
 	hmac.update(payload);
 	let our_signature = hex::encode(hmac.finalize().into_bytes());
 	if signature != our_signature {
-		return Err(Error::forbidden("Invalid signature".to_string()));
+		return Err(Error::SignatureDoesNotMatch(
+			"Invalid signature".to_string(),
+		));
 	}
 
 	Ok(key)
@@ -163,7 +163,7 @@ impl From<SignedPayloadStreamError> for Error {
 		match err {
 			SignedPayloadStreamError::Stream(e) => e,
 			SignedPayloadStreamError::InvalidSignature => {
-				Error::bad_request("Invalid payload signature")
+				Error::SignatureDoesNotMatch("Invalid payload signature".into())
 			}
 			SignedPayloadStreamError::Message(e) => {
 				Error::bad_request(format!("Chunk format error: {}", e))
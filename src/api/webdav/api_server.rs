@@ -0,0 +1,239 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use futures::future::Future;
+use hyper::header::{ALLOW, CONTENT_LENGTH};
+use hyper::{Body, Request, Response, StatusCode};
+use percent_encoding::percent_decode_str;
+
+use opentelemetry::{trace::SpanRef, KeyValue};
+
+use garage_util::config::ApiLimitsConfig;
+use garage_util::error::Error as GarageError;
+use garage_util::socket_address::UnixOrTCPSocketAddress;
+
+use garage_model::garage::Garage;
+
+use crate::generic_server::*;
+use crate::webdav::error::*;
+
+use crate::signature::payload::check_basic_auth;
+
+use crate::helpers::*;
+use crate::s3::get::{handle_get, handle_head};
+use crate::s3::put::handle_put;
+use crate::webdav::collection::*;
+use crate::webdav::propfind::*;
+
+/// Methods advertised in the Allow header of OPTIONS responses
+const ALLOWED_METHODS: &str = "OPTIONS, PROPFIND, GET, HEAD, PUT, DELETE, MKCOL";
+
+pub struct WebDavServer {
+	garage: Arc<Garage>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Method {
+	Options,
+	PropFind,
+	Get,
+	Head,
+	Put,
+	Delete,
+	MkCol,
+}
+
+pub(crate) struct WebDavEndpoint {
+	method: Method,
+	/// Name of the bucket, None for the root collection whose members are
+	/// the buckets of the access key
+	bucket_name: Option<String>,
+	/// Path of the resource in the bucket, without leading `/`
+	path: String,
+}
+
+impl WebDavServer {
+	pub async fn run(
+		garage: Arc<Garage>,
+		bind_addr: UnixOrTCPSocketAddress,
+		s3_region: String,
+		shutdown_signal: impl Future<Output = ()>,
+	) -> Result<(), GarageError> {
+		let in_flight_requests = garage.in_flight_requests.clone();
+		let per_bucket_metrics = garage.config.admin.metrics_per_bucket;
+		ApiServer::new(
+			s3_region,
+			host_id(&garage),
+			WebDavServer { garage },
+			in_flight_requests,
+			per_bucket_metrics,
+		)
+		.run_server(bind_addr, None, shutdown_signal)
+		.await
+	}
+}
+
+#[async_trait]
+impl ApiHandler for WebDavServer {
+	const API_NAME: &'static str = "webdav";
+	const API_NAME_DISPLAY: &'static str = "WebDAV";
+
+	type Endpoint = WebDavEndpoint;
+	type Error = Error;
+
+	fn limits(&self) -> ApiLimitsConfig {
+		self.garage
+			.live_config
+			.load()
+			.webdav
+			.as_ref()
+			.map(|webdav| webdav.limits.clone())
+			.unwrap_or_default()
+	}
+
+	fn parse_endpoint(&self, req: &Request<Body>) -> Result<WebDavEndpoint, Error> {
+		let method = match req.method().as_str() {
+			"OPTIONS" => Method::Options,
+			"PROPFIND" => Method::PropFind,
+			"GET" => Method::Get,
+			"HEAD" => Method::Head,
+			"PUT" => Method::Put,
+			"DELETE" => Method::Delete,
+			"MKCOL" => Method::MkCol,
+			m => {
+				return Err(Error::MethodNotAllowed(format!(
+					"{} is not supported by the WebDAV server",
+					m
+				)))
+			}
+		};
+
+		let path = percent_decode_str(req.uri().path())
+			.decode_utf8()
+			.map_err(Error::InvalidUtf8Str)?;
+		let path = path.trim_start_matches('/');
+		let (bucket_name, path) = match path.split_once('/') {
+			Some((bucket, path)) => (bucket, path),
+			None => (path, ""),
+		};
+
+		Ok(WebDavEndpoint {
+			method,
+			bucket_name: Some(bucket_name.to_string()).filter(|b| !b.is_empty()),
+			path: path.to_string(),
+		})
+	}
+
+	async fn handle(
+		&self,
+		req: Request<Body>,
+		endpoint: WebDavEndpoint,
+	) -> Result<Response<Body>, Error> {
+		let WebDavEndpoint {
+			method,
+			bucket_name,
+			path,
+		} = endpoint;
+		let garage = self.garage.clone();
+
+		// The OPTIONS method is answered without credentials, clients using
+		// it to discover that the server speaks WebDAV
+		if method == Method::Options {
+			return Ok(Response::builder()
+				.header("DAV", "1")
+				.header(ALLOW, ALLOWED_METHODS)
+				.header(CONTENT_LENGTH, "0")
+				.body(Body::empty())?);
+		}
+
+		let api_key = check_basic_auth(&garage, &req)
+			.await?
+			.ok_or_else(|| Error::Unauthorized("Credentials are required".into()))?;
+		let req = count_key_usage_request(&garage, &api_key.key_id, req);
+
+		let bucket_name = match bucket_name {
+			Some(b) => b,
+			None if method == Method::PropFind => {
+				let resp = handle_propfind_root(&garage, &req, &api_key).await?;
+				return Ok(count_key_usage_response(&garage, &api_key.key_id, resp));
+			}
+			None => {
+				return Err(Error::MethodNotAllowed(
+					"The root collection only lists buckets".into(),
+				))
+			}
+		};
+
+		let bucket_id = garage
+			.bucket_helper()
+			.resolve_bucket(&bucket_name, &api_key)
+			.await?;
+		let bucket = garage
+			.bucket_helper()
+			.get_existing_bucket(bucket_id)
+			.await?;
+
+		let allowed = match method {
+			Method::PropFind | Method::Get | Method::Head => api_key.allow_read(&bucket_id),
+			_ => api_key.allow_write(&bucket_id),
+		};
+		if !allowed {
+			return Err(Error::forbidden("Operation is not allowed for this key."));
+		}
+		MetricsBucket::set(&req, bucket_id);
+
+		let is_collection = path.is_empty() || path.ends_with('/');
+		let resp = match method {
+			Method::PropFind => handle_propfind(&garage, &req, &bucket, &bucket_name, &path).await,
+			Method::Get | Method::Head | Method::Put if is_collection => Err(
+				Error::MethodNotAllowed("The resource is a collection".into()),
+			),
+			Method::Get => Ok(handle_get(garage, &req, &bucket, &path, None).await?),
+			Method::Head => Ok(handle_head(garage, &req, &bucket, &path, None).await?),
+			Method::Put => {
+				let mut resp = handle_put(garage, req, &bucket, &path, None).await?;
+				*resp.status_mut() = StatusCode::CREATED;
+				Ok(resp)
+			}
+			Method::Delete => handle_delete_resource(garage, &bucket, &path).await,
+			Method::MkCol => handle_mkcol(garage, req, &bucket, &path).await,
+			Method::Options => unreachable!(),
+		};
+
+		Ok(count_key_usage_response(
+			&self.garage,
+			&api_key.key_id,
+			resp?,
+		))
+	}
+}
+
+impl ApiEndpoint for WebDavEndpoint {
+	fn name(&self) -> &'static str {
+		match self.method {
+			Method::Options => "Options",
+			Method::PropFind => "PropFind",
+			Method::Get => "Get",
+			Method::Head => "Head",
+			Method::Put => "Put",
+			Method::Delete => "Delete",
+			Method::MkCol => "MkCol",
+		}
+	}
+
+	fn add_span_attributes(&self, span: SpanRef<'_>) {
+		if let Some(bucket_name) = &self.bucket_name {
+			span.set_attribute(KeyValue::new("bucket", bucket_name.clone()));
+		}
+		span.set_attribute(KeyValue::new("path", self.path.clone()));
+	}
+
+	fn request_kind(&self) -> RequestKind {
+		match self.method {
+			Method::Put => RequestKind::Put,
+			Method::PropFind => RequestKind::ListScan,
+			_ => RequestKind::Other,
+		}
+	}
+}
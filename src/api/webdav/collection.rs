@@ -0,0 +1,74 @@
+use std::sync::Arc;
+
+use hyper::{Body, Request, Response, StatusCode};
+
+use garage_model::bucket_table::Bucket;
+use garage_model::garage::Garage;
+
+use crate::object_tree::*;
+use crate::s3::delete::handle_delete;
+use crate::s3::put::handle_put;
+use crate::webdav::error::*;
+
+/// Handle a MKCOL request, which stores the marker object of the collection
+pub async fn handle_mkcol(
+	garage: Arc<Garage>,
+	req: Request<Body>,
+	bucket: &Bucket,
+	path: &str,
+) -> Result<Response<Body>, Error> {
+	if path.is_empty() {
+		return Err(Error::MethodNotAllowed(
+			"Buckets cannot be created through WebDAV".into(),
+		));
+	}
+	if resolve_path(&garage, bucket, path).await?.is_some() {
+		return Err(Error::MethodNotAllowed(
+			"The resource already exists".into(),
+		));
+	}
+
+	let prefix = dir_prefix(path);
+	match resolve_path(&garage, bucket, parent_dir(&prefix)).await? {
+		Some(Node::Dir(_)) => (),
+		_ => {
+			return Err(Error::Conflict(
+				"The parent collection does not exist".into(),
+			))
+		}
+	}
+
+	handle_put(garage, req, bucket, &prefix, None).await?;
+	Ok(Response::builder()
+		.status(StatusCode::CREATED)
+		.body(Body::empty())?)
+}
+
+/// Handle a DELETE request, deleting a collection with all its members
+pub async fn handle_delete_resource(
+	garage: Arc<Garage>,
+	bucket: &Bucket,
+	path: &str,
+) -> Result<Response<Body>, Error> {
+	match resolve_path(&garage, bucket, path)
+		.await?
+		.ok_or(Error::NotFound)?
+	{
+		Node::File(_) => {
+			handle_delete(garage, bucket, path).await?;
+		}
+		Node::Dir(prefix) if prefix.is_empty() => {
+			return Err(Error::MethodNotAllowed(
+				"Buckets cannot be deleted through WebDAV".into(),
+			));
+		}
+		Node::Dir(prefix) => {
+			for key in list_dir_keys(&garage, bucket, &prefix).await? {
+				handle_delete(garage.clone(), bucket, &key).await?;
+			}
+		}
+	}
+	Ok(Response::builder()
+		.status(StatusCode::NO_CONTENT)
+		.body(Body::empty())?)
+}
@@ -0,0 +1,128 @@
+use err_derive::Error;
+use hyper::header::HeaderValue;
+use hyper::{Body, HeaderMap, StatusCode};
+
+use garage_model::helper::error::Error as HelperError;
+
+use crate::common_error::CommonError;
+pub use crate::common_error::{CommonErrorDerivative, OkOrBadRequest, OkOrInternalError};
+use crate::generic_server::ApiError;
+use crate::s3::error::Error as S3Error;
+use crate::signature::error::Error as SignatureError;
+
+/// Errors of this crate
+#[derive(Debug, Error)]
+pub enum Error {
+	#[error(display = "{}", _0)]
+	/// Error from common error
+	Common(CommonError),
+
+	/// Error returned by one of the S3 handlers the WebDAV methods are built on
+	#[error(display = "{}", _0)]
+	S3(S3Error),
+
+	/// The request has no valid credentials
+	#[error(display = "Unauthorized: {}", _0)]
+	Unauthorized(String),
+
+	/// The resource requested does not exist
+	#[error(display = "Not found")]
+	NotFound,
+
+	/// The method cannot be applied to the resource
+	#[error(display = "Method not allowed: {}", _0)]
+	MethodNotAllowed(String),
+
+	/// The parent collection of the resource does not exist
+	#[error(display = "Conflict: {}", _0)]
+	Conflict(String),
+
+	/// The request contained an invalid UTF-8 sequence in its path
+	#[error(display = "Invalid UTF-8: {}", _0)]
+	InvalidUtf8Str(#[error(source)] std::str::Utf8Error),
+}
+
+impl<T> From<T> for Error
+where
+	CommonError: From<T>,
+{
+	fn from(err: T) -> Self {
+		Error::Common(CommonError::from(err))
+	}
+}
+
+impl CommonErrorDerivative for Error {}
+
+impl From<HelperError> for Error {
+	fn from(err: HelperError) -> Self {
+		match err {
+			HelperError::Internal(i) => Self::Common(CommonError::InternalError(i)),
+			HelperError::BadRequest(b) => Self::Common(CommonError::BadRequest(b)),
+			HelperError::InvalidBucketName(n) => Self::Common(CommonError::InvalidBucketName(n)),
+			HelperError::NoSuchBucket(_) => Self::NotFound,
+			e => Self::Common(CommonError::BadRequest(format!("{}", e))),
+		}
+	}
+}
+
+impl From<S3Error> for Error {
+	fn from(err: S3Error) -> Self {
+		match err {
+			S3Error::Common(c) => Self::Common(c),
+			S3Error::NoSuchKey => Self::NotFound,
+			e => Self::S3(e),
+		}
+	}
+}
+
+impl From<SignatureError> for Error {
+	fn from(err: SignatureError) -> Self {
+		match err {
+			SignatureError::Common(c) => Self::Common(c),
+			SignatureError::InvalidAccessKeyId(k) => {
+				Self::Unauthorized(format!("No such key: {}", k))
+			}
+			SignatureError::ExpiredAccessKey(k) => {
+				Self::Unauthorized(format!("Key has expired: {}", k))
+			}
+			SignatureError::SignatureDoesNotMatch(m) => Self::Unauthorized(m),
+			SignatureError::InvalidUtf8Str(i) => Self::InvalidUtf8Str(i),
+			e => Self::Common(CommonError::BadRequest(format!("{}", e))),
+		}
+	}
+}
+
+impl ApiError for Error {
+	/// Get the HTTP status code that best represents the meaning of the error for the client
+	fn http_status_code(&self) -> StatusCode {
+		match self {
+			Error::Common(CommonError::NoSuchBucket(_)) => StatusCode::NOT_FOUND,
+			Error::Common(c) => c.http_status_code(),
+			Error::S3(e) => e.http_status_code(),
+			Error::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+			Error::NotFound => StatusCode::NOT_FOUND,
+			Error::MethodNotAllowed(_) => StatusCode::METHOD_NOT_ALLOWED,
+			Error::Conflict(_) => StatusCode::CONFLICT,
+			Error::InvalidUtf8Str(_) => StatusCode::BAD_REQUEST,
+		}
+	}
+
+	fn add_http_headers(&self, header_map: &mut HeaderMap<HeaderValue>) {
+		use hyper::header;
+		header_map.append(
+			header::CONTENT_TYPE,
+			"text/plain; charset=utf-8".parse().unwrap(),
+		);
+		if let Error::Unauthorized(_) = self {
+			// Clients only send their credentials once asked for them
+			header_map.append(
+				header::WWW_AUTHENTICATE,
+				"Basic realm=\"garage\"".parse().unwrap(),
+			);
+		}
+	}
+
+	fn http_body(&self, _garage_region: &str, _path: &str, _request_id: &str) -> Body {
+		Body::from(format!("{}\n", self))
+	}
+}
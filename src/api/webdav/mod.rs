@@ -0,0 +1,5 @@
+pub mod api_server;
+mod error;
+
+mod collection;
+mod propfind;
@@ -0,0 +1,254 @@
+use std::collections::BTreeSet;
+use std::fmt::Write;
+use std::time::{Duration, UNIX_EPOCH};
+
+use hyper::{Body, Request, Response, StatusCode};
+use quick_xml::escape::escape;
+
+use garage_table::*;
+
+use garage_model::bucket_table::Bucket;
+use garage_model::garage::Garage;
+use garage_model::key_table::Key;
+
+use crate::encoding::uri_encode;
+use crate::object_tree::*;
+use crate::webdav::error::*;
+
+/// An entry of a multistatus response
+struct PropEntry {
+	/// Path of the resource, ending with `/` for a collection
+	href: String,
+	name: String,
+	file: Option<FileInfo>,
+}
+
+/// Handle a PROPFIND request on the root collection, whose members are the
+/// buckets the access key can read
+pub async fn handle_propfind_root(
+	garage: &Garage,
+	req: &Request<Body>,
+	api_key: &Key,
+) -> Result<Response<Body>, Error> {
+	let mut entries = vec![PropEntry {
+		href: "/".into(),
+		name: String::new(),
+		file: None,
+	}];
+	if parse_depth(req)? == 0 {
+		return multistatus(&entries);
+	}
+
+	let key_p = api_key.params().ok_or_internal_error(
+		"Key should not be in deleted state at this point (in handle_propfind_root)",
+	)?;
+
+	let mut names = BTreeSet::new();
+	for bucket_id in api_key.authorized_bucket_ids() {
+		if !api_key.allow_read(&bucket_id) {
+			continue;
+		}
+		if let Some(bucket) = garage.bucket_table.get(&EmptyKey, &bucket_id).await? {
+			for (alias, _, _active) in bucket.aliases().iter().filter(|(_, _, active)| *active) {
+				let alias_opt = garage.bucket_alias_table.get(&EmptyKey, alias).await?;
+				if let Some(alias_ent) = alias_opt {
+					if *alias_ent.state.get() == Some(bucket_id) {
+						names.insert(alias_ent.name().to_string());
+					}
+				}
+			}
+		}
+	}
+	for (alias, _, id_opt) in key_p.local_aliases.items() {
+		if matches!(id_opt, Some(id) if api_key.allow_read(id)) {
+			names.insert(alias.clone());
+		}
+	}
+
+	entries.extend(names.into_iter().map(|name| PropEntry {
+		href: format!("/{}/", uri_encode(&name, true)),
+		name,
+		file: None,
+	}));
+	multistatus(&entries)
+}
+
+/// Handle a PROPFIND request on a resource of a bucket
+pub async fn handle_propfind(
+	garage: &Garage,
+	req: &Request<Body>,
+	bucket: &Bucket,
+	bucket_name: &str,
+	path: &str,
+) -> Result<Response<Body>, Error> {
+	let depth = parse_depth(req)?;
+	let base = format!("/{}/", uri_encode(bucket_name, true));
+
+	let mut entries = vec![];
+	match resolve_path(garage, bucket, path)
+		.await?
+		.ok_or(Error::NotFound)?
+	{
+		Node::File(file) => entries.push(PropEntry {
+			href: format!("{}{}", base, uri_encode(path, false)),
+			name: path.rsplit('/').next().unwrap_or_default().to_string(),
+			file: Some(file),
+		}),
+		Node::Dir(prefix) => {
+			let name = match prefix.trim_end_matches('/').rsplit('/').next() {
+				Some(n) if !n.is_empty() => n.to_string(),
+				_ => bucket_name.to_string(),
+			};
+			entries.push(PropEntry {
+				href: format!("{}{}", base, uri_encode(&prefix, false)),
+				name,
+				file: None,
+			});
+			if depth > 0 {
+				for member in list_dir(garage, bucket, &prefix).await? {
+					let mut href = format!("{}{}", base, uri_encode(&prefix, false));
+					href.push_str(&uri_encode(&member.name, true));
+					if member.file.is_none() {
+						href.push('/');
+					}
+					entries.push(PropEntry {
+						href,
+						name: member.name,
+						file: member.file,
+					});
+				}
+			}
+		}
+	}
+	multistatus(&entries)
+}
+
+/// Depth of a PROPFIND request: 0 or 1, a request for an infinite depth
+/// (the default) being processed as a request for depth 1
+fn parse_depth(req: &Request<Body>) -> Result<u8, Error> {
+	let depth = req
+		.headers()
+		.get("depth")
+		.map(|d| d.to_str())
+		.transpose()
+		.ok_or_bad_request("Invalid Depth header")?;
+	match depth {
+		Some("0") => Ok(0),
+		Some("1") | Some("infinity") | None => Ok(1),
+		Some(d) => Err(Error::bad_request(format!("Invalid Depth header: {}", d))),
+	}
+}
+
+fn multistatus(entries: &[PropEntry]) -> Result<Response<Body>, Error> {
+	let mut xml = String::from(
+		"<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<D:multistatus xmlns:D=\"DAV:\">\n",
+	);
+	for entry in entries {
+		write_entry(&mut xml, entry).ok_or_internal_error("Unable to write XML")?;
+	}
+	xml.push_str("</D:multistatus>\n");
+
+	Ok(Response::builder()
+		.status(StatusCode::MULTI_STATUS)
+		.header("Content-Type", "application/xml; charset=utf-8")
+		.body(Body::from(xml))?)
+}
+
+fn write_entry(xml: &mut String, entry: &PropEntry) -> std::fmt::Result {
+	writeln!(xml, "<D:response>")?;
+	writeln!(xml, "<D:href>{}</D:href>", escape(&entry.href))?;
+	writeln!(xml, "<D:propstat>\n<D:prop>")?;
+	writeln!(
+		xml,
+		"<D:displayname>{}</D:displayname>",
+		escape(&entry.name)
+	)?;
+	match &entry.file {
+		None => writeln!(xml, "<D:resourcetype><D:collection/></D:resourcetype>")?,
+		Some(file) => {
+			let modified = UNIX_EPOCH + Duration::from_millis(file.timestamp);
+			writeln!(xml, "<D:resourcetype/>")?;
+			writeln!(
+				xml,
+				"<D:getcontentlength>{}</D:getcontentlength>",
+				file.size
+			)?;
+			writeln!(
+				xml,
+				"<D:getlastmodified>{}</D:getlastmodified>",
+				httpdate::fmt_http_date(modified)
+			)?;
+			if !file.etag.is_empty() {
+				writeln!(xml, "<D:getetag>\"{}\"</D:getetag>", escape(&file.etag))?;
+			}
+			writeln!(
+				xml,
+				"<D:getcontenttype>{}</D:getcontenttype>",
+				escape(&file.content_type)
+			)?;
+		}
+	}
+	writeln!(xml, "</D:prop>\n<D:status>HTTP/1.1 200 OK</D:status>")?;
+	writeln!(xml, "</D:propstat>\n</D:response>")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_parse_depth() {
+		let depth = |value: Option<&str>| {
+			let mut req = Request::builder();
+			if let Some(v) = value {
+				req = req.header("Depth", v);
+			}
+			parse_depth(&req.body(Body::empty()).unwrap())
+		};
+		assert_eq!(depth(Some("0")).unwrap(), 0);
+		assert_eq!(depth(Some("1")).unwrap(), 1);
+		assert_eq!(depth(Some("infinity")).unwrap(), 1);
+		assert_eq!(depth(None).unwrap(), 1);
+		assert!(depth(Some("2")).is_err());
+	}
+
+	#[test]
+	fn test_write_entry() {
+		let mut xml = String::new();
+		write_entry(
+			&mut xml,
+			&PropEntry {
+				href: "/bucket/dir/".into(),
+				name: "dir".into(),
+				file: None,
+			},
+		)
+		.unwrap();
+		assert!(xml.contains("<D:href>/bucket/dir/</D:href>"));
+		assert!(xml.contains("<D:collection/>"));
+		assert!(!xml.contains("getcontentlength"));
+
+		let mut xml = String::new();
+		write_entry(
+			&mut xml,
+			&PropEntry {
+				href: "/bucket/dir/a%26b.txt".into(),
+				name: "a&b.txt".into(),
+				file: Some(FileInfo {
+					size: 42,
+					etag: "abcd".into(),
+					content_type: "text/plain".into(),
+					timestamp: 0,
+				}),
+			},
+		)
+		.unwrap();
+		assert!(xml.contains("<D:displayname>a&amp;b.txt</D:displayname>"));
+		assert!(xml.contains("<D:resourcetype/>"));
+		assert!(xml.contains("<D:getcontentlength>42</D:getcontentlength>"));
+		assert!(
+			xml.contains("<D:getlastmodified>Thu, 01 Jan 1970 00:00:00 GMT</D:getlastmodified>")
+		);
+		assert!(xml.contains("<D:getetag>\"abcd\"</D:getetag>"));
+	}
+}
@@ -0,0 +1,138 @@
+//! Holds on blocks that are being read by clients: a block that is no longer
+//! referenced is not deleted while a hold on it has not expired, so that a
+//! long download of an object that is deleted in the meantime can complete.
+//!
+//! The node that reads the blocks of an object asks the nodes that store
+//! them to hold them, and renews the holds for as long as the download
+//! goes on. Holds are kept in memory and expire on their own, so a node
+//! that crashes in the middle of a download does not keep blocks forever.
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures::future::join_all;
+use tokio::select;
+use tokio::sync::oneshot;
+
+use garage_util::data::*;
+use garage_util::time::*;
+
+use garage_rpc::*;
+
+use garage_table::replication::TableReplication;
+
+use crate::manager::{BlockManager, BlockRpc, BLOCK_GC_DELAY};
+
+/// Duration of a hold, after which it expires if it is not renewed
+pub(crate) const HOLD_DURATION: Duration = Duration::from_secs(1800);
+/// Interval at which the holds of an ongoing download are renewed
+const HOLD_RENEW_INTERVAL: Duration = Duration::from_secs(600);
+
+/// Blocks held on this node, with the time at which their hold expires
+#[derive(Default)]
+pub(crate) struct BlockHolds {
+	holds: Mutex<HashMap<Hash, u64>>,
+}
+
+impl BlockHolds {
+	pub(crate) fn hold(&self, hashes: &[Hash], until: u64) {
+		let now = now_msec();
+		let mut holds = self.holds.lock().unwrap();
+		holds.retain(|_, t| *t > now);
+		for hash in hashes {
+			let t = holds.entry(*hash).or_default();
+			*t = std::cmp::max(*t, until);
+		}
+	}
+
+	/// The time until which a block is held, if it is
+	pub(crate) fn held_until(&self, hash: &Hash) -> Option<u64> {
+		let holds = self.holds.lock().unwrap();
+		holds.get(hash).copied().filter(|t| *t > now_msec())
+	}
+}
+
+/// Hold on the blocks of an object that is being downloaded, renewed until
+/// it is dropped
+pub struct BlockHold {
+	_stop: oneshot::Sender<()>,
+}
+
+impl BlockManager {
+	/// Keep blocks from being deleted for as long as the returned hold is
+	/// not dropped. As unreferenced blocks are only deleted after
+	/// `BLOCK_GC_DELAY`, nodes are only asked to hold the blocks once the
+	/// download lasts longer than half of this delay.
+	pub fn hold_blocks(self: &Arc<Self>, hashes: Vec<Hash>) -> BlockHold {
+		let (stop_tx, mut stop_rx) = oneshot::channel::<()>();
+		let manager = self.clone();
+		tokio::spawn(async move {
+			let mut delay = BLOCK_GC_DELAY / 2;
+			loop {
+				select! {
+					_ = tokio::time::sleep(delay) => (),
+					_ = &mut stop_rx => break,
+				}
+				manager.rpc_hold_blocks(&hashes).await;
+				delay = HOLD_RENEW_INTERVAL;
+			}
+		});
+		BlockHold { _stop: stop_tx }
+	}
+
+	/// Ask the nodes that store some blocks to hold them
+	async fn rpc_hold_blocks(&self, hashes: &[Hash]) {
+		let mut per_node = HashMap::<Uuid, Vec<Hash>>::new();
+		for hash in hashes {
+			for node in self.replication.write_nodes(hash) {
+				per_node.entry(node).or_default().push(*hash);
+			}
+		}
+
+		let duration = HOLD_DURATION.as_millis() as u64;
+		let calls = per_node.into_iter().map(|(node, hashes)| async move {
+			let res = self
+				.system
+				.rpc
+				.call(
+					&self.endpoint,
+					node,
+					BlockRpc::HoldBlocks(hashes, duration),
+					RequestStrategy::with_priority(PRIO_BACKGROUND),
+				)
+				.await;
+			if let Err(e) = res {
+				warn!("Could not hold blocks on node {:?}: {}", node, e);
+			}
+		});
+		join_all(calls).await;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_block_holds() {
+		let holds = BlockHolds::default();
+		let now = now_msec();
+		let (a, b, c) = (gen_uuid(), gen_uuid(), gen_uuid());
+
+		holds.hold(&[a, b], now + 60_000);
+		assert_eq!(holds.held_until(&a), Some(now + 60_000));
+		assert_eq!(holds.held_until(&c), None);
+
+		// Holds are only ever extended
+		holds.hold(&[a], now + 10_000);
+		holds.hold(&[b], now + 120_000);
+		assert_eq!(holds.held_until(&a), Some(now + 60_000));
+		assert_eq!(holds.held_until(&b), Some(now + 120_000));
+
+		// Expired holds no longer count, and are removed by the next hold
+		holds.hold(&[c], now - 1);
+		assert_eq!(holds.held_until(&c), None);
+		holds.hold(&[a], now + 60_000);
+		assert!(!holds.holds.lock().unwrap().contains_key(&c));
+	}
+}
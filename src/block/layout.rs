@@ -6,6 +6,7 @@ use garage_util::config::DataDirEnum;
 use garage_util::data::Hash;
 use garage_util::error::{Error, OkOrMessage};
 use garage_util::migrate::*;
+use garage_util::platform::native_path;
 
 type Idx = u16;
 
@@ -228,6 +229,12 @@ impl DataLayout {
 		self.block_dir_from(hash, &self.data_dirs[idir].path)
 	}
 
+	/// Data directory in which a block is written
+	pub(crate) fn primary_data_dir(&self, hash: &Hash) -> &PathBuf {
+		let ipart = self.partition_from(hash);
+		&self.data_dirs[self.part_prim[ipart] as usize].path
+	}
+
 	pub(crate) fn secondary_block_dirs<'a>(
 		&'a self,
 		hash: &'a Hash,
@@ -242,11 +249,12 @@ impl DataLayout {
 		u16::from_be_bytes([
 			hash.as_slice()[HASH_DRIVE_BYTES.0],
 			hash.as_slice()[HASH_DRIVE_BYTES.1],
-		]) as usize % DRIVE_NPART
+		]) as usize
+			% DRIVE_NPART
 	}
 
 	fn block_dir_from(&self, hash: &Hash, dir: &PathBuf) -> PathBuf {
-		let mut path = dir.clone();
+		let mut path = native_path(dir);
 		path.push(hex::encode(&hash.as_slice()[0..1]));
 		path.push(hex::encode(&hash.as_slice()[1..2]));
 		path
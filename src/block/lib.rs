@@ -1,6 +1,7 @@
 #[macro_use]
 extern crate tracing;
 
+pub mod hold;
 pub mod manager;
 pub mod repair;
 pub mod resync;
@@ -9,3 +10,4 @@ mod block;
 mod layout;
 mod metrics;
 mod rc;
+mod watermark;
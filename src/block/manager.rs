@@ -1,3 +1,5 @@
+use std::convert::TryInto;
+use std::ops::Bound;
 use std::path::PathBuf;
 use std::pin::Pin;
 use std::sync::Arc;
@@ -25,25 +27,28 @@ use garage_rpc::rpc_helper::netapp::stream::{stream_asyncread, ByteStream};
 use garage_db as db;
 
 use garage_util::background::{vars, BackgroundRunner};
-use garage_util::config::DataDirEnum;
+use garage_util::config::{DataDirEnum, DiskWatermarkConfig};
 use garage_util::data::*;
 use garage_util::error::*;
 use garage_util::metrics::RecordDuration;
 use garage_util::persister::{Persister, PersisterShared};
-use garage_util::time::msec_to_rfc3339;
+use garage_util::platform;
+use garage_util::time::*;
 
 use garage_rpc::rpc_helper::OrderTag;
 use garage_rpc::system::System;
 use garage_rpc::*;
 
-use garage_table::replication::{TableReplication, TableShardedReplication};
+use garage_table::replication::{QuorumPolicy, TableReplication, TableShardedReplication};
 
 use crate::block::*;
+use crate::hold::*;
 use crate::layout::*;
 use crate::metrics::*;
 use crate::rc::*;
 use crate::repair::*;
 use crate::resync::*;
+use crate::watermark::DiskWatermark;
 
 /// Size under which data will be stored inlined in database instead of as files
 pub const INLINE_THRESHOLD: usize = 3072;
@@ -69,6 +74,19 @@ pub enum BlockRpc {
 	NeedBlockQuery(Hash),
 	/// Response : whether the node do require that block
 	NeedBlockReply(bool),
+	/// Ask other node for the reference count of a block and whether they store it
+	BlockStatusQuery(Hash),
+	/// Response : reference count of the block on the node, and whether it is stored there
+	BlockStatusReply(BlockStatus),
+	/// Ask other node for the status of a block, also checking the content of the stored block
+	VerifyBlockQuery(Hash),
+	/// Ask other node to mark a block as permanently lost (true), or to remove that mark (false)
+	SetBlockLost(Hash, bool),
+	/// Ask other node to check now whether it has a block, fetching it if it is missing
+	ResyncBlockNow(Hash),
+	/// Ask other node not to delete blocks for some time (in msec), because
+	/// they are being read by a client
+	HoldBlocks(Vec<Hash>, u64),
 }
 
 impl Rpc for BlockRpc {
@@ -93,11 +111,21 @@ pub struct BlockManager {
 	pub(crate) rc: BlockRc,
 	pub resync: BlockResyncManager,
 
+	/// Blocks that have been marked as permanently lost by an administrator,
+	/// with the time at which they were marked
+	lost: db::Tree,
+
+	/// Blocks that must not be deleted yet because clients are reading them
+	pub(crate) holds: BlockHolds,
+
 	pub(crate) system: Arc<System>,
 	pub(crate) endpoint: Arc<Endpoint<BlockRpc, Self>>,
 
 	pub(crate) metrics: BlockManagerMetrics,
 
+	/// Refuses new blocks when the disks of the node are too full
+	pub(crate) watermark: DiskWatermark,
+
 	pub scrub_persister: PersisterShared<ScrubWorkerPersisted>,
 	tx_scrub_command: ArcSwapOption<mpsc::Sender<ScrubWorkerCommand>>,
 }
@@ -111,6 +139,24 @@ pub struct BlockResyncErrorInfo {
 	pub next_try: u64,
 }
 
+/// Status of a block on a storage node
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct BlockStatus {
+	pub refcount: u64,
+	pub stored: bool,
+	/// Whether the content of the stored block matches its hash,
+	/// if it was checked
+	#[serde(default)]
+	pub valid: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BlockLostInfo {
+	pub hash: Hash,
+	pub refcount: u64,
+	pub marked_at: u64,
+}
+
 // The number of different mutexes used to parallelize write access to data blocks
 const MUTEX_COUNT: usize = 256;
 
@@ -125,6 +171,7 @@ impl BlockManager {
 		data_dir: DataDirEnum,
 		data_fsync: bool,
 		compression_level: Option<i32>,
+		disk_watermark: DiskWatermarkConfig,
 		replication: TableShardedReplication,
 		system: Arc<System>,
 	) -> Result<Arc<Self>, Error> {
@@ -150,20 +197,30 @@ impl BlockManager {
 			.expect("Unable to open block_local_rc tree");
 		let rc = BlockRc::new(rc);
 
+		let lost = db
+			.open_tree("block_local_lost")
+			.expect("Unable to open block_local_lost tree");
+
 		let resync = BlockResyncManager::new(db, &system);
 
-		let endpoint = system
-			.netapp
-			.endpoint("garage_block/manager.rs/Rpc".to_string());
+		let endpoint = system.endpoint("garage_block/manager.rs/Rpc".to_string());
+
+		let scrub_persister = PersisterShared::new(&system.metadata_dir, "scrub_info");
 
 		let metrics = BlockManagerMetrics::new(
 			compression_level,
-			rc.rc.clone(),
+			&rc,
 			resync.queue.clone(),
 			resync.errors.clone(),
+			scrub_persister.clone(),
+			data_layout
+				.data_dirs
+				.iter()
+				.map(|d| d.path.clone())
+				.collect(),
 		);
 
-		let scrub_persister = PersisterShared::new(&system.metadata_dir, "scrub_info");
+		let watermark = DiskWatermark::new(disk_watermark, system.metadata_dir.clone());
 
 		let block_manager = Arc::new(Self {
 			replication,
@@ -177,13 +234,18 @@ impl BlockManager {
 				.collect::<Vec<_>>(),
 			rc,
 			resync,
+			lost,
+			holds: BlockHolds::default(),
 			system,
 			endpoint,
 			metrics,
+			watermark,
 			scrub_persister,
 			tx_scrub_command: ArcSwapOption::new(None),
 		});
-		block_manager.endpoint.set_handler(block_manager.clone());
+		block_manager
+			.system
+			.set_handler(&block_manager.endpoint, block_manager.clone());
 		block_manager.scrub_persister.set_with(|_| ()).unwrap();
 
 		Ok(block_manager)
@@ -264,13 +326,20 @@ impl BlockManager {
 		F: Fn(DataBlockHeader, ByteStream) -> Fut,
 		Fut: futures::Future<Output = Result<T, Error>>,
 	{
+		if self.is_block_lost(hash)? {
+			return Err(Error::Message(format!(
+				"Block {:?} has been marked as permanently lost",
+				hash
+			)));
+		}
+
 		let who = self.replication.read_nodes(hash);
 		let who = self.system.rpc.request_order(&who);
 
 		for node in who.iter() {
-			let node_id = NodeID::from(*node);
-			let rpc = self.endpoint.call_streaming(
-				&node_id,
+			let rpc = self.system.rpc.call_streaming(
+				&self.endpoint,
+				*node,
 				BlockRpc::GetBlock(*hash, order_tag),
 				PRIO_NORMAL | PRIO_SECONDARY,
 			);
@@ -295,7 +364,10 @@ impl BlockManager {
 						}
 					};
 					match f(header, stream).await {
-						Ok(ret) => return Ok(ret),
+						Ok(ret) => {
+							self.metrics.block_fetched();
+							return Ok(ret);
+						}
 						Err(e) => {
 							debug!("Get block {:?}: error reading stream from node {:?}: {}", hash, node, e);
 						}
@@ -354,7 +426,20 @@ impl BlockManager {
 
 	/// Send block to nodes that should have it
 	pub async fn rpc_put_block(&self, hash: Hash, data: Bytes) -> Result<(), Error> {
+		self.rpc_put_block_with_quorum(hash, data, QuorumPolicy::Quorum)
+			.await
+	}
+
+	/// Send block to nodes that should have it, waiting for a number
+	/// of acknowledgements given by the specified quorum policy
+	pub async fn rpc_put_block_with_quorum(
+		&self,
+		hash: Hash,
+		data: Bytes,
+		quorum: QuorumPolicy,
+	) -> Result<(), Error> {
 		let who = self.replication.write_nodes(&hash);
+		let quorum = quorum.write_quorum(&self.replication, who.len());
 
 		let (header, bytes) = DataBlock::from_buffer(data, self.compression_level)
 			.await
@@ -368,8 +453,7 @@ impl BlockManager {
 				&self.endpoint,
 				&who[..],
 				put_block_rpc,
-				RequestStrategy::with_priority(PRIO_NORMAL | PRIO_SECONDARY)
-					.with_quorum(self.replication.write_quorum()),
+				RequestStrategy::with_priority(PRIO_NORMAL | PRIO_SECONDARY).with_quorum(quorum),
 			)
 			.await?;
 
@@ -386,6 +470,54 @@ impl BlockManager {
 		Ok(self.rc.rc.fast_len()?)
 	}
 
+	/// Count the blocks stored on this node whose reference count has dropped
+	/// to zero, and that are waiting to be deleted, as well as the space they use
+	pub async fn unreferenced_blocks(&self) -> Result<(u64, u64), Error> {
+		let (mut count, mut bytes) = (0, 0);
+		let mut next_start: Option<Hash> = None;
+		loop {
+			// Read the RC table by batches, so that the iterator is not kept
+			// open while looking for block files (see repair.rs)
+			let mut batch = vec![];
+			let mut n_read = 0;
+			let start_bound = match next_start.as_ref() {
+				None => Bound::Unbounded,
+				Some(x) => Bound::Excluded(x.as_slice()),
+			};
+			for entry in self
+				.rc
+				.rc
+				.range::<&[u8], _>((start_bound, Bound::Unbounded))?
+			{
+				let (hash, rc) = entry?;
+				let hash = Hash::try_from(&hash[..]).unwrap();
+				if RcEntry::parse(&rc).is_zero() {
+					batch.push(hash);
+				}
+				next_start = Some(hash);
+				n_read += 1;
+				if n_read >= 1000 {
+					break;
+				}
+			}
+			if n_read == 0 {
+				break;
+			}
+
+			for hash in batch {
+				let path = match self.find_block(&hash).await {
+					Some(DataBlockPath::Plain(p)) | Some(DataBlockPath::Compressed(p)) => p,
+					None => continue,
+				};
+				if let Ok(meta) = fs::metadata(&path).await {
+					count += 1;
+					bytes += meta.len();
+				}
+			}
+		}
+		Ok((count, bytes))
+	}
+
 	/// Send command to start/stop/manager scrub worker
 	pub async fn send_scrub_command(&self, cmd: ScrubWorkerCommand) -> Result<(), Error> {
 		let tx = self.tx_scrub_command.load();
@@ -394,11 +526,157 @@ impl BlockManager {
 		Ok(())
 	}
 
+	/// Progress of the current scrub (between 0 and 1) as of its last
+	/// checkpoint, which is saved every minute, or None if no scrub is
+	/// running or paused
+	pub fn scrub_progress(&self) -> Option<f32> {
+		self.scrub_persister
+			.get_with(|p| p.checkpoint.as_ref().map(|c| c.progress()))
+	}
+
+	/// Time at which the last complete scrub finished
+	pub fn scrub_last_completed(&self) -> u64 {
+		self.scrub_persister
+			.get_with(|p| p.time_last_complete_scrub)
+	}
+
+	/// Number of blocks transferred to and from other nodes since startup
+	pub fn transfer_stats(&self) -> &BlockTransferStats {
+		&self.metrics.stats
+	}
+
+	/// Number of corrupted blocks detected by the last scrubs
+	pub fn scrub_corruptions_detected(&self) -> u64 {
+		self.scrub_persister.get_with(|p| p.corruptions_detected)
+	}
+
+	/// Number of blocks that are no longer referenced and are waiting to
+	/// be deleted. This scans the whole reference counter table.
+	pub fn gc_candidates(&self) -> Result<u64, Error> {
+		self.rc.gc_candidates()
+	}
+
+	/// Directories in which new blocks are currently refused because
+	/// their free space is below the configured watermarks
+	pub fn full_dirs(&self) -> Vec<(PathBuf, String)> {
+		self.watermark.full_dirs()
+	}
+
+	/// Paths of the data directories of this node
+	pub fn data_dirs(&self) -> Vec<PathBuf> {
+		self.data_layout
+			.load()
+			.data_dirs
+			.iter()
+			.map(|d| d.path.clone())
+			.collect()
+	}
+
 	/// Get the reference count of a block
 	pub fn get_block_rc(&self, hash: &Hash) -> Result<u64, Error> {
 		Ok(self.rc.get_block_rc(hash)?.as_u64())
 	}
 
+	/// Ask all nodes that should store a block whether they actually have it
+	pub async fn rpc_get_block_status(
+		&self,
+		hash: &Hash,
+	) -> Result<Vec<(Uuid, Result<BlockStatus, Error>)>, Error> {
+		self.rpc_block_status_query(hash, BlockRpc::BlockStatusQuery(*hash))
+			.await
+	}
+
+	/// Ask all nodes that should store a block whether they actually have it,
+	/// and to check that the content of their copy matches the block's hash
+	pub async fn rpc_verify_block(
+		&self,
+		hash: &Hash,
+	) -> Result<Vec<(Uuid, Result<BlockStatus, Error>)>, Error> {
+		self.rpc_block_status_query(hash, BlockRpc::VerifyBlockQuery(*hash))
+			.await
+	}
+
+	async fn rpc_block_status_query(
+		&self,
+		hash: &Hash,
+		msg: BlockRpc,
+	) -> Result<Vec<(Uuid, Result<BlockStatus, Error>)>, Error> {
+		let who = self.replication.write_nodes(hash);
+		let resps = self
+			.system
+			.rpc
+			.call_many(
+				&self.endpoint,
+				&who,
+				msg,
+				RequestStrategy::with_priority(PRIO_NORMAL),
+			)
+			.await?;
+
+		Ok(resps
+			.into_iter()
+			.map(|(node, resp)| {
+				let status = match resp {
+					Ok(BlockRpc::BlockStatusReply(status)) => Ok(status),
+					Ok(m) => Err(Error::unexpected_rpc_message(m)),
+					Err(e) => Err(e),
+				};
+				(node, status)
+			})
+			.collect())
+	}
+
+	/// Mark a block as permanently lost (or remove that mark) on all nodes of the cluster
+	pub async fn rpc_set_block_lost(
+		&self,
+		hash: &Hash,
+		lost: bool,
+	) -> Result<Vec<(Uuid, Result<(), Error>)>, Error> {
+		let who = self.system.ring.borrow().layout.node_ids().to_vec();
+		self.rpc_call_nodes(&who, BlockRpc::SetBlockLost(*hash, lost))
+			.await
+	}
+
+	/// Ask all nodes that should store a block to check now whether they have it,
+	/// and to fetch it from other nodes if it is missing
+	pub async fn rpc_resync_block_now(
+		&self,
+		hash: &Hash,
+	) -> Result<Vec<(Uuid, Result<(), Error>)>, Error> {
+		let who = self.replication.write_nodes(hash);
+		self.rpc_call_nodes(&who, BlockRpc::ResyncBlockNow(*hash))
+			.await
+	}
+
+	async fn rpc_call_nodes(
+		&self,
+		who: &[Uuid],
+		msg: BlockRpc,
+	) -> Result<Vec<(Uuid, Result<(), Error>)>, Error> {
+		let resps = self
+			.system
+			.rpc
+			.call_many(
+				&self.endpoint,
+				who,
+				msg,
+				RequestStrategy::with_priority(PRIO_NORMAL),
+			)
+			.await?;
+
+		Ok(resps
+			.into_iter()
+			.map(|(node, resp)| {
+				let res = match resp {
+					Ok(BlockRpc::Ok) => Ok(()),
+					Ok(m) => Err(Error::unexpected_rpc_message(m)),
+					Err(e) => Err(e),
+				};
+				(node, res)
+			})
+			.collect())
+	}
+
 	/// List all resync errors
 	pub fn list_resync_errors(&self) -> Result<Vec<BlockResyncErrorInfo>, Error> {
 		let mut blocks = Vec::with_capacity(self.resync.errors.len());
@@ -419,6 +697,43 @@ impl BlockManager {
 		Ok(blocks)
 	}
 
+	/// List all blocks marked as lost on this node
+	pub fn list_lost_blocks(&self) -> Result<Vec<BlockLostInfo>, Error> {
+		let mut blocks = Vec::with_capacity(self.lost.len()?);
+		for ent in self.lost.iter()? {
+			let (hash, marked_at) = ent?;
+			blocks.push(BlockLostInfo {
+				hash: Hash::try_from(&hash).unwrap(),
+				refcount: 0,
+				marked_at: u64::from_be_bytes(marked_at[0..8].try_into().unwrap()),
+			});
+		}
+		for block in blocks.iter_mut() {
+			block.refcount = self.get_block_rc(&block.hash)?;
+		}
+		Ok(blocks)
+	}
+
+	/// Whether a block has been marked as permanently lost on this node
+	pub fn is_block_lost(&self, hash: &Hash) -> Result<bool, Error> {
+		Ok(self.lost.get(hash)?.is_some())
+	}
+
+	/// Mark a block as permanently lost on this node (or remove that mark).
+	/// Reading a lost block fails immediately instead of asking all nodes
+	/// for it, and the resync worker doesn't try to fetch it anymore.
+	pub fn set_block_lost(&self, hash: &Hash, lost: bool) -> Result<(), Error> {
+		if lost {
+			if !self.is_block_lost(hash)? {
+				info!("Marking block {:?} as permanently lost", hash);
+				self.lost.insert(hash, u64::to_be_bytes(now_msec()))?;
+			}
+		} else if self.lost.remove(hash)?.is_some() {
+			info!("Block {:?} is no longer marked as lost", hash);
+		}
+		Ok(())
+	}
+
 	//// ----- Managing the reference counter ----
 
 	/// Increment the number of time a block is used, putting it to resynchronization if it is
@@ -500,6 +815,9 @@ impl BlockManager {
 			))
 			.await?;
 
+		// The block was found again, it is not lost anymore
+		self.set_block_lost(hash, false)?;
+
 		Ok(())
 	}
 
@@ -510,6 +828,7 @@ impl BlockManager {
 		};
 
 		let (header, data) = block.into_parts();
+		self.metrics.block_sent();
 
 		let resp = Resp::new(Ok(BlockRpc::PutBlock {
 			hash: *hash,
@@ -530,6 +849,10 @@ impl BlockManager {
 		async {
 			match self.find_block(hash).await {
 				Some(p) => self.read_block_from(hash, &p).await,
+				None if self.is_block_lost(hash)? => Err(Error::Message(format!(
+					"block {:?} has been marked as permanently lost",
+					hash
+				))),
 				None => {
 					// Not found but maybe we should have had it ??
 					self.resync
@@ -563,6 +886,9 @@ impl BlockManager {
 		f.read_to_end(&mut data).await?;
 		self.metrics.bytes_read.add(data.len() as u64);
 		drop(f);
+		self.system
+			.fault_injection
+			.after_block_read(hash, &mut data);
 
 		let data = if compressed {
 			DataBlock::Compressed(data.into())
@@ -596,6 +922,35 @@ impl BlockManager {
 		Ok(rc.is_nonzero() && !exists)
 	}
 
+	/// Get the reference count of a block on this node and whether it is stored here
+	pub async fn block_status(&self, hash: &Hash) -> Result<BlockStatus, Error> {
+		Ok(BlockStatus {
+			refcount: self.get_block_rc(hash)?,
+			stored: self.find_block(hash).await.is_some(),
+			valid: None,
+		})
+	}
+
+	/// Get the status of a block on this node, reading the stored block
+	/// to check that its content matches its hash. Corrupted blocks are
+	/// moved away and resynced as when they are read.
+	pub async fn verify_block(&self, hash: &Hash) -> Result<BlockStatus, Error> {
+		let refcount = self.get_block_rc(hash)?;
+		let valid = match self.find_block(hash).await {
+			Some(path) => match self.read_block_from(hash, &path).await {
+				Ok(_) => Some(true),
+				Err(Error::CorruptData(_)) => Some(false),
+				Err(e) => return Err(e),
+			},
+			None => None,
+		};
+		Ok(BlockStatus {
+			refcount,
+			stored: valid.is_some(),
+			valid,
+		})
+	}
+
 	/// Delete block if it is not needed anymore
 	pub(crate) async fn delete_if_unneeded(&self, hash: &Hash) -> Result<(), Error> {
 		self.lock_mutate(hash)
@@ -681,6 +1036,23 @@ impl StreamingEndpointHandler<BlockRpc> for BlockManager {
 			BlockRpc::NeedBlockQuery(h) => {
 				Resp::new(self.need_block(h).await.map(BlockRpc::NeedBlockReply))
 			}
+			BlockRpc::BlockStatusQuery(h) => {
+				Resp::new(self.block_status(h).await.map(BlockRpc::BlockStatusReply))
+			}
+			BlockRpc::VerifyBlockQuery(h) => {
+				Resp::new(self.verify_block(h).await.map(BlockRpc::BlockStatusReply))
+			}
+			BlockRpc::SetBlockLost(h, lost) => {
+				Resp::new(self.set_block_lost(h, *lost).map(|()| BlockRpc::Ok))
+			}
+			BlockRpc::ResyncBlockNow(h) => {
+				Resp::new(self.resync.resync_now(h).map(|()| BlockRpc::Ok))
+			}
+			BlockRpc::HoldBlocks(hashes, duration) => {
+				let duration = std::cmp::min(*duration, HOLD_DURATION.as_millis() as u64);
+				self.holds.hold(hashes, now_msec() + duration);
+				Resp::new(Ok(BlockRpc::Ok))
+			}
 			m => Resp::new(Err(Error::unexpected_rpc_message(m))),
 		}
 	}
@@ -704,10 +1076,18 @@ impl BlockManagerLocked {
 		mgr: &BlockManager,
 		existing_path: Option<DataBlockPath>,
 	) -> Result<(), Error> {
+		mgr.system.fault_injection.before_block_write(hash)?;
+
 		let compressed = data.is_compressed();
 		let data = data.inner_buffer();
 
-		let directory = mgr.data_layout.load().primary_block_dir(hash);
+		let (data_dir, directory) = {
+			let data_layout = mgr.data_layout.load();
+			(
+				data_layout.primary_data_dir(hash).clone(),
+				data_layout.primary_block_dir(hash),
+			)
+		};
 
 		let mut tgt_path = directory.clone();
 		tgt_path.push(hex::encode(hash));
@@ -739,6 +1119,8 @@ impl BlockManagerLocked {
 		};
 		assert!(to_delete.as_ref() != Some(&tgt_path));
 
+		mgr.watermark.check_block_write(&data_dir)?;
+
 		let mut path_tmp = tgt_path.clone();
 		let tmp_extension = format!("tmp{}", hex::encode(thread_rng().gen::<[u8; 4]>()));
 		path_tmp.set_extension(tmp_extension);
@@ -757,7 +1139,7 @@ impl BlockManagerLocked {
 
 		drop(f);
 
-		fs::rename(path_tmp, tgt_path).await?;
+		platform::rename_replace(&path_tmp, &tgt_path).await?;
 
 		delete_on_drop.cancel();
 
@@ -769,15 +1151,8 @@ impl BlockManagerLocked {
 			// We want to ensure that when this function returns, data is properly persisted
 			// to disk. The first step is the sync_all above that does an fsync on the data file.
 			// Now, we do an fsync on the containing directory, to ensure that the rename
-			// is persisted properly. See:
-			// http://thedjbway.b0llix.net/qmail/syncdir.html
-			let dir = fs::OpenOptions::new()
-				.read(true)
-				.mode(0)
-				.open(directory)
-				.await?;
-			dir.sync_all().await?;
-			drop(dir);
+			// is persisted properly.
+			platform::sync_dir(&directory).await?;
 		}
 
 		Ok(())
@@ -797,7 +1172,7 @@ impl BlockManagerLocked {
 			}
 		};
 
-		fs::rename(path, path2).await?;
+		platform::rename_replace(path, &path2).await?;
 		Ok(())
 	}
 
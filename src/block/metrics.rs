@@ -1,14 +1,34 @@
-use opentelemetry::{global, metrics::*};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use opentelemetry::{global, metrics::*, KeyValue};
 
-use garage_db as db;
 use garage_db::counted_tree_hack::CountedTree;
 
+use garage_util::persister::PersisterShared;
+use garage_util::platform::disk_space;
+
+use crate::rc::BlockRc;
+use crate::repair::ScrubWorkerPersisted;
+use crate::resync::queue_oldest_age;
+
+/// Counting the blocks waiting to be deleted requires a scan of the
+/// reference counter, which is done at most once in this interval
+const GC_CANDIDATES_REFRESH_INTERVAL: Duration = Duration::from_secs(600);
+
 /// TableMetrics reference all counter used for metrics
 pub struct BlockManagerMetrics {
 	pub(crate) _compression_level: ValueObserver<u64>,
 	pub(crate) _rc_size: ValueObserver<u64>,
 	pub(crate) _resync_queue_len: ValueObserver<u64>,
+	pub(crate) _resync_queue_oldest_age: ValueObserver<f64>,
 	pub(crate) _resync_errored_blocks: ValueObserver<u64>,
+	pub(crate) _gc_candidates: ValueObserver<u64>,
+	pub(crate) _scrub_corruptions: ValueObserver<u64>,
+	pub(crate) _data_dir_avail: ValueObserver<u64>,
+	pub(crate) _data_dir_total: ValueObserver<u64>,
 
 	pub(crate) resync_counter: BoundCounter<u64>,
 	pub(crate) resync_error_counter: BoundCounter<u64>,
@@ -16,6 +36,11 @@ pub struct BlockManagerMetrics {
 	pub(crate) resync_send_counter: Counter<u64>,
 	pub(crate) resync_recv_counter: BoundCounter<u64>,
 
+	pub(crate) blocks_fetched: BoundCounter<u64>,
+	pub(crate) blocks_sent: BoundCounter<u64>,
+	/// Same as the counters above, readable for `garage stats`
+	pub(crate) stats: BlockTransferStats,
+
 	pub(crate) bytes_read: BoundCounter<u64>,
 	pub(crate) block_read_duration: BoundValueRecorder<f64>,
 	pub(crate) bytes_written: BoundCounter<u64>,
@@ -25,14 +50,65 @@ pub struct BlockManagerMetrics {
 	pub(crate) corruption_counter: BoundCounter<u64>,
 }
 
+/// Number of blocks transferred to and from other nodes to serve reads
+/// since the node started, and bandwidth of the transfers of blocks
+/// received during resync
+pub struct BlockTransferStats {
+	started: Instant,
+	fetched: AtomicU64,
+	sent: AtomicU64,
+	resync_bytes: AtomicU64,
+	resync_micros: AtomicU64,
+}
+
+impl BlockTransferStats {
+	/// Blocks fetched from other nodes, and average per second
+	pub fn fetched(&self) -> (u64, f64) {
+		self.with_rate(self.fetched.load(Ordering::Relaxed))
+	}
+
+	/// Blocks sent to other nodes, and average per second
+	pub fn sent(&self) -> (u64, f64) {
+		self.with_rate(self.sent.load(Ordering::Relaxed))
+	}
+
+	/// Number of bytes received during resync
+	pub fn resync_bytes(&self) -> u64 {
+		self.resync_bytes.load(Ordering::Relaxed)
+	}
+
+	/// Average rate at which blocks were received during resync, in
+	/// bytes per second, if any block has been received
+	pub fn resync_bandwidth(&self) -> Option<u64> {
+		let bytes = self.resync_bytes.load(Ordering::Relaxed);
+		let micros = self.resync_micros.load(Ordering::Relaxed);
+		if bytes == 0 || micros == 0 {
+			return None;
+		}
+		Some(bytes * 1_000_000 / micros)
+	}
+
+	fn with_rate(&self, n: u64) -> (u64, f64) {
+		let secs = self.started.elapsed().as_secs_f64().max(1.);
+		(n, n as f64 / secs)
+	}
+}
+
 impl BlockManagerMetrics {
 	pub fn new(
 		compression_level: Option<i32>,
-		rc_tree: db::Tree,
+		rc: &BlockRc,
 		resync_queue: CountedTree,
 		resync_errors: CountedTree,
+		scrub_persister: PersisterShared<ScrubWorkerPersisted>,
+		data_dirs: Vec<PathBuf>,
 	) -> Self {
 		let meter = global::meter("garage_model/block");
+		let rc_tree = rc.rc.clone();
+		let gc_rc = BlockRc::new(rc.rc.clone());
+		let gc_candidates = Mutex::new(None::<(Instant, u64)>);
+		let resync_queue_2 = resync_queue.clone();
+		let data_dirs_2 = data_dirs.clone();
 		Self {
 			_compression_level: meter
 				.u64_value_observer("block.compression_level", move |observer| {
@@ -59,12 +135,72 @@ impl BlockManagerMetrics {
 					"Number of block hashes queued for local check and possible resync",
 				)
 				.init(),
+			_resync_queue_oldest_age: meter
+				.f64_value_observer("block.resync_queue_oldest_age", move |observer| {
+					if let Ok(age) = queue_oldest_age(&resync_queue_2) {
+						observer.observe(age.as_secs_f64(), &[])
+					}
+				})
+				.with_description(
+					"Time for which the first item of the resync queue has been due, in seconds",
+				)
+				.init(),
 			_resync_errored_blocks: meter
 				.u64_value_observer("block.resync_errored_blocks", move |observer| {
 					observer.observe(resync_errors.len() as u64, &[])
 				})
 				.with_description("Number of block hashes whose last resync resulted in an error")
 				.init(),
+			_gc_candidates: meter
+				.u64_value_observer("block.gc_candidates", move |observer| {
+					let mut cached = gc_candidates.lock().unwrap();
+					let fresh = matches!(*cached, Some((t, _)) if t.elapsed() < GC_CANDIDATES_REFRESH_INTERVAL);
+					if !fresh {
+						match gc_rc.gc_candidates() {
+							Ok(n) => *cached = Some((Instant::now(), n)),
+							Err(e) => warn!("Unable to count blocks to be deleted: {}", e),
+						}
+					}
+					if let Some((_, n)) = *cached {
+						observer.observe(n, &[])
+					}
+				})
+				.with_description(
+					"Number of blocks that are no longer referenced and will be deleted",
+				)
+				.init(),
+			_scrub_corruptions: meter
+				.u64_value_observer("block.scrub_corruptions_detected", move |observer| {
+					observer.observe(scrub_persister.get_with(|p| p.corruptions_detected), &[])
+				})
+				.with_description("Number of corrupted blocks detected by the last scrubs")
+				.init(),
+			_data_dir_avail: meter
+				.u64_value_observer("block.data_dir_available_bytes", move |observer| {
+					for dir in data_dirs.iter() {
+						if let Ok(space) = disk_space(dir) {
+							observer.observe(
+								space.available,
+								&[KeyValue::new("dir", dir.display().to_string())],
+							)
+						}
+					}
+				})
+				.with_description("Space available on the filesystem of each data directory")
+				.init(),
+			_data_dir_total: meter
+				.u64_value_observer("block.data_dir_total_bytes", move |observer| {
+					for dir in data_dirs_2.iter() {
+						if let Ok(space) = disk_space(dir) {
+							observer.observe(
+								space.total,
+								&[KeyValue::new("dir", dir.display().to_string())],
+							)
+						}
+					}
+				})
+				.with_description("Size of the filesystem of each data directory")
+				.init(),
 
 			resync_counter: meter
 				.u64_counter("block.resync_counter")
@@ -91,6 +227,24 @@ impl BlockManagerMetrics {
 				.init()
 				.bind(&[]),
 
+			blocks_fetched: meter
+				.u64_counter("block.fetched_counter")
+				.with_description("Number of blocks fetched from other nodes to serve reads")
+				.init()
+				.bind(&[]),
+			blocks_sent: meter
+				.u64_counter("block.sent_counter")
+				.with_description("Number of blocks sent to other nodes to serve their reads")
+				.init()
+				.bind(&[]),
+			stats: BlockTransferStats {
+				started: Instant::now(),
+				fetched: AtomicU64::new(0),
+				sent: AtomicU64::new(0),
+				resync_bytes: AtomicU64::new(0),
+				resync_micros: AtomicU64::new(0),
+			},
+
 			bytes_read: meter
 				.u64_counter("block.bytes_read")
 				.with_description("Number of bytes read from disk")
@@ -124,4 +278,24 @@ impl BlockManagerMetrics {
 				.bind(&[]),
 		}
 	}
+
+	pub(crate) fn block_fetched(&self) {
+		self.blocks_fetched.add(1);
+		self.stats.fetched.fetch_add(1, Ordering::Relaxed);
+	}
+
+	pub(crate) fn block_sent(&self) {
+		self.blocks_sent.add(1);
+		self.stats.sent.fetch_add(1, Ordering::Relaxed);
+	}
+
+	pub(crate) fn block_resynced(&self, bytes: usize, duration: Duration) {
+		self.resync_recv_counter.add(1);
+		self.stats
+			.resync_bytes
+			.fetch_add(bytes as u64, Ordering::Relaxed);
+		self.stats
+			.resync_micros
+			.fetch_add(duration.as_micros() as u64, Ordering::Relaxed);
+	}
 }
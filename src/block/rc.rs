@@ -52,6 +52,19 @@ impl BlockRc {
 		Ok(RcEntry::parse_opt(self.rc.get(hash.as_ref())?))
 	}
 
+	/// Count the blocks that are no longer referenced and are waiting
+	/// to be deleted. This scans the whole RC table.
+	pub(crate) fn gc_candidates(&self) -> Result<u64, Error> {
+		let mut n = 0;
+		for entry in self.rc.iter()? {
+			let (_, v) = entry?;
+			if RcEntry::parse(&v).is_deletable() {
+				n += 1;
+			}
+		}
+		Ok(n)
+	}
+
 	/// Delete an entry in the RC table if it is deletable and the
 	/// deletion time has passed
 	pub(crate) fn clear_deleted_block_rc(&self, hash: &Hash) -> Result<(), Error> {
@@ -93,7 +106,7 @@ pub(crate) enum RcEntry {
 }
 
 impl RcEntry {
-	fn parse(bytes: &[u8]) -> Self {
+	pub(crate) fn parse(bytes: &[u8]) -> Self {
 		if bytes.len() == 8 {
 			RcEntry::Present {
 				count: u64::from_be_bytes(bytes.try_into().unwrap()),
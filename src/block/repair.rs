@@ -1,5 +1,5 @@
 use core::ops::Bound;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -34,27 +34,104 @@ const INITIAL_SCRUB_TRANQUILITY: u32 = 4;
 
 pub struct RepairWorker {
 	manager: Arc<BlockManager>,
+	scope: RepairScope,
 	next_start: Option<Hash>,
 	block_iter: Option<BlockStoreIterator>,
+	counters: WorkerCounters,
+}
+
+/// Restriction of a block repair to some of the blocks of the node,
+/// e.g. to the blocks of a data directory whose disk was replaced
+#[derive(Clone, Debug, Default)]
+pub struct RepairScope {
+	/// First block hash to repair (included)
+	pub start: Option<Hash>,
+	/// Last block hash to repair (excluded)
+	pub end: Option<Hash>,
+	/// Only repair blocks whose primary location is this data directory
+	pub data_dir: Option<PathBuf>,
+}
+
+impl RepairScope {
+	fn contains(&self, manager: &BlockManager, hash: &Hash) -> bool {
+		self.start.map(|s| *hash >= s).unwrap_or(true)
+			&& self.end.map(|e| *hash < e).unwrap_or(true)
+			&& self
+				.data_dir
+				.as_ref()
+				.map(|d| {
+					manager
+						.data_layout
+						.load()
+						.primary_block_dir(hash)
+						.starts_with(d)
+				})
+				.unwrap_or(true)
+	}
 }
 
 impl RepairWorker {
-	pub fn new(manager: Arc<BlockManager>) -> Self {
-		Self {
+	pub fn new(manager: Arc<BlockManager>, scope: RepairScope) -> Result<Self, Error> {
+		if let Some(dir) = &scope.data_dir {
+			let data_layout = manager.data_layout.load();
+			if !data_layout.data_dirs.iter().any(|d| d.path == *dir) {
+				return Err(Error::Message(format!(
+					"{} is not a data directory of this node",
+					dir.display()
+				)));
+			}
+		}
+		Ok(Self {
 			manager,
+			scope,
 			next_start: None,
 			block_iter: None,
+			counters: WorkerCounters::new(),
+		})
+	}
+
+	/// Returns progress done, between 0 and 1: the first half is the
+	/// iteration on the reference counters, the second half the iteration
+	/// on the blocks stored on disk
+	pub fn progress(&self) -> f32 {
+		match &self.block_iter {
+			None => {
+				let start = self.scope.start.as_ref().map(hash_position).unwrap_or(0.);
+				let end = self.scope.end.as_ref().map(hash_position).unwrap_or(1.);
+				let pos = self.next_start.as_ref().map(hash_position).unwrap_or(start);
+				if end > start {
+					(pos - start) / (end - start) / 2.
+				} else {
+					0.
+				}
+			}
+			Some(bi) => 0.5 + bi.progress() / 2.,
 		}
 	}
 }
 
+/// Position of a hash in the hash space, between 0 and 1
+fn hash_position(hash: &Hash) -> f32 {
+	let mut prefix = [0u8; 4];
+	prefix.copy_from_slice(&hash.as_slice()[..4]);
+	u32::from_be_bytes(prefix) as f32 / (u32::MAX as f32 + 1.)
+}
+
 #[async_trait]
 impl Worker for RepairWorker {
 	fn name(&self) -> String {
 		"Block repair worker".into()
 	}
 
+	fn is_maintenance(&self) -> bool {
+		true
+	}
+
 	fn status(&self) -> WorkerStatus {
+		let counters = Some(WorkerCounters {
+			fraction: Some(self.progress()),
+			..self.counters.clone()
+		});
 		match self.block_iter.as_ref() {
 			None => {
 				let idx_bytes = self
@@ -73,12 +150,14 @@ impl Worker for RepairWorker {
 						"Currently in phase 1, iterator position: {}",
 						hex::encode(idx_bytes)
 					)],
+					counters,
 					..Default::default()
 				}
 			}
 			Some(bi) => WorkerStatus {
 				progress: Some(format!("{:.2}%", bi.progress() * 100.)),
 				freeform: vec!["Currently in phase 2".into()],
+				counters,
 				..Default::default()
 			},
 		}
@@ -100,15 +179,20 @@ impl Worker for RepairWorker {
 				// in a mutex (see db/sqlite_adapter.rs and discussion in PR #322).
 				// TODO: maybe do this with tokio::task::spawn_blocking ?
 				let mut batch_of_hashes = vec![];
-				let start_bound = match self.next_start.as_ref() {
-					None => Bound::Unbounded,
+				let start_bound = match (self.next_start.as_ref(), self.scope.start.as_ref()) {
+					(Some(x), _) => Bound::Excluded(x.as_slice()),
+					(None, Some(x)) => Bound::Included(x.as_slice()),
+					(None, None) => Bound::Unbounded,
+				};
+				let end_bound = match self.scope.end.as_ref() {
 					Some(x) => Bound::Excluded(x.as_slice()),
+					None => Bound::Unbounded,
 				};
 				for entry in self
 					.manager
 					.rc
 					.rc
-					.range::<&[u8], _>((start_bound, Bound::Unbounded))?
+					.range::<&[u8], _>((start_bound, end_bound))?
 				{
 					let (hash, _) = entry?;
 					let hash = Hash::try_from(&hash[..]).unwrap();
@@ -119,14 +203,20 @@ impl Worker for RepairWorker {
 				}
 				if batch_of_hashes.is_empty() {
 					// move on to phase 2
-					self.block_iter = Some(BlockStoreIterator::new(&self.manager));
+					self.block_iter = Some(BlockStoreIterator::new(
+						&self.manager,
+						self.scope.data_dir.as_deref(),
+					));
 					return Ok(WorkerState::Busy);
 				}
 
+				self.counters.items += batch_of_hashes.len() as u64;
 				for hash in batch_of_hashes.into_iter() {
-					self.manager
-						.resync
-						.put_to_resync(&hash, Duration::from_secs(0))?;
+					if self.scope.contains(&self.manager, &hash) {
+						self.manager
+							.resync
+							.put_to_resync(&hash, Duration::from_secs(0))?;
+					}
 					self.next_start = Some(hash)
 				}
 
@@ -138,9 +228,12 @@ impl Worker for RepairWorker {
 				// This allows us to find blocks we are storing but don't actually need,
 				// so that we can offload them if necessary and then delete them locally.
 				if let Some((_path, hash)) = bi.next().await? {
-					self.manager
-						.resync
-						.put_to_resync(&hash, Duration::from_secs(0))?;
+					self.counters.items += 1;
+					if self.scope.contains(&self.manager, &hash) {
+						self.manager
+							.resync
+							.put_to_resync(&hash, Duration::from_secs(0))?;
+					}
 					Ok(WorkerState::Busy)
 				} else {
 					Ok(WorkerState::Done)
@@ -237,6 +330,8 @@ pub struct ScrubWorker {
 
 	work: ScrubWorkerState,
 	tranquilizer: Tranquilizer,
+	/// Blocks and bytes checked since the scrub was last started or resumed
+	counters: WorkerCounters,
 
 	persister: PersisterShared<ScrubWorkerPersisted>,
 }
@@ -295,18 +390,23 @@ impl ScrubWorker {
 		rx_cmd: mpsc::Receiver<ScrubWorkerCommand>,
 		persister: PersisterShared<ScrubWorkerPersisted>,
 	) -> Self {
+		let mut counters = WorkerCounters::new();
 		let work = match persister.get_with(|x| x.checkpoint.clone()) {
 			None => ScrubWorkerState::Finished,
-			Some(iterator) => ScrubWorkerState::Running {
-				iterator,
-				t_cp: now_msec(),
-			},
+			Some(iterator) => {
+				counters.fraction_start = iterator.progress();
+				ScrubWorkerState::Running {
+					iterator,
+					t_cp: now_msec(),
+				}
+			}
 		};
 		Self {
 			manager,
 			rx_cmd,
 			work,
 			tranquilizer: Tranquilizer::new(30),
+			counters,
 			persister,
 		}
 	}
@@ -317,13 +417,14 @@ impl ScrubWorker {
 				self.work = match std::mem::take(&mut self.work) {
 					ScrubWorkerState::Finished => {
 						info!("Scrub worker initializing, now performing datastore scrub");
-						let iterator = BlockStoreIterator::new(&self.manager);
+						let iterator = BlockStoreIterator::new(&self.manager, None);
 						if let Err(e) = self
 							.persister
 							.set_with(|x| x.checkpoint = Some(iterator.clone()))
 						{
 							error!("Could not save scrub checkpoint: {}", e);
 						}
+						self.counters = WorkerCounters::new();
 						ScrubWorkerState::Running {
 							iterator,
 							t_cp: now_msec(),
@@ -358,10 +459,16 @@ impl ScrubWorker {
 			}
 			ScrubWorkerCommand::Resume => {
 				self.work = match std::mem::take(&mut self.work) {
-					ScrubWorkerState::Paused { iterator, .. } => ScrubWorkerState::Running {
-						iterator,
-						t_cp: now_msec(),
-					},
+					ScrubWorkerState::Paused { iterator, .. } => {
+						self.counters = WorkerCounters {
+							fraction_start: iterator.progress(),
+							..WorkerCounters::new()
+						};
+						ScrubWorkerState::Running {
+							iterator,
+							t_cp: now_msec(),
+						}
+					}
 					work => {
 						error!("Cannot resume scrub worker: not paused!");
 						work
@@ -392,6 +499,10 @@ impl Worker for ScrubWorker {
 		"Block scrub worker".into()
 	}
 
+	fn is_maintenance(&self) -> bool {
+		true
+	}
+
 	fn status(&self) -> WorkerStatus {
 		let (corruptions_detected, tranquility, time_last_complete_scrub, time_next_run_scrub) =
 			self.persister.get_with(|p| {
@@ -411,6 +522,10 @@ impl Worker for ScrubWorker {
 		match &self.work {
 			ScrubWorkerState::Running { iterator, .. } => {
 				s.progress = Some(format!("{:.2}%", iterator.progress() * 100.));
+				s.counters = Some(WorkerCounters {
+					fraction: Some(iterator.progress()),
+					..self.counters.clone()
+				});
 			}
 			ScrubWorkerState::Paused { iterator, t_resume } => {
 				s.progress = Some(format!("{:.2}%", iterator.progress() * 100.));
@@ -454,8 +569,9 @@ impl Worker for ScrubWorker {
 							self.persister.set_with(|p| p.corruptions_detected += 1)?;
 						}
 						Err(e) => return Err(e),
-						_ => (),
+						Ok(block) => self.counters.bytes += block.inner_buffer().len() as u64,
 					};
+					self.counters.items += 1;
 
 					if now - *t_cp > 60 * 1000 {
 						self.persister
@@ -539,7 +655,7 @@ pub struct RebalanceWorker {
 
 impl RebalanceWorker {
 	pub fn new(manager: Arc<BlockManager>) -> Self {
-		let block_iter = BlockStoreIterator::new(&manager);
+		let block_iter = BlockStoreIterator::new(&manager, None);
 		Self {
 			manager,
 			block_iter,
@@ -557,6 +673,10 @@ impl Worker for RebalanceWorker {
 		"Block rebalance worker".into()
 	}
 
+	fn is_maintenance(&self) -> bool {
+		true
+	}
+
 	fn status(&self) -> WorkerStatus {
 		let t_cur = self.t_finished.unwrap_or_else(|| now_msec());
 		let rate = self.moved_bytes / std::cmp::max(1, (t_cur - self.t_started) / 1000);
@@ -632,7 +752,9 @@ impl Worker for RebalanceWorker {
 const PROGRESS_FP: u64 = 1_000_000_000;
 
 impl BlockStoreIterator {
-	fn new(manager: &BlockManager) -> Self {
+	/// Iterate on the blocks stored in all data directories,
+	/// or only in `only_dir` if it is given
+	fn new(manager: &BlockManager, only_dir: Option<&Path>) -> Self {
 		let data_layout = manager.data_layout.load_full();
 
 		let mut dir_cap = vec![0; data_layout.data_dirs.len()];
@@ -644,11 +766,19 @@ impl BlockStoreIterator {
 				dir_cap[*sec as usize] += 1;
 			}
 		}
-		let sum_cap = dir_cap.iter().sum::<usize>() as u64;
+
+		let dirs = data_layout
+			.data_dirs
+			.iter()
+			.zip(dir_cap)
+			.filter(|(dir, _)| only_dir.map(|d| dir.path == d).unwrap_or(true))
+			.collect::<Vec<_>>();
+		// a single directory without partitions can be selected (e.g. a read-only one)
+		let sum_cap = std::cmp::max(dirs.iter().map(|(_, cap)| *cap as u64).sum::<u64>(), 1);
 
 		let mut cum_cap = 0;
 		let mut todo = vec![];
-		for (dir, cap) in data_layout.data_dirs.iter().zip(dir_cap.into_iter()) {
+		for (dir, cap) in dirs.into_iter() {
 			let progress_min = (cum_cap * PROGRESS_FP) / sum_cap;
 			let progress_max = ((cum_cap + cap as u64) * PROGRESS_FP) / sum_cap;
 			cum_cap += cap as u64;
@@ -670,7 +800,7 @@ impl BlockStoreIterator {
 	}
 
 	/// Returns progress done, between 0 and 1
-	fn progress(&self) -> f32 {
+	pub(crate) fn progress(&self) -> f32 {
 		self.todo
 			.last()
 			.map(|x| match x {
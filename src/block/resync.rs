@@ -1,7 +1,7 @@
 use std::collections::HashSet;
 use std::convert::TryInto;
 use std::sync::{Arc, Mutex};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
@@ -52,10 +52,19 @@ pub struct BlockResyncManager {
 	pub(crate) errors: CountedTree,
 
 	busy_set: BusySet,
+	run: Mutex<Option<ResyncRun>>,
 
 	persister: PersisterShared<ResyncPersistedConfig>,
 }
 
+/// Progress of the resync since the queue was last empty
+struct ResyncRun {
+	started: u64,
+	blocks: u64,
+	/// Number of bytes received during resync when the run started
+	bytes_base: u64,
+}
+
 #[derive(Serialize, Deserialize, Clone, Copy)]
 struct ResyncPersistedConfig {
 	n_workers: usize,
@@ -85,6 +94,18 @@ struct BusyBlock {
 	busy_set: BusySet,
 }
 
+/// Items of the resync queue are ordered by the time at which they are
+/// to be processed, which is the prefix of their key
+pub(crate) fn queue_oldest_age(queue: &CountedTree) -> Result<Duration, Error> {
+	match queue.first()? {
+		Some((k, _)) => {
+			let time = u64::from_be_bytes(k[0..8].try_into().unwrap());
+			Ok(Duration::from_millis(now_msec().saturating_sub(time)))
+		}
+		None => Ok(Duration::ZERO),
+	}
+}
+
 impl BlockResyncManager {
 	pub(crate) fn new(db: &db::Db, system: &System) -> Self {
 		let queue = db
@@ -104,6 +125,7 @@ impl BlockResyncManager {
 			notify: Arc::new(Notify::new()),
 			errors,
 			busy_set: Arc::new(Mutex::new(HashSet::new())),
+			run: Mutex::new(None),
 			persister,
 		}
 	}
@@ -116,6 +138,31 @@ impl BlockResyncManager {
 		Ok(self.queue.len())
 	}
 
+	/// Get the time for which the first item of the resync queue has been
+	/// due, or zero if the queue is empty
+	pub fn queue_oldest_age(&self) -> Result<Duration, Error> {
+		queue_oldest_age(&self.queue)
+	}
+
+	/// Get the counters of the blocks processed since the resync queue
+	/// was last empty, or None if it is empty
+	pub fn progress(&self, manager: &BlockManager) -> Option<WorkerCounters> {
+		let run = self.run.lock().unwrap();
+		let run = run.as_ref()?;
+		let queue_len = self.queue_len().unwrap_or(0) as u64;
+		Some(WorkerCounters {
+			started: run.started,
+			items: run.blocks,
+			items_total: Some(run.blocks + queue_len),
+			bytes: manager
+				.metrics
+				.stats
+				.resync_bytes()
+				.saturating_sub(run.bytes_base),
+			..Default::default()
+		})
+	}
+
 	/// Get number of blocks that have an error
 	pub fn errors_len(&self) -> Result<usize, Error> {
 		// (see queue_len comment)
@@ -140,6 +187,19 @@ impl BlockResyncManager {
 		)))
 	}
 
+	/// Put a block in the resync queue to be processed immediately,
+	/// whether or not it is in an errored state
+	pub fn resync_now(&self, hash: &Hash) -> Result<(), Error> {
+		let now = now_msec();
+		if let Some(ec) = self.errors.get(hash)? {
+			let mut ec = ErrorCounter::decode(&ec);
+			ec.last_try = now - ec.delay_msec();
+			self.errors.insert(hash, ec.encode())?;
+		}
+		self.put_to_resync_at(hash, now)?;
+		Ok(())
+	}
+
 	pub fn register_bg_vars(&self, vars: &mut vars::BgVars) {
 		let notify = self.notify.clone();
 		vars.register_rw(
@@ -296,6 +356,12 @@ impl BlockResyncManager {
 					.with_attributes(vec![KeyValue::new("block", format!("{:?}", hash))])
 					.start(&tracer);
 
+				self.run.lock().unwrap().get_or_insert_with(|| ResyncRun {
+					started: now,
+					blocks: 0,
+					bytes_base: manager.metrics.stats.resync_bytes(),
+				});
+
 				let res = self
 					.resync_block(manager, &hash)
 					.with_context(Context::current_with_span(span))
@@ -303,6 +369,9 @@ impl BlockResyncManager {
 					.await;
 
 				manager.metrics.resync_counter.add(1);
+				if let Some(run) = self.run.lock().unwrap().as_mut() {
+					run.blocks += 1;
+				}
 
 				if let Err(e) = &res {
 					manager.metrics.resync_error_counter.add(1);
@@ -327,6 +396,8 @@ impl BlockResyncManager {
 
 				Ok(ResyncIterResult::BusyDidSomething)
 			} else {
+				// Only blocks to retry later remain in the queue
+				self.run.lock().unwrap().take();
 				Ok(ResyncIterResult::IdleFor(Duration::from_millis(
 					time_msec - now,
 				)))
@@ -338,6 +409,7 @@ impl BlockResyncManager {
 			// between the time we checked the queue and the first poll
 			// to resync_notify.notified(): if that happens, we'll just loop
 			// back 10 seconds later, which is fine.
+			self.run.lock().unwrap().take();
 			Ok(ResyncIterResult::IdleFor(Duration::from_secs(10)))
 		}
 	}
@@ -374,6 +446,15 @@ impl BlockResyncManager {
 		}
 
 		if exists && rc.is_deletable() {
+			if let Some(until) = manager.holds.held_until(hash) {
+				debug!(
+					"Resync block {:?}: not deleting block held by a download",
+					hash
+				);
+				self.put_to_resync_at(hash, until + 10_000)?;
+				return Ok(());
+			}
+
 			info!("Resync block {:?}: offloading and deleting", hash);
 			let existing_path = existing_path.unwrap();
 
@@ -454,15 +535,31 @@ impl BlockResyncManager {
 			manager.rc.clear_deleted_block_rc(hash)?;
 		}
 
+		if rc.is_nonzero() && !exists && manager.is_block_lost(hash)? {
+			debug!("Resync block {:?}: not fetching block marked as lost", hash);
+			return Ok(());
+		}
+
+		if !rc.is_nonzero() {
+			// Nothing references the block anymore, forget that it was lost
+			manager.set_block_lost(hash, false)?;
+		}
+
 		if rc.is_nonzero() && !exists {
 			info!(
 				"Resync block {:?}: fetching absent but needed block (refcount > 0)",
 				hash
 			);
 
+			let start = Instant::now();
 			let block_data = manager.rpc_get_raw_block(hash, None).await?;
 
-			manager.metrics.resync_recv_counter.add(1);
+			manager
+				.metrics
+				.block_resynced(block_data.inner_buffer().len(), start.elapsed());
+			manager
+				.system
+				.set_resync_bandwidth(manager.metrics.stats.resync_bandwidth());
 
 			manager.write_block(hash, &block_data).await?;
 		}
@@ -505,6 +602,10 @@ impl Worker for ResyncWorker {
 		format!("Block resync worker #{}", self.index + 1)
 	}
 
+	fn is_maintenance(&self) -> bool {
+		true
+	}
+
 	fn status(&self) -> WorkerStatus {
 		let (n_workers, tranquility) = self.persister.get_with(|x| (x.n_workers, x.tranquility));
 
@@ -519,6 +620,7 @@ impl Worker for ResyncWorker {
 			queue_length: Some(self.manager.resync.queue_len().unwrap_or(0) as u64),
 			tranquility: Some(tranquility),
 			persistent_errors: Some(self.manager.resync.errors_len().unwrap_or(0) as u64),
+			counters: self.manager.resync.progress(&self.manager),
 			..Default::default()
 		}
 	}
@@ -0,0 +1,158 @@
+//! Protection of the disks of the node against filling up: new blocks are
+//! refused when the free space of the data directory where they would be
+//! written, or of the metadata directory, is below the configured watermarks.
+//! Reads and deletions are not affected.
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use garage_util::config::DiskWatermarkConfig;
+use garage_util::error::Error;
+use garage_util::platform::{disk_space, DiskSpace};
+
+/// The free space of a directory is measured again after this delay
+const DISK_SPACE_REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+pub(crate) struct DiskWatermark {
+	config: DiskWatermarkConfig,
+	metadata_dir: PathBuf,
+	dirs: Mutex<HashMap<PathBuf, DirState>>,
+}
+
+struct DirState {
+	measured: Option<Instant>,
+	/// Why new blocks are refused in this directory, if they are
+	full: Option<String>,
+}
+
+impl DiskWatermark {
+	pub(crate) fn new(config: DiskWatermarkConfig, metadata_dir: PathBuf) -> Self {
+		Self {
+			config,
+			metadata_dir,
+			dirs: Mutex::new(HashMap::new()),
+		}
+	}
+
+	/// Check that a new block can be written in a data directory, returning
+	/// an `InsufficientStorage` error if it cannot
+	pub(crate) fn check_block_write(&self, data_dir: &Path) -> Result<(), Error> {
+		self.check_dir(&self.metadata_dir, None, self.config.metadata_min_free)?;
+		self.check_dir(
+			data_dir,
+			self.config.data_max_usage,
+			self.config.data_min_free,
+		)
+	}
+
+	/// Directories in which new blocks were refused when last checked,
+	/// with the reason
+	pub(crate) fn full_dirs(&self) -> Vec<(PathBuf, String)> {
+		self.dirs
+			.lock()
+			.unwrap()
+			.iter()
+			.filter_map(|(dir, state)| Some((dir.clone(), state.full.clone()?)))
+			.collect()
+	}
+
+	fn check_dir(
+		&self,
+		dir: &Path,
+		max_usage: Option<f64>,
+		min_free: Option<usize>,
+	) -> Result<(), Error> {
+		if max_usage.is_none() && min_free.is_none() {
+			return Ok(());
+		}
+
+		let mut dirs = self.dirs.lock().unwrap();
+		let state = dirs.entry(dir.to_path_buf()).or_insert(DirState {
+			measured: None,
+			full: None,
+		});
+
+		let outdated = state
+			.measured
+			.map(|t| t.elapsed() >= DISK_SPACE_REFRESH_INTERVAL)
+			.unwrap_or(true);
+		if outdated {
+			let full = match disk_space(dir) {
+				Ok(space) => watermark_exceeded(&space, max_usage, min_free),
+				Err(e) => {
+					// Don't refuse writes because free space cannot be measured
+					warn!("Unable to get free space of {}: {}", dir.display(), e);
+					None
+				}
+			};
+			match (&full, &state.full) {
+				(Some(why), None) => warn!("Refusing new data blocks, {}: {}", dir.display(), why),
+				(None, Some(_)) => info!(
+					"Accepting new data blocks again, {} is below the watermark",
+					dir.display()
+				),
+				_ => (),
+			}
+			*state = DirState {
+				measured: Some(Instant::now()),
+				full,
+			};
+		}
+
+		match &state.full {
+			Some(why) => Err(Error::InsufficientStorage(format!(
+				"{}: {}",
+				dir.display(),
+				why
+			))),
+			None => Ok(()),
+		}
+	}
+}
+
+fn watermark_exceeded(
+	space: &DiskSpace,
+	max_usage: Option<f64>,
+	min_free: Option<usize>,
+) -> Option<String> {
+	if let Some(min_free) = min_free {
+		if space.available < min_free as u64 {
+			return Some(format!(
+				"{} free, below the watermark of {}",
+				bytesize::ByteSize(space.available),
+				bytesize::ByteSize(min_free as u64)
+			));
+		}
+	}
+	if let Some(max_usage) = max_usage {
+		let usage = 1. - space.available as f64 / space.total.max(1) as f64;
+		if usage > max_usage {
+			return Some(format!(
+				"{:.1}% used, above the watermark of {:.1}%",
+				usage * 100.,
+				max_usage * 100.
+			));
+		}
+	}
+	None
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_watermark_exceeded() {
+		let space = DiskSpace {
+			filesystem_id: 0,
+			available: 50,
+			total: 1000,
+		};
+		assert!(watermark_exceeded(&space, None, None).is_none());
+		assert!(watermark_exceeded(&space, Some(0.9), None).is_some());
+		assert!(watermark_exceeded(&space, Some(0.96), None).is_none());
+		assert!(watermark_exceeded(&space, None, Some(100)).is_some());
+		assert!(watermark_exceeded(&space, None, Some(50)).is_none());
+	}
+}
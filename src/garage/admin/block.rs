@@ -21,6 +21,24 @@ impl AdminRpcHandler {
 				self.handle_block_retry_now(*all, blocks).await
 			}
 			BlockOperation::Purge { yes, blocks } => self.handle_block_purge(*yes, blocks).await,
+			BlockOperation::Refs { all_errors, blocks } => {
+				self.handle_block_refs(*all_errors, blocks).await
+			}
+			BlockOperation::Fetch { blocks } => self.handle_block_fetch(blocks).await,
+			BlockOperation::MarkLost { yes, blocks } => {
+				if !yes {
+					return Err(Error::BadRequest(
+						"Pass the --yes flag to confirm that the blocks are lost.".into(),
+					));
+				}
+				self.handle_block_set_lost(blocks, true).await
+			}
+			BlockOperation::UnmarkLost { blocks } => {
+				self.handle_block_set_lost(blocks, false).await
+			}
+			BlockOperation::ListLost => Ok(AdminRpc::BlockLostList(
+				self.garage.block_manager.list_lost_blocks()?,
+			)),
 		}
 	}
 
@@ -144,6 +162,143 @@ impl AdminRpcHandler {
 		)))
 	}
 
+	async fn handle_block_refs(
+		&self,
+		all_errors: bool,
+		blocks: &[String],
+	) -> Result<AdminRpc, Error> {
+		let mut hashes = parse_block_hashes(blocks)?;
+		if all_errors {
+			let errors = self.garage.block_manager.list_resync_errors()?;
+			hashes.extend(errors.into_iter().map(|e| e.hash));
+		} else if hashes.is_empty() {
+			return Err(Error::BadRequest(
+				"Specify hashes of blocks or --all-errors".into(),
+			));
+		}
+
+		let mut refs = vec![];
+		for hash in hashes {
+			let block_refs = self
+				.garage
+				.block_ref_table
+				.get_range(
+					&hash,
+					None,
+					Some(DeletedFilter::NotDeleted),
+					10000,
+					Default::default(),
+				)
+				.await?;
+
+			for br in block_refs {
+				let version = match self
+					.garage
+					.version_table
+					.get(&br.version, &EmptyKey)
+					.await?
+				{
+					Some(v) if !v.deleted.get() => v,
+					_ => continue,
+				};
+				let (bucket_id, key, upload_id) = match version.backlink {
+					VersionBacklink::Object { bucket_id, key } => (bucket_id, key, None),
+					VersionBacklink::MultipartUpload { upload_id } => {
+						match self.garage.mpu_table.get(&upload_id, &EmptyKey).await? {
+							Some(mpu) => (mpu.bucket_id, mpu.key, Some(upload_id)),
+							None => continue,
+						}
+					}
+				};
+
+				refs.push(BlockObjectRef {
+					block: hash,
+					version: version.uuid,
+					bucket_id,
+					key,
+					upload_id,
+				});
+			}
+		}
+
+		let mut bucket_names = HashMap::new();
+		for r in refs.iter() {
+			if bucket_names.contains_key(&r.bucket_id) {
+				continue;
+			}
+			if let Ok(bucket) = self
+				.garage
+				.bucket_helper()
+				.get_existing_bucket(r.bucket_id)
+				.await
+			{
+				if let Some((alias, _, _)) = bucket.aliases().iter().find(|(_, _, active)| *active)
+				{
+					bucket_names.insert(r.bucket_id, alias.clone());
+				}
+			}
+		}
+
+		Ok(AdminRpc::BlockRefList { refs, bucket_names })
+	}
+
+	async fn handle_block_fetch(&self, blocks: &[String]) -> Result<AdminRpc, Error> {
+		let mut failures = vec![];
+		for hash in parse_block_hashes(blocks)? {
+			let resps = self
+				.garage
+				.block_manager
+				.rpc_resync_block_now(&hash)
+				.await?;
+			for (node, res) in resps {
+				if let Err(e) = res {
+					failures.push(format!("{:?} on node {:?}: {}", hash, node, e));
+				}
+			}
+		}
+
+		block_rpc_result(
+			format!(
+				"{} blocks put in resync queue of their storage nodes (check logs to see results)",
+				blocks.len()
+			),
+			failures,
+		)
+	}
+
+	async fn handle_block_set_lost(
+		&self,
+		blocks: &[String],
+		lost: bool,
+	) -> Result<AdminRpc, Error> {
+		let mut failures = vec![];
+		for hash in parse_block_hashes(blocks)? {
+			let resps = self
+				.garage
+				.block_manager
+				.rpc_set_block_lost(&hash, lost)
+				.await?;
+			for (node, res) in resps {
+				if let Err(e) = res {
+					failures.push(format!("{:?} on node {:?}: {}", hash, node, e));
+				}
+			}
+		}
+
+		block_rpc_result(
+			format!(
+				"{} blocks {} on all nodes",
+				blocks.len(),
+				if lost {
+					"marked as lost"
+				} else {
+					"no longer marked as lost"
+				}
+			),
+			failures,
+		)
+	}
+
 	async fn handle_block_purge_version_backlink(
 		&self,
 		version: &Version,
@@ -190,3 +345,24 @@ impl AdminRpcHandler {
 		Ok(())
 	}
 }
+
+fn parse_block_hashes(blocks: &[String]) -> Result<Vec<Hash>, Error> {
+	blocks
+		.iter()
+		.map(|hash| {
+			let hash = hex::decode(hash).ok_or_bad_request("invalid hash")?;
+			Hash::try_from(&hash).ok_or_bad_request("invalid hash")
+		})
+		.collect()
+}
+
+fn block_rpc_result(msg: String, failures: Vec<String>) -> Result<AdminRpc, Error> {
+	if failures.is_empty() {
+		Ok(AdminRpc::Ok(msg))
+	} else {
+		Err(Error::BadRequest(format!(
+			"Operation failed for some nodes, it can safely be run again:\n{}",
+			failures.join("\n")
+		)))
+	}
+}
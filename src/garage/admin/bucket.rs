@@ -1,15 +1,21 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::Write;
 
 use garage_util::crdt::*;
 use garage_util::time::*;
 
+use garage_table::replication::QuorumPolicy;
 use garage_table::*;
 
 use garage_model::bucket_alias_table::*;
 use garage_model::bucket_table::*;
 use garage_model::helper::error::{Error, OkOrBadRequest};
 use garage_model::permission::*;
+use garage_model::s3::clone_bucket_worker::CloneBucketWorker;
+use garage_model::s3::empty_bucket_worker::EmptyBucketWorker;
+use garage_model::s3::trash::*;
+
+use garage_api::s3::website::sign_website_url;
 
 use crate::cli::*;
 
@@ -27,10 +33,29 @@ impl AdminRpcHandler {
 			BucketOperation::Allow(query) => self.handle_bucket_allow(query).await,
 			BucketOperation::Deny(query) => self.handle_bucket_deny(query).await,
 			BucketOperation::Website(query) => self.handle_bucket_website(query).await,
+			BucketOperation::PublicRead(query) => self.handle_bucket_public_read(query).await,
+			BucketOperation::WebsiteSigning(query) => {
+				self.handle_bucket_website_signing(query).await
+			}
+			BucketOperation::SignUrl(query) => self.handle_bucket_sign_url(query).await,
 			BucketOperation::SetQuotas(query) => self.handle_bucket_set_quotas(query).await,
+			BucketOperation::SetAlarms(query) => self.handle_bucket_set_alarms(query).await,
+			BucketOperation::SetTrash(query) => self.handle_bucket_set_trash(query).await,
+			BucketOperation::SetQuorum(query) => self.handle_bucket_set_quorum(query).await,
+			BucketOperation::SetFirstBlockSizes(query) => {
+				self.handle_bucket_set_first_block_sizes(query).await
+			}
 			BucketOperation::CleanupIncompleteUploads(query) => {
 				self.handle_bucket_cleanup_incomplete_uploads(query).await
 			}
+			BucketOperation::Du(query) => self.handle_bucket_du(query).await,
+			BucketOperation::Empty(query) => self.handle_empty_bucket(query).await,
+			BucketOperation::Clone(query) => self.handle_clone_bucket(query).await,
+			BucketOperation::Trash(query) => self.handle_bucket_trash(query).await,
+			BucketOperation::Restore(query) => self.handle_bucket_restore(query).await,
+			BucketOperation::DeletionProtection(query) => {
+				self.handle_bucket_deletion_protection(query).await
+			}
 		}
 	}
 
@@ -50,7 +75,7 @@ impl AdminRpcHandler {
 		Ok(AdminRpc::BucketList(buckets))
 	}
 
-	async fn handle_bucket_info(&self, query: &BucketOpt) -> Result<AdminRpc, Error> {
+	async fn handle_bucket_info(&self, query: &BucketInfoOpt) -> Result<AdminRpc, Error> {
 		let bucket_id = self
 			.garage
 			.bucket_helper()
@@ -82,6 +107,15 @@ impl AdminRpcHandler {
 			.map(|x| x.filtered_values(&self.garage.system.ring.borrow()))
 			.unwrap_or_default();
 
+		let trash_counters = self
+			.garage
+			.object_counter_table
+			.table
+			.get(&trash_partition(&bucket_id), &EmptyKey)
+			.await?
+			.map(|x| x.filtered_values(&self.garage.system.ring.borrow()))
+			.unwrap_or_default();
+
 		let mut relevant_keys = HashMap::new();
 		for (k, _) in bucket
 			.state
@@ -117,11 +151,19 @@ impl AdminRpcHandler {
 			}
 		}
 
+		let dedup = if query.dedup {
+			Some(self.garage.bucket_helper().dedup_stats(&bucket_id).await?)
+		} else {
+			None
+		};
+
 		Ok(AdminRpc::BucketInfo {
 			bucket,
 			relevant_keys,
 			counters,
 			mpu_counters,
+			trash_counters,
+			dedup,
 		})
 	}
 
@@ -176,6 +218,9 @@ impl AdminRpcHandler {
 		// Check bucket doesn't have other aliases
 		let mut bucket = helper.get_existing_bucket(bucket_id).await?;
 		let bucket_state = bucket.state.as_option().unwrap();
+		if *bucket_state.deletion_protection.get() {
+			return Err(Error::BadRequest(format!("Bucket {} is protected against deletion. Use `bucket deletion-protection --disable` first.", query.name)));
+		}
 		if bucket_state
 			.aliases
 			.items()
@@ -314,9 +359,11 @@ impl AdminRpcHandler {
 			.get_existing_matching_key(&query.key_pattern)
 			.await?;
 
-		let allow_read = query.read || key.allow_read(&bucket_id);
-		let allow_write = query.write || key.allow_write(&bucket_id);
-		let allow_owner = query.owner || key.allow_owner(&bucket_id);
+		// Permissions given by the groups of the key are managed with the groups
+		let perm = key.own_bucket_permissions(&bucket_id);
+		let allow_read = query.read || perm.allow_read;
+		let allow_write = query.write || perm.allow_write;
+		let allow_owner = query.owner || perm.allow_owner;
 
 		helper
 			.set_bucket_key_permissions(
@@ -349,9 +396,10 @@ impl AdminRpcHandler {
 			.get_existing_matching_key(&query.key_pattern)
 			.await?;
 
-		let allow_read = !query.read && key.allow_read(&bucket_id);
-		let allow_write = !query.write && key.allow_write(&bucket_id);
-		let allow_owner = !query.owner && key.allow_owner(&bucket_id);
+		let perm = key.own_bucket_permissions(&bucket_id);
+		let allow_read = !query.read && perm.allow_read;
+		let allow_write = !query.write && perm.allow_write;
+		let allow_owner = !query.owner && perm.allow_owner;
 
 		helper
 			.set_bucket_key_permissions(
@@ -393,10 +441,41 @@ impl AdminRpcHandler {
 			));
 		}
 
+		if query.expires_after.unwrap_or(0) > MAX_WEBSITE_EXPIRES_AFTER {
+			return Err(Error::BadRequest(format!(
+				"--expires-after cannot be more than {} seconds",
+				MAX_WEBSITE_EXPIRES_AFTER
+			)));
+		}
+
 		let website = if query.allow {
+			// Routing rules can only be set through the S3 API, keep them
+			let routing_rules = bucket_state
+				.website_config
+				.get()
+				.as_ref()
+				.map(|c| c.routing_rules.clone())
+				.unwrap_or_default();
+			let mut error_documents = BTreeMap::new();
+			for ed in query.error_documents.iter() {
+				let (code, key) = ed
+					.split_once('=')
+					.and_then(|(c, k)| Some((c.parse::<u16>().ok()?, k)))
+					.filter(|(c, k)| (400..600).contains(c) && !k.is_empty())
+					.ok_or_bad_request(format!(
+						"Invalid error document: {} (expected <code>=<key>, with a 4xx or 5xx code)",
+						ed
+					))?;
+				error_documents.insert(code, key.to_string());
+			}
 			Some(WebsiteConfig {
 				index_document: query.index_document.clone(),
 				error_document: query.error_document.clone(),
+				directory_listing: query.directory_listing,
+				routing_rules,
+				error_documents,
+				cache_control: query.cache_control.clone(),
+				expires_after: query.expires_after,
 			})
 		} else {
 			None
@@ -414,6 +493,164 @@ impl AdminRpcHandler {
 		Ok(AdminRpc::Ok(msg))
 	}
 
+	async fn handle_bucket_public_read(&self, query: &PublicReadOpt) -> Result<AdminRpc, Error> {
+		let bucket_id = self
+			.garage
+			.bucket_helper()
+			.resolve_global_bucket_name(&query.bucket)
+			.await?
+			.ok_or_bad_request("Bucket not found")?;
+
+		let mut bucket = self
+			.garage
+			.bucket_helper()
+			.get_existing_bucket(bucket_id)
+			.await?;
+		let bucket_state = bucket.state.as_option_mut().unwrap();
+
+		if !(query.allow ^ query.deny) {
+			return Err(Error::BadRequest(
+				"You must specify exactly one flag, either --allow or --deny".to_string(),
+			));
+		}
+
+		bucket_state.public_read.update(query.allow);
+		self.garage.bucket_table.insert(&bucket).await?;
+
+		let msg = if query.allow {
+			format!("Anonymous read access allowed for {}", &query.bucket)
+		} else {
+			format!("Anonymous read access denied for {}", &query.bucket)
+		};
+
+		Ok(AdminRpc::Ok(msg))
+	}
+
+	async fn handle_bucket_deletion_protection(
+		&self,
+		query: &BucketProtectionOpt,
+	) -> Result<AdminRpc, Error> {
+		let bucket_id = self
+			.garage
+			.bucket_helper()
+			.resolve_global_bucket_name(&query.bucket)
+			.await?
+			.ok_or_bad_request("Bucket not found")?;
+
+		let mut bucket = self
+			.garage
+			.bucket_helper()
+			.get_existing_bucket(bucket_id)
+			.await?;
+		let bucket_state = bucket.state.as_option_mut().unwrap();
+
+		if !(query.enable ^ query.disable) {
+			return Err(Error::BadRequest(
+				"You must specify exactly one flag, either --enable or --disable".to_string(),
+			));
+		}
+
+		bucket_state.deletion_protection.update(query.enable);
+		if query.disable {
+			bucket_state.pending_deletion.update(None);
+		}
+		self.garage.bucket_table.insert(&bucket).await?;
+
+		let msg = if query.enable {
+			format!("Deletion protection enabled for {}", &query.bucket)
+		} else {
+			format!("Deletion protection disabled for {}", &query.bucket)
+		};
+
+		Ok(AdminRpc::Ok(msg))
+	}
+
+	async fn handle_bucket_website_signing(
+		&self,
+		query: &WebsiteSigningOpt,
+	) -> Result<AdminRpc, Error> {
+		let bucket_id = self
+			.garage
+			.bucket_helper()
+			.resolve_global_bucket_name(&query.bucket)
+			.await?
+			.ok_or_bad_request("Bucket not found")?;
+
+		let mut bucket = self
+			.garage
+			.bucket_helper()
+			.get_existing_bucket(bucket_id)
+			.await?;
+		let bucket_state = bucket.state.as_option_mut().unwrap();
+
+		if !(query.allow ^ query.deny) {
+			return Err(Error::BadRequest(
+				"You must specify exactly one flag, either --allow or --deny".to_string(),
+			));
+		}
+
+		if query.deny {
+			bucket_state.website_signing.update(None);
+			self.garage.bucket_table.insert(&bucket).await?;
+			return Ok(AdminRpc::Ok(format!(
+				"Signed URLs are not required anymore for the website of {}",
+				&query.bucket
+			)));
+		}
+
+		// The secret is kept when only the prefixes are changed,
+		// so that URLs that were already signed remain valid
+		let signing = match bucket_state.website_signing.get() {
+			Some(s) if !query.new_secret => WebsiteSigning {
+				secret: s.secret.clone(),
+				prefixes: query.prefixes.clone(),
+			},
+			_ => WebsiteSigning::new(query.prefixes.clone()),
+		};
+		bucket_state.website_signing.update(Some(signing.clone()));
+		self.garage.bucket_table.insert(&bucket).await?;
+
+		let mut msg = if signing.prefixes.is_empty() {
+			format!(
+				"Signed URLs are now required for all objects of the website of {}",
+				&query.bucket
+			)
+		} else {
+			format!(
+				"Signed URLs are now required for objects of the website of {} whose key starts with: {}",
+				&query.bucket,
+				signing.prefixes.join(", ")
+			)
+		};
+		write!(&mut msg, "\nSecret: {}", signing.secret).unwrap();
+		Ok(AdminRpc::Ok(msg))
+	}
+
+	async fn handle_bucket_sign_url(&self, query: &SignUrlOpt) -> Result<AdminRpc, Error> {
+		let bucket_id = self
+			.garage
+			.bucket_helper()
+			.resolve_global_bucket_name(&query.bucket)
+			.await?
+			.ok_or_bad_request("Bucket not found")?;
+
+		let bucket = self
+			.garage
+			.bucket_helper()
+			.get_existing_bucket(bucket_id)
+			.await?;
+		let signing = bucket
+			.website_signing()
+			.ok_or_bad_request("Signed URLs are not enabled for the website of this bucket")?;
+
+		let expires = now_msec() / 1000 + query.expires_in;
+		Ok(AdminRpc::Ok(sign_website_url(
+			&signing.secret,
+			&query.key,
+			expires,
+		)))
+	}
+
 	async fn handle_bucket_set_quotas(&self, query: &SetQuotasOpt) -> Result<AdminRpc, Error> {
 		let bucket_id = self
 			.garage
@@ -429,9 +666,9 @@ impl AdminRpcHandler {
 			.await?;
 		let bucket_state = bucket.state.as_option_mut().unwrap();
 
-		if query.max_size.is_none() && query.max_objects.is_none() {
+		if query.max_size.is_none() && query.max_objects.is_none() && query.soft.is_none() {
 			return Err(Error::BadRequest(
-				"You must specify either --max-size or --max-objects (or both) for this command to do something.".to_string(),
+				"You must specify at least one of --max-size, --max-objects or --soft for this command to do something.".to_string(),
 			));
 		}
 
@@ -459,6 +696,10 @@ impl AdminRpcHandler {
 			_ => (),
 		}
 
+		if let Some(soft) = query.soft {
+			quotas.soft = soft;
+		}
+
 		bucket_state.quotas.update(quotas);
 		self.garage.bucket_table.insert(&bucket).await?;
 
@@ -468,6 +709,186 @@ impl AdminRpcHandler {
 		)))
 	}
 
+	async fn handle_bucket_set_alarms(&self, query: &SetAlarmsOpt) -> Result<AdminRpc, Error> {
+		let bucket_id = self
+			.garage
+			.bucket_helper()
+			.resolve_global_bucket_name(&query.bucket)
+			.await?
+			.ok_or_bad_request("Bucket not found")?;
+
+		let mut bucket = self
+			.garage
+			.bucket_helper()
+			.get_existing_bucket(bucket_id)
+			.await?;
+		let bucket_state = bucket.state.as_option_mut().unwrap();
+
+		if query.max_size.is_none() && query.max_objects.is_none() && query.quota_percent.is_none()
+		{
+			return Err(Error::BadRequest(
+				"You must specify at least one of --max-size, --max-objects or --quota-percent for this command to do something.".to_string(),
+			));
+		}
+
+		let mut alarms = bucket_state.alarms.get().clone();
+
+		match query.max_size.as_ref().map(String::as_ref) {
+			Some("none") => alarms.max_size = None,
+			Some(v) => {
+				let bs = v
+					.parse::<bytesize::ByteSize>()
+					.ok_or_bad_request(format!("Invalid size specified: {}", v))?;
+				alarms.max_size = Some(bs.as_u64());
+			}
+			_ => (),
+		}
+
+		match query.max_objects.as_ref().map(String::as_ref) {
+			Some("none") => alarms.max_objects = None,
+			Some(v) => {
+				let mo = v
+					.parse::<u64>()
+					.ok_or_bad_request(format!("Invalid number specified: {}", v))?;
+				alarms.max_objects = Some(mo);
+			}
+			_ => (),
+		}
+
+		match query.quota_percent.as_ref().map(String::as_ref) {
+			Some("none") => alarms.quota_percent = None,
+			Some(v) => {
+				let qp = v
+					.trim_end_matches('%')
+					.parse::<u64>()
+					.ok()
+					.filter(|p| (1..=100).contains(p))
+					.ok_or_bad_request(format!("Invalid percentage specified: {}", v))?;
+				alarms.quota_percent = Some(qp);
+			}
+			_ => (),
+		}
+
+		bucket_state.alarms.update(alarms);
+		self.garage.bucket_table.insert(&bucket).await?;
+
+		Ok(AdminRpc::Ok(format!(
+			"Alarms updated for {}",
+			&query.bucket
+		)))
+	}
+
+	async fn handle_bucket_set_trash(&self, query: &SetTrashOpt) -> Result<AdminRpc, Error> {
+		let bucket_id = self
+			.garage
+			.bucket_helper()
+			.resolve_global_bucket_name(&query.bucket)
+			.await?
+			.ok_or_bad_request("Bucket not found")?;
+
+		let mut bucket = self
+			.garage
+			.bucket_helper()
+			.get_existing_bucket(bucket_id)
+			.await?;
+		let bucket_state = bucket.state.as_option_mut().unwrap();
+
+		let retention_days = match query.retention_days.as_str() {
+			"none" => None,
+			v => Some(
+				v.parse::<u64>()
+					.ok()
+					.filter(|d| *d > 0)
+					.ok_or_bad_request(format!("Invalid number of days specified: {}", v))?,
+			),
+		};
+		bucket_state.trash_retention_days.update(retention_days);
+		self.garage.bucket_table.insert(&bucket).await?;
+
+		Ok(AdminRpc::Ok(match retention_days {
+			Some(d) => format!(
+				"Objects deleted from {} are now kept in its trash for {} days",
+				&query.bucket, d
+			),
+			None => format!(
+				"Trash disabled for {}, objects in its trash will be deleted",
+				&query.bucket
+			),
+		}))
+	}
+
+	async fn handle_bucket_set_quorum(&self, query: &SetQuorumOpt) -> Result<AdminRpc, Error> {
+		let bucket_id = self
+			.garage
+			.bucket_helper()
+			.resolve_global_bucket_name(&query.bucket)
+			.await?
+			.ok_or_bad_request("Bucket not found")?;
+
+		let mut bucket = self
+			.garage
+			.bucket_helper()
+			.get_existing_bucket(bucket_id)
+			.await?;
+		let bucket_state = bucket.state.as_option_mut().unwrap();
+
+		let policy = QuorumPolicy::parse(&query.policy).ok_or_bad_request(format!(
+			"Invalid quorum policy: {} (expected quorum, all or one)",
+			query.policy
+		))?;
+
+		bucket_state.quorum_policy.update(policy);
+		self.garage.bucket_table.insert(&bucket).await?;
+
+		Ok(AdminRpc::Ok(format!(
+			"Quorum policy for {} set to {}",
+			&query.bucket,
+			policy.as_str()
+		)))
+	}
+
+	async fn handle_bucket_set_first_block_sizes(
+		&self,
+		query: &SetFirstBlockSizesOpt,
+	) -> Result<AdminRpc, Error> {
+		let bucket_id = self
+			.garage
+			.bucket_helper()
+			.resolve_global_bucket_name(&query.bucket)
+			.await?
+			.ok_or_bad_request("Bucket not found")?;
+
+		let mut bucket = self
+			.garage
+			.bucket_helper()
+			.get_existing_bucket(bucket_id)
+			.await?;
+		let bucket_state = bucket.state.as_option_mut().unwrap();
+
+		let sizes = query
+			.sizes
+			.iter()
+			.map(|v| {
+				v.parse::<bytesize::ByteSize>()
+					.ok()
+					.filter(|bs| bs.as_u64() > 0)
+					.map(|bs| bs.as_u64())
+					.ok_or_bad_request(format!("Invalid size specified: {}", v))
+			})
+			.collect::<Result<Vec<_>, _>>()?;
+
+		let msg = if sizes.is_empty() {
+			bucket_state.first_block_sizes.update(None);
+			format!("First block sizes removed for {}", &query.bucket)
+		} else {
+			bucket_state.first_block_sizes.update(Some(sizes));
+			format!("First block sizes set for {}", &query.bucket)
+		};
+		self.garage.bucket_table.insert(&bucket).await?;
+
+		Ok(AdminRpc::Ok(msg))
+	}
+
 	async fn handle_bucket_cleanup_incomplete_uploads(
 		&self,
 		query: &CleanupIncompleteUploadsOpt,
@@ -503,4 +924,153 @@ impl AdminRpcHandler {
 
 		Ok(AdminRpc::Ok(ret))
 	}
+
+	async fn handle_bucket_du(&self, query: &BucketDuOpt) -> Result<AdminRpc, Error> {
+		let bucket_id = self
+			.garage
+			.bucket_helper()
+			.resolve_global_bucket_name(&query.name)
+			.await?
+			.ok_or_bad_request("Bucket not found")?;
+
+		let usage = self
+			.garage
+			.bucket_helper()
+			.usage_by_prefix(&bucket_id, &query.prefix, &query.delimiter)
+			.await?;
+
+		Ok(AdminRpc::BucketUsage {
+			prefix: query.prefix.clone(),
+			usage,
+		})
+	}
+
+	async fn handle_empty_bucket(&self, query: &EmptyBucketOpt) -> Result<AdminRpc, Error> {
+		let bucket_id = self
+			.garage
+			.bucket_helper()
+			.resolve_global_bucket_name(&query.name)
+			.await?
+			.ok_or_bad_request("Bucket not found")?;
+
+		if !query.yes {
+			return Err(Error::BadRequest(
+				"Add --yes flag to really perform this operation".to_string(),
+			));
+		}
+		if query.rate == 0 {
+			return Err(Error::BadRequest("Rate must be at least 1".to_string()));
+		}
+
+		let job = match EmptyBucketWorker::start(
+			&self.garage,
+			&self.background,
+			bucket_id,
+			query.rate,
+		)? {
+			Some(job) => job,
+			None => {
+				return Err(Error::BadRequest(format!(
+					"Bucket {} is already being emptied by node {:?}",
+					query.name, self.garage.system.id
+				)))
+			}
+		};
+
+		Ok(AdminRpc::Ok(format!(
+			"Deletion of the objects of bucket {} started on node {:?} as job {:?}, its progress can be followed with `garage job list`.",
+			query.name, self.garage.system.id, job.id
+		)))
+	}
+
+	async fn handle_clone_bucket(&self, query: &CloneBucketOpt) -> Result<AdminRpc, Error> {
+		let helper = self.garage.bucket_helper();
+		let source_id = helper
+			.resolve_global_bucket_name(&query.source)
+			.await?
+			.ok_or_bad_request(format!("Bucket not found: {}", query.source))?;
+		let dest_id = helper
+			.resolve_global_bucket_name(&query.destination)
+			.await?
+			.ok_or_bad_request(format!("Bucket not found: {}", query.destination))?;
+		if source_id == dest_id {
+			return Err(Error::BadRequest(
+				"Source and destination buckets must be different".to_string(),
+			));
+		}
+
+		match CloneBucketWorker::start(
+			self.garage.clone(),
+			source_id,
+			dest_id,
+			query.prefix.clone(),
+		)? {
+			Some(worker) => self.background.spawn_worker(worker),
+			None => {
+				return Err(Error::BadRequest(format!(
+					"Bucket {} is already being copied into {} by node {:?}",
+					query.source, query.destination, self.garage.system.id
+				)))
+			}
+		}
+
+		Ok(AdminRpc::Ok(format!(
+			"Copy of bucket {} into {} started on node {:?}, its progress can be followed with `garage worker list`.",
+			query.source, query.destination, self.garage.system.id
+		)))
+	}
+
+	async fn handle_bucket_trash(&self, query: &BucketTrashOpt) -> Result<AdminRpc, Error> {
+		let bucket_id = self
+			.garage
+			.bucket_helper()
+			.resolve_global_bucket_name(&query.name)
+			.await?
+			.ok_or_bad_request("Bucket not found")?;
+
+		let trashed = list_trash(&self.garage, bucket_id, &query.prefix, None, query.limit).await?;
+		Ok(AdminRpc::TrashList(trashed))
+	}
+
+	async fn handle_bucket_restore(&self, query: &RestoreTrashOpt) -> Result<AdminRpc, Error> {
+		let bucket_id = self
+			.garage
+			.bucket_helper()
+			.resolve_global_bucket_name(&query.name)
+			.await?
+			.ok_or_bad_request("Bucket not found")?;
+
+		let (restored, skipped) = match (&query.prefix, query.keys.is_empty()) {
+			(Some(prefix), true) => {
+				restore_prefix_from_trash(&self.garage, bucket_id, prefix).await?
+			}
+			(None, false) => {
+				let mut restored = 0;
+				for key in query.keys.iter() {
+					if restore_from_trash(&self.garage, bucket_id, key).await? {
+						restored += 1;
+					}
+				}
+				(restored, query.keys.len() as u64 - restored)
+			}
+			_ => {
+				return Err(Error::BadRequest(
+					"You must specify either keys of objects or --prefix, but not both."
+						.to_string(),
+				))
+			}
+		};
+
+		let mut msg = format!("{} objects restored in {}", restored, query.name);
+		if skipped > 0 {
+			writeln!(&mut msg).unwrap();
+			write!(
+				&mut msg,
+				"{} objects were not restored, because they are not in the trash or their key was written again",
+				skipped
+			)
+			.unwrap();
+		}
+		Ok(AdminRpc::Ok(msg))
+	}
 }
@@ -0,0 +1,271 @@
+//! Read-only access to the objects of a bucket as a tree of files and
+//! directories, for `garage mount`. The requests are handled by the node the
+//! command is connected to, which reads the objects from its tables and their
+//! data from its block manager.
+use garage_table::*;
+
+use garage_api::helpers::key_after_prefix;
+
+use garage_model::helper::error::{Error, OkOrBadRequest};
+use garage_model::helper::object::current_version;
+use garage_model::s3::object_table::*;
+
+use super::*;
+
+/// Number of objects read at once when listing a directory
+const LIST_BATCH: usize = 1000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum FsRequest {
+	/// Get the ID of a bucket from its global name
+	Bucket(String),
+	/// List the objects and the common prefixes directly under a prefix
+	List { bucket: Uuid, prefix: String },
+	/// Read a range of a version of an object
+	Read {
+		bucket: Uuid,
+		key: String,
+		version: Uuid,
+		offset: u64,
+		len: u64,
+	},
+}
+
+/// A file or a directory of a listing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FsEntry {
+	/// Name of the entry, relative to the listed prefix
+	pub name: String,
+	/// Current version of the object, None for a directory
+	pub file: Option<FsFile>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct FsFile {
+	pub version: Uuid,
+	pub size: u64,
+	/// Time at which the version was created, in msec
+	pub timestamp: u64,
+}
+
+impl AdminRpcHandler {
+	pub(super) async fn handle_fs(&self, req: &FsRequest) -> Result<AdminRpc, Error> {
+		match req {
+			FsRequest::Bucket(name) => {
+				let bucket_id = self
+					.garage
+					.bucket_helper()
+					.resolve_global_bucket_name(name)
+					.await?
+					.ok_or_else(|| Error::NoSuchBucket(name.clone()))?;
+				Ok(AdminRpc::FsBucket(bucket_id))
+			}
+			FsRequest::List { bucket, prefix } => self.handle_fs_list(bucket, prefix).await,
+			FsRequest::Read {
+				bucket,
+				key,
+				version,
+				offset,
+				len,
+			} => {
+				self.handle_fs_read(bucket, key, version, *offset, *len)
+					.await
+			}
+		}
+	}
+
+	async fn handle_fs_list(&self, bucket: &Uuid, prefix: &str) -> Result<AdminRpc, Error> {
+		let mut entries = vec![];
+		let mut start = Some(prefix.to_string());
+		while let Some(cursor) = start.take() {
+			let objects = self
+				.garage
+				.object_table
+				.get_range(
+					bucket,
+					Some(cursor),
+					Some(ObjectFilter::IsData),
+					LIST_BATCH,
+					EnumerationOrder::Forward,
+				)
+				.await?;
+			let mut next = objects
+				.last()
+				.filter(|_| objects.len() == LIST_BATCH)
+				.map(|o| format!("{}\0", o.key));
+
+			for object in objects.iter() {
+				let rest = match object.key.strip_prefix(prefix) {
+					Some(rest) => rest,
+					None => return Ok(AdminRpc::FsDir(entries)),
+				};
+				if let Some(i) = rest.find('/') {
+					// Skip all the objects of the sub-directory at once
+					let dir = format!("{}{}", prefix, &rest[..=i]);
+					next = key_after_prefix(&dir);
+					if i > 0 {
+						entries.push(FsEntry {
+							name: rest[..i].to_string(),
+							file: None,
+						});
+					}
+					break;
+				}
+				if rest.is_empty() {
+					// Marker object of the directory itself
+					continue;
+				}
+				if let Some(file) = current_file(object) {
+					entries.push(FsEntry {
+						name: rest.to_string(),
+						file: Some(file),
+					});
+				}
+			}
+
+			start = next;
+		}
+		Ok(AdminRpc::FsDir(entries))
+	}
+
+	async fn handle_fs_read(
+		&self,
+		bucket: &Uuid,
+		key: &String,
+		version: &Uuid,
+		offset: u64,
+		len: u64,
+	) -> Result<AdminRpc, Error> {
+		let object = self
+			.garage
+			.object_table
+			.get(bucket, key)
+			.await?
+			.ok_or_bad_request("Object not found")?;
+		let data = object
+			.versions()
+			.iter()
+			.find(|v| v.uuid == *version)
+			.and_then(|v| match &v.state {
+				ObjectVersionState::Complete(data) => Some(data),
+				_ => None,
+			})
+			.ok_or_bad_request("Object version not found, it may have been deleted")?;
+
+		let (first_block, size) = match data {
+			ObjectVersionData::Inline(meta, bytes) => {
+				let size = std::cmp::min(meta.size, bytes.len() as u64);
+				let start = std::cmp::min(offset, size) as usize;
+				let end = std::cmp::min(offset.saturating_add(len), size) as usize;
+				return Ok(AdminRpc::FsData(bytes[start..end].to_vec()));
+			}
+			ObjectVersionData::FirstBlock(meta, hash) => (*hash, meta.size),
+			ObjectVersionData::DeleteMarker => {
+				return Err(Error::BadRequest("Object is a delete marker".into()))
+			}
+		};
+
+		let end = std::cmp::min(offset.saturating_add(len), size);
+		if offset >= end {
+			return Ok(AdminRpc::FsData(vec![]));
+		}
+
+		let blocks = match self.garage.version_table.get(version, &EmptyKey).await? {
+			Some(v) => v
+				.blocks
+				.items()
+				.iter()
+				.map(|(_, vb)| (vb.hash, vb.size))
+				.collect::<Vec<_>>(),
+			None => vec![(first_block, size)],
+		};
+
+		let mut data = Vec::with_capacity((end - offset) as usize);
+		for (hash, from, to) in block_ranges(&blocks, offset, end) {
+			let block = self.garage.block_manager.rpc_get_block(&hash, None).await?;
+			let to = std::cmp::min(to, block.len());
+			let from = std::cmp::min(from, to);
+			data.extend_from_slice(&block[from..to]);
+		}
+		Ok(AdminRpc::FsData(data))
+	}
+}
+
+/// Blocks to read for the range `offset..end` of an object made of blocks
+/// given with their sizes, and the range to read in each of them
+fn block_ranges(blocks: &[(Hash, u64)], offset: u64, end: u64) -> Vec<(Hash, usize, usize)> {
+	let mut ranges = vec![];
+	let mut block_start = 0;
+	for (hash, block_size) in blocks {
+		let block_end = block_start + block_size;
+		if block_end > offset && block_start < end {
+			let from = offset.saturating_sub(block_start) as usize;
+			let to = (std::cmp::min(end, block_end) - block_start) as usize;
+			ranges.push((*hash, from, to));
+		}
+		if block_end >= end {
+			break;
+		}
+		block_start = block_end;
+	}
+	ranges
+}
+
+/// The last complete version of an object, None if it is a delete marker
+fn current_file(object: &Object) -> Option<FsFile> {
+	let (version, meta) = current_version(object)?;
+	Some(FsFile {
+		version: version.uuid,
+		size: meta.size,
+		timestamp: version.timestamp,
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_block_ranges() {
+		let (a, b, c) = (blake2sum(b"a"), blake2sum(b"b"), blake2sum(b"c"));
+		let blocks = [(a, 10), (b, 10), (c, 5)];
+
+		assert_eq!(
+			block_ranges(&blocks, 0, 25),
+			vec![(a, 0, 10), (b, 0, 10), (c, 0, 5)]
+		);
+		assert_eq!(block_ranges(&blocks, 3, 7), vec![(a, 3, 7)]);
+		assert_eq!(block_ranges(&blocks, 8, 12), vec![(a, 8, 10), (b, 0, 2)]);
+		assert_eq!(block_ranges(&blocks, 10, 20), vec![(b, 0, 10)]);
+		assert_eq!(block_ranges(&blocks, 15, 25), vec![(b, 5, 10), (c, 0, 5)]);
+		assert!(block_ranges(&blocks, 25, 25).is_empty());
+	}
+
+	#[test]
+	fn test_current_file() {
+		let meta = ObjectVersionMeta {
+			headers: ObjectVersionHeaders {
+				content_type: "text/plain".into(),
+				other: Default::default(),
+			},
+			size: 10,
+			etag: "etag".into(),
+		};
+		let version = |timestamp, data| ObjectVersion {
+			uuid: gen_uuid(),
+			timestamp,
+			state: ObjectVersionState::Complete(data),
+		};
+		let first = version(1, ObjectVersionData::Inline(meta, vec![0; 10]));
+		let first_uuid = first.uuid;
+
+		let object = Object::new(gen_uuid(), "a".into(), vec![first.clone()]);
+		let file = current_file(&object).unwrap();
+		assert_eq!(file.version, first_uuid);
+		assert_eq!(file.size, 10);
+
+		let deleted = version(2, ObjectVersionData::DeleteMarker);
+		let object = Object::new(gen_uuid(), "a".into(), vec![first, deleted]);
+		assert!(current_file(&object).is_none());
+	}
+}
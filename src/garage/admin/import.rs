@@ -0,0 +1,58 @@
+use garage_api::s3::import::{start_import, ImportParams};
+
+use garage_model::helper::error::{Error, OkOrBadRequest};
+
+use crate::cli::*;
+
+use super::*;
+
+impl AdminRpcHandler {
+	pub(super) async fn handle_import_cmd(&self, cmd: &ImportOperation) -> Result<AdminRpc, Error> {
+		match cmd {
+			ImportOperation::S3(opt) => self.handle_import_s3(opt).await,
+		}
+	}
+
+	async fn handle_import_s3(&self, opt: &ImportS3Opt) -> Result<AdminRpc, Error> {
+		let bucket_id = self
+			.garage
+			.bucket_helper()
+			.resolve_global_bucket_name(&opt.bucket)
+			.await?
+			.ok_or_bad_request("Bucket not found")?;
+
+		let params = ImportParams {
+			endpoint: opt.from.clone(),
+			region: opt.region.clone(),
+			access_key_id: opt
+				.access_key_id
+				.clone()
+				.ok_or_bad_request("No access key ID given for the remote service")?,
+			secret_access_key: opt
+				.secret_access_key
+				.clone()
+				.ok_or_bad_request("No secret access key given for the remote service")?,
+			remote_bucket: opt
+				.remote_bucket
+				.clone()
+				.unwrap_or_else(|| opt.bucket.clone()),
+			prefix: opt.prefix.clone(),
+			concurrency: opt.concurrency,
+		};
+
+		let job = start_import(
+			&self.garage,
+			&self.background,
+			bucket_id,
+			opt.bucket.clone(),
+			params,
+		)?;
+
+		Ok(AdminRpc::Ok(format!(
+			"Import into bucket {} started on node {:?} as job {}, its progress can be followed with `garage job list`.",
+			opt.bucket,
+			self.garage.system.id,
+			hex::encode(job.id)
+		)))
+	}
+}
@@ -0,0 +1,50 @@
+use garage_model::helper::error::{Error, OkOrBadRequest};
+use garage_model::job::JobState;
+
+use crate::cli::*;
+
+use super::*;
+
+impl AdminRpcHandler {
+	pub(super) async fn handle_job_cmd(&self, cmd: &JobOperation) -> Result<AdminRpc, Error> {
+		match cmd {
+			JobOperation::List { running } => {
+				let mut jobs = self.garage.jobs.list()?;
+				if *running {
+					jobs.retain(|j| j.state == JobState::Running);
+				}
+				Ok(AdminRpc::JobList(jobs))
+			}
+			JobOperation::Cancel { id } => {
+				let mut candidates = self
+					.garage
+					.jobs
+					.list()?
+					.into_iter()
+					.filter(|j| hex::encode(j.id).starts_with(id.as_str()))
+					.collect::<Vec<_>>();
+				if candidates.len() > 1 {
+					return Err(Error::BadRequest(format!(
+						"Several jobs match ID {}, give more characters",
+						id
+					)));
+				}
+				let job = candidates
+					.pop()
+					.ok_or_bad_request(format!("No job with ID {}", id))?;
+				if job.state != JobState::Running {
+					return Err(Error::BadRequest(format!(
+						"Job {} is not running",
+						hex::encode(job.id)
+					)));
+				}
+				self.garage.jobs.cancel(job.id)?;
+				Ok(AdminRpc::Ok(format!(
+					"Job {} ({}) is being cancelled.",
+					hex::encode(job.id),
+					job.description
+				)))
+			}
+		}
+	}
+}
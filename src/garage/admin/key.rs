@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 
 use garage_table::*;
+use garage_util::time::now_msec;
 
 use garage_model::helper::error::*;
 use garage_model::key_table::*;
@@ -20,6 +21,10 @@ impl AdminRpcHandler {
 			KeyOperation::Allow(query) => self.handle_allow_key(query).await,
 			KeyOperation::Deny(query) => self.handle_deny_key(query).await,
 			KeyOperation::Import(query) => self.handle_import_key(query).await,
+			KeyOperation::SetExpiration(query) => self.handle_set_key_expiration(query).await,
+			KeyOperation::DeletionProtection(query) => {
+				self.handle_key_deletion_protection(query).await
+			}
 		}
 	}
 
@@ -56,7 +61,46 @@ impl AdminRpcHandler {
 	}
 
 	async fn handle_create_key(&self, query: &KeyNewOpt) -> Result<AdminRpc, Error> {
-		let key = Key::new(&query.name);
+		let mut key = Key::new(&query.name);
+		if let Some(expires_in) = &query.expires_in {
+			let expiration = parse_expiration(expires_in)?;
+			key.params_mut().unwrap().expiration.update(expiration);
+		}
+		self.garage.key_table.insert(&key).await?;
+		self.key_info_result(key).await
+	}
+
+	async fn handle_set_key_expiration(&self, query: &KeyExpirationOpt) -> Result<AdminRpc, Error> {
+		let mut key = self
+			.garage
+			.key_helper()
+			.get_existing_matching_key(&query.key_pattern)
+			.await?;
+		let expiration = parse_expiration(&query.expires_in)?;
+		key.params_mut().unwrap().expiration.update(expiration);
+		self.garage.key_table.insert(&key).await?;
+		self.key_info_result(key).await
+	}
+
+	async fn handle_key_deletion_protection(
+		&self,
+		query: &KeyProtectionOpt,
+	) -> Result<AdminRpc, Error> {
+		let mut key = self
+			.garage
+			.key_helper()
+			.get_existing_matching_key(&query.key_pattern)
+			.await?;
+		if !(query.enable ^ query.disable) {
+			return Err(Error::BadRequest(
+				"You must specify exactly one flag, either --enable or --disable".to_string(),
+			));
+		}
+		let key_state = key.params_mut().unwrap();
+		key_state.deletion_protection.update(query.enable);
+		if query.disable {
+			key_state.pending_deletion.update(None);
+		}
 		self.garage.key_table.insert(&key).await?;
 		self.key_info_result(key).await
 	}
@@ -82,6 +126,13 @@ impl AdminRpcHandler {
 			.get_existing_matching_key(&query.key_pattern)
 			.await?;
 
+		if key.deletion_protection() {
+			return Err(Error::BadRequest(format!(
+				"Key {} is protected against deletion. Use `key deletion-protection --disable` first.",
+				key.key_id
+			)));
+		}
+
 		if !query.yes {
 			return Err(Error::BadRequest(
 				"Add --yes flag to really perform this operation".to_string(),
@@ -158,3 +209,13 @@ impl AdminRpcHandler {
 		Ok(AdminRpc::KeyInfo(key, relevant_buckets))
 	}
 }
+
+/// Parse a delay from now after which a key expires, or `never`
+fn parse_expiration(expires_in: &str) -> Result<Option<u64>, Error> {
+	if expires_in == "never" {
+		return Ok(None);
+	}
+	let delay = parse_duration::parse::parse(expires_in)
+		.map_err(|e| Error::BadRequest(format!("Invalid delay {}: {}", expires_in, e)))?;
+	Ok(Some(now_msec() + delay.as_millis() as u64))
+}
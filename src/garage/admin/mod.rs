@@ -1,41 +1,65 @@
 mod block;
 mod bucket;
+mod fs;
+mod import;
+mod job;
 mod key;
+mod object;
+mod stats;
 
 use std::collections::HashMap;
-use std::fmt::Write;
 use std::sync::Arc;
 
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 
-use format_table::format_table_to_string;
-
 use garage_util::background::BackgroundRunner;
 use garage_util::data::*;
 use garage_util::error::Error as GarageError;
+use garage_util::log_filter;
+use garage_util::time::*;
 
-use garage_table::replication::*;
 use garage_table::*;
 
-use garage_rpc::ring::PARTITION_BITS;
 use garage_rpc::*;
 
-use garage_block::manager::BlockResyncErrorInfo;
+use garage_block::manager::{BlockLostInfo, BlockResyncErrorInfo, BlockStatus};
 
 use garage_model::bucket_table::*;
 use garage_model::garage::Garage;
+use garage_model::garbage_stats::GarbageStats;
+use garage_model::helper::bucket::{BucketUsage, DedupStats};
 use garage_model::helper::error::{Error, OkOrBadRequest};
+use garage_model::job::JobRecord;
 use garage_model::key_table::*;
 use garage_model::migrate::Migrate;
 use garage_model::s3::mpu_table::MultipartUpload;
+use garage_model::s3::object_table::*;
+use garage_model::s3::trash::TrashedObject;
 use garage_model::s3::version_table::Version;
 
 use crate::cli::*;
 use crate::repair::online::launch_online_repair;
 
+pub use fs::{FsEntry, FsRequest};
+#[cfg(feature = "fuse")]
+pub use fs::FsFile;
+
 pub const ADMIN_RPC_PATH: &str = "garage/admin_rpc.rs/Rpc";
 
+/// Status of a block on each of the nodes that should store it
+pub type BlockStatusByNode = Vec<(Uuid, Result<BlockStatus, String>)>;
+
+/// An object or multipart upload that references a data block
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BlockObjectRef {
+	pub block: Hash,
+	pub version: Uuid,
+	pub bucket_id: Uuid,
+	pub key: String,
+	pub upload_id: Option<Uuid>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[allow(clippy::large_enum_variant)]
 pub enum AdminRpc {
@@ -45,7 +69,12 @@ pub enum AdminRpc {
 	Migrate(MigrateOpt),
 	Stats(StatsOpt),
 	Worker(WorkerOperation),
+	Job(JobOperation),
 	BlockOperation(BlockOperation),
+	ObjectOperation(ObjectOperation),
+	LogFilter(LogFilterOperation),
+	Import(ImportOperation),
+	Fs(FsRequest),
 
 	// Replies
 	Ok(String),
@@ -55,7 +84,14 @@ pub enum AdminRpc {
 		relevant_keys: HashMap<String, Key>,
 		counters: HashMap<String, i64>,
 		mpu_counters: HashMap<String, i64>,
+		trash_counters: HashMap<String, i64>,
+		dedup: Option<DedupStats>,
 	},
+	BucketUsage {
+		prefix: String,
+		usage: BucketUsage,
+	},
+	TrashList(Vec<TrashedObject>),
 	KeyList(Vec<(String, String)>),
 	KeyInfo(Key, HashMap<Uuid, Bucket>),
 	WorkerList(
@@ -64,13 +100,36 @@ pub enum AdminRpc {
 	),
 	WorkerVars(Vec<(Uuid, String, String)>),
 	WorkerInfo(usize, garage_util::background::WorkerInfo),
+	JobList(Vec<JobRecord>),
+	LogFilters(Vec<(Uuid, String)>),
+	GarbageStats(Vec<(Uuid, Result<Option<GarbageStats>, String>)>),
 	BlockErrorList(Vec<BlockResyncErrorInfo>),
+	BlockLostList(Vec<BlockLostInfo>),
+	BlockRefList {
+		refs: Vec<BlockObjectRef>,
+		bucket_names: HashMap<Uuid, String>,
+	},
 	BlockInfo {
 		hash: Hash,
 		refcount: u64,
 		versions: Vec<Result<Version, Uuid>>,
 		uploads: Vec<MultipartUpload>,
 	},
+	MultipartUploadList {
+		uploads: Vec<MultipartUpload>,
+		bucket_names: HashMap<Uuid, String>,
+		/// Access keys that started the uploads, by upload id
+		initiators: HashMap<Uuid, String>,
+		aborted: bool,
+	},
+	ObjectInfo {
+		object: Object,
+		versions: HashMap<Uuid, Version>,
+		blocks: HashMap<Hash, BlockStatusByNode>,
+	},
+	FsBucket(Uuid),
+	FsDir(Vec<FsEntry>),
+	FsData(#[serde(with = "serde_bytes")] Vec<u8>),
 }
 
 impl Rpc for AdminRpc {
@@ -85,13 +144,16 @@ pub struct AdminRpcHandler {
 
 impl AdminRpcHandler {
 	pub fn new(garage: Arc<Garage>, background: Arc<BackgroundRunner>) -> Arc<Self> {
-		let endpoint = garage.system.netapp.endpoint(ADMIN_RPC_PATH.into());
+		let endpoint = garage.system.endpoint(ADMIN_RPC_PATH.into());
 		let admin = Arc::new(Self {
 			garage,
 			background,
 			endpoint,
 		});
-		admin.endpoint.set_handler(admin.clone());
+		admin
+			.garage
+			.system
+			.set_handler(&admin.endpoint, admin.clone());
 		admin
 	}
 
@@ -116,6 +178,21 @@ impl AdminRpcHandler {
 	// ================ REPAIR COMMANDS ====================
 
 	async fn handle_launch_repair(self: &Arc<Self>, opt: RepairOpt) -> Result<AdminRpc, Error> {
+		if let RepairWhat::MultipartUploads {
+			older_than: Some(older_than),
+			initiator,
+			abort,
+		} = &opt.what
+		{
+			if *abort && !opt.yes {
+				return Err(Error::BadRequest(
+					"Please provide the --yes flag to abort multipart uploads.".to_string(),
+				));
+			}
+			return self
+				.handle_old_multipart_uploads(older_than, initiator.as_deref(), *abort)
+				.await;
+		}
 		if !opt.yes {
 			return Err(Error::BadRequest(
 				"Please provide the --yes flag to initiate repair operations.".to_string(),
@@ -158,250 +235,112 @@ impl AdminRpcHandler {
 		}
 	}
 
-	// ================ STATS COMMANDS ====================
-
-	async fn handle_stats(&self, opt: StatsOpt) -> Result<AdminRpc, Error> {
-		if opt.all_nodes {
-			let mut ret = String::new();
-			let ring = self.garage.system.ring.borrow().clone();
-
-			for node in ring.layout.node_ids().iter() {
-				let mut opt = opt.clone();
-				opt.all_nodes = false;
-				opt.skip_global = true;
-
-				writeln!(&mut ret, "\n======================").unwrap();
-				writeln!(&mut ret, "Stats for node {:?}:", node).unwrap();
-
-				let node_id = (*node).into();
-				match self
-					.endpoint
-					.call(&node_id, AdminRpc::Stats(opt), PRIO_NORMAL)
-					.await
-				{
-					Ok(Ok(AdminRpc::Ok(s))) => writeln!(&mut ret, "{}", s).unwrap(),
-					Ok(Ok(x)) => writeln!(&mut ret, "Bad answer: {:?}", x).unwrap(),
-					Ok(Err(e)) => writeln!(&mut ret, "Remote error: {}", e).unwrap(),
-					Err(e) => writeln!(&mut ret, "Network error: {}", e).unwrap(),
-				}
-			}
+	async fn handle_old_multipart_uploads(
+		&self,
+		older_than: &str,
+		initiator: Option<&str>,
+		abort: bool,
+	) -> Result<AdminRpc, Error> {
+		let duration = parse_duration::parse::parse(older_than)
+			.ok_or_bad_request("Invalid duration passed for --older-than parameter")?;
+		let older_than = now_msec().saturating_sub(duration.as_millis() as u64);
 
-			writeln!(&mut ret, "\n======================").unwrap();
-			write!(
-				&mut ret,
-				"Cluster statistics:\n\n{}",
-				self.gather_cluster_stats()
+		let buckets = self
+			.garage
+			.bucket_table
+			.get_range(
+				&EmptyKey,
+				None,
+				Some(DeletedFilter::NotDeleted),
+				10000,
+				EnumerationOrder::Forward,
 			)
-			.unwrap();
-
-			Ok(AdminRpc::Ok(ret))
-		} else {
-			Ok(AdminRpc::Ok(self.gather_stats_local(opt)?))
-		}
-	}
-
-	fn gather_stats_local(&self, opt: StatsOpt) -> Result<String, Error> {
-		let mut ret = String::new();
-		writeln!(
-			&mut ret,
-			"\nGarage version: {} [features: {}]\nRust compiler version: {}",
-			garage_util::version::garage_version(),
-			garage_util::version::garage_features()
-				.map(|list| list.join(", "))
-				.unwrap_or_else(|| "(unknown)".into()),
-			garage_util::version::rust_version(),
-		)
-		.unwrap();
-
-		writeln!(&mut ret, "\nDatabase engine: {}", self.garage.db.engine()).unwrap();
-
-		// Gather table statistics
-		let mut table = vec!["  Table\tItems\tMklItems\tMklTodo\tGcTodo".into()];
-		table.push(self.gather_table_stats(&self.garage.bucket_table, opt.detailed)?);
-		table.push(self.gather_table_stats(&self.garage.key_table, opt.detailed)?);
-		table.push(self.gather_table_stats(&self.garage.object_table, opt.detailed)?);
-		table.push(self.gather_table_stats(&self.garage.version_table, opt.detailed)?);
-		table.push(self.gather_table_stats(&self.garage.block_ref_table, opt.detailed)?);
-		write!(
-			&mut ret,
-			"\nTable stats:\n{}",
-			format_table_to_string(table)
-		)
-		.unwrap();
-
-		// Gather block manager statistics
-		writeln!(&mut ret, "\nBlock manager stats:").unwrap();
-		let rc_len = if opt.detailed {
-			self.garage.block_manager.rc_len()?.to_string()
-		} else {
-			self.garage
-				.block_manager
-				.rc_fast_len()?
-				.map(|x| x.to_string())
-				.unwrap_or_else(|| "NC".into())
-		};
-
-		writeln!(
-			&mut ret,
-			"  number of RC entries (~= number of blocks): {}",
-			rc_len
-		)
-		.unwrap();
-		writeln!(
-			&mut ret,
-			"  resync queue length: {}",
-			self.garage.block_manager.resync.queue_len()?
-		)
-		.unwrap();
-		writeln!(
-			&mut ret,
-			"  blocks with resync errors: {}",
-			self.garage.block_manager.resync.errors_len()?
-		)
-		.unwrap();
-
-		if !opt.detailed {
-			writeln!(&mut ret, "\nIf values are missing above (marked as NC), consider adding the --detailed flag (this will be slow).").unwrap();
-		}
-
-		if !opt.skip_global {
-			write!(&mut ret, "\n{}", self.gather_cluster_stats()).unwrap();
-		}
-
-		Ok(ret)
-	}
+			.await?;
+
+		let mut uploads = vec![];
+		let mut bucket_names = HashMap::new();
+		let mut initiators = HashMap::new();
+		for bucket in buckets {
+			if let Some((alias, _, _)) = bucket.aliases().iter().find(|(_, _, active)| *active) {
+				bucket_names.insert(bucket.id, alias.clone());
+			}
 
-	fn gather_cluster_stats(&self) -> String {
-		let mut ret = String::new();
+			let mut start = None;
+			loop {
+				let objects = self
+					.garage
+					.object_table
+					.get_range(
+						&bucket.id,
+						start,
+						Some(ObjectFilter::IsUploading {
+							check_multipart: Some(true),
+						}),
+						1000,
+						EnumerationOrder::Forward,
+					)
+					.await?;
+
+				let mut abortions = vec![];
+				for object in objects.iter() {
+					let old_versions = object
+						.versions()
+						.iter()
+						.filter(|v| v.is_uploading(Some(true)) && v.timestamp < older_than)
+						.filter(|v| initiator.is_none() || v.initiator() == initiator)
+						.collect::<Vec<_>>();
+					for v in old_versions.iter() {
+						if let Some(initiator) = v.initiator() {
+							initiators.insert(v.uuid, initiator.to_string());
+						}
+						let mpu = self
+							.garage
+							.mpu_table
+							.get(&v.uuid, &EmptyKey)
+							.await?
+							.unwrap_or_else(|| {
+								MultipartUpload::new(
+									v.uuid,
+									v.timestamp,
+									bucket.id,
+									object.key.clone(),
+									false,
+								)
+							});
+						uploads.push(mpu);
+					}
+					if abort && !old_versions.is_empty() {
+						let aborted_versions = old_versions
+							.iter()
+							.map(|v| ObjectVersion {
+								state: ObjectVersionState::Aborted,
+								uuid: v.uuid,
+								timestamp: v.timestamp,
+							})
+							.collect::<Vec<_>>();
+						abortions.push(Object::new(
+							bucket.id,
+							object.key.clone(),
+							aborted_versions,
+						));
+					}
+				}
+				self.garage.object_table.insert_many(abortions).await?;
 
-		// Gather storage node and free space statistics
-		let layout = &self.garage.system.ring.borrow().layout;
-		let mut node_partition_count = HashMap::<Uuid, u64>::new();
-		for short_id in layout.ring_assignment_data.iter() {
-			let id = layout.node_id_vec[*short_id as usize];
-			*node_partition_count.entry(id).or_default() += 1;
-		}
-		let node_info = self
-			.garage
-			.system
-			.get_known_nodes()
-			.into_iter()
-			.map(|n| (n.id, n))
-			.collect::<HashMap<_, _>>();
-
-		let mut table = vec!["  ID\tHostname\tZone\tCapacity\tPart.\tDataAvail\tMetaAvail".into()];
-		for (id, parts) in node_partition_count.iter() {
-			let info = node_info.get(id);
-			let status = info.map(|x| &x.status);
-			let role = layout.roles.get(id).and_then(|x| x.0.as_ref());
-			let hostname = status.map(|x| x.hostname.as_str()).unwrap_or("?");
-			let zone = role.map(|x| x.zone.as_str()).unwrap_or("?");
-			let capacity = role
-				.map(|x| x.capacity_string())
-				.unwrap_or_else(|| "?".into());
-			let avail_str = |x| match x {
-				Some((avail, total)) => {
-					let pct = (avail as f64) / (total as f64) * 100.;
-					let avail = bytesize::ByteSize::b(avail);
-					let total = bytesize::ByteSize::b(total);
-					format!("{}/{} ({:.1}%)", avail, total, pct)
+				if objects.len() < 1000 {
+					break;
+				} else {
+					start = Some(objects.last().unwrap().key.clone());
 				}
-				None => "?".into(),
-			};
-			let data_avail = avail_str(status.and_then(|x| x.data_disk_avail));
-			let meta_avail = avail_str(status.and_then(|x| x.meta_disk_avail));
-			table.push(format!(
-				"  {:?}\t{}\t{}\t{}\t{}\t{}\t{}",
-				id, hostname, zone, capacity, parts, data_avail, meta_avail
-			));
-		}
-		write!(
-			&mut ret,
-			"Storage nodes:\n{}",
-			format_table_to_string(table)
-		)
-		.unwrap();
-
-		let meta_part_avail = node_partition_count
-			.iter()
-			.filter_map(|(id, parts)| {
-				node_info
-					.get(id)
-					.and_then(|x| x.status.meta_disk_avail)
-					.map(|c| c.0 / *parts)
-			})
-			.collect::<Vec<_>>();
-		let data_part_avail = node_partition_count
-			.iter()
-			.filter_map(|(id, parts)| {
-				node_info
-					.get(id)
-					.and_then(|x| x.status.data_disk_avail)
-					.map(|c| c.0 / *parts)
-			})
-			.collect::<Vec<_>>();
-		if !meta_part_avail.is_empty() && !data_part_avail.is_empty() {
-			let meta_avail =
-				bytesize::ByteSize(meta_part_avail.iter().min().unwrap() * (1 << PARTITION_BITS));
-			let data_avail =
-				bytesize::ByteSize(data_part_avail.iter().min().unwrap() * (1 << PARTITION_BITS));
-			writeln!(
-				&mut ret,
-				"\nEstimated available storage space cluster-wide (might be lower in practice):"
-			)
-			.unwrap();
-			if meta_part_avail.len() < node_partition_count.len()
-				|| data_part_avail.len() < node_partition_count.len()
-			{
-				writeln!(&mut ret, "  data: < {}", data_avail).unwrap();
-				writeln!(&mut ret, "  metadata: < {}", meta_avail).unwrap();
-				writeln!(&mut ret, "A precise estimate could not be given as information is missing for some storage nodes.").unwrap();
-			} else {
-				writeln!(&mut ret, "  data: {}", data_avail).unwrap();
-				writeln!(&mut ret, "  metadata: {}", meta_avail).unwrap();
 			}
 		}
 
-		ret
-	}
-
-	fn gather_table_stats<F, R>(
-		&self,
-		t: &Arc<Table<F, R>>,
-		detailed: bool,
-	) -> Result<String, Error>
-	where
-		F: TableSchema + 'static,
-		R: TableReplication + 'static,
-	{
-		let (data_len, mkl_len) = if detailed {
-			(
-				t.data.store.len().map_err(GarageError::from)?.to_string(),
-				t.merkle_updater.merkle_tree_len()?.to_string(),
-			)
-		} else {
-			(
-				t.data
-					.store
-					.fast_len()
-					.map_err(GarageError::from)?
-					.map(|x| x.to_string())
-					.unwrap_or_else(|| "NC".into()),
-				t.merkle_updater
-					.merkle_tree_fast_len()?
-					.map(|x| x.to_string())
-					.unwrap_or_else(|| "NC".into()),
-			)
-		};
-
-		Ok(format!(
-			"  {}\t{}\t{}\t{}\t{}",
-			F::TABLE_NAME,
-			data_len,
-			mkl_len,
-			t.merkle_updater.todo_len()?,
-			t.data.gc_todo_len()?
-		))
+		Ok(AdminRpc::MultipartUploadList {
+			uploads,
+			bucket_names,
+			initiators,
+			aborted: abort,
+		})
 	}
 
 	// ================ WORKER COMMANDS ====================
@@ -518,6 +457,48 @@ impl AdminRpcHandler {
 			)]))
 		}
 	}
+
+	// ================ LOG FILTER COMMANDS ====================
+
+	async fn handle_log_filter_cmd(&self, cmd: &LogFilterOperation) -> Result<AdminRpc, Error> {
+		let all_nodes = match cmd {
+			LogFilterOperation::Get { all_nodes } => *all_nodes,
+			LogFilterOperation::Set { all_nodes, .. } => *all_nodes,
+		};
+
+		if all_nodes {
+			let mut ret = vec![];
+			let ring = self.garage.system.ring.borrow().clone();
+			for node in ring.layout.node_ids().iter() {
+				let node = (*node).into();
+				let local_cmd = match cmd {
+					LogFilterOperation::Get { .. } => LogFilterOperation::Get { all_nodes: false },
+					LogFilterOperation::Set { filter, .. } => LogFilterOperation::Set {
+						all_nodes: false,
+						filter: filter.clone(),
+					},
+				};
+				match self
+					.endpoint
+					.call(&node, AdminRpc::LogFilter(local_cmd), PRIO_NORMAL)
+					.await??
+				{
+					AdminRpc::LogFilters(v) => ret.extend(v),
+					m => return Err(GarageError::unexpected_rpc_message(m).into()),
+				}
+			}
+			Ok(AdminRpc::LogFilters(ret))
+		} else {
+			if let LogFilterOperation::Set { filter, .. } = cmd {
+				log_filter::set_log_filter(filter).map_err(|e| Error::BadRequest(e.to_string()))?;
+				info!("Log filter changed to `{}`", filter);
+			}
+			Ok(AdminRpc::LogFilters(vec![(
+				self.garage.system.id,
+				log_filter::get_log_filter()?,
+			)]))
+		}
+	}
 }
 
 #[async_trait]
@@ -534,7 +515,12 @@ impl EndpointHandler<AdminRpc> for AdminRpcHandler {
 			AdminRpc::LaunchRepair(opt) => self.handle_launch_repair(opt.clone()).await,
 			AdminRpc::Stats(opt) => self.handle_stats(opt.clone()).await,
 			AdminRpc::Worker(wo) => self.handle_worker_cmd(wo).await,
+			AdminRpc::Job(jo) => self.handle_job_cmd(jo).await,
 			AdminRpc::BlockOperation(bo) => self.handle_block_cmd(bo).await,
+			AdminRpc::ObjectOperation(oo) => self.handle_object_cmd(oo).await,
+			AdminRpc::LogFilter(lfo) => self.handle_log_filter_cmd(lfo).await,
+			AdminRpc::Import(io) => self.handle_import_cmd(io).await,
+			AdminRpc::Fs(req) => self.handle_fs(req).await,
 			m => Err(GarageError::unexpected_rpc_message(m).into()),
 		}
 	}
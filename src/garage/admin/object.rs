@@ -0,0 +1,68 @@
+use garage_table::*;
+
+use garage_model::helper::error::{Error, OkOrBadRequest};
+use garage_model::s3::object_table::*;
+
+use crate::cli::*;
+
+use super::*;
+
+impl AdminRpcHandler {
+	pub(super) async fn handle_object_cmd(&self, cmd: &ObjectOperation) -> Result<AdminRpc, Error> {
+		match cmd {
+			ObjectOperation::Info { bucket, key } => self.handle_object_info(bucket, key).await,
+		}
+	}
+
+	async fn handle_object_info(&self, bucket: &String, key: &String) -> Result<AdminRpc, Error> {
+		let bucket_id = self
+			.garage
+			.bucket_helper()
+			.resolve_global_bucket_name(bucket)
+			.await?
+			.ok_or_bad_request("Bucket not found")?;
+
+		let object = self
+			.garage
+			.object_table
+			.get(&bucket_id, key)
+			.await?
+			.ok_or_bad_request("Object not found")?;
+
+		let mut versions = HashMap::new();
+		let mut blocks = HashMap::new();
+		for ov in object.versions() {
+			let mut hashes = vec![];
+			if let ObjectVersionState::Complete(ObjectVersionData::FirstBlock(_, hash)) = &ov.state
+			{
+				hashes.push(*hash);
+			}
+
+			if let Some(v) = self.garage.version_table.get(&ov.uuid, &EmptyKey).await? {
+				hashes.extend(v.blocks.items().iter().map(|(_, vb)| vb.hash));
+				versions.insert(ov.uuid, v);
+			}
+
+			for hash in hashes {
+				if blocks.contains_key(&hash) {
+					continue;
+				}
+				let status = self
+					.garage
+					.block_manager
+					.rpc_get_block_status(&hash)
+					.await?
+					.into_iter()
+					.map(|(node, st)| (node, st.map_err(|e| e.to_string())))
+					.collect::<Vec<_>>();
+				blocks.insert(hash, status);
+			}
+		}
+
+		Ok(AdminRpc::ObjectInfo {
+			object,
+			versions,
+			blocks,
+		})
+	}
+}
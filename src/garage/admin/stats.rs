@@ -0,0 +1,701 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use format_table::format_table_to_string;
+
+use garage_util::data::*;
+use garage_util::error::Error as GarageError;
+use garage_util::platform;
+
+use garage_table::replication::*;
+use garage_table::*;
+
+use garage_rpc::ring::PARTITION_BITS;
+use garage_rpc::*;
+
+use garage_model::helper::error::Error;
+
+use crate::cli::*;
+
+use super::*;
+
+/// Statistics returned by `garage stats --json`
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatsReport {
+	pub nodes: Vec<NodeStatsResult>,
+	pub cluster: Option<ClusterStats>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeStatsResult {
+	pub id: String,
+	pub stats: Option<NodeStats>,
+	pub error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeStats {
+	pub garage_version: String,
+	pub garage_features: Option<Vec<String>>,
+	pub rust_version: String,
+	pub db_engine: String,
+	pub tables: Vec<TableStats>,
+	pub block_manager: BlockManagerStats,
+	#[serde(default)]
+	pub links: Vec<LinkStats>,
+}
+
+/// Sizes of a table; items of the table and of its Merkle tree are only
+/// counted with --detailed if the database engine cannot give them quickly
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TableStats {
+	pub name: String,
+	pub items: Option<usize>,
+	pub merkle_items: Option<usize>,
+	pub merkle_todo: usize,
+	pub gc_todo: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockManagerStats {
+	pub rc_entries: Option<usize>,
+	pub resync_queue_len: usize,
+	pub resync_queue_oldest_age_secs: u64,
+	pub resync_errors: usize,
+	pub blocks_fetched: u64,
+	pub blocks_fetched_per_sec: f64,
+	pub blocks_sent: u64,
+	pub blocks_sent_per_sec: f64,
+	pub scrub_corruptions: u64,
+	pub gc_candidates: Option<u64>,
+	pub data_dirs: Vec<DataDirStats>,
+}
+
+/// Bytes exchanged with another node in the RPCs made by this node
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LinkStats {
+	pub node: String,
+	pub zone: Option<String>,
+	pub bytes_sent: u64,
+	pub bytes_sent_per_sec: f64,
+	pub bytes_received: u64,
+	pub bytes_received_per_sec: f64,
+	/// Rate limit of the link, in bytes per second in each direction
+	pub max_rate: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DataDirStats {
+	pub path: PathBuf,
+	pub available: Option<u64>,
+	pub total: Option<u64>,
+	pub error: Option<String>,
+	/// Why the directory refuses new blocks, if it does
+	pub full: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClusterStats {
+	pub storage_nodes: Vec<StorageNodeStats>,
+	pub estimated_data_avail: Option<u64>,
+	pub estimated_meta_avail: Option<u64>,
+	/// Information is missing for some storage nodes, the space available
+	/// cluster-wide is lower than the estimates
+	pub estimate_is_upper_bound: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageNodeStats {
+	pub id: String,
+	pub hostname: Option<String>,
+	pub zone: Option<String>,
+	pub capacity: Option<u64>,
+	pub partitions: u64,
+	pub data_avail: Option<DiskAvail>,
+	pub meta_avail: Option<DiskAvail>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiskAvail {
+	pub available: u64,
+	pub total: u64,
+}
+
+impl AdminRpcHandler {
+	pub(super) async fn handle_stats(&self, opt: StatsOpt) -> Result<AdminRpc, Error> {
+		if opt.garbage {
+			return self.handle_garbage_stats(opt).await;
+		}
+
+		if opt.json {
+			let report = self.gather_stats_report(opt).await?;
+			return Ok(AdminRpc::Ok(
+				serde_json::to_string_pretty(&report).map_err(GarageError::from)?,
+			));
+		}
+
+		if opt.all_nodes {
+			let mut ret = String::new();
+			let ring = self.garage.system.ring.borrow().clone();
+
+			for node in ring.layout.node_ids().iter() {
+				let mut opt = opt.clone();
+				opt.all_nodes = false;
+				opt.skip_global = true;
+
+				writeln!(&mut ret, "\n======================").unwrap();
+				writeln!(&mut ret, "Stats for node {:?}:", node).unwrap();
+
+				let node_id = (*node).into();
+				match self
+					.endpoint
+					.call(&node_id, AdminRpc::Stats(opt), PRIO_NORMAL)
+					.await
+				{
+					Ok(Ok(AdminRpc::Ok(s))) => writeln!(&mut ret, "{}", s).unwrap(),
+					Ok(Ok(x)) => writeln!(&mut ret, "Bad answer: {:?}", x).unwrap(),
+					Ok(Err(e)) => writeln!(&mut ret, "Remote error: {}", e).unwrap(),
+					Err(e) => writeln!(&mut ret, "Network error: {}", e).unwrap(),
+				}
+			}
+
+			writeln!(&mut ret, "\n======================").unwrap();
+			write!(
+				&mut ret,
+				"Cluster statistics:\n\n{}",
+				format_cluster_stats(&self.gather_cluster_stats())
+			)
+			.unwrap();
+
+			Ok(AdminRpc::Ok(ret))
+		} else {
+			let mut ret = format_node_stats(&self.gather_stats_local(&opt)?, opt.detailed);
+			if !opt.skip_global {
+				write!(
+					&mut ret,
+					"\n{}",
+					format_cluster_stats(&self.gather_cluster_stats())
+				)
+				.unwrap();
+			}
+			Ok(AdminRpc::Ok(ret))
+		}
+	}
+
+	async fn gather_stats_report(&self, opt: StatsOpt) -> Result<StatsReport, Error> {
+		let cluster = if opt.skip_global {
+			None
+		} else {
+			Some(self.gather_cluster_stats())
+		};
+
+		if !opt.all_nodes {
+			return Ok(StatsReport {
+				nodes: vec![NodeStatsResult {
+					id: hex::encode(self.garage.system.id),
+					stats: Some(self.gather_stats_local(&opt)?),
+					error: None,
+				}],
+				cluster,
+			});
+		}
+
+		let mut nodes = vec![];
+		let ring = self.garage.system.ring.borrow().clone();
+		for node in ring.layout.node_ids().iter() {
+			let mut opt = opt.clone();
+			opt.all_nodes = false;
+			opt.skip_global = true;
+
+			let node_id = (*node).into();
+			let res = match self
+				.endpoint
+				.call(&node_id, AdminRpc::Stats(opt), PRIO_NORMAL)
+				.await
+			{
+				Ok(Ok(AdminRpc::Ok(s))) => serde_json::from_str::<StatsReport>(&s)
+					.map_err(|e| e.to_string())
+					.and_then(|r| {
+						r.nodes
+							.into_iter()
+							.next()
+							.and_then(|n| n.stats)
+							.ok_or_else(|| "No statistics in answer".to_string())
+					}),
+				Ok(Ok(x)) => Err(format!("Bad answer: {:?}", x)),
+				Ok(Err(e)) => Err(format!("Remote error: {}", e)),
+				Err(e) => Err(format!("Network error: {}", e)),
+			};
+			nodes.push(match res {
+				Ok(stats) => NodeStatsResult {
+					id: hex::encode(node),
+					stats: Some(stats),
+					error: None,
+				},
+				Err(e) => NodeStatsResult {
+					id: hex::encode(node),
+					stats: None,
+					error: Some(e),
+				},
+			});
+		}
+
+		Ok(StatsReport { nodes, cluster })
+	}
+
+	async fn handle_garbage_stats(&self, opt: StatsOpt) -> Result<AdminRpc, Error> {
+		if opt.skip_global {
+			let stats = self
+				.garage
+				.garbage_stats_persister
+				.get_with(|x| x.last.clone());
+			return Ok(AdminRpc::GarbageStats(vec![(
+				self.garage.system.id,
+				Ok(stats),
+			)]));
+		}
+
+		// Garbage statistics are always gathered from all nodes, as the space
+		// that can be reclaimed is spread over the whole cluster
+		let mut ret = vec![];
+		let ring = self.garage.system.ring.borrow().clone();
+		for node in ring.layout.node_ids().iter() {
+			let mut opt = opt.clone();
+			opt.skip_global = true;
+
+			let node_id = (*node).into();
+			match self
+				.endpoint
+				.call(&node_id, AdminRpc::Stats(opt), PRIO_NORMAL)
+				.await
+			{
+				Ok(Ok(AdminRpc::GarbageStats(v))) => ret.extend(v),
+				Ok(Ok(m)) => return Err(GarageError::unexpected_rpc_message(m).into()),
+				Ok(Err(e)) => ret.push((*node, Err(e.to_string()))),
+				Err(e) => ret.push((*node, Err(e.to_string()))),
+			}
+		}
+		Ok(AdminRpc::GarbageStats(ret))
+	}
+
+	fn gather_stats_local(&self, opt: &StatsOpt) -> Result<NodeStats, Error> {
+		let tables = vec![
+			self.gather_table_stats(&self.garage.bucket_table, opt.detailed)?,
+			self.gather_table_stats(&self.garage.key_table, opt.detailed)?,
+			self.gather_table_stats(&self.garage.object_table, opt.detailed)?,
+			self.gather_table_stats(&self.garage.version_table, opt.detailed)?,
+			self.gather_table_stats(&self.garage.block_ref_table, opt.detailed)?,
+		];
+
+		let block_manager = &self.garage.block_manager;
+		let rc_entries = if opt.detailed {
+			Some(block_manager.rc_len()?)
+		} else {
+			block_manager.rc_fast_len()?
+		};
+		let gc_candidates = if opt.detailed {
+			Some(block_manager.gc_candidates()?)
+		} else {
+			None
+		};
+		let (blocks_fetched, blocks_fetched_per_sec) = block_manager.transfer_stats().fetched();
+		let (blocks_sent, blocks_sent_per_sec) = block_manager.transfer_stats().sent();
+
+		let full_dirs = block_manager.full_dirs();
+		let data_dirs = block_manager
+			.data_dirs()
+			.into_iter()
+			.map(|path| {
+				let full = full_dirs
+					.iter()
+					.find(|(dir, _)| *dir == path)
+					.map(|(_, why)| why.clone());
+				match platform::disk_space(&path) {
+					Ok(space) => DataDirStats {
+						path,
+						available: Some(space.available),
+						total: Some(space.total),
+						error: None,
+						full,
+					},
+					Err(e) => DataDirStats {
+						path,
+						available: None,
+						total: None,
+						error: Some(e.to_string()),
+						full,
+					},
+				}
+			})
+			.collect();
+
+		let ring = self.garage.system.ring.borrow().clone();
+		let links = self
+			.garage
+			.system
+			.rpc
+			.bandwidth()
+			.link_stats()
+			.into_iter()
+			.map(|l| {
+				let secs = l.elapsed_secs.max(1.);
+				LinkStats {
+					node: hex::encode(l.node),
+					zone: ring.layout.node_role(&l.node).map(|r| r.zone.clone()),
+					bytes_sent: l.bytes_sent,
+					bytes_sent_per_sec: l.bytes_sent as f64 / secs,
+					bytes_received: l.bytes_received,
+					bytes_received_per_sec: l.bytes_received as f64 / secs,
+					max_rate: l.max_rate,
+				}
+			})
+			.collect();
+
+		Ok(NodeStats {
+			garage_version: garage_util::version::garage_version().to_string(),
+			garage_features: garage_util::version::garage_features()
+				.map(|list| list.iter().map(|f| f.to_string()).collect()),
+			rust_version: garage_util::version::rust_version().to_string(),
+			db_engine: self.garage.db.engine(),
+			tables,
+			block_manager: BlockManagerStats {
+				rc_entries,
+				resync_queue_len: block_manager.resync.queue_len()?,
+				resync_queue_oldest_age_secs: block_manager.resync.queue_oldest_age()?.as_secs(),
+				resync_errors: block_manager.resync.errors_len()?,
+				blocks_fetched,
+				blocks_fetched_per_sec,
+				blocks_sent,
+				blocks_sent_per_sec,
+				scrub_corruptions: block_manager.scrub_corruptions_detected(),
+				gc_candidates,
+				data_dirs,
+			},
+			links,
+		})
+	}
+
+	fn gather_cluster_stats(&self) -> ClusterStats {
+		// Gather storage node and free space statistics
+		let layout = &self.garage.system.ring.borrow().layout;
+		let mut node_partition_count = HashMap::<Uuid, u64>::new();
+		for short_id in layout.ring_assignment_data.iter() {
+			let id = layout.node_id_vec[*short_id as usize];
+			*node_partition_count.entry(id).or_default() += 1;
+		}
+		let node_info = self
+			.garage
+			.system
+			.get_known_nodes()
+			.into_iter()
+			.map(|n| (n.id, n))
+			.collect::<HashMap<_, _>>();
+
+		let mut storage_nodes = vec![];
+		for (id, parts) in node_partition_count.iter() {
+			let status = node_info.get(id).map(|x| &x.status);
+			let role = layout.roles.get(id).and_then(|x| x.0.as_ref());
+			let avail =
+				|x: Option<(u64, u64)>| x.map(|(available, total)| DiskAvail { available, total });
+			storage_nodes.push(StorageNodeStats {
+				id: hex::encode(id),
+				hostname: status.map(|x| x.hostname.clone()),
+				zone: role.map(|x| x.zone.clone()),
+				capacity: role.and_then(|x| x.capacity),
+				partitions: *parts,
+				data_avail: avail(status.and_then(|x| x.data_disk_avail)),
+				meta_avail: avail(status.and_then(|x| x.meta_disk_avail)),
+			});
+		}
+
+		let meta_part_avail = node_partition_count
+			.iter()
+			.filter_map(|(id, parts)| {
+				node_info
+					.get(id)
+					.and_then(|x| x.status.meta_disk_avail)
+					.map(|c| c.0 / *parts)
+			})
+			.collect::<Vec<_>>();
+		let data_part_avail = node_partition_count
+			.iter()
+			.filter_map(|(id, parts)| {
+				node_info
+					.get(id)
+					.and_then(|x| x.status.data_disk_avail)
+					.map(|c| c.0 / *parts)
+			})
+			.collect::<Vec<_>>();
+		let (estimated_data_avail, estimated_meta_avail) =
+			if !meta_part_avail.is_empty() && !data_part_avail.is_empty() {
+				(
+					Some(data_part_avail.iter().min().unwrap() * (1 << PARTITION_BITS)),
+					Some(meta_part_avail.iter().min().unwrap() * (1 << PARTITION_BITS)),
+				)
+			} else {
+				(None, None)
+			};
+
+		ClusterStats {
+			storage_nodes,
+			estimated_data_avail,
+			estimated_meta_avail,
+			estimate_is_upper_bound: meta_part_avail.len() < node_partition_count.len()
+				|| data_part_avail.len() < node_partition_count.len(),
+		}
+	}
+
+	fn gather_table_stats<F, R>(
+		&self,
+		t: &Arc<Table<F, R>>,
+		detailed: bool,
+	) -> Result<TableStats, Error>
+	where
+		F: TableSchema + 'static,
+		R: TableReplication + 'static,
+	{
+		let (items, merkle_items) = if detailed {
+			(
+				Some(t.data.store.len().map_err(GarageError::from)?),
+				Some(t.merkle_updater.merkle_tree_len()?),
+			)
+		} else {
+			(
+				t.data.store.fast_len().map_err(GarageError::from)?,
+				t.merkle_updater.merkle_tree_fast_len()?,
+			)
+		};
+
+		Ok(TableStats {
+			name: F::TABLE_NAME.to_string(),
+			items,
+			merkle_items,
+			merkle_todo: t.merkle_updater.todo_len()?,
+			gc_todo: t.data.gc_todo_len()?,
+		})
+	}
+}
+
+fn not_computed<T: ToString>(x: Option<T>) -> String {
+	x.map(|x| x.to_string()).unwrap_or_else(|| "NC".into())
+}
+
+fn format_node_stats(stats: &NodeStats, detailed: bool) -> String {
+	let mut ret = String::new();
+	writeln!(
+		&mut ret,
+		"\nGarage version: {} [features: {}]\nRust compiler version: {}",
+		stats.garage_version,
+		stats
+			.garage_features
+			.as_ref()
+			.map(|list| list.join(", "))
+			.unwrap_or_else(|| "(unknown)".into()),
+		stats.rust_version,
+	)
+	.unwrap();
+
+	writeln!(&mut ret, "\nDatabase engine: {}", stats.db_engine).unwrap();
+
+	// Table statistics
+	let mut table = vec!["  Table\tItems\tMklItems\tMklTodo\tGcTodo".into()];
+	for t in stats.tables.iter() {
+		table.push(format!(
+			"  {}\t{}\t{}\t{}\t{}",
+			t.name,
+			not_computed(t.items),
+			not_computed(t.merkle_items),
+			t.merkle_todo,
+			t.gc_todo
+		));
+	}
+	write!(
+		&mut ret,
+		"\nTable stats:\n{}",
+		format_table_to_string(table)
+	)
+	.unwrap();
+
+	// Block manager statistics
+	let bm = &stats.block_manager;
+	writeln!(&mut ret, "\nBlock manager stats:").unwrap();
+	writeln!(
+		&mut ret,
+		"  number of RC entries (~= number of blocks): {}",
+		not_computed(bm.rc_entries)
+	)
+	.unwrap();
+	writeln!(&mut ret, "  resync queue length: {}", bm.resync_queue_len).unwrap();
+	writeln!(
+		&mut ret,
+		"  resync queue oldest item age: {}s",
+		bm.resync_queue_oldest_age_secs
+	)
+	.unwrap();
+	writeln!(
+		&mut ret,
+		"  blocks with resync errors: {}",
+		bm.resync_errors
+	)
+	.unwrap();
+	writeln!(
+		&mut ret,
+		"  blocks fetched from other nodes: {} ({:.2}/s)",
+		bm.blocks_fetched, bm.blocks_fetched_per_sec
+	)
+	.unwrap();
+	writeln!(
+		&mut ret,
+		"  blocks sent to other nodes: {} ({:.2}/s)",
+		bm.blocks_sent, bm.blocks_sent_per_sec
+	)
+	.unwrap();
+	writeln!(
+		&mut ret,
+		"  corrupted blocks found by scrub: {}",
+		bm.scrub_corruptions
+	)
+	.unwrap();
+	writeln!(
+		&mut ret,
+		"  blocks waiting to be deleted: {}",
+		not_computed(bm.gc_candidates)
+	)
+	.unwrap();
+
+	writeln!(&mut ret, "  data directories:").unwrap();
+	for dir in bm.data_dirs.iter() {
+		match (dir.available, dir.total, &dir.error) {
+			(Some(available), Some(total), _) => writeln!(
+				&mut ret,
+				"    {}: {} free out of {}",
+				dir.path.display(),
+				bytesize::ByteSize(available),
+				bytesize::ByteSize(total)
+			),
+			(_, _, e) => writeln!(
+				&mut ret,
+				"    {}: unable to get free space: {}",
+				dir.path.display(),
+				e.as_deref().unwrap_or("?")
+			),
+		}
+		.unwrap();
+	}
+	for dir in bm.data_dirs.iter() {
+		if let Some(why) = &dir.full {
+			writeln!(
+				&mut ret,
+				"  refusing new blocks, {}: {}",
+				dir.path.display(),
+				why
+			)
+			.unwrap();
+		}
+	}
+
+	// Traffic with other nodes
+	if !stats.links.is_empty() {
+		let mut table = vec!["  Node\tZone\tSent\tReceived\tLimit".into()];
+		for l in stats.links.iter() {
+			table.push(format!(
+				"  {:.16}\t{}\t{} ({}/s)\t{} ({}/s)\t{}",
+				l.node,
+				l.zone.as_deref().unwrap_or("-"),
+				bytesize::ByteSize(l.bytes_sent),
+				bytesize::ByteSize(l.bytes_sent_per_sec as u64),
+				bytesize::ByteSize(l.bytes_received),
+				bytesize::ByteSize(l.bytes_received_per_sec as u64),
+				l.max_rate
+					.map(|r| format!("{}/s", bytesize::ByteSize(r)))
+					.unwrap_or_else(|| "-".into()),
+			));
+		}
+		write!(
+			&mut ret,
+			"\nRPC traffic with other nodes (requests made by this node):\n{}",
+			format_table_to_string(table)
+		)
+		.unwrap();
+	}
+
+	if !detailed {
+		writeln!(&mut ret, "\nIf values are missing above (marked as NC), consider adding the --detailed flag (this will be slow).").unwrap();
+	}
+
+	ret
+}
+
+fn format_cluster_stats(stats: &ClusterStats) -> String {
+	let mut ret = String::new();
+
+	let mut table = vec!["  ID\tHostname\tZone\tCapacity\tPart.\tDataAvail\tMetaAvail".into()];
+	for node in stats.storage_nodes.iter() {
+		let avail_str = |x: &Option<DiskAvail>| match x {
+			Some(d) => {
+				let pct = (d.available as f64) / (d.total as f64) * 100.;
+				let avail = bytesize::ByteSize::b(d.available);
+				let total = bytesize::ByteSize::b(d.total);
+				format!("{}/{} ({:.1}%)", avail, total, pct)
+			}
+			None => "?".into(),
+		};
+		let capacity = match (&node.zone, node.capacity) {
+			(None, _) => "?".into(),
+			(Some(_), Some(c)) => bytesize::ByteSize::b(c).to_string_as(false),
+			(Some(_), None) => "gateway".into(),
+		};
+		table.push(format!(
+			"  {}\t{}\t{}\t{}\t{}\t{}\t{}",
+			&node.id[..16],
+			node.hostname.as_deref().unwrap_or("?"),
+			node.zone.as_deref().unwrap_or("?"),
+			capacity,
+			node.partitions,
+			avail_str(&node.data_avail),
+			avail_str(&node.meta_avail)
+		));
+	}
+	write!(
+		&mut ret,
+		"Storage nodes:\n{}",
+		format_table_to_string(table)
+	)
+	.unwrap();
+
+	if let (Some(data_avail), Some(meta_avail)) =
+		(stats.estimated_data_avail, stats.estimated_meta_avail)
+	{
+		let data_avail = bytesize::ByteSize(data_avail);
+		let meta_avail = bytesize::ByteSize(meta_avail);
+		writeln!(
+			&mut ret,
+			"\nEstimated available storage space cluster-wide (might be lower in practice):"
+		)
+		.unwrap();
+		if stats.estimate_is_upper_bound {
+			writeln!(&mut ret, "  data: < {}", data_avail).unwrap();
+			writeln!(&mut ret, "  metadata: < {}", meta_avail).unwrap();
+			writeln!(&mut ret, "A precise estimate could not be given as information is missing for some storage nodes.").unwrap();
+		} else {
+			writeln!(&mut ret, "  data: {}", data_avail).unwrap();
+			writeln!(&mut ret, "  metadata: {}", meta_avail).unwrap();
+		}
+	}
+
+	ret
+}
@@ -11,6 +11,7 @@ use garage_rpc::*;
 use garage_model::helper::error::Error as HelperError;
 
 use crate::admin::*;
+use crate::cli::json::{self, print_json};
 use crate::cli::*;
 
 pub async fn cli_command_dispatch(
@@ -18,37 +19,52 @@ pub async fn cli_command_dispatch(
 	system_rpc_endpoint: &Endpoint<SystemRpc, ()>,
 	admin_rpc_endpoint: &Endpoint<AdminRpc, ()>,
 	rpc_host: NodeID,
+	json: bool,
 ) -> Result<(), HelperError> {
+	let admin = |args| cmd_admin(admin_rpc_endpoint, rpc_host, args, json);
 	match cmd {
-		Command::Status => Ok(cmd_status(system_rpc_endpoint, rpc_host).await?),
+		Command::Status => Ok(cmd_status(system_rpc_endpoint, rpc_host, json).await?),
 		Command::Node(NodeOperation::Connect(connect_opt)) => {
 			Ok(cmd_connect(system_rpc_endpoint, rpc_host, connect_opt).await?)
 		}
+		Command::Node(NodeOperation::LogFilter(lfo)) => admin(AdminRpc::LogFilter(lfo)).await,
 		Command::Layout(layout_opt) => {
-			Ok(cli_layout_command_dispatch(layout_opt, system_rpc_endpoint, rpc_host).await?)
+			Ok(
+				cli_layout_command_dispatch(layout_opt, system_rpc_endpoint, rpc_host, json)
+					.await?,
+			)
 		}
-		Command::Bucket(bo) => {
-			cmd_admin(admin_rpc_endpoint, rpc_host, AdminRpc::BucketOperation(bo)).await
+		Command::Bucket(bo) => admin(AdminRpc::BucketOperation(bo)).await,
+		Command::Key(ko) => admin(AdminRpc::KeyOperation(ko)).await,
+		Command::Migrate(mo) => admin(AdminRpc::Migrate(mo)).await,
+		Command::Repair(ro) => admin(AdminRpc::LaunchRepair(ro)).await,
+		Command::Stats(mut so) => {
+			so.json = json;
+			admin(AdminRpc::Stats(so)).await
 		}
-		Command::Key(ko) => {
-			cmd_admin(admin_rpc_endpoint, rpc_host, AdminRpc::KeyOperation(ko)).await
-		}
-		Command::Migrate(mo) => {
-			cmd_admin(admin_rpc_endpoint, rpc_host, AdminRpc::Migrate(mo)).await
-		}
-		Command::Repair(ro) => {
-			cmd_admin(admin_rpc_endpoint, rpc_host, AdminRpc::LaunchRepair(ro)).await
-		}
-		Command::Stats(so) => cmd_admin(admin_rpc_endpoint, rpc_host, AdminRpc::Stats(so)).await,
-		Command::Worker(wo) => cmd_admin(admin_rpc_endpoint, rpc_host, AdminRpc::Worker(wo)).await,
-		Command::Block(bo) => {
-			cmd_admin(admin_rpc_endpoint, rpc_host, AdminRpc::BlockOperation(bo)).await
+		Command::Worker(wo) => admin(AdminRpc::Worker(wo)).await,
+		Command::Job(jo) => admin(AdminRpc::Job(jo)).await,
+		Command::Block(bo) => admin(AdminRpc::BlockOperation(bo)).await,
+		Command::Object(oo) => admin(AdminRpc::ObjectOperation(oo)).await,
+		Command::Import(ImportOperation::S3(mut opt)) => {
+			// Credentials are read from the environment of the CLI, not of the node
+			if opt.access_key_id.is_none() {
+				opt.access_key_id = std::env::var("AWS_ACCESS_KEY_ID").ok();
+			}
+			if opt.secret_access_key.is_none() {
+				opt.secret_access_key = std::env::var("AWS_SECRET_ACCESS_KEY").ok();
+			}
+			admin(AdminRpc::Import(ImportOperation::S3(opt))).await
 		}
 		_ => unreachable!(),
 	}
 }
 
-pub async fn cmd_status(rpc_cli: &Endpoint<SystemRpc, ()>, rpc_host: NodeID) -> Result<(), Error> {
+pub async fn cmd_status(
+	rpc_cli: &Endpoint<SystemRpc, ()>,
+	rpc_host: NodeID,
+	json: bool,
+) -> Result<(), Error> {
 	let status = match rpc_cli
 		.call(&rpc_host, SystemRpc::GetKnownNodes, PRIO_NORMAL)
 		.await??
@@ -58,6 +74,10 @@ pub async fn cmd_status(rpc_cli: &Endpoint<SystemRpc, ()>, rpc_host: NodeID) ->
 	};
 	let layout = fetch_layout(rpc_cli, rpc_host).await?;
 
+	if json {
+		return print_json(&json::ClusterStatus::new(&status, &layout));
+	}
+
 	println!("==== HEALTHY NODES ====");
 	let mut healthy_nodes =
 		vec!["ID\tHostname\tAddress\tTags\tZone\tCapacity\tDataAvail".to_string()];
@@ -148,6 +168,30 @@ pub async fn cmd_status(rpc_cli: &Endpoint<SystemRpc, ()>, rpc_host: NodeID) ->
 		format_table(failed_nodes);
 	}
 
+	if status.iter().any(|adv| adv.status.drain.is_some()) {
+		println!("\n==== DRAINING NODES ====");
+		let mut draining_nodes =
+			vec!["ID\tHostname\tProgress\tPartitions left\tBlocks left".to_string()];
+		for adv in status.iter() {
+			if let Some(drain) = &adv.status.drain {
+				let progress = if drain.is_complete() {
+					"complete".to_string()
+				} else {
+					format!("{:.1}%", drain.progress())
+				};
+				draining_nodes.push(format!(
+					"{id:?}\t{host}\t{progress}\t{partitions}\t{blocks}",
+					id = adv.id,
+					host = adv.status.hostname,
+					progress = progress,
+					partitions = drain.partitions_remaining,
+					blocks = drain.blocks_remaining,
+				));
+			}
+		}
+		format_table(draining_nodes);
+	}
+
 	if print_staging_role_changes(&layout) {
 		println!();
 		println!("Please use `garage layout show` to check the proposed new layout and apply it.");
@@ -178,11 +222,46 @@ pub async fn cmd_admin(
 	rpc_cli: &Endpoint<AdminRpc, ()>,
 	rpc_host: NodeID,
 	args: AdminRpc,
+	json: bool,
 ) -> Result<(), HelperError> {
 	match rpc_cli.call(&rpc_host, args, PRIO_NORMAL).await?? {
 		AdminRpc::Ok(msg) => {
 			println!("{}", msg);
 		}
+		AdminRpc::BucketList(bl) if json => {
+			print_json(&bl.iter().map(json::BucketListItem::new).collect::<Vec<_>>())?;
+		}
+		AdminRpc::BucketInfo {
+			bucket,
+			relevant_keys,
+			counters,
+			mpu_counters,
+			..
+		} if json => {
+			print_json(&json::BucketInfo::new(
+				&bucket,
+				&relevant_keys,
+				&counters,
+				&mpu_counters,
+			))?;
+		}
+		AdminRpc::KeyList(kl) if json => {
+			let kl = kl
+				.into_iter()
+				.map(|(id, name)| json::KeyListItem { id, name })
+				.collect::<Vec<_>>();
+			print_json(&kl)?;
+		}
+		AdminRpc::KeyInfo(key, rb) if json => {
+			print_json(&json::KeyInfo::new(&key, &rb))?;
+		}
+		AdminRpc::GarbageStats(gs) if json => {
+			let gs = gs
+				.iter()
+				.map(|(id, stats)| json::GarbageStatsNode::new(id, stats))
+				.collect::<Vec<_>>();
+			print_json(&gs)?;
+		}
 		AdminRpc::BucketList(bl) => {
 			print_bucket_list(bl);
 		}
@@ -191,8 +270,23 @@ pub async fn cmd_admin(
 			relevant_keys,
 			counters,
 			mpu_counters,
+			trash_counters,
+			dedup,
 		} => {
-			print_bucket_info(&bucket, &relevant_keys, &counters, &mpu_counters);
+			print_bucket_info(
+				&bucket,
+				&relevant_keys,
+				&counters,
+				&mpu_counters,
+				&trash_counters,
+				dedup.as_ref(),
+			);
+		}
+		AdminRpc::BucketUsage { prefix, usage } => {
+			print_bucket_usage(&prefix, usage);
+		}
+		AdminRpc::TrashList(trashed) => {
+			print_trash_list(&trashed);
 		}
 		AdminRpc::KeyList(kl) => {
 			print_key_list(kl);
@@ -206,12 +300,27 @@ pub async fn cmd_admin(
 		AdminRpc::WorkerVars(wv) => {
 			print_worker_vars(wv);
 		}
+		AdminRpc::LogFilters(lf) => {
+			print_log_filters(lf);
+		}
 		AdminRpc::WorkerInfo(tid, wi) => {
 			print_worker_info(tid, wi);
 		}
+		AdminRpc::JobList(jobs) => {
+			print_job_list(jobs);
+		}
 		AdminRpc::BlockErrorList(el) => {
 			print_block_error_list(el);
 		}
+		AdminRpc::GarbageStats(gs) => {
+			print_garbage_stats(gs);
+		}
+		AdminRpc::BlockLostList(ll) => {
+			print_block_lost_list(ll);
+		}
+		AdminRpc::BlockRefList { refs, bucket_names } => {
+			print_block_ref_list(refs, bucket_names);
+		}
 		AdminRpc::BlockInfo {
 			hash,
 			refcount,
@@ -220,6 +329,21 @@ pub async fn cmd_admin(
 		} => {
 			print_block_info(hash, refcount, versions, uploads);
 		}
+		AdminRpc::MultipartUploadList {
+			uploads,
+			bucket_names,
+			initiators,
+			aborted,
+		} => {
+			print_multipart_upload_list(uploads, bucket_names, initiators, aborted);
+		}
+		AdminRpc::ObjectInfo {
+			object,
+			versions,
+			blocks,
+		} => {
+			print_object_info(object, versions, blocks);
+		}
 		r => {
 			error!("Unexpected response: {:?}", r);
 		}
@@ -1,7 +1,10 @@
 use std::path::PathBuf;
 
+use garage_util::config::Config;
 use garage_util::error::*;
 
+use garage_model::config_check::*;
+
 pub const READ_KEY_ERROR: &str = "Unable to read node key. It will be generated by your garage node the first time is it launched. Ensure that your garage node is currently running. (The node key is supposed to be stored in your metadata directory.)";
 
 pub fn node_id_command(config_file: PathBuf, quiet: bool) -> Result<(), Error> {
@@ -63,3 +66,31 @@ cluster. It is thus a good security measure to try to keep them secret if possib
 
 	Ok(())
 }
+
+pub fn check_config_command(
+	config_file: PathBuf,
+	fill_secrets: impl FnOnce(Config) -> Config,
+) -> Result<(), Error> {
+	let (_, issues) = check_config_file(config_file.clone(), fill_secrets)?;
+	for issue in issues.iter() {
+		println!("{}", issue);
+	}
+
+	let errors = issues
+		.iter()
+		.filter(|i| i.level == ConfigIssueLevel::Error)
+		.count();
+	if errors > 0 {
+		return Err(Error::Message(format!(
+			"{} errors found in configuration file {}",
+			errors,
+			config_file.to_string_lossy()
+		)));
+	}
+	println!(
+		"Configuration file {} is valid ({} warnings).",
+		config_file.to_string_lossy(),
+		issues.len()
+	);
+	Ok(())
+}
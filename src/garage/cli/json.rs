@@ -0,0 +1,464 @@
+//! Machine-readable output of the CLI, printed instead of the tables when
+//! the --json flag is given. Field names and shapes are those of the
+//! corresponding responses of the admin API (of which they contain a
+//! subset), so that the output is the same whether a command is run through
+//! RPC or through the admin API with --endpoint.
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use garage_util::data::*;
+use garage_util::error::Error;
+use garage_util::time::*;
+
+use garage_rpc::layout::ClusterLayout as RpcClusterLayout;
+use garage_rpc::system::KnownNodeInfo;
+
+use garage_model::bucket_table::Bucket;
+use garage_model::garbage_stats::GarbageStats;
+use garage_model::key_table::Key;
+use garage_model::s3::{mpu_table, object_table};
+
+pub fn print_json<T: Serialize>(v: &T) -> Result<(), Error> {
+	println!("{}", serde_json::to_string_pretty(v)?);
+	Ok(())
+}
+
+// ---- cluster ----
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClusterStatus {
+	pub known_nodes: Vec<KnownNode>,
+	pub layout: ClusterLayout,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KnownNode {
+	pub id: String,
+	pub addr: String,
+	pub is_up: bool,
+	pub last_seen_secs_ago: Option<u64>,
+	pub hostname: String,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub drain: Option<NodeDrain>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeDrain {
+	pub complete: bool,
+	pub progress: f64,
+	pub partitions_remaining: u64,
+	pub blocks_remaining: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClusterLayout {
+	pub version: u64,
+	pub roles: Vec<NodeRole>,
+	pub staged_role_changes: Vec<StagedRoleChange>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeRole {
+	pub id: String,
+	pub zone: String,
+	pub capacity: Option<u64>,
+	pub tags: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StagedRoleChange {
+	pub id: String,
+	#[serde(flatten)]
+	pub action: RoleChangeAction,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum RoleChangeAction {
+	Remove {
+		remove: bool,
+	},
+	Update {
+		zone: String,
+		capacity: Option<u64>,
+		tags: Vec<String>,
+	},
+}
+
+impl ClusterStatus {
+	pub fn new(nodes: &[KnownNodeInfo], layout: &RpcClusterLayout) -> Self {
+		let known_nodes = nodes
+			.iter()
+			.map(|n| KnownNode {
+				id: hex::encode(n.id),
+				addr: n.addr.to_string(),
+				is_up: n.is_up,
+				last_seen_secs_ago: n.last_seen_secs_ago,
+				hostname: n.status.hostname.clone(),
+				drain: n.status.drain.as_ref().map(|d| NodeDrain {
+					complete: d.is_complete(),
+					progress: d.progress(),
+					partitions_remaining: d.partitions_remaining,
+					blocks_remaining: d.blocks_remaining,
+				}),
+			})
+			.collect();
+		Self {
+			known_nodes,
+			layout: ClusterLayout::new(layout),
+		}
+	}
+}
+
+impl ClusterLayout {
+	pub fn new(layout: &RpcClusterLayout) -> Self {
+		let roles = layout
+			.roles
+			.items()
+			.iter()
+			.filter_map(|(k, _, v)| v.0.as_ref().map(|x| (k, x)))
+			.map(|(k, v)| NodeRole {
+				id: hex::encode(k),
+				zone: v.zone.clone(),
+				capacity: v.capacity,
+				tags: v.tags.clone(),
+			})
+			.collect();
+
+		let staged_role_changes = layout
+			.staging_roles
+			.items()
+			.iter()
+			.filter(|(k, _, v)| layout.roles.get(k) != Some(v))
+			.map(|(k, _, v)| StagedRoleChange {
+				id: hex::encode(k),
+				action: match &v.0 {
+					None => RoleChangeAction::Remove { remove: true },
+					Some(r) => RoleChangeAction::Update {
+						zone: r.zone.clone(),
+						capacity: r.capacity,
+						tags: r.tags.clone(),
+					},
+				},
+			})
+			.collect();
+
+		Self {
+			version: layout.version,
+			roles,
+			staged_role_changes,
+		}
+	}
+}
+
+// ---- buckets ----
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BucketListItem {
+	pub id: String,
+	pub global_aliases: Vec<String>,
+	pub local_aliases: Vec<BucketLocalAlias>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BucketLocalAlias {
+	pub access_key_id: String,
+	pub alias: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BucketInfo {
+	pub id: String,
+	pub global_aliases: Vec<String>,
+	pub website_access: bool,
+	pub public_read: bool,
+	pub keys: Vec<BucketInfoKey>,
+	pub objects: i64,
+	pub bytes: i64,
+	pub unfinished_uploads: i64,
+	#[serde(default)]
+	pub unfinished_multipart_uploads: i64,
+	#[serde(default)]
+	pub unfinished_multipart_upload_bytes: i64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BucketInfoKey {
+	pub access_key_id: String,
+	pub name: String,
+	pub permissions: Permissions,
+	pub bucket_local_aliases: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Permissions {
+	pub read: bool,
+	pub write: bool,
+	pub owner: bool,
+}
+
+impl Permissions {
+	pub fn flags(&self) -> String {
+		format!(
+			"{}{}{}",
+			if self.read { "R" } else { "" },
+			if self.write { "W" } else { "" },
+			if self.owner { "O" } else { "" }
+		)
+	}
+}
+
+impl BucketListItem {
+	pub fn new(bucket: &Bucket) -> Self {
+		Self {
+			id: hex::encode(bucket.id),
+			global_aliases: bucket
+				.aliases()
+				.iter()
+				.filter(|(_, _, active)| *active)
+				.map(|(name, _, _)| name.to_string())
+				.collect(),
+			local_aliases: bucket
+				.local_aliases()
+				.iter()
+				.filter(|(_, _, active)| *active)
+				.map(|((key_id, alias), _, _)| BucketLocalAlias {
+					access_key_id: key_id.to_string(),
+					alias: alias.to_string(),
+				})
+				.collect(),
+		}
+	}
+}
+
+impl BucketInfo {
+	pub fn new(
+		bucket: &Bucket,
+		relevant_keys: &HashMap<String, Key>,
+		counters: &HashMap<String, i64>,
+		mpu_counters: &HashMap<String, i64>,
+	) -> Self {
+		let params = bucket.params();
+		let keys = params
+			.map(|p| {
+				p.authorized_keys
+					.items()
+					.iter()
+					.filter(|(_, perm)| perm.is_any())
+					.map(|(key_id, perm)| BucketInfoKey {
+						access_key_id: key_id.to_string(),
+						name: relevant_keys
+							.get(key_id)
+							.and_then(|k| k.params())
+							.map(|k| k.name.get().to_string())
+							.unwrap_or_default(),
+						permissions: Permissions {
+							read: perm.allow_read,
+							write: perm.allow_write,
+							owner: perm.allow_owner,
+						},
+						bucket_local_aliases: p
+							.local_aliases
+							.items()
+							.iter()
+							.filter(|((k, _), _, active)| *active && k == key_id)
+							.map(|((_, alias), _, _)| alias.to_string())
+							.collect(),
+					})
+					.collect()
+			})
+			.unwrap_or_default();
+
+		Self {
+			id: hex::encode(bucket.id),
+			global_aliases: BucketListItem::new(bucket).global_aliases,
+			website_access: params
+				.map(|p| p.website_config.get().is_some())
+				.unwrap_or(false),
+			public_read: params.map(|p| *p.public_read.get()).unwrap_or(false),
+			keys,
+			objects: *counters.get(object_table::OBJECTS).unwrap_or(&0),
+			bytes: *counters.get(object_table::BYTES).unwrap_or(&0),
+			unfinished_uploads: *counters.get(object_table::UNFINISHED_UPLOADS).unwrap_or(&0),
+			unfinished_multipart_uploads: *mpu_counters.get(mpu_table::UPLOADS).unwrap_or(&0),
+			unfinished_multipart_upload_bytes: *mpu_counters.get(mpu_table::BYTES).unwrap_or(&0),
+		}
+	}
+}
+
+// ---- keys ----
+
+#[derive(Serialize, Deserialize)]
+pub struct KeyListItem {
+	pub id: String,
+	pub name: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyInfo {
+	pub name: String,
+	pub access_key_id: String,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub secret_access_key: Option<String>,
+	pub permissions: KeyPermissions,
+	pub expiration: Option<String>,
+	pub buckets: Vec<KeyInfoBucket>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyPermissions {
+	pub create_bucket: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyInfoBucket {
+	pub id: String,
+	pub global_aliases: Vec<String>,
+	pub local_aliases: Vec<String>,
+	pub permissions: Permissions,
+}
+
+impl KeyInfo {
+	pub fn new(key: &Key, relevant_buckets: &HashMap<Uuid, Bucket>) -> Self {
+		let params = key.params();
+		let buckets = params
+			.map(|p| {
+				p.authorized_buckets
+					.items()
+					.iter()
+					.filter(|(_, perm)| perm.is_any())
+					.map(|(bucket_id, perm)| KeyInfoBucket {
+						id: hex::encode(bucket_id),
+						global_aliases: relevant_buckets
+							.get(bucket_id)
+							.map(|b| BucketListItem::new(b).global_aliases)
+							.unwrap_or_default(),
+						local_aliases: p
+							.local_aliases
+							.items()
+							.iter()
+							.filter(|(_, _, a)| *a == Some(*bucket_id))
+							.map(|(a, _, _)| a.clone())
+							.collect(),
+						permissions: Permissions {
+							read: perm.allow_read,
+							write: perm.allow_write,
+							owner: perm.allow_owner,
+						},
+					})
+					.collect()
+			})
+			.unwrap_or_default();
+
+		Self {
+			name: params.map(|p| p.name.get().clone()).unwrap_or_default(),
+			access_key_id: key.key_id.clone(),
+			secret_access_key: params
+				.map(|p| p.secret_key.clone())
+				.filter(|s| s != "(redacted)"),
+			permissions: KeyPermissions {
+				create_bucket: params
+					.map(|p| *p.allow_create_bucket.get())
+					.unwrap_or(false),
+			},
+			expiration: key.expiration().map(msec_to_rfc3339),
+			buckets,
+		}
+	}
+}
+
+// ---- stats ----
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GarbageStatsNode {
+	pub id: String,
+	pub computed_at: Option<String>,
+	pub unreferenced_blocks: Option<u64>,
+	pub unreferenced_blocks_bytes: Option<u64>,
+	pub aborted_uploads: Option<u64>,
+	pub stale_uploads: Option<u64>,
+	pub stale_uploads_bytes: Option<u64>,
+	pub tombstones: Vec<TableTombstones>,
+	pub error: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TableTombstones {
+	pub table: String,
+	pub count: u64,
+	pub bytes: u64,
+}
+
+impl GarbageStatsNode {
+	pub fn new(id: &Uuid, stats: &Result<Option<GarbageStats>, String>) -> Self {
+		let s = stats.as_ref().ok().and_then(|s| s.as_ref());
+		Self {
+			id: hex::encode(id),
+			computed_at: s.map(|s| msec_to_rfc3339(s.computed_at)),
+			unreferenced_blocks: s.map(|s| s.unreferenced_blocks),
+			unreferenced_blocks_bytes: s.map(|s| s.unreferenced_blocks_bytes),
+			aborted_uploads: s.map(|s| s.aborted_uploads),
+			stale_uploads: s.map(|s| s.stale_uploads),
+			stale_uploads_bytes: s.map(|s| s.stale_uploads_bytes),
+			tombstones: s
+				.map(|s| {
+					s.tombstones
+						.iter()
+						.map(|t| TableTombstones {
+							table: t.table.clone(),
+							count: t.count,
+							bytes: t.bytes,
+						})
+						.collect()
+				})
+				.unwrap_or_default(),
+			error: stats.as_ref().err().cloned(),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_staged_role_changes_roundtrip() {
+		let layout = r#"{
+			"version": 3,
+			"roles": [],
+			"stagedRoleChanges": [
+				{ "id": "aa", "remove": true },
+				{ "id": "bb", "zone": "dc1", "capacity": 1000, "tags": ["x"] }
+			]
+		}"#;
+		let layout: ClusterLayout = serde_json::from_str(layout).unwrap();
+		assert!(matches!(
+			layout.staged_role_changes[0].action,
+			RoleChangeAction::Remove { remove: true }
+		));
+		assert!(matches!(
+			&layout.staged_role_changes[1].action,
+			RoleChangeAction::Update { zone, capacity: Some(1000), .. } if zone == "dc1"
+		));
+
+		let out = serde_json::to_value(&layout).unwrap();
+		assert_eq!(out["stagedRoleChanges"][0]["remove"], true);
+		assert_eq!(out["stagedRoleChanges"][1]["tags"][0], "x");
+	}
+}
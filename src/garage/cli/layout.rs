@@ -1,19 +1,25 @@
+use std::collections::HashMap;
+use std::io::Write;
+
 use bytesize::ByteSize;
 
 use format_table::format_table;
 use garage_util::crdt::Crdt;
+use garage_util::data::*;
 use garage_util::error::*;
 
 use garage_rpc::layout::*;
 use garage_rpc::system::*;
 use garage_rpc::*;
 
+use crate::cli::json::{self, print_json};
 use crate::cli::*;
 
 pub async fn cli_layout_command_dispatch(
 	cmd: LayoutOperation,
 	system_rpc_endpoint: &Endpoint<SystemRpc, ()>,
 	rpc_host: NodeID,
+	json: bool,
 ) -> Result<(), Error> {
 	match cmd {
 		LayoutOperation::Assign(assign_opt) => {
@@ -22,7 +28,16 @@ pub async fn cli_layout_command_dispatch(
 		LayoutOperation::Remove(remove_opt) => {
 			cmd_remove_role(system_rpc_endpoint, rpc_host, remove_opt).await
 		}
-		LayoutOperation::Show => cmd_show_layout(system_rpc_endpoint, rpc_host).await,
+		LayoutOperation::Drain(drain_opt) => {
+			cmd_drain_node(system_rpc_endpoint, rpc_host, drain_opt).await
+		}
+		LayoutOperation::Suggest(suggest_opt) => {
+			cmd_suggest_layout(system_rpc_endpoint, rpc_host, suggest_opt).await
+		}
+		LayoutOperation::Show => cmd_show_layout(system_rpc_endpoint, rpc_host, json).await,
+		LayoutOperation::Plan(plan_opt) => {
+			cmd_plan_layout(system_rpc_endpoint, rpc_host, plan_opt).await
+		}
 		LayoutOperation::Apply(apply_opt) => {
 			cmd_apply_layout(system_rpc_endpoint, rpc_host, apply_opt).await
 		}
@@ -155,6 +170,33 @@ pub async fn cmd_remove_role(
 	let deleted_node =
 		find_matching_node(roles.items().iter().map(|(id, _, _)| *id), &args.node_id)?;
 
+	if !args.force {
+		let status = fetch_known_nodes(rpc_cli, rpc_host).await?;
+		let node_status = status
+			.iter()
+			.find(|adv| adv.id == deleted_node && adv.is_up);
+		if let Some(adv) = node_status {
+			match adv.status.drain {
+				Some(drain) if !drain.is_complete() => {
+					return Err(Error::Message(format!(
+						"Node {:?} is still being drained ({:.1}% done, {} partitions and {} blocks remaining). Wait for the drain to complete, or use --force to remove it anyway.",
+						deleted_node,
+						drain.progress(),
+						drain.partitions_remaining,
+						drain.blocks_remaining
+					)));
+				}
+				_ if matches!(layout.node_role(&deleted_node), Some(r) if r.capacity.is_some()) => {
+					return Err(Error::Message(format!(
+						"Node {:?} stores data. Use `garage layout drain` to move its data to other nodes before removing it, or use --force to remove it anyway.",
+						deleted_node
+					)));
+				}
+				_ => (),
+			}
+		}
+	}
+
 	layout
 		.staging_roles
 		.merge(&roles.update_mutator(deleted_node, NodeRoleV(None)));
@@ -167,12 +209,131 @@ pub async fn cmd_remove_role(
 	Ok(())
 }
 
+pub async fn cmd_drain_node(
+	rpc_cli: &Endpoint<SystemRpc, ()>,
+	rpc_host: NodeID,
+	args: DrainNodeOpt,
+) -> Result<(), Error> {
+	let mut layout = fetch_layout(rpc_cli, rpc_host).await?;
+
+	let mut roles = layout.roles.clone();
+	roles.merge(&layout.staging_roles);
+
+	let drained_node =
+		find_matching_node(roles.items().iter().map(|(id, _, _)| *id), &args.node_id)?;
+
+	let role = match roles.get(&drained_node) {
+		Some(NodeRoleV(Some(role))) if role.capacity.is_some() => role,
+		_ => {
+			return Err(Error::Message(format!(
+				"Node {:?} is not a storage node in the planned layout, it cannot be drained",
+				drained_node
+			)));
+		}
+	};
+	let new_role = NodeRole {
+		capacity: None,
+		..role.clone()
+	};
+
+	layout
+		.staging_roles
+		.merge(&roles.update_mutator(drained_node, NodeRoleV(Some(new_role))));
+
+	send_layout(rpc_cli, rpc_host, layout).await?;
+
+	println!("The node will become a gateway node without storage capacity,");
+	println!(
+		"so that its data is moved to other nodes. This change is staged but not yet commited."
+	);
+	println!("Use `garage layout show` to view staged role changes,");
+	println!("and `garage layout apply` to enact staged changes.");
+	println!();
+	println!(
+		"Once the change is applied, use `garage status` to follow the progress of the drain,"
+	);
+	println!("and `garage layout remove` to remove the node once it is complete.");
+	Ok(())
+}
+
+pub async fn cmd_suggest_layout(
+	rpc_cli: &Endpoint<SystemRpc, ()>,
+	rpc_host: NodeID,
+	suggest_opt: SuggestLayoutOpt,
+) -> Result<(), Error> {
+	let suggestion = match rpc_cli
+		.call(&rpc_host, SystemRpc::GetRebalanceSuggestion, PRIO_NORMAL)
+		.await??
+	{
+		SystemRpc::ReturnRebalanceSuggestion(Some(s)) => s,
+		SystemRpc::ReturnRebalanceSuggestion(None) => {
+			println!("Data disk usage is known for less than two storage nodes, no suggestion can be made.");
+			return Ok(());
+		}
+		resp => return Err(Error::Message(format!("Invalid RPC response: {:?}", resp))),
+	};
+
+	println!(
+		"Average data disk usage: {:.1}%",
+		suggestion.average_usage * 100.
+	);
+	println!(
+		"Imbalance: {:.1}% (threshold: {:.1}%)",
+		suggestion.imbalance * 100.,
+		suggestion.threshold * 100.
+	);
+	println!();
+
+	if suggestion.changes.is_empty() {
+		println!("Data disk usage is balanced between storage nodes, no change is suggested.");
+		return Ok(());
+	}
+
+	let mut table = vec!["ID\tDisk usage\tCapacity\tSuggested capacity".to_string()];
+	for c in suggestion.changes.iter() {
+		table.push(format!(
+			"{:?}\t{:.1}%\t{}\t{}",
+			c.node,
+			c.usage * 100.,
+			ByteSize::b(c.capacity).to_string_as(false),
+			ByteSize::b(c.new_capacity).to_string_as(false),
+		));
+	}
+	format_table(table);
+	println!();
+
+	if suggest_opt.stage {
+		let mut layout = fetch_layout(rpc_cli, rpc_host).await?;
+		suggestion.stage(&mut layout);
+		send_layout(rpc_cli, rpc_host, layout).await?;
+
+		println!("Suggested capacity changes have been staged.");
+		println!("Use `garage layout show` to view staged role changes,");
+		println!("and `garage layout apply` to enact staged changes.");
+	} else {
+		println!("To stage these changes, run `garage layout suggest --stage`, or:");
+		println!();
+		for c in suggestion.changes.iter() {
+			println!(
+				"    garage layout assign -c {}B {:?}",
+				c.new_capacity, c.node
+			);
+		}
+	}
+	Ok(())
+}
+
 pub async fn cmd_show_layout(
 	rpc_cli: &Endpoint<SystemRpc, ()>,
 	rpc_host: NodeID,
+	json: bool,
 ) -> Result<(), Error> {
 	let layout = fetch_layout(rpc_cli, rpc_host).await?;
 
+	if json {
+		return print_json(&json::ClusterLayout::new(&layout));
+	}
+
 	println!("==== CURRENT CLUSTER LAYOUT ====");
 	print_cluster_layout(&layout, "No nodes currently have a role in the cluster.\nSee `garage status` to view available nodes.");
 	println!();
@@ -216,6 +377,203 @@ pub async fn cmd_show_layout(
 	Ok(())
 }
 
+pub async fn cmd_plan_layout(
+	rpc_cli: &Endpoint<SystemRpc, ()>,
+	rpc_host: NodeID,
+	plan_opt: PlanLayoutOpt,
+) -> Result<(), Error> {
+	let layout = fetch_layout(rpc_cli, rpc_host).await?;
+	let nodes = match rpc_cli
+		.call(&rpc_host, SystemRpc::GetKnownNodes, PRIO_NORMAL)
+		.await??
+	{
+		SystemRpc::ReturnKnownNodes(nodes) => nodes,
+		resp => return Err(Error::Message(format!("Invalid RPC response: {:?}", resp))),
+	};
+	let node_info = nodes.iter().map(|n| (n.id, n)).collect::<HashMap<_, _>>();
+
+	if !print_staging_role_changes(&layout) {
+		println!("No changes are staged in the cluster layout.");
+		return Ok(());
+	}
+
+	let version = layout.version + 1;
+	let (new_layout, _) = layout.clone().apply_staged_changes(Some(version))?;
+
+	let partition_count = |l: &ClusterLayout| {
+		let mut ret = HashMap::<Uuid, usize>::new();
+		for nodes in l.partition_nodes() {
+			for node in nodes {
+				*ret.entry(node).or_default() += 1;
+			}
+		}
+		ret
+	};
+	let old_parts = partition_count(&layout);
+	let new_parts = partition_count(&new_layout);
+	let incoming = layout.incoming_partitions(&new_layout);
+
+	// The size of a partition is estimated from the data disk usage of the
+	// storage nodes, which is only approximate if data disks are shared
+	let sizes = old_parts
+		.iter()
+		.filter_map(|(id, parts)| {
+			let (avail, total) = node_info.get(id)?.status.data_disk_avail?;
+			Some((total - avail) / *parts as u64)
+		})
+		.collect::<Vec<_>>();
+	let partition_size = match sizes.len() {
+		0 => None,
+		n => Some(sizes.iter().sum::<u64>() / n as u64),
+	};
+
+	// Without a bandwidth given on the command line, nodes that have not
+	// measured theirs are assumed to receive data at the median rate
+	let mut measured = nodes
+		.iter()
+		.filter_map(|n| n.status.resync_bandwidth)
+		.collect::<Vec<_>>();
+	measured.sort_unstable();
+	let median_bandwidth = measured.get(measured.len() / 2).copied();
+	let bandwidth = |id: &Uuid| match plan_opt.bandwidth {
+		Some(b) => Some(b.as_u64()),
+		None => node_info
+			.get(id)
+			.and_then(|n| n.status.resync_bandwidth)
+			.or(median_bandwidth),
+	};
+
+	let mut ids = old_parts
+		.keys()
+		.chain(new_parts.keys())
+		.copied()
+		.collect::<Vec<_>>();
+	ids.sort();
+	ids.dedup();
+
+	let zone = |id: &Uuid| {
+		new_layout
+			.node_role(id)
+			.or_else(|| layout.node_role(id))
+			.map(|r| r.zone.clone())
+			.unwrap_or_else(|| "?".into())
+	};
+	let bytes_str = |parts: usize| match partition_size {
+		Some(s) => ByteSize::b(parts as u64 * s).to_string_as(false),
+		None => "?".into(),
+	};
+
+	println!("==== PARTITIONS PER NODE ====");
+	let mut table =
+		vec!["ID\tHostname\tZone\tPartitions\tReceived\tData moved\tResync time".to_string()];
+	let mut max_secs = Some(0);
+	for id in ids.iter() {
+		let received = incoming.get(id).copied().unwrap_or(0);
+		let secs = match (partition_size, bandwidth(id)) {
+			_ if received == 0 => Some(0),
+			(Some(s), Some(b)) if b > 0 => Some(received as u64 * s / b),
+			_ => None,
+		};
+		max_secs = match (max_secs, secs) {
+			(Some(m), Some(s)) => Some(std::cmp::max(m, s)),
+			_ => None,
+		};
+		table.push(format!(
+			"{:?}\t{}\t{}\t{} -> {}\t{}\t{}\t{}",
+			id,
+			node_info
+				.get(id)
+				.map(|n| n.status.hostname.as_str())
+				.unwrap_or("?"),
+			zone(id),
+			old_parts.get(id).copied().unwrap_or(0),
+			new_parts.get(id).copied().unwrap_or(0),
+			received,
+			bytes_str(received),
+			secs.map(duration_string).unwrap_or_else(|| "?".into()),
+		));
+	}
+	format_table(table);
+
+	println!();
+	println!("==== PARTITIONS PER ZONE ====");
+	let mut zones = ids.iter().map(zone).collect::<Vec<_>>();
+	zones.sort();
+	zones.dedup();
+	let mut table = vec!["Zone\tPartitions\tReceived\tData moved".to_string()];
+	for z in zones.iter() {
+		let sum = |counts: &HashMap<Uuid, usize>| {
+			ids.iter()
+				.filter(|id| zone(id) == *z)
+				.map(|id| counts.get(id).copied().unwrap_or(0))
+				.sum::<usize>()
+		};
+		table.push(format!(
+			"{}\t{} -> {}\t{}\t{}",
+			z,
+			sum(&old_parts),
+			sum(&new_parts),
+			sum(&incoming),
+			bytes_str(sum(&incoming)),
+		));
+	}
+	format_table(table);
+
+	let total_received = incoming.values().sum::<usize>();
+	println!();
+	println!(
+		"Partition copies to transfer: {} (estimated {})",
+		total_received,
+		bytes_str(total_received)
+	);
+	match max_secs {
+		Some(s) => println!("Estimated resync time: {}", duration_string(s)),
+		None if partition_size.is_none() => println!(
+			"The data disk usage of storage nodes is not known, the resync time cannot be estimated."
+		),
+		None => println!(
+			"The resync bandwidth of some nodes is not known, use --bandwidth to estimate the resync time."
+		),
+	}
+	println!();
+
+	if !plan_opt.interactive {
+		println!("To enact the staged role changes, type:");
+		println!();
+		println!("    garage layout apply --version {}", version);
+		println!();
+		return Ok(());
+	}
+
+	print!(
+		"Apply the staged changes as layout version {}? [y/N] ",
+		version
+	);
+	std::io::stdout().flush()?;
+	let mut answer = String::new();
+	std::io::stdin().read_line(&mut answer)?;
+	if !answer.trim().eq_ignore_ascii_case("y") {
+		println!("The staged changes were not applied.");
+		return Ok(());
+	}
+
+	// The plan is only valid if nothing changed while the operator reviewed it
+	let current = fetch_layout(rpc_cli, rpc_host).await?;
+	if current.version != layout.version || current.staging_hash != layout.staging_hash {
+		return Err(Error::Message(
+			"The cluster layout or the staged changes were modified in the meantime, run `garage layout plan` again".into(),
+		));
+	}
+	cmd_apply_layout(
+		rpc_cli,
+		rpc_host,
+		ApplyLayoutOpt {
+			version: Some(version),
+		},
+	)
+	.await
+}
+
 pub async fn cmd_apply_layout(
 	rpc_cli: &Endpoint<SystemRpc, ()>,
 	rpc_host: NodeID,
@@ -312,6 +670,19 @@ pub async fn fetch_layout(
 	}
 }
 
+pub async fn fetch_known_nodes(
+	rpc_cli: &Endpoint<SystemRpc, ()>,
+	rpc_host: NodeID,
+) -> Result<Vec<KnownNodeInfo>, Error> {
+	match rpc_cli
+		.call(&rpc_host, SystemRpc::GetKnownNodes, PRIO_NORMAL)
+		.await??
+	{
+		SystemRpc::ReturnKnownNodes(nodes) => Ok(nodes),
+		resp => Err(Error::Message(format!("Invalid RPC response: {:?}", resp))),
+	}
+}
+
 pub async fn send_layout(
 	rpc_cli: &Endpoint<SystemRpc, ()>,
 	rpc_host: NodeID,
@@ -366,6 +737,7 @@ pub fn print_cluster_layout(layout: &ClusterLayout, empty_msg: &str) {
 	}
 }
 
+/// Format a duration in seconds as e.g. `2h 05m`
 pub fn print_staging_role_changes(layout: &ClusterLayout) -> bool {
 	let has_role_changes = layout
 		.staging_roles
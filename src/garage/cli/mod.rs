@@ -1,6 +1,10 @@
 pub(crate) mod cmd;
 pub(crate) mod init;
+pub(crate) mod json;
 pub(crate) mod layout;
+#[cfg(feature = "fuse")]
+pub(crate) mod mount;
+pub(crate) mod remote;
 pub(crate) mod structs;
 pub(crate) mod util;
 
@@ -9,5 +13,6 @@ pub(crate) mod convert_db;
 pub(crate) use cmd::*;
 pub(crate) use init::*;
 pub(crate) use layout::*;
+pub(crate) use remote::*;
 pub(crate) use structs::*;
 pub(crate) use util::*;
@@ -0,0 +1,334 @@
+//! `garage mount`: a bucket exposed as a read-only FUSE filesystem, in which
+//! object keys are split on `/` to form directories. Listings and reads are
+//! requests to the node the command is connected to, which reads the data of
+//! the objects with its block manager.
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use fuser::{
+	FileAttr, FileType, Filesystem, MountOption, ReplyAttr, ReplyData, ReplyDirectory, ReplyEntry,
+	Request, FUSE_ROOT_ID,
+};
+
+use garage_util::data::*;
+use garage_util::error::*;
+
+use garage_rpc::*;
+
+use crate::admin::*;
+use crate::cli::structs::MountOpt;
+
+/// Time for which attributes and directory listings are cached
+const TTL: Duration = Duration::from_secs(10);
+
+/// Size of the chunks in which files are read from the node
+const READ_CHUNK: u64 = 1024 * 1024;
+
+pub async fn cmd_mount(
+	rpc_cli: Arc<Endpoint<AdminRpc, ()>>,
+	rpc_host: NodeID,
+	opt: MountOpt,
+) -> Result<(), Error> {
+	let bucket = match fs_request(&rpc_cli, rpc_host, FsRequest::Bucket(opt.bucket.clone())).await?
+	{
+		AdminRpc::FsBucket(id) => id,
+		resp => return Err(Error::Message(format!("Invalid RPC response: {:?}", resp))),
+	};
+
+	let fs = BucketFs {
+		rpc_cli,
+		rpc_host,
+		bucket,
+		runtime: tokio::runtime::Handle::current(),
+		inodes: vec![Inode {
+			path: "".into(),
+			parent: FUSE_ROOT_ID,
+			file: None,
+			children: None,
+		}],
+		by_path: HashMap::new(),
+		uid: unsafe { libc::getuid() },
+		gid: unsafe { libc::getgid() },
+		mounted: SystemTime::now(),
+		cache: None,
+	};
+
+	let mut options = vec![
+		MountOption::RO,
+		MountOption::FSName(format!("garage:{}", opt.bucket)),
+		MountOption::Subtype("garage".into()),
+		MountOption::DefaultPermissions,
+	];
+	if opt.allow_other {
+		options.push(MountOption::AllowOther);
+	}
+	let session = fuser::spawn_mount2(fs, &opt.path, &options)
+		.map_err(|e| Error::Message(format!("Unable to mount {}: {}", opt.path.display(), e)))?;
+
+	println!(
+		"Bucket {} mounted read-only on {}, press Ctrl-C to unmount it.",
+		opt.bucket,
+		opt.path.display()
+	);
+	tokio::signal::ctrl_c().await?;
+
+	// Dropping the session unmounts the filesystem
+	drop(session);
+	Ok(())
+}
+
+async fn fs_request(
+	rpc_cli: &Endpoint<AdminRpc, ()>,
+	rpc_host: NodeID,
+	req: FsRequest,
+) -> Result<AdminRpc, Error> {
+	rpc_cli
+		.call(&rpc_host, AdminRpc::Fs(req), PRIO_NORMAL)
+		.await?
+		.map_err(|e| Error::Message(e.to_string()))
+}
+
+struct Inode {
+	/// Key of the object for a file, prefix ending with `/` for a directory
+	/// (empty for the root)
+	path: String,
+	parent: u64,
+	file: Option<FsFile>,
+	/// Names and inode numbers of the entries of a directory, and the time
+	/// at which they were listed
+	children: Option<(Instant, Vec<(String, u64)>)>,
+}
+
+struct BucketFs {
+	rpc_cli: Arc<Endpoint<AdminRpc, ()>>,
+	rpc_host: NodeID,
+	bucket: Uuid,
+	/// Runtime on which the requests to the node are run, the methods of
+	/// `Filesystem` being called from the thread of the FUSE session
+	runtime: tokio::runtime::Handle,
+	/// Inode number N is stored at index N - 1
+	inodes: Vec<Inode>,
+	by_path: HashMap<String, u64>,
+	uid: u32,
+	gid: u32,
+	mounted: SystemTime,
+	/// Last chunk read: version of the object, offset and data
+	cache: Option<(Uuid, u64, Vec<u8>)>,
+}
+
+impl BucketFs {
+	fn request(&self, req: FsRequest) -> Result<AdminRpc, Error> {
+		self.runtime
+			.block_on(fs_request(&self.rpc_cli, self.rpc_host, req))
+	}
+
+	fn inode(&self, ino: u64) -> Option<&Inode> {
+		self.inodes.get(ino.checked_sub(1)? as usize)
+	}
+
+	/// List a directory, or return its cached listing if it is recent enough
+	fn list(&mut self, ino: u64) -> Result<Vec<(String, u64)>, libc::c_int> {
+		let dir = self.inode(ino).ok_or(libc::ENOENT)?;
+		if dir.file.is_some() {
+			return Err(libc::ENOTDIR);
+		}
+		if let Some((listed, children)) = &dir.children {
+			if listed.elapsed() < TTL {
+				return Ok(children.clone());
+			}
+		}
+
+		let prefix = dir.path.clone();
+		let entries = match self.request(FsRequest::List {
+			bucket: self.bucket,
+			prefix: prefix.clone(),
+		}) {
+			Ok(AdminRpc::FsDir(entries)) => entries,
+			Ok(resp) => {
+				warn!("Invalid RPC response: {:?}", resp);
+				return Err(libc::EIO);
+			}
+			Err(e) => {
+				warn!("Unable to list {:?}: {}", prefix, e);
+				return Err(libc::EIO);
+			}
+		};
+
+		let mut children = vec![];
+		for entry in entries {
+			let path = match entry.file {
+				Some(_) => format!("{}{}", prefix, entry.name),
+				None => format!("{}{}/", prefix, entry.name),
+			};
+			let child = match self.by_path.get(&path) {
+				Some(child) => *child,
+				None => {
+					self.inodes.push(Inode {
+						path: path.clone(),
+						parent: ino,
+						file: None,
+						children: None,
+					});
+					let child = self.inodes.len() as u64;
+					self.by_path.insert(path, child);
+					child
+				}
+			};
+			self.inodes[child as usize - 1].file = entry.file;
+			children.push((entry.name, child));
+		}
+
+		self.inodes[ino as usize - 1].children = Some((Instant::now(), children.clone()));
+		Ok(children)
+	}
+
+	fn attr(&self, ino: u64, inode: &Inode) -> FileAttr {
+		let (kind, size, mtime) = match &inode.file {
+			Some(f) => (
+				FileType::RegularFile,
+				f.size,
+				UNIX_EPOCH + Duration::from_millis(f.timestamp),
+			),
+			None => (FileType::Directory, 0, self.mounted),
+		};
+		FileAttr {
+			ino,
+			size,
+			blocks: (size + 511) / 512,
+			atime: mtime,
+			mtime,
+			ctime: mtime,
+			crtime: mtime,
+			kind,
+			perm: if kind == FileType::Directory {
+				0o555
+			} else {
+				0o444
+			},
+			nlink: if kind == FileType::Directory { 2 } else { 1 },
+			uid: self.uid,
+			gid: self.gid,
+			rdev: 0,
+			blksize: READ_CHUNK as u32,
+			flags: 0,
+		}
+	}
+
+	/// Read a range of a file, from the last chunk read if it contains it
+	fn read_range(&mut self, ino: u64, offset: u64, len: u64) -> Result<&[u8], libc::c_int> {
+		let inode = self.inode(ino).ok_or(libc::ENOENT)?;
+		let file = inode.file.ok_or(libc::EISDIR)?;
+		let end = std::cmp::min(offset.saturating_add(len), file.size);
+		if offset >= end {
+			return Ok(&[]);
+		}
+
+		let cached = matches!(&self.cache, Some((version, start, data))
+			if *version == file.version && *start <= offset && end <= start + data.len() as u64);
+		if !cached {
+			let start = offset - offset % READ_CHUNK;
+			let chunk_end = ((end + READ_CHUNK - 1) / READ_CHUNK) * READ_CHUNK;
+			let key = inode.path.clone();
+			match self.request(FsRequest::Read {
+				bucket: self.bucket,
+				key: key.clone(),
+				version: file.version,
+				offset: start,
+				len: chunk_end - start,
+			}) {
+				Ok(AdminRpc::FsData(data)) => self.cache = Some((file.version, start, data)),
+				Ok(resp) => {
+					warn!("Invalid RPC response: {:?}", resp);
+					return Err(libc::EIO);
+				}
+				Err(e) => {
+					warn!("Unable to read {:?}: {}", key, e);
+					return Err(libc::EIO);
+				}
+			}
+		}
+
+		let (_, start, data) = self.cache.as_ref().unwrap();
+		let to = std::cmp::min((end - start) as usize, data.len());
+		let from = std::cmp::min((offset - start) as usize, to);
+		Ok(&data[from..to])
+	}
+}
+
+impl Filesystem for BucketFs {
+	fn lookup(&mut self, _req: &Request<'_>, parent: u64, name: &OsStr, reply: ReplyEntry) {
+		let children = match self.list(parent) {
+			Ok(c) => c,
+			Err(e) => return reply.error(e),
+		};
+		match children
+			.into_iter()
+			.find(|(n, _)| name.to_str() == Some(n.as_str()))
+		{
+			Some((_, ino)) => {
+				let attr = self.attr(ino, &self.inodes[ino as usize - 1]);
+				reply.entry(&TTL, &attr, 0);
+			}
+			None => reply.error(libc::ENOENT),
+		}
+	}
+
+	fn getattr(&mut self, _req: &Request<'_>, ino: u64, reply: ReplyAttr) {
+		match self.inode(ino) {
+			Some(inode) => reply.attr(&TTL, &self.attr(ino, inode)),
+			None => reply.error(libc::ENOENT),
+		}
+	}
+
+	fn readdir(
+		&mut self,
+		_req: &Request<'_>,
+		ino: u64,
+		_fh: u64,
+		offset: i64,
+		mut reply: ReplyDirectory,
+	) {
+		let children = match self.list(ino) {
+			Ok(c) => c,
+			Err(e) => return reply.error(e),
+		};
+		let parent = self.inodes[ino as usize - 1].parent;
+
+		let entries = vec![(".".to_string(), ino), ("..".to_string(), parent)]
+			.into_iter()
+			.chain(children);
+		for (i, (name, child)) in entries.enumerate().skip(offset as usize) {
+			let kind = match self.inodes[child as usize - 1].file {
+				Some(_) => FileType::RegularFile,
+				None => FileType::Directory,
+			};
+			// the offset given is that of the next entry
+			if reply.add(child, (i + 1) as i64, kind, name) {
+				break;
+			}
+		}
+		reply.ok();
+	}
+
+	fn read(
+		&mut self,
+		_req: &Request<'_>,
+		ino: u64,
+		_fh: u64,
+		offset: i64,
+		size: u32,
+		_flags: i32,
+		_lock_owner: Option<u64>,
+		reply: ReplyData,
+	) {
+		if offset < 0 {
+			return reply.error(libc::EINVAL);
+		}
+		match self.read_range(ino, offset as u64, size as u64) {
+			Ok(data) => reply.data(data),
+			Err(e) => reply.error(e),
+		}
+	}
+}
@@ -0,0 +1,745 @@
+//! Remote mode of the CLI: commands are run through the admin API of a node,
+//! given by `--endpoint`, and authenticated with an admin token instead of
+//! the RPC secret. Only the commands that have an equivalent in the admin API
+//! are available in this mode.
+use hyper::client::{Client as HttpClient, HttpConnector};
+use hyper::{Body, Method, Request, StatusCode};
+use hyper_rustls::HttpsConnector;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use format_table::format_table;
+
+use garage_util::error::*;
+use garage_util::time::*;
+
+use crate::cli::json::*;
+use crate::cli::*;
+
+pub struct AdminApiClient {
+	endpoint: String,
+	token: String,
+	client: HttpClient<HttpsConnector<HttpConnector>>,
+}
+
+impl AdminApiClient {
+	pub fn new(endpoint: String, token: String) -> Self {
+		let connector = hyper_rustls::HttpsConnectorBuilder::new()
+			.with_native_roots()
+			.https_or_http()
+			.enable_http1()
+			.build();
+		Self {
+			endpoint: endpoint.trim_end_matches('/').to_string(),
+			token,
+			client: HttpClient::builder().build(connector),
+		}
+	}
+
+	async fn call<T: DeserializeOwned>(
+		&self,
+		method: Method,
+		path: &str,
+		body: Option<Value>,
+	) -> Result<T, Error> {
+		let body = self.request(method, path, body).await?;
+		serde_json::from_slice(&body)
+			.map_err(|e| Error::Message(format!("Invalid response of the admin API: {}", e)))
+	}
+
+	async fn request(
+		&self,
+		method: Method,
+		path: &str,
+		body: Option<Value>,
+	) -> Result<Vec<u8>, Error> {
+		let (_, body) = self.send(method, path, body).await?;
+		Ok(body)
+	}
+
+	/// Delete a bucket or a key. If it is protected against deletion, the
+	/// deletion is only registered (status 202) and must be confirmed with
+	/// another admin token: the pending deletion is returned in that case.
+	async fn delete(&self, path: &str) -> Result<Option<PendingDeletion>, Error> {
+		let (status, body) = self.send(Method::DELETE, path, None).await?;
+		if status != StatusCode::ACCEPTED {
+			return Ok(None);
+		}
+		serde_json::from_slice(&body)
+			.map(Some)
+			.map_err(|e| Error::Message(format!("Invalid response of the admin API: {}", e)))
+	}
+
+	async fn send(
+		&self,
+		method: Method,
+		path: &str,
+		body: Option<Value>,
+	) -> Result<(StatusCode, Vec<u8>), Error> {
+		let body = match body {
+			Some(b) => Body::from(b.to_string()),
+			None => Body::empty(),
+		};
+		let mut req = Request::builder()
+			.method(method)
+			.uri(format!("{}{}", self.endpoint, path))
+			.body(body)
+			.ok_or_message("Invalid admin API endpoint")?;
+		garage_sigv4::authorize_admin(&mut req, &self.token)
+			.ok_or_message("Invalid admin token")?;
+
+		let resp = self
+			.client
+			.request(req)
+			.await
+			.err_context("Unable to connect to the admin API")?;
+		let status = resp.status();
+		let body = hyper::body::to_bytes(resp.into_body())
+			.await
+			.err_context("Unable to read the response of the admin API")?;
+
+		if !status.is_success() {
+			#[derive(Deserialize)]
+			struct ApiError {
+				message: String,
+			}
+			let message = serde_json::from_slice::<ApiError>(&body)
+				.map(|e| e.message)
+				.unwrap_or_else(|_| String::from_utf8_lossy(&body).into_owned());
+			return Err(Error::Message(match status {
+				StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+					format!("Access to the admin API was denied: {}", message)
+				}
+				_ => message,
+			}));
+		}
+		Ok((status, body.to_vec()))
+	}
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PendingDeletion {
+	requested: String,
+	requested_by: String,
+	expires: String,
+}
+
+impl PendingDeletion {
+	fn print(&self, what: &str) {
+		println!(
+			"Deletion of {} was requested by {} at {}.",
+			what, self.requested_by, self.requested
+		);
+		println!(
+			"It is protected against deletion: run the same command with another admin token before {} to delete it.",
+			self.expires
+		);
+	}
+}
+
+pub async fn cli_remote_command_dispatch(
+	cmd: Command,
+	api: &AdminApiClient,
+	json: bool,
+) -> Result<(), Error> {
+	match cmd {
+		Command::Status => cmd_remote_status(api, json).await,
+		Command::Layout(LayoutOperation::Show) => cmd_remote_show_layout(api, json).await,
+		Command::Layout(LayoutOperation::Assign(opt)) => cmd_remote_assign_role(api, opt).await,
+		Command::Layout(LayoutOperation::Apply(opt)) => {
+			let version = layout_version(api, opt.version).await?;
+			let res: ApplyLayoutResponse = api
+				.call(
+					Method::POST,
+					"/v1/layout/apply",
+					Some(json!({ "version": version })),
+				)
+				.await?;
+			for line in res.message.iter() {
+				println!("{}", line);
+			}
+			println!("New cluster layout with updated role assignment has been applied in cluster.");
+			println!("Data will now be moved around between nodes accordingly.");
+			Ok(())
+		}
+		Command::Layout(LayoutOperation::Revert(opt)) => {
+			let version = layout_version(api, opt.version).await?;
+			api.request(
+				Method::POST,
+				"/v1/layout/revert",
+				Some(json!({ "version": version })),
+			)
+			.await?;
+			println!("All proposed role changes in cluster layout have been canceled.");
+			Ok(())
+		}
+		Command::Bucket(bo) => cmd_remote_bucket(api, bo, json).await,
+		Command::Key(ko) => cmd_remote_key(api, ko, json).await,
+		_ => Err(Error::Message(
+			"This command is not available through the admin API, run it without --endpoint from a node of the cluster".into(),
+		)),
+	}
+}
+
+// ---- cluster ----
+
+#[derive(Deserialize)]
+struct ApplyLayoutResponse {
+	message: Vec<String>,
+}
+
+fn capacity_string(capacity: Option<u64>) -> String {
+	match capacity {
+		Some(c) => bytesize::ByteSize::b(c).to_string_as(false),
+		None => "gateway".to_string(),
+	}
+}
+
+async fn cmd_remote_status(api: &AdminApiClient, json: bool) -> Result<(), Error> {
+	let status: ClusterStatus = api.call(Method::GET, "/v1/status", None).await?;
+	if json {
+		return print_json(&status);
+	}
+
+	println!("==== NODES ====");
+	let mut table = vec!["ID\tHostname\tAddress\tTags\tZone\tCapacity\tStatus".to_string()];
+	for node in status.known_nodes.iter() {
+		let state = match (node.is_up, node.last_seen_secs_ago) {
+			(true, _) => "up".to_string(),
+			(false, Some(s)) => format!("down (last seen {}s ago)", s),
+			(false, None) => "down (never seen)".to_string(),
+		};
+		match status.layout.roles.iter().find(|r| r.id == node.id) {
+			Some(role) => table.push(format!(
+				"{}\t{}\t{}\t[{}]\t{}\t{}\t{}",
+				&node.id[..16],
+				node.hostname,
+				node.addr,
+				role.tags.join(","),
+				role.zone,
+				capacity_string(role.capacity),
+				state,
+			)),
+			None => table.push(format!(
+				"{}\t{}\t{}\t\tNO ROLE ASSIGNED\t\t{}",
+				&node.id[..16],
+				node.hostname,
+				node.addr,
+				state,
+			)),
+		}
+	}
+	format_table(table);
+	Ok(())
+}
+
+async fn cmd_remote_show_layout(api: &AdminApiClient, json: bool) -> Result<(), Error> {
+	let layout: ClusterLayout = api.call(Method::GET, "/v1/layout", None).await?;
+	if json {
+		return print_json(&layout);
+	}
+
+	println!("==== CURRENT CLUSTER LAYOUT ====");
+	let mut table = vec!["ID\tTags\tZone\tCapacity".to_string()];
+	for role in layout.roles.iter() {
+		table.push(format!(
+			"{}\t[{}]\t{}\t{}",
+			&role.id[..16],
+			role.tags.join(","),
+			role.zone,
+			capacity_string(role.capacity),
+		));
+	}
+	format_table(table);
+	println!();
+	println!("Current cluster layout version: {}", layout.version);
+
+	if !layout.staged_role_changes.is_empty() {
+		println!();
+		println!("==== STAGED ROLE CHANGES ====");
+		let mut table = vec!["ID\tTags\tZone\tCapacity".to_string()];
+		for change in layout.staged_role_changes.iter() {
+			match &change.action {
+				RoleChangeAction::Remove { .. } => {
+					table.push(format!("{}\tREMOVED", &change.id[..16]));
+				}
+				RoleChangeAction::Update {
+					zone,
+					capacity,
+					tags,
+				} => table.push(format!(
+					"{}\t[{}]\t{}\t{}",
+					&change.id[..16],
+					tags.join(","),
+					zone,
+					capacity_string(*capacity),
+				)),
+			}
+		}
+		format_table(table);
+		println!();
+		println!("To enact the staged role changes, type:");
+		println!();
+		println!("    garage layout apply --version {}", layout.version + 1);
+	}
+	Ok(())
+}
+
+async fn cmd_remote_assign_role(api: &AdminApiClient, opt: AssignRoleOpt) -> Result<(), Error> {
+	if !opt.replace.is_empty() {
+		return Err(Error::Message(
+			"--replace is not available through the admin API".into(),
+		));
+	}
+	let status: ClusterStatus = api.call(Method::GET, "/v1/status", None).await?;
+
+	let mut changes = vec![];
+	for pattern in opt.node_ids.iter() {
+		let candidates = status
+			.known_nodes
+			.iter()
+			.map(|n| &n.id)
+			.chain(status.layout.roles.iter().map(|r| &r.id))
+			.filter(|id| id.starts_with(pattern.as_str()))
+			.collect::<std::collections::BTreeSet<_>>();
+		let id = match candidates.iter().next() {
+			Some(id) if candidates.len() == 1 => id.to_string(),
+			_ => {
+				return Err(Error::Message(format!(
+					"{} nodes match '{}'",
+					candidates.len(),
+					pattern
+				)))
+			}
+		};
+		let old = status.layout.roles.iter().find(|r| r.id == id);
+
+		let zone = opt
+			.zone
+			.clone()
+			.or_else(|| old.map(|r| r.zone.clone()))
+			.ok_or("Please specify a zone with the -z flag")?;
+		let capacity = if opt.gateway {
+			if opt.capacity.is_some() {
+				return Err(Error::Message(
+					"-c and -g are mutually exclusive, please configure node either with c>0 to act as a storage node or with -g to act as a gateway node".into(),
+				));
+			}
+			None
+		} else if let Some(c) = opt.capacity {
+			Some(c.as_u64())
+		} else {
+			old.and_then(|r| r.capacity).map(Some).ok_or(
+				"Please specify a capacity with the -c flag, or set node explicitly as gateway with -g",
+			)?
+		};
+		let tags = if !opt.tags.is_empty() {
+			opt.tags.clone()
+		} else {
+			old.map(|r| r.tags.clone()).unwrap_or_default()
+		};
+
+		changes.push(json!({
+			"id": id,
+			"zone": zone,
+			"capacity": capacity,
+			"tags": tags,
+		}));
+	}
+
+	api.request(Method::POST, "/v1/layout", Some(Value::Array(changes)))
+		.await?;
+
+	println!("Role changes are staged but not yet committed.");
+	println!("Use `garage layout show` to view staged role changes,");
+	println!("and `garage layout apply` to enact staged changes.");
+	Ok(())
+}
+
+async fn layout_version(api: &AdminApiClient, version: Option<u64>) -> Result<u64, Error> {
+	match version {
+		Some(v) => Ok(v),
+		None => {
+			let layout: ClusterLayout = api.call(Method::GET, "/v1/layout", None).await?;
+			Ok(layout.version + 1)
+		}
+	}
+}
+
+// ---- buckets ----
+
+/// Find a bucket by global alias, or by full hexadecimal ID
+async fn get_bucket(api: &AdminApiClient, name: &str) -> Result<BucketInfo, Error> {
+	let query = if name.len() == 64 && hex::decode(name).is_ok() {
+		format!("id={}", name)
+	} else {
+		format!("globalAlias={}", garage_sigv4::uri_encode(name, true))
+	};
+	api.call(Method::GET, &format!("/v1/bucket?{}", query), None)
+		.await
+}
+
+async fn cmd_remote_bucket(
+	api: &AdminApiClient,
+	cmd: BucketOperation,
+	json: bool,
+) -> Result<(), Error> {
+	match cmd {
+		BucketOperation::List => {
+			let buckets: Vec<BucketListItem> = api.call(Method::GET, "/v1/bucket", None).await?;
+			if json {
+				return print_json(&buckets);
+			}
+			println!("List of buckets:");
+			let mut table = vec![];
+			for bucket in buckets {
+				let local_aliases = match &bucket.local_aliases[..] {
+					[] => "".into(),
+					[a] => format!("{}:{}", a.access_key_id, a.alias),
+					s => format!("[{} local aliases]", s.len()),
+				};
+				table.push(format!(
+					"\t{}\t{}\t{}",
+					bucket.global_aliases.join(","),
+					local_aliases,
+					bucket.id
+				));
+			}
+			format_table(table);
+		}
+		BucketOperation::Info(opt) => {
+			let bucket = get_bucket(api, &opt.name).await?;
+			if json {
+				return print_json(&bucket);
+			}
+			println!("Bucket: {}", bucket.id);
+			println!();
+			println!(
+				"Size: {} ({})",
+				bytesize::ByteSize::b(bucket.bytes as u64).to_string_as(true),
+				bytesize::ByteSize::b(bucket.bytes as u64).to_string_as(false)
+			);
+			println!("Objects: {}", bucket.objects);
+			println!("Unfinished uploads: {}", bucket.unfinished_uploads);
+			println!();
+			println!("Website access: {}", bucket.website_access);
+			println!("Anonymous read access: {}", bucket.public_read);
+			println!();
+			println!("Global aliases:");
+			for alias in bucket.global_aliases.iter() {
+				println!("  {}", alias);
+			}
+			println!();
+			println!("Keys:");
+			let mut table = vec![];
+			for key in bucket.keys.iter() {
+				table.push(format!(
+					"\t{}\t{}\t{}\t{}",
+					key.permissions.flags(),
+					key.access_key_id,
+					key.name,
+					key.bucket_local_aliases.join(",")
+				));
+			}
+			format_table(table);
+		}
+		BucketOperation::Create(opt) => {
+			api.request(
+				Method::POST,
+				"/v1/bucket",
+				Some(json!({ "globalAlias": opt.name })),
+			)
+			.await?;
+			println!("Bucket {} was created.", opt.name);
+		}
+		BucketOperation::Delete(opt) => {
+			let bucket = get_bucket(api, &opt.name).await?;
+			if !opt.yes {
+				println!("About to delete bucket {}.", bucket.id);
+				return Err(Error::Message(
+					"Add --yes flag to really perform this operation".into(),
+				));
+			}
+			match api
+				.delete(&format!("/v1/bucket?id={}", bucket.id))
+				.await?
+			{
+				Some(pending) => pending.print(&format!("bucket {}", opt.name)),
+				None => println!("Bucket {} was deleted.", opt.name),
+			}
+		}
+		BucketOperation::Allow(opt) => cmd_remote_bucket_perm(api, opt, true).await?,
+		BucketOperation::Deny(opt) => cmd_remote_bucket_perm(api, opt, false).await?,
+		BucketOperation::Alias(opt) => {
+			let bucket = get_bucket(api, &opt.existing_bucket).await?;
+			let path = match &opt.local {
+				None => format!(
+					"/v1/bucket/alias/global?id={}&alias={}",
+					bucket.id,
+					garage_sigv4::uri_encode(&opt.new_name, true)
+				),
+				Some(key_pattern) => format!(
+					"/v1/bucket/alias/local?id={}&accessKeyId={}&alias={}",
+					bucket.id,
+					get_key(api, key_pattern).await?.access_key_id,
+					garage_sigv4::uri_encode(&opt.new_name, true)
+				),
+			};
+			api.request(Method::PUT, &path, None).await?;
+			println!("Alias {} now points to bucket {}", opt.new_name, bucket.id);
+		}
+		BucketOperation::Unalias(opt) => {
+			let path = match &opt.local {
+				None => {
+					let bucket = get_bucket(api, &opt.name).await?;
+					format!(
+						"/v1/bucket/alias/global?id={}&alias={}",
+						bucket.id,
+						garage_sigv4::uri_encode(&opt.name, true)
+					)
+				}
+				Some(key_pattern) => {
+					let key = get_key(api, key_pattern).await?;
+					let bucket = key
+						.buckets
+						.iter()
+						.find(|b| b.local_aliases.contains(&opt.name))
+						.ok_or_else(|| {
+							Error::Message(format!(
+								"Key {} has no bucket aliased as {}",
+								key.access_key_id, opt.name
+							))
+						})?;
+					format!(
+						"/v1/bucket/alias/local?id={}&accessKeyId={}&alias={}",
+						bucket.id,
+						key.access_key_id,
+						garage_sigv4::uri_encode(&opt.name, true)
+					)
+				}
+			};
+			api.request(Method::DELETE, &path, None).await?;
+			println!("Alias {} was removed.", opt.name);
+		}
+		_ => {
+			return Err(Error::Message(
+				"This bucket operation is not available through the admin API, run it without --endpoint from a node of the cluster".into(),
+			))
+		}
+	}
+	Ok(())
+}
+
+async fn cmd_remote_bucket_perm(
+	api: &AdminApiClient,
+	opt: PermBucketOpt,
+	allow: bool,
+) -> Result<(), Error> {
+	let bucket = get_bucket(api, &opt.bucket).await?;
+	let key = get_key(api, &opt.key_pattern).await?;
+	let path = if allow {
+		"/v1/bucket/allow"
+	} else {
+		"/v1/bucket/deny"
+	};
+	let res: BucketInfo = api
+		.call(
+			Method::POST,
+			path,
+			Some(json!({
+				"bucketId": bucket.id,
+				"accessKeyId": key.access_key_id,
+				"permissions": {
+					"read": opt.read,
+					"write": opt.write,
+					"owner": opt.owner,
+				},
+			})),
+		)
+		.await?;
+
+	let perm = res
+		.keys
+		.iter()
+		.find(|k| k.access_key_id == key.access_key_id)
+		.map(|k| k.permissions.flags())
+		.unwrap_or_default();
+	println!(
+		"New permissions for {} on {}: {}",
+		key.access_key_id, opt.bucket, perm
+	);
+	Ok(())
+}
+
+// ---- keys ----
+
+/// Find a key by ID or by name
+async fn get_key(api: &AdminApiClient, pattern: &str) -> Result<KeyInfo, Error> {
+	api.call(
+		Method::GET,
+		&format!("/v1/key?search={}", garage_sigv4::uri_encode(pattern, true)),
+		None,
+	)
+	.await
+}
+
+fn print_remote_key_info(key: &KeyInfo, json: bool) -> Result<(), Error> {
+	if json {
+		return print_json(key);
+	}
+
+	println!("Key name: {}", key.name);
+	println!("Key ID: {}", key.access_key_id);
+	println!(
+		"Secret key: {}",
+		key.secret_access_key.as_deref().unwrap_or("(redacted)")
+	);
+	println!("Can create buckets: {}", key.permissions.create_bucket);
+	if let Some(expiration) = &key.expiration {
+		println!("Expires: {}", expiration);
+	}
+
+	println!();
+	println!("Buckets:");
+	let mut table = vec![];
+	for bucket in key.buckets.iter() {
+		table.push(format!(
+			"\t{}\t{}\t{}\t{}",
+			bucket.permissions.flags(),
+			bucket.global_aliases.join(","),
+			bucket.local_aliases.join(","),
+			bucket.id
+		));
+	}
+	format_table(table);
+	Ok(())
+}
+
+async fn cmd_remote_key(api: &AdminApiClient, cmd: KeyOperation, json: bool) -> Result<(), Error> {
+	match cmd {
+		KeyOperation::List => {
+			let keys: Vec<KeyListItem> = api.call(Method::GET, "/v1/key", None).await?;
+			if json {
+				return print_json(&keys);
+			}
+			println!("List of keys:");
+			let mut table = vec![];
+			for key in keys {
+				table.push(format!("\t{}\t{}", key.id, key.name));
+			}
+			format_table(table);
+		}
+		KeyOperation::Info(opt) => {
+			let key: KeyInfo = api
+				.call(
+					Method::GET,
+					&format!(
+						"/v1/key?search={}&showSecretKey={}",
+						garage_sigv4::uri_encode(&opt.key_pattern, true),
+						opt.show_secret
+					),
+					None,
+				)
+				.await?;
+			print_remote_key_info(&key, json)?;
+		}
+		KeyOperation::Create(opt) => {
+			let expiration = match &opt.expires_in {
+				Some(delay) => {
+					let delay = parse_duration::parse::parse(delay)
+						.map_err(|e| Error::Message(format!("Invalid delay {}: {}", delay, e)))?;
+					Some(msec_to_rfc3339(now_msec() + delay.as_millis() as u64))
+				}
+				None => None,
+			};
+			let key: KeyInfo = api
+				.call(
+					Method::POST,
+					"/v1/key",
+					Some(json!({ "name": opt.name, "expiration": expiration })),
+				)
+				.await?;
+			print_remote_key_info(&key, json)?;
+		}
+		KeyOperation::Rename(opt) => {
+			let key = get_key(api, &opt.key_pattern).await?;
+			let key: KeyInfo = api
+				.call(
+					Method::POST,
+					&format!("/v1/key?id={}", key.access_key_id),
+					Some(json!({ "name": opt.new_name })),
+				)
+				.await?;
+			print_remote_key_info(&key, json)?;
+		}
+		KeyOperation::Delete(opt) => {
+			let key = get_key(api, &opt.key_pattern).await?;
+			if !opt.yes {
+				println!("About to delete key {}...", key.access_key_id);
+				return Err(Error::Message(
+					"Add --yes flag to really perform this operation".into(),
+				));
+			}
+			match api
+				.delete(&format!("/v1/key?id={}", key.access_key_id))
+				.await?
+			{
+				Some(pending) => pending.print(&format!("key {}", key.access_key_id)),
+				None => println!("Key {} was deleted successfully.", key.access_key_id),
+			}
+		}
+		KeyOperation::Allow(opt) | KeyOperation::Deny(opt) if !opt.create_bucket => {
+			return Err(Error::Message(
+				"Please specify a permission flag, such as --create-bucket".into(),
+			));
+		}
+		KeyOperation::Allow(opt) => {
+			cmd_remote_key_perm(api, &opt.key_pattern, "allow", json).await?;
+		}
+		KeyOperation::Deny(opt) => {
+			cmd_remote_key_perm(api, &opt.key_pattern, "deny", json).await?;
+		}
+		KeyOperation::Import(opt) => {
+			if !opt.yes {
+				return Err(Error::Message("This command is intended to re-import keys that were previously generated by Garage. If you want to create a new key, use `garage key new` instead. Add the --yes flag if you really want to re-import a key.".to_string()));
+			}
+			let key: KeyInfo = api
+				.call(
+					Method::POST,
+					"/v1/key/import",
+					Some(json!({
+						"accessKeyId": opt.key_id,
+						"secretAccessKey": opt.secret_key,
+						"name": opt.name,
+					})),
+				)
+				.await?;
+			print_remote_key_info(&key, json)?;
+		}
+		_ => {
+			return Err(Error::Message(
+				"This key operation is not available through the admin API, run it without --endpoint from a node of the cluster".into(),
+			))
+		}
+	}
+	Ok(())
+}
+
+async fn cmd_remote_key_perm(
+	api: &AdminApiClient,
+	key_pattern: &str,
+	field: &str,
+	json: bool,
+) -> Result<(), Error> {
+	let key = get_key(api, key_pattern).await?;
+	let key: KeyInfo = api
+		.call(
+			Method::POST,
+			&format!("/v1/key?id={}", key.access_key_id),
+			Some(json!({ field: { "createBucket": true } })),
+		)
+		.await?;
+	print_remote_key_info(&key, json)
+}
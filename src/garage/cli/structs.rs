@@ -1,8 +1,13 @@
+use std::path::PathBuf;
+use std::str::FromStr;
+
 use serde::{Deserialize, Serialize};
 use structopt::StructOpt;
 
 use garage_util::version::garage_version;
 
+use garage_rpc::ring::{Partition, PARTITION_BITS};
+
 use crate::cli::convert_db;
 
 #[derive(StructOpt, Debug)]
@@ -45,6 +50,11 @@ pub enum Command {
 	#[structopt(name = "offline-repair", version = garage_version())]
 	OfflineRepair(OfflineRepairOpt),
 
+	/// Check the consistency of the metadata of a node that is not running,
+	/// and suggest repair operations to run once it is back online
+	#[structopt(name = "offline-check", version = garage_version())]
+	OfflineCheck(OfflineCheckOpt),
+
 	/// Gather node statistics
 	#[structopt(name = "stats", version = garage_version())]
 	Stats(StatsOpt),
@@ -53,10 +63,33 @@ pub enum Command {
 	#[structopt(name = "worker", version = garage_version())]
 	Worker(WorkerOperation),
 
+	/// Manage the long-running jobs of a node: repairs, imports, bucket
+	/// deletions and lifecycle runs
+	#[structopt(name = "job", version = garage_version())]
+	Job(JobOperation),
+
 	/// Low-level debug operations on data blocks
 	#[structopt(name = "block", version = garage_version())]
 	Block(BlockOperation),
 
+	/// Low-level debug operations on objects
+	#[structopt(name = "object", version = garage_version())]
+	Object(ObjectOperation),
+
+	/// Import objects from another storage service
+	#[structopt(name = "import", version = garage_version())]
+	Import(ImportOperation),
+
+	/// Mount a bucket as a read-only filesystem (FUSE), reading its objects
+	/// through the node the command is connected to
+	#[cfg(feature = "fuse")]
+	#[structopt(name = "mount", version = garage_version())]
+	Mount(MountOpt),
+
+	/// Check the configuration file thoroughly, without starting Garage
+	#[structopt(name = "check-config", version = garage_version())]
+	CheckConfig,
+
 	/// Convert metadata db between database engine formats
 	#[structopt(name = "convert-db", version = garage_version())]
 	ConvertDb(convert_db::ConvertDbOpt),
@@ -71,6 +104,10 @@ pub enum NodeOperation {
 	/// Connect to Garage node that is currently isolated from the system
 	#[structopt(name = "connect", version = garage_version())]
 	Connect(ConnectNodeOpt),
+
+	/// Get or change the filter that selects which log messages are emitted
+	#[structopt(name = "log-filter", version = garage_version())]
+	LogFilter(LogFilterOperation),
 }
 
 #[derive(StructOpt, Debug)]
@@ -87,6 +124,27 @@ pub struct ConnectNodeOpt {
 	pub(crate) node: String,
 }
 
+#[derive(Serialize, Deserialize, StructOpt, Debug, Eq, PartialEq, Clone)]
+pub enum LogFilterOperation {
+	/// Get the log filter currently in use
+	#[structopt(name = "get", version = garage_version())]
+	Get {
+		/// Get the log filter of all nodes
+		#[structopt(short = "a", long = "all-nodes")]
+		all_nodes: bool,
+	},
+	/// Change the log filter until the next restart, using the same syntax
+	/// as the RUST_LOG environment variable (e.g. `garage=info,garage_block=debug`)
+	#[structopt(name = "set", version = garage_version())]
+	Set {
+		/// Change the log filter on all nodes
+		#[structopt(short = "a", long = "all-nodes")]
+		all_nodes: bool,
+		/// New log filter
+		filter: String,
+	},
+}
+
 #[derive(StructOpt, Debug)]
 pub enum LayoutOperation {
 	/// Assign role to Garage node
@@ -97,6 +155,16 @@ pub enum LayoutOperation {
 	#[structopt(name = "remove", version = garage_version())]
 	Remove(RemoveRoleOpt),
 
+	/// Remove the storage capacity of a node, so that its data is moved to other
+	/// nodes before it is removed from the cluster
+	#[structopt(name = "drain", version = garage_version())]
+	Drain(DrainNodeOpt),
+
+	/// Suggest changes to the capacity of nodes, to balance the usage of
+	/// their data disk
+	#[structopt(name = "suggest", version = garage_version())]
+	Suggest(SuggestLayoutOpt),
+
 	/// Configure parameters value for the layout computation
 	#[structopt(name = "config", version = garage_version())]
 	Config(ConfigLayoutOpt),
@@ -105,6 +173,11 @@ pub enum LayoutOperation {
 	#[structopt(name = "show", version = garage_version())]
 	Show,
 
+	/// Show how partitions and data would move between nodes if the staged
+	/// changes were applied
+	#[structopt(name = "plan", version = garage_version())]
+	Plan(PlanLayoutOpt),
+
 	/// Apply staged changes to cluster layout
 	#[structopt(name = "apply", version = garage_version())]
 	Apply(ApplyLayoutOpt),
@@ -145,6 +218,24 @@ pub struct AssignRoleOpt {
 pub struct RemoveRoleOpt {
 	/// Node whose role to remove (prefix of hexadecimal node id)
 	pub(crate) node_id: String,
+
+	/// Remove the node even if it still stores data that has not been
+	/// moved to other nodes
+	#[structopt(long = "force")]
+	pub(crate) force: bool,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct DrainNodeOpt {
+	/// Node to drain (prefix of hexadecimal node id)
+	pub(crate) node_id: String,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct SuggestLayoutOpt {
+	/// Stage the suggested capacity changes
+	#[structopt(long = "stage")]
+	pub(crate) stage: bool,
 }
 
 #[derive(StructOpt, Debug)]
@@ -154,6 +245,18 @@ pub struct ConfigLayoutOpt {
 	pub(crate) redundancy: Option<String>,
 }
 
+#[derive(StructOpt, Debug)]
+pub struct PlanLayoutOpt {
+	/// Ask for confirmation to apply the staged changes after showing the plan
+	#[structopt(short = "i", long = "interactive")]
+	pub(crate) interactive: bool,
+
+	/// Rate at which each node receives data from other nodes, per second
+	/// (e.g. 100MB), instead of the rate measured by the nodes during resync
+	#[structopt(long = "bandwidth")]
+	pub(crate) bandwidth: Option<bytesize::ByteSize>,
+}
+
 #[derive(StructOpt, Debug)]
 pub struct ApplyLayoutOpt {
 	/// Version number of new configuration: this command will fail if
@@ -177,7 +280,7 @@ pub enum BucketOperation {
 
 	/// Get bucket info
 	#[structopt(name = "info", version = garage_version())]
-	Info(BucketOpt),
+	Info(BucketInfoOpt),
 
 	/// Create bucket
 	#[structopt(name = "create", version = garage_version())]
@@ -207,13 +310,67 @@ pub enum BucketOperation {
 	#[structopt(name = "website", version = garage_version())]
 	Website(WebsiteOpt),
 
+	/// Allow or deny anonymous read access on the anonymous S3 API endpoint
+	#[structopt(name = "public-read", version = garage_version())]
+	PublicRead(PublicReadOpt),
+
+	/// Require signed URLs to read some objects from the website of this bucket
+	#[structopt(name = "website-signing", version = garage_version())]
+	WebsiteSigning(WebsiteSigningOpt),
+
+	/// Generate a signed URL to read an object from the website of a bucket
+	#[structopt(name = "sign-url", version = garage_version())]
+	SignUrl(SignUrlOpt),
+
 	/// Set the quotas for this bucket
 	#[structopt(name = "set-quotas", version = garage_version())]
 	SetQuotas(SetQuotasOpt),
 
+	/// Set the thresholds above which alarms are raised for this bucket
+	#[structopt(name = "set-alarms", version = garage_version())]
+	SetAlarms(SetAlarmsOpt),
+
+	/// Set the number of days objects deleted from this bucket are kept in its trash
+	#[structopt(name = "set-trash", version = garage_version())]
+	SetTrash(SetTrashOpt),
+
+	/// Set the read/write quorum policy for objects of this bucket
+	#[structopt(name = "set-quorum", version = garage_version())]
+	SetQuorum(SetQuorumOpt),
+
+	/// Set the sizes of the first data blocks of objects uploaded in this bucket
+	#[structopt(name = "set-first-block-sizes", version = garage_version())]
+	SetFirstBlockSizes(SetFirstBlockSizesOpt),
+
 	/// Clean up (abort) old incomplete multipart uploads
 	#[structopt(name = "cleanup-incomplete-uploads", version = garage_version())]
 	CleanupIncompleteUploads(CleanupIncompleteUploadsOpt),
+
+	/// Show the size and number of objects of a bucket, grouped by prefix
+	#[structopt(name = "du", version = garage_version())]
+	Du(BucketDuOpt),
+
+	/// Delete all the objects of a bucket, in the background on the node
+	/// the command is sent to
+	#[structopt(name = "empty", version = garage_version())]
+	Empty(EmptyBucketOpt),
+
+	/// Copy the objects of a bucket into another bucket, without copying their
+	/// data, in the background on the node the command is sent to
+	#[structopt(name = "clone", version = garage_version())]
+	Clone(CloneBucketOpt),
+
+	/// List the objects in the trash of a bucket
+	#[structopt(name = "trash", version = garage_version())]
+	Trash(BucketTrashOpt),
+
+	/// Restore objects from the trash of a bucket
+	#[structopt(name = "restore", version = garage_version())]
+	Restore(RestoreTrashOpt),
+
+	/// Require a second admin token to delete this bucket through the admin API
+	#[structopt(name = "deletion-protection", version = garage_version())]
+	DeletionProtection(BucketProtectionOpt),
 }
 
 #[derive(Serialize, Deserialize, StructOpt, Debug)]
@@ -236,6 +393,88 @@ pub struct WebsiteOpt {
 	/// Error document: the optional document returned when an error occurs
 	#[structopt(short = "e", long = "error-document")]
 	pub error_document: Option<String>,
+
+	/// Generate a listing of objects for prefixes that have no index document
+	#[structopt(long = "directory-listing")]
+	pub directory_listing: bool,
+
+	/// Error document for a specific HTTP status code, as `<code>=<key>`
+	/// (e.g. `404=not-found.html`, can be given several times)
+	#[structopt(long = "error-document-for")]
+	pub error_documents: Vec<String>,
+
+	/// Cache-Control header to send for objects that don't have one
+	#[structopt(long = "cache-control")]
+	pub cache_control: Option<String>,
+
+	/// Send an Expires header this many seconds in the future
+	/// for objects that don't have one (at most one year)
+	#[structopt(long = "expires-after")]
+	pub expires_after: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, StructOpt, Debug)]
+pub struct WebsiteSigningOpt {
+	/// Require signed URLs
+	#[structopt(long = "allow")]
+	pub allow: bool,
+
+	/// Don't require signed URLs anymore
+	#[structopt(long = "deny")]
+	pub deny: bool,
+
+	/// Bucket name
+	pub bucket: String,
+
+	/// Only require signed URLs for objects whose key starts with this prefix
+	/// (can be given several times, all objects require them if not given)
+	#[structopt(long = "prefix")]
+	pub prefixes: Vec<String>,
+
+	/// Generate a new secret, which invalidates all URLs signed with the previous one
+	#[structopt(long = "new-secret")]
+	pub new_secret: bool,
+}
+
+#[derive(Serialize, Deserialize, StructOpt, Debug)]
+pub struct SignUrlOpt {
+	/// Bucket name
+	pub bucket: String,
+
+	/// Key of the object
+	pub key: String,
+
+	/// Number of seconds the URL is valid for
+	#[structopt(long = "expires-in", default_value = "3600")]
+	pub expires_in: u64,
+}
+
+#[derive(Serialize, Deserialize, StructOpt, Debug)]
+pub struct PublicReadOpt {
+	/// Allow anonymous read access
+	#[structopt(long = "allow")]
+	pub allow: bool,
+
+	/// Deny anonymous read access
+	#[structopt(long = "deny")]
+	pub deny: bool,
+
+	/// Bucket name
+	pub bucket: String,
+}
+
+#[derive(Serialize, Deserialize, StructOpt, Debug)]
+pub struct BucketProtectionOpt {
+	/// Enable deletion protection
+	#[structopt(long = "enable")]
+	pub enable: bool,
+
+	/// Disable deletion protection
+	#[structopt(long = "disable")]
+	pub disable: bool,
+
+	/// Bucket name
+	pub bucket: String,
 }
 
 #[derive(Serialize, Deserialize, StructOpt, Debug)]
@@ -244,6 +483,16 @@ pub struct BucketOpt {
 	pub name: String,
 }
 
+#[derive(Serialize, Deserialize, StructOpt, Debug)]
+pub struct BucketInfoOpt {
+	/// Bucket name
+	pub name: String,
+	/// Scan all objects of the bucket to compute the space saved by the
+	/// deduplication of identical data blocks
+	#[structopt(long = "dedup")]
+	pub dedup: bool,
+}
+
 #[derive(Serialize, Deserialize, StructOpt, Debug)]
 pub struct DeleteBucketOpt {
 	/// Bucket name
@@ -313,6 +562,63 @@ pub struct SetQuotasOpt {
 	/// Set a maximum number of objects for the bucket (or `none` for no restriction)
 	#[structopt(long = "max-objects")]
 	pub max_objects: Option<String>,
+
+	/// Make quotas soft (`true`): writes exceeding them are accepted with a warning
+	/// header instead of being rejected, or hard again (`false`)
+	#[structopt(long = "soft")]
+	#[serde(default)]
+	pub soft: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, StructOpt, Debug)]
+pub struct SetAlarmsOpt {
+	/// Bucket name
+	pub bucket: String,
+
+	/// Raise an alarm when the size of the bucket reaches this size
+	/// (specify a size e.g. in MiB or GiB, or `none` to remove the alarm)
+	#[structopt(long = "max-size")]
+	pub max_size: Option<String>,
+
+	/// Raise an alarm when the bucket reaches this number of objects
+	/// (or `none` to remove the alarm)
+	#[structopt(long = "max-objects")]
+	pub max_objects: Option<String>,
+
+	/// Raise an alarm when the bucket reaches this percentage of its quotas
+	/// (or `none` to remove the alarm)
+	#[structopt(long = "quota-percent")]
+	pub quota_percent: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, StructOpt, Debug)]
+pub struct SetTrashOpt {
+	/// Bucket name
+	pub bucket: String,
+
+	/// Number of days deleted objects are kept in the trash,
+	/// or `none` to disable the trash
+	pub retention_days: String,
+}
+
+#[derive(Serialize, Deserialize, StructOpt, Debug)]
+pub struct SetQuorumOpt {
+	/// Bucket name
+	pub bucket: String,
+
+	/// Quorum policy: `quorum` (cluster default), `all` or `one`
+	pub policy: String,
+}
+
+#[derive(Serialize, Deserialize, StructOpt, Debug)]
+pub struct SetFirstBlockSizesOpt {
+	/// Bucket name
+	pub bucket: String,
+
+	/// Sizes of the first blocks of objects (e.g. `128KiB 512KiB`), after which
+	/// blocks of the size set in the configuration are used (none to use it
+	/// for all blocks)
+	pub sizes: Vec<String>,
 }
 
 #[derive(Serialize, Deserialize, StructOpt, Debug)]
@@ -326,6 +632,75 @@ pub struct CleanupIncompleteUploadsOpt {
 	pub buckets: Vec<String>,
 }
 
+#[derive(Serialize, Deserialize, StructOpt, Debug)]
+pub struct EmptyBucketOpt {
+	/// Bucket name
+	pub name: String,
+
+	/// Maximum number of objects processed per second
+	#[structopt(long = "rate", default_value = "1000")]
+	pub rate: u64,
+
+	/// If this flag is not given, the objects of the bucket won't be deleted
+	#[structopt(long = "yes")]
+	pub yes: bool,
+}
+
+#[derive(Serialize, Deserialize, StructOpt, Debug)]
+pub struct CloneBucketOpt {
+	/// Name of the bucket to copy objects from
+	pub source: String,
+
+	/// Name of the bucket to copy objects into
+	pub destination: String,
+
+	/// Only copy objects whose key starts with this prefix
+	#[structopt(long = "prefix", default_value = "")]
+	pub prefix: String,
+}
+
+#[derive(Serialize, Deserialize, StructOpt, Debug)]
+pub struct BucketTrashOpt {
+	/// Only list objects whose key starts with this prefix
+	#[structopt(long = "prefix", default_value = "")]
+	pub prefix: String,
+
+	/// Maximum number of objects listed
+	#[structopt(long = "limit", default_value = "1000")]
+	pub limit: usize,
+
+	/// Bucket name
+	pub name: String,
+}
+
+#[derive(Serialize, Deserialize, StructOpt, Debug)]
+pub struct RestoreTrashOpt {
+	/// Restore all objects of the trash whose key starts with this prefix
+	#[structopt(long = "prefix")]
+	pub prefix: Option<String>,
+
+	/// Bucket name
+	pub name: String,
+
+	/// Keys of the objects to restore
+	pub keys: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, StructOpt, Debug)]
+pub struct BucketDuOpt {
+	/// Only count objects whose key starts with this prefix,
+	/// and group them by the next level of prefixes
+	#[structopt(long = "prefix", default_value = "")]
+	pub prefix: String,
+
+	/// Delimiter that separates the levels of prefixes
+	#[structopt(long = "delimiter", default_value = "/")]
+	pub delimiter: String,
+
+	/// Bucket name
+	pub name: String,
+}
+
 #[derive(Serialize, Deserialize, StructOpt, Debug)]
 pub enum KeyOperation {
 	/// List keys
@@ -359,6 +734,14 @@ pub enum KeyOperation {
 	/// Import key
 	#[structopt(name = "import", version = garage_version())]
 	Import(KeyImportOpt),
+
+	/// Set the delay after which the key can no longer be used
+	#[structopt(name = "set-expiration", version = garage_version())]
+	SetExpiration(KeyExpirationOpt),
+
+	/// Require a second admin token to delete this key through the admin API
+	#[structopt(name = "deletion-protection", version = garage_version())]
+	DeletionProtection(KeyProtectionOpt),
 }
 
 #[derive(Serialize, Deserialize, StructOpt, Debug)]
@@ -375,6 +758,10 @@ pub struct KeyNewOpt {
 	/// Name of the key
 	#[structopt(default_value = "Unnamed key")]
 	pub name: String,
+
+	/// Delay after which the key can no longer be used (e.g. `30d`)
+	#[structopt(long = "expires-in")]
+	pub expires_in: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, StructOpt, Debug)]
@@ -386,6 +773,30 @@ pub struct KeyRenameOpt {
 	pub new_name: String,
 }
 
+#[derive(Serialize, Deserialize, StructOpt, Debug)]
+pub struct KeyExpirationOpt {
+	/// ID or name of the key
+	pub key_pattern: String,
+
+	/// Delay after which the key can no longer be used (e.g. `30d`),
+	/// or `never` to remove the expiration
+	pub expires_in: String,
+}
+
+#[derive(Serialize, Deserialize, StructOpt, Debug)]
+pub struct KeyProtectionOpt {
+	/// Enable deletion protection
+	#[structopt(long = "enable")]
+	pub enable: bool,
+
+	/// Disable deletion protection
+	#[structopt(long = "disable")]
+	pub disable: bool,
+
+	/// ID or name of the key
+	pub key_pattern: String,
+}
+
 #[derive(Serialize, Deserialize, StructOpt, Debug)]
 pub struct KeyDeleteOpt {
 	/// ID or name of the key
@@ -461,16 +872,30 @@ pub enum RepairWhat {
 	Tables,
 	/// Repair (resync/rebalance) the set of stored blocks in the cluster
 	#[structopt(name = "blocks", version = garage_version())]
-	Blocks,
+	Blocks(BlockRepairOpt),
 	/// Repropagate object deletions to the version table
 	#[structopt(name = "versions", version = garage_version())]
-	Versions,
-	/// Repropagate object deletions to the multipart upload table
+	Versions(TableRepairOpt),
+	/// Repropagate object deletions to the multipart upload table,
+	/// or with --older-than, list (and optionally abort) old multipart uploads
 	#[structopt(name = "mpu", version = garage_version())]
-	MultipartUploads,
+	MultipartUploads {
+		/// List the multipart uploads of all buckets that were started longer
+		/// ago than this duration (e.g. 7d), instead of repairing the table
+		#[structopt(long = "older-than")]
+		older_than: Option<String>,
+		/// With --older-than, only list the multipart uploads started with
+		/// this access key
+		#[structopt(long = "initiator")]
+		initiator: Option<String>,
+		/// Abort the multipart uploads that are listed, deleting their data
+		/// (requires --yes)
+		#[structopt(long = "abort")]
+		abort: bool,
+	},
 	/// Repropagate version deletions to the block ref table
 	#[structopt(name = "block_refs", version = garage_version())]
-	BlockRefs,
+	BlockRefs(TableRepairOpt),
 	/// Verify integrity of all blocks on disc
 	#[structopt(name = "scrub", version = garage_version())]
 	Scrub {
@@ -482,6 +907,61 @@ pub enum RepairWhat {
 	Rebalance,
 }
 
+#[derive(Serialize, Deserialize, StructOpt, Debug, Eq, PartialEq, Clone)]
+pub struct BlockRepairOpt {
+	/// Only repair the blocks of these partitions, given as a single
+	/// partition number or as a range `<first>-<last>` (from 0 to 255)
+	#[structopt(long = "partitions")]
+	pub partitions: Option<PartitionRange>,
+
+	/// Only repair the blocks whose primary location is this data directory,
+	/// e.g. after replacing the corresponding disk
+	#[structopt(long = "data-dir")]
+	pub data_dir: Option<PathBuf>,
+}
+
+#[derive(Serialize, Deserialize, StructOpt, Debug, Eq, PartialEq, Clone)]
+pub struct TableRepairOpt {
+	/// Only repair the entries of these partitions, given as a single
+	/// partition number or as a range `<first>-<last>` (from 0 to 255)
+	#[structopt(long = "partitions")]
+	pub partitions: Option<PartitionRange>,
+
+	/// Number of entries that are repaired concurrently
+	#[structopt(long = "concurrency", default_value = "1")]
+	pub concurrency: usize,
+}
+
+/// Range of partitions, with both ends included
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone, Copy)]
+pub struct PartitionRange {
+	pub first: Partition,
+	pub last: Partition,
+}
+
+impl FromStr for PartitionRange {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let parse = |x: &str| match x.trim().parse::<Partition>() {
+			Ok(p) if (p as usize) < 1 << PARTITION_BITS => Ok(p),
+			_ => Err(format!(
+				"invalid partition number `{}` (must be between 0 and {})",
+				x,
+				(1 << PARTITION_BITS) - 1
+			)),
+		};
+		let (first, last) = match s.split_once('-') {
+			Some((first, last)) => (parse(first)?, parse(last)?),
+			None => (parse(s)?, parse(s)?),
+		};
+		if first > last {
+			return Err(format!("invalid partition range `{}`", s));
+		}
+		Ok(Self { first, last })
+	}
+}
+
 #[derive(Serialize, Deserialize, StructOpt, Debug, Eq, PartialEq, Clone)]
 pub enum ScrubCmd {
 	/// Start scrub
@@ -514,6 +994,13 @@ pub struct OfflineRepairOpt {
 	pub what: OfflineRepairWhat,
 }
 
+#[derive(Serialize, Deserialize, StructOpt, Debug, Clone)]
+pub struct OfflineCheckOpt {
+	/// Show all problems found, instead of only the first ones of each kind
+	#[structopt(short = "v", long = "verbose")]
+	pub verbose: bool,
+}
+
 #[derive(Serialize, Deserialize, StructOpt, Debug, Eq, PartialEq, Clone)]
 pub enum OfflineRepairWhat {
 	/// Repair K2V item counters
@@ -535,10 +1022,21 @@ pub struct StatsOpt {
 	#[structopt(short = "d", long = "detailed")]
 	pub detailed: bool,
 
+	/// Show the space used by data that is no longer needed on all nodes
+	/// (blocks pending garbage collection, stale uploads, tombstones)
+	#[structopt(long = "garbage")]
+	#[serde(default)]
+	pub garbage: bool,
+
 	/// Don't show global cluster stats (internal use in RPC)
 	#[structopt(skip)]
 	#[serde(default)]
 	pub skip_global: bool,
+
+	/// Return statistics as JSON (set by the global --json flag)
+	#[structopt(skip)]
+	#[serde(default)]
+	pub json: bool,
 }
 
 #[derive(Serialize, Deserialize, StructOpt, Debug, Eq, PartialEq, Clone)]
@@ -584,6 +1082,94 @@ pub struct WorkerListOpt {
 	pub errors: bool,
 }
 
+#[derive(Serialize, Deserialize, StructOpt, Debug, Eq, PartialEq, Clone)]
+pub enum JobOperation {
+	/// List the jobs of the node, running or finished recently
+	#[structopt(name = "list", version = garage_version())]
+	List {
+		/// Show only running jobs
+		#[structopt(short = "r", long = "running")]
+		running: bool,
+	},
+	/// Cancel a running job
+	#[structopt(name = "cancel", version = garage_version())]
+	Cancel {
+		/// ID of the job, or a prefix of it
+		id: String,
+	},
+}
+
+#[derive(Serialize, Deserialize, StructOpt, Debug, Eq, PartialEq, Clone)]
+pub enum ObjectOperation {
+	/// Show the versions of an object, the blocks that store its data,
+	/// and whether these blocks are present on the nodes that should store them
+	#[structopt(name = "info", version = garage_version())]
+	Info {
+		/// Name of the bucket
+		bucket: String,
+		/// Key of the object
+		key: String,
+	},
+}
+
+#[cfg(feature = "fuse")]
+#[derive(StructOpt, Debug, Clone)]
+pub struct MountOpt {
+	/// Name of the bucket to mount
+	pub bucket: String,
+
+	/// Directory on which to mount the bucket
+	pub path: PathBuf,
+
+	/// Allow other users than the one running the command to access the
+	/// mounted bucket (requires `user_allow_other` in /etc/fuse.conf)
+	#[structopt(long = "allow-other")]
+	pub allow_other: bool,
+}
+
+#[derive(Serialize, Deserialize, StructOpt, Debug, Eq, PartialEq, Clone)]
+pub enum ImportOperation {
+	/// Import the objects of a bucket of another S3-compatible service into
+	/// a bucket, in the background on the node the command is sent to
+	#[structopt(name = "s3", version = garage_version())]
+	S3(ImportS3Opt),
+}
+
+#[derive(Serialize, Deserialize, StructOpt, Debug, Eq, PartialEq, Clone)]
+pub struct ImportS3Opt {
+	/// URL of the S3 endpoint to import objects from
+	#[structopt(long = "from")]
+	pub from: String,
+
+	/// Name of the bucket to import objects into
+	#[structopt(long = "bucket")]
+	pub bucket: String,
+
+	/// Name of the bucket on the remote service, if different
+	#[structopt(long = "remote-bucket")]
+	pub remote_bucket: Option<String>,
+
+	/// Region of the remote service
+	#[structopt(long = "region", default_value = "us-east-1")]
+	pub region: String,
+
+	/// Access key ID for the remote service (default: $AWS_ACCESS_KEY_ID)
+	#[structopt(long = "access-key-id")]
+	pub access_key_id: Option<String>,
+
+	/// Secret access key for the remote service (default: $AWS_SECRET_ACCESS_KEY)
+	#[structopt(long = "secret-access-key")]
+	pub secret_access_key: Option<String>,
+
+	/// Only import objects whose key starts with this prefix
+	#[structopt(long = "prefix", default_value = "")]
+	pub prefix: String,
+
+	/// Number of objects imported at the same time
+	#[structopt(long = "concurrency", default_value = "4")]
+	pub concurrency: usize,
+}
+
 #[derive(Serialize, Deserialize, StructOpt, Debug, Eq, PartialEq, Clone)]
 pub enum BlockOperation {
 	/// List all blocks that currently have a resync error
@@ -614,4 +1200,41 @@ pub enum BlockOperation {
 		#[structopt(required = true)]
 		blocks: Vec<String>,
 	},
+	/// List the objects and multipart uploads that reference one or many blocks
+	#[structopt(name = "refs", version = garage_version())]
+	Refs {
+		/// List references to all blocks that have a resync error on this node
+		#[structopt(long = "all-errors")]
+		all_errors: bool,
+		/// Hashes of the blocks for which to list references
+		blocks: Vec<String>,
+	},
+	/// Ask the nodes that should store one or many blocks to fetch them now if they are missing
+	#[structopt(name = "fetch", version = garage_version())]
+	Fetch {
+		/// Hashes of the blocks to fetch
+		#[structopt(required = true)]
+		blocks: Vec<String>,
+	},
+	/// Mark blocks as permanently lost on all nodes, so that reading them
+	/// fails immediately and nodes stop trying to fetch them
+	#[structopt(name = "mark-lost", version = garage_version())]
+	MarkLost {
+		/// Mandatory to confirm this operation
+		#[structopt(long = "yes")]
+		yes: bool,
+		/// Hashes of the blocks to mark as lost
+		#[structopt(required = true)]
+		blocks: Vec<String>,
+	},
+	/// Remove the lost mark of blocks on all nodes
+	#[structopt(name = "unmark-lost", version = garage_version())]
+	UnmarkLost {
+		/// Hashes of the blocks that are not lost anymore
+		#[structopt(required = true)]
+		blocks: Vec<String>,
+	},
+	/// List all blocks that are marked as lost on this node
+	#[structopt(name = "list-lost", version = garage_version())]
+	ListLost,
 }
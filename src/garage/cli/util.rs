@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 use std::time::Duration;
 
+use bytesize::ByteSize;
 use format_table::format_table;
 use garage_util::background::*;
 use garage_util::crdt::*;
@@ -8,14 +9,19 @@ use garage_util::data::*;
 use garage_util::error::*;
 use garage_util::time::*;
 
-use garage_block::manager::BlockResyncErrorInfo;
+use garage_block::manager::{BlockLostInfo, BlockResyncErrorInfo, BlockStatus};
 
 use garage_model::bucket_table::*;
+use garage_model::garbage_stats::GarbageStats;
+use garage_model::helper::bucket::{BucketUsage, DedupStats, PrefixUsage};
+use garage_model::job::{JobRecord, JobState};
 use garage_model::key_table::*;
 use garage_model::s3::mpu_table::{self, MultipartUpload};
-use garage_model::s3::object_table;
+use garage_model::s3::object_table::{self, *};
+use garage_model::s3::trash::TrashedObject;
 use garage_model::s3::version_table::*;
 
+use crate::admin::{BlockObjectRef, BlockStatusByNode};
 use crate::cli::structs::WorkerListOpt;
 
 pub fn print_bucket_list(bl: Vec<Bucket>) {
@@ -50,6 +56,48 @@ pub fn print_bucket_list(bl: Vec<Bucket>) {
 	format_table(table);
 }
 
+pub fn print_bucket_usage(prefix: &str, usage: BucketUsage) {
+	let size = |u: &PrefixUsage| bytesize::ByteSize::b(u.bytes).to_string_as(true);
+
+	let mut total = usage.direct.clone();
+	let mut prefixes = usage.prefixes.into_iter().collect::<Vec<_>>();
+	prefixes.sort_by_key(|(_, u)| std::cmp::Reverse(u.bytes));
+
+	let mut table = vec!["Prefix\tSize\tObjects\tUnfinished uploads".to_string()];
+	for (p, u) in prefixes.iter() {
+		total.objects += u.objects;
+		total.unfinished_uploads += u.unfinished_uploads;
+		total.bytes += u.bytes;
+		table.push(format!(
+			"{}\t{}\t{}\t{}",
+			p,
+			size(u),
+			u.objects,
+			u.unfinished_uploads
+		));
+	}
+	if usage.direct.objects > 0 || usage.direct.unfinished_uploads > 0 {
+		table.push(format!(
+			"{}\t{}\t{}\t{}",
+			if prefix.is_empty() {
+				"(objects at top level)".to_string()
+			} else {
+				format!("{} (objects directly under)", prefix)
+			},
+			size(&usage.direct),
+			usage.direct.objects,
+			usage.direct.unfinished_uploads
+		));
+	}
+	table.push(format!(
+		"Total\t{}\t{}\t{}",
+		size(&total),
+		total.objects,
+		total.unfinished_uploads
+	));
+	format_table(table);
+}
+
 pub fn print_key_list(kl: Vec<(String, String)>) {
 	println!("List of keys:");
 	let mut table = vec![];
@@ -83,6 +131,23 @@ pub fn print_key_info(key: &Key, relevant_buckets: &HashMap<Uuid, Bucket>) {
 			println!("Key ID: {}", key.key_id);
 			println!("Secret key: {}", p.secret_key);
 			println!("Can create buckets: {}", p.allow_create_bucket.get());
+			if let Some(expiration) = key.expiration() {
+				let expired = if key.is_expired(now_msec()) {
+					" (expired)"
+				} else {
+					""
+				};
+				println!("Expires: {}{}", msec_to_rfc3339(expiration), expired);
+			}
+			if *p.deletion_protection.get() {
+				println!("Deletion protection: enabled");
+			}
+			if let Some(user) = p.ldap_user.get() {
+				println!("LDAP user: {}", user);
+			}
+			if *p.disabled.get() {
+				println!("Disabled: yes");
+			}
 			println!("\nKey-specific bucket aliases:");
 			let mut table = vec![];
 			for (alias_name, _, alias) in p.local_aliases.items().iter() {
@@ -132,11 +197,26 @@ pub fn print_key_info(key: &Key, relevant_buckets: &HashMap<Uuid, Bucket>) {
 	}
 }
 
+pub fn print_trash_list(trashed: &[TrashedObject]) {
+	let mut table = vec!["Key\tSize\tDeleted".to_string()];
+	for t in trashed.iter() {
+		table.push(format!(
+			"{}\t{}\t{}",
+			t.key,
+			bytesize::ByteSize::b(t.size).to_string_as(true),
+			msec_to_rfc3339(t.deleted)
+		));
+	}
+	format_table(table);
+}
+
 pub fn print_bucket_info(
 	bucket: &Bucket,
 	relevant_keys: &HashMap<String, Key>,
 	counters: &HashMap<String, i64>,
 	mpu_counters: &HashMap<String, i64>,
+	trash_counters: &HashMap<String, i64>,
+	dedup: Option<&DedupStats>,
 ) {
 	let key_name = |k| {
 		relevant_keys
@@ -176,7 +256,52 @@ pub fn print_bucket_info(
 				mpu_size.to_string_as(false),
 			);
 
+			if let Some(d) = dedup {
+				let logical = bytesize::ByteSize::b(d.logical_bytes);
+				let physical = bytesize::ByteSize::b(d.physical_bytes);
+				let saved = bytesize::ByteSize::b(d.logical_bytes - d.physical_bytes);
+				println!(
+					"\nData of all object versions: {} ({}) in {} blocks",
+					logical.to_string_as(true),
+					logical.to_string_as(false),
+					d.blocks
+				);
+				println!(
+					"After deduplication: {} ({}) in {} blocks",
+					physical.to_string_as(true),
+					physical.to_string_as(false),
+					d.unique_blocks
+				);
+				println!(
+					"Saved by deduplication: {} ({})",
+					saved.to_string_as(true),
+					saved.to_string_as(false)
+				);
+			}
+
 			println!("\nWebsite access: {}", p.website_config.get().is_some());
+			match p.website_signing.get() {
+				Some(s) if s.prefixes.is_empty() => {
+					println!("Signed URLs required: for all objects")
+				}
+				Some(s) => println!(
+					"Signed URLs required: for keys starting with {}",
+					s.prefixes.join(", ")
+				),
+				None => (),
+			}
+			println!("Anonymous read access: {}", p.public_read.get());
+			println!("Quorum policy: {}", p.quorum_policy.get().as_str());
+			if *p.deletion_protection.get() {
+				println!("Deletion protection: enabled");
+			}
+			if let Some(sizes) = p.first_block_sizes.get() {
+				let sizes = sizes
+					.iter()
+					.map(|s| bytesize::ByteSize::b(*s).to_string_as(true))
+					.collect::<Vec<_>>();
+				println!("First block sizes: {}", sizes.join(", "));
+			}
 
 			let quotas = p.quotas.get();
 			if quotas.max_size.is_some() || quotas.max_objects.is_some() {
@@ -192,6 +317,46 @@ pub fn print_bucket_info(
 				if let Some(mo) = quotas.max_objects {
 					println!(" maximum number of objects: {}", mo);
 				}
+				if quotas.soft {
+					println!(" soft quotas: writes exceeding them are accepted with a warning");
+				}
+			}
+
+			let alarms = p.alarms.get();
+			if *alarms != BucketAlarms::default() {
+				println!("\nAlarms:");
+				if let Some(ms) = alarms.max_size {
+					let ms = bytesize::ByteSize::b(ms);
+					println!(
+						" size above: {} ({})",
+						ms.to_string_as(true),
+						ms.to_string_as(false)
+					);
+				}
+				if let Some(mo) = alarms.max_objects {
+					println!(" number of objects above: {}", mo);
+				}
+				if let Some(qp) = alarms.quota_percent {
+					println!(" usage of quotas above: {}%", qp);
+				}
+			}
+
+			let trash_objects = *trash_counters.get(object_table::OBJECTS).unwrap_or(&0);
+			if p.trash_retention_days.get().is_some() || trash_objects > 0 {
+				println!("\nTrash:");
+				match p.trash_retention_days.get() {
+					Some(d) => println!(" deleted objects are kept for {} days", d),
+					None => println!(" disabled, objects in the trash will be deleted"),
+				}
+				let trash_size = bytesize::ByteSize::b(
+					*trash_counters.get(object_table::BYTES).unwrap_or(&0) as u64,
+				);
+				println!(
+					" objects: {}, size: {} ({})",
+					trash_objects,
+					trash_size.to_string_as(true),
+					trash_size.to_string_as(false)
+				);
 			}
 
 			println!("\nGlobal aliases:");
@@ -267,7 +432,9 @@ pub fn print_worker_list(wi: HashMap<usize, WorkerInfo>, wlo: WorkerListOpt) {
 		)
 	});
 
-	let mut table = vec!["TID\tState\tName\tTranq\tDone\tQueue\tErrors\tConsec\tLast".to_string()];
+	let now = now_msec();
+	let mut table =
+		vec!["TID\tState\tName\tTranq\tDone\tRate\tETA\tQueue\tErrors\tConsec\tLast".to_string()];
 	for (tid, info) in wi.iter() {
 		if wlo.busy && !matches!(info.state, WorkerState::Busy | WorkerState::Throttled(_)) {
 			continue;
@@ -288,8 +455,29 @@ pub fn print_worker_list(wi: HashMap<usize, WorkerInfo>, wlo: WorkerListOpt) {
 			("-".into(), "-".into())
 		};
 
+		let counters = info.status.counters.as_ref();
+		let done = counters
+			.and_then(|c| c.done_fraction())
+			.map(|f| format!("{:.2}%", f * 100.))
+			.or_else(|| info.status.progress.clone())
+			.unwrap_or_else(|| "-".into());
+		let rate = counters
+			.and_then(|c| Some((c, c.rates(now)?)))
+			.map(|(c, (items, bytes))| {
+				if c.bytes > 0 {
+					format!("{}/s", ByteSize::b(bytes as u64))
+				} else {
+					format!("{:.1}/s", items)
+				}
+			})
+			.unwrap_or_else(|| "-".into());
+		let eta = counters
+			.and_then(|c| c.eta(now))
+			.map(|d| duration_string(d.as_secs()))
+			.unwrap_or_else(|| "-".into());
+
 		table.push(format!(
-			"{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+			"{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
 			tid,
 			info.state,
 			info.name,
@@ -298,7 +486,9 @@ pub fn print_worker_list(wi: HashMap<usize, WorkerInfo>, wlo: WorkerListOpt) {
 				.as_ref()
 				.map(ToString::to_string)
 				.unwrap_or_else(|| "-".into()),
-			info.status.progress.as_deref().unwrap_or("-"),
+			done,
+			rate,
+			eta,
 			info.status
 				.queue_length
 				.as_ref()
@@ -312,6 +502,45 @@ pub fn print_worker_list(wi: HashMap<usize, WorkerInfo>, wlo: WorkerListOpt) {
 	format_table(table);
 }
 
+pub fn print_job_list(jobs: Vec<JobRecord>) {
+	let tf = timeago::Formatter::new();
+	let mut table = vec!["ID\tKind\tState\tStarted\tDone\tErrors\tDescription".to_string()];
+	for job in jobs.iter() {
+		let state = match job.state {
+			JobState::Running if job.cancel_requested => "Cancelling",
+			JobState::Running => "Running",
+			JobState::Completed => "Completed",
+			JobState::Cancelled => "Cancelled",
+			JobState::Failed => "Failed",
+		};
+		table.push(format!(
+			"{}\t{}\t{}\t{}\t{}\t{}\t{}",
+			hex::encode(&job.id.as_slice()[..8]),
+			job.kind,
+			state,
+			tf.convert(Duration::from_millis(
+				now_msec().saturating_sub(job.created)
+			)),
+			job.progress
+				.map(|p| format!("{:.2}%", p * 100.))
+				.unwrap_or_else(|| "-".into()),
+			if job.errors > 0 {
+				job.errors.to_string()
+			} else {
+				"-".into()
+			},
+			job.description,
+		));
+	}
+	format_table(table);
+
+	for job in jobs.iter() {
+		if let (JobState::Failed, Some(e)) = (job.state, &job.last_error) {
+			println!("Job {} failed: {}", hex::encode(&job.id.as_slice()[..8]), e);
+		}
+	}
+}
+
 pub fn print_worker_info(tid: usize, info: WorkerInfo) {
 	let mut table = vec![];
 	table.push(format!("Task id:\t{}", tid));
@@ -347,6 +576,41 @@ pub fn print_worker_info(tid: usize, info: WorkerInfo) {
 	if let Some(p) = info.status.progress {
 		table.push(format!("Progress:\t{}", p));
 	}
+	if let Some(c) = info.status.counters {
+		let now = now_msec();
+		if let Some(f) = c.done_fraction() {
+			table.push(format!("Done:\t{} {:.2}%", progress_bar(f, 30), f * 100.));
+		}
+		let items = match c.items_total {
+			Some(t) => format!("{} / {} items", c.items, t),
+			None => format!("{} items", c.items),
+		};
+		let bytes = match c.bytes_total {
+			Some(t) => format!(", {} / {}", ByteSize::b(c.bytes), ByteSize::b(t)),
+			None if c.bytes > 0 => format!(", {}", ByteSize::b(c.bytes)),
+			None => "".into(),
+		};
+		table.push(format!(
+			"Processed:\t{}{} in the last {}",
+			items,
+			bytes,
+			duration_string(now.saturating_sub(c.started) / 1000)
+		));
+		if let Some((items, bytes)) = c.rates(now) {
+			if c.bytes > 0 {
+				table.push(format!(
+					"Rate:\t{:.1} items/s, {}/s",
+					items,
+					ByteSize::b(bytes as u64)
+				));
+			} else {
+				table.push(format!("Rate:\t{:.1} items/s", items));
+			}
+		}
+		if let Some(eta) = c.eta(now) {
+			table.push(format!("ETA:\t{}", duration_string(eta.as_secs())));
+		}
+	}
 	if let Some(ql) = info.status.queue_length {
 		table.push(format!("Queue length:\t{}", ql));
 	}
@@ -367,6 +631,23 @@ pub fn print_worker_info(tid: usize, info: WorkerInfo) {
 	format_table(table);
 }
 
+/// Bar of `width` characters filled according to `fraction`
+fn progress_bar(fraction: f32, width: usize) -> String {
+	let filled = ((fraction.clamp(0., 1.) * width as f32) as usize).min(width);
+	format!("[{}{}]", "#".repeat(filled), ".".repeat(width - filled))
+}
+
+/// Human-readable duration, with a precision adapted to its order of
+/// magnitude
+pub fn duration_string(secs: u64) -> String {
+	match secs {
+		s if s < 60 => format!("{}s", s),
+		s if s < 3600 => format!("{}m {:02}s", s / 60, s % 60),
+		s if s < 86400 => format!("{}h {:02}m", s / 3600, (s % 3600) / 60),
+		s => format!("{}d {:02}h", s / 86400, (s % 86400) / 3600),
+	}
+}
+
 pub fn print_worker_vars(wv: Vec<(Uuid, String, String)>) {
 	let table = wv
 		.into_iter()
@@ -375,6 +656,14 @@ pub fn print_worker_vars(wv: Vec<(Uuid, String, String)>) {
 	format_table(table);
 }
 
+pub fn print_log_filters(lf: Vec<(Uuid, String)>) {
+	let table = lf
+		.into_iter()
+		.map(|(n, f)| format!("{:?}\t{}", n, f))
+		.collect::<Vec<_>>();
+	format_table(table);
+}
+
 pub fn print_block_error_list(el: Vec<BlockResyncErrorInfo>) {
 	let now = now_msec();
 	let tf = timeago::Formatter::new();
@@ -400,6 +689,143 @@ pub fn print_block_error_list(el: Vec<BlockResyncErrorInfo>) {
 	format_table(table);
 }
 
+pub fn print_block_lost_list(ll: Vec<BlockLostInfo>) {
+	if ll.is_empty() {
+		println!("No blocks are marked as lost.");
+		return;
+	}
+
+	let now = now_msec();
+	let tf = timeago::Formatter::new();
+
+	let mut table = vec!["Hash\tRC\tMarked lost".into()];
+	for l in ll {
+		table.push(format!(
+			"{}\t{}\t{}",
+			hex::encode(l.hash.as_slice()),
+			l.refcount,
+			tf.convert(Duration::from_millis(now.saturating_sub(l.marked_at))),
+		));
+	}
+	format_table(table);
+}
+
+pub fn print_garbage_stats(gs: Vec<(Uuid, Result<Option<GarbageStats>, String>)>) {
+	let now = now_msec();
+	let tf = timeago::Formatter::new();
+	let count_size = |count: u64, bytes: u64| {
+		format!(
+			"{} ({})",
+			count,
+			bytesize::ByteSize::b(bytes).to_string_as(true)
+		)
+	};
+
+	let mut total = GarbageStats::default();
+	let mut tombstones = Vec::<(String, u64, u64)>::new();
+	let mut table = vec![
+		"ID\tComputed\tUnreferenced blocks\tAborted uploads\tStale uploads\tTombstones".into(),
+	];
+	for (node, stats) in gs.iter() {
+		let s = match stats {
+			Ok(Some(s)) => s,
+			Ok(None) => {
+				table.push(format!("{:?}\tnot yet computed", node));
+				continue;
+			}
+			Err(e) => {
+				table.push(format!("{:?}\terror: {}", node, e));
+				continue;
+			}
+		};
+
+		let (n_tombstones, tombstones_bytes) = s
+			.tombstones
+			.iter()
+			.fold((0, 0), |(c, b), t| (c + t.count, b + t.bytes));
+		table.push(format!(
+			"{:?}\t{}\t{}\t{}\t{}\t{}",
+			node,
+			tf.convert(Duration::from_millis(now.saturating_sub(s.computed_at))),
+			count_size(s.unreferenced_blocks, s.unreferenced_blocks_bytes),
+			s.aborted_uploads,
+			count_size(s.stale_uploads, s.stale_uploads_bytes),
+			count_size(n_tombstones, tombstones_bytes),
+		));
+
+		total.unreferenced_blocks += s.unreferenced_blocks;
+		total.unreferenced_blocks_bytes += s.unreferenced_blocks_bytes;
+		total.aborted_uploads += s.aborted_uploads;
+		total.stale_uploads += s.stale_uploads;
+		total.stale_uploads_bytes += s.stale_uploads_bytes;
+		for t in s.tombstones.iter() {
+			match tombstones
+				.iter_mut()
+				.find(|(table, _, _)| *table == t.table)
+			{
+				Some((_, c, b)) => {
+					*c += t.count;
+					*b += t.bytes;
+				}
+				None => tombstones.push((t.table.clone(), t.count, t.bytes)),
+			}
+		}
+	}
+	let (n_tombstones, tombstones_bytes) = tombstones
+		.iter()
+		.fold((0, 0), |(c, b), (_, tc, tb)| (c + tc, b + tb));
+	table.push(format!(
+		"Total\t\t{}\t{}\t{}\t{}",
+		count_size(total.unreferenced_blocks, total.unreferenced_blocks_bytes),
+		total.aborted_uploads,
+		count_size(total.stale_uploads, total.stale_uploads_bytes),
+		count_size(n_tombstones, tombstones_bytes),
+	));
+	format_table(table);
+
+	if !tombstones.is_empty() {
+		println!();
+		let mut table = vec!["Table\tTombstones".into()];
+		for (t, count, bytes) in tombstones {
+			table.push(format!("{}\t{}", t, count_size(count, bytes)));
+		}
+		format_table(table);
+	}
+
+	println!();
+	println!("Values are the sum over all copies of the data stored on each node, and are computed once a day.");
+	println!("- Unreferenced blocks are deleted automatically by the block manager once the block GC delay has expired.");
+	println!(
+		"- Aborted uploads only use metadata space, their data is counted in unreferenced blocks."
+	);
+	println!("- Stale uploads are multipart uploads started more than a day ago that were neither completed nor aborted.");
+	println!("  They can be aborted with `garage repair --yes mpu --older-than 1d --abort`.");
+	println!("- Tombstones are deleted by table GC once they have been received by all nodes that store the entry.");
+}
+
+pub fn print_block_ref_list(refs: Vec<BlockObjectRef>, bucket_names: HashMap<Uuid, String>) {
+	if refs.is_empty() {
+		println!("No objects reference these blocks.");
+		return;
+	}
+
+	let mut table = vec!["Block\tBucket\tKey\tVersion\tMPU".into()];
+	for r in refs.iter() {
+		table.push(format!(
+			"{}\t{}\t{}\t{:?}\t{}",
+			hex::encode(r.block.as_slice()),
+			bucket_names
+				.get(&r.bucket_id)
+				.cloned()
+				.unwrap_or_else(|| hex::encode(r.bucket_id)),
+			r.key,
+			r.version,
+			r.upload_id.map(hex::encode).unwrap_or_default(),
+		));
+	}
+	format_table(table);
+}
+
 pub fn print_block_info(
 	hash: Hash,
 	refcount: u64,
@@ -453,3 +879,170 @@ pub fn print_block_info(
 		println!("Warning: refcount does not match number of non-deleted versions");
 	}
 }
+
+pub fn print_multipart_upload_list(
+	uploads: Vec<MultipartUpload>,
+	bucket_names: HashMap<Uuid, String>,
+	initiators: HashMap<Uuid, String>,
+	aborted: bool,
+) {
+	if uploads.is_empty() {
+		println!("No multipart uploads found.");
+		return;
+	}
+
+	let now = now_msec();
+	let tf = timeago::Formatter::new();
+
+	let mut total_size = 0;
+	let mut table = vec!["Bucket\tKey\tUpload ID\tInitiator\tAge\tParts\tSize".to_string()];
+	for mpu in uploads.iter() {
+		let size = mpu
+			.parts
+			.items()
+			.iter()
+			.filter_map(|(_, p)| p.size)
+			.sum::<u64>();
+		total_size += size;
+		table.push(format!(
+			"{}\t{}\t{}\t{}\t{}\t{}\t{}",
+			bucket_names
+				.get(&mpu.bucket_id)
+				.cloned()
+				.unwrap_or_else(|| hex::encode(mpu.bucket_id)),
+			mpu.key,
+			hex::encode(mpu.upload_id),
+			initiators
+				.get(&mpu.upload_id)
+				.map(String::as_str)
+				.unwrap_or("-"),
+			tf.convert(Duration::from_millis(now.saturating_sub(mpu.timestamp))),
+			mpu.parts.items().len(),
+			bytesize::ByteSize::b(size).to_string_as(true),
+		));
+	}
+	format_table(table);
+
+	println!();
+	println!(
+		"{} multipart uploads, total size {}",
+		uploads.len(),
+		bytesize::ByteSize::b(total_size).to_string_as(true)
+	);
+	if aborted {
+		println!("These uploads have been aborted, their data will be deleted.");
+	} else {
+		println!("Add the --abort and --yes flags to abort these uploads and delete their data.");
+	}
+}
+
+pub fn print_object_info(
+	object: Object,
+	versions: HashMap<Uuid, Version>,
+	blocks: HashMap<Hash, BlockStatusByNode>,
+) {
+	println!("Bucket: {}", hex::encode(object.bucket_id));
+	println!("Key: {}", object.key);
+
+	let mut missing_blocks = 0;
+	for ov in object.versions() {
+		println!();
+		println!("Version: {:?}", ov.uuid);
+		println!("Timestamp: {}", msec_to_rfc3339(ov.timestamp));
+		let first_block = match &ov.state {
+			ObjectVersionState::Uploading { multipart, .. } => {
+				println!("State: uploading (multipart: {})", multipart);
+				None
+			}
+			ObjectVersionState::Aborted => {
+				println!("State: aborted");
+				None
+			}
+			ObjectVersionState::Complete(ObjectVersionData::DeleteMarker) => {
+				println!("State: complete (delete marker)");
+				None
+			}
+			ObjectVersionState::Complete(ObjectVersionData::Inline(meta, _)) => {
+				println!("State: complete (data stored inline)");
+				println!("Size: {}", meta.size);
+				println!("ETag: {}", meta.etag);
+				None
+			}
+			ObjectVersionState::Complete(ObjectVersionData::FirstBlock(meta, hash)) => {
+				println!("State: complete");
+				println!("Size: {}", meta.size);
+				println!("ETag: {}", meta.etag);
+				Some(hash)
+			}
+		};
+
+		let version = match versions.get(&ov.uuid) {
+			Some(v) => v,
+			None => {
+				if let Some(hash) = first_block {
+					println!(
+						"Warning: version not found in version table, first block is {}",
+						hex::encode(hash)
+					);
+				}
+				continue;
+			}
+		};
+		if version.deleted.get() {
+			println!("Version is deleted in version table");
+		}
+		if version.blocks.items().is_empty() {
+			continue;
+		}
+
+		let mut table = vec!["Part\tOffset\tHash\tSize\tNodes".to_string()];
+		for (vbk, vb) in version.blocks.items().iter() {
+			let nodes = match blocks.get(&vb.hash) {
+				Some(status) => {
+					if status
+						.iter()
+						.any(|(_, st)| !matches!(st, Ok(BlockStatus { stored: true, .. })))
+					{
+						missing_blocks += 1;
+					}
+					status
+						.iter()
+						.map(|(node, st)| format!("{:?} ({})", node, block_status_str(st)))
+						.collect::<Vec<_>>()
+						.join(", ")
+				}
+				None => "?".to_string(),
+			};
+			table.push(format!(
+				"{}\t{}\t{}\t{}\t{}",
+				vbk.part_number,
+				vbk.offset,
+				hex::encode(vb.hash),
+				vb.size,
+				nodes
+			));
+		}
+		format_table(table);
+	}
+
+	if missing_blocks > 0 {
+		println!();
+		println!(
+			"Warning: {} blocks are not stored on all of the nodes that should have them",
+			missing_blocks
+		);
+	}
+}
+
+fn block_status_str(status: &Result<BlockStatus, String>) -> String {
+	match status {
+		Ok(BlockStatus { stored: true, .. }) => "present".into(),
+		Ok(BlockStatus {
+			stored: false,
+			refcount: 0,
+			..
+		}) => "missing, not referenced".into(),
+		Ok(BlockStatus { stored: false, .. }) => "MISSING".into(),
+		Err(e) => format!("error: {}", e),
+	}
+}
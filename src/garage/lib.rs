@@ -0,0 +1,6 @@
+//! Garage as a library, to run Garage instances from other programs
+
+#[macro_use]
+extern crate tracing;
+
+pub mod testing;
@@ -8,6 +8,7 @@ mod admin;
 mod cli;
 mod repair;
 mod server;
+mod systemd;
 #[cfg(feature = "telemetry-otlp")]
 mod tracing_setup;
 
@@ -50,6 +51,17 @@ struct Opt {
 	#[structopt(short = "h", long = "rpc-host", env = "GARAGE_RPC_HOST")]
 	pub rpc_host: Option<String>,
 
+	/// URL of the admin API of a node (e.g. `https://garage.example.com:3903`),
+	/// to run commands through the admin API with the token given by --admin-token,
+	/// instead of connecting to the RPC network of the cluster
+	#[structopt(long = "endpoint", env = "GARAGE_ADMIN_ENDPOINT")]
+	pub endpoint: Option<String>,
+
+	/// Print the output of bucket, key, layout, status and stats commands as JSON,
+	/// for use in scripts
+	#[structopt(long = "json", global = true)]
+	pub json: bool,
+
 	#[structopt(flatten)]
 	pub secrets: Secrets,
 
@@ -73,8 +85,8 @@ pub struct Secrets {
 	#[structopt(short = "s", long = "rpc-secret", env = "GARAGE_RPC_SECRET")]
 	pub rpc_secret: Option<String>,
 
-	/// Metrics API authentication token, replaces admin.metrics_token in config.toml when
-	/// running the Garage daemon
+	/// Admin API authentication token, replaces admin.admin_token in config.toml when
+	/// running the Garage daemon, and used to run commands with --endpoint
 	#[structopt(long = "admin-token", env = "GARAGE_ADMIN_TOKEN")]
 	pub admin_token: Option<String>,
 
@@ -104,6 +116,14 @@ async fn main() {
 		"metrics",
 		#[cfg(feature = "telemetry-otlp")]
 		"telemetry-otlp",
+		#[cfg(feature = "fuse")]
+		"fuse",
+		#[cfg(feature = "webdav")]
+		"webdav",
+		#[cfg(feature = "sftp")]
+		"sftp",
+		#[cfg(feature = "fault-injection")]
+		"fault-injection",
 		#[cfg(feature = "bundled-libs")]
 		"bundled-libs",
 		#[cfg(feature = "system-libs")]
@@ -165,10 +185,20 @@ async fn main() {
 		};
 		std::env::set_var("RUST_LOG", default_log)
 	}
-	tracing_subscriber::fmt()
+	let subscriber = tracing_subscriber::fmt()
 		.with_writer(std::io::stderr)
 		.with_env_filter(tracing_subscriber::filter::EnvFilter::from_default_env())
-		.init();
+		.with_filter_reloading();
+	let reload_handle = subscriber.reload_handle();
+	subscriber.init();
+	garage_util::log_filter::init_log_filter(
+		std::env::var("RUST_LOG").unwrap_or_default(),
+		move |filter| {
+			let filter = tracing_subscriber::filter::EnvFilter::try_new(filter)
+				.map_err(|e| e.to_string())?;
+			reload_handle.reload(filter).map_err(|e| e.to_string())
+		},
+	);
 	sodiumoxide::init().expect("Unable to init sodiumoxide");
 
 	let res = match opt.cmd {
@@ -176,6 +206,13 @@ async fn main() {
 		Command::OfflineRepair(repair_opt) => {
 			repair::offline::offline_repair(opt.config_file, opt.secrets, repair_opt).await
 		}
+		Command::OfflineCheck(check_opt) => {
+			repair::offline_check::offline_check(opt.config_file, opt.secrets, check_opt).await
+		}
+		Command::CheckConfig => {
+			let secrets = opt.secrets;
+			check_config_command(opt.config_file, |c| fill_secrets(c, secrets))
+		}
 		Command::ConvertDb(conv_opt) => {
 			cli::convert_db::do_conversion(conv_opt).map_err(From::from)
 		}
@@ -192,6 +229,15 @@ async fn main() {
 }
 
 async fn cli_command(opt: Opt) -> Result<(), Error> {
+	if let Some(endpoint) = opt.endpoint {
+		let token = opt
+			.secrets
+			.admin_token
+			.ok_or("An admin API token must be given with --admin-token when using --endpoint")?;
+		let api = AdminApiClient::new(endpoint, token);
+		return cli_remote_command_dispatch(opt.cmd, &api, opt.json).await;
+	}
+
 	let config = if opt.secrets.rpc_secret.is_none() || opt.rpc_host.is_none() {
 		Some(garage_util::config::read_config(opt.config_file.clone())
 			.err_context(format!("Unable to read configuration file {}. Configuration file is needed because -h or -s is not provided on the command line.", opt.config_file.to_string_lossy()))?)
@@ -254,7 +300,20 @@ async fn cli_command(opt: Opt) -> Result<(), Error> {
 	let system_rpc_endpoint = netapp.endpoint::<SystemRpc, ()>(SYSTEM_RPC_PATH.into());
 	let admin_rpc_endpoint = netapp.endpoint::<AdminRpc, ()>(ADMIN_RPC_PATH.into());
 
-	match cli_command_dispatch(opt.cmd, &system_rpc_endpoint, &admin_rpc_endpoint, id).await {
+	#[cfg(feature = "fuse")]
+	if let Command::Mount(mount_opt) = &opt.cmd {
+		return cli::mount::cmd_mount(admin_rpc_endpoint, id, mount_opt.clone()).await;
+	}
+
+	match cli_command_dispatch(
+		opt.cmd,
+		&system_rpc_endpoint,
+		&admin_rpc_endpoint,
+		id,
+		opt.json,
+	)
+	.await
+	{
 		Err(HelperError::Internal(i)) => Err(Error::Message(format!("Internal error: {}", i))),
 		Err(HelperError::BadRequest(b)) => Err(Error::Message(b)),
 		Err(e) => Err(Error::Message(format!("{}", e))),
@@ -1,2 +1,3 @@
 pub mod offline;
+pub mod offline_check;
 pub mod online;
@@ -0,0 +1,359 @@
+use std::ops::Bound;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use garage_util::config::*;
+use garage_util::error::*;
+
+use garage_table::replication::*;
+use garage_table::*;
+
+use garage_model::garage::Garage;
+use garage_model::s3::object_table::*;
+use garage_model::s3::version_table::*;
+
+use crate::cli::structs::*;
+use crate::{fill_secrets, Secrets};
+
+/// Number of problems of each kind that are printed, unless --verbose is given
+const MAX_EXAMPLES: usize = 10;
+
+/// Check the consistency of the metadata of a stopped node, using only the
+/// data that is stored locally. References are only checked when the
+/// referenced entry should be stored on this node according to the
+/// cluster layout.
+pub async fn offline_check(
+	config_file: PathBuf,
+	secrets: Secrets,
+	opt: OfflineCheckOpt,
+) -> Result<(), Error> {
+	info!("Loading configuration...");
+	let config = fill_secrets(read_config(config_file)?, secrets);
+
+	info!("Initializing Garage main data store...");
+	let garage = Garage::new(config)?;
+
+	let mut checks = vec![
+		Check::new(
+			"objects",
+			"object versions whose version entry is missing",
+			"tables",
+		),
+		Check::new(
+			"multipart uploads",
+			"multipart uploads that don't belong to an object",
+			"mpu",
+		),
+		Check::new(
+			"versions",
+			"versions that don't belong to an object or multipart upload",
+			"versions",
+		),
+		Check::new(
+			"versions",
+			"blocks of versions whose block reference is missing",
+			"tables",
+		),
+		Check::new(
+			"block references",
+			"block references whose version is missing or deleted",
+			"block_refs",
+		),
+		Check::new(
+			"blocks",
+			"referenced blocks that are missing from the data directory",
+			"blocks",
+		),
+	];
+
+	info!("Checking objects...");
+	let mut pos = None;
+	while let Some(objects) = next_batch(&garage.object_table, &mut pos)? {
+		for object in objects {
+			checks[0].checked += 1;
+			for v in object.versions() {
+				if !matches!(
+					v.state,
+					ObjectVersionState::Complete(ObjectVersionData::FirstBlock(_, _))
+				) || !is_local(&garage, &garage.version_table, &v.uuid)
+				{
+					continue;
+				}
+				if get_local(&garage.version_table, &v.uuid, &EmptyKey)?.is_none() {
+					checks[0].problem(&opt, || {
+						format!(
+							"bucket {:?}, key {}: version {:?} not found",
+							object.bucket_id, object.key, v.uuid
+						)
+					});
+				}
+			}
+		}
+	}
+
+	info!("Checking multipart uploads...");
+	let mut pos = None;
+	while let Some(uploads) = next_batch(&garage.mpu_table, &mut pos)? {
+		for mpu in uploads {
+			if mpu.deleted.get() || !is_local(&garage, &garage.object_table, &mpu.bucket_id) {
+				continue;
+			}
+			checks[1].checked += 1;
+			let exists = get_local(&garage.object_table, &mpu.bucket_id, &mpu.key)?
+				.map(|o| {
+					o.versions()
+						.iter()
+						.any(|v| v.uuid == mpu.upload_id && v.is_uploading(Some(true)))
+				})
+				.unwrap_or(false);
+			if !exists {
+				checks[1].problem(&opt, || {
+					format!(
+						"upload {:?} (bucket {:?}, key {}) has no uploading object version",
+						mpu.upload_id, mpu.bucket_id, mpu.key
+					)
+				});
+			}
+		}
+	}
+
+	info!("Checking versions...");
+	let mut pos = None;
+	while let Some(versions) = next_batch(&garage.version_table, &mut pos)? {
+		for version in versions {
+			if version.deleted.get() {
+				continue;
+			}
+
+			let backlink_exists = match &version.backlink {
+				VersionBacklink::Object { bucket_id, key } => {
+					if is_local(&garage, &garage.object_table, bucket_id) {
+						Some(
+							get_local(&garage.object_table, bucket_id, key)?
+								.map(|o| {
+									o.versions().iter().any(|v| {
+										v.uuid == version.uuid
+											&& v.state != ObjectVersionState::Aborted
+									})
+								})
+								.unwrap_or(false),
+						)
+					} else {
+						None
+					}
+				}
+				VersionBacklink::MultipartUpload { upload_id } => {
+					if is_local(&garage, &garage.mpu_table, upload_id) {
+						Some(
+							get_local(&garage.mpu_table, upload_id, &EmptyKey)?
+								.map(|u| !u.deleted.get())
+								.unwrap_or(false),
+						)
+					} else {
+						None
+					}
+				}
+			};
+			if let Some(exists) = backlink_exists {
+				checks[2].checked += 1;
+				if !exists {
+					checks[2].problem(&opt, || {
+						format!("version {:?} ({:?})", version.uuid, version.backlink)
+					});
+				}
+			}
+
+			for (_, vb) in version.blocks.items().iter() {
+				if !is_local(&garage, &garage.block_ref_table, &vb.hash) {
+					continue;
+				}
+				checks[3].checked += 1;
+				let block_ref = get_local(&garage.block_ref_table, &vb.hash, &version.uuid)?;
+				if !matches!(block_ref, Some(br) if !br.deleted.get()) {
+					checks[3].problem(&opt, || {
+						format!(
+							"version {:?}: no reference to block {:?}",
+							version.uuid, vb.hash
+						)
+					});
+				}
+			}
+		}
+	}
+
+	info!("Checking block references and stored blocks...");
+	let block_replication = &garage.block_manager.replication;
+	let mut last_block = None;
+	let mut pos = None;
+	while let Some(block_refs) = next_batch(&garage.block_ref_table, &mut pos)? {
+		for block_ref in block_refs {
+			if block_ref.deleted.get() {
+				continue;
+			}
+
+			if is_local(&garage, &garage.version_table, &block_ref.version) {
+				checks[4].checked += 1;
+				let version = get_local(&garage.version_table, &block_ref.version, &EmptyKey)?;
+				if !matches!(version, Some(v) if !v.deleted.get()) {
+					checks[4].problem(&opt, || {
+						format!(
+							"block {:?} referenced by version {:?}",
+							block_ref.block, block_ref.version
+						)
+					});
+				}
+			}
+
+			// Block references are sorted by block hash, so all references
+			// to a block are consecutive and the block is checked only once
+			if last_block == Some(block_ref.block) {
+				continue;
+			}
+			last_block = Some(block_ref.block);
+			if block_replication
+				.write_nodes(&block_ref.block)
+				.contains(&garage.system.id)
+			{
+				checks[5].checked += 1;
+				let status = garage.block_manager.block_status(&block_ref.block).await?;
+				if !status.stored {
+					checks[5].problem(&opt, || {
+						format!("block {:?} (refcount {})", block_ref.block, status.refcount)
+					});
+				}
+			}
+		}
+	}
+
+	print_report(&checks);
+
+	let n_problems = checks.iter().map(|c| c.problems).sum::<usize>();
+	if n_problems > 0 {
+		Err(Error::Message(format!(
+			"{} problems found in metadata",
+			n_problems
+		)))
+	} else {
+		Ok(())
+	}
+}
+
+struct Check {
+	table: &'static str,
+	description: &'static str,
+	/// Online repair procedure that fixes the problem (`garage repair <repair>`)
+	repair: &'static str,
+	checked: usize,
+	problems: usize,
+}
+
+impl Check {
+	fn new(table: &'static str, description: &'static str, repair: &'static str) -> Self {
+		Self {
+			table,
+			description,
+			repair,
+			checked: 0,
+			problems: 0,
+		}
+	}
+
+	fn problem<F: FnOnce() -> String>(&mut self, opt: &OfflineCheckOpt, msg: F) {
+		self.problems += 1;
+		if opt.verbose || self.problems <= MAX_EXAMPLES {
+			println!("{}: {}", self.description, msg());
+		} else if self.problems == MAX_EXAMPLES + 1 {
+			println!(
+				"{}: more problems found, use --verbose to show all of them",
+				self.description
+			);
+		}
+	}
+}
+
+fn print_report(checks: &[Check]) {
+	println!();
+	let mut table = vec!["Table\tChecked\tProblems\tDescription".to_string()];
+	for c in checks.iter() {
+		table.push(format!(
+			"{}\t{}\t{}\t{}",
+			c.table, c.checked, c.problems, c.description
+		));
+	}
+	format_table::format_table(table);
+
+	let mut plan = vec![];
+	for c in checks.iter().filter(|c| c.problems > 0) {
+		if !plan.contains(&c.repair) {
+			plan.push(c.repair);
+		}
+	}
+	println!();
+	if plan.is_empty() {
+		println!("No problems found.");
+	} else {
+		println!("Suggested repairs, to be run in this order once the node is back online:");
+		for (i, cmd) in plan.iter().enumerate() {
+			println!("  {}. garage repair --yes {}", i + 1, cmd);
+		}
+	}
+}
+
+/// Read the next batch of entries stored locally in a table, starting
+/// after position `pos`. The database iterator is not kept open while
+/// the entries are checked, as checks do lookups in other trees.
+fn next_batch<F: TableSchema, R: TableReplication>(
+	table: &Table<F, R>,
+	pos: &mut Option<Vec<u8>>,
+) -> Result<Option<Vec<F::E>>, Error> {
+	let low = match pos.take() {
+		Some(p) => Bound::Excluded(p),
+		None => Bound::Unbounded,
+	};
+
+	let mut batch = vec![];
+	let mut last_key = None;
+	for item in table
+		.data
+		.store
+		.range::<Vec<u8>, _>((low, Bound::Unbounded))?
+	{
+		let (k, v) = item?;
+		batch.push(table.data.decode_entry(&v)?);
+		last_key = Some(k);
+		if batch.len() >= 1000 {
+			break;
+		}
+	}
+
+	if batch.is_empty() {
+		return Ok(None);
+	}
+	*pos = last_key.map(|k| k.to_vec());
+	Ok(Some(batch))
+}
+
+/// Get an entry from the local copy of a table
+fn get_local<F: TableSchema, R: TableReplication>(
+	table: &Table<F, R>,
+	p: &F::P,
+	s: &F::S,
+) -> Result<Option<F::E>, Error> {
+	match table.data.read_entry(p, s)? {
+		Some(bytes) => Ok(Some(table.data.decode_entry(&bytes)?)),
+		None => Ok(None),
+	}
+}
+
+/// Whether entries of the given partition should be stored on this node
+fn is_local<F: TableSchema, R: TableReplication>(
+	garage: &Arc<Garage>,
+	table: &Table<F, R>,
+	p: &F::P,
+) -> bool {
+	table
+		.data
+		.replication
+		.write_nodes(&p.hash())
+		.contains(&garage.system.id)
+}
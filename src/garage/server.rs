@@ -1,6 +1,9 @@
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
 
 use tokio::sync::watch;
+use tokio::time::Instant;
 
 use garage_util::background::*;
 use garage_util::config::*;
@@ -8,17 +11,27 @@ use garage_util::error::Error;
 
 use garage_api::admin::api_server::AdminApiServer;
 use garage_api::s3::api_server::S3ApiServer;
-use garage_model::garage::Garage;
-use garage_web::WebServer;
+use garage_model::config_check::*;
+use garage_model::garage::{Garage, GarageBuilder};
+use garage_web::{AcmeWorker, WebServer};
 
 #[cfg(feature = "k2v")]
 use garage_api::k2v::api_server::K2VApiServer;
+#[cfg(feature = "sftp")]
+use garage_api::sftp::server::SftpServer;
+#[cfg(feature = "webdav")]
+use garage_api::webdav::api_server::WebDavServer;
 
 use crate::admin::*;
+use crate::systemd;
 #[cfg(feature = "telemetry-otlp")]
 use crate::tracing_setup::*;
 use crate::{fill_secrets, Secrets};
 
+/// Time given to the RPC, API and web servers to start listening before
+/// giving up on notifying systemd that Garage is ready
+const LISTENERS_STARTUP_TIMEOUT: Duration = Duration::from_secs(60);
+
 async fn wait_from(mut chan: watch::Receiver<bool>) {
 	while !*chan.borrow() {
 		if chan.changed().await.is_err() {
@@ -29,28 +42,66 @@ async fn wait_from(mut chan: watch::Receiver<bool>) {
 
 pub async fn run_server(config_file: PathBuf, secrets: Secrets) -> Result<(), Error> {
 	info!("Loading configuration...");
-	let config = fill_secrets(read_config(config_file)?, secrets);
+	let (config, issues) = check_config_file(config_file.clone(), |c| fill_secrets(c, secrets))?;
+	let mut errors = 0;
+	for issue in issues.iter() {
+		match issue.level {
+			ConfigIssueLevel::Warning => warn!("Configuration {}", issue),
+			ConfigIssueLevel::Error => {
+				error!("Configuration {}", issue);
+				errors += 1;
+			}
+		}
+	}
+	if errors > 0 {
+		return Err(Error::Message(format!(
+			"{} errors found in configuration file {}, see above",
+			errors,
+			config_file.display()
+		)));
+	}
+	let config_file = ConfigFile::open(config_file)?;
+
+	if let Some(filter) = &config.log_filter {
+		garage_util::log_filter::set_log_filter(filter)?;
+	}
 
 	// ---- Initialize Garage internals ----
 
 	#[cfg(feature = "metrics")]
-	let metrics_exporter = opentelemetry_prometheus::exporter().init();
+	// All histograms recorded by Garage are durations in seconds
+	let metrics_exporter = opentelemetry_prometheus::exporter()
+		.with_default_histogram_boundaries(vec![
+			0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0,
+			60.0,
+		])
+		.init();
 
 	info!("Initializing Garage main data store...");
-	let garage = Garage::new(config.clone())?;
+	let garage = GarageBuilder::from_config(config.clone())
+		.config_file(config_file)
+		.build()?;
 
 	info!("Initializing background runner...");
 	let watch_cancel = watch_shutdown_signal();
-	let (background, await_background_done) = BackgroundRunner::new(watch_cancel.clone());
+	watch_reload_signal(garage.clone(), watch_cancel.clone());
+	// Internal components (RPC, background workers) are stopped only once
+	// the API servers have finished processing in-flight requests,
+	// as these requests need them to complete.
+	let (send_stop_internals, watch_stop_internals) = watch::channel(false);
+	let (background, await_background_done) =
+		BackgroundRunner::new(watch_stop_internals.clone(), &config.background)?;
 
 	info!("Spawning Garage workers...");
+	garage_api::s3::import::register_job_kind(&garage.jobs);
+	garage_api::s3::update_metadata::register_job_kind(&garage.jobs);
 	garage.spawn_workers(&background);
 
 	if config.admin.trace_sink.is_some() {
 		info!("Initialize tracing...");
 
 		#[cfg(feature = "telemetry-otlp")]
-		init_tracing(config.admin.trace_sink.as_ref().unwrap(), garage.system.id)?;
+		init_tracing(&config.admin, garage.system.id)?;
 
 		#[cfg(not(feature = "telemetry-otlp"))]
 		error!("Garage was built without OTLP exporter, admin.trace_sink is ignored.");
@@ -59,12 +110,13 @@ pub async fn run_server(config_file: PathBuf, secrets: Secrets) -> Result<(), Er
 	info!("Initialize Admin API server and metrics collector...");
 	let admin_server = AdminApiServer::new(
 		garage.clone(),
+		background.clone(),
 		#[cfg(feature = "metrics")]
 		metrics_exporter,
 	);
 
 	info!("Launching internal Garage cluster communications...");
-	let run_system = tokio::spawn(garage.system.clone().run(watch_cancel.clone()));
+	let run_system = tokio::spawn(garage.system.clone().run(watch_stop_internals));
 
 	info!("Create admin RPC handler...");
 	AdminRpcHandler::new(garage.clone(), background.clone());
@@ -86,6 +138,32 @@ pub async fn run_server(config_file: PathBuf, secrets: Secrets) -> Result<(), Er
 		));
 	}
 
+	if let Some(anonymous_bind_addr) = &config.s3_api.anonymous_bind_addr {
+		info!("Initializing anonymous S3 API server...");
+		servers.push((
+			"S3 API (anonymous)",
+			tokio::spawn(S3ApiServer::run_anonymous(
+				garage.clone(),
+				anonymous_bind_addr.clone(),
+				config.s3_api.s3_region.clone(),
+				wait_from(watch_cancel.clone()),
+			)),
+		));
+	}
+
+	if let Some(tls_config) = &config.s3_api.tls {
+		info!("Initializing S3 API server (HTTPS)...");
+		servers.push((
+			"S3 API (HTTPS)",
+			tokio::spawn(S3ApiServer::run_https(
+				garage.clone(),
+				tls_config.clone(),
+				config.s3_api.s3_region.clone(),
+				wait_from(watch_cancel.clone()),
+			)),
+		));
+	}
+
 	if config.k2v_api.is_some() {
 		#[cfg(feature = "k2v")]
 		{
@@ -104,6 +182,40 @@ pub async fn run_server(config_file: PathBuf, secrets: Secrets) -> Result<(), Er
 		error!("K2V is not enabled in this build, cannot start K2V API server");
 	}
 
+	if config.webdav.is_some() {
+		#[cfg(feature = "webdav")]
+		{
+			info!("Initializing WebDAV server...");
+			servers.push((
+				"WebDAV",
+				tokio::spawn(WebDavServer::run(
+					garage.clone(),
+					config.webdav.as_ref().unwrap().api_bind_addr.clone(),
+					config.s3_api.s3_region.clone(),
+					wait_from(watch_cancel.clone()),
+				)),
+			));
+		}
+		#[cfg(not(feature = "webdav"))]
+		error!("WebDAV is not enabled in this build, cannot start WebDAV server");
+	}
+
+	if config.sftp.is_some() {
+		#[cfg(feature = "sftp")]
+		{
+			info!("Initializing SFTP server...");
+			servers.push((
+				"SFTP",
+				tokio::spawn(SftpServer::run(
+					garage.clone(),
+					wait_from(watch_cancel.clone()),
+				)),
+			));
+		}
+		#[cfg(not(feature = "sftp"))]
+		error!("SFTP is not enabled in this build, cannot start SFTP server");
+	}
+
 	if let Some(web_config) = &config.s3_web {
 		info!("Initializing web server...");
 		servers.push((
@@ -112,9 +224,43 @@ pub async fn run_server(config_file: PathBuf, secrets: Secrets) -> Result<(), Er
 				garage.clone(),
 				web_config.bind_addr.clone(),
 				web_config.root_domain.clone(),
+				web_config.compression,
 				wait_from(watch_cancel.clone()),
 			)),
 		));
+
+		if let Some(acme_config) = &web_config.acme {
+			info!("Initializing HTTPS web server...");
+			background.spawn_worker(AcmeWorker::new(
+				garage.clone(),
+				acme_config.clone(),
+				web_config.root_domain.clone(),
+			));
+			servers.push((
+				"Web (HTTPS)",
+				tokio::spawn(WebServer::run_tls(
+					garage.clone(),
+					acme_config.clone(),
+					web_config.root_domain.clone(),
+					web_config.compression,
+					wait_from(watch_cancel.clone()),
+				)),
+			));
+		}
+
+		if let Some(tls_config) = &web_config.tls {
+			info!("Initializing HTTPS web server with static certificate...");
+			servers.push((
+				"Web (HTTPS, static certificate)",
+				tokio::spawn(WebServer::run_https(
+					garage.clone(),
+					tls_config.clone(),
+					web_config.root_domain.clone(),
+					web_config.compression,
+					wait_from(watch_cancel.clone()),
+				)),
+			));
+		}
 	}
 
 	if let Some(admin_bind_addr) = &config.admin.api_bind_addr {
@@ -132,25 +278,51 @@ pub async fn run_server(config_file: PathBuf, secrets: Secrets) -> Result<(), Er
 		warn!("This Garage version is built without the metrics feature");
 	}
 
-	if servers.is_empty() {
-		// Nothing runs except netapp (not in servers)
-		// Await shutdown signal before proceeding to shutting down netapp
-		wait_from(watch_cancel).await;
-	} else {
-		// Stuff runs
-
-		// When a cancel signal is sent, stuff stops
+	// Tell systemd that Garage is ready once all servers accept connections
+	let notify_ready = {
+		let config = config.clone();
+		tokio::spawn(async move {
+			if systemd::wait_listeners_up(&config, LISTENERS_STARTUP_TIMEOUT).await {
+				info!("All servers are listening, Garage is ready.");
+				systemd::notify("READY=1");
+			}
+		})
+	};
+
+	// Await shutdown signal: when it is sent, servers stop accepting
+	// new connections
+	systemd::watchdog_until(wait_from(watch_cancel)).await;
+	notify_ready.abort();
+	systemd::notify("STOPPING=1");
+
+	if !servers.is_empty() {
+		// Give requests that are being processed some time to complete
+		let grace_period =
+			Duration::from_secs(garage.live_config.load().shutdown_grace_period_secs);
+		let deadline = Instant::now() + grace_period;
+		drain_in_flight_requests(&garage, grace_period).await;
 
 		// Collect stuff
-		for (desc, join_handle) in servers {
-			if let Err(e) = join_handle.await? {
-				error!("{} server exited with error: {}", desc, e);
-			} else {
-				info!("{} server exited without error.", desc);
+		for (desc, mut join_handle) in servers {
+			match tokio::time::timeout_at(deadline, &mut join_handle).await {
+				Ok(res) => {
+					if let Err(e) = res? {
+						error!("{} server exited with error: {}", desc, e);
+					} else {
+						info!("{} server exited without error.", desc);
+					}
+				}
+				Err(_) => {
+					warn!("{} server did not exit in time, aborting it.", desc);
+					join_handle.abort();
+				}
 			}
 		}
 	}
 
+	info!("Stopping internal components...");
+	send_stop_internals.send(true).unwrap();
+
 	// Remove RPC handlers for system to break reference cycles
 	info!("Deregistering RPC handlers for shutdown...");
 	garage.system.netapp.drop_all_handlers();
@@ -171,6 +343,39 @@ pub async fn run_server(config_file: PathBuf, secrets: Secrets) -> Result<(), Er
 	Ok(())
 }
 
+async fn drain_in_flight_requests(garage: &Garage, grace_period: Duration) {
+	let in_flight = &garage.in_flight_requests;
+	if in_flight.is_empty() {
+		return;
+	}
+
+	info!(
+		"Waiting for {} in-flight requests to complete (at most {}s)...",
+		in_flight.len(),
+		grace_period.as_secs()
+	);
+	if in_flight.wait_drained(grace_period).await {
+		info!("All in-flight requests have completed.");
+		return;
+	}
+
+	let aborted = in_flight.list();
+	warn!(
+		"Grace period expired, aborting {} in-flight requests:",
+		aborted.len()
+	);
+	for req in aborted {
+		warn!(
+			"- {} {} {} from {} (started {}s ago)",
+			req.api,
+			req.method,
+			req.uri,
+			req.client,
+			req.started.elapsed().as_secs()
+		);
+	}
+}
+
 #[cfg(unix)]
 fn watch_shutdown_signal() -> watch::Receiver<bool> {
 	use tokio::signal::unix::*;
@@ -180,17 +385,42 @@ fn watch_shutdown_signal() -> watch::Receiver<bool> {
 		let mut sigint = signal(SignalKind::interrupt()).expect("Failed to install SIGINT handler");
 		let mut sigterm =
 			signal(SignalKind::terminate()).expect("Failed to install SIGTERM handler");
-		let mut sighup = signal(SignalKind::hangup()).expect("Failed to install SIGHUP handler");
 		tokio::select! {
 			_ = sigint.recv() => info!("Received SIGINT, shutting down."),
 			_ = sigterm.recv() => info!("Received SIGTERM, shutting down."),
-			_ = sighup.recv() => info!("Received SIGHUP, shutting down."),
 		}
 		send_cancel.send(true).unwrap();
 	});
 	watch_cancel
 }
 
+/// Reload the configuration file when SIGHUP is received,
+/// until Garage starts shutting down
+#[cfg(unix)]
+fn watch_reload_signal(garage: Arc<Garage>, watch_cancel: watch::Receiver<bool>) {
+	use tokio::signal::unix::*;
+
+	let mut sighup = signal(SignalKind::hangup()).expect("Failed to install SIGHUP handler");
+	tokio::spawn(async move {
+		let cancel = wait_from(watch_cancel);
+		tokio::pin!(cancel);
+		loop {
+			tokio::select! {
+				_ = sighup.recv() => {
+					info!("Received SIGHUP, reloading configuration file.");
+					if let Err(e) = garage.reload_config() {
+						error!("Unable to reload configuration, nothing was applied: {}", e);
+					}
+				}
+				_ = &mut cancel => break,
+			}
+		}
+	});
+}
+
+#[cfg(windows)]
+fn watch_reload_signal(_garage: Arc<Garage>, _watch_cancel: watch::Receiver<bool>) {}
+
 #[cfg(windows)]
 fn watch_shutdown_signal() -> watch::Receiver<bool> {
 	use tokio::signal::windows::*;
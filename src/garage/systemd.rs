@@ -0,0 +1,142 @@
+//! Integration with the systemd service manager, using the `sd_notify`
+//! protocol: readiness and shutdown notifications, and watchdog keepalives.
+//! All of this does nothing if Garage is not started by systemd with
+//! `Type=notify` (i.e. if `NOTIFY_SOCKET` is not set).
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
+
+use futures::future::Future;
+
+use garage_util::config::Config;
+use garage_util::socket_address::UnixOrTCPSocketAddress;
+
+/// Send a notification to systemd, e.g. `READY=1`
+#[cfg(unix)]
+pub fn notify(state: &str) {
+	let path = match std::env::var_os("NOTIFY_SOCKET") {
+		Some(p) => p,
+		None => return,
+	};
+	if let Err(e) = send_notification(&path, state) {
+		warn!("Unable to send notification `{}` to systemd: {}", state, e);
+	}
+}
+
+#[cfg(unix)]
+fn send_notification(path: &std::ffi::OsStr, state: &str) -> std::io::Result<()> {
+	use std::os::unix::ffi::OsStrExt;
+	use std::os::unix::net::UnixDatagram;
+
+	let socket = UnixDatagram::unbound()?;
+	match path.as_bytes() {
+		#[cfg(target_os = "linux")]
+		[b'@', name @ ..] => {
+			use std::os::linux::net::SocketAddrExt;
+			let addr = std::os::unix::net::SocketAddr::from_abstract_name(name)?;
+			socket.send_to_addr(state.as_bytes(), &addr)?;
+		}
+		_ => {
+			socket.send_to(state.as_bytes(), path)?;
+		}
+	}
+	Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn notify(_state: &str) {}
+
+/// Interval at which keepalives must be sent to the systemd watchdog,
+/// if it is enabled (`WatchdogSec=` in the unit file)
+fn watchdog_interval() -> Option<Duration> {
+	std::env::var_os("NOTIFY_SOCKET")?;
+	// WATCHDOG_PID is set when the watchdog is meant for another process
+	if let Ok(pid) = std::env::var("WATCHDOG_PID") {
+		if pid.parse::<u32>().ok() != Some(std::process::id()) {
+			return None;
+		}
+	}
+	let usec = std::env::var("WATCHDOG_USEC").ok()?.parse::<u64>().ok()?;
+	// Keepalives are sent twice as often as required, as recommended by systemd
+	Some(Duration::from_micros(usec / 2)).filter(|d| !d.is_zero())
+}
+
+/// Wait for a future to complete, sending keepalives to the systemd
+/// watchdog in the meantime if it is enabled. As keepalives are sent from
+/// the task that awaits this, they stop if that task or the runtime hangs.
+pub async fn watchdog_until(fut: impl Future<Output = ()>) {
+	let interval = match watchdog_interval() {
+		Some(i) => i,
+		None => return fut.await,
+	};
+	info!(
+		"systemd watchdog enabled, sending keepalives every {}ms",
+		interval.as_millis()
+	);
+
+	tokio::pin!(fut);
+	let mut ticker = tokio::time::interval(interval);
+	loop {
+		tokio::select! {
+			_ = &mut fut => return,
+			_ = ticker.tick() => notify("WATCHDOG=1"),
+		}
+	}
+}
+
+/// Wait until the RPC, API and web servers of the configuration accept
+/// connections, checking every 100ms. Returns `false` if they still do not
+/// after `timeout`.
+pub async fn wait_listeners_up(config: &Config, timeout: Duration) -> bool {
+	let mut addrs = vec![UnixOrTCPSocketAddress::TCPSocket(config.rpc_bind_addr)];
+	addrs.extend(config.s3_api.api_bind_addr.clone());
+	addrs.extend(config.s3_api.anonymous_bind_addr.clone());
+	addrs.extend(config.admin.api_bind_addr.clone());
+	#[cfg(feature = "k2v")]
+	if let Some(k2v) = &config.k2v_api {
+		addrs.push(k2v.api_bind_addr.clone());
+	}
+	if let Some(tls) = &config.s3_api.tls {
+		addrs.push(UnixOrTCPSocketAddress::TCPSocket(tls.bind_addr));
+	}
+	if let Some(web) = &config.s3_web {
+		addrs.push(web.bind_addr.clone());
+		if let Some(tls) = &web.tls {
+			addrs.push(UnixOrTCPSocketAddress::TCPSocket(tls.bind_addr));
+		}
+		if let Some(acme) = &web.acme {
+			addrs.push(UnixOrTCPSocketAddress::TCPSocket(acme.tls_bind_addr));
+		}
+	}
+
+	let deadline = tokio::time::Instant::now() + timeout;
+	for addr in addrs.iter() {
+		while !accepts_connections(addr).await {
+			if tokio::time::Instant::now() >= deadline {
+				warn!("Server on {} is still not listening", addr);
+				return false;
+			}
+			tokio::time::sleep(Duration::from_millis(100)).await;
+		}
+	}
+	true
+}
+
+async fn accepts_connections(addr: &UnixOrTCPSocketAddress) -> bool {
+	match addr {
+		UnixOrTCPSocketAddress::TCPSocket(addr) => {
+			// Servers listening on all interfaces are reached on the loopback
+			let ip = match addr.ip() {
+				IpAddr::V4(ip) if ip.is_unspecified() => IpAddr::V4(Ipv4Addr::LOCALHOST),
+				IpAddr::V6(ip) if ip.is_unspecified() => IpAddr::V6(Ipv6Addr::LOCALHOST),
+				ip => ip,
+			};
+			tokio::net::TcpStream::connect(SocketAddr::new(ip, addr.port()))
+				.await
+				.is_ok()
+		}
+		#[cfg(unix)]
+		UnixOrTCPSocketAddress::UnixSocket(path) => tokio::net::UnixStream::connect(path).await.is_ok(),
+		#[cfg(not(unix))]
+		UnixOrTCPSocketAddress::UnixSocket(_) => true,
+	}
+}
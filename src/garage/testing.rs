@@ -0,0 +1,281 @@
+//! Run a single-node Garage instance inside the current process, e.g. in the
+//! integration tests of applications that use Garage, instead of starting
+//! the `garage` binary and configuring it with the CLI.
+//!
+//! ```no_run
+//! # async fn example() -> Result<(), garage_util::error::Error> {
+//! let cluster = garage::testing::TestCluster::start().await?;
+//! cluster.create_bucket("my-bucket").await?;
+//! // Use an S3 client with `cluster.s3_endpoint`, `cluster.region`
+//! // and `cluster.key` ...
+//! cluster.shutdown().await
+//! # }
+//! ```
+use std::net::{SocketAddr, TcpListener};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+use garage_util::background::BackgroundRunner;
+use garage_util::crdt::Crdt;
+use garage_util::error::Error;
+use garage_util::socket_address::UnixOrTCPSocketAddress;
+use garage_util::time::now_msec;
+
+use garage_rpc::layout::{NodeRole, NodeRoleV};
+
+use garage_api::s3::api_server::S3ApiServer;
+use garage_model::bucket_table::Bucket;
+use garage_model::garage::{Garage, GarageBuilder};
+use garage_model::key_table::Key;
+use garage_model::permission::BucketKeyPerm;
+
+#[cfg(feature = "k2v")]
+use garage_api::k2v::api_server::K2VApiServer;
+
+/// Region of the S3 and K2V APIs of test clusters
+pub const TEST_REGION: &str = "garage";
+
+/// Capacity given to the node in the cluster layout
+const TEST_NODE_CAPACITY: u64 = 1 << 30;
+
+/// Credentials of an access key
+#[derive(Debug, Clone)]
+pub struct TestKey {
+	pub access_key_id: String,
+	pub secret_access_key: String,
+}
+
+/// A running single-node Garage instance storing its data in a temporary
+/// directory, which is deleted when the instance is stopped
+pub struct TestCluster {
+	/// The Garage instance, to access its tables directly
+	pub garage: Arc<Garage>,
+	/// URL of the S3 API endpoint, e.g. `http://127.0.0.1:35627`
+	pub s3_endpoint: String,
+	/// URL of the K2V API endpoint
+	#[cfg(feature = "k2v")]
+	pub k2v_endpoint: String,
+	/// Region to use in the requests to the S3 and K2V APIs
+	pub region: String,
+	/// An access key that is allowed to create buckets, and that has
+	/// all permissions on the buckets created with `create_bucket`
+	pub key: TestKey,
+
+	path: PathBuf,
+	send_cancel: watch::Sender<bool>,
+	send_stop_internals: watch::Sender<bool>,
+	servers: Vec<JoinHandle<Result<(), Error>>>,
+	run_system: JoinHandle<()>,
+	await_background_done: JoinHandle<()>,
+}
+
+impl TestCluster {
+	/// Start a node listening on free ports of 127.0.0.1, give it a role
+	/// in the cluster layout and create an access key. When this returns,
+	/// the node is ready to serve requests.
+	pub async fn start() -> Result<Self, Error> {
+		let path = std::env::temp_dir().join(format!(
+			"garage-test-{}",
+			hex::encode(rand::random::<[u8; 8]>())
+		));
+		std::fs::create_dir_all(&path)?;
+
+		let rpc_addr = free_local_addr()?;
+		let s3_addr = free_local_addr()?;
+
+		let mut builder = GarageBuilder::new(
+			path.join("meta"),
+			path.join("data"),
+			hex::encode(rand::random::<[u8; 32]>()),
+		)
+		.db_engine(test_db_engine())
+		.rpc_bind_addr(rpc_addr)
+		.rpc_public_addr(rpc_addr.to_string());
+		let config = builder.config_mut();
+		config.s3_api.s3_region = TEST_REGION.to_string();
+		config.s3_api.api_bind_addr = Some(UnixOrTCPSocketAddress::TCPSocket(s3_addr));
+		#[cfg(feature = "k2v")]
+		let k2v_addr = free_local_addr()?;
+		#[cfg(feature = "k2v")]
+		{
+			config.k2v_api = Some(garage_util::config::K2VApiConfig {
+				api_bind_addr: UnixOrTCPSocketAddress::TCPSocket(k2v_addr),
+				limits: Default::default(),
+			});
+		}
+		let garage = builder.build()?;
+
+		let (send_cancel, watch_cancel) = watch::channel(false);
+		let (send_stop_internals, watch_stop_internals) = watch::channel(false);
+		let (background, await_background_done) =
+			BackgroundRunner::new(watch_stop_internals.clone(), &garage.config.background)?;
+		garage_api::s3::import::register_job_kind(&garage.jobs);
+		garage_api::s3::update_metadata::register_job_kind(&garage.jobs);
+		garage.spawn_workers(&background);
+		let run_system = tokio::spawn(garage.system.clone().run(watch_stop_internals));
+
+		let mut servers = vec![tokio::spawn(S3ApiServer::run(
+			garage.clone(),
+			UnixOrTCPSocketAddress::TCPSocket(s3_addr),
+			TEST_REGION.to_string(),
+			wait_from(watch_cancel.clone()),
+		))];
+		#[cfg(feature = "k2v")]
+		servers.push(tokio::spawn(K2VApiServer::run(
+			garage.clone(),
+			UnixOrTCPSocketAddress::TCPSocket(k2v_addr),
+			TEST_REGION.to_string(),
+			wait_from(watch_cancel.clone()),
+		)));
+
+		let mut cluster = Self {
+			garage,
+			s3_endpoint: format!("http://{}", s3_addr),
+			#[cfg(feature = "k2v")]
+			k2v_endpoint: format!("http://{}", k2v_addr),
+			region: TEST_REGION.to_string(),
+			key: TestKey {
+				access_key_id: String::new(),
+				secret_access_key: String::new(),
+			},
+			path,
+			send_cancel,
+			send_stop_internals,
+			servers,
+			run_system,
+			await_background_done,
+		};
+
+		cluster.setup_layout().await?;
+		cluster.key = cluster.create_key("test-key").await?;
+		wait_listening(s3_addr).await?;
+		#[cfg(feature = "k2v")]
+		wait_listening(k2v_addr).await?;
+
+		Ok(cluster)
+	}
+
+	/// Create an access key that is allowed to create buckets
+	pub async fn create_key(&self, name: &str) -> Result<TestKey, Error> {
+		let mut key = Key::new(name);
+		key.params_mut().unwrap().allow_create_bucket.update(true);
+		self.garage.key_table.insert(&key).await?;
+
+		Ok(TestKey {
+			access_key_id: key.key_id.clone(),
+			secret_access_key: key.params().unwrap().secret_key.clone(),
+		})
+	}
+
+	/// Create a bucket with the given global name, on which `self.key`
+	/// has read, write and owner permissions. Returns the bucket ID.
+	pub async fn create_bucket(&self, name: &str) -> Result<String, Error> {
+		let bucket = Bucket::new();
+		self.garage.bucket_table.insert(&bucket).await?;
+
+		let helper = self.garage.bucket_helper();
+		helper
+			.set_global_bucket_alias(bucket.id, &name.to_string())
+			.await
+			.map_err(|e| Error::Message(e.to_string()))?;
+		helper
+			.set_bucket_key_permissions(
+				bucket.id,
+				&self.key.access_key_id,
+				BucketKeyPerm {
+					timestamp: now_msec(),
+					allow_read: true,
+					allow_write: true,
+					allow_owner: true,
+				},
+			)
+			.await
+			.map_err(|e| Error::Message(e.to_string()))?;
+
+		Ok(hex::encode(bucket.id))
+	}
+
+	/// Stop the node, letting it finish its background tasks, and delete
+	/// its temporary directory
+	pub async fn shutdown(mut self) -> Result<(), Error> {
+		let _ = self.send_cancel.send(true);
+		for server in std::mem::take(&mut self.servers) {
+			server.await??;
+		}
+
+		let _ = self.send_stop_internals.send(true);
+		self.garage.system.netapp.drop_all_handlers();
+		(&mut self.run_system).await?;
+		(&mut self.await_background_done).await?;
+		Ok(())
+	}
+
+	async fn setup_layout(&self) -> Result<(), Error> {
+		let system = &self.garage.system;
+		let mut layout = system.get_cluster_layout();
+		let role = NodeRoleV(Some(NodeRole {
+			zone: "test".to_string(),
+			capacity: Some(TEST_NODE_CAPACITY),
+			tags: vec![],
+		}));
+		let update = layout.roles.update_mutator(system.id, role);
+		layout.staging_roles.merge(&update);
+
+		let version = layout.version + 1;
+		let (layout, _) = layout.apply_staged_changes(Some(version))?;
+		system.update_cluster_layout(&layout).await?;
+		Ok(())
+	}
+}
+
+impl Drop for TestCluster {
+	fn drop(&mut self) {
+		// Stop the node if `shutdown` was not called, e.g. when a test panics
+		let _ = self.send_cancel.send(true);
+		let _ = self.send_stop_internals.send(true);
+		if let Err(e) = std::fs::remove_dir_all(&self.path) {
+			warn!(
+				"Unable to delete test cluster directory {}: {}",
+				self.path.display(),
+				e
+			);
+		}
+	}
+}
+
+fn test_db_engine() -> &'static str {
+	if cfg!(feature = "lmdb") {
+		"lmdb"
+	} else if cfg!(feature = "sqlite") {
+		"sqlite"
+	} else {
+		"sled"
+	}
+}
+
+/// Find a port of 127.0.0.1 that is not used
+fn free_local_addr() -> Result<SocketAddr, Error> {
+	Ok(TcpListener::bind("127.0.0.1:0")?.local_addr()?)
+}
+
+async fn wait_listening(addr: SocketAddr) -> Result<(), Error> {
+	for _ in 0..100 {
+		if tokio::net::TcpStream::connect(addr).await.is_ok() {
+			return Ok(());
+		}
+		tokio::time::sleep(Duration::from_millis(50)).await;
+	}
+	Err(Error::Message(format!("Server on {} did not start", addr)))
+}
+
+async fn wait_from(mut chan: watch::Receiver<bool>) {
+	while !*chan.borrow() {
+		if chan.changed().await.is_err() {
+			return;
+		}
+	}
+}
@@ -51,3 +51,70 @@ async fn test_admin_bucket_perms() {
 
 	assert!(hb().await.is_err());
 }
+
+#[tokio::test]
+async fn test_admin_remote_mode() {
+	const REMOTE_BCKT_NAME: &str = "seau-distant";
+
+	let ctx = common::context();
+
+	let hb = || ctx.client.head_bucket().bucket(REMOTE_BCKT_NAME).send();
+
+	// Commands run through the admin API act on the cluster
+	ctx.garage
+		.remote_command()
+		.args(["bucket", "create", REMOTE_BCKT_NAME])
+		.quiet()
+		.expect_success_status("Could not create bucket through the admin API");
+
+	ctx.garage
+		.remote_command()
+		.args([
+			"bucket",
+			"allow",
+			"--read",
+			"--key",
+			&ctx.key.id,
+			REMOTE_BCKT_NAME,
+		])
+		.quiet()
+		.expect_success_status("Could not allow key through the admin API");
+
+	assert!(hb().await.is_ok());
+
+	let info = ctx
+		.garage
+		.remote_command()
+		.args(["bucket", "info", REMOTE_BCKT_NAME])
+		.expect_success_output("Could not get bucket info through the admin API");
+	let info = String::from_utf8(info.stdout).unwrap();
+	assert!(info.contains(REMOTE_BCKT_NAME));
+	assert!(info.contains(&ctx.key.id));
+
+	// A wrong admin token is refused
+	let output = ctx
+		.garage
+		.command()
+		.args([
+			"--endpoint",
+			&format!("http://127.0.0.1:{}", ctx.garage.admin_port),
+			"--admin-token",
+			"not-the-admin-token",
+			"bucket",
+			"list",
+		])
+		.output()
+		.unwrap();
+	assert!(!output.status.success());
+	assert!(String::from_utf8_lossy(&output.stderr).contains("Access to the admin API was denied"));
+
+	// Commands without an equivalent in the admin API are not run
+	let status = ctx
+		.garage
+		.remote_command()
+		.args(["stats"])
+		.quiet()
+		.status()
+		.unwrap();
+	assert!(!status.success());
+}
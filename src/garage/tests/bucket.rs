@@ -1,6 +1,6 @@
 use crate::common;
 use crate::common::ext::CommandExt;
-use aws_sdk_s3::operation::delete_bucket::DeleteBucketOutput;
+use aws_sdk_s3::operation::{RequestId, RequestIdExt};
 
 #[tokio::test]
 async fn test_bucket_all() {
@@ -87,7 +87,9 @@ async fn test_bucket_all() {
 			.await
 			.unwrap();
 
-		assert_eq!(r, DeleteBucketOutput::builder().build());
+		// The response contains no data, only the IDs of the request
+		assert!(r.request_id().is_some());
+		assert!(r.extended_request_id().is_some());
 	}
 	{
 		// Check bucket is deleted with List buckets
@@ -101,3 +103,56 @@ async fn test_bucket_all() {
 			.any(|x| x.name.as_ref().unwrap() == "hello"));
 	}
 }
+
+#[tokio::test]
+async fn test_bucket_quorum_policy() {
+	let ctx = common::context();
+	let bucket = ctx.create_bucket("quorum-policy");
+
+	// An unknown policy is rejected and leaves the bucket unchanged
+	let status = ctx
+		.garage
+		.command()
+		.args(["bucket", "set-quorum", &bucket, "two"])
+		.quiet()
+		.status()
+		.unwrap();
+	assert!(!status.success());
+
+	ctx.garage
+		.command()
+		.args(["bucket", "set-quorum", &bucket, "all"])
+		.quiet()
+		.expect_success_output("Could not set quorum policy");
+
+	let info = ctx
+		.garage
+		.command()
+		.args(["bucket", "info", &bucket])
+		.expect_success_output("Could not get bucket info");
+	assert!(String::from_utf8(info.stdout)
+		.unwrap()
+		.contains("Quorum policy: all"));
+
+	// Objects can still be written and read back under the stricter policy
+	ctx.client
+		.put_object()
+		.bucket(&bucket)
+		.key("hello")
+		.body(b"world".to_vec().into())
+		.send()
+		.await
+		.unwrap();
+	let o = ctx
+		.client
+		.get_object()
+		.bucket(&bucket)
+		.key("hello")
+		.send()
+		.await
+		.unwrap();
+	assert_eq!(
+		o.body.collect().await.unwrap().into_bytes().as_ref(),
+		b"world"
+	);
+}
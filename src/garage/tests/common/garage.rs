@@ -11,6 +11,8 @@ pub const DEFAULT_PORT: u16 = 49995;
 static GARAGE_TEST_SECRET: &str =
 	"c3ea8cb80333d04e208d136698b1a01ae370d463f0d435ab2177510b3478bf44";
 
+static GARAGE_TEST_ADMIN_TOKEN: &str = "garage-integ-test-admin-token";
+
 #[derive(Debug, Default, Clone)]
 pub struct Key {
 	pub name: Option<String>,
@@ -26,6 +28,7 @@ pub struct Instance {
 	pub k2v_port: u16,
 	pub web_port: u16,
 	pub admin_port: u16,
+	pub anonymous_port: u16,
 }
 
 impl Instance {
@@ -63,6 +66,7 @@ rpc_secret = "{secret}"
 [s3_api]
 s3_region = "{region}"
 api_bind_addr = "127.0.0.1:{s3_port}"
+anonymous_bind_addr = "127.0.0.1:{anonymous_port}"
 root_domain = ".s3.garage"
 
 [k2v_api]
@@ -75,15 +79,18 @@ index = "index.html"
 
 [admin]
 api_bind_addr = "127.0.0.1:{admin_port}"
+admin_token = "{admin_token}"
 "#,
 			path = path.display(),
 			secret = GARAGE_TEST_SECRET,
+			admin_token = GARAGE_TEST_ADMIN_TOKEN,
 			region = super::REGION,
 			s3_port = port,
 			k2v_port = port + 1,
 			rpc_port = port + 2,
 			web_port = port + 3,
 			admin_port = port + 4,
+			anonymous_port = port + 5,
 		);
 		fs::write(path.join("config.toml"), config).expect("Could not write garage config file");
 
@@ -108,6 +115,7 @@ api_bind_addr = "127.0.0.1:{admin_port}"
 			k2v_port: port + 1,
 			web_port: port + 3,
 			admin_port: port + 4,
+			anonymous_port: port + 5,
 		}
 	}
 
@@ -163,6 +171,17 @@ api_bind_addr = "127.0.0.1:{admin_port}"
 		command(&self.path.join("config.toml"))
 	}
 
+	/// Command running the CLI in remote mode, through the admin API
+	pub fn remote_command(&self) -> process::Command {
+		let mut command = self.command();
+		command
+			.arg("--endpoint")
+			.arg(format!("http://127.0.0.1:{}", self.admin_port))
+			.arg("--admin-token")
+			.arg(GARAGE_TEST_ADMIN_TOKEN);
+		command
+	}
+
 	pub fn node_id(&self) -> String {
 		let output = self
 			.command()
@@ -0,0 +1,46 @@
+use aws_sdk_s3::config::{Credentials, Region};
+use aws_sdk_s3::primitives::ByteStream;
+use aws_sdk_s3::{Client, Config};
+
+use garage::testing::TestCluster;
+
+#[tokio::test]
+async fn test_embedded_cluster() {
+	let cluster = TestCluster::start().await.unwrap();
+	cluster.create_bucket("test-embedded").await.unwrap();
+
+	let credentials = Credentials::new(
+		&cluster.key.access_key_id,
+		&cluster.key.secret_access_key,
+		None,
+		None,
+		"garage-embedded-test",
+	);
+	let client = Client::from_conf(
+		Config::builder()
+			.endpoint_url(&cluster.s3_endpoint)
+			.region(Region::new(cluster.region.clone()))
+			.credentials_provider(credentials)
+			.build(),
+	);
+
+	client
+		.put_object()
+		.bucket("test-embedded")
+		.key("test")
+		.body(ByteStream::from_static(b"Hello world!"))
+		.send()
+		.await
+		.unwrap();
+
+	let res = client
+		.get_object()
+		.bucket("test-embedded")
+		.key("test")
+		.send()
+		.await
+		.unwrap();
+	assert_bytes_eq!(res.body, b"Hello world!");
+
+	cluster.shutdown().await.unwrap();
+}
@@ -457,7 +457,13 @@ async fn test_listmultipart() {
 			.await
 			.unwrap();
 
-		assert_eq!(r.uploads.unwrap().len(), 5);
+		let keys = r
+			.uploads
+			.unwrap()
+			.into_iter()
+			.map(|u| u.key.unwrap())
+			.collect::<Vec<_>>();
+		assert_eq!(keys, KEYS_MULTIPART);
 		assert!(r.common_prefixes.is_none());
 	}
 	{
@@ -415,6 +415,86 @@ async fn test_uploadlistpart() {
 	}
 }
 
+#[tokio::test]
+async fn test_multipart_min_part_size() {
+	let ctx = common::context();
+	let bucket = ctx.create_bucket("minpartsize");
+
+	let small = vec![0x11; 1024];
+	let big = vec![0x22; SZ_5MB];
+
+	let up = ctx
+		.client
+		.create_multipart_upload()
+		.bucket(&bucket)
+		.key("a")
+		.send()
+		.await
+		.unwrap();
+	let uid = up.upload_id.as_ref().unwrap();
+
+	let mut etags = vec![];
+	for (part_number, data) in [(1, &small), (2, &big), (3, &small)] {
+		let p = ctx
+			.client
+			.upload_part()
+			.bucket(&bucket)
+			.key("a")
+			.upload_id(uid)
+			.part_number(part_number)
+			.body(ByteStream::from(data.clone()))
+			.send()
+			.await
+			.unwrap();
+		etags.push(p.e_tag.unwrap());
+	}
+
+	let completed = |parts: &[i32]| {
+		let mut cmp = CompletedMultipartUpload::builder();
+		for part_number in parts {
+			cmp = cmp.parts(
+				CompletedPart::builder()
+					.part_number(*part_number)
+					.e_tag(&etags[*part_number as usize - 1])
+					.build(),
+			);
+		}
+		cmp.build()
+	};
+
+	// Only the last part can be smaller than the minimum part size
+	assert!(ctx
+		.client
+		.complete_multipart_upload()
+		.bucket(&bucket)
+		.key("a")
+		.upload_id(uid)
+		.multipart_upload(completed(&[1, 2, 3]))
+		.send()
+		.await
+		.is_err());
+
+	ctx.client
+		.complete_multipart_upload()
+		.bucket(&bucket)
+		.key("a")
+		.upload_id(uid)
+		.multipart_upload(completed(&[2, 3]))
+		.send()
+		.await
+		.unwrap();
+
+	let r = ctx
+		.client
+		.head_object()
+		.bucket(&bucket)
+		.key("a")
+		.send()
+		.await
+		.unwrap();
+	assert_eq!(r.content_length, (SZ_5MB + 1024) as i64);
+}
+
 #[tokio::test]
 async fn test_uploadpartcopy() {
 	let ctx = common::context();
@@ -624,3 +704,98 @@ async fn test_uploadpartcopy() {
 	assert_eq!(real_obj.len(), exp_obj.len());
 	assert_eq!(real_obj, exp_obj);
 }
+
+#[tokio::test]
+async fn test_get_part_of_single_part_object() {
+	let ctx = common::context();
+	let bucket = ctx.create_bucket("getpartsingle");
+
+	for (key, data) in [("empty", &b""[..]), ("small", &b"hello"[..])] {
+		ctx.client
+			.put_object()
+			.bucket(&bucket)
+			.key(key)
+			.body(ByteStream::from(data.to_vec()))
+			.send()
+			.await
+			.unwrap();
+
+		let o = ctx
+			.client
+			.get_object()
+			.bucket(&bucket)
+			.key(key)
+			.part_number(1)
+			.send()
+			.await
+			.unwrap();
+		assert_eq!(o.parts_count, 1);
+		assert_eq!(o.content_length, data.len() as i64);
+		assert_bytes_eq!(o.body, data);
+
+		let r = ctx
+			.client
+			.head_object()
+			.bucket(&bucket)
+			.key(key)
+			.part_number(1)
+			.send()
+			.await
+			.unwrap();
+		assert_eq!(r.parts_count, 1);
+		assert_eq!(r.content_length, data.len() as i64);
+
+		assert!(ctx
+			.client
+			.get_object()
+			.bucket(&bucket)
+			.key(key)
+			.part_number(2)
+			.send()
+			.await
+			.is_err());
+	}
+}
+
+#[tokio::test]
+async fn test_multipart_initiator() {
+	let ctx = common::context();
+	let bucket = ctx.create_bucket("multipartinitiator");
+
+	let up = ctx
+		.client
+		.create_multipart_upload()
+		.bucket(&bucket)
+		.key("a")
+		.send()
+		.await
+		.unwrap();
+	let uid = up.upload_id.as_ref().unwrap();
+
+	// The access key that started the upload is given as its initiator,
+	// both when listing uploads and when listing the parts of an upload
+	let r = ctx
+		.client
+		.list_multipart_uploads()
+		.bucket(&bucket)
+		.send()
+		.await
+		.unwrap();
+	let uploads = r.uploads.unwrap();
+	assert_eq!(uploads.len(), 1);
+	let initiator = uploads[0].initiator.as_ref().unwrap();
+	assert_eq!(initiator.id.as_deref(), Some(ctx.key.id.as_str()));
+	assert!(initiator.display_name.is_some());
+
+	let r = ctx
+		.client
+		.list_parts()
+		.bucket(&bucket)
+		.key("a")
+		.upload_id(uid)
+		.send()
+		.await
+		.unwrap();
+	let initiator = r.initiator.unwrap();
+	assert_eq!(initiator.id.as_deref(), Some(ctx.key.id.as_str()));
+}
@@ -1,6 +1,8 @@
 use crate::common;
+use crate::common::ext::CommandExt;
 use aws_sdk_s3::primitives::ByteStream;
 use aws_sdk_s3::types::{Delete, ObjectIdentifier};
+use hyper::{Method, StatusCode};
 
 const STD_KEY: &str = "hello world";
 const CTRL_KEY: &str = "\x00\x01\x02\x00";
@@ -273,3 +275,169 @@ async fn test_deleteobject() {
 		.await
 		.unwrap();
 }
+
+#[tokio::test]
+async fn test_concurrent_appends() {
+	let ctx = common::context();
+	let bucket = ctx.create_bucket("concurrent-appends");
+	// The custom requester does not encode the path of requests
+	let key = "appended";
+
+	ctx.client
+		.put_object()
+		.bucket(&bucket)
+		.key(key)
+		.body(ByteStream::from_static(&BODY[..10]))
+		.send()
+		.await
+		.unwrap();
+
+	let append = |data: &[u8], position: usize| {
+		let mut req = ctx.custom_request.builder(bucket.clone());
+		req.method(Method::POST)
+			.path(key.to_owned())
+			.query_param("append", None::<String>)
+			.query_param("position", Some(position))
+			.body(data.to_vec());
+		async move { req.send().await.unwrap().status() }
+	};
+
+	// Two appends at the same position: at most one of them may succeed,
+	// and the data of an append that succeeded is never lost
+	let (a, b) = tokio::join!(append(&BODY[10..30], 10), append(&BODY[30..50], 10));
+	for status in [a, b] {
+		assert!(status == StatusCode::OK || status == StatusCode::CONFLICT);
+	}
+	assert!(a != StatusCode::OK || b != StatusCode::OK);
+
+	let expected = match (a, b) {
+		(StatusCode::OK, _) => [&BODY[..10], &BODY[10..30]].concat(),
+		(_, StatusCode::OK) => [&BODY[..10], &BODY[30..50]].concat(),
+		_ => BODY[..10].to_vec(),
+	};
+	let o = ctx
+		.client
+		.get_object()
+		.bucket(&bucket)
+		.key(key)
+		.send()
+		.await
+		.unwrap();
+	assert_bytes_eq!(o.body, &expected[..]);
+
+	// Appending at the new end of the object succeeds again
+	assert_eq!(append(b"end", expected.len()).await, StatusCode::OK);
+
+	// Appends are refused when concurrent appends can't be detected
+	ctx.garage
+		.command()
+		.args(["bucket", "set-quorum", &bucket, "one"])
+		.quiet()
+		.expect_success_output("Could not set quorum policy");
+	assert_eq!(
+		append(b"more", expected.len() + 3).await,
+		StatusCode::BAD_REQUEST
+	);
+}
+
+#[tokio::test]
+async fn test_rename_object() {
+	let ctx = common::context();
+	let bucket = ctx.create_bucket("rename-object");
+
+	ctx.client
+		.put_object()
+		.bucket(&bucket)
+		.key("source")
+		.body(ByteStream::from_static(BODY))
+		.send()
+		.await
+		.unwrap();
+
+	let rename = |source: &str, dest: &str, value: &str| {
+		let mut req = ctx.custom_request.builder(bucket.clone());
+		req.method(Method::PUT)
+			.path(dest.to_owned())
+			.signed_header("x-amz-copy-source", format!("{}/{}", bucket, source))
+			.signed_header("x-garage-rename", value);
+		async move { req.send().await.unwrap().status() }
+	};
+
+	// Invalid values and renaming an object to itself are refused
+	assert_eq!(
+		rename("source", "moved", "yes").await,
+		StatusCode::BAD_REQUEST
+	);
+	assert_eq!(
+		rename("source", "source", "true").await,
+		StatusCode::BAD_REQUEST
+	);
+
+	assert_eq!(rename("source", "moved", "true").await, StatusCode::OK);
+
+	// The object is only found under its new key
+	let o = ctx
+		.client
+		.get_object()
+		.bucket(&bucket)
+		.key("moved")
+		.send()
+		.await
+		.unwrap();
+	assert_bytes_eq!(o.body, BODY);
+	let r = ctx
+		.client
+		.get_object()
+		.bucket(&bucket)
+		.key("source")
+		.send()
+		.await;
+	assert!(r.is_err());
+}
+
+#[tokio::test]
+async fn test_get_during_overwrites() {
+	const SIZE: usize = 5 * 1024 * 1024 / 2;
+
+	let ctx = common::context();
+	let bucket = ctx.create_bucket("get-during-overwrites");
+
+	let put = |byte: u8| {
+		ctx.client
+			.put_object()
+			.bucket(&bucket)
+			.key(STD_KEY)
+			.body(ByteStream::from(vec![byte; SIZE]))
+			.send()
+	};
+	put(b'a').await.unwrap();
+
+	let overwrites = async {
+		for byte in b"bcdef" {
+			put(*byte).await.unwrap();
+		}
+	};
+	let reads = async {
+		let mut bodies = vec![];
+		for _ in 0..10 {
+			let o = ctx
+				.client
+				.get_object()
+				.bucket(&bucket)
+				.key(STD_KEY)
+				.send()
+				.await
+				.unwrap();
+			bodies.push(o.body.collect().await.unwrap().into_bytes());
+		}
+		bodies
+	};
+	let ((), bodies) = tokio::join!(overwrites, reads);
+
+	// Each GET returns the blocks of a single version of the object, even
+	// when the object is overwritten while its blocks are being read
+	for body in bodies {
+		assert_eq!(body.len(), SIZE);
+		assert!(body.iter().all(|b| *b == body[0]));
+	}
+}
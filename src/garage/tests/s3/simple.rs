@@ -29,3 +29,106 @@ async fn test_simple() {
 
 	assert_bytes_eq!(res.body, b"Hello world!");
 }
+
+#[tokio::test]
+async fn test_access_denied() {
+	use http::{Method, StatusCode};
+
+	let ctx = common::context();
+	let bucket = ctx.create_bucket("test-access-denied");
+
+	// Another context has its own key, that has no permission on the bucket
+	let other = common::context();
+
+	let resp = other
+		.custom_request
+		.builder(bucket.clone())
+		.method(Method::GET)
+		.path("test".to_owned())
+		.send()
+		.await
+		.unwrap();
+	assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+	let resp = other
+		.custom_request
+		.builder("test-access-denied-nonexistent".to_owned())
+		.method(Method::GET)
+		.path("test".to_owned())
+		.send()
+		.await
+		.unwrap();
+	assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+	// The key allowed on the bucket gets NoSuchKey for missing objects
+	let resp = ctx
+		.custom_request
+		.builder(bucket)
+		.method(Method::GET)
+		.path("test".to_owned())
+		.send()
+		.await
+		.unwrap();
+	assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_anonymous_endpoint() {
+	use crate::common::ext::*;
+	use aws_sdk_s3::primitives::ByteStream;
+	use http::{Request, StatusCode};
+	use hyper::{Body, Client};
+
+	let ctx = common::context();
+	let bucket = ctx.create_bucket("test-anonymous");
+
+	ctx.client
+		.put_object()
+		.bucket(&bucket)
+		.key("test")
+		.body(ByteStream::from_static(b"Hello world!"))
+		.send()
+		.await
+		.unwrap();
+
+	let client = Client::new();
+	let req = |method: &str, path: &str| {
+		Request::builder()
+			.method(method)
+			.uri(format!(
+				"http://127.0.0.1:{}/{}/{}",
+				ctx.garage.anonymous_port, bucket, path
+			))
+			.body(Body::empty())
+			.unwrap()
+	};
+
+	// Buckets are not public by default
+	let resp = client.request(req("GET", "test")).await.unwrap();
+	assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+	ctx.garage
+		.command()
+		.args(["bucket", "public-read", "--allow", &bucket])
+		.quiet()
+		.expect_success_status("Could not allow anonymous read access");
+
+	let resp = client.request(req("GET", "test")).await.unwrap();
+	assert_eq!(resp.status(), StatusCode::OK);
+	assert_eq!(
+		hyper::body::to_bytes(resp.into_body())
+			.await
+			.unwrap()
+			.as_ref(),
+		b"Hello world!"
+	);
+
+	let resp = client.request(req("GET", "?list-type=2")).await.unwrap();
+	assert_eq!(resp.status(), StatusCode::OK);
+
+	// Write operations are never allowed
+	let resp = client.request(req("PUT", "test2")).await.unwrap();
+	assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+	let resp = client.request(req("DELETE", "test")).await.unwrap();
+	assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+}
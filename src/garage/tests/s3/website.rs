@@ -201,6 +201,7 @@ async fn test_website_s3_api() {
 				.allowed_methods("GET")
 				.allowed_methods("PUT")
 				.allowed_origins("*")
+				.max_age_seconds(3600)
 				.build(),
 		)
 		.build();
@@ -303,12 +304,42 @@ async fn test_website_s3_api() {
 			resp.headers().get("access-control-allow-origin").unwrap(),
 			"*"
 		);
+		assert_eq!(
+			resp.headers().get("access-control-max-age").unwrap(),
+			"3600"
+		);
 		assert_ne!(
 			to_bytes(resp.body_mut()).await.unwrap().as_ref(),
 			BODY.as_ref()
 		);
 	}
 
+	// Test CORS headers on the error document
+	{
+		let req = Request::builder()
+			.method("GET")
+			.uri(format!(
+				"http://127.0.0.1:{}/wrong.html",
+				ctx.garage.web_port
+			))
+			.header("Host", format!("{}.web.garage", BCKT_NAME))
+			.header("Origin", "https://example.com")
+			.body(Body::empty())
+			.unwrap();
+
+		let mut resp = client.request(req).await.unwrap();
+
+		assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+		assert_eq!(
+			resp.headers().get("access-control-allow-origin").unwrap(),
+			"*"
+		);
+		assert_eq!(
+			to_bytes(resp.body_mut()).await.unwrap().as_ref(),
+			BODY_ERR.as_ref()
+		);
+	}
+
 	// Test CORS with a forbidden preflight request
 	{
 		let req = Request::builder()
@@ -6,27 +6,60 @@ use opentelemetry::sdk::{
 };
 use opentelemetry::KeyValue;
 use opentelemetry_otlp::WithExportConfig;
+use tonic::metadata::{MetadataKey, MetadataMap};
 
+use garage_util::config::AdminConfig;
 use garage_util::data::*;
 use garage_util::error::*;
 
-pub fn init_tracing(export_to: &str, node_id: Uuid) -> Result<(), Error> {
+pub fn init_tracing(config: &AdminConfig, node_id: Uuid) -> Result<(), Error> {
+	let export_to = config
+		.trace_sink
+		.as_ref()
+		.ok_or_message("trace_sink is not set")?;
 	let node_id = hex::encode(&node_id.as_slice()[..8]);
 
+	let mut metadata = MetadataMap::new();
+	for (name, value) in config.trace_headers.iter() {
+		let name = MetadataKey::from_bytes(name.to_ascii_lowercase().as_bytes())
+			.ok_or_message(format!("Invalid trace header name: {}", name))?;
+		let value = value
+			.parse()
+			.ok_or_message(format!("Invalid value for trace header {}", name))?;
+		metadata.insert(name, value);
+	}
+
+	let sampling_ratio = config.trace_sampling_ratio.unwrap_or(1.0);
+	if !(0.0..=1.0).contains(&sampling_ratio) {
+		return Err(Error::Message(format!(
+			"Invalid trace_sampling_ratio {}, must be between 0 and 1",
+			sampling_ratio
+		)));
+	}
+	// Head-based sampling: the decision is taken when the root span of a
+	// trace is created, and followed by all its children
+	let sampler = Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(sampling_ratio)));
+
+	let service_name = config
+		.trace_service_name
+		.clone()
+		.unwrap_or_else(|| "garage".to_string());
+
 	opentelemetry_otlp::new_pipeline()
 		.tracing()
 		.with_exporter(
 			opentelemetry_otlp::new_exporter()
 				.tonic()
 				.with_endpoint(export_to)
+				.with_metadata(metadata)
 				.with_timeout(Duration::from_secs(3)),
 		)
 		.with_trace_config(
 			trace::config()
 				.with_id_generator(IdGenerator::default())
-				.with_sampler(Sampler::AlwaysOn)
+				.with_sampler(sampler)
 				.with_resource(Resource::new(vec![
-					KeyValue::new("service.name", "garage"),
+					KeyValue::new("service.name", service_name),
 					KeyValue::new("service.instance.id", node_id),
 				])),
 		)
@@ -26,10 +26,8 @@ pub enum Error {
 	Header(#[from] hyper::header::ToStrError),
 	#[error("deserialization error: {0}")]
 	Deserialization(#[from] serde_json::Error),
-	#[error("invalid signature parameters: {0}")]
-	SignParameters(#[from] aws_sigv4::signing_params::BuildError),
 	#[error("could not sign request: {0}")]
-	SignRequest(#[from] aws_sigv4::http_request::SigningError),
+	SignRequest(#[from] garage_sigv4::Error),
 	#[error("request timed out")]
 	Timeout,
 	#[error("{0}")]
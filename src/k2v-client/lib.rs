@@ -1,8 +1,8 @@
 use std::collections::BTreeMap;
-use std::convert::TryInto;
-use std::time::{Duration, SystemTime};
+use std::time::Duration;
 
 use base64::prelude::*;
+use chrono::Utc;
 use log::{debug, error};
 use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
 
@@ -13,7 +13,7 @@ use hyper::{body::Bytes, Body};
 use hyper::{client::connect::HttpConnector, Client as HttpClient};
 use hyper_rustls::HttpsConnector;
 
-use aws_sigv4::http_request::{sign, SignableRequest, SigningParams, SigningSettings};
+use garage_sigv4::{sign_request, Payload, SigningParams, SERVICE_K2V};
 
 use serde::de::Error as DeError;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
@@ -24,8 +24,6 @@ pub use error::Error;
 
 const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
 const DEFAULT_POLL_TIMEOUT: Duration = Duration::from_secs(300);
-const SERVICE: &str = "k2v";
-const AMZ_CONTENT_SHA256: HeaderName = HeaderName::from_static("x-amz-content-sha256");
 const GARAGE_CAUSALITY_TOKEN: HeaderName = HeaderName::from_static("x-garage-causality-token");
 
 const STRICT_ENCODE_SET: AsciiSet = NON_ALPHANUMERIC
@@ -352,32 +350,18 @@ impl K2vClient {
 		req.headers_mut()
 			.insert(http::header::USER_AGENT, self.user_agent.clone());
 
-		use sha2::{Digest, Sha256};
-		let mut hasher = Sha256::new();
-		hasher.update(req.body());
-		let hash = hex::encode(&hasher.finalize());
-		req.headers_mut()
-			.insert(AMZ_CONTENT_SHA256, hash.try_into().unwrap());
-
 		debug!("request uri: {:?}", req.uri());
 
 		// Sign request
-		let signing_settings = SigningSettings::default();
-		let signing_params = SigningParams::builder()
-			.access_key(&self.config.aws_access_key_id)
-			.secret_key(&self.config.aws_secret_access_key)
-			.region(&self.config.region)
-			.service_name(SERVICE)
-			.time(SystemTime::now())
-			.settings(signing_settings)
-			.build()?;
-		// Convert the HTTP request into a signable request
-		let signable_request = SignableRequest::from(&req);
-
-		// Sign and then apply the signature to the request
-		let (signing_instructions, _signature) =
-			sign(signable_request, &signing_params)?.into_parts();
-		signing_instructions.apply_to_request(&mut req);
+		let signing_params = SigningParams {
+			access_key_id: &self.config.aws_access_key_id,
+			secret_access_key: &self.config.aws_secret_access_key,
+			region: &self.config.region,
+			service: SERVICE_K2V,
+			time: Utc::now(),
+		};
+		let body = req.body().clone();
+		sign_request(&mut req, &signing_params, Payload::Signed(&body))?;
 
 		// Send and wait for timeout
 		let res = tokio::select! {
@@ -0,0 +1,351 @@
+//! Evaluation of the alarms defined on the size and number of objects of
+//! buckets (e.g. with `garage bucket set-alarms`)
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use opentelemetry::{global, metrics::ValueObserver, KeyValue};
+use serde::Serialize;
+use tokio::sync::watch;
+
+use garage_util::background::*;
+use garage_util::data::*;
+use garage_util::error::Error;
+use garage_util::persister::Persister;
+
+use garage_rpc::webhook::post_json;
+
+use garage_table::*;
+
+use crate::bucket_table::*;
+use crate::garage::Garage;
+use crate::s3::object_table::{BYTES, OBJECTS};
+
+mod v090 {
+	use garage_util::data::Uuid;
+	use serde::{Deserialize, Serialize};
+	use std::collections::{BTreeMap, BTreeSet};
+
+	#[derive(Serialize, Deserialize, Default, Clone)]
+	pub struct BucketAlarmWorkerPersisted {
+		/// Alarms of each bucket whose firing was sent to the webhook,
+		/// so that it is not sent again when the node restarts
+		pub notified: BTreeMap<Uuid, BTreeSet<String>>,
+	}
+
+	impl garage_util::migrate::InitialFormat for BucketAlarmWorkerPersisted {
+		const VERSION_MARKER: &'static [u8] = b"G09bawp";
+	}
+}
+
+pub use v090::*;
+
+/// State of an alarm of a bucket at its last evaluation
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AlarmState {
+	/// `size`, `objects`, `quota_size` or `quota_objects`
+	pub alarm: &'static str,
+	pub value: u64,
+	pub threshold: u64,
+	pub firing: bool,
+}
+
+/// Evaluate the alarms of a bucket from its counters
+pub fn evaluate_alarms(
+	alarms: &BucketAlarms,
+	quotas: &BucketQuotas,
+	objects: u64,
+	bytes: u64,
+) -> Vec<AlarmState> {
+	let quota_threshold =
+		|quota: Option<u64>| Some(quota? as u128 * alarms.quota_percent? as u128 / 100);
+	[
+		("size", bytes, alarms.max_size),
+		("objects", objects, alarms.max_objects),
+		(
+			"quota_size",
+			bytes,
+			quota_threshold(quotas.max_size).map(|t| t as u64),
+		),
+		(
+			"quota_objects",
+			objects,
+			quota_threshold(quotas.max_objects).map(|t| t as u64),
+		),
+	]
+	.iter()
+	.filter_map(|&(alarm, value, threshold)| {
+		let threshold = threshold?;
+		Some(AlarmState {
+			alarm,
+			value,
+			threshold,
+			firing: value >= threshold,
+		})
+	})
+	.collect()
+}
+
+/// Body of the requests sent to the webhook
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AlarmNotification<'a> {
+	node: String,
+	bucket_id: String,
+	alarm: &'a str,
+	status: &'static str,
+	/// Not set when the alarm is resolved because it was removed
+	value: Option<u64>,
+	threshold: Option<u64>,
+}
+
+/// Worker that evaluates the alarms of all buckets from the object counters
+/// on a regular basis, and exports their state as the `bucket.alarm_firing`
+/// metric. It runs on all nodes, but only the storage node with the smallest ID
+/// among those that are up sends the alarms that start or stop firing
+/// to the webhook.
+pub struct BucketAlarmWorker {
+	garage: Arc<Garage>,
+	persister: Persister<BucketAlarmWorkerPersisted>,
+	states: Arc<Mutex<BTreeMap<Uuid, Vec<AlarmState>>>>,
+	_firing_observer: ValueObserver<u64>,
+}
+
+impl BucketAlarmWorker {
+	pub fn new(garage: Arc<Garage>) -> Self {
+		let persister = Persister::new(&garage.system.metadata_dir, "bucket_alarm_worker_state");
+		let states = Arc::new(Mutex::new(BTreeMap::<Uuid, Vec<AlarmState>>::new()));
+
+		let meter = global::meter("garage_model/bucket_alarm");
+		let observed_states = states.clone();
+		let firing_observer = meter
+			.u64_value_observer("bucket.alarm_firing", move |observer| {
+				for (bucket_id, states) in observed_states.lock().unwrap().iter() {
+					for state in states.iter() {
+						observer.observe(
+							state.firing as u64,
+							&[
+								KeyValue::new("bucket_id", hex::encode(bucket_id)),
+								KeyValue::new("alarm", state.alarm),
+							],
+						);
+					}
+				}
+			})
+			.with_description("Whether an alarm of a bucket is firing (1) or not (0)")
+			.init();
+
+		Self {
+			garage,
+			persister,
+			states,
+			_firing_observer: firing_observer,
+		}
+	}
+
+	async fn check(&mut self) -> Result<(), Error> {
+		let buckets = self
+			.garage
+			.bucket_table
+			.get_range(
+				&EmptyKey,
+				None,
+				Some(DeletedFilter::NotDeleted),
+				10000,
+				EnumerationOrder::Forward,
+			)
+			.await?;
+
+		let mut states = BTreeMap::new();
+		for bucket in buckets.iter() {
+			let params = match bucket.state.as_option() {
+				Some(p) => p,
+				None => continue,
+			};
+			if *params.alarms.get() == BucketAlarms::default() {
+				continue;
+			}
+			let counters = self
+				.garage
+				.object_counter_table
+				.table
+				.get(&bucket.id, &EmptyKey)
+				.await?
+				.map(|x| x.filtered_values(&self.garage.system.ring.borrow()))
+				.unwrap_or_default();
+			let count = |name| std::cmp::max(*counters.get(name).unwrap_or(&0), 0) as u64;
+			states.insert(
+				bucket.id,
+				evaluate_alarms(
+					params.alarms.get(),
+					params.quotas.get(),
+					count(OBJECTS),
+					count(BYTES),
+				),
+			);
+		}
+		*self.states.lock().unwrap() = states.clone();
+
+		if !self.garage.system.is_leader() {
+			return Ok(());
+		}
+		let live_config = self.garage.live_config.load_full();
+		let webhook_url = match &live_config.bucket_alarms.webhook_url {
+			Some(url) => url,
+			None => return Ok(()),
+		};
+
+		// If the webhook cannot be reached, notifications are sent again
+		// at the next evaluation
+		let persisted = self.persister.load().unwrap_or_default();
+		let mut notified = BTreeMap::<Uuid, BTreeSet<String>>::new();
+		for (bucket_id, alarms) in persisted.notified.iter() {
+			for alarm in alarms.iter() {
+				// Alarms that were removed, or whose bucket was deleted,
+				// are resolved
+				let state = states
+					.get(bucket_id)
+					.and_then(|s| s.iter().find(|s| s.alarm == alarm));
+				let firing = state.map(|s| s.firing).unwrap_or(false);
+				if firing || !self.notify(webhook_url, *bucket_id, alarm, state).await {
+					notified
+						.entry(*bucket_id)
+						.or_default()
+						.insert(alarm.clone());
+				}
+			}
+		}
+		for (bucket_id, bucket_states) in states.iter() {
+			for state in bucket_states.iter().filter(|s| s.firing) {
+				let was_notified = persisted
+					.notified
+					.get(bucket_id)
+					.map(|a| a.contains(state.alarm))
+					.unwrap_or(false);
+				if !was_notified
+					&& self
+						.notify(webhook_url, *bucket_id, state.alarm, Some(state))
+						.await
+				{
+					notified
+						.entry(*bucket_id)
+						.or_default()
+						.insert(state.alarm.to_string());
+				}
+			}
+		}
+		self.persister
+			.save(&BucketAlarmWorkerPersisted { notified })?;
+
+		Ok(())
+	}
+
+	/// Send an alarm that started or stopped firing to the webhook,
+	/// returning whether it was sent
+	async fn notify(
+		&self,
+		url: &str,
+		bucket_id: Uuid,
+		alarm: &str,
+		state: Option<&AlarmState>,
+	) -> bool {
+		let status = match state {
+			Some(s) if s.firing => "firing",
+			_ => "resolved",
+		};
+		info!("Alarm {} of bucket {:?} is {}", alarm, bucket_id, status);
+		let body = AlarmNotification {
+			node: hex::encode(self.garage.system.id),
+			bucket_id: hex::encode(bucket_id),
+			alarm,
+			status,
+			value: state.map(|s| s.value),
+			threshold: state.map(|s| s.threshold),
+		};
+		match post_json(url, &body).await {
+			Ok(()) => true,
+			Err(e) => {
+				warn!("Unable to send bucket alarm to webhook: {}", e);
+				false
+			}
+		}
+	}
+}
+
+#[async_trait]
+impl Worker for BucketAlarmWorker {
+	fn name(&self) -> String {
+		"Bucket alarms".to_string()
+	}
+
+	fn status(&self) -> WorkerStatus {
+		let states = self.states.lock().unwrap();
+		let firing = states
+			.values()
+			.flat_map(|s| s.iter())
+			.filter(|s| s.firing)
+			.count();
+		WorkerStatus {
+			freeform: vec![
+				format!("Buckets with alarms: {}", states.len()),
+				format!("Firing alarms: {}", firing),
+			],
+			..Default::default()
+		}
+	}
+
+	async fn work(&mut self, _must_exit: &mut watch::Receiver<bool>) -> Result<WorkerState, Error> {
+		self.check().await?;
+		Ok(WorkerState::Idle)
+	}
+
+	async fn wait_for_work(&mut self) -> WorkerState {
+		let interval = self
+			.garage
+			.live_config
+			.load()
+			.bucket_alarms
+			.check_interval_secs;
+		tokio::time::sleep(Duration::from_secs(interval)).await;
+		WorkerState::Busy
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_evaluate_alarms() {
+		let alarms = BucketAlarms {
+			max_size: Some(1000),
+			max_objects: None,
+			quota_percent: Some(90),
+		};
+		let quotas = BucketQuotas {
+			max_size: None,
+			max_objects: Some(100),
+			soft: false,
+		};
+
+		let firing = |objects, bytes| {
+			evaluate_alarms(&alarms, &quotas, objects, bytes)
+				.into_iter()
+				.map(|s| (s.alarm, s.firing))
+				.collect::<Vec<_>>()
+		};
+		assert_eq!(
+			firing(10, 10),
+			vec![("size", false), ("quota_objects", false)]
+		);
+		assert_eq!(
+			firing(90, 1000),
+			vec![("size", true), ("quota_objects", true)]
+		);
+
+		// Without quota_percent, quotas do not raise alarms
+		let alarms = BucketAlarms::default();
+		assert!(evaluate_alarms(&alarms, &quotas, 1000, 1000).is_empty());
+	}
+}
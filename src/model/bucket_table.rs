@@ -1,4 +1,5 @@
 use garage_table::crdt::*;
+use garage_table::replication::QuorumPolicy;
 use garage_table::*;
 use garage_util::data::*;
 use garage_util::time::*;
@@ -6,10 +7,12 @@ use garage_util::time::*;
 use crate::permission::BucketKeyPerm;
 
 mod v08 {
-	use crate::permission::BucketKeyPerm;
+	use crate::permission::{BucketKeyPerm, PendingDeletion};
+	use garage_table::replication::QuorumPolicy;
 	use garage_util::crdt;
 	use garage_util::data::Uuid;
 	use serde::{Deserialize, Serialize};
+	use std::collections::BTreeMap;
 
 	/// A bucket is a collection of objects
 	///
@@ -54,12 +57,95 @@ mod v08 {
 		/// Bucket quotas
 		#[serde(default)]
 		pub quotas: crdt::Lww<BucketQuotas>,
+		/// Quorum policy used for reads and writes of objects in this bucket
+		#[serde(default)]
+		pub quorum_policy: crdt::Lww<QuorumPolicy>,
+		/// Headers applied to objects uploaded in this bucket
+		#[serde(default)]
+		pub object_defaults: crdt::Lww<ObjectDefaults>,
+		/// Whether objects of this bucket can be read without authentication
+		/// on the anonymous S3 API endpoint
+		#[serde(default)]
+		pub public_read: crdt::Lww<bool>,
+		/// Sizes of the first data blocks of objects uploaded in this bucket,
+		/// after which blocks of the size set in the configuration are used
+		#[serde(default)]
+		pub first_block_sizes: crdt::Lww<Option<Vec<u64>>>,
+		/// Thresholds on the size and number of objects of the bucket
+		/// above which an alarm is raised
+		#[serde(default)]
+		pub alarms: crdt::Lww<BucketAlarms>,
+		/// If Some(x), objects deleted with the S3 API are kept in the trash
+		/// of the bucket for x days, and can be restored during this time
+		#[serde(default)]
+		pub trash_retention_days: crdt::Lww<Option<u64>>,
+		/// Objects of the website of this bucket that can only be read
+		/// with signed URLs
+		#[serde(default)]
+		pub website_signing: crdt::Lww<Option<WebsiteSigning>>,
+		/// Whether deleting the bucket through the admin API must be
+		/// confirmed with a second admin token
+		#[serde(default)]
+		pub deletion_protection: crdt::Lww<bool>,
+		/// Deletion requested through the admin API and waiting for confirmation
+		#[serde(default)]
+		pub pending_deletion: crdt::Lww<Option<PendingDeletion>>,
 	}
 
 	#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
 	pub struct WebsiteConfig {
 		pub index_document: String,
 		pub error_document: Option<String>,
+		/// Whether to generate a listing of the objects under a prefix
+		/// when no index document exists for it
+		#[serde(default)]
+		pub directory_listing: bool,
+		/// Redirection rules, evaluated in order
+		#[serde(default)]
+		pub routing_rules: Vec<RoutingRule>,
+		/// Error documents for specific HTTP status codes,
+		/// used instead of `error_document` for these codes
+		#[serde(default)]
+		pub error_documents: BTreeMap<u16, String>,
+		/// Cache-Control header sent for objects that don't define their own
+		#[serde(default)]
+		pub cache_control: Option<String>,
+		/// If Some(x), an Expires header set to x seconds in the future is sent
+		/// for objects that don't define their own
+		#[serde(default)]
+		pub expires_after: Option<u64>,
+	}
+
+	/// Website redirection rule
+	#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+	pub struct RoutingRule {
+		/// If Some(x), the rule applies only to keys starting with prefix x
+		pub prefix: Option<String>,
+		/// If Some(x), the rule applies only when serving the request
+		/// returned HTTP status code x
+		pub http_error_code: Option<u16>,
+		/// Where to redirect matching requests
+		pub redirect: Redirect,
+	}
+
+	#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+	pub struct Redirect {
+		/// Host name to redirect to, if different from the requested one
+		pub hostname: Option<String>,
+		/// Protocol (http or https) to redirect to
+		pub protocol: Option<String>,
+		/// HTTP status code of the redirection (default 301)
+		pub http_redirect_code: Option<u16>,
+		/// How the key is transformed when redirecting
+		pub replace_key: Option<RedirectReplaceKey>,
+	}
+
+	#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+	pub enum RedirectReplaceKey {
+		/// Replace the whole key by this one
+		Key(String),
+		/// Replace the prefix matched by the rule by this one
+		Prefix(String),
 	}
 
 	#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
@@ -70,6 +156,9 @@ mod v08 {
 		pub allow_methods: Vec<String>,
 		pub allow_headers: Vec<String>,
 		pub expose_headers: Vec<String>,
+		/// Allow requests with credentials (cookies, HTTP authentication)
+		#[serde(default)]
+		pub allow_credentials: bool,
 	}
 
 	/// Lifecycle configuration rule
@@ -114,6 +203,53 @@ mod v08 {
 		pub max_size: Option<u64>,
 		/// Maximum number of non-deleted objects in the bucket
 		pub max_objects: Option<u64>,
+		/// Soft quotas: writes that exceed the quotas are accepted,
+		/// and only reported in response headers and metrics
+		#[serde(default)]
+		pub soft: bool,
+	}
+
+	#[derive(Default, PartialEq, Eq, PartialOrd, Ord, Clone, Debug, Serialize, Deserialize)]
+	pub struct BucketAlarms {
+		/// Size in bytes above which an alarm is raised
+		pub max_size: Option<u64>,
+		/// Number of objects above which an alarm is raised
+		pub max_objects: Option<u64>,
+		/// Percentage of the quotas of the bucket above which an alarm is raised
+		pub quota_percent: Option<u64>,
+	}
+
+	/// Policies applied to the headers of objects when they are uploaded
+	#[derive(Default, PartialEq, Eq, PartialOrd, Ord, Clone, Debug, Serialize, Deserialize)]
+	pub struct ObjectDefaults {
+		/// Cache-Control header of objects uploaded without one
+		pub cache_control: Option<String>,
+		/// Content-Disposition header of objects uploaded without one
+		pub content_disposition: Option<String>,
+		/// Whether the Content-Type of objects is determined from the extension
+		/// of their key, instead of using the one given by the client
+		#[serde(default)]
+		pub content_type_from_extension: bool,
+		/// Whether the Content-Type of objects uploaded without a meaningful one
+		/// (e.g. application/octet-stream) is determined from the extension of
+		/// their key, or else from the first bytes of their content
+		#[serde(default)]
+		pub sniff_content_type: bool,
+		/// Names of x-amz-meta-* headers that are not stored
+		/// (without the x-amz-meta- prefix, in lowercase)
+		#[serde(default)]
+		pub strip_meta: Vec<String>,
+	}
+
+	/// Objects of a website that are only served for URLs signed
+	/// with a secret of the bucket
+	#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Debug, Serialize, Deserialize)]
+	pub struct WebsiteSigning {
+		/// Secret with which URLs are signed
+		pub secret: String,
+		/// Prefixes of the keys of objects that require a signed URL,
+		/// all objects of the bucket require one if empty
+		pub prefixes: Vec<String>,
 	}
 
 	impl garage_util::migrate::InitialFormat for Bucket {}
@@ -121,10 +257,42 @@ mod v08 {
 
 pub use v08::*;
 
+/// Maximum value of `expires_after` in the website configuration of a bucket,
+/// one year: HTTP servers should not send Expires dates further in the future
+pub const MAX_WEBSITE_EXPIRES_AFTER: u64 = 365 * 24 * 3600;
+
 impl AutoCrdt for BucketQuotas {
 	const WARN_IF_DIFFERENT: bool = true;
 }
 
+impl AutoCrdt for BucketAlarms {
+	const WARN_IF_DIFFERENT: bool = true;
+}
+
+impl AutoCrdt for ObjectDefaults {
+	const WARN_IF_DIFFERENT: bool = true;
+}
+
+impl AutoCrdt for WebsiteSigning {
+	const WARN_IF_DIFFERENT: bool = true;
+}
+
+impl WebsiteSigning {
+	/// Configuration with a new random secret
+	pub fn new(prefixes: Vec<String>) -> Self {
+		Self {
+			secret: hex::encode(&rand::random::<[u8; 32]>()[..]),
+			prefixes,
+		}
+	}
+
+	/// Returns true if the object with the given key can only be read
+	/// from the website with a signed URL
+	pub fn requires_signature(&self, key: &str) -> bool {
+		self.prefixes.is_empty() || self.prefixes.iter().any(|p| key.starts_with(p.as_str()))
+	}
+}
+
 impl BucketParams {
 	/// Create an empty BucketParams with no authorized keys and no website accesss
 	fn new() -> Self {
@@ -137,6 +305,15 @@ impl BucketParams {
 			cors_config: crdt::Lww::new(None),
 			lifecycle_config: crdt::Lww::new(None),
 			quotas: crdt::Lww::new(BucketQuotas::default()),
+			quorum_policy: crdt::Lww::new(QuorumPolicy::default()),
+			object_defaults: crdt::Lww::new(ObjectDefaults::default()),
+			public_read: crdt::Lww::new(false),
+			first_block_sizes: crdt::Lww::new(None),
+			alarms: crdt::Lww::new(BucketAlarms::default()),
+			trash_retention_days: crdt::Lww::new(None),
+			website_signing: crdt::Lww::new(None),
+			deletion_protection: crdt::Lww::new(false),
+			pending_deletion: crdt::Lww::new(None),
 		}
 	}
 }
@@ -153,6 +330,15 @@ impl Crdt for BucketParams {
 		self.cors_config.merge(&o.cors_config);
 		self.lifecycle_config.merge(&o.lifecycle_config);
 		self.quotas.merge(&o.quotas);
+		self.quorum_policy.merge(&o.quorum_policy);
+		self.object_defaults.merge(&o.object_defaults);
+		self.public_read.merge(&o.public_read);
+		self.first_block_sizes.merge(&o.first_block_sizes);
+		self.alarms.merge(&o.alarms);
+		self.trash_retention_days.merge(&o.trash_retention_days);
+		self.website_signing.merge(&o.website_signing);
+		self.deletion_protection.merge(&o.deletion_protection);
+		self.pending_deletion.merge(&o.pending_deletion);
 	}
 }
 
@@ -223,6 +409,52 @@ impl Bucket {
 			.map(|s| s.local_aliases.items())
 			.unwrap_or(&[])
 	}
+
+	/// Returns the quorum policy to use for objects of this bucket
+	pub fn quorum_policy(&self) -> QuorumPolicy {
+		self.params()
+			.map(|s| *s.quorum_policy.get())
+			.unwrap_or_default()
+	}
+
+	/// Returns the policies applied to the headers of uploaded objects
+	pub fn object_defaults(&self) -> ObjectDefaults {
+		self.params()
+			.map(|s| s.object_defaults.get().clone())
+			.unwrap_or_default()
+	}
+
+	/// Returns true if objects of this bucket can be read anonymously
+	pub fn public_read(&self) -> bool {
+		self.params().map(|s| *s.public_read.get()).unwrap_or(false)
+	}
+
+	/// Returns the sizes of the first data blocks of uploaded objects
+	pub fn first_block_sizes(&self) -> Vec<u64> {
+		self.params()
+			.and_then(|s| s.first_block_sizes.get().clone())
+			.unwrap_or_default()
+	}
+
+	/// Returns the number of days deleted objects are kept in the trash,
+	/// or None if the trash is disabled
+	pub fn trash_retention_days(&self) -> Option<u64> {
+		self.params().and_then(|s| *s.trash_retention_days.get())
+	}
+
+	/// Returns the configuration of signed URLs of the website of this bucket,
+	/// if some of its objects can only be read with them
+	pub fn website_signing(&self) -> Option<WebsiteSigning> {
+		self.params().and_then(|s| s.website_signing.get().clone())
+	}
+
+	/// Returns true if deleting this bucket through the admin API must be
+	/// confirmed with a second admin token
+	pub fn deletion_protection(&self) -> bool {
+		self.params()
+			.map(|s| *s.deletion_protection.get())
+			.unwrap_or(false)
+	}
 }
 
 impl Entry<EmptyKey, Uuid> for Bucket {
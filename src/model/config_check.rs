@@ -0,0 +1,649 @@
+//! Thorough validation of the configuration of a node, to report all the
+//! problems of a configuration file at once with an explanation of how to fix
+//! them, instead of failing on the first one while the node is initialized
+use std::fmt;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+
+use garage_rpc::replication_mode::ReplicationMode;
+use garage_util::config::*;
+use garage_util::error::Error;
+use garage_util::socket_address::UnixOrTCPSocketAddress;
+
+/// Severity of a problem found in the configuration
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigIssueLevel {
+	/// Garage can run with this configuration, but probably not as intended
+	Warning,
+	/// Garage cannot run with this configuration
+	Error,
+}
+
+/// A problem found in the configuration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigIssue {
+	pub level: ConfigIssueLevel,
+	/// Setting the problem is about, e.g. `s3_api.api_bind_addr`
+	pub setting: String,
+	pub message: String,
+}
+
+impl fmt::Display for ConfigIssue {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let level = match self.level {
+			ConfigIssueLevel::Warning => "warning",
+			ConfigIssueLevel::Error => "error",
+		};
+		write!(f, "{}: {}: {}", level, self.setting, self.message)
+	}
+}
+
+/// Read the configuration file and check it thoroughly. Fails if the file
+/// cannot be read or parsed, and otherwise returns the problems found.
+/// Secrets given on the command line or in the environment are taken into
+/// account by `fill_secrets`, which is called on the parsed configuration
+/// before it is checked.
+pub fn check_config_file(
+	config_file: PathBuf,
+	fill_secrets: impl FnOnce(Config) -> Config,
+) -> Result<(Config, Vec<ConfigIssue>), Error> {
+	let (config, unknown) =
+		read_config_with_unknown_settings(config_file.clone()).map_err(|e| {
+			Error::Message(format!(
+				"Unable to read configuration file {}: {}",
+				config_file.display(),
+				e
+			))
+		})?;
+	let config = fill_secrets(config);
+
+	let mut check = ConfigCheck::default();
+	for setting in unknown {
+		check.warning(
+			&setting,
+			"unknown setting, it is ignored (check its spelling and the section it is in)",
+		);
+	}
+	check.config(&config);
+	Ok((config, check.issues))
+}
+
+/// Check a parsed configuration thoroughly, and return the problems found
+pub fn check_config(config: &Config) -> Vec<ConfigIssue> {
+	let mut check = ConfigCheck::default();
+	check.config(config);
+	check.issues
+}
+
+#[derive(Default)]
+struct ConfigCheck {
+	issues: Vec<ConfigIssue>,
+}
+
+impl ConfigCheck {
+	fn error(&mut self, setting: &str, message: impl Into<String>) {
+		self.issues.push(ConfigIssue {
+			level: ConfigIssueLevel::Error,
+			setting: setting.to_string(),
+			message: message.into(),
+		});
+	}
+
+	fn warning(&mut self, setting: &str, message: impl Into<String>) {
+		self.issues.push(ConfigIssue {
+			level: ConfigIssueLevel::Warning,
+			setting: setting.to_string(),
+			message: message.into(),
+		});
+	}
+
+	fn config(&mut self, config: &Config) {
+		self.directories(config);
+		self.db_engine(config);
+		self.replication(config);
+		self.rpc(config);
+		self.bind_addrs(config);
+		self.tls_files(config);
+
+		if !(0.0..=1.0).contains(&config.rebalance.threshold) {
+			self.error("rebalance.threshold", "must be between 0 and 1");
+		}
+		if let Some(ratio) = config.admin.trace_sampling_ratio {
+			if !(0.0..=1.0).contains(&ratio) {
+				self.error("admin.trace_sampling_ratio", "must be between 0 and 1");
+			}
+		}
+		self.background(config);
+		self.disk_watermark(config);
+		self.admin_oidc(config);
+		self.ldap_sync(config);
+	}
+
+	fn ldap_sync(&mut self, config: &Config) {
+		let ldap = match &config.ldap_sync {
+			Some(ldap) => ldap,
+			None => return,
+		};
+		if !cfg!(feature = "ldap") {
+			self.error(
+				"ldap_sync",
+				"Garage was built without the ldap feature, keys are not synchronized",
+			);
+		}
+		if ldap.url.starts_with("ldap://") {
+			self.warning("ldap_sync.url", "the bind password is sent without TLS");
+		} else if !ldap.url.starts_with("ldaps://") {
+			self.error("ldap_sync.url", "must be a ldap:// or ldaps:// URL");
+		}
+		if ldap.bind_password.is_none() {
+			self.error(
+				"ldap_sync",
+				"one of bind_password or bind_password_file must be set",
+			);
+		}
+		if ldap.interval_secs == 0 {
+			self.error("ldap_sync.interval_secs", "must be at least 1");
+		}
+	}
+
+	fn admin_oidc(&mut self, config: &Config) {
+		let oidc = match &config.admin.oidc {
+			Some(oidc) => oidc,
+			None => return,
+		};
+		for (setting, url) in [
+			("admin.oidc.issuer", Some(&oidc.issuer)),
+			("admin.oidc.jwks_url", oidc.jwks_url.as_ref()),
+		] {
+			match url {
+				Some(u) if u.starts_with("https://") => (),
+				Some(u) if u.starts_with("http://") => self.warning(
+					setting,
+					"keys used to validate tokens are fetched without TLS",
+				),
+				Some(_) => self.error(setting, "must be a http:// or https:// URL"),
+				None => (),
+			}
+		}
+		if oidc.audience.is_empty() {
+			self.error("admin.oidc.audience", "must not be empty");
+		}
+		if oidc.scopes.is_empty() {
+			self.warning(
+				"admin.oidc.scopes",
+				"no claim value is mapped to a scope, tokens give no access",
+			);
+		}
+	}
+
+	fn background(&mut self, config: &Config) {
+		let bg = &config.background;
+		if bg.maintenance_threads == Some(0) {
+			self.error("background.maintenance_threads", "must be at least 1");
+		}
+		if bg.max_concurrent_maintenance == Some(0) {
+			self.error(
+				"background.max_concurrent_maintenance",
+				"must be at least 1",
+			);
+		}
+		if !(0..=19).contains(&bg.maintenance_nice) {
+			self.error("background.maintenance_nice", "must be between 0 and 19");
+		}
+		if bg.maintenance_threads.is_none() && (bg.maintenance_nice > 0 || bg.maintenance_io_idle) {
+			self.warning(
+				"background",
+				"maintenance_nice and maintenance_io_idle only apply when maintenance_threads is set",
+			);
+		}
+		if !cfg!(target_os = "linux") && (bg.maintenance_nice > 0 || bg.maintenance_io_idle) {
+			self.warning(
+				"background",
+				"maintenance_nice and maintenance_io_idle are only supported on Linux",
+			);
+		}
+	}
+
+	fn disk_watermark(&mut self, config: &Config) {
+		if let Some(usage) = config.disk_watermark.data_max_usage {
+			if !(usage > 0.0 && usage <= 1.0) {
+				self.error(
+					"disk_watermark.data_max_usage",
+					"must be between 0 (excluded) and 1",
+				);
+			}
+		}
+	}
+
+	// ---- directories ----
+
+	fn directories(&mut self, config: &Config) {
+		self.directory("metadata_dir", &config.metadata_dir);
+		match &config.data_dir {
+			DataDirEnum::Single(path) => self.directory("data_dir", path),
+			DataDirEnum::Multiple(dirs) => {
+				for (i, dir) in dirs.iter().enumerate() {
+					let setting = format!("data_dir[{}]", i);
+					match (&dir.capacity, dir.read_only) {
+						(Some(cap), false) => match cap.parse::<bytesize::ByteSize>() {
+							Ok(c) if c.as_u64() > 0 => (),
+							Ok(_) => self.error(&setting, "capacity must not be zero"),
+							Err(_) => self.error(
+								&setting,
+								format!("invalid capacity `{}`, expected e.g. `2T`", cap),
+							),
+						},
+						(None, true) => (),
+						(Some(_), true) => self.error(
+							&setting,
+							"read-only data directories must not have a capacity",
+						),
+						(None, false) => self.error(
+							&setting,
+							"data directories must have a capacity or be marked read_only",
+						),
+					}
+					if dirs[..i].iter().any(|d| d.path == dir.path) {
+						self.error(
+							&setting,
+							format!("directory {} is listed twice", dir.path.display()),
+						);
+					}
+					if dir.read_only {
+						if !dir.path.is_dir() {
+							self.error(
+								&setting,
+								format!(
+									"read-only data directory {} does not exist",
+									dir.path.display()
+								),
+							);
+						}
+					} else {
+						self.directory(&setting, &dir.path);
+					}
+				}
+				if !dirs.iter().any(|d| !d.read_only) {
+					self.error(
+						"data_dir",
+						"no data directory is writable, at least one must have a capacity",
+					);
+				}
+			}
+		}
+	}
+
+	/// Check that a directory exists and is writable, or can be created
+	fn directory(&mut self, setting: &str, path: &Path) {
+		if path.is_relative() {
+			self.warning(
+				setting,
+				format!(
+					"{} is a relative path, it depends on the directory Garage is started from",
+					path.display()
+				),
+			);
+		}
+
+		if path.exists() {
+			if !path.is_dir() {
+				self.error(
+					setting,
+					format!("{} exists but is not a directory", path.display()),
+				);
+			} else if let Err(e) = check_writable(path) {
+				self.error(
+					setting,
+					format!(
+						"directory {} is not writable by the user running Garage: {}",
+						path.display(),
+						e
+					),
+				);
+			}
+			return;
+		}
+
+		// Garage creates missing directories along with their parents
+		match path.ancestors().skip(1).find(|p| p.exists()) {
+			Some(parent) if !parent.is_dir() => self.error(
+				setting,
+				format!(
+					"{} cannot be created as {} is not a directory",
+					path.display(),
+					parent.display()
+				),
+			),
+			Some(parent) => {
+				if let Err(e) = check_writable(parent) {
+					self.error(
+						setting,
+						format!(
+							"{} does not exist and cannot be created, as {} is not writable by the user running Garage: {}",
+							path.display(),
+							parent.display(),
+							e
+						),
+					);
+				}
+			}
+			None => self.error(
+				setting,
+				format!("{} does not exist and cannot be created", path.display()),
+			),
+		}
+	}
+
+	// ---- metadata database ----
+
+	fn db_engine(&mut self, config: &Config) {
+		let available = match config.db_engine.as_str() {
+			"sled" => {
+				self.warning(
+					"db_engine",
+					"the sled engine is deprecated, migrate to lmdb or sqlite with `garage convert-db`",
+				);
+				cfg!(feature = "sled")
+			}
+			"sqlite" | "sqlite3" | "rusqlite" => cfg!(feature = "sqlite"),
+			"lmdb" | "heed" => cfg!(feature = "lmdb"),
+			e => {
+				self.error(
+					"db_engine",
+					format!("unknown engine `{}`, expected lmdb, sqlite or sled", e),
+				);
+				return;
+			}
+		};
+		if !available {
+			self.error(
+				"db_engine",
+				format!(
+					"engine `{}` is not available in this build of Garage",
+					config.db_engine
+				),
+			);
+		}
+	}
+
+	// ---- replication ----
+
+	fn replication(&mut self, config: &Config) {
+		let replication_mode = match ReplicationMode::parse(&config.replication_mode) {
+			Some(r) => r,
+			None => {
+				self.error(
+					"replication_mode",
+					format!(
+						"invalid value `{}`, expected one of none, 2, 2-dangerous, 3, 3-degraded, 3-dangerous",
+						config.replication_mode
+					),
+				);
+				return;
+			}
+		};
+
+		let has_peers = !config.bootstrap_peers.is_empty()
+			|| config.consul_discovery.is_some()
+			|| config.kubernetes_discovery.is_some();
+		match replication_mode {
+			ReplicationMode::None if has_peers => self.warning(
+				"replication_mode",
+				"data is stored on a single node although other nodes are configured \
+				(bootstrap_peers or discovery), set a replication mode of 2 or 3 to store copies",
+			),
+			ReplicationMode::TwoWayDangerous | ReplicationMode::ThreeWayDangerous => self.warning(
+				"replication_mode",
+				format!(
+						"`{}` accepts writes stored on a single node, which can lose data if that node fails",
+						config.replication_mode
+					),
+			),
+			_ => (),
+		}
+	}
+
+	// ---- RPC ----
+
+	fn rpc(&mut self, config: &Config) {
+		match &config.rpc_secret {
+			None => self.error(
+				"rpc_secret",
+				"missing, set rpc_secret or rpc_secret_file, or the GARAGE_RPC_SECRET environment variable \
+				(generate one with `openssl rand -hex 32`)",
+			),
+			Some(s) if hex::decode(s).map(|x| x.len() != 32).unwrap_or(true) => self.error(
+				"rpc_secret",
+				"must be 32 bytes encoded in hexadecimal (64 characters), generate one with `openssl rand -hex 32`",
+			),
+			Some(_) => (),
+		}
+
+		if let Some(addr) = &config.rpc_public_addr {
+			if !has_port(addr) {
+				self.error(
+					"rpc_public_addr",
+					format!(
+						"`{}` must be an address or host name with a port, e.g. `{}:3901`",
+						addr, addr
+					),
+				);
+			}
+		}
+
+		for (i, peer) in config.bootstrap_peers.iter().enumerate() {
+			let valid = match peer.split_once('@') {
+				Some((key, addr)) => {
+					hex::decode(key).map(|k| k.len() == 32).unwrap_or(false) && has_port(addr)
+				}
+				None => false,
+			};
+			if !valid {
+				self.error(
+					&format!("bootstrap_peers[{}]", i),
+					format!(
+						"`{}` must be `<node id>@<address>:<port>`, where the node id is given by `garage node id`",
+						peer
+					),
+				);
+			}
+		}
+	}
+
+	// ---- listening addresses ----
+
+	fn bind_addrs(&mut self, config: &Config) {
+		let mut addrs = vec![("rpc_bind_addr", config.rpc_bind_addr)];
+		let mut unix_addrs = vec![];
+
+		let mut add = |setting: &'static str, addr: &UnixOrTCPSocketAddress| match addr {
+			UnixOrTCPSocketAddress::TCPSocket(a) => addrs.push((setting, *a)),
+			UnixOrTCPSocketAddress::UnixSocket(p) => unix_addrs.push((setting, p.clone())),
+		};
+		if let Some(a) = &config.s3_api.api_bind_addr {
+			add("s3_api.api_bind_addr", a);
+		}
+		if let Some(a) = &config.s3_api.anonymous_bind_addr {
+			add("s3_api.anonymous_bind_addr", a);
+		}
+		if let Some(k2v) = &config.k2v_api {
+			add("k2v_api.api_bind_addr", &k2v.api_bind_addr);
+		}
+		if let Some(web) = &config.s3_web {
+			add("s3_web.bind_addr", &web.bind_addr);
+		}
+		if let Some(a) = &config.admin.api_bind_addr {
+			add("admin.api_bind_addr", a);
+		}
+		if let Some(tls) = &config.s3_api.tls {
+			addrs.push(("s3_api.tls.bind_addr", tls.bind_addr));
+		}
+		if let Some(web) = &config.s3_web {
+			if let Some(tls) = &web.tls {
+				addrs.push(("s3_web.tls.bind_addr", tls.bind_addr));
+			}
+			if let Some(acme) = &web.acme {
+				addrs.push(("s3_web.acme.tls_bind_addr", acme.tls_bind_addr));
+			}
+		}
+
+		for (i, (setting, addr)) in addrs.iter().enumerate() {
+			if let Some((other, _)) = addrs[..i].iter().find(|(_, a)| addr_conflict(*a, *addr)) {
+				self.error(
+					setting,
+					format!(
+						"address {} is also used by {}, each endpoint needs its own port",
+						addr, other
+					),
+				);
+			}
+		}
+		for (i, (setting, path)) in unix_addrs.iter().enumerate() {
+			if let Some((other, _)) = unix_addrs[..i].iter().find(|(_, p)| p == path) {
+				self.error(
+					setting,
+					format!("socket {} is also used by {}", path.display(), other),
+				);
+			}
+		}
+	}
+
+	// ---- TLS ----
+
+	fn tls_files(&mut self, config: &Config) {
+		if let Some(tls) = &config.s3_api.tls {
+			self.tls_config("s3_api.tls", tls);
+		}
+		if let Some(tls) = config.s3_web.as_ref().and_then(|w| w.tls.as_ref()) {
+			self.tls_config("s3_web.tls", tls);
+		}
+		if let Some(acme) = config.s3_web.as_ref().and_then(|w| w.acme.as_ref()) {
+			if !acme.agree_terms_of_service {
+				self.error(
+					"s3_web.acme.agree_terms_of_service",
+					"the terms of service of the ACME server must be agreed to before certificates can be obtained",
+				);
+			}
+		}
+	}
+
+	fn tls_config(&mut self, section: &str, tls: &TlsConfig) {
+		let mut files = vec![("cert_file", &tls.cert_file), ("key_file", &tls.key_file)];
+		if let Some(ca_file) = &tls.client_ca_file {
+			files.push(("client_ca_file", ca_file));
+		}
+		for (name, path) in files {
+			if let Err(e) = std::fs::File::open(path) {
+				self.error(
+					&format!("{}.{}", section, name),
+					format!("unable to read {}: {}", path.display(), e),
+				);
+			}
+		}
+
+		if !tls.client_cert_keys.is_empty() {
+			if section != "s3_api.tls" {
+				self.warning(
+					&format!("{}.client_cert_keys", section),
+					"client certificates are only mapped to access keys by the S3 API",
+				);
+			} else if tls.client_ca_file.is_none() {
+				self.error(
+					&format!("{}.client_cert_keys", section),
+					"client_ca_file must be set for client certificates to be verified",
+				);
+			}
+		}
+	}
+}
+
+/// Check that files can be created in a directory
+fn check_writable(dir: &Path) -> std::io::Result<()> {
+	let test_file = dir.join(format!(".garage-check-config-{}", std::process::id()));
+	std::fs::File::create(&test_file)?;
+	std::fs::remove_file(&test_file)
+}
+
+/// Whether an address or host name is followed by a port number
+fn has_port(addr: &str) -> bool {
+	addr.rsplit_once(':')
+		.map(|(host, port)| !host.is_empty() && port.parse::<u16>().is_ok())
+		.unwrap_or(false)
+}
+
+/// Whether two endpoints cannot listen on these addresses at the same time
+fn addr_conflict(a: SocketAddr, b: SocketAddr) -> bool {
+	a.port() == b.port() && (a.ip() == b.ip() || a.ip().is_unspecified() || b.ip().is_unspecified())
+}
+
+#[cfg(test)]
+mod tests {
+	use std::io::Write;
+
+	use super::*;
+
+	#[test]
+	fn test_check_config_file() -> Result<(), Error> {
+		let dir = mktemp::Temp::new_dir()?;
+		let mut path = dir.to_path_buf();
+		path.push("garage.toml");
+		let mut file = std::fs::File::create(&path)?;
+		writeln!(
+			file,
+			r#"
+			metadata_dir = "{0}/meta"
+			data_dir = "/proc/garage-check-config/data"
+			replication_mode = "4"
+			rpc_bind_addr = "[::]:3901"
+			rpc_public_addr = "192.168.0.1"
+			rpc_secret = "00"
+			bootstrap_peers = ["192.168.0.2:3901"]
+
+			[s3_api]
+			s3_region = "garage"
+			api_bind_addr = "127.0.0.1:3901"
+			root_domian = ".s3.garage"
+			"#,
+			dir.display()
+		)?;
+		drop(file);
+
+		let (_, issues) = check_config_file(path, |c| c)?;
+		let errors = issues
+			.iter()
+			.filter(|i| i.level == ConfigIssueLevel::Error)
+			.map(|i| i.setting.as_str())
+			.collect::<Vec<_>>();
+		assert_eq!(
+			errors,
+			vec![
+				"data_dir",
+				"replication_mode",
+				"rpc_secret",
+				"rpc_public_addr",
+				"bootstrap_peers[0]",
+				"s3_api.api_bind_addr",
+			]
+		);
+		let warnings = issues
+			.iter()
+			.filter(|i| i.level == ConfigIssueLevel::Warning)
+			.map(|i| i.setting.as_str())
+			.collect::<Vec<_>>();
+		assert_eq!(warnings, vec!["s3_api.root_domian"]);
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_addr_conflict() {
+		let addr = |s: &str| s.parse::<SocketAddr>().unwrap();
+		assert!(addr_conflict(addr("[::]:3900"), addr("127.0.0.1:3900")));
+		assert!(addr_conflict(addr("10.0.0.1:3900"), addr("10.0.0.1:3900")));
+		assert!(!addr_conflict(addr("10.0.0.1:3900"), addr("10.0.0.2:3900")));
+		assert!(!addr_conflict(addr("[::]:3900"), addr("[::]:3901")));
+		assert!(has_port("garage.example.com:3901"));
+		assert!(has_port("[fc00::1]:3901"));
+		assert!(!has_port("192.168.0.1"));
+	}
+}
@@ -0,0 +1,182 @@
+//! Tracking of the progress of moving data off a node that no longer
+//! has a storage role in the cluster layout (e.g. after `garage layout drain`)
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::select;
+use tokio::sync::watch;
+
+use garage_util::background::*;
+use garage_util::error::Error;
+use garage_util::persister::Persister;
+
+use garage_rpc::ring::Ring;
+use garage_rpc::system::DrainStatus;
+
+use garage_table::replication::TableReplication;
+use garage_table::*;
+
+use crate::garage::Garage;
+
+const DRAIN_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+mod v090 {
+	use serde::{Deserialize, Serialize};
+
+	#[derive(Serialize, Deserialize, Default, Clone)]
+	pub struct DrainWorkerPersisted {
+		/// Number of partitions and blocks that the node had to offload
+		/// when it lost its storage role
+		pub initial: Option<(u64, u64)>,
+	}
+
+	impl garage_util::migrate::InitialFormat for DrainWorkerPersisted {
+		const VERSION_MARKER: &'static [u8] = b"G09dwp";
+	}
+}
+
+pub use v090::*;
+
+pub struct DrainWorker {
+	garage: Arc<Garage>,
+	ring_recv: watch::Receiver<Arc<Ring>>,
+	persister: Persister<DrainWorkerPersisted>,
+	persisted: DrainWorkerPersisted,
+	status: Option<DrainStatus>,
+}
+
+impl DrainWorker {
+	pub fn new(garage: Arc<Garage>) -> Self {
+		let persister = Persister::new(&garage.system.metadata_dir, "drain_worker_state");
+		let persisted = persister.load().unwrap_or_default();
+		Self {
+			ring_recv: garage.system.ring.clone(),
+			garage,
+			persister,
+			persisted,
+			status: None,
+		}
+	}
+
+	fn check(&mut self) -> Result<(), Error> {
+		let my_id = self.garage.system.id;
+		let is_storage_node = matches!(
+			self.garage.system.ring.borrow().layout.node_role(&my_id),
+			Some(role) if role.capacity.is_some()
+		);
+
+		let status = if is_storage_node {
+			if self.persisted.initial.is_some() {
+				info!("Node has a storage role again, drain is cancelled");
+				self.persisted.initial = None;
+				self.persister.save(&self.persisted)?;
+			}
+			None
+		} else {
+			let garage = &self.garage;
+			let partitions_remaining = partitions_to_offload(&garage.object_table)?
+				+ partitions_to_offload(&garage.object_counter_table.table)?
+				+ partitions_to_offload(&garage.mpu_table)?
+				+ partitions_to_offload(&garage.mpu_counter_table.table)?
+				+ partitions_to_offload(&garage.version_table)?
+				+ partitions_to_offload(&garage.block_ref_table)?;
+			#[cfg(feature = "k2v")]
+			let partitions_remaining = partitions_remaining
+				+ partitions_to_offload(&garage.k2v.item_table)?
+				+ partitions_to_offload(&garage.k2v.counter_table.table)?;
+			let blocks_remaining = garage.block_manager.rc_len()? as u64;
+
+			match self.persisted.initial {
+				Some((partitions_initial, blocks_initial)) => Some(DrainStatus {
+					partitions_remaining,
+					partitions_initial,
+					blocks_remaining,
+					blocks_initial,
+				}),
+				None if partitions_remaining == 0 && blocks_remaining == 0 => None,
+				None => {
+					info!(
+						"Node no longer has a storage role, starting to track drain progress ({} partitions and {} blocks to offload)",
+						partitions_remaining, blocks_remaining
+					);
+					self.persisted.initial = Some((partitions_remaining, blocks_remaining));
+					self.persister.save(&self.persisted)?;
+					Some(DrainStatus {
+						partitions_remaining,
+						partitions_initial: partitions_remaining,
+						blocks_remaining,
+						blocks_initial: blocks_remaining,
+					})
+				}
+			}
+		};
+
+		self.status = status;
+		self.garage.system.set_drain_status(status);
+		Ok(())
+	}
+}
+
+#[async_trait]
+impl Worker for DrainWorker {
+	fn name(&self) -> String {
+		"Drain progress tracker".to_string()
+	}
+
+	fn status(&self) -> WorkerStatus {
+		match &self.status {
+			Some(st) => WorkerStatus {
+				progress: Some(format!("{:.1}%", st.progress())),
+				freeform: vec![
+					format!("Partitions remaining: {}", st.partitions_remaining),
+					format!("Blocks remaining: {}", st.blocks_remaining),
+				],
+				..Default::default()
+			},
+			None => WorkerStatus {
+				freeform: vec!["Node is not draining".to_string()],
+				..Default::default()
+			},
+		}
+	}
+
+	async fn work(&mut self, _must_exit: &mut watch::Receiver<bool>) -> Result<WorkerState, Error> {
+		self.check()?;
+		Ok(WorkerState::Idle)
+	}
+
+	async fn wait_for_work(&mut self) -> WorkerState {
+		// Check immediately when the layout changes, so that the amount of data
+		// to offload is known before data starts being moved to other nodes
+		select! {
+			_ = tokio::time::sleep(DRAIN_CHECK_INTERVAL) => (),
+			_ = self.ring_recv.changed() => (),
+		}
+		WorkerState::Busy
+	}
+}
+
+/// Count the partitions of a table for which this node still stores data,
+/// but that it should not store according to the current layout
+fn partitions_to_offload<F: TableSchema, R: TableReplication>(
+	table: &Table<F, R>,
+) -> Result<u64, Error> {
+	let my_id = table.system.id;
+	let partitions = table.data.replication.partitions();
+
+	let mut count = 0;
+	for (i, (_, begin)) in partitions.iter().enumerate() {
+		if table.data.replication.write_nodes(begin).contains(&my_id) {
+			continue;
+		}
+		let end = partitions
+			.get(i + 1)
+			.map(|(_, h)| *h)
+			.unwrap_or_else(|| [0xFFu8; 32].into());
+		if table.data.store.range(*begin..end)?.next().is_some() {
+			count += 1;
+		}
+	}
+	Ok(count)
+}
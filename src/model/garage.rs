@@ -1,5 +1,8 @@
+use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::sync::Arc;
 
+use arc_swap::ArcSwap;
 use netapp::NetworkKey;
 
 use garage_db as db;
@@ -7,6 +10,8 @@ use garage_db as db;
 use garage_util::background::*;
 use garage_util::config::*;
 use garage_util::error::*;
+use garage_util::in_flight::InFlightRequests;
+use garage_util::log_filter;
 use garage_util::persister::PersisterShared;
 
 use garage_rpc::replication_mode::ReplicationMode;
@@ -17,17 +22,32 @@ use garage_table::replication::TableFullReplication;
 use garage_table::replication::TableShardedReplication;
 use garage_table::*;
 
+use crate::bucket_alarm_worker;
+use crate::drain_worker;
+use crate::garbage_stats;
+use crate::key_expiration_worker;
+#[cfg(feature = "ldap")]
+use crate::ldap_sync_worker;
 use crate::s3::block_ref_table::*;
+use crate::s3::clone_bucket_worker;
+use crate::s3::empty_bucket_worker;
 use crate::s3::lifecycle_worker;
 use crate::s3::mpu_table::*;
 use crate::s3::object_table::*;
+use crate::s3::trash;
 use crate::s3::version_table::*;
 
 use crate::bucket_alias_table::*;
 use crate::bucket_table::*;
 use crate::helper;
 use crate::index_counter::*;
+use crate::job::JobQueue;
 use crate::key_table::*;
+use crate::key_usage::KeyUsageCounter;
+use crate::permission_group_table::*;
+use crate::repair;
+use crate::tls_certificate_table::*;
+use crate::web_usage::WebUsageCounter;
 
 #[cfg(feature = "k2v")]
 use crate::k2v::{item_table::*, rpc::*, sub::*};
@@ -36,6 +56,11 @@ use crate::k2v::{item_table::*, rpc::*, sub::*};
 pub struct Garage {
 	/// The parsed configuration Garage is running
 	pub config: Config,
+	/// The configuration in effect, which differs from `config` in the
+	/// settings changed by reloading the configuration file
+	pub live_config: ArcSwap<Config>,
+	/// The configuration file Garage was started from, if any
+	config_file: Option<ConfigFile>,
 	/// The set of background variables that can be viewed/modified at runtime
 	pub bg_vars: vars::BgVars,
 
@@ -55,6 +80,14 @@ pub struct Garage {
 	pub bucket_alias_table: Arc<Table<BucketAliasTable, TableFullReplication>>,
 	/// Table containing api keys
 	pub key_table: Arc<Table<KeyTable, TableFullReplication>>,
+	/// Table containing permission groups of api keys
+	pub permission_group_table: Arc<Table<PermissionGroupTable, TableFullReplication>>,
+	/// Counters of the usage of each access key
+	pub key_usage: Arc<KeyUsageCounter>,
+	/// Counters of the usage of the website of each bucket
+	pub web_usage: Arc<WebUsageCounter>,
+	/// Table containing TLS certificates for the web endpoint
+	pub tls_certificate_table: Arc<Table<TlsCertificateTable, TableFullReplication>>,
 
 	/// Table containing S3 objects
 	pub object_table: Arc<Table<ObjectTable, TableShardedReplication>>,
@@ -71,6 +104,17 @@ pub struct Garage {
 
 	/// Persister for lifecycle worker info
 	pub lifecycle_persister: PersisterShared<lifecycle_worker::LifecycleWorkerPersisted>,
+	/// Persister for the last garbage statistics computed on this node
+	pub garbage_stats_persister: PersisterShared<garbage_stats::GarbageStatsPersisted>,
+	/// Persister for the progress of the buckets being emptied by this node
+	pub empty_bucket_persister: PersisterShared<empty_bucket_worker::EmptyBucketWorkerPersisted>,
+	/// Persister for the progress of the copies of buckets made by this node
+	pub clone_bucket_persister: PersisterShared<clone_bucket_worker::CloneBucketWorkerPersisted>,
+
+	/// Requests being processed by the API and web servers of this node
+	pub in_flight_requests: Arc<InFlightRequests>,
+	/// Long-running operations of this node, saved to be resumed on restart
+	pub jobs: Arc<JobQueue>,
 
 	#[cfg(feature = "k2v")]
 	pub k2v: GarageK2V,
@@ -89,6 +133,10 @@ pub struct GarageK2V {
 impl Garage {
 	/// Create and run garage
 	pub fn new(config: Config) -> Result<Arc<Self>, Error> {
+		Self::new_inner(config, None)
+	}
+
+	fn new_inner(config: Config, config_file: Option<ConfigFile>) -> Result<Arc<Self>, Error> {
 		// Create meta dir and data dir if they don't exist already
 		std::fs::create_dir_all(&config.metadata_dir)
 			.ok_or_message("Unable to create Garage metadata directory")?;
@@ -253,6 +301,7 @@ impl Garage {
 			config.data_dir.clone(),
 			config.data_fsync,
 			config.compression_level,
+			config.disk_watermark.clone(),
 			data_rep_param,
 			system.clone(),
 		)?;
@@ -270,7 +319,21 @@ impl Garage {
 			&db,
 		);
 		info!("Initialize key_table_table...");
-		let key_table = Table::new(KeyTable, control_rep_param, system.clone(), &db);
+		let key_table = Table::new(KeyTable, control_rep_param.clone(), system.clone(), &db);
+		info!("Initialize permission_group_table...");
+		let permission_group_table = Table::new(
+			PermissionGroupTable,
+			control_rep_param.clone(),
+			system.clone(),
+			&db,
+		);
+		info!("Initialize key usage counter table...");
+		let key_usage = KeyUsageCounter::new(system.clone(), meta_rep_param.clone(), &db);
+		info!("Initialize web usage counter table...");
+		let web_usage = WebUsageCounter::new(system.clone(), meta_rep_param.clone(), &db);
+		info!("Initialize tls_certificate_table...");
+		let tls_certificate_table =
+			Table::new(TlsCertificateTable, control_rep_param, system.clone(), &db);
 
 		// ---- S3 tables ----
 		info!("Initialize block_ref_table...");
@@ -327,6 +390,14 @@ impl Garage {
 		let lifecycle_persister =
 			PersisterShared::new(&system.metadata_dir, "lifecycle_worker_state");
 		lifecycle_worker::register_bg_vars(&lifecycle_persister, &mut bg_vars);
+		let garbage_stats_persister = PersisterShared::new(&system.metadata_dir, "garbage_stats");
+		let empty_bucket_persister =
+			PersisterShared::new(&system.metadata_dir, "empty_bucket_worker_state");
+		let clone_bucket_persister =
+			PersisterShared::new(&system.metadata_dir, "clone_bucket_worker_state");
+
+		info!("Initialize job queue...");
+		let jobs = JobQueue::new(&db)?;
 
 		// ---- K2V ----
 		#[cfg(feature = "k2v")]
@@ -334,7 +405,9 @@ impl Garage {
 
 		// -- done --
 		Ok(Arc::new(Self {
+			live_config: ArcSwap::new(Arc::new(config.clone())),
 			config,
+			config_file,
 			bg_vars,
 			replication_mode,
 			db,
@@ -343,6 +416,10 @@ impl Garage {
 			bucket_table,
 			bucket_alias_table,
 			key_table,
+			permission_group_table,
+			key_usage,
+			web_usage,
+			tls_certificate_table,
 			object_table,
 			object_counter_table,
 			mpu_table,
@@ -350,17 +427,29 @@ impl Garage {
 			version_table,
 			block_ref_table,
 			lifecycle_persister,
+			garbage_stats_persister,
+			empty_bucket_persister,
+			clone_bucket_persister,
+			in_flight_requests: InFlightRequests::new(),
+			jobs,
 			#[cfg(feature = "k2v")]
 			k2v,
 		}))
 	}
 
+	/// Spawn the background workers of the node, and resume its jobs.
+	/// Kinds of jobs defined outside of this crate must be registered in
+	/// `self.jobs` before this is called.
 	pub fn spawn_workers(self: &Arc<Self>, bg: &BackgroundRunner) {
 		self.block_manager.spawn_workers(bg);
 
 		self.bucket_table.spawn_workers(bg);
 		self.bucket_alias_table.spawn_workers(bg);
 		self.key_table.spawn_workers(bg);
+		self.permission_group_table.spawn_workers(bg);
+		self.key_usage.spawn_workers(bg);
+		self.web_usage.spawn_workers(bg);
+		self.tls_certificate_table.spawn_workers(bg);
 
 		self.object_table.spawn_workers(bg);
 		self.object_counter_table.spawn_workers(bg);
@@ -369,15 +458,100 @@ impl Garage {
 		self.version_table.spawn_workers(bg);
 		self.block_ref_table.spawn_workers(bg);
 
+		// Jobs are resumed before the lifecycle worker records its run
+		if let Err(e) = empty_bucket_worker::EmptyBucketWorker::migrate_persisted(self) {
+			error!("Unable to move bucket deletions to the job queue: {}", e);
+		}
+		self.register_job_kinds();
+		self.jobs.resume_all(self, bg);
+
 		bg.spawn_worker(lifecycle_worker::LifecycleWorker::new(
 			self.clone(),
 			self.lifecycle_persister.clone(),
 		));
 
+		bg.spawn_worker(garbage_stats::GarbageStatsWorker::new(
+			self.clone(),
+			self.garbage_stats_persister.clone(),
+		));
+
+		for worker in clone_bucket_worker::CloneBucketWorker::resume_all(self) {
+			bg.spawn_worker(worker);
+		}
+		bg.spawn_worker(trash::TrashWorker::new(self.clone()));
+
+		bg.spawn_worker(bucket_alarm_worker::BucketAlarmWorker::new(self.clone()));
+		bg.spawn_worker(key_expiration_worker::KeyExpirationWorker::new(
+			self.clone(),
+		));
+		bg.spawn_worker(drain_worker::DrainWorker::new(self.clone()));
+		if let Some(ldap) = &self.config.ldap_sync {
+			#[cfg(feature = "ldap")]
+			bg.spawn_worker(ldap_sync_worker::LdapSyncWorker::new(
+				self.clone(),
+				ldap.clone(),
+			));
+			#[cfg(not(feature = "ldap"))]
+			error!(
+				"LDAP synchronization of keys is configured for {}, but Garage was built without the ldap feature",
+				ldap.url
+			);
+		}
+		bg.spawn_worker(garage_rpc::rebalance::RebalanceWorker::new(
+			self.system.clone(),
+		));
+
 		#[cfg(feature = "k2v")]
 		self.k2v.spawn_workers(bg);
 	}
 
+	fn register_job_kinds(&self) {
+		self.jobs.register_kind(
+			empty_bucket_worker::JOB_KIND,
+			empty_bucket_worker::EmptyBucketWorker::resume,
+		);
+		repair::register_job_kinds(&self.jobs);
+	}
+
+	/// Read the configuration file again and apply the changes to the
+	/// settings that can be changed without restarting. Nothing is applied
+	/// if the new configuration is invalid.
+	pub fn reload_config(&self) -> Result<ConfigReload, Error> {
+		let config_file = self
+			.config_file
+			.as_ref()
+			.ok_or_message("Garage was not started from a configuration file")?;
+		let (new_config, reload) = config_file.reload()?;
+		let prev_config = self.live_config.load_full();
+		let live_config = prev_config.with_reloadable_settings(&new_config);
+
+		// The log filter is only changed if it was changed in the file,
+		// to keep a filter set through the admin API otherwise
+		if live_config.log_filter != prev_config.log_filter {
+			match &live_config.log_filter {
+				Some(filter) => log_filter::set_log_filter(filter)?,
+				None => log_filter::reset_log_filter()?,
+			}
+		}
+		self.system
+			.set_rebalance_config(live_config.rebalance.clone());
+		self.live_config.store(Arc::new(live_config));
+
+		info!(
+			"Reloaded configuration file {}: {} changed settings applied, {} changed settings require a restart",
+			config_file.path().display(),
+			reload.applied.len(),
+			reload.requires_restart.len()
+		);
+		for setting in reload.requires_restart.iter() {
+			warn!(
+				"Setting {} was changed in the configuration file, but is only applied after a restart",
+				setting
+			);
+		}
+		Ok(reload)
+	}
+
 	pub fn bucket_helper(&self) -> helper::bucket::BucketHelper {
 		helper::bucket::BucketHelper(self)
 	}
@@ -385,6 +559,118 @@ impl Garage {
 	pub fn key_helper(&self) -> helper::key::KeyHelper {
 		helper::key::KeyHelper(self)
 	}
+
+	pub fn permission_group_helper(&self) -> helper::group::PermissionGroupHelper {
+		helper::group::PermissionGroupHelper(self)
+	}
+
+	pub fn object_helper(&self) -> helper::object::ObjectHelper {
+		helper::object::ObjectHelper(self)
+	}
+}
+
+/// Builder for a `Garage` instance, for programs that embed Garage
+/// as a library instead of running the `garage` binary.
+///
+/// The resulting instance is not running yet: the caller is responsible
+/// for creating a `BackgroundRunner`, calling `Garage::spawn_workers` and
+/// `System::run` like the Garage server does.
+pub struct GarageBuilder {
+	config: Config,
+	config_file: Option<ConfigFile>,
+}
+
+impl GarageBuilder {
+	/// Start building a single-node Garage instance storing its metadata
+	/// and data in the given directories. `rpc_secret` is the hex-encoded
+	/// 32-byte secret shared by all nodes of the cluster.
+	pub fn new(metadata_dir: PathBuf, data_dir: PathBuf, rpc_secret: String) -> Self {
+		Self {
+			config: Config::new(
+				metadata_dir,
+				data_dir,
+				"none",
+				SocketAddr::from(([127, 0, 0, 1], 3901)),
+				rpc_secret,
+			),
+			config_file: None,
+		}
+	}
+
+	/// Start from an existing configuration, e.g. one read from a config file
+	pub fn from_config(config: Config) -> Self {
+		Self {
+			config,
+			config_file: None,
+		}
+	}
+
+	/// Set the configuration file the configuration was read from,
+	/// so that it can be reloaded with `Garage::reload_config`
+	pub fn config_file(mut self, config_file: ConfigFile) -> Self {
+		self.config_file = Some(config_file);
+		self
+	}
+
+	/// Set the replication mode (`none`, `2`, `3`, ...)
+	pub fn replication_mode(mut self, replication_mode: &str) -> Self {
+		self.config.replication_mode = replication_mode.to_string();
+		self
+	}
+
+	/// Set the metadata database engine (`lmdb`, `sqlite` or `sled`)
+	pub fn db_engine(mut self, db_engine: &str) -> Self {
+		self.config.db_engine = db_engine.to_string();
+		self
+	}
+
+	/// Set the address to bind for inter-node RPC
+	pub fn rpc_bind_addr(mut self, addr: SocketAddr) -> Self {
+		self.config.rpc_bind_addr = addr;
+		self
+	}
+
+	/// Set the address other nodes should use to contact this node
+	pub fn rpc_public_addr(mut self, addr: String) -> Self {
+		self.config.rpc_public_addr = Some(addr);
+		self
+	}
+
+	/// Set the list of peers to connect to when starting
+	pub fn bootstrap_peers(mut self, peers: Vec<String>) -> Self {
+		self.config.bootstrap_peers = peers;
+		self
+	}
+
+	/// Set the size of data blocks
+	pub fn block_size(mut self, block_size: usize) -> Self {
+		self.config.block_size = block_size;
+		self
+	}
+
+	/// Set the zstd compression level of data blocks (`None` disables compression)
+	pub fn compression_level(mut self, level: Option<i32>) -> Self {
+		self.config.compression_level = level;
+		self
+	}
+
+	/// Set whether metadata and data writes are fsync'ed to disk
+	pub fn fsync(mut self, metadata_fsync: bool, data_fsync: bool) -> Self {
+		self.config.metadata_fsync = metadata_fsync;
+		self.config.data_fsync = data_fsync;
+		self
+	}
+
+	/// Access the configuration being built, to set options
+	/// that have no dedicated builder method
+	pub fn config_mut(&mut self) -> &mut Config {
+		&mut self.config
+	}
+
+	/// Open the database and initialize all tables
+	pub fn build(self) -> Result<Arc<Garage>, Error> {
+		Garage::new_inner(self.config, self.config_file)
+	}
 }
 
 #[cfg(feature = "k2v")]
@@ -0,0 +1,242 @@
+//! Periodic accounting of the space used on this node by data that is no
+//! longer needed: blocks waiting to be garbage collected, aborted and stale
+//! uploads, and table tombstones
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use tokio::sync::watch;
+
+use garage_util::background::*;
+use garage_util::error::Error;
+use garage_util::persister::PersisterShared;
+use garage_util::time::*;
+
+use garage_table::replication::TableReplication;
+use garage_table::*;
+
+use crate::garage::Garage;
+use crate::s3::object_table::*;
+
+/// Delay between two computations of garbage statistics
+const GARBAGE_STATS_INTERVAL: Duration = Duration::from_secs(24 * 3600);
+
+/// Multipart uploads that were started longer ago than this
+/// and are not yet completed are counted as stale
+const STALE_UPLOAD_AGE: Duration = Duration::from_secs(24 * 3600);
+
+mod v090 {
+	use serde::{Deserialize, Serialize};
+
+	#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+	pub struct GarbageStats {
+		/// Time at which the computation of these statistics was completed
+		pub computed_at: u64,
+		/// Blocks stored on this node whose reference count has dropped to zero,
+		/// that are deleted once the block GC delay has expired
+		pub unreferenced_blocks: u64,
+		pub unreferenced_blocks_bytes: u64,
+		/// Object versions of aborted uploads still stored in the object table
+		pub aborted_uploads: u64,
+		/// Multipart uploads that were started more than a day ago and are
+		/// neither completed nor aborted, and total size of their uploaded parts
+		pub stale_uploads: u64,
+		pub stale_uploads_bytes: u64,
+		/// Tombstones stored in each table, waiting for table GC
+		pub tombstones: Vec<TombstoneStats>,
+	}
+
+	#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+	pub struct TombstoneStats {
+		pub table: String,
+		pub count: u64,
+		/// Size of the encoded tombstone entries in the metadata database
+		pub bytes: u64,
+	}
+
+	#[derive(Serialize, Deserialize, Default, Clone)]
+	pub struct GarbageStatsPersisted {
+		pub last: Option<GarbageStats>,
+	}
+
+	impl garage_util::migrate::InitialFormat for GarbageStatsPersisted {
+		const VERSION_MARKER: &'static [u8] = b"G09gsp";
+	}
+}
+
+pub use v090::*;
+
+pub struct GarbageStatsWorker {
+	garage: Arc<Garage>,
+	state: State,
+	persister: PersisterShared<GarbageStatsPersisted>,
+}
+
+enum State {
+	Completed(u64),
+	Running {
+		stats: GarbageStats,
+		step: usize,
+		pos: Vec<u8>,
+	},
+}
+
+impl State {
+	fn start() -> Self {
+		State::Running {
+			stats: GarbageStats::default(),
+			step: 0,
+			pos: vec![],
+		}
+	}
+}
+
+impl GarbageStatsWorker {
+	pub fn new(garage: Arc<Garage>, persister: PersisterShared<GarbageStatsPersisted>) -> Self {
+		let state = match persister.get_with(|x| x.last.as_ref().map(|s| s.computed_at)) {
+			Some(t) => State::Completed(t),
+			None => State::start(),
+		};
+		Self {
+			garage,
+			state,
+			persister,
+		}
+	}
+}
+
+#[async_trait]
+impl Worker for GarbageStatsWorker {
+	fn name(&self) -> String {
+		"Garbage statistics".to_string()
+	}
+
+	fn status(&self) -> WorkerStatus {
+		match &self.state {
+			State::Completed(t) => WorkerStatus {
+				freeform: vec![format!("Last completed: {}", msec_to_rfc3339(*t))],
+				..Default::default()
+			},
+			State::Running { step, .. } => WorkerStatus {
+				progress: Some(format!("step {}", step + 1)),
+				..Default::default()
+			},
+		}
+	}
+
+	async fn work(&mut self, _must_exit: &mut watch::Receiver<bool>) -> Result<WorkerState, Error> {
+		let (stats, step, pos) = match &mut self.state {
+			State::Completed(_) => return Ok(WorkerState::Idle),
+			State::Running { stats, step, pos } => (stats, step, pos),
+		};
+
+		let garage = &self.garage;
+		let done = match *step {
+			0 => scan_batch(&garage.object_table, pos, stats, |stats, object| {
+				stats.aborted_uploads += object
+					.versions()
+					.iter()
+					.filter(|v| v.state == ObjectVersionState::Aborted)
+					.count() as u64;
+			})?,
+			1 => {
+				let stale_before = now_msec().saturating_sub(STALE_UPLOAD_AGE.as_millis() as u64);
+				scan_batch(&garage.mpu_table, pos, stats, |stats, mpu| {
+					if !mpu.deleted.get() && mpu.timestamp < stale_before {
+						stats.stale_uploads += 1;
+						stats.stale_uploads_bytes += mpu
+							.parts
+							.items()
+							.iter()
+							.filter_map(|(_, part)| part.size)
+							.sum::<u64>();
+					}
+				})?
+			}
+			2 => scan_batch(&garage.version_table, pos, stats, |_, _| ())?,
+			3 => scan_batch(&garage.block_ref_table, pos, stats, |_, _| ())?,
+			#[cfg(feature = "k2v")]
+			4 => scan_batch(&garage.k2v.item_table, pos, stats, |_, _| ())?,
+			_ => {
+				let (count, bytes) = garage.block_manager.unreferenced_blocks().await?;
+				stats.unreferenced_blocks = count;
+				stats.unreferenced_blocks_bytes = bytes;
+				stats.computed_at = now_msec();
+
+				info!(
+					"Garbage statistics computed: {} unreferenced blocks, {} stale uploads, {} tombstones",
+					stats.unreferenced_blocks,
+					stats.stale_uploads,
+					stats.tombstones.iter().map(|t| t.count).sum::<u64>()
+				);
+				let stats = stats.clone();
+				self.persister.set_with(|x| x.last = Some(stats.clone()))?;
+				self.state = State::Completed(stats.computed_at);
+				return Ok(WorkerState::Idle);
+			}
+		};
+
+		if done {
+			*step += 1;
+			pos.clear();
+		}
+		Ok(WorkerState::Busy)
+	}
+
+	async fn wait_for_work(&mut self) -> WorkerState {
+		if let State::Completed(t) = &self.state {
+			let next_start = t + GARBAGE_STATS_INTERVAL.as_millis() as u64;
+			let now = now_msec();
+			if now < next_start {
+				tokio::time::sleep_until(
+					(Instant::now() + Duration::from_millis(next_start - now)).into(),
+				)
+				.await;
+			}
+			self.state = State::start();
+		}
+		WorkerState::Busy
+	}
+}
+
+/// Count the tombstones among a batch of entries of a table, starting after
+/// position `pos`, and call `f` on each entry. Returns true once the whole
+/// table has been scanned.
+fn scan_batch<F, R, G>(
+	table: &Table<F, R>,
+	pos: &mut Vec<u8>,
+	stats: &mut GarbageStats,
+	mut f: G,
+) -> Result<bool, Error>
+where
+	F: TableSchema,
+	R: TableReplication,
+	G: FnMut(&mut GarbageStats, &F::E),
+{
+	if !stats.tombstones.iter().any(|t| t.table == F::TABLE_NAME) {
+		stats.tombstones.push(TombstoneStats {
+			table: F::TABLE_NAME.to_string(),
+			..Default::default()
+		});
+	}
+
+	for _ in 0..1000 {
+		let (k, v) = match table.data.store.get_gt(&pos)? {
+			Some(kv) => kv,
+			None => return Ok(true),
+		};
+		let entry = table.data.decode_entry(&v)?;
+		if entry.is_tombstone() {
+			let t = stats
+				.tombstones
+				.iter_mut()
+				.find(|t| t.table == F::TABLE_NAME)
+				.unwrap();
+			t.count += 1;
+			t.bytes += v.len() as u64;
+		}
+		f(stats, &entry);
+		*pos = k.to_vec();
+	}
+	Ok(false)
+}
@@ -1,5 +1,8 @@
+use std::collections::{BTreeMap, HashSet};
 use std::time::Duration;
 
+use serde::{Deserialize, Serialize};
+
 use garage_util::crdt::*;
 use garage_util::data::*;
 use garage_util::error::{Error as GarageError, OkOrMessage};
@@ -12,12 +15,60 @@ use crate::bucket_table::*;
 use crate::garage::Garage;
 use crate::helper::error::*;
 use crate::helper::key::KeyHelper;
+use crate::index_counter::CountedItem;
 use crate::key_table::*;
 use crate::permission::BucketKeyPerm;
 use crate::s3::object_table::*;
 
 pub struct BucketHelper<'a>(pub(crate) &'a Garage);
 
+/// Space used by the objects of a bucket stored under a common prefix
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct PrefixUsage {
+	pub objects: u64,
+	pub unfinished_uploads: u64,
+	pub bytes: u64,
+}
+
+/// Space used by the objects of a bucket, grouped by their prefix
+/// up to the first delimiter after the prefix that was scanned
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct BucketUsage {
+	/// Objects that are directly under the scanned prefix,
+	/// i.e. with no delimiter in the rest of their key
+	pub direct: PrefixUsage,
+	/// Objects grouped by common prefix
+	pub prefixes: BTreeMap<String, PrefixUsage>,
+}
+
+/// Space used by the data of the objects of a bucket, before and after
+/// deduplication of identical data blocks
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct DedupStats {
+	/// Total size of the stored versions of objects
+	pub logical_bytes: u64,
+	/// Total size of the distinct data blocks of these versions and of
+	/// their inline data
+	pub physical_bytes: u64,
+	/// Number of data blocks of these versions
+	pub blocks: u64,
+	/// Number of distinct data blocks of these versions
+	pub unique_blocks: u64,
+}
+
+impl PrefixUsage {
+	fn add(&mut self, object: &Object) {
+		for (name, count) in object.counts() {
+			match name {
+				OBJECTS => self.objects += count as u64,
+				UNFINISHED_UPLOADS => self.unfinished_uploads += count as u64,
+				BYTES => self.bytes += count as u64,
+				_ => (),
+			}
+		}
+	}
+}
+
 #[allow(clippy::ptr_arg)]
 impl<'a> BucketHelper<'a> {
 	pub async fn resolve_global_bucket_name(
@@ -543,4 +594,139 @@ impl<'a> BucketHelper<'a> {
 
 		Ok(ret)
 	}
+
+	/// Compute the space used by the objects under a given prefix of a bucket,
+	/// grouped by common prefix up to the next delimiter (as in a ListObjects
+	/// call). The whole prefix is scanned server-side, without sending the
+	/// list of objects to the caller.
+	pub async fn usage_by_prefix(
+		&self,
+		bucket_id: &Uuid,
+		prefix: &str,
+		delimiter: &str,
+	) -> Result<BucketUsage, Error> {
+		let mut ret = BucketUsage::default();
+		let mut start = Some(prefix.to_string());
+		let mut last_key = None;
+
+		loop {
+			let objects = self
+				.0
+				.object_table
+				.get_range(bucket_id, start, None, 1000, EnumerationOrder::Forward)
+				.await?;
+
+			// Each page starts with the last object of the previous page,
+			// which has already been counted
+			for object in objects.iter().filter(|o| Some(&o.key) != last_key.as_ref()) {
+				if !object.key.starts_with(prefix) {
+					return Ok(ret);
+				}
+				match common_prefix(&object.key, prefix, delimiter) {
+					Some(cp) => ret.prefixes.entry(cp.to_string()).or_default().add(object),
+					None => ret.direct.add(object),
+				}
+			}
+
+			if objects.len() < 1000 {
+				break;
+			} else {
+				last_key = Some(objects.last().unwrap().key.clone());
+				start = last_key.clone();
+			}
+		}
+
+		Ok(ret)
+	}
+
+	/// Compute the space saved by the deduplication of data blocks that are
+	/// used several times by the objects of a bucket. All objects and their
+	/// versions are scanned, and the hashes of all their blocks are kept
+	/// in memory during the scan.
+	pub async fn dedup_stats(&self, bucket_id: &Uuid) -> Result<DedupStats, Error> {
+		let mut ret = DedupStats::default();
+		let mut seen_blocks = HashSet::new();
+		let mut start = None;
+
+		loop {
+			let objects = self
+				.0
+				.object_table
+				.get_range(
+					bucket_id,
+					start.clone(),
+					None,
+					1000,
+					EnumerationOrder::Forward,
+				)
+				.await?;
+
+			let mut block_versions = vec![];
+			for object in objects.iter().filter(|o| Some(&o.key) != start.as_ref()) {
+				for v in object.versions() {
+					match &v.state {
+						ObjectVersionState::Complete(ObjectVersionData::Inline(meta, _)) => {
+							ret.logical_bytes += meta.size;
+							ret.physical_bytes += meta.size;
+						}
+						ObjectVersionState::Complete(ObjectVersionData::FirstBlock(_, _)) => {
+							block_versions.push(v.uuid);
+						}
+						_ => (),
+					}
+				}
+			}
+
+			let versions = futures::future::try_join_all(
+				block_versions
+					.iter()
+					.map(|uuid| self.0.version_table.get(uuid, &EmptyKey)),
+			)
+			.await?;
+			for version in versions.iter().flatten().filter(|v| !v.deleted.get()) {
+				for (_, block) in version.blocks.items().iter() {
+					ret.logical_bytes += block.size;
+					ret.blocks += 1;
+					if seen_blocks.insert(block.hash) {
+						ret.physical_bytes += block.size;
+						ret.unique_blocks += 1;
+					}
+				}
+			}
+
+			if objects.len() < 1000 {
+				break;
+			} else {
+				start = Some(objects.last().unwrap().key.clone());
+			}
+		}
+
+		Ok(ret)
+	}
+}
+
+/// Returns the prefix of `key` up to and including the first occurence of
+/// `delimiter` after `prefix`, if there is one
+fn common_prefix<'k>(key: &'k str, prefix: &str, delimiter: &str) -> Option<&'k str> {
+	if delimiter.is_empty() {
+		return None;
+	}
+	key[prefix.len()..]
+		.find(delimiter)
+		.map(|i| &key[..prefix.len() + i + delimiter.len()])
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_common_prefix() {
+		assert_eq!(common_prefix("a/b/c", "", "/"), Some("a/"));
+		assert_eq!(common_prefix("a/b/c", "a/", "/"), Some("a/b/"));
+		assert_eq!(common_prefix("a/b/c", "a/b/", "/"), None);
+		assert_eq!(common_prefix("a/b/c", "a", "/"), Some("a/"));
+		assert_eq!(common_prefix("a/b/c", "", ""), None);
+		assert_eq!(common_prefix("a--b--c", "a--", "--"), Some("a--b--"));
+	}
 }
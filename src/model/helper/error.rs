@@ -20,6 +20,9 @@ pub enum Error {
 
 	#[error(display = "Bucket not found: {}", _0)]
 	NoSuchBucket(String),
+
+	#[error(display = "Permission group not found: {}", _0)]
+	NoSuchPermissionGroup(String),
 }
 
 impl From<netapp::error::Error> for Error {
@@ -0,0 +1,205 @@
+use std::collections::BTreeMap;
+
+use garage_table::util::*;
+use garage_util::crdt::*;
+use garage_util::data::*;
+use garage_util::time::*;
+
+use crate::garage::Garage;
+use crate::helper::error::*;
+use crate::helper::key::KeyHelper;
+use crate::permission::BucketKeyPerm;
+use crate::permission_group_table::*;
+
+pub struct PermissionGroupHelper<'a>(pub(crate) &'a Garage);
+
+#[allow(clippy::ptr_arg)]
+impl<'a> PermissionGroupHelper<'a> {
+	/// Returns a permission group if it is present in the group table,
+	/// only if it is in non-deleted state.
+	pub async fn get_existing_group(&self, group_id: Uuid) -> Result<PermissionGroup, Error> {
+		self.0
+			.permission_group_table
+			.get(&EmptyKey, &group_id)
+			.await?
+			.filter(|g| !g.is_deleted())
+			.ok_or_else(|| Error::NoSuchPermissionGroup(hex::encode(group_id)))
+	}
+
+	/// Returns all of the permission groups that are not deleted
+	pub async fn list_groups(&self) -> Result<Vec<PermissionGroup>, Error> {
+		Ok(self
+			.0
+			.permission_group_table
+			.get_range(
+				&EmptyKey,
+				None,
+				Some(DeletedFilter::NotDeleted),
+				10000,
+				EnumerationOrder::Forward,
+			)
+			.await?)
+	}
+
+	/// Sets the permissions given by a group on a bucket,
+	/// and updates the permissions of the members of the group
+	pub async fn set_group_bucket_permissions(
+		&self,
+		group_id: Uuid,
+		bucket_id: Uuid,
+		mut perm: BucketKeyPerm,
+	) -> Result<(), Error> {
+		let mut group = self.get_existing_group(group_id).await?;
+		let params = group.params_mut().unwrap();
+
+		if let Some(p) = params.bucket_permissions.get(&bucket_id) {
+			perm.timestamp = increment_logical_clock_2(perm.timestamp, p.timestamp);
+		}
+		params.bucket_permissions.put(bucket_id, perm);
+		let members = params.member_keys().cloned().collect::<Vec<_>>();
+
+		self.0.permission_group_table.insert(&group).await?;
+		self.update_members(&members).await
+	}
+
+	/// Adds and removes access keys from the members of a group,
+	/// and updates their permissions
+	pub async fn set_group_members(
+		&self,
+		group_id: Uuid,
+		add: &[String],
+		remove: &[String],
+	) -> Result<(), Error> {
+		let key_helper = KeyHelper(self.0);
+
+		let mut group = self.get_existing_group(group_id).await?;
+		let params = group.params_mut().unwrap();
+
+		for key_id in add {
+			key_helper.get_existing_key(key_id).await?;
+			params.keys.update_in_place(key_id.clone(), true);
+		}
+		for key_id in remove {
+			params.keys.update_in_place(key_id.clone(), false);
+		}
+		self.0.permission_group_table.insert(&group).await?;
+
+		let changed = add.iter().chain(remove.iter()).cloned().collect::<Vec<_>>();
+		self.update_members(&changed).await
+	}
+
+	/// Deletes a permission group, and removes the permissions
+	/// it gave to its members
+	pub async fn delete_group(&self, group: &mut PermissionGroup) -> Result<(), Error> {
+		let members = group
+			.params()
+			.map(|p| p.member_keys().cloned().collect::<Vec<_>>())
+			.unwrap_or_default();
+
+		group.state = Deletable::delete();
+		self.0.permission_group_table.insert(group).await?;
+
+		self.update_members(&members).await
+	}
+
+	/// Copies the permissions given by the groups of access keys in the
+	/// `group_permissions` field of these keys
+	pub async fn update_members(&self, key_ids: &[String]) -> Result<(), Error> {
+		let groups = self.list_groups().await?;
+		for key_id in key_ids {
+			self.update_key(&groups, key_id).await?;
+		}
+		Ok(())
+	}
+
+	async fn update_key(&self, groups: &[PermissionGroup], key_id: &String) -> Result<(), Error> {
+		let mut key = match self.0.key_table.get(&EmptyKey, key_id).await? {
+			Some(k) if !k.is_deleted() => k,
+			_ => return Ok(()),
+		};
+		let state = key.params_mut().unwrap();
+
+		let mut new_perms = group_permissions(groups, key_id);
+		for (bucket_id, _) in state.group_permissions.items().iter() {
+			new_perms
+				.entry(*bucket_id)
+				.or_insert(BucketKeyPerm::NO_PERMISSIONS);
+		}
+
+		let mut update = Map::new();
+		for (bucket_id, mut perm) in new_perms {
+			match state.group_permissions.get(&bucket_id) {
+				Some(old) if old.same_as(&perm) => continue,
+				None if !perm.is_any() => continue,
+				Some(old) => perm.timestamp = increment_logical_clock_2(now_msec(), old.timestamp),
+				None => perm.timestamp = now_msec(),
+			}
+			update.put(bucket_id, perm);
+		}
+
+		if !update.is_empty() {
+			state.group_permissions.merge(&update);
+			self.0.key_table.insert(&key).await?;
+		}
+		Ok(())
+	}
+}
+
+/// Permissions given to an access key on each bucket by the groups
+/// it is a member of
+fn group_permissions(groups: &[PermissionGroup], key_id: &String) -> BTreeMap<Uuid, BucketKeyPerm> {
+	let mut perms = BTreeMap::<Uuid, BucketKeyPerm>::new();
+	for group in groups.iter().filter(|g| g.has_member(key_id)) {
+		for (bucket_id, perm) in group.params().unwrap().bucket_permissions.items().iter() {
+			let prev = perms
+				.get(bucket_id)
+				.copied()
+				.unwrap_or(BucketKeyPerm::NO_PERMISSIONS);
+			perms.insert(*bucket_id, prev.union(perm));
+		}
+	}
+	perms
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn perm(read: bool, write: bool) -> BucketKeyPerm {
+		BucketKeyPerm {
+			timestamp: 1,
+			allow_read: read,
+			allow_write: write,
+			allow_owner: false,
+		}
+	}
+
+	#[test]
+	fn test_group_permissions() {
+		let bucket = gen_uuid();
+		let key = "GK1".to_string();
+
+		let mut readers = PermissionGroup::new("readers".into());
+		let params = readers.params_mut().unwrap();
+		params.bucket_permissions.put(bucket, perm(true, false));
+		params.keys.update_in_place(key.clone(), true);
+
+		let mut writers = PermissionGroup::new("writers".into());
+		let params = writers.params_mut().unwrap();
+		params.bucket_permissions.put(bucket, perm(false, true));
+		params.keys.update_in_place(key.clone(), false);
+
+		let perms = group_permissions(&[readers.clone(), writers.clone()], &key);
+		assert!(perms[&bucket].same_as(&perm(true, false)));
+
+		writers
+			.params_mut()
+			.unwrap()
+			.keys
+			.update_in_place(key.clone(), true);
+		let perms = group_permissions(&[readers, writers], &key);
+		assert!(perms[&bucket].same_as(&perm(true, true)));
+
+		assert!(group_permissions(&[], &key).is_empty());
+	}
+}
@@ -1,3 +1,5 @@
 pub mod bucket;
 pub mod error;
+pub mod group;
 pub mod key;
+pub mod object;
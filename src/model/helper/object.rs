@@ -0,0 +1,110 @@
+use garage_util::data::*;
+use garage_util::time::*;
+
+use garage_table::util::*;
+
+use crate::garage::Garage;
+use crate::helper::error::*;
+use crate::s3::object_table::*;
+
+pub struct ObjectHelper<'a>(pub(crate) &'a Garage);
+
+#[allow(clippy::ptr_arg)]
+impl<'a> ObjectHelper<'a> {
+	/// Returns the last complete version of an object, if the object
+	/// exists and its last version is not a delete marker.
+	pub async fn get_object(
+		&self,
+		bucket_id: Uuid,
+		key: &String,
+	) -> Result<Option<(Object, ObjectVersion)>, Error> {
+		let object = match self.0.object_table.get(&bucket_id, key).await? {
+			Some(o) => o,
+			None => return Ok(None),
+		};
+		let last_version = object
+			.versions()
+			.iter()
+			.rev()
+			.find(|v| v.is_complete())
+			.filter(|v| v.is_data())
+			.cloned();
+		Ok(last_version.map(|v| (object, v)))
+	}
+
+	/// Lists at most `limit` objects of a bucket whose key is greater or
+	/// equal to `start`, skipping objects that have been deleted.
+	pub async fn list_objects(
+		&self,
+		bucket_id: Uuid,
+		start: Option<String>,
+		limit: usize,
+	) -> Result<Vec<Object>, Error> {
+		Ok(self
+			.0
+			.object_table
+			.get_range(
+				&bucket_id,
+				start,
+				Some(ObjectFilter::IsData),
+				limit,
+				EnumerationOrder::Forward,
+			)
+			.await?)
+	}
+
+	/// Deletes an object by adding a delete marker as its newest version.
+	/// Returns the UUID of the delete marker, or `None` if there
+	/// was no object to delete.
+	pub async fn delete_object(
+		&self,
+		bucket_id: Uuid,
+		key: &String,
+	) -> Result<Option<Uuid>, Error> {
+		let object = match self.0.object_table.get(&bucket_id, key).await? {
+			Some(o) => o,
+			None => return Ok(None),
+		};
+
+		let mut timestamp = now_msec();
+		let mut has_data = false;
+		for v in object.versions() {
+			if !matches!(
+				v.state,
+				ObjectVersionState::Aborted
+					| ObjectVersionState::Complete(ObjectVersionData::DeleteMarker)
+			) {
+				has_data = true;
+				timestamp = std::cmp::max(timestamp, v.timestamp + 1);
+			}
+		}
+		if !has_data {
+			return Ok(None);
+		}
+
+		let version_uuid = gen_uuid();
+		let object = Object::new(
+			bucket_id,
+			key.clone(),
+			vec![ObjectVersion {
+				uuid: version_uuid,
+				timestamp,
+				state: ObjectVersionState::Complete(ObjectVersionData::DeleteMarker),
+			}],
+		);
+		self.0.object_table.insert(&object).await?;
+
+		Ok(Some(version_uuid))
+	}
+}
+
+/// Returns the last complete version of an object and its metadata, or None
+/// if the object has no complete version or if its last one is a delete marker.
+pub fn current_version(object: &Object) -> Option<(&ObjectVersion, &ObjectVersionMeta)> {
+	let version = object.versions().iter().rev().find(|v| v.is_complete())?;
+	match &version.state {
+		ObjectVersionState::Complete(ObjectVersionData::Inline(meta, _))
+		| ObjectVersionState::Complete(ObjectVersionData::FirstBlock(meta, _)) => Some((version, meta)),
+		_ => None,
+	}
+}
@@ -0,0 +1,446 @@
+//! Persistent jobs: long-running background operations (bucket wipes,
+//! imports, repairs, lifecycle runs...) whose state is saved in a table of the
+//! metadata database of the node, so that they can be listed and cancelled
+//! through a single interface, and resumed when the node restarts.
+//!
+//! A job is run by a worker implementing `JobWorker`, which regularly
+//! reports its progress and a checkpoint that is saved in the job record.
+//! When the node restarts, jobs that were running are given to the resume
+//! function registered for their kind, which builds a new worker from the
+//! parameters and the last checkpoint of the job.
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use serde::{de::DeserializeOwned, Serialize};
+use tokio::sync::watch;
+
+use garage_db as db;
+
+use garage_util::background::*;
+use garage_util::data::*;
+use garage_util::error::{Error, OkOrMessage};
+use garage_util::migrate::Migrate;
+use garage_util::time::now_msec;
+
+use crate::garage::Garage;
+
+/// Minimum time between two saves of the progress of a job
+pub(crate) const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Time after which the records of finished jobs are deleted
+const FINISHED_JOB_RETENTION_MSEC: u64 = 7 * 24 * 3600 * 1000;
+
+mod v090 {
+	use garage_util::data::Uuid;
+	use serde::{Deserialize, Serialize};
+
+	#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+	pub enum JobState {
+		Running,
+		Completed,
+		Cancelled,
+		/// The job could not be resumed after a restart
+		Failed,
+	}
+
+	#[derive(Serialize, Deserialize, Clone, Debug)]
+	pub struct JobRecord {
+		pub id: Uuid,
+		/// Kind of job, which determines how its parameters and checkpoint
+		/// are interpreted, e.g. `empty-bucket`
+		pub kind: String,
+		/// Human-readable description of the job
+		pub description: String,
+		#[serde(with = "serde_bytes")]
+		pub params: Vec<u8>,
+
+		pub state: JobState,
+		pub created: u64,
+		pub updated: u64,
+		pub finished: Option<u64>,
+		pub cancel_requested: bool,
+
+		/// Fraction of the work done, between 0 and 1, if known
+		pub progress: Option<f32>,
+		/// Progress as reported by the worker running the job
+		pub status: Option<String>,
+		/// State from which the job can be resumed
+		#[serde(with = "serde_bytes")]
+		pub checkpoint: Vec<u8>,
+
+		/// Number of errors encountered by the worker, which retries the
+		/// failed step after each of them
+		pub errors: u64,
+		pub last_error: Option<String>,
+	}
+
+	impl garage_util::migrate::InitialFormat for JobRecord {
+		const VERSION_MARKER: &'static [u8] = b"G09job";
+	}
+}
+
+pub use v090::*;
+
+impl JobRecord {
+	/// Decode the parameters of the job
+	pub fn params<P: DeserializeOwned>(&self) -> Result<P, Error> {
+		Ok(rmp_serde::decode::from_slice(&self.params)?)
+	}
+
+	/// Decode the last checkpoint of the job, or None if the job did not
+	/// save a checkpoint
+	pub fn checkpoint<C: DeserializeOwned>(&self) -> Result<Option<C>, Error> {
+		if self.checkpoint.is_empty() {
+			return Ok(None);
+		}
+		Ok(Some(rmp_serde::decode::from_slice(&self.checkpoint)?))
+	}
+
+	fn finish(&mut self, state: JobState) {
+		if self.state == JobState::Running {
+			self.state = state;
+			self.finished = Some(now_msec());
+			if state == JobState::Completed {
+				self.progress = Some(1.);
+			}
+		}
+	}
+}
+
+/// Encode the checkpoint of a job, to be returned by `JobWorker::checkpoint`
+pub fn encode_checkpoint<C: Serialize>(checkpoint: &C) -> Vec<u8> {
+	rmp_serde::encode::to_vec_named(checkpoint).expect("cannot encode job checkpoint")
+}
+
+/// Worker running a persistent job
+#[async_trait]
+pub trait JobWorker: Worker {
+	/// Fraction of the work done, between 0 and 1, if known
+	fn progress(&self) -> Option<f32> {
+		None
+	}
+
+	/// State to save in the job record, from which the job can be resumed
+	/// (encoded with `encode_checkpoint`)
+	fn checkpoint(&self) -> Vec<u8> {
+		vec![]
+	}
+
+	/// Called when the job is cancelled, before the worker is stopped
+	async fn cancel(&mut self) -> Result<(), Error> {
+		Ok(())
+	}
+}
+
+/// Function building the worker that resumes a job after a restart
+pub type ResumeFn = fn(&Arc<Garage>, &JobRecord) -> Result<Box<dyn JobWorker>, Error>;
+
+/// The persistent jobs of this node
+pub struct JobQueue {
+	tree: db::Tree,
+	resume_fns: RwLock<HashMap<String, ResumeFn>>,
+	cancelled: Mutex<HashSet<Uuid>>,
+}
+
+impl JobQueue {
+	pub(crate) fn new(db: &db::Db) -> Result<Arc<Self>, Error> {
+		let tree = db
+			.open_tree("jobs")
+			.ok_or_message("Unable to open jobs tree")?;
+		Ok(Arc::new(Self {
+			tree,
+			resume_fns: RwLock::new(HashMap::new()),
+			cancelled: Mutex::new(HashSet::new()),
+		}))
+	}
+
+	/// Register the function that resumes the jobs of a kind after a restart.
+	/// Jobs that were running, and whose kind has no resume function when
+	/// `resume_all` is called, are marked as failed.
+	pub fn register_kind(&self, kind: &str, resume: ResumeFn) {
+		self.resume_fns
+			.write()
+			.unwrap()
+			.insert(kind.to_string(), resume);
+	}
+
+	/// Record a new job and spawn the worker that runs it
+	pub fn start<P: Serialize>(
+		self: &Arc<Self>,
+		bg: &BackgroundRunner,
+		kind: &str,
+		description: String,
+		params: &P,
+		worker: Box<dyn JobWorker>,
+	) -> Result<JobRecord, Error> {
+		let job = self.create(kind, description, params)?;
+		bg.spawn_worker(QueuedJob::new(self.clone(), job.id, worker));
+		Ok(job)
+	}
+
+	/// Record a new job that is run by a worker managed by the caller, which
+	/// reports its progress with `report` and `finish`
+	pub fn create<P: Serialize>(
+		&self,
+		kind: &str,
+		description: String,
+		params: &P,
+	) -> Result<JobRecord, Error> {
+		self.prune()?;
+
+		let now = now_msec();
+		let job = JobRecord {
+			id: gen_uuid(),
+			kind: kind.to_string(),
+			description,
+			params: rmp_serde::encode::to_vec_named(params)?,
+			state: JobState::Running,
+			created: now,
+			updated: now,
+			finished: None,
+			cancel_requested: false,
+			progress: None,
+			status: None,
+			checkpoint: vec![],
+			errors: 0,
+			last_error: None,
+		};
+		self.save(&job)?;
+		info!("Job {:?} started: {}", job.id, job.description);
+		Ok(job)
+	}
+
+	/// Spawn workers for the jobs that were running when the node stopped
+	pub fn resume_all(self: &Arc<Self>, garage: &Arc<Garage>, bg: &BackgroundRunner) {
+		let jobs = match self.list() {
+			Ok(jobs) => jobs,
+			Err(e) => {
+				error!("Unable to read the list of jobs: {}", e);
+				return;
+			}
+		};
+		for job in jobs {
+			if job.state != JobState::Running {
+				continue;
+			}
+			if job.cancel_requested {
+				self.cancelled.lock().unwrap().insert(job.id);
+				if let Err(e) = self.finish(job.id, JobState::Cancelled) {
+					error!("Unable to save state of job {:?}: {}", job.id, e);
+				}
+				continue;
+			}
+
+			let resume = self.resume_fns.read().unwrap().get(&job.kind).cloned();
+			let res = match resume {
+				Some(resume) => resume(garage, &job),
+				None => Err(Error::Message(format!(
+					"jobs of kind {} cannot be resumed",
+					job.kind
+				))),
+			};
+			match res {
+				Ok(worker) => {
+					info!("Resuming job {:?}: {}", job.id, job.description);
+					bg.spawn_worker(QueuedJob::new(self.clone(), job.id, worker));
+				}
+				Err(e) => {
+					warn!(
+						"Job {:?} ({}) interrupted by the restart of the node: {}",
+						job.id, job.description, e
+					);
+					let res = self.update(job.id, |j| {
+						j.last_error = Some(format!("Interrupted by a restart: {}", e));
+						j.finish(JobState::Failed);
+					});
+					if let Err(e) = res {
+						error!("Unable to save state of job {:?}: {}", job.id, e);
+					}
+				}
+			}
+		}
+	}
+
+	/// All the jobs of this node, from the oldest to the most recent
+	pub fn list(&self) -> Result<Vec<JobRecord>, Error> {
+		let mut jobs = vec![];
+		for item in self.tree.iter()? {
+			let (_, v) = item?;
+			jobs.push(JobRecord::decode(&v).ok_or_message("Cannot decode job record")?);
+		}
+		jobs.sort_by_key(|j| j.created);
+		Ok(jobs)
+	}
+
+	pub fn get(&self, id: Uuid) -> Result<Option<JobRecord>, Error> {
+		match self.tree.get(id.as_slice())? {
+			Some(v) => Ok(Some(
+				JobRecord::decode(&v).ok_or_message("Cannot decode job record")?,
+			)),
+			None => Ok(None),
+		}
+	}
+
+	/// Ask the worker running a job to stop
+	pub fn cancel(&self, id: Uuid) -> Result<JobRecord, Error> {
+		let job = self
+			.update(id, |j| {
+				if j.state == JobState::Running {
+					j.cancel_requested = true;
+				}
+			})?
+			.ok_or_else(|| Error::Message(format!("Job {:?} does not exist", id)))?;
+		if job.cancel_requested {
+			info!("Cancelling job {:?}: {}", id, job.description);
+			self.cancelled.lock().unwrap().insert(id);
+		}
+		Ok(job)
+	}
+
+	/// Whether the job was cancelled, in which case its worker must stop
+	pub fn is_cancelled(&self, id: Uuid) -> bool {
+		self.cancelled.lock().unwrap().contains(&id)
+	}
+
+	/// Save the progress of a job
+	pub fn report(
+		&self,
+		id: Uuid,
+		progress: Option<f32>,
+		status: Option<String>,
+		checkpoint: Vec<u8>,
+	) -> Result<(), Error> {
+		self.update(id, |j| {
+			j.progress = progress;
+			j.status = status;
+			j.checkpoint = checkpoint;
+		})?;
+		Ok(())
+	}
+
+	/// Mark a job as finished
+	pub fn finish(&self, id: Uuid, state: JobState) -> Result<(), Error> {
+		self.update(id, |j| j.finish(state))?;
+		self.cancelled.lock().unwrap().remove(&id);
+		Ok(())
+	}
+
+	fn record_error(&self, id: Uuid, error: &Error) -> Result<(), Error> {
+		self.update(id, |j| {
+			j.errors += 1;
+			j.last_error = Some(error.to_string());
+		})?;
+		Ok(())
+	}
+
+	fn update(&self, id: Uuid, f: impl FnOnce(&mut JobRecord)) -> Result<Option<JobRecord>, Error> {
+		let mut job = match self.get(id)? {
+			Some(j) => j,
+			None => return Ok(None),
+		};
+		f(&mut job);
+		job.updated = now_msec();
+		self.save(&job)?;
+		Ok(Some(job))
+	}
+
+	fn save(&self, job: &JobRecord) -> Result<(), Error> {
+		self.tree.insert(job.id.as_slice(), job.encode()?)?;
+		Ok(())
+	}
+
+	/// Delete the records of jobs that finished long ago
+	fn prune(&self) -> Result<(), Error> {
+		let limit = now_msec().saturating_sub(FINISHED_JOB_RETENTION_MSEC);
+		for job in self.list()? {
+			if job.finished.map(|t| t < limit).unwrap_or(false) {
+				self.tree.remove(job.id.as_slice())?;
+			}
+		}
+		Ok(())
+	}
+}
+
+// ---- worker running a job ----
+
+/// Runs the worker of a job, saving its progress in the job record and
+/// stopping it when the job is cancelled
+struct QueuedJob {
+	queue: Arc<JobQueue>,
+	id: Uuid,
+	inner: Box<dyn JobWorker>,
+	last_report: Option<Instant>,
+}
+
+impl QueuedJob {
+	fn new(queue: Arc<JobQueue>, id: Uuid, inner: Box<dyn JobWorker>) -> Self {
+		Self {
+			queue,
+			id,
+			inner,
+			last_report: None,
+		}
+	}
+
+	fn report(&mut self) -> Result<(), Error> {
+		self.last_report = Some(Instant::now());
+		let status = self.inner.status();
+		let progress = self
+			.inner
+			.progress()
+			.or_else(|| status.counters.as_ref().and_then(|c| c.done_fraction()));
+		self.queue
+			.report(self.id, progress, status.progress, self.inner.checkpoint())
+	}
+}
+
+#[async_trait]
+impl Worker for QueuedJob {
+	fn name(&self) -> String {
+		self.inner.name()
+	}
+
+	fn status(&self) -> WorkerStatus {
+		self.inner.status()
+	}
+
+	fn is_maintenance(&self) -> bool {
+		self.inner.is_maintenance()
+	}
+
+	async fn work(&mut self, must_exit: &mut watch::Receiver<bool>) -> Result<WorkerState, Error> {
+		if self.queue.is_cancelled(self.id) {
+			self.inner.cancel().await?;
+			self.report()?;
+			self.queue.finish(self.id, JobState::Cancelled)?;
+			info!("{}: cancelled", self.inner.name());
+			return Ok(WorkerState::Done);
+		}
+
+		let res = self.inner.work(must_exit).await;
+
+		match &res {
+			Ok(WorkerState::Done) => {
+				self.report()?;
+				self.queue.finish(self.id, JobState::Completed)?;
+			}
+			Err(e) => self.queue.record_error(self.id, e)?,
+			Ok(_) => {
+				if self
+					.last_report
+					.map(|t| t.elapsed() > CHECKPOINT_INTERVAL)
+					.unwrap_or(true)
+				{
+					self.report()?;
+				}
+			}
+		}
+		res
+	}
+
+	async fn wait_for_work(&mut self) -> WorkerState {
+		self.inner.wait_for_work().await
+	}
+}
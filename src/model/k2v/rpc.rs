@@ -95,7 +95,7 @@ impl K2VRpcHandler {
 		let local_timestamp_tree = db
 			.open_tree("k2v_local_timestamp")
 			.expect("Unable to open DB tree for k2v local timestamp");
-		let endpoint = system.netapp.endpoint("garage_model/k2v/Rpc".to_string());
+		let endpoint = system.endpoint("garage_model/k2v/Rpc".to_string());
 
 		let rpc_handler = Arc::new(Self {
 			system,
@@ -104,7 +104,9 @@ impl K2VRpcHandler {
 			endpoint,
 			subscriptions,
 		});
-		rpc_handler.endpoint.set_handler(rpc_handler.clone());
+		rpc_handler
+			.system
+			.set_handler(&rpc_handler.endpoint, rpc_handler.clone());
 
 		rpc_handler
 	}
@@ -338,8 +340,7 @@ impl K2VRpcHandler {
 			}
 		}
 		if errors.len() > nodes.len() - quorum {
-			let errors = errors.iter().map(|e| format!("{}", e)).collect::<Vec<_>>();
-			return Err(Error::Quorum(quorum, resps.len(), nodes.len(), errors).into());
+			return Err(Error::quorum(quorum, resps.len(), nodes.len(), &errors).into());
 		}
 
 		// Take all returned items into account to produce the response.
@@ -0,0 +1,166 @@
+//! Export of the access keys that have expired or expire soon as the
+//! `key.expires_in_seconds` metric, so that they can be renewed before the
+//! applications that use them start failing
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use opentelemetry::{global, metrics::ValueObserver, KeyValue};
+use tokio::sync::watch;
+
+use garage_util::background::*;
+use garage_util::error::Error;
+use garage_util::time::now_msec;
+
+use garage_table::*;
+
+use crate::garage::Garage;
+use crate::key_table::*;
+
+/// Interval at which the expiration of keys is checked
+const CHECK_INTERVAL: Duration = Duration::from_secs(600);
+
+/// Keys expiring within this number of days are exported by default
+const DEFAULT_METRIC_DAYS: u64 = 7;
+
+/// A key that has expired or expires soon
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExpiringKey {
+	pub key_id: String,
+	pub name: String,
+	/// Zero for expired keys
+	pub expires_in_secs: u64,
+}
+
+/// Keys that have expired, or expire before `now + within_msec`
+pub fn expiring_keys(keys: &[Key], now: u64, within_msec: u64) -> Vec<ExpiringKey> {
+	keys.iter()
+		.filter_map(|key| {
+			let expiration = key.expiration()?;
+			if expiration > now.saturating_add(within_msec) {
+				return None;
+			}
+			Some(ExpiringKey {
+				key_id: key.key_id.clone(),
+				name: key.params()?.name.get().clone(),
+				expires_in_secs: expiration.saturating_sub(now) / 1000,
+			})
+		})
+		.collect()
+}
+
+pub struct KeyExpirationWorker {
+	garage: Arc<Garage>,
+	expiring: Arc<Mutex<Vec<ExpiringKey>>>,
+	_expiring_observer: ValueObserver<u64>,
+}
+
+impl KeyExpirationWorker {
+	pub fn new(garage: Arc<Garage>) -> Self {
+		let expiring = Arc::new(Mutex::new(Vec::<ExpiringKey>::new()));
+
+		let meter = global::meter("garage_model/key_expiration");
+		let observed = expiring.clone();
+		let expiring_observer = meter
+			.u64_value_observer("key.expires_in_seconds", move |observer| {
+				for key in observed.lock().unwrap().iter() {
+					observer.observe(
+						key.expires_in_secs,
+						&[
+							KeyValue::new("key_id", key.key_id.clone()),
+							KeyValue::new("name", key.name.clone()),
+						],
+					);
+				}
+			})
+			.with_description(
+				"Seconds before access keys that expire soon can no longer be used (0 if expired)",
+			)
+			.init();
+
+		Self {
+			garage,
+			expiring,
+			_expiring_observer: expiring_observer,
+		}
+	}
+}
+
+#[async_trait]
+impl Worker for KeyExpirationWorker {
+	fn name(&self) -> String {
+		"Key expiration".to_string()
+	}
+
+	fn status(&self) -> WorkerStatus {
+		let expiring = self.expiring.lock().unwrap();
+		let expired = expiring.iter().filter(|k| k.expires_in_secs == 0).count();
+		WorkerStatus {
+			freeform: vec![
+				format!("Expired keys: {}", expired),
+				format!("Keys expiring soon: {}", expiring.len() - expired),
+			],
+			..Default::default()
+		}
+	}
+
+	async fn work(&mut self, _must_exit: &mut watch::Receiver<bool>) -> Result<WorkerState, Error> {
+		let keys = self
+			.garage
+			.key_table
+			.get_range(
+				&EmptyKey,
+				None,
+				Some(KeyFilter::Deleted(DeletedFilter::NotDeleted)),
+				10000,
+				EnumerationOrder::Forward,
+			)
+			.await?;
+		let days = self
+			.garage
+			.config
+			.admin
+			.key_expiration_metric_days
+			.unwrap_or(DEFAULT_METRIC_DAYS);
+		*self.expiring.lock().unwrap() = expiring_keys(&keys, now_msec(), days * 24 * 3600 * 1000);
+		Ok(WorkerState::Idle)
+	}
+
+	async fn wait_for_work(&mut self) -> WorkerState {
+		tokio::time::sleep(CHECK_INTERVAL).await;
+		WorkerState::Busy
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_expiring_keys() {
+		let mut keys = vec![Key::new("never"), Key::new("soon"), Key::new("later")];
+		let now = 1_000_000_000;
+		let day = 24 * 3600 * 1000;
+		keys[1]
+			.params_mut()
+			.unwrap()
+			.expiration
+			.update(Some(now + day));
+		keys[2]
+			.params_mut()
+			.unwrap()
+			.expiration
+			.update(Some(now + 30 * day));
+
+		let expiring = expiring_keys(&keys, now, 7 * day);
+		assert_eq!(expiring.len(), 1);
+		assert_eq!(expiring[0].name, "soon");
+		assert_eq!(expiring[0].expires_in_secs, 24 * 3600);
+
+		let expiring = expiring_keys(&keys, now + 2 * day, 0);
+		assert_eq!(expiring.len(), 1);
+		assert_eq!(expiring[0].expires_in_secs, 0);
+		assert!(keys[1].is_expired(now + 2 * day));
+		assert!(!keys[2].is_expired(now + 2 * day));
+	}
+}
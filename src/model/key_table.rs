@@ -49,7 +49,7 @@ pub(crate) mod v05 {
 
 mod v08 {
 	use super::v05;
-	use crate::permission::BucketKeyPerm;
+	use crate::permission::{BucketKeyPerm, PendingDeletion};
 	use garage_util::crdt;
 	use garage_util::data::Uuid;
 	use serde::{Deserialize, Serialize};
@@ -84,6 +84,39 @@ mod v08 {
 		/// A key can have a local view of buckets names it is
 		/// the only one to see, this is the namespace for these aliases
 		pub local_aliases: crdt::LwwMap<String, Option<Uuid>>,
+
+		/// Time (in msec since epoch) after which requests signed with
+		/// the key are refused
+		#[serde(default)]
+		pub expiration: crdt::Lww<Option<u64>>,
+
+		/// Whether deleting the key through the admin API must be
+		/// confirmed with a second admin token
+		#[serde(default)]
+		pub deletion_protection: crdt::Lww<bool>,
+
+		/// Deletion requested through the admin API and waiting for confirmation
+		#[serde(default)]
+		pub pending_deletion: crdt::Lww<Option<PendingDeletion>>,
+
+		/// Permissions given to the key by the permission groups it is
+		/// a member of, copied from the groups when they are modified
+		#[serde(default)]
+		pub group_permissions: crdt::Map<Uuid, BucketKeyPerm>,
+
+		/// User of the LDAP directory for which the key was provisioned
+		#[serde(default)]
+		pub ldap_user: crdt::Lww<Option<String>>,
+
+		/// Whether requests signed with the key are refused, e.g. because
+		/// its LDAP user is no longer a member of the synchronized group
+		#[serde(default)]
+		pub disabled: crdt::Lww<bool>,
+
+		/// Whether the secret of a provisioned key can still be retrieved
+		/// once through the admin API
+		#[serde(default)]
+		pub secret_pending: crdt::Lww<bool>,
 	}
 
 	impl garage_util::migrate::Migrate for Key {
@@ -104,6 +137,13 @@ mod v08 {
 					allow_create_bucket: crdt::Lww::new(false),
 					authorized_buckets: crdt::Map::new(),
 					local_aliases: crdt::LwwMap::new(),
+					expiration: crdt::Lww::new(None),
+					deletion_protection: crdt::Lww::new(false),
+					pending_deletion: crdt::Lww::new(None),
+					group_permissions: crdt::Map::new(),
+					ldap_user: crdt::Lww::new(None),
+					disabled: crdt::Lww::new(false),
+					secret_pending: crdt::Lww::new(false),
 				})
 			};
 			Key {
@@ -124,6 +164,13 @@ impl KeyParams {
 			allow_create_bucket: crdt::Lww::new(false),
 			authorized_buckets: crdt::Map::new(),
 			local_aliases: crdt::LwwMap::new(),
+			expiration: crdt::Lww::new(None),
+			deletion_protection: crdt::Lww::new(false),
+			pending_deletion: crdt::Lww::new(None),
+			group_permissions: crdt::Map::new(),
+			ldap_user: crdt::Lww::new(None),
+			disabled: crdt::Lww::new(false),
+			secret_pending: crdt::Lww::new(false),
 		}
 	}
 }
@@ -134,6 +181,13 @@ impl Crdt for KeyParams {
 		self.allow_create_bucket.merge(&o.allow_create_bucket);
 		self.authorized_buckets.merge(&o.authorized_buckets);
 		self.local_aliases.merge(&o.local_aliases);
+		self.expiration.merge(&o.expiration);
+		self.deletion_protection.merge(&o.deletion_protection);
+		self.pending_deletion.merge(&o.pending_deletion);
+		self.group_permissions.merge(&o.group_permissions);
+		self.ldap_user.merge(&o.ldap_user);
+		self.disabled.merge(&o.disabled);
+		self.secret_pending.merge(&o.secret_pending);
 	}
 }
 
@@ -187,14 +241,72 @@ impl Key {
 		self.state.as_option_mut()
 	}
 
-	/// Get permissions for a bucket
+	/// Time (in msec since epoch) after which the key can no longer be used
+	pub fn expiration(&self) -> Option<u64> {
+		self.params().and_then(|p| *p.expiration.get())
+	}
+
+	/// Check if the key has expired at the given time (in msec since epoch)
+	pub fn is_expired(&self, now: u64) -> bool {
+		self.expiration().map(|t| now >= t).unwrap_or(false)
+	}
+
+	/// Returns true if requests signed with the key are refused
+	pub fn is_disabled(&self) -> bool {
+		self.params().map(|p| *p.disabled.get()).unwrap_or(false)
+	}
+
+	/// User of the LDAP directory for which the key was provisioned
+	pub fn ldap_user(&self) -> Option<&String> {
+		self.params().and_then(|p| p.ldap_user.get().as_ref())
+	}
+
+	/// Returns true if deleting this key through the admin API must be
+	/// confirmed with a second admin token
+	pub fn deletion_protection(&self) -> bool {
+		self.params()
+			.map(|p| *p.deletion_protection.get())
+			.unwrap_or(false)
+	}
+
+	/// Get permissions for a bucket, given to the key directly
+	/// or through its permission groups
 	pub fn bucket_permissions(&self, bucket: &Uuid) -> BucketKeyPerm {
+		let own = self.own_bucket_permissions(bucket);
+		match self.params().and_then(|p| p.group_permissions.get(bucket)) {
+			Some(group) => own.union(group),
+			None => own,
+		}
+	}
+
+	/// Get permissions for a bucket given to the key directly,
+	/// not through its permission groups
+	pub fn own_bucket_permissions(&self, bucket: &Uuid) -> BucketKeyPerm {
 		self.params()
 			.and_then(|params| params.authorized_buckets.get(bucket))
 			.cloned()
 			.unwrap_or(BucketKeyPerm::NO_PERMISSIONS)
 	}
 
+	/// Returns the IDs of the buckets on which the key has some permissions,
+	/// directly or through its permission groups
+	pub fn authorized_bucket_ids(&self) -> Vec<Uuid> {
+		let mut ids = match self.params() {
+			Some(p) => p
+				.authorized_buckets
+				.items()
+				.iter()
+				.chain(p.group_permissions.items().iter())
+				.filter(|(_, perm)| perm.is_any())
+				.map(|(id, _)| *id)
+				.collect::<Vec<_>>(),
+			None => vec![],
+		};
+		ids.sort();
+		ids.dedup();
+		ids
+	}
+
 	/// Check if `Key` is allowed to read in bucket
 	pub fn allow_read(&self, bucket: &Uuid) -> bool {
 		self.bucket_permissions(bucket).allow_read
@@ -0,0 +1,166 @@
+//! Accounting of the requests made and of the bytes transferred with each
+//! access key, in windows of one hour, e.g. for billing
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::select;
+use tokio::sync::watch;
+
+use garage_db as db;
+
+use garage_rpc::system::System;
+use garage_util::background::*;
+use garage_util::error::*;
+use garage_util::time::*;
+
+use garage_table::replication::TableShardedReplication;
+
+use crate::index_counter::*;
+
+pub const REQUESTS: &str = "requests";
+pub const BYTES_RECEIVED: &str = "bytes_received";
+pub const BYTES_SENT: &str = "bytes_sent";
+
+/// Duration of the time windows in which usage is accounted
+pub const USAGE_WINDOW_MSEC: u64 = 3600 * 1000;
+
+/// Interval at which usage accounted in memory is written to the counter table
+const FLUSH_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Usage of an access key during a time window, that was recorded by this node
+/// since the last flush. It is counted as an increment in the key usage
+/// counter table.
+#[derive(Clone, PartialEq)]
+pub struct KeyUsage {
+	key_id: String,
+	window: String,
+	requests: u64,
+	bytes_received: u64,
+	bytes_sent: u64,
+}
+
+impl CountedItem for KeyUsage {
+	const COUNTER_TABLE_NAME: &'static str = "key_usage_counter";
+
+	// Partition key = access key id
+	type CP = String;
+	// Sort key = start of the time window
+	type CS = String;
+
+	fn counter_partition_key(&self) -> &String {
+		&self.key_id
+	}
+	fn counter_sort_key(&self) -> &String {
+		&self.window
+	}
+
+	fn counts(&self) -> Vec<(&'static str, i64)> {
+		vec![
+			(REQUESTS, self.requests as i64),
+			(BYTES_RECEIVED, self.bytes_received as i64),
+			(BYTES_SENT, self.bytes_sent as i64),
+		]
+	}
+}
+
+/// Sort key of the time window that contains a timestamp (in msec)
+pub fn window_sort_key(timestamp: u64) -> String {
+	format!("{:020}", timestamp - timestamp % USAGE_WINDOW_MSEC)
+}
+
+/// Start of a time window (in msec) from its sort key
+pub fn parse_window_sort_key(sk: &str) -> Option<u64> {
+	sk.parse().ok()
+}
+
+/// Total usage in a counter entry. Unlike for the counters of table entries,
+/// each node counts different requests, so the values of all nodes are summed.
+pub fn total_usage<T: CountedItem>(entry: &CounterEntry<T>) -> HashMap<String, i64> {
+	entry
+		.values
+		.iter()
+		.map(|(name, v)| (name.clone(), v.node_values.values().map(|(_, v)| v).sum()))
+		.collect()
+}
+
+pub struct KeyUsageCounter {
+	db: db::Db,
+	pub counter: Arc<IndexCounter<KeyUsage>>,
+	pending: Mutex<HashMap<(String, String), KeyUsage>>,
+}
+
+impl KeyUsageCounter {
+	pub fn new(
+		system: Arc<System>,
+		replication: TableShardedReplication,
+		db: &db::Db,
+	) -> Arc<Self> {
+		Arc::new(Self {
+			db: db.clone(),
+			counter: IndexCounter::new(system, replication, db),
+			pending: Mutex::new(HashMap::new()),
+		})
+	}
+
+	pub fn spawn_workers(self: &Arc<Self>, bg: &BackgroundRunner) {
+		self.counter.spawn_workers(bg);
+		bg.spawn_worker(KeyUsageFlushWorker(self.clone()));
+	}
+
+	/// Record usage of an access key. Usage is accumulated in memory
+	/// and written to the counter table every few seconds.
+	pub fn record(&self, key_id: &str, requests: u64, bytes_received: u64, bytes_sent: u64) {
+		let window = window_sort_key(now_msec());
+		let mut pending = self.pending.lock().unwrap();
+		let usage = pending
+			.entry((key_id.to_string(), window.clone()))
+			.or_insert_with(|| KeyUsage {
+				key_id: key_id.to_string(),
+				window,
+				requests: 0,
+				bytes_received: 0,
+				bytes_sent: 0,
+			});
+		usage.requests += requests;
+		usage.bytes_received += bytes_received;
+		usage.bytes_sent += bytes_sent;
+	}
+
+	fn flush(&self) -> Result<(), Error> {
+		let pending = std::mem::take(&mut *self.pending.lock().unwrap());
+		for usage in pending.values() {
+			self.db
+				.transaction(|tx| self.counter.count(tx, None, Some(usage)))?;
+		}
+		Ok(())
+	}
+}
+
+struct KeyUsageFlushWorker(Arc<KeyUsageCounter>);
+
+#[async_trait]
+impl Worker for KeyUsageFlushWorker {
+	fn name(&self) -> String {
+		"Key usage accounting".into()
+	}
+
+	async fn work(&mut self, must_exit: &mut watch::Receiver<bool>) -> Result<WorkerState, Error> {
+		select! {
+			_ = tokio::time::sleep(FLUSH_INTERVAL) => (),
+			_ = must_exit.changed() => (),
+		}
+		// Usage is also flushed when the node is stopping
+		self.0.flush()?;
+		if *must_exit.borrow() {
+			Ok(WorkerState::Done)
+		} else {
+			Ok(WorkerState::Busy)
+		}
+	}
+
+	async fn wait_for_work(&mut self) -> WorkerState {
+		unreachable!()
+	}
+}
@@ -0,0 +1,247 @@
+//! Provisioning of access keys for the members of a group of an LDAP
+//! directory. Each member of the group is given an access key named after
+//! them, whose secret can be retrieved once through the admin API. The keys
+//! of users that leave the group are disabled, and enabled again if they
+//! come back, so that onboarding and offboarding follow the directory.
+use std::collections::BTreeSet;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use ldap3::{ldap_escape, LdapConnAsync, LdapConnSettings, Scope, SearchEntry};
+use tokio::sync::watch;
+
+use garage_util::background::*;
+use garage_util::config::LdapSyncConfig;
+use garage_util::error::Error;
+
+use garage_table::*;
+
+use crate::garage::Garage;
+use crate::key_table::*;
+
+/// Timeout of the connection to the LDAP server
+const LDAP_CONN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Change to make to the access keys so that they match the directory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LdapSyncAction {
+	/// Create a key for a user that has none
+	Create(String),
+	/// Disable the key of a user that left the group
+	Disable(String),
+	/// Enable again the key of a user that came back in the group
+	Enable(String),
+}
+
+/// Changes to make to the access keys provisioned from the directory, so
+/// that exactly the members of the group have an enabled key
+pub fn plan_ldap_sync(members: &BTreeSet<String>, keys: &[Key]) -> Vec<LdapSyncAction> {
+	let mut actions = vec![];
+	let mut provisioned = BTreeSet::new();
+	for key in keys.iter().filter(|k| !k.is_deleted()) {
+		let user = match key.ldap_user() {
+			Some(u) => u,
+			None => continue,
+		};
+		provisioned.insert(user);
+		match (members.contains(user), key.is_disabled()) {
+			(true, true) => actions.push(LdapSyncAction::Enable(key.key_id.clone())),
+			(false, false) => actions.push(LdapSyncAction::Disable(key.key_id.clone())),
+			_ => (),
+		}
+	}
+	for user in members.iter().filter(|u| !provisioned.contains(u)) {
+		actions.push(LdapSyncAction::Create(user.clone()));
+	}
+	actions
+}
+
+pub struct LdapSyncWorker {
+	garage: Arc<Garage>,
+	config: LdapSyncConfig,
+	members: Arc<Mutex<Option<usize>>>,
+}
+
+impl LdapSyncWorker {
+	pub fn new(garage: Arc<Garage>, config: LdapSyncConfig) -> Self {
+		Self {
+			garage,
+			config,
+			members: Arc::new(Mutex::new(None)),
+		}
+	}
+
+	/// Users that are members of the group, identified by `user_attribute`
+	async fn group_members(&self) -> Result<BTreeSet<String>, Error> {
+		let cfg = &self.config;
+		let settings = LdapConnSettings::new().set_conn_timeout(LDAP_CONN_TIMEOUT);
+		let (conn, mut ldap) = LdapConnAsync::with_settings(settings, &cfg.url)
+			.await
+			.map_err(ldap_error)?;
+		tokio::spawn(async move {
+			if let Err(e) = conn.drive().await {
+				warn!("LDAP connection error: {}", e);
+			}
+		});
+
+		ldap.simple_bind(&cfg.bind_dn, cfg.bind_password.as_deref().unwrap_or(""))
+			.await
+			.and_then(|r| r.success())
+			.map_err(ldap_error)?;
+
+		let filter = format!("(memberOf={})", ldap_escape(&cfg.group_dn));
+		let (entries, _) = ldap
+			.search(
+				&cfg.base_dn,
+				Scope::Subtree,
+				&filter,
+				vec![cfg.user_attribute.as_str()],
+			)
+			.await
+			.and_then(|r| r.success())
+			.map_err(ldap_error)?;
+		let _ = ldap.unbind().await;
+
+		Ok(entries
+			.into_iter()
+			.map(SearchEntry::construct)
+			.filter_map(|e| e.attrs.get(&cfg.user_attribute)?.first().cloned())
+			.collect())
+	}
+
+	async fn sync(&self) -> Result<(), Error> {
+		let members = self.group_members().await?;
+		*self.members.lock().unwrap() = Some(members.len());
+
+		let keys = self
+			.garage
+			.key_table
+			.get_range(
+				&EmptyKey,
+				None,
+				Some(KeyFilter::Deleted(DeletedFilter::NotDeleted)),
+				10000,
+				EnumerationOrder::Forward,
+			)
+			.await?;
+
+		let actions = plan_ldap_sync(&members, &keys);
+		let disables = actions
+			.iter()
+			.filter(|a| matches!(a, LdapSyncAction::Disable(_)))
+			.count();
+		if members.is_empty() && disables > 0 {
+			// Most likely an error in the configuration or in the directory
+			warn!(
+				"LDAP group {} has no members, not disabling {} keys",
+				self.config.group_dn, disables
+			);
+			return Ok(());
+		}
+
+		for action in actions {
+			match action {
+				LdapSyncAction::Create(user) => {
+					let mut key = Key::new(&user);
+					let params = key.params_mut().unwrap();
+					params.ldap_user.update(Some(user.clone()));
+					params.secret_pending.update(true);
+					self.garage.key_table.insert(&key).await?;
+					info!("Created key {} for LDAP user {}", key.key_id, user);
+				}
+				LdapSyncAction::Disable(key_id) => self.set_disabled(&keys, &key_id, true).await?,
+				LdapSyncAction::Enable(key_id) => self.set_disabled(&keys, &key_id, false).await?,
+			}
+		}
+		Ok(())
+	}
+
+	async fn set_disabled(&self, keys: &[Key], key_id: &str, disabled: bool) -> Result<(), Error> {
+		let mut key = match keys.iter().find(|k| k.key_id == key_id) {
+			Some(k) => k.clone(),
+			None => return Ok(()),
+		};
+		key.params_mut().unwrap().disabled.update(disabled);
+		self.garage.key_table.insert(&key).await?;
+		info!(
+			"{} key {} of LDAP user {}",
+			if disabled { "Disabled" } else { "Enabled" },
+			key_id,
+			key.ldap_user().map(String::as_str).unwrap_or_default()
+		);
+		Ok(())
+	}
+}
+
+fn ldap_error(e: ldap3::LdapError) -> Error {
+	Error::Message(format!("LDAP error: {}", e))
+}
+
+#[async_trait]
+impl Worker for LdapSyncWorker {
+	fn name(&self) -> String {
+		"LDAP key sync".to_string()
+	}
+
+	fn status(&self) -> WorkerStatus {
+		WorkerStatus {
+			freeform: match *self.members.lock().unwrap() {
+				Some(n) => vec![format!("Members of the LDAP group: {}", n)],
+				None => vec![],
+			},
+			..Default::default()
+		}
+	}
+
+	async fn work(&mut self, _must_exit: &mut watch::Receiver<bool>) -> Result<WorkerState, Error> {
+		// Keys are only provisioned by one node, to avoid creating several
+		// keys for a new user
+		if self.garage.system.is_leader() {
+			self.sync().await?;
+		}
+		Ok(WorkerState::Idle)
+	}
+
+	async fn wait_for_work(&mut self) -> WorkerState {
+		tokio::time::sleep(Duration::from_secs(self.config.interval_secs)).await;
+		WorkerState::Busy
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn provisioned(user: &str, disabled: bool) -> Key {
+		let mut key = Key::new(user);
+		let params = key.params_mut().unwrap();
+		params.ldap_user.update(Some(user.to_string()));
+		params.disabled.update(disabled);
+		key
+	}
+
+	#[test]
+	fn test_plan_ldap_sync() {
+		let keys = vec![
+			provisioned("alice", false),
+			provisioned("bob", false),
+			provisioned("carol", true),
+			Key::new("manual"),
+		];
+		let members = ["alice", "carol", "dave"]
+			.iter()
+			.map(|u| u.to_string())
+			.collect::<BTreeSet<_>>();
+
+		assert_eq!(
+			plan_ldap_sync(&members, &keys),
+			vec![
+				LdapSyncAction::Disable(keys[1].key_id.clone()),
+				LdapSyncAction::Enable(keys[2].key_id.clone()),
+				LdapSyncAction::Create("dave".to_string()),
+			]
+		);
+		assert!(plan_ldap_sync(&members, &[]).len() == 3);
+	}
+}
@@ -11,11 +11,24 @@ pub mod index_counter;
 pub mod bucket_alias_table;
 pub mod bucket_table;
 pub mod key_table;
+pub mod key_usage;
+pub mod permission_group_table;
+pub mod tls_certificate_table;
+pub mod web_usage;
 
 #[cfg(feature = "k2v")]
 pub mod k2v;
 pub mod s3;
 
+pub mod bucket_alarm_worker;
+pub mod config_check;
+pub mod drain_worker;
 pub mod garage;
+pub mod garbage_stats;
 pub mod helper;
+pub mod job;
+pub mod key_expiration_worker;
+#[cfg(feature = "ldap")]
+pub mod ldap_sync_worker;
 pub mod migrate;
+pub mod repair;
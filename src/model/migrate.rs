@@ -62,6 +62,11 @@ impl Migrate {
 			Some(WebsiteConfig {
 				index_document: "index.html".into(),
 				error_document: None,
+				directory_listing: false,
+				routing_rules: vec![],
+				error_documents: Default::default(),
+				cache_control: None,
+				expires_after: None,
 			})
 		} else {
 			None
@@ -80,6 +85,15 @@ impl Migrate {
 					cors_config: Lww::new(None),
 					lifecycle_config: Lww::new(None),
 					quotas: Lww::new(Default::default()),
+					quorum_policy: Lww::new(Default::default()),
+					object_defaults: Lww::new(Default::default()),
+					public_read: Lww::new(false),
+					first_block_sizes: Lww::new(None),
+					alarms: Lww::new(Default::default()),
+					trash_retention_days: Lww::new(None),
+					website_signing: Lww::new(None),
+					deletion_protection: Lww::new(false),
+					pending_deletion: Lww::new(None),
 				}),
 			})
 			.await?;
@@ -38,6 +38,24 @@ impl BucketKeyPerm {
 	pub fn is_any(&self) -> bool {
 		self.allow_read || self.allow_write || self.allow_owner
 	}
+
+	/// Permissions given by either of two permission sets
+	pub fn union(&self, other: &Self) -> Self {
+		Self {
+			timestamp: std::cmp::max(self.timestamp, other.timestamp),
+			allow_read: self.allow_read || other.allow_read,
+			allow_write: self.allow_write || other.allow_write,
+			allow_owner: self.allow_owner || other.allow_owner,
+		}
+	}
+
+	/// Returns true if both permission sets give the same permissions,
+	/// regardless of their timestamps
+	pub fn same_as(&self, other: &Self) -> bool {
+		self.allow_read == other.allow_read
+			&& self.allow_write == other.allow_write
+			&& self.allow_owner == other.allow_owner
+	}
 }
 
 impl Crdt for BucketKeyPerm {
@@ -62,3 +80,22 @@ impl Crdt for BucketKeyPerm {
 		}
 	}
 }
+
+/// Deletion of a bucket or key protected against deletion, requested through
+/// the admin API, that must be confirmed with a different admin token
+#[derive(PartialOrd, Ord, PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+pub struct PendingDeletion {
+	/// Time (in msec since epoch) at which the deletion was requested
+	pub requested: u64,
+	/// Time (in msec since epoch) until which the deletion can be confirmed,
+	/// so that changing the confirmation delay in the configuration does
+	/// not affect the deletions that are already pending
+	pub expires: u64,
+	/// Identity of the requester: `token:<fingerprint>` for an admin token
+	/// of the configuration, `jwt:<subject>` for a JWT
+	pub requested_by: String,
+}
+
+impl AutoCrdt for PendingDeletion {
+	const WARN_IF_DIFFERENT: bool = false;
+}
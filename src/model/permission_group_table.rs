@@ -0,0 +1,145 @@
+use garage_table::crdt::*;
+use garage_table::*;
+use garage_util::data::*;
+use garage_util::time::*;
+
+use crate::permission::BucketKeyPerm;
+
+mod v09 {
+	use crate::permission::BucketKeyPerm;
+	use garage_util::crdt;
+	use garage_util::data::Uuid;
+	use serde::{Deserialize, Serialize};
+
+	/// A permission group is a named set of permissions on buckets,
+	/// that are given to all of the access keys that are members of the group.
+	///
+	/// The permissions given by the groups of a key are copied in its
+	/// `group_permissions` field when the group or its members change,
+	/// so that checking the permissions of a key does not require
+	/// reading the groups.
+	#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+	pub struct PermissionGroup {
+		/// ID of the group
+		pub id: Uuid,
+		/// State, and configuration if not deleted, of the group
+		pub state: crdt::Deletable<PermissionGroupParams>,
+	}
+
+	/// Configuration of a permission group
+	#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+	pub struct PermissionGroupParams {
+		/// Group's creation date
+		pub creation_date: u64,
+		/// Name of the group
+		pub name: crdt::Lww<String>,
+		/// Permissions given by the group on buckets
+		pub bucket_permissions: crdt::Map<Uuid, BucketKeyPerm>,
+		/// Access keys that are members of the group
+		pub keys: crdt::LwwMap<String, bool>,
+	}
+
+	impl garage_util::migrate::InitialFormat for PermissionGroup {
+		const VERSION_MARKER: &'static [u8] = b"G09permgroup";
+	}
+}
+
+pub use v09::*;
+
+impl PermissionGroupParams {
+	fn new(name: String) -> Self {
+		PermissionGroupParams {
+			creation_date: now_msec(),
+			name: crdt::Lww::new(name),
+			bucket_permissions: crdt::Map::new(),
+			keys: crdt::LwwMap::new(),
+		}
+	}
+
+	/// Returns the IDs of the access keys that are members of the group
+	pub fn member_keys(&self) -> impl Iterator<Item = &String> {
+		self.keys
+			.items()
+			.iter()
+			.filter(|(_, _, member)| *member)
+			.map(|(k, _, _)| k)
+	}
+}
+
+impl Crdt for PermissionGroupParams {
+	fn merge(&mut self, o: &Self) {
+		self.name.merge(&o.name);
+		self.bucket_permissions.merge(&o.bucket_permissions);
+		self.keys.merge(&o.keys);
+	}
+}
+
+impl PermissionGroup {
+	/// Create a new permission group with a random ID
+	pub fn new(name: String) -> Self {
+		PermissionGroup {
+			id: gen_uuid(),
+			state: crdt::Deletable::present(PermissionGroupParams::new(name)),
+		}
+	}
+
+	/// Returns true if this represents a deleted group
+	pub fn is_deleted(&self) -> bool {
+		self.state.is_deleted()
+	}
+
+	/// Returns an option representing the parameters (None if in deleted state)
+	pub fn params(&self) -> Option<&PermissionGroupParams> {
+		self.state.as_option()
+	}
+
+	/// Mutable version of `.params()`
+	pub fn params_mut(&mut self) -> Option<&mut PermissionGroupParams> {
+		self.state.as_option_mut()
+	}
+
+	/// Returns true if the access key is a member of the group
+	pub fn has_member(&self, key_id: &String) -> bool {
+		self.params()
+			.and_then(|p| p.keys.get(key_id).copied())
+			.unwrap_or(false)
+	}
+
+	/// Get the permissions given by the group on a bucket
+	pub fn bucket_permissions(&self, bucket: &Uuid) -> BucketKeyPerm {
+		self.params()
+			.and_then(|p| p.bucket_permissions.get(bucket))
+			.cloned()
+			.unwrap_or(BucketKeyPerm::NO_PERMISSIONS)
+	}
+}
+
+impl Entry<EmptyKey, Uuid> for PermissionGroup {
+	fn partition_key(&self) -> &EmptyKey {
+		&EmptyKey
+	}
+	fn sort_key(&self) -> &Uuid {
+		&self.id
+	}
+}
+
+impl Crdt for PermissionGroup {
+	fn merge(&mut self, other: &Self) {
+		self.state.merge(&other.state);
+	}
+}
+
+pub struct PermissionGroupTable;
+
+impl TableSchema for PermissionGroupTable {
+	const TABLE_NAME: &'static str = "permission_group";
+
+	type P = EmptyKey;
+	type S = Uuid;
+	type E = PermissionGroup;
+	type Filter = DeletedFilter;
+
+	fn matches_filter(entry: &Self::E, filter: &Self::Filter) -> bool {
+		filter.apply(entry.is_deleted())
+	}
+}
@@ -0,0 +1,328 @@
+//! Repair operations of a node, launched with `garage repair` or through the
+//! admin API. Each repair is run as a job of the job queue, whose progress can
+//! be followed and which can be cancelled.
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+
+use garage_block::repair::{RepairScope, RepairWorker, ScrubWorkerCommand};
+use garage_rpc::ring::{partition_first_hash, Partition, PARTITION_BITS};
+use garage_util::background::*;
+use garage_util::data::*;
+use garage_util::error::Error;
+use garage_util::time::now_msec;
+
+use crate::garage::Garage;
+use crate::job::*;
+
+pub mod table;
+
+use table::*;
+
+/// Kind of the repair jobs
+pub const JOB_KIND: &str = "repair";
+
+/// Interval at which the progress of the scrub is checked
+const SCRUB_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Time after which a scrub job is considered to have been stopped if the
+/// scrub worker has no scrub in progress, without it having completed
+const SCRUB_START_DELAY_MSEC: u64 = 10_000;
+
+/// The repair operations that can be launched as jobs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RepairType {
+	/// Repropagate object deletions to the version table
+	Versions,
+	/// Repropagate object deletions to the multipart upload table
+	MultipartUploads,
+	/// Repropagate version deletions to the block ref table
+	BlockRefs,
+	/// Check that the blocks referenced by the node are stored, fetching
+	/// the missing ones, and offload the blocks that are not needed
+	Blocks,
+	/// Verify the integrity of all blocks stored on the node
+	Scrub,
+}
+
+/// Parameters of a repair
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RepairParams {
+	/// Only repair these partitions, given as first and last partition
+	/// (table and block repairs)
+	pub partitions: Option<(Partition, Partition)>,
+	/// Number of entries that are repaired concurrently (table repairs)
+	pub concurrency: usize,
+	/// Only repair the blocks whose primary location is this data
+	/// directory (block repair)
+	pub data_dir: Option<PathBuf>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RepairJobParams {
+	repair: RepairType,
+	params: RepairParams,
+}
+
+/// Launch a repair on this node, as a job of the job queue
+pub async fn launch_repair(
+	garage: &Arc<Garage>,
+	bg: &BackgroundRunner,
+	repair: RepairType,
+	params: RepairParams,
+) -> Result<JobRecord, Error> {
+	if repair == RepairType::Scrub {
+		if garage.block_manager.scrub_progress().is_some() {
+			return Err(Error::Message(
+				"A scrub is already running or paused on this node".into(),
+			));
+		}
+		garage
+			.block_manager
+			.send_scrub_command(ScrubWorkerCommand::Start)
+			.await?;
+	}
+
+	let job_params = RepairJobParams { repair, params };
+	let worker = repair_worker(garage, &job_params, now_msec(), None)?;
+	let description = match job_params.params.partitions {
+		Some((first, last)) => format!("Repair {:?} (partitions {}-{})", repair, first, last),
+		None => format!("Repair {:?}", repair),
+	};
+	garage
+		.jobs
+		.start(bg, JOB_KIND, description, &job_params, worker)
+}
+
+pub(crate) fn register_job_kinds(jobs: &JobQueue) {
+	jobs.register_kind(JOB_KIND, resume_repair);
+}
+
+fn resume_repair(garage: &Arc<Garage>, record: &JobRecord) -> Result<Box<dyn JobWorker>, Error> {
+	let job_params = record.params::<RepairJobParams>()?;
+	let checkpoint = record.checkpoint::<Option<Vec<u8>>>()?.flatten();
+	repair_worker(garage, &job_params, record.created, checkpoint)
+}
+
+/// Build the worker running a repair, resuming table repairs from the
+/// position saved in their checkpoint. Block repairs start over.
+fn repair_worker(
+	garage: &Arc<Garage>,
+	job_params: &RepairJobParams,
+	started: u64,
+	pos: Option<Vec<u8>>,
+) -> Result<Box<dyn JobWorker>, Error> {
+	let params = &job_params.params;
+	let (start, end) = match params.partitions {
+		Some((first, last)) => partition_hash_range(first, last),
+		None => (None, None),
+	};
+
+	let worker: Box<dyn JobWorker> = match job_params.repair {
+		RepairType::Versions => {
+			info!("Repairing the versions table");
+			Box::new(
+				TableRepairWorker::new(
+					garage.clone(),
+					RepairVersions,
+					start,
+					end,
+					params.concurrency,
+				)
+				.resume_from(pos),
+			)
+		}
+		RepairType::MultipartUploads => {
+			info!("Repairing the multipart uploads table");
+			Box::new(
+				TableRepairWorker::new(garage.clone(), RepairMpu, start, end, params.concurrency)
+					.resume_from(pos),
+			)
+		}
+		RepairType::BlockRefs => {
+			info!("Repairing the block refs table");
+			Box::new(
+				TableRepairWorker::new(
+					garage.clone(),
+					RepairBlockRefs,
+					start,
+					end,
+					params.concurrency,
+				)
+				.resume_from(pos),
+			)
+		}
+		RepairType::Blocks => {
+			info!("Repairing the stored blocks");
+			let scope = RepairScope {
+				start,
+				end,
+				data_dir: params.data_dir.clone(),
+			};
+			Box::new(RepairWorker::new(garage.block_manager.clone(), scope)?)
+		}
+		RepairType::Scrub => Box::new(ScrubJobWorker {
+			garage: garage.clone(),
+			started,
+			progress: 0.,
+			done: false,
+		}),
+	};
+	Ok(worker)
+}
+
+/// Range of hashes that fall in a range of partitions, as first hash (included)
+/// and last hash (excluded, None for the end of the hash space)
+pub fn partition_hash_range(first: Partition, last: Partition) -> (Option<Hash>, Option<Hash>) {
+	let start = partition_first_hash(first);
+	let end = if (last as usize + 1) < (1 << PARTITION_BITS) {
+		Some(partition_first_hash(last + 1))
+	} else {
+		None
+	};
+	(Some(start), end)
+}
+
+/// Position of a hash in the hash space, between 0 and 1. Only the first
+/// three bytes are used, as they are represented exactly by an f32.
+pub(crate) fn hash_position(hash: &[u8]) -> f32 {
+	let mut prefix = [0u8; 4];
+	let len = std::cmp::min(hash.len(), 3);
+	prefix[1..len + 1].copy_from_slice(&hash[..len]);
+	u32::from_be_bytes(prefix) as f32 / (1u32 << 24) as f32
+}
+
+// ---- job workers ----
+
+impl<R: TableRepair> JobWorker for TableRepairWorker<R> {
+	fn progress(&self) -> Option<f32> {
+		Some(TableRepairWorker::progress(self))
+	}
+
+	fn checkpoint(&self) -> Vec<u8> {
+		encode_checkpoint(&self.position())
+	}
+}
+
+impl JobWorker for RepairWorker {
+	fn progress(&self) -> Option<f32> {
+		Some(RepairWorker::progress(self))
+	}
+}
+
+/// The scrub is run by the scrub worker of the block manager, which always
+/// exists: the job only follows its progress, and cancels it when the job
+/// is cancelled
+struct ScrubJobWorker {
+	garage: Arc<Garage>,
+	started: u64,
+	progress: f32,
+	done: bool,
+}
+
+#[async_trait]
+impl Worker for ScrubJobWorker {
+	fn name(&self) -> String {
+		"Scrub job".into()
+	}
+
+	fn status(&self) -> WorkerStatus {
+		WorkerStatus {
+			progress: Some(format!("{:.2}%", self.progress * 100.)),
+			..Default::default()
+		}
+	}
+
+	async fn work(&mut self, _must_exit: &mut watch::Receiver<bool>) -> Result<WorkerState, Error> {
+		if self.done {
+			return Ok(WorkerState::Done);
+		}
+		let manager = &self.garage.block_manager;
+		match manager.scrub_progress() {
+			Some(p) => {
+				self.progress = p;
+				Ok(WorkerState::Idle)
+			}
+			None if manager.scrub_last_completed() >= self.started => {
+				self.progress = 1.;
+				Ok(WorkerState::Done)
+			}
+			None if now_msec() > self.started + SCRUB_START_DELAY_MSEC => {
+				warn!("The scrub was stopped without having completed");
+				Ok(WorkerState::Done)
+			}
+			None => Ok(WorkerState::Idle),
+		}
+	}
+
+	async fn wait_for_work(&mut self) -> WorkerState {
+		tokio::time::sleep(SCRUB_POLL_INTERVAL).await;
+		WorkerState::Busy
+	}
+}
+
+#[async_trait]
+impl JobWorker for ScrubJobWorker {
+	fn progress(&self) -> Option<f32> {
+		Some(self.progress)
+	}
+
+	async fn cancel(&mut self) -> Result<(), Error> {
+		self.done = true;
+		self.garage
+			.block_manager
+			.send_scrub_command(ScrubWorkerCommand::Cancel)
+			.await
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_partition_hash_range() {
+		let (start, end) = partition_hash_range(0, 0);
+		assert_eq!(start, Some(partition_first_hash(0)));
+		assert_eq!(end, Some(partition_first_hash(1)));
+
+		let last = ((1 << PARTITION_BITS) - 1) as Partition;
+		let (start, end) = partition_hash_range(3, last);
+		assert_eq!(start, Some(partition_first_hash(3)));
+		assert_eq!(end, None);
+	}
+
+	#[test]
+	fn test_hash_position() {
+		assert_eq!(hash_position(&[0u8; 32]), 0.);
+		let middle = partition_first_hash(1 << (PARTITION_BITS - 1));
+		assert_eq!(hash_position(middle.as_slice()), 0.5);
+		assert!(hash_position(&[0xffu8; 32]) < 1.);
+		assert_eq!(hash_position(&[0x80]), 0.5);
+	}
+
+	#[test]
+	fn test_repair_job_params() {
+		// Parameters are saved in the job record so that repairs can be
+		// resumed after a restart
+		let job_params = RepairJobParams {
+			repair: RepairType::BlockRefs,
+			params: RepairParams {
+				partitions: Some((4, 12)),
+				concurrency: 8,
+				data_dir: None,
+			},
+		};
+		let encoded = rmp_serde::encode::to_vec_named(&job_params).unwrap();
+		let decoded: RepairJobParams = rmp_serde::decode::from_slice(&encoded).unwrap();
+		assert_eq!(decoded.repair, RepairType::BlockRefs);
+		assert_eq!(decoded.params.partitions, Some((4, 12)));
+		assert_eq!(decoded.params.concurrency, 8);
+		assert_eq!(decoded.params.data_dir, None);
+	}
+}
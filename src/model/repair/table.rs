@@ -0,0 +1,309 @@
+//! Repair of the metadata tables: entries that reference a deleted entry of
+//! another table are marked as deleted as well
+use std::ops::Bound;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use futures::future::try_join_all;
+use tokio::sync::watch;
+
+use garage_table::replication::*;
+use garage_table::*;
+
+use garage_util::background::*;
+use garage_util::data::*;
+use garage_util::error::{Error, OkOrMessage};
+use garage_util::migrate::Migrate;
+
+use crate::garage::Garage;
+use crate::s3::block_ref_table::*;
+use crate::s3::mpu_table::*;
+use crate::s3::object_table::*;
+use crate::s3::version_table::*;
+
+use super::hash_position;
+
+/// Repair procedure for the entries of a table
+#[async_trait]
+pub trait TableRepair: Send + Sync + 'static {
+	type T: TableSchema;
+
+	fn table(garage: &Garage) -> &Table<Self::T, TableShardedReplication>;
+
+	async fn process(
+		&self,
+		garage: &Garage,
+		entry: <<Self as TableRepair>::T as TableSchema>::E,
+	) -> Result<bool, Error>;
+}
+
+/// Worker that goes through the entries of a table stored on this node
+/// and repairs them
+pub struct TableRepairWorker<T: TableRepair> {
+	garage: Arc<Garage>,
+	start: Option<Hash>,
+	end: Option<Hash>,
+	concurrency: usize,
+	pos: Option<Vec<u8>>,
+	counter: usize,
+	repairs: usize,
+	counters: WorkerCounters,
+	inner: T,
+}
+
+impl<R: TableRepair> TableRepairWorker<R> {
+	/// Repair the entries whose partition key hash is between `start`
+	/// (included) and `end` (excluded), processing `concurrency` entries
+	/// at a time
+	pub fn new(
+		garage: Arc<Garage>,
+		inner: R,
+		start: Option<Hash>,
+		end: Option<Hash>,
+		concurrency: usize,
+	) -> Self {
+		Self {
+			garage,
+			inner,
+			start,
+			end,
+			concurrency: std::cmp::max(concurrency, 1),
+			pos: None,
+			counter: 0,
+			repairs: 0,
+			counters: WorkerCounters::new(),
+		}
+	}
+
+	/// Position of the last entry processed, from which the repair can be
+	/// resumed
+	pub fn position(&self) -> Option<&[u8]> {
+		self.pos.as_deref()
+	}
+
+	/// Resume the repair after the entry at the given position
+	pub fn resume_from(mut self, pos: Option<Vec<u8>>) -> Self {
+		self.pos = pos;
+		self.counters.fraction_start = self.progress();
+		self
+	}
+
+	/// Returns progress done, between 0 and 1, according to the position
+	/// of the last entry processed in the range of partition keys
+	pub fn progress(&self) -> f32 {
+		let start = self
+			.start
+			.map(|h| hash_position(h.as_slice()))
+			.unwrap_or(0.);
+		let end = self.end.map(|h| hash_position(h.as_slice())).unwrap_or(1.);
+		match &self.pos {
+			Some(pos) if end > start => ((hash_position(pos) - start) / (end - start)).min(1.),
+			_ => 0.,
+		}
+	}
+}
+
+#[async_trait]
+impl<R: TableRepair> Worker for TableRepairWorker<R> {
+	fn name(&self) -> String {
+		format!("{} repair worker", R::T::TABLE_NAME)
+	}
+
+	fn is_maintenance(&self) -> bool {
+		true
+	}
+
+	fn status(&self) -> WorkerStatus {
+		WorkerStatus {
+			progress: Some(format!("{} ({})", self.counter, self.repairs)),
+			counters: Some(WorkerCounters {
+				items: self.counter as u64,
+				fraction: Some(self.progress()),
+				..self.counters.clone()
+			}),
+			..Default::default()
+		}
+	}
+
+	async fn work(&mut self, _must_exit: &mut watch::Receiver<bool>) -> Result<WorkerState, Error> {
+		let start_bound = match (&self.pos, &self.start) {
+			(Some(pos), _) => Bound::Excluded(pos.clone()),
+			(None, Some(start)) => Bound::Included(start.to_vec()),
+			(None, None) => Bound::Unbounded,
+		};
+		let end_bound = match &self.end {
+			Some(end) => Bound::Excluded(end.to_vec()),
+			None => Bound::Unbounded,
+		};
+
+		// Read a batch of entries, which are then processed concurrently
+		let mut entries = vec![];
+		for item in R::table(&self.garage)
+			.data
+			.store
+			.range::<Vec<u8>, _>((start_bound, end_bound))?
+		{
+			let (k, v) = item?;
+			let entry =
+				<R::T as TableSchema>::E::decode(&v).ok_or_message("Cannot decode table entry")?;
+			entries.push(entry);
+			self.pos = Some(k.to_vec());
+			if entries.len() >= self.concurrency {
+				break;
+			}
+		}
+
+		if entries.is_empty() {
+			info!(
+				"{}: finished, done {}, fixed {}",
+				self.name(),
+				self.counter,
+				self.repairs
+			);
+			return Ok(WorkerState::Done);
+		}
+
+		self.counter += entries.len();
+		let results = try_join_all(
+			entries
+				.into_iter()
+				.map(|entry| self.inner.process(&self.garage, entry)),
+		)
+		.await?;
+		self.repairs += results.into_iter().filter(|x| *x).count();
+
+		Ok(WorkerState::Busy)
+	}
+
+	async fn wait_for_work(&mut self) -> WorkerState {
+		unreachable!()
+	}
+}
+
+// ----
+
+/// Mark as deleted the versions whose object or multipart upload was deleted
+pub struct RepairVersions;
+
+#[async_trait]
+impl TableRepair for RepairVersions {
+	type T = VersionTable;
+
+	fn table(garage: &Garage) -> &Table<Self::T, TableShardedReplication> {
+		&garage.version_table
+	}
+
+	async fn process(&self, garage: &Garage, version: Version) -> Result<bool, Error> {
+		if !version.deleted.get() {
+			let ref_exists = match &version.backlink {
+				VersionBacklink::Object { bucket_id, key } => garage
+					.object_table
+					.get(bucket_id, key)
+					.await?
+					.map(|o| {
+						o.versions().iter().any(|x| {
+							x.uuid == version.uuid && x.state != ObjectVersionState::Aborted
+						})
+					})
+					.unwrap_or(false),
+				VersionBacklink::MultipartUpload { upload_id } => garage
+					.mpu_table
+					.get(upload_id, &EmptyKey)
+					.await?
+					.map(|u| !u.deleted.get())
+					.unwrap_or(false),
+			};
+
+			if !ref_exists {
+				info!("Repair versions: marking version as deleted: {:?}", version);
+				garage
+					.version_table
+					.insert(&Version::new(version.uuid, version.backlink, true))
+					.await?;
+				return Ok(true);
+			}
+		}
+
+		Ok(false)
+	}
+}
+
+// ----
+
+/// Mark as deleted the block references whose version was deleted
+pub struct RepairBlockRefs;
+
+#[async_trait]
+impl TableRepair for RepairBlockRefs {
+	type T = BlockRefTable;
+
+	fn table(garage: &Garage) -> &Table<Self::T, TableShardedReplication> {
+		&garage.block_ref_table
+	}
+
+	async fn process(&self, garage: &Garage, mut block_ref: BlockRef) -> Result<bool, Error> {
+		if !block_ref.deleted.get() {
+			let ref_exists = garage
+				.version_table
+				.get(&block_ref.version, &EmptyKey)
+				.await?
+				.map(|v| !v.deleted.get())
+				.unwrap_or(false);
+
+			if !ref_exists {
+				info!(
+					"Repair block ref: marking block_ref as deleted: {:?}",
+					block_ref
+				);
+				block_ref.deleted.set();
+				garage.block_ref_table.insert(&block_ref).await?;
+				return Ok(true);
+			}
+		}
+
+		Ok(false)
+	}
+}
+
+// ----
+
+/// Mark as deleted the multipart uploads whose object version was deleted
+/// or completed
+pub struct RepairMpu;
+
+#[async_trait]
+impl TableRepair for RepairMpu {
+	type T = MultipartUploadTable;
+
+	fn table(garage: &Garage) -> &Table<Self::T, TableShardedReplication> {
+		&garage.mpu_table
+	}
+
+	async fn process(&self, garage: &Garage, mut mpu: MultipartUpload) -> Result<bool, Error> {
+		if !mpu.deleted.get() {
+			let ref_exists = garage
+				.object_table
+				.get(&mpu.bucket_id, &mpu.key)
+				.await?
+				.map(|o| {
+					o.versions()
+						.iter()
+						.any(|x| x.uuid == mpu.upload_id && x.is_uploading(Some(true)))
+				})
+				.unwrap_or(false);
+
+			if !ref_exists {
+				info!(
+					"Repair multipart uploads: marking mpu as deleted: {:?}",
+					mpu
+				);
+				mpu.parts.clear();
+				mpu.deleted.set();
+				garage.mpu_table.insert(&mpu).await?;
+				return Ok(true);
+			}
+		}
+
+		Ok(false)
+	}
+}
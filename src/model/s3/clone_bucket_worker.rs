@@ -0,0 +1,359 @@
+//! Copy of the objects of a bucket into another bucket in the background
+//! (e.g. after `garage bucket clone`)
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use tokio::sync::watch;
+
+use garage_util::background::*;
+use garage_util::data::*;
+use garage_util::error::Error;
+use garage_util::time::*;
+
+use garage_table::*;
+
+use crate::garage::Garage;
+use crate::s3::block_ref_table::*;
+use crate::s3::object_table::*;
+use crate::s3::version_table::*;
+
+/// Number of objects copied in each batch
+const BATCH_SIZE: usize = 100;
+
+mod v090 {
+	use garage_util::data::Uuid;
+	use serde::{Deserialize, Serialize};
+
+	#[derive(Serialize, Deserialize, Default, Clone)]
+	pub struct CloneBucketWorkerPersisted {
+		/// Copies of buckets made by this node
+		pub jobs: Vec<CloneBucketJob>,
+	}
+
+	#[derive(Serialize, Deserialize, Clone, Debug)]
+	pub struct CloneBucketJob {
+		pub source_bucket_id: Uuid,
+		pub dest_bucket_id: Uuid,
+		/// Only objects whose key starts with this prefix are copied
+		pub prefix: String,
+		/// Time at which the job was started
+		pub started: u64,
+		/// Key of the last object processed
+		pub last_key: Option<String>,
+		pub objects_copied: u64,
+		pub bytes_copied: u64,
+		pub objects_skipped: u64,
+	}
+
+	impl garage_util::migrate::InitialFormat for CloneBucketWorkerPersisted {
+		const VERSION_MARKER: &'static [u8] = b"G09cbwp";
+	}
+}
+
+pub use v090::*;
+
+/// Worker that copies the objects of a bucket into another bucket.
+///
+/// Only the entries of the object, version and block ref tables are copied:
+/// the copies reference the same data blocks as the original objects.
+/// Objects that are already present in the destination bucket with the same
+/// size and ETag are skipped. Its progress is saved after each batch of objects,
+/// so that it is resumed when the node restarts.
+pub struct CloneBucketWorker {
+	garage: Arc<Garage>,
+	job: CloneBucketJob,
+}
+
+impl CloneBucketWorker {
+	/// Record a job to copy the objects of a bucket, and return the worker that
+	/// runs it, or None if the same copy is already being made by this node
+	pub fn start(
+		garage: Arc<Garage>,
+		source_bucket_id: Uuid,
+		dest_bucket_id: Uuid,
+		prefix: String,
+	) -> Result<Option<Self>, Error> {
+		let job = CloneBucketJob {
+			source_bucket_id,
+			dest_bucket_id,
+			prefix,
+			started: now_msec(),
+			last_key: None,
+			objects_copied: 0,
+			bytes_copied: 0,
+			objects_skipped: 0,
+		};
+		let mut added = false;
+		garage.clone_bucket_persister.set_with(|p| {
+			if !p.jobs.iter().any(|j| j.same_copy(&job)) {
+				p.jobs.push(job.clone());
+				added = true;
+			}
+		})?;
+		Ok(added.then(|| Self { garage, job }))
+	}
+
+	/// Workers for the jobs that were not finished when the node was stopped
+	pub fn resume_all(garage: &Arc<Garage>) -> Vec<Self> {
+		garage
+			.clone_bucket_persister
+			.get_with(|p| p.jobs.clone())
+			.into_iter()
+			.map(|job| Self {
+				garage: garage.clone(),
+				job,
+			})
+			.collect()
+	}
+
+	/// Copy the objects of the next batch, returning whether all the objects
+	/// of the source bucket with the prefix were processed
+	async fn copy_batch(&mut self) -> Result<bool, Error> {
+		// Start after the last key processed, i.e. at the key that follows it
+		// in lexicographic order
+		let start = match &self.job.last_key {
+			Some(k) => format!("{}\0", k),
+			None => self.job.prefix.clone(),
+		};
+		let objects = self
+			.garage
+			.object_table
+			.get_range(
+				&self.job.source_bucket_id,
+				Some(start),
+				Some(ObjectFilter::IsData),
+				BATCH_SIZE,
+				EnumerationOrder::Forward,
+			)
+			.await?;
+
+		let mut finished = objects.len() < BATCH_SIZE;
+		for object in objects.iter() {
+			if !object.key.starts_with(&self.job.prefix) {
+				finished = true;
+				break;
+			}
+			match self.copy_object(object).await? {
+				Some(size) => {
+					self.job.objects_copied += 1;
+					self.job.bytes_copied += size;
+				}
+				None => self.job.objects_skipped += 1,
+			}
+			self.job.last_key = Some(object.key.clone());
+		}
+
+		let job = self.job.clone();
+		self.garage.clone_bucket_persister.set_with(|p| {
+			if finished {
+				p.jobs.retain(|j| !j.same_copy(&job));
+			} else if let Some(j) = p.jobs.iter_mut().find(|j| j.same_copy(&job)) {
+				*j = job;
+			}
+		})?;
+
+		Ok(finished)
+	}
+
+	/// Copy the current version of an object into the destination bucket,
+	/// returning its size, or None if it was already present there
+	async fn copy_object(&self, object: &Object) -> Result<Option<u64>, Error> {
+		let garage = &self.garage;
+		let dest_bucket_id = self.job.dest_bucket_id;
+
+		let source_version = match object.versions().iter().rev().find(|v| v.is_data()) {
+			Some(v) => v,
+			None => return Ok(None),
+		};
+		let source_meta = match &source_version.state {
+			ObjectVersionState::Complete(ObjectVersionData::Inline(meta, _))
+			| ObjectVersionState::Complete(ObjectVersionData::FirstBlock(meta, _)) => meta,
+			_ => return Ok(None),
+		};
+
+		let existing = garage
+			.object_table
+			.get(&dest_bucket_id, &object.key)
+			.await?;
+		let existing_meta = existing.as_ref().and_then(|o| {
+			o.versions()
+				.iter()
+				.rev()
+				.find(|v| v.is_data())
+				.and_then(|v| match &v.state {
+					ObjectVersionState::Complete(ObjectVersionData::Inline(meta, _))
+					| ObjectVersionState::Complete(ObjectVersionData::FirstBlock(meta, _)) => Some(meta),
+					_ => None,
+				})
+		});
+		if let Some(meta) = existing_meta {
+			if meta.size == source_meta.size && meta.etag == source_meta.etag {
+				return Ok(None);
+			}
+		}
+
+		copy_object_version(
+			garage,
+			source_version,
+			dest_bucket_id,
+			&object.key,
+			now_msec(),
+		)
+		.await
+	}
+}
+
+/// Copy a complete version of an object to a key of a bucket, as a new version
+/// with the given timestamp that references the same data blocks.
+/// Returns the size of the object, or None if the version is not a complete
+/// version with data or was deleted in the meantime.
+pub async fn copy_object_version(
+	garage: &Garage,
+	source_version: &ObjectVersion,
+	dest_bucket_id: Uuid,
+	dest_key: &str,
+	timestamp: u64,
+) -> Result<Option<u64>, Error> {
+	let (source_data, source_meta) = match &source_version.state {
+		ObjectVersionState::Complete(data @ ObjectVersionData::Inline(meta, _))
+		| ObjectVersionState::Complete(data @ ObjectVersionData::FirstBlock(meta, _)) => (data, meta),
+		_ => return Ok(None),
+	};
+
+	let new_uuid = gen_uuid();
+
+	if let ObjectVersionData::FirstBlock(_, _) = source_data {
+		let source_version = match garage
+			.version_table
+			.get(&source_version.uuid, &EmptyKey)
+			.await?
+		{
+			Some(v) if !v.deleted.get() => v,
+			_ => return Ok(None),
+		};
+
+		// Write an "uploading" marker in the object table, so that the
+		// version is not deleted before the copy is complete
+		let tmp_dest_object = Object::new(
+			dest_bucket_id,
+			dest_key.to_string(),
+			vec![ObjectVersion {
+				uuid: new_uuid,
+				timestamp,
+				state: ObjectVersionState::Uploading {
+					headers: source_meta.headers.clone(),
+					multipart: false,
+					initiator: None,
+				},
+			}],
+		);
+		garage.object_table.insert(&tmp_dest_object).await?;
+
+		let mut dest_version = Version::new(
+			new_uuid,
+			VersionBacklink::Object {
+				bucket_id: dest_bucket_id,
+				key: dest_key.to_string(),
+			},
+			false,
+		);
+		for (bk, bv) in source_version.blocks.items().iter() {
+			dest_version.blocks.put(*bk, *bv);
+		}
+		let dest_block_refs = dest_version
+			.blocks
+			.items()
+			.iter()
+			.map(|(_, vb)| BlockRef {
+				block: vb.hash,
+				version: new_uuid,
+				deleted: false.into(),
+			})
+			.collect::<Vec<_>>();
+		futures::try_join!(
+			garage.version_table.insert(&dest_version),
+			garage.block_ref_table.insert_many(&dest_block_refs[..]),
+		)?;
+	}
+
+	let dest_object = Object::new(
+		dest_bucket_id,
+		dest_key.to_string(),
+		vec![ObjectVersion {
+			uuid: new_uuid,
+			timestamp,
+			state: ObjectVersionState::Complete(source_data.clone()),
+		}],
+	);
+	garage.object_table.insert(&dest_object).await?;
+
+	Ok(Some(source_meta.size))
+}
+
+impl CloneBucketJob {
+	fn same_copy(&self, other: &CloneBucketJob) -> bool {
+		self.source_bucket_id == other.source_bucket_id
+			&& self.dest_bucket_id == other.dest_bucket_id
+			&& self.prefix == other.prefix
+	}
+}
+
+#[async_trait]
+impl Worker for CloneBucketWorker {
+	fn name(&self) -> String {
+		format!(
+			"Clone bucket {:?} into {:?}",
+			self.job.source_bucket_id, self.job.dest_bucket_id
+		)
+	}
+
+	fn status(&self) -> WorkerStatus {
+		let mut freeform = vec![format!("Started: {}", msec_to_rfc3339(self.job.started))];
+		if !self.job.prefix.is_empty() {
+			freeform.push(format!("Prefix: {}", self.job.prefix));
+		}
+		freeform.push(format!(
+			"Last key: {}",
+			self.job.last_key.as_deref().unwrap_or("-")
+		));
+		WorkerStatus {
+			progress: Some(format!(
+				"{} copied ({} bytes), {} skipped",
+				self.job.objects_copied, self.job.bytes_copied, self.job.objects_skipped
+			)),
+			freeform,
+			..Default::default()
+		}
+	}
+
+	async fn work(&mut self, _must_exit: &mut watch::Receiver<bool>) -> Result<WorkerState, Error> {
+		let dest_bucket = self
+			.garage
+			.bucket_table
+			.get(&EmptyKey, &self.job.dest_bucket_id)
+			.await?;
+		if dest_bucket.map(|b| b.is_deleted()).unwrap_or(true) {
+			warn!("{}: stopping, destination bucket was deleted", self.name());
+			let job = self.job.clone();
+			self.garage
+				.clone_bucket_persister
+				.set_with(|p| p.jobs.retain(|j| !j.same_copy(&job)))?;
+			return Ok(WorkerState::Done);
+		}
+
+		if self.copy_batch().await? {
+			info!(
+				"{}: finished, {} objects copied, {} skipped",
+				self.name(),
+				self.job.objects_copied,
+				self.job.objects_skipped
+			);
+			return Ok(WorkerState::Done);
+		}
+		Ok(WorkerState::Busy)
+	}
+
+	async fn wait_for_work(&mut self) -> WorkerState {
+		unreachable!()
+	}
+}
@@ -0,0 +1,299 @@
+//! Deletion of all the objects of a bucket in the background
+//! (e.g. after `garage bucket empty`)
+use std::sync::Arc;
+use std::time::Instant;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+
+use garage_util::background::*;
+use garage_util::data::*;
+use garage_util::error::Error;
+use garage_util::time::*;
+
+use garage_table::*;
+
+use crate::garage::Garage;
+use crate::job::*;
+use crate::s3::object_table::*;
+
+/// Maximum number of objects deleted in each batch
+const BATCH_SIZE: usize = 1000;
+
+/// Kind of the jobs emptying buckets
+pub const JOB_KIND: &str = "empty-bucket";
+
+mod v090 {
+	use garage_util::data::Uuid;
+	use serde::{Deserialize, Serialize};
+
+	/// Buckets being emptied by this node, saved before jobs were saved
+	/// in the job queue. Only read to move them to the job queue.
+	#[derive(Serialize, Deserialize, Default, Clone)]
+	pub struct EmptyBucketWorkerPersisted {
+		pub jobs: Vec<EmptyBucketJob>,
+	}
+
+	#[derive(Serialize, Deserialize, Clone, Debug)]
+	pub struct EmptyBucketJob {
+		pub bucket_id: Uuid,
+		/// Maximum number of objects processed per second
+		pub rate: u64,
+		/// Time at which the job was started
+		pub started: u64,
+		/// Key of the last object processed
+		pub last_key: Option<String>,
+		pub objects_deleted: u64,
+	}
+
+	impl garage_util::migrate::InitialFormat for EmptyBucketWorkerPersisted {
+		const VERSION_MARKER: &'static [u8] = b"G09ebwp";
+	}
+}
+
+pub use v090::*;
+
+#[derive(Serialize, Deserialize)]
+struct EmptyBucketParams {
+	bucket_id: Uuid,
+	rate: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct EmptyBucketCheckpoint {
+	last_key: Option<String>,
+	objects_deleted: u64,
+}
+
+/// Worker that deletes all the objects of a bucket, by writing delete markers
+/// and aborting uploads in progress as a DeleteObject call would: their data
+/// is then removed by the usual garbage collection of versions and blocks.
+///
+/// It runs as a job of the job queue, so that it is resumed when the node
+/// restarts.
+pub struct EmptyBucketWorker {
+	garage: Arc<Garage>,
+	bucket_id: Uuid,
+	job: EmptyBucketJob,
+}
+
+impl EmptyBucketWorker {
+	/// Start a job emptying a bucket, or return None if the bucket is
+	/// already being emptied by this node
+	pub fn start(
+		garage: &Arc<Garage>,
+		bg: &BackgroundRunner,
+		bucket_id: Uuid,
+		rate: u64,
+	) -> Result<Option<JobRecord>, Error> {
+		if Self::job_status(garage, bucket_id)?.is_some() {
+			return Ok(None);
+		}
+		let worker = Self {
+			garage: garage.clone(),
+			bucket_id,
+			job: EmptyBucketJob {
+				bucket_id,
+				rate,
+				started: now_msec(),
+				last_key: None,
+				objects_deleted: 0,
+			},
+		};
+		let job = garage.jobs.start(
+			bg,
+			JOB_KIND,
+			format!("Empty bucket {:?}", bucket_id),
+			&EmptyBucketParams { bucket_id, rate },
+			Box::new(worker),
+		)?;
+		Ok(Some(job))
+	}
+
+	/// Build the worker that resumes a job after a restart
+	pub fn resume(garage: &Arc<Garage>, record: &JobRecord) -> Result<Box<dyn JobWorker>, Error> {
+		let params = record.params::<EmptyBucketParams>()?;
+		let checkpoint = record.checkpoint::<EmptyBucketCheckpoint>()?;
+		Ok(Box::new(Self {
+			garage: garage.clone(),
+			bucket_id: params.bucket_id,
+			job: job_from_record(params, checkpoint, record.created),
+		}))
+	}
+
+	/// Move the jobs saved by previous versions of Garage to the job queue
+	pub fn migrate_persisted(garage: &Garage) -> Result<(), Error> {
+		let jobs = garage.empty_bucket_persister.get_with(|p| p.jobs.clone());
+		for job in jobs {
+			let params = EmptyBucketParams {
+				bucket_id: job.bucket_id,
+				rate: job.rate,
+			};
+			let record = garage.jobs.create(
+				JOB_KIND,
+				format!("Empty bucket {:?}", job.bucket_id),
+				&params,
+			)?;
+			let checkpoint = EmptyBucketCheckpoint {
+				last_key: job.last_key,
+				objects_deleted: job.objects_deleted,
+			};
+			garage
+				.jobs
+				.report(record.id, None, None, encode_checkpoint(&checkpoint))?;
+		}
+		garage.empty_bucket_persister.set_with(|p| p.jobs.clear())
+	}
+
+	/// State of the job emptying a bucket on this node, if there is one,
+	/// as of its last checkpoint
+	pub fn job_status(garage: &Garage, bucket_id: Uuid) -> Result<Option<EmptyBucketJob>, Error> {
+		for record in garage.jobs.list()? {
+			if record.kind != JOB_KIND || record.state != JobState::Running {
+				continue;
+			}
+			let params = record.params::<EmptyBucketParams>()?;
+			if params.bucket_id == bucket_id {
+				let checkpoint = record.checkpoint::<EmptyBucketCheckpoint>()?;
+				return Ok(Some(job_from_record(params, checkpoint, record.created)));
+			}
+		}
+		Ok(None)
+	}
+
+	/// Delete the objects of the next batch, returning the number of
+	/// objects processed and whether the end of the bucket was reached
+	async fn delete_batch(&mut self) -> Result<(usize, bool), Error> {
+		let limit = (self.job.rate as usize).clamp(1, BATCH_SIZE);
+		// Start after the last key processed, i.e. at the key that follows it
+		// in lexicographic order
+		let start = self.job.last_key.as_ref().map(|k| format!("{}\0", k));
+		let objects = self
+			.garage
+			.object_table
+			.get_range(
+				&self.bucket_id,
+				start,
+				None,
+				limit,
+				EnumerationOrder::Forward,
+			)
+			.await?;
+
+		let deletions = objects
+			.iter()
+			.filter_map(|object| {
+				let live_versions = object
+					.versions()
+					.iter()
+					.filter(|v| {
+						!matches!(
+							v.state,
+							ObjectVersionState::Aborted
+								| ObjectVersionState::Complete(ObjectVersionData::DeleteMarker)
+						)
+					})
+					.collect::<Vec<_>>();
+				if live_versions.is_empty() {
+					return None;
+				}
+				// The delete marker supersedes all versions of the object,
+				// including those of uploads that are still in progress
+				let timestamp = live_versions
+					.iter()
+					.map(|v| v.timestamp + 1)
+					.fold(now_msec(), std::cmp::max);
+				Some(Object::new(
+					object.bucket_id,
+					object.key.clone(),
+					vec![ObjectVersion {
+						uuid: gen_uuid(),
+						timestamp,
+						state: ObjectVersionState::Complete(ObjectVersionData::DeleteMarker),
+					}],
+				))
+			})
+			.collect::<Vec<_>>();
+		let n_deleted = deletions.len() as u64;
+		self.garage.object_table.insert_many(deletions).await?;
+
+		let finished = objects.len() < limit;
+		if let Some(last) = objects.last() {
+			self.job.last_key = Some(last.key.clone());
+		}
+		self.job.objects_deleted += n_deleted;
+
+		Ok((objects.len(), finished))
+	}
+}
+
+#[async_trait]
+impl Worker for EmptyBucketWorker {
+	fn name(&self) -> String {
+		format!("Empty bucket {:?}", self.bucket_id)
+	}
+
+	fn status(&self) -> WorkerStatus {
+		WorkerStatus {
+			progress: Some(format!("{} objects deleted", self.job.objects_deleted)),
+			freeform: vec![
+				format!("Rate limit: {} objects/s", self.job.rate),
+				format!("Started: {}", msec_to_rfc3339(self.job.started)),
+				format!("Last key: {}", self.job.last_key.as_deref().unwrap_or("-")),
+			],
+			..Default::default()
+		}
+	}
+
+	async fn work(&mut self, _must_exit: &mut watch::Receiver<bool>) -> Result<WorkerState, Error> {
+		let start = Instant::now();
+		let (count, finished) = self.delete_batch().await?;
+		if finished {
+			info!(
+				"{}: finished, {} objects deleted",
+				self.name(),
+				self.job.objects_deleted
+			);
+			return Ok(WorkerState::Done);
+		}
+
+		let target = count as f32 / std::cmp::max(self.job.rate, 1) as f32;
+		let elapsed = start.elapsed().as_secs_f32();
+		if elapsed < target {
+			Ok(WorkerState::Throttled(target - elapsed))
+		} else {
+			Ok(WorkerState::Busy)
+		}
+	}
+
+	async fn wait_for_work(&mut self) -> WorkerState {
+		unreachable!()
+	}
+}
+
+impl JobWorker for EmptyBucketWorker {
+	fn checkpoint(&self) -> Vec<u8> {
+		encode_checkpoint(&EmptyBucketCheckpoint {
+			last_key: self.job.last_key.clone(),
+			objects_deleted: self.job.objects_deleted,
+		})
+	}
+}
+
+fn job_from_record(
+	params: EmptyBucketParams,
+	checkpoint: Option<EmptyBucketCheckpoint>,
+	started: u64,
+) -> EmptyBucketJob {
+	let (last_key, objects_deleted) = checkpoint
+		.map(|c| (c.last_key, c.objects_deleted))
+		.unwrap_or((None, 0));
+	EmptyBucketJob {
+		bucket_id: params.bucket_id,
+		rate: params.rate,
+		started,
+		last_key,
+		objects_deleted,
+	}
+}
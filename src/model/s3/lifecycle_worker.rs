@@ -14,7 +14,9 @@ use garage_util::time::*;
 use garage_table::EmptyKey;
 
 use crate::bucket_table::*;
+use crate::job::{JobState, CHECKPOINT_INTERVAL};
 use crate::s3::object_table::*;
+use crate::s3::trash::trash_partition;
 
 use crate::garage::Garage;
 
@@ -33,10 +35,15 @@ mod v090 {
 
 pub use v090::*;
 
+/// Kind of the jobs recording the daily runs of the lifecycle worker.
+/// Runs are not resumed after a restart: they start over from the beginning.
+pub const JOB_KIND: &str = "lifecycle";
+
 pub struct LifecycleWorker {
 	garage: Arc<Garage>,
 
 	state: State,
+	last_report: Option<Instant>,
 
 	persister: PersisterShared<LifecycleWorkerPersisted>,
 }
@@ -50,6 +57,8 @@ enum State {
 		objects_expired: usize,
 		mpu_aborted: usize,
 		last_bucket: Option<Bucket>,
+		/// Job recording this run, created when the run starts
+		job: Option<Uuid>,
 	},
 }
 
@@ -83,9 +92,67 @@ impl LifecycleWorker {
 		Self {
 			garage,
 			state,
+			last_report: None,
 			persister,
 		}
 	}
+
+	fn create_job(garage: &Garage, date: NaiveDate) -> Option<Uuid> {
+		let res = garage.jobs.create(
+			JOB_KIND,
+			format!("Object lifecycle for {}", date),
+			&date.to_string(),
+		);
+		match res {
+			Ok(job) => Some(job.id),
+			Err(e) => {
+				error!("Lifecycle worker: unable to record job: {}", e);
+				None
+			}
+		}
+	}
+
+	fn report_job(&mut self, id: Uuid, state: Option<JobState>) {
+		self.last_report = Some(Instant::now());
+		let (progress, status) = match &self.state {
+			State::Running {
+				counter,
+				objects_expired,
+				mpu_aborted,
+				..
+			} => (
+				self.progress(*counter),
+				format!(
+					"{} objects expired, {} multipart uploads aborted",
+					objects_expired, mpu_aborted
+				),
+			),
+			State::Completed(_) => return,
+		};
+		let mut res = self.garage.jobs.report(id, progress, Some(status), vec![]);
+		if let Some(state) = state {
+			res = res.and_then(|()| self.garage.jobs.finish(id, state));
+		}
+		if let Err(e) = res {
+			error!("Lifecycle worker: unable to save state of job: {}", e);
+		}
+	}
+
+	/// Fraction of the objects processed, approximated using the number
+	/// of objects in the table
+	fn progress(&self, counter: usize) -> Option<f32> {
+		let total = self
+			.garage
+			.object_table
+			.data
+			.store
+			.fast_len()
+			.unwrap_or(None)?;
+		if total == 0 {
+			return Some(1.);
+		}
+		Some(std::cmp::min(counter, total) as f32 / total as f32)
+	}
 }
 
 impl State {
@@ -98,6 +165,7 @@ impl State {
 			objects_expired: 0,
 			mpu_aborted: 0,
 			last_bucket: None,
+			job: None,
 		}
 	}
 }
@@ -158,7 +226,21 @@ impl Worker for LifecycleWorker {
 				mpu_aborted,
 				pos,
 				last_bucket,
+				job,
 			} => {
+				if job.is_none() {
+					*job = Self::create_job(&self.garage, *date);
+				}
+				let job = *job;
+				let jobs = &self.garage.jobs;
+				if let Some(id) = job.filter(|id| jobs.is_cancelled(*id)) {
+					info!("Lifecycle worker cancelled for {}", date);
+					let date = *date;
+					self.report_job(id, Some(JobState::Cancelled));
+					self.state = State::Completed(date);
+					return Ok(WorkerState::Idle);
+				}
+
 				// Process a batch of 100 items before yielding to bg task scheduler
 				for _ in 0..100 {
 					let (object_bytes, next_pos) = match self
@@ -172,7 +254,11 @@ impl Worker for LifecycleWorker {
 							info!("Lifecycle worker finished for {}, objects expired: {}, mpu aborted: {}", date, *objects_expired, *mpu_aborted);
 							self.persister
 								.set_with(|x| x.last_completed = Some(date.to_string()))?;
-							self.state = State::Completed(*date);
+							let date = *date;
+							if let Some(id) = job {
+								self.report_job(id, Some(JobState::Completed));
+							}
+							self.state = State::Completed(date);
 							return Ok(WorkerState::Idle);
 						}
 						Some((k, v)) => (v, k),
@@ -203,6 +289,16 @@ impl Worker for LifecycleWorker {
 					}
 				}
 
+				if let Some(id) = job {
+					if self
+						.last_report
+						.map(|t| t.elapsed() > CHECKPOINT_INTERVAL)
+						.unwrap_or(true)
+					{
+						self.report_job(id, None);
+					}
+				}
+
 				Ok(WorkerState::Busy)
 			}
 		}
@@ -257,6 +353,15 @@ async fn process_object(
 				.await?
 			{
 				Some(b) => b,
+				None if garage
+					.bucket_table
+					.get(&EmptyKey, &trash_partition(&object.bucket_id))
+					.await?
+					.is_some() =>
+				{
+					// Objects in the trash of a bucket are deleted by the trash worker
+					return Ok(Skip::SkipBucket);
+				}
 				None => {
 					warn!(
 						"Lifecycle worker: object in non-existent bucket {:?}",
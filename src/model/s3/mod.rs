@@ -3,4 +3,7 @@ pub mod mpu_table;
 pub mod object_table;
 pub mod version_table;
 
+pub mod clone_bucket_worker;
+pub mod empty_bucket_worker;
 pub mod lifecycle_worker;
+pub mod trash;
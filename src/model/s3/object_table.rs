@@ -17,6 +17,34 @@ pub const OBJECTS: &str = "objects";
 pub const UNFINISHED_UPLOADS: &str = "unfinished_uploads";
 pub const BYTES: &str = "bytes";
 
+/// Header in which the storage class of an object is stored, when it is
+/// not the default class
+pub const STORAGE_CLASS_HEADER: &str = "x-amz-storage-class";
+/// Storage class of the objects for which no other class was requested
+pub const DEFAULT_STORAGE_CLASS: &str = "STANDARD";
+
+/// Storage classes that can be requested for objects, other than the default
+/// class, with the names of their object and byte counters. Garage stores all
+/// objects the same way: the class is only recorded, returned and counted.
+/// Usage of the default class is the total usage minus that of other classes.
+pub const STORAGE_CLASSES: &[(&str, &str, &str)] = &[
+	(
+		"REDUCED_REDUNDANCY",
+		"objects.REDUCED_REDUNDANCY",
+		"bytes.REDUCED_REDUNDANCY",
+	),
+	("STANDARD_IA", "objects.STANDARD_IA", "bytes.STANDARD_IA"),
+	("ONEZONE_IA", "objects.ONEZONE_IA", "bytes.ONEZONE_IA"),
+	(
+		"INTELLIGENT_TIERING",
+		"objects.INTELLIGENT_TIERING",
+		"bytes.INTELLIGENT_TIERING",
+	),
+	("GLACIER", "objects.GLACIER", "bytes.GLACIER"),
+	("GLACIER_IR", "objects.GLACIER_IR", "bytes.GLACIER_IR"),
+	("DEEP_ARCHIVE", "objects.DEEP_ARCHIVE", "bytes.DEEP_ARCHIVE"),
+];
+
 mod v05 {
 	use garage_util::data::{Hash, Uuid};
 	use serde::{Deserialize, Serialize};
@@ -172,6 +200,10 @@ mod v09 {
 			multipart: bool,
 			/// Headers to be included in the final object
 			headers: ObjectVersionHeaders,
+			/// Access key that started the upload, recorded for multipart
+			/// uploads
+			#[serde(default)]
+			initiator: Option<String>,
 		},
 		/// The version is fully received
 		Complete(ObjectVersionData),
@@ -195,6 +227,7 @@ mod v09 {
 						v08::ObjectVersionState::Uploading(h) => ObjectVersionState::Uploading {
 							multipart: false,
 							headers: h,
+							initiator: None,
 						},
 						v08::ObjectVersionState::Complete(d) => ObjectVersionState::Complete(d),
 						v08::ObjectVersionState::Aborted => ObjectVersionState::Aborted,
@@ -278,6 +311,15 @@ impl ObjectVersion {
 		(self.timestamp, self.uuid)
 	}
 
+	/// Access key that started the upload of this version, if it is being
+	/// uploaded and the key was recorded
+	pub fn initiator(&self) -> Option<&str> {
+		match &self.state {
+			ObjectVersionState::Uploading { initiator, .. } => initiator.as_deref(),
+			_ => None,
+		}
+	}
+
 	/// Is the object version currently being uploaded
 	///
 	/// matches only multipart uploads if check_multipart is Some(true)
@@ -509,10 +551,38 @@ impl CountedItem for Object {
 			})
 			.sum::<u64>();
 
-		vec![
+		let mut counts = vec![
 			(OBJECTS, n_objects),
 			(UNFINISHED_UPLOADS, n_unfinished_uploads as i64),
 			(BYTES, n_bytes as i64),
-		]
+		];
+
+		// Usage of the non-default storage classes
+		for v in versions.iter() {
+			if let ObjectVersionState::Complete(
+				ObjectVersionData::Inline(meta, _) | ObjectVersionData::FirstBlock(meta, _),
+			) = &v.state
+			{
+				let class = meta.headers.storage_class();
+				if let Some((_, objects, bytes)) =
+					STORAGE_CLASSES.iter().find(|(c, _, _)| *c == class)
+				{
+					counts.push((objects, 1));
+					counts.push((bytes, meta.size as i64));
+				}
+			}
+		}
+
+		counts
+	}
+}
+
+impl ObjectVersionHeaders {
+	/// Storage class that was requested for the object
+	pub fn storage_class(&self) -> &str {
+		self.other
+			.get(STORAGE_CLASS_HEADER)
+			.map(String::as_str)
+			.unwrap_or(DEFAULT_STORAGE_CLASS)
 	}
 }
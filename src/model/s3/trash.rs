@@ -0,0 +1,330 @@
+//! Trash of buckets: objects deleted with the S3 API in buckets that have a
+//! trash retention period are kept for this period, and can be restored.
+//! The trash of a bucket is stored in a separate partition of the object
+//! table, so its objects are not counted in the usage and quotas of the bucket.
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+
+use garage_util::background::*;
+use garage_util::data::*;
+use garage_util::error::Error;
+use garage_util::time::*;
+
+use garage_table::*;
+
+use crate::bucket_table::Bucket;
+use crate::garage::Garage;
+use crate::s3::clone_bucket_worker::copy_object_version;
+use crate::s3::object_table::*;
+
+const TRASH_PURGE_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Number of objects of the trash processed in each batch
+const BATCH_SIZE: usize = 1000;
+
+/// An object in the trash of a bucket
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TrashedObject {
+	pub key: String,
+	pub size: u64,
+	pub etag: String,
+	/// Time at which the object was deleted
+	pub deleted: u64,
+}
+
+/// Identifier of the partition of the object table in which the trash of
+/// a bucket is stored. It is obtained by inverting the bits of the ID of the
+/// bucket, so that the bucket of a trash partition can be found from it.
+/// Trash partitions are not buckets, and cannot be accessed with the S3 API.
+pub fn trash_partition(bucket_id: &Uuid) -> Uuid {
+	let mut id = *bucket_id;
+	for b in id.as_slice_mut() {
+		*b = !*b;
+	}
+	id
+}
+
+/// Copy the current version of an object into the trash of its bucket,
+/// before it is deleted. A previous copy of an object with the same key
+/// is replaced.
+pub async fn move_to_trash(garage: &Garage, bucket_id: Uuid, object: &Object) -> Result<(), Error> {
+	let current = object
+		.versions()
+		.iter()
+		.rev()
+		.find(|v| v.is_complete())
+		.filter(|v| v.is_data());
+	if let Some(v) = current {
+		copy_object_version(
+			garage,
+			v,
+			trash_partition(&bucket_id),
+			&object.key,
+			now_msec(),
+		)
+		.await?;
+	}
+	Ok(())
+}
+
+/// List at most `limit` objects of the trash of a bucket whose key starts
+/// with `prefix`, from key `start`
+pub async fn list_trash(
+	garage: &Garage,
+	bucket_id: Uuid,
+	prefix: &str,
+	start: Option<String>,
+	limit: usize,
+) -> Result<Vec<TrashedObject>, Error> {
+	let start = match start {
+		Some(s) if s.as_str() > prefix => s,
+		_ => prefix.to_string(),
+	};
+	let objects = garage
+		.object_table
+		.get_range(
+			&trash_partition(&bucket_id),
+			Some(start),
+			Some(ObjectFilter::IsData),
+			limit,
+			EnumerationOrder::Forward,
+		)
+		.await?;
+	Ok(objects
+		.iter()
+		.take_while(|o| o.key.starts_with(prefix))
+		.filter_map(trashed_object)
+		.collect())
+}
+
+fn trashed_object(object: &Object) -> Option<TrashedObject> {
+	let v = object.versions().iter().rev().find(|v| v.is_data())?;
+	match &v.state {
+		ObjectVersionState::Complete(ObjectVersionData::Inline(meta, _))
+		| ObjectVersionState::Complete(ObjectVersionData::FirstBlock(meta, _)) => Some(TrashedObject {
+			key: object.key.clone(),
+			size: meta.size,
+			etag: meta.etag.clone(),
+			deleted: v.timestamp,
+		}),
+		_ => None,
+	}
+}
+
+/// Restore an object from the trash of its bucket, returning whether it was
+/// restored. Objects whose key was written again since they were deleted are
+/// not restored.
+pub async fn restore_from_trash(
+	garage: &Garage,
+	bucket_id: Uuid,
+	key: &str,
+) -> Result<bool, Error> {
+	let partition = trash_partition(&bucket_id);
+	let trashed = match garage
+		.object_table
+		.get(&partition, &key.to_string())
+		.await?
+	{
+		Some(o) => o,
+		None => return Ok(false),
+	};
+	let trashed_version = match trashed.versions().iter().rev().find(|v| v.is_data()) {
+		Some(v) => v,
+		None => return Ok(false),
+	};
+
+	let object = garage
+		.object_table
+		.get(&bucket_id, &key.to_string())
+		.await?;
+	let versions = object.as_ref().map(|o| o.versions()).unwrap_or_default();
+	if versions.iter().any(|v| v.is_data()) {
+		return Ok(false);
+	}
+	let timestamp = versions
+		.iter()
+		.map(|v| v.timestamp + 1)
+		.fold(now_msec(), std::cmp::max);
+
+	if copy_object_version(garage, trashed_version, bucket_id, key, timestamp)
+		.await?
+		.is_none()
+	{
+		return Ok(false);
+	}
+	delete_from_trash(garage, &trashed).await?;
+	Ok(true)
+}
+
+/// Restore all the objects of the trash of a bucket whose key starts with
+/// `prefix`, returning the number of objects restored and the number of
+/// objects that were not restored
+pub async fn restore_prefix_from_trash(
+	garage: &Garage,
+	bucket_id: Uuid,
+	prefix: &str,
+) -> Result<(u64, u64), Error> {
+	let mut restored = 0;
+	let mut skipped = 0;
+	let mut start = None;
+	loop {
+		let trashed = list_trash(garage, bucket_id, prefix, start, BATCH_SIZE).await?;
+		for t in trashed.iter() {
+			if restore_from_trash(garage, bucket_id, &t.key).await? {
+				restored += 1;
+			} else {
+				skipped += 1;
+			}
+		}
+		match trashed.last() {
+			Some(last) if trashed.len() == BATCH_SIZE => start = Some(format!("{}\0", last.key)),
+			_ => break,
+		}
+	}
+	Ok((restored, skipped))
+}
+
+/// Delete an object from the trash, which deletes its data
+async fn delete_from_trash(garage: &Garage, trashed: &Object) -> Result<(), Error> {
+	let timestamp = trashed
+		.versions()
+		.iter()
+		.map(|v| v.timestamp + 1)
+		.fold(now_msec(), std::cmp::max);
+	let object = Object::new(
+		trashed.bucket_id,
+		trashed.key.clone(),
+		vec![ObjectVersion {
+			uuid: gen_uuid(),
+			timestamp,
+			state: ObjectVersionState::Complete(ObjectVersionData::DeleteMarker),
+		}],
+	);
+	garage.object_table.insert(&object).await?;
+	Ok(())
+}
+
+/// Worker that deletes the objects of the trash of buckets once their
+/// retention period is over, and all objects of the trash of buckets that were
+/// deleted or whose trash was disabled. It runs on all nodes but only acts on
+/// the storage node with the smallest ID among those that are up.
+pub struct TrashWorker {
+	garage: Arc<Garage>,
+	last_purged: u64,
+}
+
+impl TrashWorker {
+	pub fn new(garage: Arc<Garage>) -> Self {
+		Self {
+			garage,
+			last_purged: 0,
+		}
+	}
+
+	async fn purge(&mut self) -> Result<(), Error> {
+		if !self.garage.system.is_leader() {
+			return Ok(());
+		}
+
+		let now = now_msec();
+		let mut purged = 0;
+		let mut start = None;
+		loop {
+			let buckets = self
+				.garage
+				.bucket_table
+				.get_range(
+					&EmptyKey,
+					start,
+					Some(DeletedFilter::Any),
+					BATCH_SIZE,
+					EnumerationOrder::Forward,
+				)
+				.await?;
+			// The range starts at the last bucket of the previous batch
+			for bucket in buckets.iter().filter(|b| Some(b.id) != start) {
+				purged += self.purge_bucket(bucket, now).await?;
+			}
+			match buckets.last() {
+				Some(last) if buckets.len() == BATCH_SIZE => start = Some(last.id),
+				_ => break,
+			}
+		}
+
+		if purged > 0 {
+			info!("Deleted {} objects from the trash of buckets", purged);
+		}
+		self.last_purged = now;
+		Ok(())
+	}
+
+	/// Delete the objects of the trash of a bucket whose retention period
+	/// is over, returning the number of objects deleted
+	async fn purge_bucket(&self, bucket: &Bucket, now: u64) -> Result<usize, Error> {
+		let expiration = bucket
+			.trash_retention_days()
+			.map(|days| now.saturating_sub(days * 24 * 3600 * 1000))
+			.unwrap_or(u64::MAX);
+
+		let mut purged = 0;
+		let mut start = None;
+		loop {
+			let objects = self
+				.garage
+				.object_table
+				.get_range(
+					&trash_partition(&bucket.id),
+					start,
+					Some(ObjectFilter::IsData),
+					BATCH_SIZE,
+					EnumerationOrder::Forward,
+				)
+				.await?;
+			for object in objects.iter() {
+				if matches!(trashed_object(object), Some(t) if t.deleted < expiration) {
+					delete_from_trash(&self.garage, object).await?;
+					purged += 1;
+				}
+			}
+			match objects.last() {
+				Some(last) if objects.len() == BATCH_SIZE => {
+					start = Some(format!("{}\0", last.key))
+				}
+				_ => break,
+			}
+		}
+		Ok(purged)
+	}
+}
+
+#[async_trait]
+impl Worker for TrashWorker {
+	fn name(&self) -> String {
+		"Bucket trash".to_string()
+	}
+
+	fn status(&self) -> WorkerStatus {
+		let last_purged = match self.last_purged {
+			0 => "never".to_string(),
+			t => msec_to_rfc3339(t),
+		};
+		WorkerStatus {
+			freeform: vec![format!("Last purge: {}", last_purged)],
+			..Default::default()
+		}
+	}
+
+	async fn work(&mut self, _must_exit: &mut watch::Receiver<bool>) -> Result<WorkerState, Error> {
+		self.purge().await?;
+		Ok(WorkerState::Idle)
+	}
+
+	async fn wait_for_work(&mut self) -> WorkerState {
+		tokio::time::sleep(TRASH_PURGE_INTERVAL).await;
+		WorkerState::Busy
+	}
+}
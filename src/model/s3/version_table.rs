@@ -138,6 +138,12 @@ pub(crate) mod v09 {
 		// upload), used to find whether it has been deleted and this version
 		// should in turn be deleted (see versions repair procedure)
 		pub backlink: VersionBacklink,
+
+		/// Whether this version is being uploaded by resumable PutObject
+		/// requests (Garage extension): its blocks are kept when a request is
+		/// interrupted, and the upload can be continued from their end
+		#[serde(default)]
+		pub resumable: crdt::Bool,
 	}
 
 	#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
@@ -167,6 +173,7 @@ pub(crate) mod v09 {
 					bucket_id: old.bucket_id,
 					key: old.key,
 				},
+				resumable: false.into(),
 			}
 		}
 	}
@@ -181,6 +188,7 @@ impl Version {
 			deleted: deleted.into(),
 			blocks: crdt::Map::new(),
 			backlink,
+			resumable: false.into(),
 		}
 	}
 
@@ -235,6 +243,7 @@ impl Entry<Uuid, EmptyKey> for Version {
 impl Crdt for Version {
 	fn merge(&mut self, other: &Self) {
 		self.deleted.merge(&other.deleted);
+		self.resumable.merge(&other.resumable);
 
 		if self.deleted.get() {
 			self.blocks.clear();
@@ -0,0 +1,106 @@
+use garage_table::crdt::*;
+use garage_table::*;
+
+mod v09 {
+	use std::collections::BTreeMap;
+
+	use garage_util::crdt;
+	use garage_util::data::Uuid;
+	use serde::{Deserialize, Serialize};
+
+	/// The TLS certificate table holds the certificates obtained with ACME
+	/// for the domain names served by the web endpoint, as well as the
+	/// orders that are in progress for them. It is replicated on all
+	/// nodes, so that any node can answer the ACME server's validation
+	/// requests and serve the certificates.
+	#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+	pub struct TlsCertificate {
+		pub(super) domain: String,
+		/// The current certificate for this domain, if one has been issued
+		pub certificate: crdt::Lww<Option<CertificateData>>,
+		/// The order that is in progress for this domain, if any
+		pub pending_order: crdt::Lww<Option<PendingOrder>>,
+		/// Timestamp and message of the last error that happened
+		/// while trying to obtain a certificate for this domain
+		pub last_error: crdt::Lww<Option<(u64, String)>>,
+	}
+
+	#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+	pub struct CertificateData {
+		/// PEM-encoded certificate chain, leaf certificate first
+		pub cert_chain_pem: String,
+		/// PEM-encoded PKCS#8 private key of the certificate, encrypted
+		/// with a key derived from the RPC secret so that it is not stored
+		/// in clear in the metadata of every node (see `garage_web::tls`)
+		#[serde(with = "serde_bytes")]
+		pub encrypted_private_key: Vec<u8>,
+		/// Timestamp at which the certificate was issued, in msec
+		pub issued_at: u64,
+	}
+
+	#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+	pub struct PendingOrder {
+		/// Node that is requesting the certificate
+		pub node: Uuid,
+		/// Timestamp at which the order was started, in msec
+		pub started_at: u64,
+		/// HTTP-01 challenges for this order: map from the token sent by
+		/// the ACME server to the key authorization it expects in return
+		pub challenges: BTreeMap<String, String>,
+	}
+
+	impl garage_util::migrate::InitialFormat for TlsCertificate {
+		const VERSION_MARKER: &'static [u8] = b"G09tlscert";
+	}
+}
+
+pub use v09::*;
+
+impl TlsCertificate {
+	pub fn new(domain: String) -> Self {
+		// Use timestamp zero, so that values stored for this domain
+		// by other nodes always take precedence
+		TlsCertificate {
+			domain,
+			certificate: crdt::Lww::raw(0, None),
+			pending_order: crdt::Lww::raw(0, None),
+			last_error: crdt::Lww::raw(0, None),
+		}
+	}
+
+	pub fn domain(&self) -> &str {
+		&self.domain
+	}
+}
+
+impl Crdt for TlsCertificate {
+	fn merge(&mut self, o: &Self) {
+		self.certificate.merge(&o.certificate);
+		self.pending_order.merge(&o.pending_order);
+		self.last_error.merge(&o.last_error);
+	}
+}
+
+impl Entry<EmptyKey, String> for TlsCertificate {
+	fn partition_key(&self) -> &EmptyKey {
+		&EmptyKey
+	}
+	fn sort_key(&self) -> &String {
+		&self.domain
+	}
+}
+
+pub struct TlsCertificateTable;
+
+impl TableSchema for TlsCertificateTable {
+	const TABLE_NAME: &'static str = "tls_certificate";
+
+	type P = EmptyKey;
+	type S = String;
+	type E = TlsCertificate;
+	type Filter = ();
+
+	fn matches_filter(_entry: &Self::E, _filter: &Self::Filter) -> bool {
+		true
+	}
+}
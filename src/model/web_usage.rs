@@ -0,0 +1,147 @@
+//! Accounting of the requests served and of the bytes sent by the web
+//! endpoint for each bucket, in windows of one hour, e.g. to find the
+//! websites that use the most bandwidth
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::select;
+use tokio::sync::watch;
+
+use garage_db as db;
+
+use garage_rpc::system::System;
+use garage_util::background::*;
+use garage_util::data::*;
+use garage_util::error::*;
+use garage_util::time::*;
+
+use garage_table::replication::TableShardedReplication;
+
+use crate::index_counter::*;
+use crate::key_usage::window_sort_key;
+
+pub const REQUESTS: &str = "requests";
+pub const ERRORS: &str = "errors";
+pub const BYTES_SENT: &str = "bytes_sent";
+
+/// Interval at which usage accounted in memory is written to the counter table
+const FLUSH_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Usage of the website of a bucket during a time window, that was recorded
+/// by this node since the last flush. It is counted as an increment in the
+/// web usage counter table.
+#[derive(Clone, PartialEq)]
+pub struct WebUsage {
+	window: String,
+	bucket_id: Uuid,
+	requests: u64,
+	errors: u64,
+	bytes_sent: u64,
+}
+
+impl CountedItem for WebUsage {
+	const COUNTER_TABLE_NAME: &'static str = "web_usage_counter";
+
+	// Partition key = start of the time window, so that the usage of all
+	// buckets during a window can be read at once
+	type CP = String;
+	// Sort key = bucket id
+	type CS = Uuid;
+
+	fn counter_partition_key(&self) -> &String {
+		&self.window
+	}
+	fn counter_sort_key(&self) -> &Uuid {
+		&self.bucket_id
+	}
+
+	fn counts(&self) -> Vec<(&'static str, i64)> {
+		vec![
+			(REQUESTS, self.requests as i64),
+			(ERRORS, self.errors as i64),
+			(BYTES_SENT, self.bytes_sent as i64),
+		]
+	}
+}
+
+pub struct WebUsageCounter {
+	db: db::Db,
+	pub counter: Arc<IndexCounter<WebUsage>>,
+	pending: Mutex<HashMap<(String, Uuid), WebUsage>>,
+}
+
+impl WebUsageCounter {
+	pub fn new(
+		system: Arc<System>,
+		replication: TableShardedReplication,
+		db: &db::Db,
+	) -> Arc<Self> {
+		Arc::new(Self {
+			db: db.clone(),
+			counter: IndexCounter::new(system, replication, db),
+			pending: Mutex::new(HashMap::new()),
+		})
+	}
+
+	pub fn spawn_workers(self: &Arc<Self>, bg: &BackgroundRunner) {
+		self.counter.spawn_workers(bg);
+		bg.spawn_worker(WebUsageFlushWorker(self.clone()));
+	}
+
+	/// Record usage of the website of a bucket. Usage is accumulated
+	/// in memory and written to the counter table every few seconds.
+	pub fn record(&self, bucket_id: Uuid, requests: u64, errors: u64, bytes_sent: u64) {
+		let window = window_sort_key(now_msec());
+		let mut pending = self.pending.lock().unwrap();
+		let usage = pending
+			.entry((window.clone(), bucket_id))
+			.or_insert_with(|| WebUsage {
+				window,
+				bucket_id,
+				requests: 0,
+				errors: 0,
+				bytes_sent: 0,
+			});
+		usage.requests += requests;
+		usage.errors += errors;
+		usage.bytes_sent += bytes_sent;
+	}
+
+	fn flush(&self) -> Result<(), Error> {
+		let pending = std::mem::take(&mut *self.pending.lock().unwrap());
+		for usage in pending.values() {
+			self.db
+				.transaction(|tx| self.counter.count(tx, None, Some(usage)))?;
+		}
+		Ok(())
+	}
+}
+
+struct WebUsageFlushWorker(Arc<WebUsageCounter>);
+
+#[async_trait]
+impl Worker for WebUsageFlushWorker {
+	fn name(&self) -> String {
+		"Web usage accounting".into()
+	}
+
+	async fn work(&mut self, must_exit: &mut watch::Receiver<bool>) -> Result<WorkerState, Error> {
+		select! {
+			_ = tokio::time::sleep(FLUSH_INTERVAL) => (),
+			_ = must_exit.changed() => (),
+		}
+		// Usage is also flushed when the node is stopping
+		self.0.flush()?;
+		if *must_exit.borrow() {
+			Ok(WorkerState::Done)
+		} else {
+			Ok(WorkerState::Busy)
+		}
+	}
+
+	async fn wait_for_work(&mut self) -> WorkerState {
+		unreachable!()
+	}
+}
@@ -0,0 +1,304 @@
+//! Accounting of the bytes exchanged with each other node in the RPCs made
+//! by this node, and shaping of the traffic on the links between zones that
+//! have a rate limit.
+//!
+//! The size of a request or of a response is the size of its serialized
+//! message, which is measured without buffering it, and the number of bytes
+//! read from its attached stream. Limits apply separately to each direction:
+//! the requests of this node wait until they can be sent within the rate of
+//! the link, and the streams of the responses are read no faster than this
+//! rate. Requests and responses on links without a limit are only counted.
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use futures::stream::StreamExt;
+use tokio::sync::{mpsc, watch};
+use tokio_stream::wrappers::ReceiverStream;
+
+use netapp::message::{Message as Rpc, Req, Resp};
+use netapp::stream::ByteStream;
+
+use garage_util::config::RpcLinkLimitConfig;
+use garage_util::data::*;
+use garage_util::encode::nonversioned_encoded_len;
+use garage_util::error::Error;
+
+use crate::ring::Ring;
+
+/// Bytes exchanged with another node since this node started
+#[derive(Debug, Clone)]
+pub struct LinkStats {
+	pub node: Uuid,
+	pub bytes_sent: u64,
+	pub bytes_received: u64,
+	/// Seconds since the node started, to compute average rates
+	pub elapsed_secs: f64,
+	/// Rate limit of the link with the node, in bytes per second
+	pub max_rate: Option<u64>,
+}
+
+pub struct Bandwidth {
+	ring: watch::Receiver<Arc<Ring>>,
+	our_node_id: Uuid,
+	limits: Vec<LinkLimit>,
+	started: Instant,
+	links: RwLock<HashMap<Uuid, Arc<LinkCounters>>>,
+}
+
+struct LinkLimit {
+	zones: [String; 2],
+	max_rate: u64,
+	send: Arc<RateLimiter>,
+	receive: Arc<RateLimiter>,
+}
+
+#[derive(Default)]
+struct LinkCounters {
+	sent: AtomicU64,
+	received: AtomicU64,
+}
+
+/// Token bucket that lets through bursts of up to one second of traffic
+struct RateLimiter {
+	rate: f64,
+	/// Available tokens, negative when transfers are waiting for
+	/// tokens that they have already taken, and time of last update
+	state: Mutex<(f64, Instant)>,
+}
+
+impl Bandwidth {
+	pub(crate) fn new(
+		our_node_id: Uuid,
+		ring: watch::Receiver<Arc<Ring>>,
+		limits: &[RpcLinkLimitConfig],
+	) -> Self {
+		let limits = limits
+			.iter()
+			.filter(|l| {
+				if l.max_rate == 0 {
+					warn!("Ignoring RPC link limit of 0 between zones {:?}", l.zones);
+				}
+				l.max_rate > 0
+			})
+			.map(|l| LinkLimit {
+				zones: l.zones.clone(),
+				max_rate: l.max_rate as u64,
+				send: Arc::new(RateLimiter::new(l.max_rate as u64)),
+				receive: Arc::new(RateLimiter::new(l.max_rate as u64)),
+			})
+			.collect();
+		Self {
+			ring,
+			our_node_id,
+			limits,
+			started: Instant::now(),
+			links: RwLock::new(HashMap::new()),
+		}
+	}
+
+	/// Bytes exchanged with each node this node has made RPCs to
+	pub fn link_stats(&self) -> Vec<LinkStats> {
+		let elapsed_secs = self.started.elapsed().as_secs_f64();
+		let mut stats = self
+			.links
+			.read()
+			.unwrap()
+			.iter()
+			.map(|(node, counters)| LinkStats {
+				node: *node,
+				bytes_sent: counters.sent.load(Ordering::Relaxed),
+				bytes_received: counters.received.load(Ordering::Relaxed),
+				elapsed_secs,
+				max_rate: self.limit(*node).map(|l| l.max_rate),
+			})
+			.collect::<Vec<_>>();
+		stats.sort_by_key(|s| s.node);
+		stats
+	}
+
+	/// Count a request to a node, wait until it can be sent within the rate
+	/// limit of the link, and make its stream count and respect the limit
+	pub(crate) async fn shape_request<M: Rpc>(
+		&self,
+		to: Uuid,
+		mut req: Req<M>,
+	) -> Result<Req<M>, Error> {
+		if to == self.our_node_id {
+			return Ok(req);
+		}
+		let counters = self.counters(to);
+		let limiter = self.limit(to).map(|l| l.send.clone());
+
+		let size = nonversioned_encoded_len(req.msg())?;
+		counters.sent.fetch_add(size, Ordering::Relaxed);
+		if let Some(limiter) = &limiter {
+			limiter.take(size).await;
+		}
+
+		Ok(match req.take_stream() {
+			Some(stream) => {
+				let counter = move |n| {
+					counters.sent.fetch_add(n, Ordering::Relaxed);
+				};
+				req.with_stream(shape_stream(stream, counter, limiter))
+			}
+			None => req,
+		})
+	}
+
+	/// Count the response of a node, and make its stream count and respect
+	/// the rate limit of the link
+	pub(crate) async fn shape_response<M: Rpc>(
+		&self,
+		from: Uuid,
+		resp: Resp<M>,
+	) -> Result<Resp<M>, Error> {
+		if from == self.our_node_id {
+			return Ok(resp);
+		}
+		let counters = self.counters(from);
+		let limiter = self.limit(from).map(|l| l.receive.clone());
+
+		let (msg, stream) = resp.into_parts();
+		let size = nonversioned_encoded_len(&msg)?;
+		counters.received.fetch_add(size, Ordering::Relaxed);
+		if let Some(limiter) = &limiter {
+			limiter.take(size).await;
+		}
+
+		let resp = Resp::new(msg);
+		Ok(match stream {
+			Some(stream) => {
+				let counter = move |n| {
+					counters.received.fetch_add(n, Ordering::Relaxed);
+				};
+				resp.with_stream(shape_stream(stream, counter, limiter))
+			}
+			None => resp,
+		})
+	}
+
+	fn counters(&self, node: Uuid) -> Arc<LinkCounters> {
+		if let Some(counters) = self.links.read().unwrap().get(&node) {
+			return counters.clone();
+		}
+		self.links.write().unwrap().entry(node).or_default().clone()
+	}
+
+	/// The first limit that applies to the zones of this node and of
+	/// another node
+	fn limit(&self, node: Uuid) -> Option<&LinkLimit> {
+		if self.limits.is_empty() {
+			return None;
+		}
+		let ring = self.ring.borrow();
+		let our_zone = &ring.layout.node_role(&self.our_node_id)?.zone;
+		let their_zone = &ring.layout.node_role(&node)?.zone;
+		self.limits.iter().find(|l| {
+			(l.zones[0] == *our_zone && l.zones[1] == *their_zone)
+				|| (l.zones[1] == *our_zone && l.zones[0] == *their_zone)
+		})
+	}
+}
+
+/// Count the bytes of a stream as they are read, no faster than the rate
+/// of the limiter if there is one
+fn shape_stream<F>(
+	mut stream: ByteStream,
+	count: F,
+	limiter: Option<Arc<RateLimiter>>,
+) -> ByteStream
+where
+	F: Fn(u64) + Send + Sync + 'static,
+{
+	let limiter = match limiter {
+		None => {
+			return Box::pin(stream.inspect(move |packet| {
+				if let Ok(bytes) = packet {
+					count(bytes.len() as u64);
+				}
+			}))
+		}
+		Some(limiter) => limiter,
+	};
+
+	let (tx, rx) = mpsc::channel(1);
+	tokio::spawn(async move {
+		while let Some(packet) = stream.next().await {
+			if let Ok(bytes) = &packet {
+				count(bytes.len() as u64);
+				limiter.take(bytes.len() as u64).await;
+			}
+			if tx.send(packet).await.is_err() {
+				break;
+			}
+		}
+	});
+	Box::pin(ReceiverStream::new(rx))
+}
+
+impl RateLimiter {
+	fn new(rate: u64) -> Self {
+		Self {
+			rate: rate as f64,
+			state: Mutex::new((rate as f64, Instant::now())),
+		}
+	}
+
+	/// Wait until some bytes can be transferred
+	async fn take(&self, bytes: u64) {
+		let wait = {
+			let mut state = self.state.lock().unwrap();
+			let now = Instant::now();
+			let (tokens, last) = *state;
+			let refill = now.saturating_duration_since(last).as_secs_f64() * self.rate;
+			let tokens = (tokens + refill).min(self.rate) - bytes as f64;
+			*state = (tokens, now);
+			// The tokens are taken right away, so the next transfers
+			// wait for this one to have been let through
+			(tokens < 0.).then(|| Duration::from_secs_f64(-tokens / self.rate))
+		};
+		if let Some(wait) = wait {
+			tokio::time::sleep(wait).await;
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[tokio::test]
+	async fn test_rate_limiter() {
+		let limiter = RateLimiter::new(1_000_000);
+		let start = Instant::now();
+
+		// A burst of one second of traffic goes through immediately
+		limiter.take(1_000_000).await;
+		assert!(start.elapsed() < Duration::from_millis(100));
+
+		// The next bytes wait for the tokens to be refilled
+		limiter.take(200_000).await;
+		assert!(start.elapsed() >= Duration::from_millis(200));
+	}
+
+	#[tokio::test]
+	async fn test_rate_limiter_concurrent() {
+		let limiter = Arc::new(RateLimiter::new(1_000_000));
+		limiter.take(1_000_000).await;
+		let start = Instant::now();
+
+		let limiter2 = limiter.clone();
+		let first = tokio::spawn(async move { limiter2.take(500_000).await });
+		tokio::time::sleep(Duration::from_millis(50)).await;
+
+		// The limiter is not locked while a transfer waits, and the next
+		// transfer waits for the tokens taken by the first one
+		assert!(limiter.state.try_lock().is_ok());
+		limiter.take(500_000).await;
+		first.await.unwrap();
+		assert!(start.elapsed() >= Duration::from_millis(900));
+	}
+}
@@ -0,0 +1,317 @@
+//! Fault injection, to test how a cluster behaves when RPCs and disks fail:
+//! quorums, retries, resync and repair procedures. Faults are only injected
+//! in builds with the `fault-injection` feature, where they are configured
+//! through the admin API. In other builds, `FaultInjector` does nothing.
+use serde::{Deserialize, Serialize};
+
+use garage_util::data::*;
+use garage_util::error::Error;
+
+#[cfg(feature = "fault-injection")]
+use std::sync::{
+	atomic::{AtomicU64, Ordering},
+	Mutex,
+};
+#[cfg(feature = "fault-injection")]
+use std::time::Duration;
+
+#[cfg(feature = "fault-injection")]
+use arc_swap::ArcSwap;
+#[cfg(feature = "fault-injection")]
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+/// Whether this build of Garage can inject faults
+pub const FAULT_INJECTION_ENABLED: bool = cfg!(feature = "fault-injection");
+
+/// Faults to inject. All probabilities are between 0 and 1, and the default
+/// configuration injects no fault.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct FaultConfig {
+	/// Probability that an outgoing RPC is dropped: it fails with a timeout,
+	/// without being sent
+	pub rpc_drop_probability: f64,
+	/// Probability that an outgoing RPC is delayed by `rpc_delay_msec`
+	pub rpc_delay_probability: f64,
+	pub rpc_delay_msec: u64,
+	/// Probability that the response to an outgoing RPC is corrupted:
+	/// it is received but cannot be decoded
+	pub rpc_corrupt_probability: f64,
+	/// Only inject RPC faults on these endpoints (e.g. `garage_block/manager.rs/Rpc`),
+	/// or on all endpoints if empty
+	pub rpc_endpoints: Vec<String>,
+	/// Only inject RPC faults on calls to these nodes (hex-encoded IDs,
+	/// or prefixes of them), or to all nodes if empty
+	pub rpc_nodes: Vec<String>,
+	/// Probability that writing a data block to disk fails
+	pub block_write_fail_probability: f64,
+	/// Probability that a data block read from disk has a flipped bit,
+	/// as if the disk had corrupted it
+	pub block_read_corrupt_probability: f64,
+	/// Seed of the random generator deciding which faults are injected,
+	/// to reproduce a sequence of faults. Random if not set.
+	pub seed: Option<u64>,
+}
+
+impl FaultConfig {
+	fn check(&self) -> Result<(), Error> {
+		let probabilities = [
+			("rpcDropProbability", self.rpc_drop_probability),
+			("rpcDelayProbability", self.rpc_delay_probability),
+			("rpcCorruptProbability", self.rpc_corrupt_probability),
+			(
+				"blockWriteFailProbability",
+				self.block_write_fail_probability,
+			),
+			(
+				"blockReadCorruptProbability",
+				self.block_read_corrupt_probability,
+			),
+		];
+		for (name, p) in probabilities {
+			if !(0.0..=1.0).contains(&p) {
+				return Err(Error::Message(format!(
+					"{} must be between 0 and 1, got {}",
+					name, p
+				)));
+			}
+		}
+		Ok(())
+	}
+
+	#[cfg(feature = "fault-injection")]
+	fn applies_to_rpc(&self, endpoint: &str, to: Uuid) -> bool {
+		let to = hex::encode(to);
+		(self.rpc_endpoints.is_empty() || self.rpc_endpoints.iter().any(|e| e == endpoint))
+			&& (self.rpc_nodes.is_empty() || self.rpc_nodes.iter().any(|n| to.starts_with(n)))
+	}
+}
+
+/// Number of faults injected since the configuration was last set
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FaultStats {
+	pub rpc_dropped: u64,
+	pub rpc_delayed: u64,
+	pub rpc_corrupted: u64,
+	pub block_write_failed: u64,
+	pub block_read_corrupted: u64,
+}
+
+/// Decides which faults to inject, according to a `FaultConfig`
+#[derive(Default)]
+pub struct FaultInjector {
+	#[cfg(feature = "fault-injection")]
+	inner: Mutex<FaultInjectorInner>,
+	#[cfg(feature = "fault-injection")]
+	config: ArcSwap<FaultConfig>,
+	#[cfg(feature = "fault-injection")]
+	stats: [AtomicU64; 5],
+}
+
+#[cfg(feature = "fault-injection")]
+struct FaultInjectorInner {
+	rng: StdRng,
+}
+
+#[cfg(feature = "fault-injection")]
+impl Default for FaultInjectorInner {
+	fn default() -> Self {
+		Self {
+			rng: StdRng::from_entropy(),
+		}
+	}
+}
+
+#[cfg(feature = "fault-injection")]
+const STAT_RPC_DROPPED: usize = 0;
+#[cfg(feature = "fault-injection")]
+const STAT_RPC_DELAYED: usize = 1;
+#[cfg(feature = "fault-injection")]
+const STAT_RPC_CORRUPTED: usize = 2;
+#[cfg(feature = "fault-injection")]
+const STAT_BLOCK_WRITE_FAILED: usize = 3;
+#[cfg(feature = "fault-injection")]
+const STAT_BLOCK_READ_CORRUPTED: usize = 4;
+
+impl FaultInjector {
+	/// Current configuration of injected faults
+	pub fn config(&self) -> FaultConfig {
+		#[cfg(feature = "fault-injection")]
+		return self.config.load().as_ref().clone();
+		#[cfg(not(feature = "fault-injection"))]
+		return FaultConfig::default();
+	}
+
+	/// Set the faults to inject, and reset the statistics
+	pub fn set_config(&self, config: FaultConfig) -> Result<(), Error> {
+		config.check()?;
+
+		#[cfg(not(feature = "fault-injection"))]
+		if config != FaultConfig::default() {
+			return Err(Error::Message(
+				"This build of Garage does not have the fault-injection feature".into(),
+			));
+		}
+
+		#[cfg(feature = "fault-injection")]
+		{
+			let mut inner = self.inner.lock().unwrap();
+			inner.rng = match config.seed {
+				Some(seed) => StdRng::seed_from_u64(seed),
+				None => StdRng::from_entropy(),
+			};
+			for stat in self.stats.iter() {
+				stat.store(0, Ordering::Relaxed);
+			}
+			if config != FaultConfig::default() {
+				warn!("Fault injection enabled: {:?}", config);
+			}
+			self.config.store(std::sync::Arc::new(config));
+		}
+
+		Ok(())
+	}
+
+	/// Number of faults injected since the configuration was last set
+	pub fn stats(&self) -> FaultStats {
+		#[cfg(feature = "fault-injection")]
+		{
+			let get = |i: usize| self.stats[i].load(Ordering::Relaxed);
+			return FaultStats {
+				rpc_dropped: get(STAT_RPC_DROPPED),
+				rpc_delayed: get(STAT_RPC_DELAYED),
+				rpc_corrupted: get(STAT_RPC_CORRUPTED),
+				block_write_failed: get(STAT_BLOCK_WRITE_FAILED),
+				block_read_corrupted: get(STAT_BLOCK_READ_CORRUPTED),
+			};
+		}
+		#[cfg(not(feature = "fault-injection"))]
+		FaultStats::default()
+	}
+
+	#[cfg(feature = "fault-injection")]
+	fn draw(&self, probability: f64, stat: usize) -> bool {
+		if probability <= 0.0 {
+			return false;
+		}
+		let hit = self.inner.lock().unwrap().rng.gen_bool(probability);
+		if hit {
+			self.stats[stat].fetch_add(1, Ordering::Relaxed);
+		}
+		hit
+	}
+
+	/// Called before sending a RPC: returns an error if the RPC is to be
+	/// dropped, after waiting if it is to be delayed
+	pub async fn before_rpc(&self, endpoint: &str, to: Uuid) -> Result<(), Error> {
+		#[cfg(feature = "fault-injection")]
+		{
+			let config = self.config.load();
+			if !config.applies_to_rpc(endpoint, to) {
+				return Ok(());
+			}
+			if self.draw(config.rpc_delay_probability, STAT_RPC_DELAYED) {
+				tokio::time::sleep(Duration::from_millis(config.rpc_delay_msec)).await;
+			}
+			if self.draw(config.rpc_drop_probability, STAT_RPC_DROPPED) {
+				debug!("Fault injection: dropping RPC {} to {:?}", endpoint, to);
+				return Err(Error::Timeout);
+			}
+		}
+		#[cfg(not(feature = "fault-injection"))]
+		let _ = (endpoint, to);
+		Ok(())
+	}
+
+	/// Called when the response to a RPC has been received: returns an
+	/// error if the response is to be corrupted
+	pub fn after_rpc(&self, endpoint: &str, to: Uuid) -> Result<(), Error> {
+		#[cfg(feature = "fault-injection")]
+		{
+			let config = self.config.load();
+			if config.applies_to_rpc(endpoint, to)
+				&& self.draw(config.rpc_corrupt_probability, STAT_RPC_CORRUPTED)
+			{
+				debug!(
+					"Fault injection: corrupting response of RPC {} to {:?}",
+					endpoint, to
+				);
+				return Err(Error::Message(format!(
+					"Unable to decode response of RPC {}: corrupted message (injected fault)",
+					endpoint
+				)));
+			}
+		}
+		#[cfg(not(feature = "fault-injection"))]
+		let _ = (endpoint, to);
+		Ok(())
+	}
+
+	/// Called before writing a data block to disk: returns an error if
+	/// the write is to fail
+	pub fn before_block_write(&self, hash: &Hash) -> Result<(), Error> {
+		#[cfg(feature = "fault-injection")]
+		{
+			let p = self.config.load().block_write_fail_probability;
+			if self.draw(p, STAT_BLOCK_WRITE_FAILED) {
+				debug!("Fault injection: failing write of block {:?}", hash);
+				return Err(Error::Io(std::io::Error::new(
+					std::io::ErrorKind::Other,
+					"write error (injected fault)",
+				)));
+			}
+		}
+		#[cfg(not(feature = "fault-injection"))]
+		let _ = hash;
+		Ok(())
+	}
+
+	/// Called on the data of a block read from disk: flips one of its
+	/// bits if the read is to be corrupted
+	pub fn after_block_read(&self, hash: &Hash, data: &mut [u8]) {
+		#[cfg(feature = "fault-injection")]
+		{
+			let p = self.config.load().block_read_corrupt_probability;
+			if !data.is_empty() && self.draw(p, STAT_BLOCK_READ_CORRUPTED) {
+				debug!("Fault injection: corrupting read of block {:?}", hash);
+				let i = self.inner.lock().unwrap().rng.gen_range(0..data.len() * 8);
+				data[i / 8] ^= 1 << (i % 8);
+			}
+		}
+		#[cfg(not(feature = "fault-injection"))]
+		let _ = (hash, data);
+	}
+}
+
+#[cfg(all(test, feature = "fault-injection"))]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_seeded_faults_are_reproducible() {
+		let config = FaultConfig {
+			block_write_fail_probability: 0.5,
+			seed: Some(42),
+			..Default::default()
+		};
+		let run = || {
+			let injector = FaultInjector::default();
+			injector.set_config(config.clone()).unwrap();
+			(0..100)
+				.map(|_| injector.before_block_write(&Hash::from([0u8; 32])).is_err())
+				.collect::<Vec<_>>()
+		};
+		let faults = run();
+		assert_eq!(faults, run());
+		assert!(faults.iter().any(|f| *f) && faults.iter().any(|f| !*f));
+
+		let injector = FaultInjector::default();
+		assert!(injector
+			.set_config(FaultConfig {
+				rpc_drop_probability: 2.0,
+				..Default::default()
+			})
+			.is_err());
+	}
+}
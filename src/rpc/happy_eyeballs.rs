@@ -0,0 +1,115 @@
+//! Connection to nodes that are reachable at several addresses, such as
+//! dual-stack nodes that publish both an IPv6 and an IPv4 address. As in the
+//! Happy Eyeballs algorithm (RFC 8305), connection attempts are started one
+//! after the other with a short delay, IPv6 first, and the first one that
+//! succeeds is kept, so that a broken address family does not delay
+//! connections by a full timeout.
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::future::BoxFuture;
+use futures::stream::{FuturesUnordered, StreamExt};
+use tokio::select;
+
+use netapp::{NetApp, NodeID};
+
+/// Delay after which a connection attempt to the next address is started,
+/// if the previous attempts have not completed yet
+const CONNECTION_ATTEMPT_DELAY: Duration = Duration::from_millis(250);
+
+/// Order the addresses of a node for connection attempts: duplicates are
+/// removed, and IPv6 and IPv4 addresses are interleaved, IPv6 first
+pub fn sort_addrs(addrs: &[SocketAddr]) -> Vec<SocketAddr> {
+	let mut v6 = vec![];
+	let mut v4 = vec![];
+	for addr in addrs {
+		let family = if addr.is_ipv6() { &mut v6 } else { &mut v4 };
+		if !family.contains(addr) {
+			family.push(*addr);
+		}
+	}
+
+	let mut sorted = Vec::with_capacity(v6.len() + v4.len());
+	let (mut v6, mut v4) = (v6.into_iter(), v4.into_iter());
+	loop {
+		match (v6.next(), v4.next()) {
+			(None, None) => return sorted,
+			(a, b) => sorted.extend(a.into_iter().chain(b)),
+		}
+	}
+}
+
+/// Connect to a node at the first of its addresses that accepts a connection,
+/// returning that address, or the errors of all the attempts
+pub async fn connect(
+	netapp: Arc<NetApp>,
+	id: NodeID,
+	addrs: &[SocketAddr],
+) -> Result<SocketAddr, Vec<(SocketAddr, netapp::error::Error)>> {
+	let mut pending = sort_addrs(addrs).into_iter().peekable();
+	let mut attempts: FuturesUnordered<
+		BoxFuture<'static, (SocketAddr, Result<(), netapp::error::Error>)>,
+	> = FuturesUnordered::new();
+	let mut errors = vec![];
+
+	let mut next = pending.next();
+	loop {
+		if let Some(addr) = next.take() {
+			let netapp = netapp.clone();
+			attempts.push(Box::pin(async move {
+				(addr, netapp.try_connect(addr, id).await)
+			}));
+		}
+
+		select! {
+			res = attempts.next() => match res {
+				Some((addr, Ok(()))) => return Ok(addr),
+				Some((addr, Err(e))) => {
+					errors.push((addr, e));
+					// Try the next address right away
+					next = pending.next();
+				}
+				None => return Err(errors),
+			},
+			_ = tokio::time::sleep(CONNECTION_ATTEMPT_DELAY), if pending.peek().is_some() => {
+				next = pending.next();
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_sort_addrs() {
+		let addrs = [
+			"192.0.2.1:3901",
+			"192.0.2.2:3901",
+			"[2001:db8::1]:3901",
+			"192.0.2.1:3901",
+			"[2001:db8::2]:3901",
+			"192.0.2.3:3901",
+		]
+		.iter()
+		.map(|a| a.parse().unwrap())
+		.collect::<Vec<SocketAddr>>();
+
+		let sorted = sort_addrs(&addrs)
+			.iter()
+			.map(|a| a.to_string())
+			.collect::<Vec<_>>();
+		assert_eq!(
+			sorted,
+			vec![
+				"[2001:db8::1]:3901",
+				"192.0.2.1:3901",
+				"[2001:db8::2]:3901",
+				"192.0.2.2:3901",
+				"192.0.2.3:3901",
+			]
+		);
+	}
+}
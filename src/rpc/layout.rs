@@ -435,6 +435,30 @@ To know the correct value of the new layout version, invoke `garage layout show`
 		))
 	}
 
+	/// Returns the nodes that store each partition in the ring
+	pub fn partition_nodes(&self) -> Vec<Vec<Uuid>> {
+		self.ring_assignment_data
+			.chunks(self.replication_factor)
+			.map(|c| c.iter().map(|i| self.node_id_vec[*i as usize]).collect())
+			.collect()
+	}
+
+	/// Returns, for each node, the number of partitions that it does not
+	/// store in this layout and that it has to receive from other nodes
+	/// when the layout `new` is applied
+	pub fn incoming_partitions(&self, new: &ClusterLayout) -> HashMap<Uuid, usize> {
+		let old = self.partition_nodes();
+		let mut ret = HashMap::new();
+		for (p, nodes) in new.partition_nodes().into_iter().enumerate() {
+			for node in nodes {
+				if !old.get(p).map(|o| o.contains(&node)).unwrap_or(false) {
+					*ret.entry(node).or_default() += 1;
+				}
+			}
+		}
+		ret
+	}
+
 	/// Returns the sum of capacities of non gateway nodes in the cluster
 	fn get_total_capacity(&self) -> Result<u64, Error> {
 		let mut total_capacity = 0;
@@ -1290,4 +1314,49 @@ mod tests {
 		assert_eq!(cl.check(), Ok(()));
 		assert!(matches!(check_against_naive(&cl), Ok(true)));
 	}
+
+	#[test]
+	fn test_incoming_partitions() {
+		let node_zone_vec = vec!["A", "B", "C"]
+			.into_iter()
+			.map(|x| x.to_string())
+			.collect();
+		let mut cl = ClusterLayout::new(3);
+		update_layout(
+			&mut cl,
+			&vec![1, 2, 3],
+			&vec![1000, 1000, 1000],
+			&node_zone_vec,
+			3,
+		);
+		let (cl, _) = cl.apply_staged_changes(Some(1)).unwrap();
+
+		// Starting from an empty layout, each node receives all the partitions
+		let incoming = ClusterLayout::new(3).incoming_partitions(&cl);
+		for uuid in cl.node_ids().iter() {
+			assert_eq!(incoming[uuid], NB_PARTITIONS);
+		}
+		assert!(cl.incoming_partitions(&cl).is_empty());
+
+		// A new node in a new zone takes some of the partitions of the other
+		// nodes, and is the only one to receive data
+		let new_node = FixedBytes32::try_from(&[3u8; 32]).unwrap();
+		let mut cl2 = cl.clone();
+		let update = cl2.staging_roles.update_mutator(
+			new_node,
+			NodeRoleV(Some(NodeRole {
+				zone: "D".into(),
+				capacity: Some(1000),
+				tags: vec![],
+			})),
+		);
+		cl2.staging_roles.merge(&update);
+		cl2.staging_hash = cl2.calculate_staging_hash();
+		let (cl2, _) = cl2.apply_staged_changes(Some(2)).unwrap();
+
+		let incoming = cl.incoming_partitions(&cl2);
+		assert_eq!(incoming.keys().collect::<Vec<_>>(), vec![&new_node]);
+		assert_eq!(incoming[&new_node], cl2.get_node_usage(&new_node).unwrap());
+		assert!(incoming[&new_node] > 0);
+	}
 }
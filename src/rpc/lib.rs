@@ -11,11 +11,17 @@ mod consul;
 #[cfg(feature = "kubernetes-discovery")]
 mod kubernetes;
 
+pub mod bandwidth;
+pub mod fault_injection;
 pub mod graph_algo;
+pub mod happy_eyeballs;
 pub mod layout;
+pub mod rebalance;
+pub mod relay;
 pub mod replication_mode;
 pub mod ring;
 pub mod system;
+pub mod webhook;
 
 pub mod rpc_helper;
 
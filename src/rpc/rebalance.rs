@@ -0,0 +1,333 @@
+//! Monitoring of the data disk usage of storage nodes, which suggests
+//! changes to the capacity of nodes when their usage diverges
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+
+use garage_util::background::*;
+use garage_util::config::RebalanceConfig;
+use garage_util::crdt::Crdt;
+use garage_util::data::*;
+use garage_util::error::*;
+use garage_util::persister::Persister;
+use garage_util::time::*;
+
+use crate::layout::*;
+use crate::system::System;
+use crate::webhook::post_json;
+
+const REBALANCE_CHECK_INTERVAL: Duration = Duration::from_secs(600);
+
+/// Capacities are rounded to this value in suggested layout changes
+const CAPACITY_ROUNDING: u64 = 1_000_000;
+
+/// Suggested changes to the capacity of storage nodes, computed from
+/// the usage of their data disk
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RebalanceSuggestion {
+	/// Threshold above which capacity changes are suggested
+	pub threshold: f64,
+	/// Average fraction of the data disk used on storage nodes
+	pub average_usage: f64,
+	/// Largest difference between the data disk usage of a node and the average
+	pub imbalance: f64,
+	/// Suggested changes, empty if the imbalance is below the threshold
+	pub changes: Vec<CapacityChange>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CapacityChange {
+	pub node: Uuid,
+	/// Fraction of the data disk used on the node
+	pub usage: f64,
+	pub capacity: u64,
+	pub new_capacity: u64,
+}
+
+/// Compute suggested capacity changes from the data disk usage of storage nodes,
+/// given as `(avail, total)` for each node.
+/// Nodes whose data disk is fuller than the average have their capacity reduced,
+/// and nodes whose data disk is emptier have it increased, in proportion of
+/// their free space. Returns None if the usage of fewer than two storage nodes
+/// is known.
+pub fn suggest_rebalance(
+	layout: &ClusterLayout,
+	disk_usage: &HashMap<Uuid, (u64, u64)>,
+	config: &RebalanceConfig,
+) -> Option<RebalanceSuggestion> {
+	let nodes = layout
+		.roles
+		.items()
+		.iter()
+		.filter_map(|(id, _, role)| {
+			let capacity = role.0.as_ref()?.capacity?;
+			match disk_usage.get(id) {
+				Some((avail, total)) if *total > 0 => {
+					Some((*id, capacity, 1. - *avail as f64 / *total as f64))
+				}
+				_ => None,
+			}
+		})
+		.collect::<Vec<_>>();
+	if nodes.len() < 2 {
+		return None;
+	}
+
+	let average_usage = nodes.iter().map(|(_, _, u)| u).sum::<f64>() / nodes.len() as f64;
+	let imbalance = nodes
+		.iter()
+		.map(|(_, _, u)| (u - average_usage).abs())
+		.fold(0., f64::max);
+
+	let mut changes = vec![];
+	if imbalance >= config.threshold && average_usage < 1. {
+		for (node, capacity, usage) in nodes {
+			let ratio = ((1. - usage) / (1. - average_usage)).clamp(
+				1. - config.max_capacity_change,
+				1. + config.max_capacity_change,
+			);
+			let new_capacity = (capacity as f64 * ratio / CAPACITY_ROUNDING as f64).round() as u64
+				* CAPACITY_ROUNDING;
+			let new_capacity = std::cmp::max(new_capacity, CAPACITY_ROUNDING);
+			if new_capacity != capacity {
+				changes.push(CapacityChange {
+					node,
+					usage,
+					capacity,
+					new_capacity,
+				});
+			}
+		}
+	}
+
+	Some(RebalanceSuggestion {
+		threshold: config.threshold,
+		average_usage,
+		imbalance,
+		changes,
+	})
+}
+
+impl RebalanceSuggestion {
+	/// Stage the suggested capacity changes in a cluster layout
+	pub fn stage(&self, layout: &mut ClusterLayout) {
+		let mut roles = layout.roles.clone();
+		roles.merge(&layout.staging_roles);
+		for change in self.changes.iter() {
+			if let Some(NodeRoleV(Some(role))) = roles.get(&change.node) {
+				let new_role = NodeRole {
+					capacity: Some(change.new_capacity),
+					..role.clone()
+				};
+				layout
+					.staging_roles
+					.merge(&roles.update_mutator(change.node, NodeRoleV(Some(new_role))));
+			}
+		}
+	}
+}
+
+mod v090 {
+	use serde::{Deserialize, Serialize};
+
+	#[derive(Serialize, Deserialize, Default, Clone)]
+	pub struct RebalanceWorkerPersisted {
+		pub last_auto_apply: u64,
+	}
+
+	impl garage_util::migrate::InitialFormat for RebalanceWorkerPersisted {
+		const VERSION_MARKER: &'static [u8] = b"G09rbwp";
+	}
+}
+
+use v090::*;
+
+/// Worker that checks the balance of data between storage nodes on a regular
+/// basis. It runs on all nodes but only acts (calls the webhook and applies
+/// layout changes) on the storage node with the smallest ID among those that
+/// are up, so that a single notification is sent for each new suggestion.
+pub struct RebalanceWorker {
+	system: Arc<System>,
+	persister: Persister<RebalanceWorkerPersisted>,
+	last_notified: Option<Vec<CapacityChange>>,
+	last_suggestion: Option<RebalanceSuggestion>,
+}
+
+impl RebalanceWorker {
+	pub fn new(system: Arc<System>) -> Self {
+		let persister = Persister::new(&system.metadata_dir, "rebalance_worker_state");
+		Self {
+			system,
+			persister,
+			last_notified: None,
+			last_suggestion: None,
+		}
+	}
+
+	async fn check(&mut self) -> Result<(), Error> {
+		let suggestion = self.system.rebalance_suggestion();
+		self.last_suggestion = suggestion.clone();
+
+		let suggestion = match suggestion {
+			Some(s) if !s.changes.is_empty() => s,
+			_ => {
+				self.last_notified = None;
+				return Ok(());
+			}
+		};
+		if !self.system.is_leader() {
+			return Ok(());
+		}
+		let config = self.system.rebalance_config.load_full();
+
+		if self.last_notified.as_ref() != Some(&suggestion.changes) {
+			info!(
+				"Data disk usage is unbalanced between storage nodes (imbalance {:.1}%), suggesting capacity changes for {} nodes",
+				suggestion.imbalance * 100.,
+				suggestion.changes.len()
+			);
+			if let Some(url) = &config.webhook_url {
+				if let Err(e) = call_webhook(url, self.system.id, &suggestion).await {
+					warn!("Unable to send rebalance suggestion to webhook: {}", e);
+				}
+			}
+			self.last_notified = Some(suggestion.changes.clone());
+		}
+
+		if config.auto_apply {
+			let mut persisted = self.persister.load().unwrap_or_default();
+			let now = now_msec();
+			if now < persisted.last_auto_apply + config.auto_apply_interval_secs * 1000 {
+				return Ok(());
+			}
+
+			let mut layout = self.system.get_cluster_layout();
+			if !layout.staging_roles.items().is_empty() {
+				info!("Not applying rebalance suggestion automatically, as other layout changes are staged");
+				return Ok(());
+			}
+			suggestion.stage(&mut layout);
+			let version = layout.version + 1;
+			let (layout, _) = layout.apply_staged_changes(Some(version))?;
+			self.system.update_cluster_layout(&layout).await?;
+			info!(
+				"Applied rebalance suggestion automatically in layout version {}",
+				version
+			);
+
+			persisted.last_auto_apply = now;
+			self.persister.save(&persisted)?;
+		}
+
+		Ok(())
+	}
+}
+
+#[async_trait]
+impl Worker for RebalanceWorker {
+	fn name(&self) -> String {
+		"Rebalance monitor".to_string()
+	}
+
+	fn status(&self) -> WorkerStatus {
+		let freeform = match &self.last_suggestion {
+			None => vec!["Data disk usage of storage nodes is unknown".to_string()],
+			Some(s) => vec![
+				format!("Average data disk usage: {:.1}%", s.average_usage * 100.),
+				format!("Imbalance: {:.1}%", s.imbalance * 100.),
+				format!("Suggested capacity changes: {}", s.changes.len()),
+			],
+		};
+		WorkerStatus {
+			freeform,
+			..Default::default()
+		}
+	}
+
+	async fn work(&mut self, _must_exit: &mut watch::Receiver<bool>) -> Result<WorkerState, Error> {
+		self.check().await?;
+		Ok(WorkerState::Idle)
+	}
+
+	async fn wait_for_work(&mut self) -> WorkerState {
+		tokio::time::sleep(REBALANCE_CHECK_INTERVAL).await;
+		WorkerState::Busy
+	}
+}
+
+async fn call_webhook(
+	url: &str,
+	node: Uuid,
+	suggestion: &RebalanceSuggestion,
+) -> Result<(), Error> {
+	let changes = suggestion
+		.changes
+		.iter()
+		.map(|c| {
+			serde_json::json!({
+				"id": hex::encode(c.node),
+				"usage": c.usage,
+				"capacity": c.capacity,
+				"newCapacity": c.new_capacity,
+			})
+		})
+		.collect::<Vec<_>>();
+	let body = serde_json::json!({
+		"node": hex::encode(node),
+		"threshold": suggestion.threshold,
+		"averageUsage": suggestion.average_usage,
+		"imbalance": suggestion.imbalance,
+		"changes": changes,
+	});
+
+	post_json(url, &body).await
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_suggest_rebalance() {
+		let mut layout = ClusterLayout::new(3);
+		let ids = (0..3u8).map(|i| Uuid::from([i; 32])).collect::<Vec<_>>();
+		for id in ids.iter() {
+			let role = NodeRole {
+				zone: "dc1".into(),
+				capacity: Some(100_000_000),
+				tags: vec![],
+			};
+			layout
+				.roles
+				.merge(&layout.roles.update_mutator(*id, NodeRoleV(Some(role))));
+		}
+
+		let config = RebalanceConfig::default();
+		let mut usage = HashMap::new();
+		usage.insert(ids[0], (50, 100));
+		assert_eq!(suggest_rebalance(&layout, &usage, &config), None);
+
+		usage.insert(ids[1], (55, 100));
+		usage.insert(ids[2], (45, 100));
+		let s = suggest_rebalance(&layout, &usage, &config).unwrap();
+		assert!(s.changes.is_empty());
+
+		usage.insert(ids[1], (80, 100));
+		usage.insert(ids[2], (20, 100));
+		let s = suggest_rebalance(&layout, &usage, &config).unwrap();
+		assert!((s.imbalance - 0.3).abs() < 1e-9);
+		let new_capacity = |id: &Uuid| {
+			s.changes
+				.iter()
+				.find(|c| c.node == *id)
+				.map(|c| c.new_capacity)
+		};
+		assert_eq!(new_capacity(&ids[0]), None);
+		assert_eq!(new_capacity(&ids[1]), Some(120_000_000));
+		assert_eq!(new_capacity(&ids[2]), Some(80_000_000));
+	}
+}
@@ -0,0 +1,598 @@
+//! Relaying of RPCs for nodes that cannot accept inbound connections.
+//!
+//! A node running in outbound-only mode connects to all the other nodes but
+//! does not advertise any address. As the RPC layer only sends requests on
+//! connections a node has opened itself, other nodes cannot send requests
+//! to it directly: instead, the outbound-only node continuously polls the
+//! nodes it is connected to for the requests that are addressed to it, and
+//! sends back the responses. Requests between two nodes that cannot connect
+//! to each other are forwarded through a node connected to both.
+//!
+//! Relayed requests and responses are buffered in full, including the
+//! attached streams, which is fine for the size of data blocks.
+//!
+//! Relayed requests carry the trace context of their caller in W3C Trace
+//! Context headers, so that the spans of the node that handles them are part
+//! of the same trace, as for requests sent directly.
+use std::collections::{HashMap, HashSet};
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+use futures::stream::StreamExt;
+use serde::{Deserialize, Serialize};
+use tokio::select;
+use tokio::sync::{oneshot, watch, Notify};
+
+use opentelemetry::propagation::TextMapPropagator;
+use opentelemetry::sdk::propagation::TraceContextPropagator;
+use opentelemetry::{
+	trace::{FutureExt as OtelFutureExt, SpanKind, TraceContextExt, Tracer},
+	Context,
+};
+
+use netapp::endpoint::{Endpoint, EndpointHandler, StreamingEndpointHandler};
+use netapp::message::{Message as Rpc, Req, RequestPriority, Resp, PRIO_HIGH};
+use netapp::peering::fullmesh::FullMeshPeeringStrategy;
+use netapp::stream::ByteStream;
+use netapp::{NetApp, NodeID};
+
+use garage_util::data::*;
+use garage_util::encode::{nonversioned_decode, nonversioned_encode};
+use garage_util::error::{Error, OkOrMessage};
+
+const RELAY_RPC_PATH: &str = "garage_rpc/relay.rs/Rpc";
+
+/// Time after which a poll request returns even if no request is waiting
+const POLL_TIMEOUT: Duration = Duration::from_secs(20);
+/// Time after which a node that no longer polls is considered gone
+const MAILBOX_EXPIRY: Duration = Duration::from_secs(30);
+/// Interval at which an outbound-only node checks for nodes to poll
+const POLL_LOOP_INTERVAL: Duration = Duration::from_secs(2);
+
+/// RPC messages used to relay requests between nodes
+#[derive(Debug, Serialize, Deserialize)]
+pub enum RelayRpc {
+	Ok,
+	/// Ask a node to pass a request on to a node it can reach
+	Forward {
+		to: Uuid,
+		req: RelayedReq,
+	},
+	/// Handle a request on behalf of another node
+	Deliver(RelayedReq),
+	/// Response to a forwarded or delivered request
+	Response(RelayedResp),
+	/// Wait for requests addressed to the sending node
+	Poll,
+	/// Requests addressed to the polling node, with their ids
+	Requests(Vec<(u64, RelayedReq)>),
+	/// Response of the polling node to one of the requests
+	Reply {
+		id: u64,
+		resp: Result<RelayedResp, String>,
+	},
+}
+
+impl Rpc for RelayRpc {
+	type Response = Result<RelayRpc, Error>;
+}
+
+/// A request to an endpoint, with its message and stream serialized
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelayedReq {
+	/// Node that made the request, which handlers see as its sender
+	from: Uuid,
+	path: String,
+	prio: RequestPriority,
+	#[serde(with = "serde_bytes")]
+	body: Vec<u8>,
+	#[serde(with = "serde_bytes")]
+	stream: Option<Vec<u8>>,
+	/// Trace context of the caller
+	#[serde(default)]
+	headers: HashMap<String, String>,
+}
+
+/// A response of an endpoint, with its message and stream serialized
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelayedResp {
+	#[serde(with = "serde_bytes")]
+	body: Vec<u8>,
+	#[serde(with = "serde_bytes")]
+	stream: Option<Vec<u8>>,
+}
+
+/// Requests waiting to be picked up by a polling node
+struct Mailbox {
+	queue: Vec<(u64, RelayedReq)>,
+	notify: Arc<Notify>,
+	last_poll: Instant,
+}
+
+/// An endpoint handler to which relayed requests can be delivered
+trait RelayTarget: Send + Sync {
+	fn deliver(&self, req: RelayedReq) -> BoxFuture<'_, Result<RelayedResp, Error>>;
+}
+
+struct RelayHandler<M, H> {
+	handler: Arc<H>,
+	_phantom: PhantomData<M>,
+}
+
+pub struct Relay {
+	id: NodeID,
+	outbound_only: bool,
+	fullmesh: Arc<FullMeshPeeringStrategy>,
+	endpoint: Arc<Endpoint<RelayRpc, Relay>>,
+
+	targets: RwLock<HashMap<String, Arc<dyn RelayTarget>>>,
+	/// Nodes that announced that they run in outbound-only mode
+	outbound_only_nodes: RwLock<HashSet<NodeID>>,
+
+	mailboxes: Mutex<HashMap<NodeID, Mailbox>>,
+	pending: Mutex<HashMap<u64, oneshot::Sender<Result<RelayedResp, String>>>>,
+	next_id: AtomicU64,
+}
+
+impl Relay {
+	pub(crate) fn new(
+		netapp: &Arc<NetApp>,
+		fullmesh: Arc<FullMeshPeeringStrategy>,
+		outbound_only: bool,
+	) -> Arc<Self> {
+		let relay = Arc::new(Self {
+			id: netapp.id,
+			outbound_only,
+			fullmesh,
+			endpoint: netapp.endpoint(RELAY_RPC_PATH.into()),
+			targets: RwLock::new(HashMap::new()),
+			outbound_only_nodes: RwLock::new(HashSet::new()),
+			mailboxes: Mutex::new(HashMap::new()),
+			pending: Mutex::new(HashMap::new()),
+			next_id: AtomicU64::new(0),
+		});
+		relay.endpoint.set_handler(relay.clone());
+		relay
+	}
+
+	/// Set the handler of an endpoint of this node, to which relayed
+	/// requests are also delivered. Relayed requests are passed to the
+	/// handler directly, so that it sees the node that made them as their
+	/// sender and not this node.
+	pub fn set_handler<M, H>(&self, endpoint: &Endpoint<M, H>, handler: Arc<H>)
+	where
+		M: Rpc,
+		H: StreamingEndpointHandler<M> + 'static,
+	{
+		endpoint.set_handler(handler.clone());
+		let target = RelayHandler {
+			handler,
+			_phantom: PhantomData::<M>,
+		};
+		self.targets
+			.write()
+			.unwrap()
+			.insert(endpoint.path().to_string(), Arc::new(target));
+	}
+
+	/// Record whether a node runs in outbound-only mode, as it announced
+	pub(crate) fn set_outbound_only(&self, node: NodeID, outbound_only: bool) {
+		let mut nodes = self.outbound_only_nodes.write().unwrap();
+		if outbound_only {
+			nodes.insert(node);
+		} else {
+			nodes.remove(&node);
+		}
+	}
+
+	pub fn is_outbound_only(&self, node: &NodeID) -> bool {
+		self.outbound_only_nodes.read().unwrap().contains(node)
+	}
+
+	/// Whether a node currently polls this node for its requests
+	pub fn is_polled_by(&self, node: &NodeID) -> bool {
+		self.mailboxes
+			.lock()
+			.unwrap()
+			.get(node)
+			.map(|m| m.last_poll.elapsed() < MAILBOX_EXPIRY)
+			.unwrap_or(false)
+	}
+
+	/// Whether requests to a node have to be relayed, because it runs in
+	/// outbound-only mode and we have no connection to it
+	pub(crate) fn must_relay(&self, node: &NodeID) -> bool {
+		*node != self.id
+			&& self.is_outbound_only(node)
+			&& !self
+				.fullmesh
+				.get_peer_list()
+				.iter()
+				.any(|p| p.id == *node && p.is_up())
+	}
+
+	/// Send a request to a node that cannot be reached directly
+	pub(crate) async fn call<M: Rpc>(
+		&self,
+		path: &str,
+		to: NodeID,
+		req: Req<M>,
+		prio: RequestPriority,
+	) -> Result<Resp<M>, Error> {
+		let req = RelayedReq::new(self.id, path, prio, req).await?;
+		let resp = if self.is_polled_by(&to) {
+			self.enqueue(to, req).await?
+		} else {
+			self.forward(to, req).await?
+		};
+		resp.decode()
+	}
+
+	/// Send a request through the first connected node that can reach its
+	/// destination
+	async fn forward(&self, to: NodeID, req: RelayedReq) -> Result<RelayedResp, Error> {
+		let relays = self
+			.fullmesh
+			.get_peer_list()
+			.iter()
+			.filter(|p| p.is_up() && p.id != self.id && !self.is_outbound_only(&p.id))
+			.map(|p| p.id)
+			.collect::<Vec<_>>();
+
+		let mut errors = vec![];
+		for relay in relays {
+			let msg = RelayRpc::Forward {
+				to: to.into(),
+				req: req.clone(),
+			};
+			match self.endpoint.call(&relay, msg, req.prio).await {
+				Ok(Ok(RelayRpc::Response(resp))) => return Ok(resp),
+				Ok(Ok(m)) => errors.push(Error::unexpected_rpc_message(m)),
+				Ok(Err(e)) => errors.push(e),
+				Err(e) => errors.push(e.into()),
+			}
+		}
+		Err(Error::Message(format!(
+			"No node could relay request to {:?}: {:?}",
+			to, errors
+		)))
+	}
+
+	/// Queue a request for a node that polls this node, and wait for its
+	/// response
+	async fn enqueue(&self, to: NodeID, req: RelayedReq) -> Result<RelayedResp, Error> {
+		let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+		let (tx, rx) = oneshot::channel();
+		self.pending.lock().unwrap().insert(id, tx);
+		let _pending = PendingGuard(self, id);
+
+		{
+			let mut mailboxes = self.mailboxes.lock().unwrap();
+			let mailbox = mailboxes
+				.get_mut(&to)
+				.filter(|m| m.last_poll.elapsed() < MAILBOX_EXPIRY)
+				.ok_or_message("Node no longer polls for relayed requests")?;
+			mailbox.queue.push((id, req));
+			mailbox.notify.notify_one();
+		}
+
+		rx.await
+			.ok_or_message("Relayed request was dropped")?
+			.map_err(Error::Message)
+	}
+
+	/// Deliver a relayed request to the local endpoint it is addressed to
+	pub(crate) async fn deliver(&self, req: RelayedReq) -> Result<RelayedResp, Error> {
+		let target = self
+			.targets
+			.read()
+			.unwrap()
+			.get(&req.path)
+			.cloned()
+			.ok_or_else(|| {
+				Error::Message(format!("No endpoint for relayed request: {}", req.path))
+			})?;
+
+		let tracer = opentelemetry::global::tracer("garage");
+		let span = tracer
+			.span_builder(format!(">> Relayed RPC {}", req.path))
+			.with_kind(SpanKind::Server)
+			.start_with_context(&tracer, &req.trace_context());
+		target
+			.deliver(req)
+			.with_context(Context::current_with_span(span))
+			.await
+	}
+
+	async fn handle_forward(
+		&self,
+		from: NodeID,
+		to: NodeID,
+		mut req: RelayedReq,
+	) -> Result<RelayedResp, Error> {
+		// The node to which the request is delivered trusts its sender as
+		// checked here, as it only receives it from this node
+		if Uuid::from(from) != req.from {
+			return Err(Error::Message(format!(
+				"Node {:?} asked to relay a request of another node {:?}",
+				from, req.from
+			)));
+		}
+		// Make the delivery a child of the span of this node, which is itself
+		// a child of the caller's span
+		req.set_trace_context(&Context::current());
+		if self
+			.fullmesh
+			.get_peer_list()
+			.iter()
+			.any(|p| p.id == to && p.is_up())
+		{
+			let prio = req.prio;
+			match self
+				.endpoint
+				.call(&to, RelayRpc::Deliver(req), prio)
+				.await??
+			{
+				RelayRpc::Response(resp) => Ok(resp),
+				m => Err(Error::unexpected_rpc_message(m)),
+			}
+		} else if self.is_polled_by(&to) {
+			self.enqueue(to, req).await
+		} else {
+			Err(Error::Message(format!("Not connected to {:?}", to)))
+		}
+	}
+
+	async fn handle_poll(&self, from: NodeID) -> Result<RelayRpc, Error> {
+		let notify = {
+			let mut mailboxes = self.mailboxes.lock().unwrap();
+			let mailbox = mailboxes.entry(from).or_insert_with(|| Mailbox {
+				queue: vec![],
+				notify: Arc::new(Notify::new()),
+				last_poll: Instant::now(),
+			});
+			mailbox.last_poll = Instant::now();
+			if !mailbox.queue.is_empty() {
+				return Ok(RelayRpc::Requests(std::mem::take(&mut mailbox.queue)));
+			}
+			mailbox.notify.clone()
+		};
+
+		select! {
+			_ = notify.notified() => (),
+			_ = tokio::time::sleep(POLL_TIMEOUT) => (),
+		}
+
+		let mut mailboxes = self.mailboxes.lock().unwrap();
+		let requests = match mailboxes.get_mut(&from) {
+			Some(mailbox) => {
+				mailbox.last_poll = Instant::now();
+				std::mem::take(&mut mailbox.queue)
+			}
+			None => vec![],
+		};
+		Ok(RelayRpc::Requests(requests))
+	}
+
+	/// If this node runs in outbound-only mode, poll all the nodes it is
+	/// connected to for the requests addressed to it
+	pub(crate) async fn poll_loop(self: Arc<Self>, mut must_exit: watch::Receiver<bool>) {
+		if !self.outbound_only {
+			return;
+		}
+
+		let polling = Arc::new(Mutex::new(HashSet::new()));
+		while !*must_exit.borrow() {
+			for peer in self.fullmesh.get_peer_list().iter() {
+				if peer.id == self.id || !peer.is_up() {
+					continue;
+				}
+				if polling.lock().unwrap().insert(peer.id) {
+					let relay = self.clone();
+					let polling = polling.clone();
+					let must_exit = must_exit.clone();
+					let peer = peer.id;
+					tokio::spawn(async move {
+						relay.poll(peer, must_exit).await;
+						polling.lock().unwrap().remove(&peer);
+					});
+				}
+			}
+
+			select! {
+				_ = tokio::time::sleep(POLL_LOOP_INTERVAL) => {},
+				_ = must_exit.changed() => {},
+			}
+		}
+	}
+
+	async fn poll(self: &Arc<Self>, peer: NodeID, must_exit: watch::Receiver<bool>) {
+		while !*must_exit.borrow() {
+			let requests = match self.endpoint.call(&peer, RelayRpc::Poll, PRIO_HIGH).await {
+				Ok(Ok(RelayRpc::Requests(requests))) => requests,
+				Ok(Ok(m)) => {
+					warn!("Unexpected response to relay poll: {:?}", m);
+					return;
+				}
+				Ok(Err(e)) => {
+					debug!("Relay poll to {:?} failed: {}", peer, e);
+					return;
+				}
+				Err(e) => {
+					debug!("Relay poll to {:?} failed: {}", peer, e);
+					return;
+				}
+			};
+
+			for (id, req) in requests {
+				let relay = self.clone();
+				tokio::spawn(async move {
+					let prio = req.prio;
+					let resp = relay.deliver(req).await.map_err(|e| e.to_string());
+					let reply = RelayRpc::Reply { id, resp };
+					if let Err(e) = relay.endpoint.call(&peer, reply, prio).await {
+						warn!("Could not send relayed response to {:?}: {}", peer, e);
+					}
+				});
+			}
+		}
+	}
+}
+
+#[async_trait]
+impl EndpointHandler<RelayRpc> for Relay {
+	async fn handle(self: &Arc<Self>, msg: &RelayRpc, from: NodeID) -> Result<RelayRpc, Error> {
+		match msg {
+			RelayRpc::Forward { to, req } => self
+				.handle_forward(from, (*to).into(), req.clone())
+				.await
+				.map(RelayRpc::Response),
+			RelayRpc::Deliver(req) => self.deliver(req.clone()).await.map(RelayRpc::Response),
+			RelayRpc::Poll => self.handle_poll(from).await,
+			RelayRpc::Reply { id, resp } => {
+				if let Some(tx) = self.pending.lock().unwrap().remove(id) {
+					let _ = tx.send(resp.clone());
+				}
+				Ok(RelayRpc::Ok)
+			}
+			m => Err(Error::unexpected_rpc_message(m)),
+		}
+	}
+}
+
+impl<M, H> RelayTarget for RelayHandler<M, H>
+where
+	M: Rpc,
+	H: StreamingEndpointHandler<M> + 'static,
+{
+	fn deliver(&self, req: RelayedReq) -> BoxFuture<'_, Result<RelayedResp, Error>> {
+		Box::pin(async move {
+			let mut msg = Req::new(nonversioned_decode::<M>(&req.body)?)?;
+			if let Some(stream) = req.stream {
+				msg = msg.with_stream_from_buffer(stream.into());
+			}
+			let resp = self.handler.handle(msg, req.from.into()).await;
+			RelayedResp::new(resp).await
+		})
+	}
+}
+
+impl RelayedReq {
+	pub(crate) async fn new<M: Rpc>(
+		from: NodeID,
+		path: &str,
+		prio: RequestPriority,
+		mut req: Req<M>,
+	) -> Result<Self, Error> {
+		let stream = match req.take_stream() {
+			Some(stream) => Some(read_stream_to_end(stream).await?),
+			None => None,
+		};
+		let mut req = Self {
+			from: from.into(),
+			path: path.to_string(),
+			prio,
+			body: nonversioned_encode(req.msg())?,
+			stream,
+			headers: HashMap::new(),
+		};
+		req.set_trace_context(&Context::current());
+		Ok(req)
+	}
+
+	fn set_trace_context(&mut self, cx: &Context) {
+		self.headers.clear();
+		TraceContextPropagator::new().inject_context(cx, &mut self.headers);
+	}
+
+	/// The trace context of the caller, or an empty context for requests of
+	/// nodes that do not send it
+	fn trace_context(&self) -> Context {
+		TraceContextPropagator::new().extract(&self.headers)
+	}
+}
+
+impl RelayedResp {
+	async fn new<M: Rpc>(resp: Resp<M>) -> Result<Self, Error> {
+		let (msg, stream) = resp.into_parts();
+		let stream = match stream {
+			Some(stream) => Some(read_stream_to_end(stream).await?),
+			None => None,
+		};
+		Ok(Self {
+			body: nonversioned_encode(&msg)?,
+			stream,
+		})
+	}
+
+	fn decode<M: Rpc>(self) -> Result<Resp<M>, Error> {
+		let resp = Resp::new(nonversioned_decode::<M::Response>(&self.body)?);
+		Ok(match self.stream {
+			Some(stream) => resp.with_stream_from_buffer(stream.into()),
+			None => resp,
+		})
+	}
+}
+
+/// Removes a request from the pending requests when its caller stops waiting
+struct PendingGuard<'a>(&'a Relay, u64);
+
+impl<'a> Drop for PendingGuard<'a> {
+	fn drop(&mut self) {
+		self.0.pending.lock().unwrap().remove(&self.1);
+	}
+}
+
+async fn read_stream_to_end(mut stream: ByteStream) -> Result<Vec<u8>, Error> {
+	let mut buf = vec![];
+	while let Some(part) = stream.next().await {
+		buf.extend_from_slice(&part.ok_or_message("error in stream")?);
+	}
+	Ok(buf)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use opentelemetry::trace::{SpanContext, SpanId, TraceFlags, TraceId, TraceState};
+
+	#[test]
+	fn test_relayed_trace_context() {
+		let span_context = SpanContext::new(
+			TraceId::from_bytes(0x0123456789abcdef0123456789abcdef_u128.to_be_bytes()),
+			SpanId::from_bytes(0x0123456789abcdef_u64.to_be_bytes()),
+			TraceFlags::SAMPLED,
+			false,
+			TraceState::default(),
+		);
+		let mut req = RelayedReq {
+			from: [1u8; 32].into(),
+			path: "test".into(),
+			prio: PRIO_HIGH,
+			body: vec![],
+			stream: None,
+			headers: HashMap::new(),
+		};
+		req.set_trace_context(&Context::new().with_remote_span_context(span_context.clone()));
+
+		let req = nonversioned_decode::<RelayedReq>(&nonversioned_encode(&req).unwrap()).unwrap();
+		let cx = req.trace_context();
+		let remote = cx.span().span_context().clone();
+		assert_eq!(remote.trace_id(), span_context.trace_id());
+		assert_eq!(remote.span_id(), span_context.span_id());
+		assert!(remote.is_sampled());
+		assert!(remote.is_remote());
+
+		// Requests of nodes that do not send a trace context start new traces
+		let req = RelayedReq {
+			headers: HashMap::new(),
+			..req
+		};
+		assert!(!req.trace_context().span().span_context().is_valid());
+	}
+}
@@ -1,5 +1,6 @@
 //! Module containing types related to computing nodes which should receive a copy of data blocks
 //! and metadata
+use std::collections::HashMap;
 use std::convert::TryInto;
 
 use garage_util::data::*;
@@ -21,6 +22,13 @@ pub const PARTITION_BITS: usize = 8;
 
 const PARTITION_MASK_U16: u16 = ((1 << PARTITION_BITS) - 1) << (16 - PARTITION_BITS);
 
+/// Get the first hash of a partition key that would fall in the given partition
+pub fn partition_first_hash(partition: Partition) -> Hash {
+	let mut location = [0u8; 32];
+	location[..2].copy_from_slice(&u16::to_be_bytes(partition << (16 - PARTITION_BITS))[..]);
+	location.into()
+}
+
 /// A ring distributing fairly objects to nodes
 #[derive(Clone)]
 pub struct Ring {
@@ -127,6 +135,28 @@ impl Ring {
 		ret
 	}
 
+	/// Average number of copies of a partition that are stored in each zone,
+	/// i.e. the number of bytes stored in a zone for each byte of data
+	/// spread over all partitions
+	pub fn zone_replication(&self) -> HashMap<String, f64> {
+		let mut ret = HashMap::new();
+		if self.ring.is_empty() {
+			return ret;
+		}
+		for entry in self.ring.iter() {
+			for i in entry.nodes_buf[..self.replication_factor].iter() {
+				let node = self.nodes[*i as usize];
+				if let Some(role) = self.layout.node_role(&node) {
+					*ret.entry(role.zone.clone()).or_insert(0.) += 1.;
+				}
+			}
+		}
+		for copies in ret.values_mut() {
+			*copies /= self.ring.len() as f64;
+		}
+		ret
+	}
+
 	/// Walk the ring to find the n servers in which data should be replicated
 	pub fn get_nodes(&self, position: &Hash, n: usize) -> Vec<Uuid> {
 		if self.ring.len() != 1 << PARTITION_BITS {
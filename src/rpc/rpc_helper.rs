@@ -26,7 +26,10 @@ use garage_util::data::*;
 use garage_util::error::Error;
 use garage_util::metrics::RecordDuration;
 
+use crate::bandwidth::Bandwidth;
+use crate::fault_injection::FaultInjector;
 use crate::metrics::RpcMetrics;
+use crate::relay::Relay;
 use crate::ring::Ring;
 
 // Default RPC timeout = 5 minutes
@@ -94,6 +97,9 @@ struct RpcHelperInner {
 	ring: watch::Receiver<Arc<Ring>>,
 	metrics: RpcMetrics,
 	rpc_timeout: Duration,
+	fault_injection: Arc<FaultInjector>,
+	relay: Arc<Relay>,
+	bandwidth: Bandwidth,
 }
 
 impl RpcHelper {
@@ -102,6 +108,9 @@ impl RpcHelper {
 		fullmesh: Arc<FullMeshPeeringStrategy>,
 		ring: watch::Receiver<Arc<Ring>>,
 		rpc_timeout: Option<Duration>,
+		fault_injection: Arc<FaultInjector>,
+		relay: Arc<Relay>,
+		bandwidth: Bandwidth,
 	) -> Self {
 		let metrics = RpcMetrics::new();
 
@@ -111,6 +120,9 @@ impl RpcHelper {
 			ring,
 			metrics,
 			rpc_timeout: rpc_timeout.unwrap_or(DEFAULT_TIMEOUT),
+			fault_injection,
+			relay,
+			bandwidth,
 		}))
 	}
 
@@ -118,6 +130,45 @@ impl RpcHelper {
 		self.0.rpc_timeout
 	}
 
+	/// Relay for the requests to nodes that cannot be reached directly
+	pub fn relay(&self) -> &Arc<Relay> {
+		&self.0.relay
+	}
+
+	/// Bytes exchanged with other nodes and rate limits of the links
+	pub fn bandwidth(&self) -> &Bandwidth {
+		&self.0.bandwidth
+	}
+
+	/// Send a request to a node and return its response along with its
+	/// stream, through a relay if the node cannot be reached directly,
+	/// within the rate limit of the link with the node
+	pub async fn call_streaming<M, N, H>(
+		&self,
+		endpoint: &Endpoint<M, H>,
+		to: Uuid,
+		msg: N,
+		prio: RequestPriority,
+	) -> Result<Resp<M>, Error>
+	where
+		M: Rpc,
+		N: IntoReq<M> + Send,
+		H: StreamingEndpointHandler<M>,
+	{
+		let node_id = to.into();
+		let req = msg.into_req().map_err(netapp::error::Error::from)?;
+		let req = self.0.bandwidth.shape_request(to, req).await?;
+		let resp = if self.0.relay.must_relay(&node_id) {
+			self.0
+				.relay
+				.call(endpoint.path(), node_id, req, prio)
+				.await?
+		} else {
+			endpoint.call_streaming(&node_id, req, prio).await?
+		};
+		self.0.bandwidth.shape_response(to, resp).await
+	}
+
 	pub async fn call<M, N, H, S>(
 		&self,
 		endpoint: &Endpoint<M, H>,
@@ -129,6 +180,7 @@ impl RpcHelper {
 		M: Rpc<Response = Result<S, Error>>,
 		N: IntoReq<M> + Send,
 		H: StreamingEndpointHandler<M>,
+		S: Send,
 	{
 		let metric_tags = [
 			KeyValue::new("rpc_endpoint", endpoint.path().to_string()),
@@ -138,10 +190,26 @@ impl RpcHelper {
 
 		self.0.metrics.rpc_counter.add(1, &metric_tags);
 
-		let node_id = to.into();
-		let rpc_call = endpoint
-			.call_streaming(&node_id, msg, strat.rs_priority)
-			.record_duration(&self.0.metrics.rpc_duration, &metric_tags);
+		let fault_injection = &self.0.fault_injection;
+		let rpc_call = async {
+			fault_injection.before_rpc(endpoint.path(), to).await?;
+
+			let res = self
+				.call_streaming(endpoint, to, msg, strat.rs_priority)
+				.record_duration(&self.0.metrics.rpc_duration, &metric_tags)
+				.await;
+			if res.is_err() {
+				self.0.metrics.rpc_netapp_error_counter.add(1, &metric_tags);
+			}
+			let res = res?.into_msg();
+			fault_injection.after_rpc(endpoint.path(), to)?;
+
+			if res.is_err() {
+				self.0.metrics.rpc_garage_error_counter.add(1, &metric_tags);
+			}
+
+			res
+		};
 
 		let timeout = async {
 			match strat.rs_timeout {
@@ -152,18 +220,7 @@ impl RpcHelper {
 		};
 
 		select! {
-			res = rpc_call => {
-				if res.is_err() {
-					self.0.metrics.rpc_netapp_error_counter.add(1, &metric_tags);
-				}
-				let res = res?.into_msg();
-
-				if res.is_err() {
-					self.0.metrics.rpc_garage_error_counter.add(1, &metric_tags);
-				}
-
-				Ok(res?)
-			}
+			res = rpc_call => res,
 			() = timeout => {
 				self.0.metrics.rpc_timeout_counter.add(1, &metric_tags);
 				Err(Error::Timeout)
@@ -182,6 +239,7 @@ impl RpcHelper {
 		M: Rpc<Response = Result<S, Error>>,
 		N: IntoReq<M>,
 		H: StreamingEndpointHandler<M>,
+		S: Send,
 	{
 		let msg = msg.into_req().map_err(netapp::error::Error::from)?;
 
@@ -207,6 +265,7 @@ impl RpcHelper {
 		M: Rpc<Response = Result<S, Error>>,
 		N: IntoReq<M>,
 		H: StreamingEndpointHandler<M>,
+		S: Send,
 	{
 		let to = self
 			.0
@@ -384,8 +443,7 @@ impl RpcHelper {
 		if successes.len() >= quorum {
 			Ok(successes)
 		} else {
-			let errors = errors.iter().map(|e| format!("{}", e)).collect::<Vec<_>>();
-			Err(Error::Quorum(quorum, successes.len(), to.len(), errors))
+			Err(Error::quorum(quorum, successes.len(), to.len(), &errors))
 		}
 	}
 
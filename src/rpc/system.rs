@@ -1,5 +1,5 @@
 //! Module containing structs related to membership management
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::io::{Read, Write};
 use std::net::{IpAddr, SocketAddr};
 use std::path::{Path, PathBuf};
@@ -24,17 +24,23 @@ use netapp::{NetApp, NetworkKey, NodeID, NodeKey};
 
 #[cfg(feature = "kubernetes-discovery")]
 use garage_util::config::KubernetesDiscoveryConfig;
-use garage_util::config::{Config, DataDirEnum};
+use garage_util::config::{Config, DataDirEnum, RebalanceConfig};
 use garage_util::data::*;
 use garage_util::error::*;
 use garage_util::persister::Persister;
+use garage_util::platform::disk_space;
 use garage_util::time::*;
 
+use crate::bandwidth::Bandwidth;
 #[cfg(feature = "consul-discovery")]
 use crate::consul::ConsulDiscovery;
+use crate::fault_injection::FaultInjector;
+use crate::happy_eyeballs;
 #[cfg(feature = "kubernetes-discovery")]
 use crate::kubernetes::*;
 use crate::layout::*;
+use crate::rebalance::*;
+use crate::relay::Relay;
 use crate::replication_mode::*;
 use crate::ring::*;
 use crate::rpc_helper::*;
@@ -70,6 +76,10 @@ pub enum SystemRpc {
 	GetKnownNodes,
 	/// Return known nodes
 	ReturnKnownNodes(Vec<KnownNodeInfo>),
+	/// Get suggested capacity changes to balance data disk usage between nodes
+	GetRebalanceSuggestion,
+	/// Return suggested capacity changes, None if data disk usage is unknown
+	ReturnRebalanceSuggestion(Option<RebalanceSuggestion>),
 }
 
 impl Rpc for SystemRpc {
@@ -94,6 +104,8 @@ pub struct System {
 	pub netapp: Arc<NetApp>,
 	fullmesh: Arc<FullMeshPeeringStrategy>,
 	pub rpc: RpcHelper,
+	/// Faults injected in RPCs and block storage, for testing
+	pub fault_injection: Arc<FaultInjector>,
 
 	system_endpoint: Arc<Endpoint<SystemRpc, System>>,
 
@@ -120,6 +132,8 @@ pub struct System {
 	pub metadata_dir: PathBuf,
 	/// Path to data directory
 	pub data_dir: DataDirEnum,
+
+	pub(crate) rebalance_config: ArcSwap<RebalanceConfig>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -140,6 +154,39 @@ pub struct NodeStatus {
 	/// Disk usage on partition containing data directory (tuple: `(avail, total)`)
 	#[serde(default)]
 	pub data_disk_avail: Option<(u64, u64)>,
+
+	/// Progress of moving data off the node, if it no longer has a storage role
+	/// but still holds data, or has been drained since it lost its storage role
+	#[serde(default)]
+	pub drain: Option<DrainStatus>,
+
+	/// Rate at which the node has received data blocks from other nodes
+	/// during resync, in bytes per second, if it has received any
+	#[serde(default)]
+	pub resync_bandwidth: Option<u64>,
+
+	/// Addresses at which the node accepts RPC connections, as configured
+	/// or detected on the node, IPv6 addresses first
+	#[serde(default)]
+	pub rpc_public_addrs: Vec<SocketAddr>,
+
+	/// Whether the node does not accept inbound connections, and receives
+	/// its requests through the nodes it connects to
+	#[serde(default)]
+	pub outbound_only: bool,
+}
+
+/// Progress of moving data off a node that no longer has a storage role
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct DrainStatus {
+	/// Number of table partitions that the node still has to offload
+	pub partitions_remaining: u64,
+	/// Number of table partitions that the node had to offload when draining started
+	pub partitions_initial: u64,
+	/// Number of data blocks still referenced on the node
+	pub blocks_remaining: u64,
+	/// Number of data blocks referenced on the node when draining started
+	pub blocks_initial: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -222,11 +269,14 @@ pub fn gen_node_key(metadata_dir: &Path) -> Result<NodeKey, Error> {
 		let (pubkey, key) = ed25519::gen_keypair();
 
 		{
-			use std::os::unix::fs::PermissionsExt;
 			let mut f = std::fs::File::create(key_file.as_path())?;
-			let mut perm = f.metadata()?.permissions();
-			perm.set_mode(0o600);
-			std::fs::set_permissions(key_file.as_path(), perm)?;
+			#[cfg(unix)]
+			{
+				use std::os::unix::fs::PermissionsExt;
+				let mut perm = f.metadata()?.permissions();
+				perm.set_mode(0o600);
+				std::fs::set_permissions(key_file.as_path(), perm)?;
+			}
 			f.write_all(&key[..])?;
 		}
 
@@ -289,7 +339,11 @@ impl System {
 		let ring = Ring::new(cluster_layout, replication_factor);
 		let (update_ring, ring) = watch::channel(Arc::new(ring));
 
-		let rpc_public_addr = match &config.rpc_public_addr {
+		let rpc_public_addrs = match &config.rpc_public_addr {
+			_ if config.rpc_outbound_only => {
+				info!("Running in outbound-only mode, requests to this node will be relayed by the nodes it connects to.");
+				vec![]
+			}
 			Some(a_str) => {
 				use std::net::ToSocketAddrs;
 				match a_str.to_socket_addrs() {
@@ -298,30 +352,35 @@ impl System {
 							"Cannot resolve rpc_public_addr {} from config file: {}.",
 							a_str, e
 						);
-						None
+						vec![]
 					}
 					Ok(a) => {
 						let a = a.collect::<Vec<_>>();
 						if a.is_empty() {
 							error!("rpc_public_addr {} resolve to no known IP address", a_str);
 						}
-						if a.len() > 1 {
-							warn!("Multiple possible resolutions for rpc_public_addr: {:?}. Taking the first one.", a);
-						}
-						a.into_iter().next()
+						a
 					}
 				}
 			}
 			None => {
-				let addr =
-					get_default_ip().map(|ip| SocketAddr::new(ip, config.rpc_bind_addr.port()));
-				if let Some(a) = addr {
-					warn!("Using autodetected rpc_public_addr: {}. Consider specifying it explicitly in configuration file if possible.", a);
+				let addrs = get_default_ips()
+					.into_iter()
+					.map(|ip| SocketAddr::new(ip, config.rpc_bind_addr.port()))
+					.collect::<Vec<_>>();
+				if !addrs.is_empty() {
+					warn!("Using autodetected rpc_public_addr: {:?}. Consider specifying it explicitly in configuration file if possible.", addrs);
 				}
-				addr
+				addrs
 			}
 		};
-		if rpc_public_addr.is_none() {
+		// Other nodes learn the first address through the peer exchange of
+		// the RPC layer, and all of them through the status of this node
+		let rpc_public_addrs = happy_eyeballs::sort_addrs(&rpc_public_addrs);
+		let rpc_public_addr = rpc_public_addrs.first().copied();
+		local_status.rpc_public_addrs = rpc_public_addrs;
+		local_status.outbound_only = config.rpc_outbound_only;
+		if rpc_public_addr.is_none() && !config.rpc_outbound_only {
 			warn!("This Garage node does not know its publicly reachable RPC address, this might hamper intra-cluster communication.");
 		}
 
@@ -331,6 +390,7 @@ impl System {
 			fullmesh.set_ping_timeout_millis(ping_timeout);
 		}
 
+		let relay = Relay::new(&netapp, fullmesh.clone(), config.rpc_outbound_only);
 		let system_endpoint = netapp.endpoint(SYSTEM_RPC_PATH.into());
 
 		#[cfg(feature = "consul-discovery")]
@@ -351,6 +411,8 @@ impl System {
 			warn!("Kubernetes discovery is not enabled in this build.");
 		}
 
+		let fault_injection = Arc::new(FaultInjector::default());
+
 		let sys = Arc::new(System {
 			id: netapp.id.into(),
 			persist_cluster_layout,
@@ -364,7 +426,11 @@ impl System {
 				fullmesh,
 				ring.clone(),
 				config.rpc_timeout_msec.map(Duration::from_millis),
+				fault_injection.clone(),
+				relay,
+				Bandwidth::new(netapp.id.into(), ring.clone(), &config.rpc_link_limits),
 			),
+			fault_injection,
 			system_endpoint,
 			replication_mode,
 			replication_factor,
@@ -382,8 +448,9 @@ impl System {
 			update_ring: Mutex::new(update_ring),
 			metadata_dir: config.metadata_dir.clone(),
 			data_dir: config.data_dir.clone(),
+			rebalance_config: ArcSwap::new(Arc::new(config.rebalance.clone())),
 		});
-		sys.system_endpoint.set_handler(sys.clone());
+		sys.set_handler(&sys.system_endpoint, sys.clone());
 		Ok(sys)
 	}
 
@@ -396,9 +463,30 @@ impl System {
 			self.fullmesh.clone().run(must_exit.clone()),
 			self.discovery_loop(must_exit.clone()),
 			self.status_exchange_loop(must_exit.clone()),
+			self.rpc.relay().clone().poll_loop(must_exit.clone()),
 		);
 	}
 
+	/// Create an endpoint of the RPC layer, whose handler must be set with
+	/// `set_handler`
+	pub fn endpoint<M, H>(&self, path: String) -> Arc<Endpoint<M, H>>
+	where
+		M: Rpc,
+		H: StreamingEndpointHandler<M> + 'static,
+	{
+		self.netapp.endpoint(path)
+	}
+
+	/// Set the handler of an endpoint, to which requests are also relayed
+	/// from nodes that cannot reach this node directly
+	pub fn set_handler<M, H>(&self, endpoint: &Endpoint<M, H>, handler: Arc<H>)
+	where
+		M: Rpc,
+		H: StreamingEndpointHandler<M> + 'static,
+	{
+		self.rpc.relay().set_handler(endpoint, handler);
+	}
+
 	// ---- Administrative operations (directly available and
 	//      also available through RPC) ----
 
@@ -411,7 +499,7 @@ impl System {
 			.map(|n| KnownNodeInfo {
 				id: n.id.into(),
 				addr: n.addr,
-				is_up: n.is_up(),
+				is_up: n.is_up() || self.rpc.relay().is_polled_by(&n.id),
 				last_seen_secs_ago: n
 					.last_seen
 					.map(|t| (Instant::now().saturating_duration_since(t)).as_secs()),
@@ -446,18 +534,13 @@ impl System {
 					node
 				))
 			})?;
-		let mut errors = vec![];
-		for addr in addrs.iter() {
-			match self.netapp.clone().try_connect(*addr, pubkey).await {
-				Ok(()) => return Ok(()),
-				Err(e) => {
-					errors.push((
-						*addr,
-						Error::Message(connect_error_message(*addr, pubkey, e)),
-					));
-				}
-			}
-		}
+		let errors = match happy_eyeballs::connect(self.netapp.clone(), pubkey, &addrs).await {
+			Ok(_) => return Ok(()),
+			Err(errors) => errors
+				.into_iter()
+				.map(|(addr, e)| (addr, Error::Message(connect_error_message(addr, pubkey, e))))
+				.collect::<Vec<_>>(),
+		};
 		if errors.len() == 1 {
 			Err(Error::Message(errors[0].1.to_string()))
 		} else {
@@ -604,6 +687,62 @@ impl System {
 		self.local_status.swap(Arc::new(new_si));
 	}
 
+	/// Update the drain progress of this node that is advertised to other nodes
+	pub fn set_drain_status(&self, drain: Option<DrainStatus>) {
+		self.local_status.rcu(|status| {
+			let mut status = status.as_ref().clone();
+			status.drain = drain;
+			status
+		});
+	}
+
+	/// Update the resync bandwidth of this node that is advertised to other nodes
+	pub fn set_resync_bandwidth(&self, bandwidth: Option<u64>) {
+		self.local_status.rcu(|status| {
+			let mut status = status.as_ref().clone();
+			status.resync_bandwidth = bandwidth;
+			status
+		});
+	}
+
+	/// Change the configuration of the monitoring of the balance of data
+	/// disk usage, e.g. when the configuration file is reloaded
+	pub fn set_rebalance_config(&self, config: RebalanceConfig) {
+		self.rebalance_config.store(Arc::new(config));
+	}
+
+	/// Compute suggested capacity changes from the data disk usage of
+	/// storage nodes that are up
+	pub fn rebalance_suggestion(&self) -> Option<RebalanceSuggestion> {
+		let disk_usage = self
+			.get_known_nodes()
+			.into_iter()
+			.filter(|n| n.is_up)
+			.filter_map(|n| Some((n.id, n.status.data_disk_avail?)))
+			.collect::<HashMap<_, _>>();
+		suggest_rebalance(
+			&self.ring.borrow().layout,
+			&disk_usage,
+			&self.rebalance_config.load(),
+		)
+	}
+
+	/// Whether this node is the storage node with the smallest ID among those
+	/// that are up, which sends the notifications of the cluster so that they
+	/// are sent only once
+	pub fn is_leader(&self) -> bool {
+		let layout = self.get_cluster_layout();
+		let leader = self
+			.get_known_nodes()
+			.into_iter()
+			.filter(|n| {
+				n.is_up && matches!(layout.node_role(&n.id), Some(r) if r.capacity.is_some())
+			})
+			.map(|n| n.id)
+			.min();
+		leader == Some(self.id)
+	}
+
 	// --- RPC HANDLERS ---
 
 	async fn handle_connect(&self, node: &str) -> Result<SystemRpc, Error> {
@@ -641,6 +780,9 @@ impl System {
 			tokio::spawn(self.clone().pull_cluster_layout(from));
 		}
 
+		self.rpc
+			.relay()
+			.set_outbound_only(from.into(), info.outbound_only);
 		self.node_status
 			.write()
 			.unwrap()
@@ -728,12 +870,8 @@ impl System {
 			let not_configured = self.ring.borrow().layout.check().is_err();
 			let no_peers = self.fullmesh.get_peer_list().len() < self.replication_factor;
 			let expected_n_nodes = self.ring.borrow().layout.num_nodes();
-			let bad_peers = self
-				.fullmesh
-				.get_peer_list()
-				.iter()
-				.filter(|p| p.is_up())
-				.count() != expected_n_nodes;
+			let bad_peers =
+				self.get_known_nodes().iter().filter(|n| n.is_up).count() != expected_n_nodes;
 
 			if not_configured || no_peers || bad_peers {
 				info!("Doing a bootstrap/discovery step (not_configured: {}, no_peers: {}, bad_peers: {})", not_configured, no_peers, bad_peers);
@@ -780,11 +918,36 @@ impl System {
 					}
 				}
 
+				// Add the addresses of the nodes we know of that are down, some
+				// of which might not be the one the RPC layer tries them at
+				let up_peers = self
+					.fullmesh
+					.get_peer_list()
+					.iter()
+					.filter(|p| p.is_up())
+					.map(|p| p.id)
+					.collect::<HashSet<_>>();
+				let relay = self.rpc.relay();
+				ping_list.extend(
+					self.peer_addrs()
+						.into_iter()
+						.filter(|(id, _)| !up_peers.contains(id) && !relay.is_outbound_only(id))
+						.flat_map(|(id, addrs)| addrs.into_iter().map(move |a| (id, a))),
+				);
+
+				// Connect to each node at the first of its addresses that answers
+				let mut node_addrs = HashMap::<NodeID, Vec<SocketAddr>>::new();
 				for (node_id, node_addr) in ping_list {
-					let self2 = self.clone();
+					node_addrs.entry(node_id).or_default().push(node_addr);
+				}
+				for (node_id, addrs) in node_addrs {
+					let netapp = self.netapp.clone();
 					tokio::spawn(async move {
-						if let Err(e) = self2.netapp.clone().try_connect(node_addr, node_id).await {
-							error!("{}", connect_error_message(node_addr, node_id, e));
+						if let Err(errors) = happy_eyeballs::connect(netapp, node_id, &addrs).await
+						{
+							for (addr, e) in errors {
+								error!("{}", connect_error_message(addr, node_id, e));
+							}
 						}
 					});
 				}
@@ -809,12 +972,17 @@ impl System {
 
 	async fn save_peer_list(&self) -> Result<(), Error> {
 		// Prepare new peer list to save to file
-		// It is a vec of tuples (node ID as Uuid, node SocketAddr)
+		// It is a vec of tuples (node ID as Uuid, node SocketAddr),
+		// with one tuple for each address of a node
 		let mut peer_list = self
-			.fullmesh
-			.get_peer_list()
-			.iter()
-			.map(|n| (n.id.into(), n.addr))
+			.peer_addrs()
+			.into_iter()
+			.flat_map(|(id, addrs)| {
+				let id: Uuid = id.into();
+				happy_eyeballs::sort_addrs(&addrs)
+					.into_iter()
+					.map(move |a| (id, a))
+			})
 			.collect::<Vec<_>>();
 
 		// Before doing it, we read the current peer list file (if it exists)
@@ -833,6 +1001,21 @@ impl System {
 			.await
 	}
 
+	/// All the addresses known for the nodes of the peer list: the address
+	/// the RPC layer connects to them at, and those they advertise
+	fn peer_addrs(&self) -> HashMap<NodeID, Vec<SocketAddr>> {
+		let node_status = self.node_status.read().unwrap();
+		let mut addrs = HashMap::new();
+		for peer in self.fullmesh.get_peer_list().iter() {
+			let peer_addrs = addrs.entry(peer.id).or_insert_with(Vec::new);
+			peer_addrs.push(peer.addr);
+			if let Some((_, status)) = node_status.get(&peer.id.into()) {
+				peer_addrs.extend(status.rpc_public_addrs.iter().copied());
+			}
+		}
+		addrs
+	}
+
 	async fn pull_cluster_layout(self: Arc<Self>, peer: Uuid) {
 		let resp = self
 			.rpc
@@ -860,11 +1043,36 @@ impl EndpointHandler<SystemRpc> for System {
 				self.clone().handle_advertise_cluster_layout(adv).await
 			}
 			SystemRpc::GetKnownNodes => Ok(self.handle_get_known_nodes()),
+			SystemRpc::GetRebalanceSuggestion => Ok(SystemRpc::ReturnRebalanceSuggestion(
+				self.rebalance_suggestion(),
+			)),
 			m => Err(Error::unexpected_rpc_message(m)),
 		}
 	}
 }
 
+impl DrainStatus {
+	/// Whether all data has been moved off the node
+	pub fn is_complete(&self) -> bool {
+		self.partitions_remaining == 0 && self.blocks_remaining == 0
+	}
+
+	/// Percentage of the data that has been moved off the node, as the
+	/// average of the progress of partition and block offloading
+	pub fn progress(&self) -> f64 {
+		let progress = |remaining: u64, initial: u64| {
+			if initial == 0 || remaining == 0 {
+				100.
+			} else {
+				100. * (1. - remaining as f64 / initial.max(remaining) as f64)
+			}
+		};
+		(progress(self.partitions_remaining, self.partitions_initial)
+			+ progress(self.blocks_remaining, self.blocks_initial))
+			/ 2.
+	}
+}
+
 impl NodeStatus {
 	fn initial(replication_factor: usize, layout: &ClusterLayout) -> Self {
 		NodeStatus {
@@ -876,6 +1084,10 @@ impl NodeStatus {
 			cluster_layout_staging_hash: layout.staging_hash,
 			meta_disk_avail: None,
 			data_disk_avail: None,
+			drain: None,
+			resync_bandwidth: None,
+			rpc_public_addrs: vec![],
+			outbound_only: false,
 		}
 	}
 
@@ -887,6 +1099,10 @@ impl NodeStatus {
 			cluster_layout_staging_hash: Hash::from([0u8; 32]),
 			meta_disk_avail: None,
 			data_disk_avail: None,
+			drain: None,
+			resync_bandwidth: None,
+			rpc_public_addrs: vec![],
+			outbound_only: false,
 		}
 	}
 
@@ -896,13 +1112,8 @@ impl NodeStatus {
 		data_dir: &DataDirEnum,
 		metrics: &SystemMetrics,
 	) {
-		use nix::sys::statvfs::statvfs;
-		let mount_avail = |path: &Path| match statvfs(path) {
-			Ok(x) => {
-				let avail = x.blocks_available() * x.fragment_size() as u64;
-				let total = x.blocks() * x.fragment_size() as u64;
-				Some((x.filesystem_id(), avail, total))
-			}
+		let mount_avail = |path: &Path| match disk_space(path) {
+			Ok(x) => Some((x.filesystem_id, x.available, x.total)),
 			Err(_) => None,
 		};
 
@@ -955,12 +1166,23 @@ impl NodeStatus {
 	}
 }
 
-fn get_default_ip() -> Option<IpAddr> {
-	pnet_datalink::interfaces()
-		.iter()
+/// Addresses of the first network interface that is up and is not a
+/// loopback interface: its first IPv6 address that is not link-local,
+/// and its first IPv4 address
+fn get_default_ips() -> Vec<IpAddr> {
+	let interface = match pnet_datalink::interfaces()
+		.into_iter()
 		.find(|e| e.is_up() && !e.is_loopback() && !e.ips.is_empty())
-		.and_then(|e| e.ips.first())
-		.map(|a| a.ip())
+	{
+		Some(i) => i,
+		None => return vec![],
+	};
+	let ips = interface.ips.iter().map(|a| a.ip());
+	let v6 = ips
+		.clone()
+		.find(|ip| matches!(ip, IpAddr::V6(v6) if v6.segments()[0] & 0xffc0 != 0xfe80));
+	let v4 = ips.clone().find(IpAddr::is_ipv4);
+	v6.into_iter().chain(v4).collect()
 }
 
 async fn resolve_peers(peers: &[String]) -> Vec<(NodeID, SocketAddr)> {
@@ -989,3 +1211,54 @@ fn connect_error_message(
 ) -> String {
 	format!("Error establishing RPC connection to remote node: {}@{}.\nThis can happen if the remote node is not reachable on the network, but also if the two nodes are not configured with the same rpc_secret.\n{}", hex::encode(pubkey), addr, e)
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use crate::relay::RelayedReq;
+
+	#[tokio::test]
+	async fn test_relayed_status_recorded_under_sender() {
+		let metadata_dir =
+			std::env::temp_dir().join(format!("garage-test-{}", hex::encode(gen_uuid())));
+		let config = Config::new(
+			metadata_dir.clone(),
+			metadata_dir.join("data"),
+			"1",
+			"127.0.0.1:3901".parse().unwrap(),
+			hex::encode([0u8; 32]),
+		);
+		let system = System::new(
+			NetworkKey::from_slice(&[0u8; 32]).unwrap(),
+			ReplicationMode::parse("1").unwrap(),
+			&config,
+		)
+		.unwrap();
+
+		// A node running in outbound-only mode advertises its status
+		// through this node, which relays it to itself
+		let sender: Uuid = [1u8; 32].into();
+		let mut status = (**system.local_status.load()).clone();
+		status.hostname = "sender".to_string();
+		status.outbound_only = true;
+		let req = RelayedReq::new(
+			sender.into(),
+			SYSTEM_RPC_PATH,
+			PRIO_NORMAL,
+			Req::new(SystemRpc::AdvertiseStatus(status)).unwrap(),
+		)
+		.await
+		.unwrap();
+		system.rpc.relay().deliver(req).await.unwrap();
+
+		let node_status = system.node_status.read().unwrap();
+		assert_eq!(node_status.get(&sender).unwrap().1.hostname, "sender");
+		assert!(!node_status.contains_key(&system.id));
+		assert!(system.rpc.relay().is_outbound_only(&sender.into()));
+		assert!(!system.rpc.relay().is_outbound_only(&system.id.into()));
+		drop(node_status);
+
+		std::fs::remove_dir_all(&metadata_dir).unwrap();
+	}
+}
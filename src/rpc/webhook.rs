@@ -0,0 +1,39 @@
+//! Notifications sent to HTTP endpoints configured by the operator
+use std::time::Duration;
+
+use hyper::{Body, Client, Method, Request};
+use serde::Serialize;
+
+use garage_util::error::*;
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Send a JSON document to a webhook in a POST request
+pub async fn post_json<T: Serialize>(url: &str, body: &T) -> Result<(), Error> {
+	let connector = hyper_rustls::HttpsConnectorBuilder::new()
+		.with_native_roots()
+		.https_or_http()
+		.enable_http1()
+		.build();
+	let req = Request::builder()
+		.method(Method::POST)
+		.uri(url)
+		.header("content-type", "application/json")
+		.body(Body::from(serde_json::to_vec(body)?))
+		.ok_or_message("invalid webhook request")?;
+	let resp = tokio::time::timeout(
+		WEBHOOK_TIMEOUT,
+		Client::builder().build::<_, Body>(connector).request(req),
+	)
+	.await
+	.ok_or_message("webhook request timed out")?
+	.ok_or_message("webhook request failed")?;
+
+	if !resp.status().is_success() {
+		return Err(Error::Message(format!(
+			"webhook returned HTTP status {}",
+			resp.status()
+		)));
+	}
+	Ok(())
+}
@@ -0,0 +1,77 @@
+use super::*;
+
+pub async fn bucket_head(ctx: &Context) -> CaseResult {
+	ctx.client
+		.head_bucket()
+		.bucket(&ctx.bucket)
+		.send()
+		.await
+		.map_err(req_err("HeadBucket"))?;
+
+	let res = ctx
+		.client
+		.head_bucket()
+		.bucket(format!("{}-not-exist", ctx.bucket))
+		.send()
+		.await;
+	check!(res.is_err(), "HeadBucket on a missing bucket succeeded");
+	Ok(())
+}
+
+pub async fn bucket_create_exists(ctx: &Context) -> CaseResult {
+	// The bucket is already owned by the same key
+	let res = ctx.client.create_bucket().bucket(&ctx.bucket).send().await;
+	match res {
+		Ok(_) => Ok(()),
+		Err(e) => expect_error("CreateBucket", Err::<(), _>(e), "BucketAlreadyOwnedByYou"),
+	}
+}
+
+pub async fn bucket_delete_nonempty(ctx: &Context) -> CaseResult {
+	put(ctx, "foo", b"bar").await?;
+	let res = ctx.client.delete_bucket().bucket(&ctx.bucket).send().await;
+	expect_error("DeleteBucket", res, "BucketNotEmpty")
+}
+
+pub async fn bucket_delete_empty(ctx: &Context) -> CaseResult {
+	ctx.client
+		.delete_bucket()
+		.bucket(&ctx.bucket)
+		.send()
+		.await
+		.map_err(req_err("DeleteBucket"))?;
+
+	let res = ctx
+		.client
+		.list_objects_v2()
+		.bucket(&ctx.bucket)
+		.send()
+		.await;
+	expect_error("ListObjectsV2", res, "NoSuchBucket")
+}
+
+pub async fn bucket_list_buckets(ctx: &Context) -> CaseResult {
+	let res = ctx
+		.client
+		.list_buckets()
+		.send()
+		.await
+		.map_err(req_err("ListBuckets"))?;
+	let found = res
+		.buckets()
+		.unwrap_or_default()
+		.iter()
+		.any(|b| b.name() == Some(ctx.bucket.as_str()));
+	check!(found, "bucket {} is not in ListBuckets", ctx.bucket);
+	Ok(())
+}
+
+pub async fn bucket_get_location(ctx: &Context) -> CaseResult {
+	ctx.client
+		.get_bucket_location()
+		.bucket(&ctx.bucket)
+		.send()
+		.await
+		.map_err(req_err("GetBucketLocation"))?;
+	Ok(())
+}
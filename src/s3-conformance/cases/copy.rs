@@ -0,0 +1,70 @@
+use aws_sdk_s3::types::MetadataDirective;
+
+use super::*;
+
+pub async fn object_copy_same_bucket(ctx: &Context) -> CaseResult {
+	put(ctx, "foo", b"Hello world!").await?;
+	ctx.client
+		.copy_object()
+		.bucket(&ctx.bucket)
+		.key("bar")
+		.copy_source(format!("{}/foo", ctx.bucket))
+		.send()
+		.await
+		.map_err(req_err("CopyObject"))?;
+	check_eq!(get(ctx, "bar").await?, b"Hello world!".to_vec());
+	Ok(())
+}
+
+pub async fn object_copy_replace_metadata(ctx: &Context) -> CaseResult {
+	ctx.client
+		.put_object()
+		.bucket(&ctx.bucket)
+		.key("foo")
+		.content_type("text/plain")
+		.metadata("meta1", "original")
+		.body(ByteStream::from_static(b"bar"))
+		.send()
+		.await
+		.map_err(req_err("PutObject"))?;
+	ctx.client
+		.copy_object()
+		.bucket(&ctx.bucket)
+		.key("foo")
+		.copy_source(format!("{}/foo", ctx.bucket))
+		.metadata_directive(MetadataDirective::Replace)
+		.content_type("application/octet-stream")
+		.metadata("meta1", "replaced")
+		.send()
+		.await
+		.map_err(req_err("CopyObject"))?;
+
+	let res = ctx
+		.client
+		.head_object()
+		.bucket(&ctx.bucket)
+		.key("foo")
+		.send()
+		.await
+		.map_err(req_err("HeadObject"))?;
+	check_eq!(res.content_type(), Some("application/octet-stream"));
+	check_eq!(
+		res.metadata()
+			.and_then(|m| m.get("meta1"))
+			.map(String::as_str),
+		Some("replaced")
+	);
+	Ok(())
+}
+
+pub async fn object_copy_not_exist(ctx: &Context) -> CaseResult {
+	let res = ctx
+		.client
+		.copy_object()
+		.bucket(&ctx.bucket)
+		.key("bar")
+		.copy_source(format!("{}/not-exist", ctx.bucket))
+		.send()
+		.await;
+	expect_error("CopyObject", res, "NoSuchKey")
+}
@@ -0,0 +1,162 @@
+use super::*;
+
+const KEYS: [&str; 6] = ["a", "a/a", "a/b", "a/c/d", "b", "c"];
+
+async fn put_keys(ctx: &Context) -> CaseResult {
+	for key in KEYS {
+		put(ctx, key, b"x").await?;
+	}
+	Ok(())
+}
+
+pub async fn bucket_list_empty(ctx: &Context) -> CaseResult {
+	let res = ctx
+		.client
+		.list_objects()
+		.bucket(&ctx.bucket)
+		.send()
+		.await
+		.map_err(req_err("ListObjects"))?;
+	check_eq!(res.contents().unwrap_or_default().len(), 0);
+	check_eq!(res.is_truncated(), false);
+	Ok(())
+}
+
+pub async fn bucket_list_prefix(ctx: &Context) -> CaseResult {
+	put_keys(ctx).await?;
+	let res = ctx
+		.client
+		.list_objects_v2()
+		.bucket(&ctx.bucket)
+		.prefix("a/")
+		.send()
+		.await
+		.map_err(req_err("ListObjectsV2"))?;
+	let keys = res
+		.contents()
+		.unwrap_or_default()
+		.iter()
+		.filter_map(|o| o.key())
+		.collect::<Vec<_>>();
+	check_eq!(keys, vec!["a/a", "a/b", "a/c/d"]);
+	Ok(())
+}
+
+pub async fn bucket_list_delimiter(ctx: &Context) -> CaseResult {
+	put_keys(ctx).await?;
+	let res = ctx
+		.client
+		.list_objects_v2()
+		.bucket(&ctx.bucket)
+		.delimiter("/")
+		.send()
+		.await
+		.map_err(req_err("ListObjectsV2"))?;
+	let keys = res
+		.contents()
+		.unwrap_or_default()
+		.iter()
+		.filter_map(|o| o.key())
+		.collect::<Vec<_>>();
+	let prefixes = res
+		.common_prefixes()
+		.unwrap_or_default()
+		.iter()
+		.filter_map(|p| p.prefix())
+		.collect::<Vec<_>>();
+	check_eq!(keys, vec!["a", "b", "c"]);
+	check_eq!(prefixes, vec!["a/"]);
+	Ok(())
+}
+
+pub async fn bucket_list_maxkeys_pagination(ctx: &Context) -> CaseResult {
+	put_keys(ctx).await?;
+	let mut keys = vec![];
+	let mut marker = None;
+	loop {
+		let res = ctx
+			.client
+			.list_objects()
+			.bucket(&ctx.bucket)
+			.max_keys(2)
+			.set_marker(marker.clone())
+			.send()
+			.await
+			.map_err(req_err("ListObjects"))?;
+		let page = res
+			.contents()
+			.unwrap_or_default()
+			.iter()
+			.filter_map(|o| o.key().map(String::from))
+			.collect::<Vec<_>>();
+		check!(page.len() <= 2, "ListObjects returned more than max-keys");
+		keys.extend(page);
+		if !res.is_truncated() {
+			break;
+		}
+		marker = res
+			.next_marker()
+			.map(String::from)
+			.or_else(|| keys.last().cloned());
+		check!(
+			keys.len() <= KEYS.len(),
+			"ListObjects pagination does not end"
+		);
+	}
+	check_eq!(keys, KEYS.to_vec());
+	Ok(())
+}
+
+pub async fn bucket_listv2_continuation(ctx: &Context) -> CaseResult {
+	put_keys(ctx).await?;
+	let mut keys = vec![];
+	let mut token = None;
+	loop {
+		let res = ctx
+			.client
+			.list_objects_v2()
+			.bucket(&ctx.bucket)
+			.max_keys(4)
+			.set_continuation_token(token)
+			.send()
+			.await
+			.map_err(req_err("ListObjectsV2"))?;
+		keys.extend(
+			res.contents()
+				.unwrap_or_default()
+				.iter()
+				.filter_map(|o| o.key().map(String::from)),
+		);
+		token = res.next_continuation_token().map(String::from);
+		check_eq!(res.is_truncated(), token.is_some());
+		if token.is_none() {
+			break;
+		}
+		check!(
+			keys.len() <= KEYS.len(),
+			"ListObjectsV2 pagination does not end"
+		);
+	}
+	check_eq!(keys, KEYS.to_vec());
+	Ok(())
+}
+
+pub async fn bucket_listv2_start_after(ctx: &Context) -> CaseResult {
+	put_keys(ctx).await?;
+	let res = ctx
+		.client
+		.list_objects_v2()
+		.bucket(&ctx.bucket)
+		.start_after("a/b")
+		.send()
+		.await
+		.map_err(req_err("ListObjectsV2"))?;
+	let keys = res
+		.contents()
+		.unwrap_or_default()
+		.iter()
+		.filter_map(|o| o.key())
+		.collect::<Vec<_>>();
+	check_eq!(keys, vec!["a/c/d", "b", "c"]);
+	Ok(())
+}
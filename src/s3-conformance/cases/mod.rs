@@ -0,0 +1,146 @@
+//! The conformance cases, grouped by feature. Names follow the names of the
+//! corresponding tests of ceph/s3-tests where there is one.
+
+use aws_sdk_s3::error::{ProvideErrorMetadata, SdkError};
+use aws_sdk_s3::primitives::ByteStream;
+
+use crate::{Case, CaseResult, Context, Expect};
+
+mod bucket;
+mod copy;
+mod list;
+mod multipart;
+mod object;
+mod unsupported;
+
+macro_rules! case {
+	($feature:expr, $expect:ident, $m:ident :: $f:ident) => {
+		Case {
+			name: stringify!($f),
+			feature: $feature,
+			expect: Expect::$expect,
+			run: |ctx| Box::pin($m::$f(ctx)),
+		}
+	};
+}
+
+pub static CASES: &[Case] = &[
+	case!("bucket", Pass, bucket::bucket_head),
+	case!("bucket", Pass, bucket::bucket_create_exists),
+	case!("bucket", Pass, bucket::bucket_delete_nonempty),
+	case!("bucket", Pass, bucket::bucket_delete_empty),
+	case!("bucket", Pass, bucket::bucket_list_buckets),
+	case!("bucket", Pass, bucket::bucket_get_location),
+	case!("object", Pass, object::object_write_read),
+	case!("object", Pass, object::object_write_overwrite),
+	case!("object", Pass, object::object_head),
+	case!("object", Pass, object::object_read_not_exist),
+	case!("object", Pass, object::object_delete),
+	case!("object", Pass, object::object_delete_not_exist),
+	case!("object", Pass, object::object_metadata),
+	case!("object", Pass, object::object_empty_body),
+	case!("object", Pass, object::object_ranged_read),
+	case!("object", Pass, object::object_ranged_read_suffix),
+	case!("object", Pass, object::object_ranged_read_invalid),
+	case!("object", Pass, object::object_delete_multiple),
+	case!("object", KnownFailure, object::object_delete_multiple_not_exist),
+	case!("object", Pass, object::object_if_none_match),
+	case!("list", Pass, list::bucket_list_empty),
+	case!("list", Pass, list::bucket_list_prefix),
+	case!("list", Pass, list::bucket_list_delimiter),
+	case!("list", Pass, list::bucket_list_maxkeys_pagination),
+	case!("list", Pass, list::bucket_listv2_continuation),
+	case!("list", Pass, list::bucket_listv2_start_after),
+	case!("multipart", Pass, multipart::multipart_upload),
+	case!("multipart", Pass, multipart::multipart_upload_abort),
+	case!("multipart", Pass, multipart::multipart_upload_small_part),
+	case!("multipart", Pass, multipart::multipart_list_parts),
+	case!("multipart", Pass, multipart::multipart_list_uploads),
+	case!("copy", Pass, copy::object_copy_same_bucket),
+	case!("copy", Pass, copy::object_copy_replace_metadata),
+	case!("copy", Pass, copy::object_copy_not_exist),
+	case!("versioning", KnownFailure, unsupported::versioning_enable),
+	case!("tagging", KnownFailure, unsupported::object_tagging),
+	case!("tagging", KnownFailure, unsupported::bucket_tagging),
+	case!("acl", KnownFailure, unsupported::object_acl),
+	case!("object-lock", KnownFailure, unsupported::object_lock),
+];
+
+// ---- helpers used by the cases ----
+
+/// Fail the case with a message if a condition does not hold
+macro_rules! check {
+	($cond:expr, $($arg:tt)+) => {
+		if !$cond {
+			return Err(format!($($arg)+));
+		}
+	};
+}
+pub(crate) use check;
+
+/// Fail the case if two values are different
+macro_rules! check_eq {
+	($left:expr, $right:expr) => {
+		if $left != $right {
+			return Err(format!(
+				"{} is {:?}, expected {:?}",
+				stringify!($left),
+				$left,
+				$right
+			));
+		}
+	};
+}
+pub(crate) use check_eq;
+
+/// Describe an error of a request that should have succeeded
+fn req_err<E: std::fmt::Debug>(what: &str) -> impl Fn(SdkError<E>) -> String + '_ {
+	move |e| format!("{} failed: {:?}", what, e)
+}
+
+/// Check that a request failed with the given S3 error code
+fn expect_error<T, E>(what: &str, res: Result<T, SdkError<E>>, code: &str) -> CaseResult
+where
+	E: ProvideErrorMetadata + std::fmt::Debug,
+{
+	match res {
+		Ok(_) => Err(format!("{} succeeded, expected error {}", what, code)),
+		Err(SdkError::ServiceError(e)) if e.err().code() == Some(code) => Ok(()),
+		Err(e) => Err(format!(
+			"{} failed with error {:?}, expected {}",
+			what, e, code
+		)),
+	}
+}
+
+async fn put(ctx: &Context, key: &str, body: &'static [u8]) -> CaseResult {
+	ctx.client
+		.put_object()
+		.bucket(&ctx.bucket)
+		.key(key)
+		.body(ByteStream::from_static(body))
+		.send()
+		.await
+		.map_err(req_err("PutObject"))?;
+	Ok(())
+}
+
+async fn get(ctx: &Context, key: &str) -> Result<Vec<u8>, String> {
+	let res = ctx
+		.client
+		.get_object()
+		.bucket(&ctx.bucket)
+		.key(key)
+		.send()
+		.await
+		.map_err(req_err("GetObject"))?;
+	read_body(res.body).await
+}
+
+async fn read_body(body: ByteStream) -> Result<Vec<u8>, String> {
+	let data = body
+		.collect()
+		.await
+		.map_err(|e| format!("unable to read body: {}", e))?;
+	Ok(data.into_bytes().to_vec())
+}
@@ -0,0 +1,179 @@
+use aws_sdk_s3::operation::complete_multipart_upload::CompleteMultipartUploadError;
+use aws_sdk_s3::types::{CompletedMultipartUpload, CompletedPart};
+
+use super::*;
+
+const SZ_5MB: usize = 5 * 1024 * 1024;
+
+async fn create_upload(ctx: &Context, key: &str) -> Result<String, String> {
+	ctx.client
+		.create_multipart_upload()
+		.bucket(&ctx.bucket)
+		.key(key)
+		.send()
+		.await
+		.map_err(req_err("CreateMultipartUpload"))?
+		.upload_id
+		.ok_or_else(|| "CreateMultipartUpload returned no upload ID".to_string())
+}
+
+async fn upload_part(
+	ctx: &Context,
+	key: &str,
+	upload_id: &str,
+	part_number: i32,
+	data: Vec<u8>,
+) -> Result<CompletedPart, String> {
+	let res = ctx
+		.client
+		.upload_part()
+		.bucket(&ctx.bucket)
+		.key(key)
+		.upload_id(upload_id)
+		.part_number(part_number)
+		.body(ByteStream::from(data))
+		.send()
+		.await
+		.map_err(req_err("UploadPart"))?;
+	Ok(CompletedPart::builder()
+		.set_e_tag(res.e_tag)
+		.part_number(part_number)
+		.build())
+}
+
+async fn complete_upload(
+	ctx: &Context,
+	key: &str,
+	upload_id: &str,
+	parts: Vec<CompletedPart>,
+) -> Result<(), SdkError<CompleteMultipartUploadError>> {
+	ctx.client
+		.complete_multipart_upload()
+		.bucket(&ctx.bucket)
+		.key(key)
+		.upload_id(upload_id)
+		.multipart_upload(
+			CompletedMultipartUpload::builder()
+				.set_parts(Some(parts))
+				.build(),
+		)
+		.send()
+		.await?;
+	Ok(())
+}
+
+pub async fn multipart_upload(ctx: &Context) -> CaseResult {
+	let upload_id = create_upload(ctx, "mpu").await?;
+	let p1 = upload_part(ctx, "mpu", &upload_id, 1, vec![b'a'; SZ_5MB]).await?;
+	let p2 = upload_part(ctx, "mpu", &upload_id, 2, vec![b'b'; 1024]).await?;
+	complete_upload(ctx, "mpu", &upload_id, vec![p1, p2])
+		.await
+		.map_err(req_err("CompleteMultipartUpload"))?;
+
+	let data = get(ctx, "mpu").await?;
+	check_eq!(data.len(), SZ_5MB + 1024);
+	check!(
+		data[..SZ_5MB].iter().all(|b| *b == b'a') && data[SZ_5MB..].iter().all(|b| *b == b'b'),
+		"object data does not match uploaded parts"
+	);
+
+	let etag = ctx
+		.client
+		.head_object()
+		.bucket(&ctx.bucket)
+		.key("mpu")
+		.send()
+		.await
+		.map_err(req_err("HeadObject"))?
+		.e_tag
+		.unwrap_or_default();
+	check!(
+		etag.trim_matches('"').ends_with("-2"),
+		"ETag {} of a multipart object does not end with the number of parts",
+		etag
+	);
+	Ok(())
+}
+
+pub async fn multipart_upload_abort(ctx: &Context) -> CaseResult {
+	let upload_id = create_upload(ctx, "mpu").await?;
+	upload_part(ctx, "mpu", &upload_id, 1, vec![b'a'; 1024]).await?;
+	ctx.client
+		.abort_multipart_upload()
+		.bucket(&ctx.bucket)
+		.key("mpu")
+		.upload_id(&upload_id)
+		.send()
+		.await
+		.map_err(req_err("AbortMultipartUpload"))?;
+
+	let res = ctx
+		.client
+		.list_parts()
+		.bucket(&ctx.bucket)
+		.key("mpu")
+		.upload_id(&upload_id)
+		.send()
+		.await;
+	expect_error("ListParts", res, "NoSuchUpload")
+}
+
+pub async fn multipart_upload_small_part(ctx: &Context) -> CaseResult {
+	let upload_id = create_upload(ctx, "mpu").await?;
+	let p1 = upload_part(ctx, "mpu", &upload_id, 1, vec![b'a'; 1024]).await?;
+	let p2 = upload_part(ctx, "mpu", &upload_id, 2, vec![b'b'; 1024]).await?;
+	let res = complete_upload(ctx, "mpu", &upload_id, vec![p1, p2]).await;
+	expect_error("CompleteMultipartUpload", res, "EntityTooSmall")
+}
+
+pub async fn multipart_list_parts(ctx: &Context) -> CaseResult {
+	let upload_id = create_upload(ctx, "mpu").await?;
+	for i in 1..=3 {
+		upload_part(ctx, "mpu", &upload_id, i, vec![b'a'; 1024]).await?;
+	}
+	let res = ctx
+		.client
+		.list_parts()
+		.bucket(&ctx.bucket)
+		.key("mpu")
+		.upload_id(&upload_id)
+		.send()
+		.await
+		.map_err(req_err("ListParts"))?;
+	let parts = res
+		.parts()
+		.unwrap_or_default()
+		.iter()
+		.map(|p| (p.part_number(), p.size()))
+		.collect::<Vec<_>>();
+	check_eq!(parts, vec![(1, 1024), (2, 1024), (3, 1024)]);
+	Ok(())
+}
+
+pub async fn multipart_list_uploads(ctx: &Context) -> CaseResult {
+	let upload_a = create_upload(ctx, "a").await?;
+	let upload_b = create_upload(ctx, "b").await?;
+	let res = ctx
+		.client
+		.list_multipart_uploads()
+		.bucket(&ctx.bucket)
+		.send()
+		.await
+		.map_err(req_err("ListMultipartUploads"))?;
+	let uploads = res
+		.uploads()
+		.unwrap_or_default()
+		.iter()
+		.map(|u| {
+			(
+				u.key().unwrap_or_default(),
+				u.upload_id().unwrap_or_default(),
+			)
+		})
+		.collect::<Vec<_>>();
+	check_eq!(
+		uploads,
+		vec![("a", upload_a.as_str()), ("b", upload_b.as_str())]
+	);
+	Ok(())
+}
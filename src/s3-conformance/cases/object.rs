@@ -0,0 +1,242 @@
+use aws_sdk_s3::types::{Delete, ObjectIdentifier};
+
+use super::*;
+
+pub async fn object_write_read(ctx: &Context) -> CaseResult {
+	put(ctx, "foo", b"Hello world!").await?;
+	check_eq!(get(ctx, "foo").await?, b"Hello world!".to_vec());
+	Ok(())
+}
+
+pub async fn object_write_overwrite(ctx: &Context) -> CaseResult {
+	put(ctx, "foo", b"first").await?;
+	put(ctx, "foo", b"second").await?;
+	check_eq!(get(ctx, "foo").await?, b"second".to_vec());
+	Ok(())
+}
+
+pub async fn object_head(ctx: &Context) -> CaseResult {
+	put(ctx, "foo", b"Hello world!").await?;
+	let res = ctx
+		.client
+		.head_object()
+		.bucket(&ctx.bucket)
+		.key("foo")
+		.send()
+		.await
+		.map_err(req_err("HeadObject"))?;
+	check_eq!(res.content_length(), 12);
+	check!(res.e_tag().is_some(), "HeadObject returned no ETag");
+	check!(
+		res.last_modified().is_some(),
+		"HeadObject returned no Last-Modified"
+	);
+	Ok(())
+}
+
+pub async fn object_read_not_exist(ctx: &Context) -> CaseResult {
+	let res = ctx
+		.client
+		.get_object()
+		.bucket(&ctx.bucket)
+		.key("not-exist")
+		.send()
+		.await;
+	expect_error("GetObject", res, "NoSuchKey")
+}
+
+pub async fn object_delete(ctx: &Context) -> CaseResult {
+	put(ctx, "foo", b"bar").await?;
+	ctx.client
+		.delete_object()
+		.bucket(&ctx.bucket)
+		.key("foo")
+		.send()
+		.await
+		.map_err(req_err("DeleteObject"))?;
+
+	let res = ctx
+		.client
+		.get_object()
+		.bucket(&ctx.bucket)
+		.key("foo")
+		.send()
+		.await;
+	expect_error("GetObject", res, "NoSuchKey")
+}
+
+pub async fn object_delete_not_exist(ctx: &Context) -> CaseResult {
+	// Deleting a missing object is not an error
+	ctx.client
+		.delete_object()
+		.bucket(&ctx.bucket)
+		.key("not-exist")
+		.send()
+		.await
+		.map_err(req_err("DeleteObject"))?;
+	Ok(())
+}
+
+pub async fn object_metadata(ctx: &Context) -> CaseResult {
+	ctx.client
+		.put_object()
+		.bucket(&ctx.bucket)
+		.key("foo")
+		.content_type("text/plain")
+		.cache_control("no-cache")
+		.metadata("meta1", "mymeta")
+		.body(ByteStream::from_static(b"bar"))
+		.send()
+		.await
+		.map_err(req_err("PutObject"))?;
+
+	let res = ctx
+		.client
+		.get_object()
+		.bucket(&ctx.bucket)
+		.key("foo")
+		.send()
+		.await
+		.map_err(req_err("GetObject"))?;
+	check_eq!(res.content_type(), Some("text/plain"));
+	check_eq!(res.cache_control(), Some("no-cache"));
+	check_eq!(
+		res.metadata()
+			.and_then(|m| m.get("meta1"))
+			.map(String::as_str),
+		Some("mymeta")
+	);
+	Ok(())
+}
+
+pub async fn object_empty_body(ctx: &Context) -> CaseResult {
+	put(ctx, "empty", b"").await?;
+	check_eq!(get(ctx, "empty").await?, Vec::<u8>::new());
+	Ok(())
+}
+
+async fn get_range(ctx: &Context, key: &str, range: &str) -> Result<Vec<u8>, String> {
+	let res = ctx
+		.client
+		.get_object()
+		.bucket(&ctx.bucket)
+		.key(key)
+		.range(range)
+		.send()
+		.await
+		.map_err(req_err("GetObject"))?;
+	read_body(res.body).await
+}
+
+pub async fn object_ranged_read(ctx: &Context) -> CaseResult {
+	put(ctx, "foo", b"0123456789").await?;
+	check_eq!(get_range(ctx, "foo", "bytes=2-5").await?, b"2345".to_vec());
+	check_eq!(get_range(ctx, "foo", "bytes=7-").await?, b"789".to_vec());
+	Ok(())
+}
+
+pub async fn object_ranged_read_suffix(ctx: &Context) -> CaseResult {
+	put(ctx, "foo", b"0123456789").await?;
+	check_eq!(get_range(ctx, "foo", "bytes=-3").await?, b"789".to_vec());
+	Ok(())
+}
+
+pub async fn object_ranged_read_invalid(ctx: &Context) -> CaseResult {
+	put(ctx, "foo", b"0123456789").await?;
+	let res = ctx
+		.client
+		.get_object()
+		.bucket(&ctx.bucket)
+		.key("foo")
+		.range("bytes=40-50")
+		.send()
+		.await;
+	expect_error("GetObject", res, "InvalidRange")
+}
+
+pub async fn object_delete_multiple(ctx: &Context) -> CaseResult {
+	for key in ["a", "b", "c"] {
+		put(ctx, key, b"x").await?;
+	}
+
+	let objects = ["a", "b"]
+		.iter()
+		.map(|k| ObjectIdentifier::builder().key(*k).build())
+		.collect::<Vec<_>>();
+	let res = ctx
+		.client
+		.delete_objects()
+		.bucket(&ctx.bucket)
+		.delete(Delete::builder().set_objects(Some(objects)).build())
+		.send()
+		.await
+		.map_err(req_err("DeleteObjects"))?;
+	check_eq!(res.deleted().map(|d| d.len()), Some(2));
+
+	let res = ctx
+		.client
+		.list_objects_v2()
+		.bucket(&ctx.bucket)
+		.send()
+		.await
+		.map_err(req_err("ListObjectsV2"))?;
+	let keys = res
+		.contents()
+		.unwrap_or_default()
+		.iter()
+		.filter_map(|o| o.key())
+		.collect::<Vec<_>>();
+	check_eq!(keys, vec!["c"]);
+	Ok(())
+}
+
+/// Keys that do not exist are reported as deleted by S3, whereas Garage
+/// reports a NoSuchKey error for them
+pub async fn object_delete_multiple_not_exist(ctx: &Context) -> CaseResult {
+	put(ctx, "a", b"x").await?;
+
+	let objects = ["a", "not-exist"]
+		.iter()
+		.map(|k| ObjectIdentifier::builder().key(*k).build())
+		.collect::<Vec<_>>();
+	let res = ctx
+		.client
+		.delete_objects()
+		.bucket(&ctx.bucket)
+		.delete(Delete::builder().set_objects(Some(objects)).build())
+		.send()
+		.await
+		.map_err(req_err("DeleteObjects"))?;
+	check_eq!(res.deleted().map(|d| d.len()), Some(2));
+	check_eq!(res.errors().map(|e| e.len()).unwrap_or(0), 0);
+	Ok(())
+}
+
+pub async fn object_if_none_match(ctx: &Context) -> CaseResult {
+	put(ctx, "foo", b"bar").await?;
+	let etag = ctx
+		.client
+		.head_object()
+		.bucket(&ctx.bucket)
+		.key("foo")
+		.send()
+		.await
+		.map_err(req_err("HeadObject"))?
+		.e_tag
+		.ok_or("HeadObject returned no ETag")?;
+
+	let res = ctx
+		.client
+		.get_object()
+		.bucket(&ctx.bucket)
+		.key("foo")
+		.if_none_match(etag)
+		.send()
+		.await;
+	// 304 Not Modified has no body, hence no error code
+	check!(
+		res.is_err(),
+		"GetObject with matching If-None-Match succeeded"
+	);
+	Ok(())
+}
@@ -0,0 +1,140 @@
+//! Cases of features that Garage does not implement yet
+
+use aws_sdk_s3::types::{
+	BucketVersioningStatus, ObjectCannedAcl, ObjectLockEnabled, Tag, Tagging,
+	VersioningConfiguration,
+};
+
+use super::*;
+
+pub async fn versioning_enable(ctx: &Context) -> CaseResult {
+	ctx.client
+		.put_bucket_versioning()
+		.bucket(&ctx.bucket)
+		.versioning_configuration(
+			VersioningConfiguration::builder()
+				.status(BucketVersioningStatus::Enabled)
+				.build(),
+		)
+		.send()
+		.await
+		.map_err(req_err("PutBucketVersioning"))?;
+
+	put(ctx, "foo", b"first").await?;
+	put(ctx, "foo", b"second").await?;
+	let res = ctx
+		.client
+		.list_object_versions()
+		.bucket(&ctx.bucket)
+		.send()
+		.await
+		.map_err(req_err("ListObjectVersions"))?;
+	check_eq!(res.versions().unwrap_or_default().len(), 2);
+	Ok(())
+}
+
+fn tagging() -> Tagging {
+	Tagging::builder()
+		.tag_set(Tag::builder().key("color").value("blue").build())
+		.build()
+}
+
+pub async fn object_tagging(ctx: &Context) -> CaseResult {
+	put(ctx, "foo", b"bar").await?;
+	ctx.client
+		.put_object_tagging()
+		.bucket(&ctx.bucket)
+		.key("foo")
+		.tagging(tagging())
+		.send()
+		.await
+		.map_err(req_err("PutObjectTagging"))?;
+
+	let res = ctx
+		.client
+		.get_object_tagging()
+		.bucket(&ctx.bucket)
+		.key("foo")
+		.send()
+		.await
+		.map_err(req_err("GetObjectTagging"))?;
+	check_eq!(
+		res.tag_set().unwrap_or_default(),
+		tagging().tag_set().unwrap()
+	);
+	Ok(())
+}
+
+pub async fn bucket_tagging(ctx: &Context) -> CaseResult {
+	ctx.client
+		.put_bucket_tagging()
+		.bucket(&ctx.bucket)
+		.tagging(tagging())
+		.send()
+		.await
+		.map_err(req_err("PutBucketTagging"))?;
+
+	let res = ctx
+		.client
+		.get_bucket_tagging()
+		.bucket(&ctx.bucket)
+		.send()
+		.await
+		.map_err(req_err("GetBucketTagging"))?;
+	check_eq!(
+		res.tag_set().unwrap_or_default(),
+		tagging().tag_set().unwrap()
+	);
+	Ok(())
+}
+
+pub async fn object_acl(ctx: &Context) -> CaseResult {
+	put(ctx, "foo", b"bar").await?;
+	ctx.client
+		.put_object_acl()
+		.bucket(&ctx.bucket)
+		.key("foo")
+		.acl(ObjectCannedAcl::PublicRead)
+		.send()
+		.await
+		.map_err(req_err("PutObjectAcl"))?;
+	Ok(())
+}
+
+pub async fn object_lock(ctx: &Context) -> CaseResult {
+	let res = ctx
+		.client
+		.get_object_lock_configuration()
+		.bucket(&ctx.bucket)
+		.send()
+		.await;
+	// A bucket created without object lock has no configuration
+	expect_error(
+		"GetObjectLockConfiguration",
+		res,
+		"ObjectLockConfigurationNotFoundError",
+	)?;
+
+	let bucket = format!("{}-lock", ctx.bucket);
+	let res = ctx
+		.client
+		.create_bucket()
+		.bucket(&bucket)
+		.object_lock_enabled_for_bucket(true)
+		.send()
+		.await;
+	res.map_err(req_err("CreateBucket"))?;
+	let res = ctx
+		.client
+		.get_object_lock_configuration()
+		.bucket(&bucket)
+		.send()
+		.await
+		.map_err(req_err("GetObjectLockConfiguration"))?;
+	check_eq!(
+		res.object_lock_configuration()
+			.and_then(|c| c.object_lock_enabled()),
+		Some(&ObjectLockEnabled::Enabled)
+	);
+	Ok(())
+}
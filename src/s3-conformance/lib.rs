@@ -0,0 +1,164 @@
+//! S3 API conformance suite: a Rust reimplementation of a subset of the
+//! ceph/s3-tests suite, run with the AWS SDK against an in-process Garage
+//! node (`garage::testing::TestCluster`).
+//!
+//! Each case belongs to a feature (e.g. `multipart`, `versioning`) and is
+//! either expected to pass, or a known failure for features Garage does not
+//! implement yet. The suite produces a machine-readable report, so that
+//! work on a feature can be checked by looking at its cases going from
+//! known failures to passing.
+
+use std::time::Instant;
+
+use aws_sdk_s3::config::{Credentials, Region};
+use aws_sdk_s3::Client;
+use serde::Serialize;
+
+use garage::testing::TestCluster;
+
+mod cases;
+
+pub use cases::CASES;
+
+/// Result of a case that fails: a description of what went wrong
+pub type CaseResult = Result<(), String>;
+
+/// Whether a case is expected to pass with the current version of Garage
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Expect {
+	Pass,
+	/// The case tests a feature that Garage does not implement (yet)
+	KnownFailure,
+}
+
+/// A conformance test case
+pub struct Case {
+	pub name: &'static str,
+	pub feature: &'static str,
+	pub expect: Expect,
+	pub(crate) run: for<'a> fn(&'a Context) -> futures::future::BoxFuture<'a, CaseResult>,
+}
+
+/// What cases are given to run their requests
+pub struct Context {
+	pub client: Client,
+	/// Name of a bucket that is created empty for each case
+	pub bucket: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+	Pass,
+	Fail,
+}
+
+/// Outcome of a case in the report
+#[derive(Debug, Clone, Serialize)]
+pub struct CaseReport {
+	pub name: &'static str,
+	pub feature: &'static str,
+	pub expect: Expect,
+	pub status: Status,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub message: Option<String>,
+	pub duration_msec: u64,
+}
+
+impl CaseReport {
+	/// A case expected to pass that fails
+	pub fn is_regression(&self) -> bool {
+		self.expect == Expect::Pass && self.status == Status::Fail
+	}
+
+	/// A case expected to fail that passes: the feature may now be
+	/// implemented, and the case should be marked as expected to pass
+	pub fn is_unexpected_pass(&self) -> bool {
+		self.expect == Expect::KnownFailure && self.status == Status::Pass
+	}
+}
+
+/// Report of a run of the conformance suite
+#[derive(Debug, Clone, Serialize)]
+pub struct Report {
+	pub total: usize,
+	pub passed: usize,
+	pub failed: usize,
+	pub regressions: usize,
+	pub unexpected_passes: usize,
+	pub cases: Vec<CaseReport>,
+}
+
+impl Report {
+	fn new(cases: Vec<CaseReport>) -> Self {
+		Self {
+			total: cases.len(),
+			passed: cases.iter().filter(|c| c.status == Status::Pass).count(),
+			failed: cases.iter().filter(|c| c.status == Status::Fail).count(),
+			regressions: cases.iter().filter(|c| c.is_regression()).count(),
+			unexpected_passes: cases.iter().filter(|c| c.is_unexpected_pass()).count(),
+			cases,
+		}
+	}
+}
+
+/// Build an S3 client for the default key of a test cluster
+pub fn client(cluster: &TestCluster) -> Client {
+	let credentials = Credentials::new(
+		&cluster.key.access_key_id,
+		&cluster.key.secret_access_key,
+		None,
+		None,
+		"garage-s3-conformance",
+	);
+	let config = aws_sdk_s3::Config::builder()
+		.endpoint_url(&cluster.s3_endpoint)
+		.region(Region::new(cluster.region.clone()))
+		.credentials_provider(credentials)
+		.force_path_style(true)
+		.build();
+	Client::from_conf(config)
+}
+
+/// Run the cases whose name or feature contains `filter` (all cases if
+/// it is `None`) against a test cluster, each one in a new bucket
+pub async fn run_suite(cluster: &TestCluster, filter: Option<&str>) -> Report {
+	let client = client(cluster);
+	let mut reports = vec![];
+
+	for (i, case) in CASES.iter().enumerate() {
+		if let Some(f) = filter {
+			if !case.name.contains(f) && !case.feature.contains(f) {
+				continue;
+			}
+		}
+
+		let start = Instant::now();
+		let bucket = format!("conformance-{}", i);
+		let result = match client.create_bucket().bucket(&bucket).send().await {
+			Ok(_) => {
+				let ctx = Context {
+					client: client.clone(),
+					bucket,
+				};
+				(case.run)(&ctx).await
+			}
+			Err(e) => Err(format!("unable to create bucket {}: {}", bucket, e)),
+		};
+
+		reports.push(CaseReport {
+			name: case.name,
+			feature: case.feature,
+			expect: case.expect,
+			status: match result {
+				Ok(()) => Status::Pass,
+				Err(_) => Status::Fail,
+			},
+			message: result.err(),
+			duration_msec: start.elapsed().as_millis() as u64,
+		});
+	}
+
+	Report::new(reports)
+}
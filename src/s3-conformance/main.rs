@@ -0,0 +1,60 @@
+//! Run the S3 API conformance suite against an in-process Garage node,
+//! and print a JSON report of the results
+
+use std::path::PathBuf;
+
+use structopt::StructOpt;
+
+use garage::testing::TestCluster;
+use garage_s3_conformance::run_suite;
+
+#[derive(StructOpt, Debug)]
+#[structopt(name = "garage-s3-conformance")]
+struct Opt {
+	/// Only run the cases whose name or feature contains this string
+	#[structopt(short = "f", long = "filter")]
+	filter: Option<String>,
+
+	/// Write the report to this file instead of the standard output
+	#[structopt(short = "o", long = "output")]
+	output: Option<PathBuf>,
+}
+
+#[tokio::main]
+async fn main() {
+	let opt = Opt::from_args();
+
+	let cluster = TestCluster::start().await.expect("Unable to start Garage");
+	let report = run_suite(&cluster, opt.filter.as_deref()).await;
+	if let Err(e) = cluster.shutdown().await {
+		eprintln!("Error while stopping Garage: {}", e);
+	}
+
+	for case in report.cases.iter() {
+		let status = match (case.is_regression(), case.is_unexpected_pass()) {
+			(true, _) => "REGRESSION",
+			(_, true) => "UNEXPECTED PASS",
+			_ => "ok",
+		};
+		eprintln!("{:<12} {:<36} {}", case.feature, case.name, status);
+		if let Some(msg) = &case.message {
+			if case.is_regression() {
+				eprintln!("    {}", msg);
+			}
+		}
+	}
+	eprintln!(
+		"{} cases: {} passed, {} failed ({} regressions, {} unexpected passes)",
+		report.total, report.passed, report.failed, report.regressions, report.unexpected_passes
+	);
+
+	let json = serde_json::to_string_pretty(&report).unwrap();
+	match opt.output {
+		Some(path) => std::fs::write(path, json).expect("Unable to write report"),
+		None => println!("{}", json),
+	}
+
+	if report.regressions > 0 {
+		std::process::exit(1);
+	}
+}
@@ -0,0 +1,18 @@
+use garage::testing::TestCluster;
+use garage_s3_conformance::run_suite;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_s3_conformance() {
+	let cluster = TestCluster::start().await.unwrap();
+	let report = run_suite(&cluster, None).await;
+	cluster.shutdown().await.unwrap();
+
+	let regressions = report
+		.cases
+		.iter()
+		.filter(|c| c.is_regression())
+		.map(|c| format!("{}: {}", c.name, c.message.as_deref().unwrap_or("")))
+		.collect::<Vec<_>>();
+	assert!(regressions.is_empty(), "{:#?}", regressions);
+	assert_eq!(report.unexpected_passes, 0, "{:#?}", report);
+}
@@ -51,16 +51,14 @@ impl Rpc for GcRpc {
 
 impl<F: TableSchema, R: TableReplication> TableGc<F, R> {
 	pub(crate) fn new(system: Arc<System>, data: Arc<TableData<F, R>>) -> Arc<Self> {
-		let endpoint = system
-			.netapp
-			.endpoint(format!("garage_table/gc.rs/Rpc:{}", F::TABLE_NAME));
+		let endpoint = system.endpoint(format!("garage_table/gc.rs/Rpc:{}", F::TABLE_NAME));
 
 		let gc = Arc::new(Self {
 			system,
 			data,
 			endpoint,
 		});
-		gc.endpoint.set_handler(gc.clone());
+		gc.system.set_handler(&gc.endpoint, gc.clone());
 
 		gc
 	}
@@ -311,6 +309,10 @@ impl<F: TableSchema, R: TableReplication> Worker for GcWorker<F, R> {
 		format!("{} GC", F::TABLE_NAME)
 	}
 
+	fn is_maintenance(&self) -> bool {
+		true
+	}
+
 	fn status(&self) -> WorkerStatus {
 		WorkerStatus {
 			queue_length: Some(self.gc.data.gc_todo_len().unwrap_or(0) as u64),
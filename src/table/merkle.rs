@@ -308,6 +308,10 @@ impl<F: TableSchema, R: TableReplication> Worker for MerkleWorker<F, R> {
 		format!("{} Merkle", F::TABLE_NAME)
 	}
 
+	fn is_maintenance(&self) -> bool {
+		true
+	}
+
 	fn status(&self) -> WorkerStatus {
 		WorkerStatus {
 			queue_length: Some(self.0.todo_len().unwrap_or(0) as u64),
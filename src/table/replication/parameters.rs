@@ -1,4 +1,7 @@
+use serde::{Deserialize, Serialize};
+
 use garage_rpc::ring::*;
+use garage_util::crdt::AutoCrdt;
 use garage_util::data::*;
 
 /// Trait to describe how a table shall be replicated
@@ -23,3 +26,136 @@ pub trait TableReplication: Send + Sync + 'static {
 	/// List of existing partitions
 	fn partitions(&self) -> Vec<(Partition, Hash)>;
 }
+
+/// Quorum policy that can be requested for individual reads and writes,
+/// allowing to trade consistency for availability and latency
+/// (or the other way around)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum QuorumPolicy {
+	/// Use the quorums defined by the replication mode of the cluster
+	#[default]
+	Quorum,
+	/// Wait for all nodes storing the data to answer
+	All,
+	/// Wait for a single node to answer
+	One,
+}
+
+impl AutoCrdt for QuorumPolicy {
+	const WARN_IF_DIFFERENT: bool = true;
+}
+
+impl QuorumPolicy {
+	pub fn parse(v: &str) -> Option<Self> {
+		match v.to_lowercase().as_str() {
+			"quorum" | "default" => Some(Self::Quorum),
+			"all" => Some(Self::All),
+			"one" => Some(Self::One),
+			_ => None,
+		}
+	}
+
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			Self::Quorum => "quorum",
+			Self::All => "all",
+			Self::One => "one",
+		}
+	}
+
+	/// Number of responses needed for a read sent to `n_nodes` nodes
+	pub fn read_quorum<R: TableReplication + ?Sized>(
+		&self,
+		replication: &R,
+		n_nodes: usize,
+	) -> usize {
+		match self {
+			Self::Quorum => replication.read_quorum(),
+			Self::All => n_nodes,
+			Self::One => 1,
+		}
+	}
+
+	/// Number of responses needed for a write sent to `n_nodes` nodes
+	pub fn write_quorum<R: TableReplication + ?Sized>(
+		&self,
+		replication: &R,
+		n_nodes: usize,
+	) -> usize {
+		match self {
+			Self::Quorum => replication.write_quorum(),
+			Self::All => n_nodes,
+			Self::One => 1,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use garage_rpc::replication_mode::ReplicationMode;
+
+	/// Replication that only has the quorums of a replication mode
+	struct TestReplication(ReplicationMode);
+
+	impl TableReplication for TestReplication {
+		fn read_nodes(&self, _hash: &Hash) -> Vec<Uuid> {
+			vec![]
+		}
+		fn read_quorum(&self) -> usize {
+			self.0.read_quorum()
+		}
+		fn write_nodes(&self, _hash: &Hash) -> Vec<Uuid> {
+			vec![]
+		}
+		fn write_quorum(&self) -> usize {
+			self.0.write_quorum()
+		}
+		fn max_write_errors(&self) -> usize {
+			self.0.replication_factor() - self.0.write_quorum()
+		}
+		fn partition_of(&self, _hash: &Hash) -> Partition {
+			0
+		}
+		fn partitions(&self) -> Vec<(Partition, Hash)> {
+			vec![]
+		}
+	}
+
+	#[test]
+	fn test_quorum_policy() {
+		// (replication mode, read quorum, write quorum) with QuorumPolicy::Quorum
+		let cases = [
+			("1", 1, 1),
+			("2", 1, 2),
+			("2-dangerous", 1, 1),
+			("3", 2, 2),
+			("3-degraded", 1, 2),
+			("3-dangerous", 1, 1),
+		];
+		for (mode, read_quorum, write_quorum) in cases {
+			let mode = ReplicationMode::parse(mode).unwrap();
+			let n = mode.replication_factor();
+			let replication = TestReplication(mode);
+
+			let quorum = QuorumPolicy::Quorum;
+			assert_eq!(quorum.read_quorum(&replication, n), read_quorum);
+			assert_eq!(quorum.write_quorum(&replication, n), write_quorum);
+
+			assert_eq!(QuorumPolicy::All.read_quorum(&replication, n), n);
+			assert_eq!(QuorumPolicy::All.write_quorum(&replication, n), n);
+
+			assert_eq!(QuorumPolicy::One.read_quorum(&replication, n), 1);
+			assert_eq!(QuorumPolicy::One.write_quorum(&replication, n), 1);
+		}
+	}
+
+	#[test]
+	fn test_quorum_policy_parse() {
+		for policy in [QuorumPolicy::Quorum, QuorumPolicy::All, QuorumPolicy::One] {
+			assert_eq!(QuorumPolicy::parse(policy.as_str()), Some(policy));
+		}
+		assert_eq!(QuorumPolicy::parse("DEFAULT"), Some(QuorumPolicy::Quorum));
+		assert_eq!(QuorumPolicy::parse("two"), None);
+	}
+}
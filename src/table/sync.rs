@@ -68,9 +68,7 @@ impl<F: TableSchema, R: TableReplication> TableSyncer<F, R> {
 		data: Arc<TableData<F, R>>,
 		merkle: Arc<MerkleUpdater<F, R>>,
 	) -> Arc<Self> {
-		let endpoint = system
-			.netapp
-			.endpoint(format!("garage_table/sync.rs/Rpc:{}", F::TABLE_NAME));
+		let endpoint = system.endpoint(format!("garage_table/sync.rs/Rpc:{}", F::TABLE_NAME));
 
 		let syncer = Arc::new(Self {
 			system,
@@ -79,7 +77,7 @@ impl<F: TableSchema, R: TableReplication> TableSyncer<F, R> {
 			add_full_sync_tx: ArcSwapOption::new(None),
 			endpoint,
 		});
-		syncer.endpoint.set_handler(syncer.clone());
+		syncer.system.set_handler(&syncer.endpoint, syncer.clone());
 
 		syncer
 	}
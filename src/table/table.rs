@@ -69,9 +69,7 @@ impl<F: TableSchema, R: TableReplication> Table<F, R> {
 	// =============== PUBLIC INTERFACE FUNCTIONS (new, insert, get, etc) ===============
 
 	pub fn new(instance: F, replication: R, system: Arc<System>, db: &db::Db) -> Arc<Self> {
-		let endpoint = system
-			.netapp
-			.endpoint(format!("garage_table/table.rs/Rpc:{}", F::TABLE_NAME));
+		let endpoint = system.endpoint(format!("garage_table/table.rs/Rpc:{}", F::TABLE_NAME));
 
 		let data = TableData::new(system.clone(), instance, replication, db);
 
@@ -89,7 +87,7 @@ impl<F: TableSchema, R: TableReplication> Table<F, R> {
 			endpoint,
 		});
 
-		table.endpoint.set_handler(table.clone());
+		table.system.set_handler(&table.endpoint, table.clone());
 
 		table
 	}
@@ -102,10 +100,16 @@ impl<F: TableSchema, R: TableReplication> Table<F, R> {
 	}
 
 	pub async fn insert(&self, e: &F::E) -> Result<(), Error> {
+		self.insert_with_quorum(e, QuorumPolicy::Quorum).await
+	}
+
+	/// Insert an entry, waiting for a number of acknowledgements
+	/// given by the specified quorum policy
+	pub async fn insert_with_quorum(&self, e: &F::E, quorum: QuorumPolicy) -> Result<(), Error> {
 		let tracer = opentelemetry::global::tracer("garage_table");
 		let span = tracer.start(format!("{} insert", F::TABLE_NAME));
 
-		self.insert_internal(e)
+		self.insert_internal(e, quorum)
 			.bound_record_duration(&self.data.metrics.put_request_duration)
 			.with_context(Context::current_with_span(span))
 			.await?;
@@ -115,9 +119,10 @@ impl<F: TableSchema, R: TableReplication> Table<F, R> {
 		Ok(())
 	}
 
-	async fn insert_internal(&self, e: &F::E) -> Result<(), Error> {
+	async fn insert_internal(&self, e: &F::E, quorum: QuorumPolicy) -> Result<(), Error> {
 		let hash = e.partition_key().hash();
 		let who = self.data.replication.write_nodes(&hash);
+		let quorum = quorum.write_quorum(&self.data.replication, who.len());
 
 		let e_enc = Arc::new(ByteBuf::from(e.encode()?));
 		let rpc = TableRpc::<F>::Update(vec![e_enc]);
@@ -128,8 +133,7 @@ impl<F: TableSchema, R: TableReplication> Table<F, R> {
 				&self.endpoint,
 				&who[..],
 				rpc,
-				RequestStrategy::with_priority(PRIO_NORMAL)
-					.with_quorum(self.data.replication.write_quorum()),
+				RequestStrategy::with_priority(PRIO_NORMAL).with_quorum(quorum),
 			)
 			.await?;
 
@@ -210,12 +214,24 @@ impl<F: TableSchema, R: TableReplication> Table<F, R> {
 		self: &Arc<Self>,
 		partition_key: &F::P,
 		sort_key: &F::S,
+	) -> Result<Option<F::E>, Error> {
+		self.get_with_quorum(partition_key, sort_key, QuorumPolicy::Quorum)
+			.await
+	}
+
+	/// Read an entry, waiting for a number of responses
+	/// given by the specified quorum policy
+	pub async fn get_with_quorum(
+		self: &Arc<Self>,
+		partition_key: &F::P,
+		sort_key: &F::S,
+		quorum: QuorumPolicy,
 	) -> Result<Option<F::E>, Error> {
 		let tracer = opentelemetry::global::tracer("garage_table");
 		let span = tracer.start(format!("{} get", F::TABLE_NAME));
 
 		let res = self
-			.get_internal(partition_key, sort_key)
+			.get_internal(partition_key, sort_key, quorum)
 			.bound_record_duration(&self.data.metrics.get_request_duration)
 			.with_context(Context::current_with_span(span))
 			.await?;
@@ -229,9 +245,11 @@ impl<F: TableSchema, R: TableReplication> Table<F, R> {
 		self: &Arc<Self>,
 		partition_key: &F::P,
 		sort_key: &F::S,
+		quorum: QuorumPolicy,
 	) -> Result<Option<F::E>, Error> {
 		let hash = partition_key.hash();
 		let who = self.data.replication.read_nodes(&hash);
+		let quorum = quorum.read_quorum(&self.data.replication, who.len());
 
 		let rpc = TableRpc::<F>::ReadEntry(partition_key.clone(), sort_key.clone());
 		let resps = self
@@ -242,7 +260,7 @@ impl<F: TableSchema, R: TableReplication> Table<F, R> {
 				&who[..],
 				rpc,
 				RequestStrategy::with_priority(PRIO_NORMAL)
-					.with_quorum(self.data.replication.read_quorum())
+					.with_quorum(quorum)
 					.interrupt_after_quorum(true),
 			)
 			.await?;
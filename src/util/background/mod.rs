@@ -4,10 +4,18 @@ pub mod vars;
 pub mod worker;
 
 use std::collections::HashMap;
+use std::sync::atomic::AtomicUsize;
 use std::sync::Arc;
+use std::time::Duration;
 
 use serde::{Deserialize, Serialize};
-use tokio::sync::{mpsc, watch};
+use tokio::runtime::Runtime;
+use tokio::sync::{mpsc, watch, Semaphore};
+
+use crate::config::BackgroundConfig;
+use crate::error::Error;
+use crate::platform::lower_thread_priority;
+use crate::time::now_msec;
 
 use worker::WorkerProcessor;
 pub use worker::{Worker, WorkerState};
@@ -15,7 +23,21 @@ pub use worker::{Worker, WorkerState};
 /// Job runner for futures and async functions
 pub struct BackgroundRunner {
 	send_worker: mpsc::UnboundedSender<Box<dyn Worker>>,
+	send_maintenance_worker: mpsc::UnboundedSender<Box<dyn Worker>>,
 	worker_info: Arc<std::sync::Mutex<HashMap<usize, WorkerInfo>>>,
+	_maintenance_runtime: Option<MaintenanceRuntime>,
+}
+
+/// Dedicated runtime of the maintenance workers
+struct MaintenanceRuntime(Option<Runtime>);
+
+impl Drop for MaintenanceRuntime {
+	fn drop(&mut self) {
+		// A runtime cannot be dropped normally from an asynchronous context
+		if let Some(rt) = self.0.take() {
+			rt.shutdown_background();
+		}
+	}
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
@@ -37,26 +59,156 @@ pub struct WorkerStatus {
 	pub queue_length: Option<u64>,
 	pub persistent_errors: Option<u64>,
 	pub freeform: Vec<String>,
+	#[serde(default)]
+	pub counters: Option<WorkerCounters>,
+}
+
+/// Counters of the work done by a worker going through a given amount of
+/// work, from which the CLI shows its progress, rate and estimated time of
+/// completion
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
+pub struct WorkerCounters {
+	/// Time at which the worker started counting, in msec
+	pub started: u64,
+	/// Number of items processed since `started`
+	pub items: u64,
+	/// Total number of items, including those already processed, if known
+	pub items_total: Option<u64>,
+	/// Number of bytes processed since `started`
+	pub bytes: u64,
+	/// Total number of bytes, including those already processed, if known
+	pub bytes_total: Option<u64>,
+	/// Fraction of the work done, between 0 and 1, for workers that do not
+	/// know the total number of items they have to process
+	pub fraction: Option<f32>,
+	/// Value of `fraction` at `started`, e.g. when resuming from a checkpoint
+	pub fraction_start: f32,
+}
+
+impl WorkerCounters {
+	/// Start counting now
+	pub fn new() -> Self {
+		Self {
+			started: now_msec(),
+			..Default::default()
+		}
+	}
+
+	/// Fraction of the work done, between 0 and 1, if known
+	pub fn done_fraction(&self) -> Option<f32> {
+		let ratio = |done: u64, total: Option<u64>| {
+			total.map(|t| if t > 0 { done as f32 / t as f32 } else { 1. })
+		};
+		self.fraction
+			.or_else(|| ratio(self.bytes, self.bytes_total))
+			.or_else(|| ratio(self.items, self.items_total))
+			.map(|f| f.clamp(0., 1.))
+	}
+
+	/// Number of items and of bytes processed per second since `started`
+	pub fn rates(&self, now: u64) -> Option<(f64, f64)> {
+		let secs = now.saturating_sub(self.started) as f64 / 1000.;
+		if secs > 0. {
+			Some((self.items as f64 / secs, self.bytes as f64 / secs))
+		} else {
+			None
+		}
+	}
+
+	/// Estimated time remaining before the work is done, assuming it
+	/// continues at the same pace as since `started`
+	pub fn eta(&self, now: u64) -> Option<Duration> {
+		let done = self.done_fraction()?;
+		if done >= 1. {
+			return Some(Duration::ZERO);
+		}
+		let start = match self.fraction {
+			Some(_) => self.fraction_start,
+			None => 0.,
+		};
+		if done <= start {
+			return None;
+		}
+		let elapsed = now.saturating_sub(self.started) as f64;
+		let remaining = elapsed * (1. - done) as f64 / (done - start) as f64;
+		Some(Duration::from_millis(remaining as u64))
+	}
 }
 
 impl BackgroundRunner {
-	/// Create a new BackgroundRunner
-	pub fn new(stop_signal: watch::Receiver<bool>) -> (Arc<Self>, tokio::task::JoinHandle<()>) {
+	/// Create a new BackgroundRunner. Maintenance workers are run as configured
+	/// in `config`: on a dedicated runtime, and with limited concurrency.
+	pub fn new(
+		stop_signal: watch::Receiver<bool>,
+		config: &BackgroundConfig,
+	) -> Result<(Arc<Self>, tokio::task::JoinHandle<()>), Error> {
 		let (send_worker, worker_out) = mpsc::unbounded_channel::<Box<dyn Worker>>();
+		let (send_maintenance_worker, maintenance_worker_out) =
+			mpsc::unbounded_channel::<Box<dyn Worker>>();
 
 		let worker_info = Arc::new(std::sync::Mutex::new(HashMap::new()));
-		let mut worker_processor =
-			WorkerProcessor::new(worker_out, stop_signal, worker_info.clone());
+		let next_task_id = Arc::new(AtomicUsize::new(1));
+		let mut worker_processor = WorkerProcessor::new(
+			worker_out,
+			stop_signal.clone(),
+			worker_info.clone(),
+			next_task_id.clone(),
+			None,
+		);
+		let mut maintenance_processor = WorkerProcessor::new(
+			maintenance_worker_out,
+			stop_signal,
+			worker_info.clone(),
+			next_task_id,
+			config
+				.max_concurrent_maintenance
+				.map(|n| Arc::new(Semaphore::new(n))),
+		);
+
+		let maintenance_runtime = match config.maintenance_threads {
+			Some(threads) => {
+				let nice = config.maintenance_nice;
+				let io_idle = config.maintenance_io_idle;
+				let rt = tokio::runtime::Builder::new_multi_thread()
+					.worker_threads(threads)
+					.thread_name("garage-maintenance")
+					.on_thread_start(move || {
+						if let Err(e) = lower_thread_priority(nice, io_idle) {
+							warn!("Unable to lower priority of maintenance thread: {}", e);
+						}
+					})
+					.enable_all()
+					.build()?;
+				info!(
+					"Maintenance workers run on {} dedicated threads (nice: {}, idle IO: {})",
+					threads, nice, io_idle
+				);
+				Some(MaintenanceRuntime(Some(rt)))
+			}
+			None => None,
+		};
 
+		let maintenance_run = async move {
+			maintenance_processor.run().await;
+		};
+		let await_maintenance_done = match &maintenance_runtime {
+			Some(MaintenanceRuntime(Some(rt))) => rt.spawn(maintenance_run),
+			_ => tokio::spawn(maintenance_run),
+		};
 		let await_all_done = tokio::spawn(async move {
 			worker_processor.run().await;
+			if let Err(e) = await_maintenance_done.await {
+				error!("Maintenance workers did not exit properly: {}", e);
+			}
 		});
 
 		let bgrunner = Arc::new(Self {
 			send_worker,
+			send_maintenance_worker,
 			worker_info,
+			_maintenance_runtime: maintenance_runtime,
 		});
-		(bgrunner, await_all_done)
+		Ok((bgrunner, await_all_done))
 	}
 
 	pub fn get_worker_info(&self) -> HashMap<usize, WorkerInfo> {
@@ -67,9 +219,48 @@ impl BackgroundRunner {
 	where
 		W: Worker + 'static,
 	{
-		self.send_worker
-			.send(Box::new(worker))
-			.ok()
+		let chan = if worker.is_maintenance() {
+			&self.send_maintenance_worker
+		} else {
+			&self.send_worker
+		};
+		chan.send(Box::new(worker))
 			.expect("Could not put worker in queue");
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_worker_counters_eta() {
+		let c = WorkerCounters {
+			started: 1000,
+			items: 50,
+			items_total: Some(200),
+			bytes: 5000,
+			..Default::default()
+		};
+		assert_eq!(c.done_fraction(), Some(0.25));
+		assert_eq!(c.rates(11000), Some((5., 500.)));
+		assert_eq!(c.eta(11000), Some(Duration::from_secs(30)));
+
+		// resumed from a checkpoint at 50%, now at 75%
+		let c = WorkerCounters {
+			started: 0,
+			fraction: Some(0.75),
+			fraction_start: 0.5,
+			..Default::default()
+		};
+		assert_eq!(c.eta(10_000), Some(Duration::from_secs(10)));
+
+		let c = WorkerCounters {
+			started: 0,
+			items: 10,
+			..Default::default()
+		};
+		assert_eq!(c.done_fraction(), None);
+		assert_eq!(c.eta(10_000), None);
+	}
+}
@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -8,7 +9,7 @@ use futures::stream::FuturesUnordered;
 use futures::StreamExt;
 use serde::{Deserialize, Serialize};
 use tokio::select;
-use tokio::sync::{mpsc, watch};
+use tokio::sync::{mpsc, watch, Semaphore};
 
 use crate::background::{WorkerInfo, WorkerStatus};
 use crate::error::Error;
@@ -45,6 +46,13 @@ pub trait Worker: Send {
 		Default::default()
 	}
 
+	/// Whether this worker maintains the data of the node (merkle updaters,
+	/// GC, resync, scrub, repairs) rather than serving it, in which case it
+	/// is run with the priority configured in the `[background]` section
+	fn is_maintenance(&self) -> bool {
+		false
+	}
+
 	/// Work: do a basic unit of work, if one is available (otherwise, should return
 	/// WorkerState::Idle immediately).  We will do our best to not interrupt this future in the
 	/// middle of processing, it will only be interrupted at the last minute when Garage is trying
@@ -62,6 +70,10 @@ pub(crate) struct WorkerProcessor {
 	stop_signal: watch::Receiver<bool>,
 	worker_chan: mpsc::UnboundedReceiver<Box<dyn Worker>>,
 	worker_info: Arc<std::sync::Mutex<HashMap<usize, WorkerInfo>>>,
+	/// Shared with the other processors of the runner, so that task IDs are unique
+	next_task_id: Arc<AtomicUsize>,
+	/// Limit on the number of workers doing work at the same time
+	concurrency: Option<Arc<Semaphore>>,
 }
 
 impl WorkerProcessor {
@@ -69,17 +81,20 @@ impl WorkerProcessor {
 		worker_chan: mpsc::UnboundedReceiver<Box<dyn Worker>>,
 		stop_signal: watch::Receiver<bool>,
 		worker_info: Arc<std::sync::Mutex<HashMap<usize, WorkerInfo>>>,
+		next_task_id: Arc<AtomicUsize>,
+		concurrency: Option<Arc<Semaphore>>,
 	) -> Self {
 		Self {
 			stop_signal,
 			worker_chan,
 			worker_info,
+			next_task_id,
+			concurrency,
 		}
 	}
 
 	pub(crate) async fn run(&mut self) {
 		let mut workers = FuturesUnordered::new();
-		let mut next_task_id = 1;
 
 		while !*self.stop_signal.borrow() {
 			let await_next_worker = async {
@@ -92,12 +107,12 @@ impl WorkerProcessor {
 			select! {
 				new_worker_opt = self.worker_chan.recv() => {
 					if let Some(new_worker) = new_worker_opt {
-						let task_id = next_task_id;
-						next_task_id += 1;
+						let task_id = self.next_task_id.fetch_add(1, Ordering::Relaxed);
 						let stop_signal = self.stop_signal.clone();
 						let mut worker = WorkerHandler {
 								task_id,
 								stop_signal,
+								concurrency: self.concurrency.clone(),
 								worker: new_worker,
 								state: WorkerState::Busy,
 								errors: 0,
@@ -178,6 +193,7 @@ impl WorkerProcessor {
 struct WorkerHandler {
 	task_id: usize,
 	stop_signal: watch::Receiver<bool>,
+	concurrency: Option<Arc<Semaphore>>,
 	worker: Box<dyn Worker>,
 	state: WorkerState,
 	errors: usize,
@@ -188,28 +204,39 @@ struct WorkerHandler {
 impl WorkerHandler {
 	async fn step(&mut self) {
 		match self.state {
-			WorkerState::Busy => match self.worker.work(&mut self.stop_signal).await {
-				Ok(s) => {
-					self.state = s;
-					self.consecutive_errors = 0;
-				}
-				Err(e) => {
-					error!(
-						"Error in worker {} (TID {}): {}",
-						self.worker.name(),
-						self.task_id,
-						e
-					);
-					self.errors += 1;
-					self.consecutive_errors += 1;
-					self.last_error = Some((format!("{}", e), now_msec()));
-					// Sleep a bit so that error won't repeat immediately, exponential backoff
-					// strategy (min 1sec, max ~60sec)
-					self.state = WorkerState::Throttled(
-						(1.5f32).powf(std::cmp::min(10, self.consecutive_errors - 1) as f32),
-					);
+			WorkerState::Busy => {
+				// Wait for our turn if the number of workers doing work at
+				// the same time is limited
+				let _permit = match &self.concurrency {
+					Some(sem) => select! {
+						permit = sem.acquire() => Some(permit.expect("semaphore closed")),
+						_ = self.stop_signal.changed() => return,
+					},
+					None => None,
+				};
+				match self.worker.work(&mut self.stop_signal).await {
+					Ok(s) => {
+						self.state = s;
+						self.consecutive_errors = 0;
+					}
+					Err(e) => {
+						error!(
+							"Error in worker {} (TID {}): {}",
+							self.worker.name(),
+							self.task_id,
+							e
+						);
+						self.errors += 1;
+						self.consecutive_errors += 1;
+						self.last_error = Some((format!("{}", e), now_msec()));
+						// Sleep a bit so that error won't repeat immediately, exponential backoff
+						// strategy (min 1sec, max ~60sec)
+						self.state = WorkerState::Throttled(
+							(1.5f32).powf(std::cmp::min(10, self.consecutive_errors - 1) as f32),
+						);
+					}
 				}
-			},
+			}
 			WorkerState::Throttled(delay) => {
 				// Sleep for given delay and go back to busy state
 				select! {
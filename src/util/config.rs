@@ -54,11 +54,18 @@ pub struct Config {
 	pub rpc_bind_addr: SocketAddr,
 	/// Public IP address of this node
 	pub rpc_public_addr: Option<String>,
+	/// Do not accept inbound connections from other nodes, and have their
+	/// requests relayed through the nodes this node connects to
+	#[serde(default)]
+	pub rpc_outbound_only: bool,
 
 	/// Timeout for Netapp's ping messagess
 	pub rpc_ping_timeout_msec: Option<u64>,
 	/// Timeout for Netapp RPC calls
 	pub rpc_timeout_msec: Option<u64>,
+	/// Rate limits of the RPC traffic between the nodes of some zones
+	#[serde(default)]
+	pub rpc_link_limits: Vec<RpcLinkLimitConfig>,
 
 	// -- Bootstraping and discovery
 	/// Bootstrap peers RPC address
@@ -98,12 +105,49 @@ pub struct Config {
 	/// Configuration for K2V api
 	pub k2v_api: Option<K2VApiConfig>,
 
+	/// Configuration for the WebDAV gateway
+	pub webdav: Option<WebDavConfig>,
+
+	/// Configuration for the SFTP server
+	pub sftp: Option<SftpConfig>,
+
 	/// Configuration for serving files as normal web server
 	pub s3_web: Option<WebConfig>,
 
 	/// Configuration for the admin API endpoint
 	#[serde(default = "Default::default")]
 	pub admin: AdminConfig,
+
+	/// Maximum time to wait for requests being processed to complete
+	/// when shutting down, in seconds
+	#[serde(default = "default_shutdown_grace_period_secs")]
+	pub shutdown_grace_period_secs: u64,
+
+	/// Filter that selects which log messages are emitted, with the syntax
+	/// of the `RUST_LOG` environment variable, which it overrides if set
+	pub log_filter: Option<String>,
+
+	/// Configuration for the monitoring of the balance of data disk usage
+	/// between storage nodes
+	#[serde(default)]
+	pub rebalance: RebalanceConfig,
+
+	/// Configuration for the evaluation of the alarms defined on buckets
+	#[serde(default)]
+	pub bucket_alarms: BucketAlarmsConfig,
+
+	/// Configuration of the priority of the background workers that
+	/// maintain the data of the node
+	#[serde(default)]
+	pub background: BackgroundConfig,
+
+	/// Thresholds of disk usage above which the node stops accepting new
+	/// data blocks, while still serving reads and deletes
+	#[serde(default)]
+	pub disk_watermark: DiskWatermarkConfig,
+
+	/// Provisioning of access keys for the members of an LDAP group
+	pub ldap_sync: Option<LdapSyncConfig>,
 }
 
 /// Value for data_dir: either a single directory or a list of dirs with attributes
@@ -126,16 +170,70 @@ pub struct DataDir {
 	pub read_only: bool,
 }
 
+/// Rate limit of the RPC traffic between the nodes of two zones
+#[derive(Deserialize, Debug, Clone)]
+pub struct RpcLinkLimitConfig {
+	/// The two zones, which can be the same zone to limit the traffic
+	/// inside a zone
+	pub zones: [String; 2],
+	/// Maximum number of bytes per second that a node sends to the nodes
+	/// of the other zone, and receives from them, in each direction
+	#[serde(deserialize_with = "deserialize_capacity")]
+	pub max_rate: usize,
+}
+
 /// Configuration for S3 api
 #[derive(Deserialize, Debug, Clone)]
 pub struct S3ApiConfig {
 	/// Address and port to bind for api serving
 	pub api_bind_addr: Option<UnixOrTCPSocketAddress>,
+	/// Address and port to bind for an endpoint that only serves
+	/// unauthenticated read requests on buckets marked as public
+	pub anonymous_bind_addr: Option<UnixOrTCPSocketAddress>,
 	/// S3 region to use
 	pub s3_region: String,
 	/// Suffix to remove from domain name to find bucket. If None,
 	/// vhost-style S3 request are disabled
 	pub root_domain: Option<String>,
+	/// Additional suffixes to remove from domain name to find bucket,
+	/// in which `*` matches any single domain label
+	#[serde(default)]
+	pub root_domains: Vec<String>,
+	/// Use the X-Forwarded-Host header set by a reverse proxy instead of
+	/// the Host header to find bucket and check request signatures
+	#[serde(default)]
+	pub trust_forwarded_host: bool,
+	/// Answer AccessDenied instead of NoSuchBucket to requests for buckets
+	/// that do not exist, so that bucket names in use cannot be probed
+	#[serde(default)]
+	pub hide_bucket_existence: bool,
+	/// Minimum size of all parts but the last one of a multipart upload,
+	/// checked when the upload is completed (0 to disable)
+	#[serde(
+		deserialize_with = "deserialize_capacity",
+		default = "default_min_part_size"
+	)]
+	pub min_part_size: usize,
+	/// Maximum number of data blocks of a single upload request that are
+	/// written to storage nodes at the same time
+	#[serde(default = "default_put_blocks_max_parallel")]
+	pub put_blocks_max_parallel: usize,
+	/// Also serve the S3 API over HTTPS
+	pub tls: Option<TlsConfig>,
+	/// Limits on the requests processed by the S3 API server
+	#[serde(default)]
+	pub limits: ApiLimitsConfig,
+}
+
+impl S3ApiConfig {
+	/// All root domains used for vhost-style S3 requests
+	pub fn all_root_domains(&self) -> Vec<&str> {
+		self.root_domain
+			.iter()
+			.chain(self.root_domains.iter())
+			.map(String::as_str)
+			.collect()
+	}
 }
 
 /// Configuration for K2V api
@@ -143,6 +241,62 @@ pub struct S3ApiConfig {
 pub struct K2VApiConfig {
 	/// Address and port to bind for api serving
 	pub api_bind_addr: UnixOrTCPSocketAddress,
+	/// Limits on the requests processed by the K2V API server
+	#[serde(default)]
+	pub limits: ApiLimitsConfig,
+}
+
+/// Configuration for the WebDAV gateway, which exposes the buckets of an
+/// access key as collections of files
+#[derive(Deserialize, Debug, Clone)]
+pub struct WebDavConfig {
+	/// Address and port to bind for the WebDAV server
+	pub api_bind_addr: UnixOrTCPSocketAddress,
+	/// Limits on the requests processed by the WebDAV server
+	#[serde(default)]
+	pub limits: ApiLimitsConfig,
+}
+
+/// Configuration for the SFTP server, whose users are mapped to access keys
+/// and have a bucket, or a prefix in a bucket, as home directory
+#[derive(Deserialize, Debug, Clone)]
+pub struct SftpConfig {
+	/// Address and port to bind for the SFTP server
+	pub bind_addr: SocketAddr,
+	/// Private key of the SSH server, in OpenSSH format
+	pub host_key_file: PathBuf,
+	/// Users allowed to log in, by name
+	#[serde(default)]
+	pub users: std::collections::HashMap<String, SftpUserConfig>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SftpUserConfig {
+	/// ID of the access key with which the requests of the user are made,
+	/// its secret key being the password of the user
+	pub access_key_id: String,
+	/// Home directory of the user, as the name of a bucket optionally
+	/// followed by a prefix (e.g. `my-bucket/uploads/`)
+	pub home: String,
+	/// Public keys with which the user can log in, in the format of
+	/// OpenSSH `authorized_keys` files
+	#[serde(default)]
+	pub authorized_keys: Vec<String>,
+}
+
+/// Limits on the requests processed by an API server, to protect it
+/// from running out of memory under bursts of load
+#[derive(Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct ApiLimitsConfig {
+	/// Maximum size of request bodies, in bytes
+	#[serde(deserialize_with = "deserialize_opt_capacity", default)]
+	pub max_request_body_size: Option<usize>,
+	/// Maximum number of requests that write data (e.g. PutObject,
+	/// UploadPart) processed concurrently
+	pub max_concurrent_puts: Option<usize>,
+	/// Maximum number of requests that list objects (e.g. ListObjects)
+	/// processed concurrently
+	pub max_concurrent_list_scans: Option<usize>,
 }
 
 /// Configuration for serving files as normal web server
@@ -152,6 +306,54 @@ pub struct WebConfig {
 	pub bind_addr: UnixOrTCPSocketAddress,
 	/// Suffix to remove from domain name to find bucket
 	pub root_domain: String,
+	/// Compress responses with gzip or brotli for clients that support it
+	#[serde(default)]
+	pub compression: bool,
+	/// Serve websites over HTTPS with certificates obtained using ACME
+	pub acme: Option<AcmeConfig>,
+	/// Serve websites over HTTPS with a static certificate
+	pub tls: Option<TlsConfig>,
+}
+
+/// Configuration for serving an endpoint over HTTPS
+#[derive(Deserialize, Debug, Clone)]
+pub struct TlsConfig {
+	/// Address and port to bind for HTTPS serving
+	pub bind_addr: SocketAddr,
+	/// Path to the PEM-encoded certificate chain
+	pub cert_file: PathBuf,
+	/// Path to the PEM-encoded private key
+	pub key_file: PathBuf,
+	/// Also serve HTTP/3 over QUIC on the same port
+	#[serde(default)]
+	pub http3: bool,
+	/// Path to PEM-encoded CA certificates. When set, clients must present
+	/// a certificate signed by one of these CAs
+	#[serde(default)]
+	pub client_ca_file: Option<PathBuf>,
+	/// Access keys used for the requests that carry no signature, by common
+	/// name of the client certificate (S3 API only)
+	#[serde(default)]
+	pub client_cert_keys: std::collections::HashMap<String, String>,
+}
+
+/// Configuration for obtaining TLS certificates for websites using ACME
+#[derive(Deserialize, Debug, Clone)]
+pub struct AcmeConfig {
+	/// Address and port to bind for HTTPS web serving
+	pub tls_bind_addr: SocketAddr,
+	/// Contact email address given to the ACME server
+	pub contact_email: String,
+	/// Whether the terms of service of the ACME server are agreed to
+	#[serde(default)]
+	pub agree_terms_of_service: bool,
+	/// URL of the directory of the ACME server
+	#[serde(default = "default_acme_directory_url")]
+	pub directory_url: String,
+	/// Also obtain certificates for bucket names that are domain names,
+	/// and not only for their subdomain of root_domain
+	#[serde(default)]
+	pub custom_domains: bool,
 }
 
 /// Configuration for the admin and monitoring HTTP API
@@ -164,14 +366,203 @@ pub struct AdminConfig {
 	pub metrics_token: Option<String>,
 	/// File to read metrics token from
 	pub metrics_token_file: Option<String>,
+	/// Label API metrics with the name of the bucket. This can create
+	/// many metric series on clusters with many buckets.
+	#[serde(default)]
+	pub metrics_per_bucket: bool,
+	/// Access keys that expire in less than this number of days are
+	/// exported in the `key.expires_in_seconds` metric (default: 7)
+	pub key_expiration_metric_days: Option<u64>,
 
 	/// Bearer token to use to access Admin API endpoints
 	pub admin_token: Option<String>,
 	/// File to read admin token from
 	pub admin_token_file: Option<String>,
+	/// Other bearer tokens giving the same access as the admin token,
+	/// e.g. one for each administrator
+	#[serde(default)]
+	pub additional_admin_tokens: Vec<String>,
+	/// Number of seconds during which the deletion of a bucket or key
+	/// protected against deletion can be confirmed with a second admin token
+	/// (default: 3600)
+	pub deletion_confirmation_secs: Option<u64>,
+	/// Refuse updates of buckets and keys through the admin API that
+	/// don't give the version they apply to in an If-Match header
+	#[serde(default)]
+	pub require_if_match: bool,
+	/// Accept JWTs issued by an OpenID Connect provider as bearer tokens
+	/// for the admin API, in addition to the admin token
+	pub oidc: Option<AdminOidcConfig>,
 
 	/// OTLP server to where to export traces
 	pub trace_sink: Option<String>,
+	/// Headers (gRPC metadata) to add to the requests made to the OTLP server,
+	/// e.g. for authentication
+	#[serde(default)]
+	pub trace_headers: std::collections::HashMap<String, String>,
+	/// Service name under which traces are exported (default: garage)
+	pub trace_service_name: Option<String>,
+	/// Fraction of the requests for which a trace is exported,
+	/// between 0 and 1 (default: 1)
+	pub trace_sampling_ratio: Option<f64>,
+}
+
+/// Configuration of the authentication to the admin API with JWTs issued
+/// by an OpenID Connect provider
+#[derive(Deserialize, Debug, Clone)]
+pub struct AdminOidcConfig {
+	/// Issuer of the tokens, which must match their `iss` claim
+	pub issuer: String,
+	/// Audience of the tokens, which must match their `aud` claim
+	pub audience: String,
+	/// URL of the JSON Web Key Set with which tokens are validated
+	/// (default: discovered from the OpenID configuration of the issuer)
+	pub jwks_url: Option<String>,
+	/// Interval between two refreshes of the JSON Web Key Set,
+	/// in seconds (default: 3600)
+	#[serde(default = "default_oidc_jwks_refresh_secs")]
+	pub jwks_refresh_secs: u64,
+	/// Allow fetching the OpenID configuration and the key set over
+	/// plain HTTP instead of HTTPS (default: false)
+	#[serde(default)]
+	pub allow_insecure_http: bool,
+	/// Claim of the tokens whose values are mapped to admin scopes,
+	/// e.g. `groups` or `roles` (default: `scope`)
+	#[serde(default = "default_oidc_scopes_claim")]
+	pub scopes_claim: String,
+	/// Admin scope given by each value of the claim
+	#[serde(default)]
+	pub scopes: std::collections::HashMap<String, AdminScope>,
+}
+
+/// Access to the admin API given to the bearer of a JWT
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum AdminScope {
+	/// Access to the metrics endpoint only
+	Metrics,
+	/// Access to the endpoints that don't modify the cluster, and to metrics
+	Read,
+	/// Access to all endpoints, as with the admin token
+	Admin,
+}
+
+/// Configuration of the synchronization of access keys with the members
+/// of a group of an LDAP directory (e.g. Active Directory)
+#[derive(Deserialize, Debug, Clone)]
+pub struct LdapSyncConfig {
+	/// URL of the LDAP server, e.g. `ldaps://ldap.example.com`
+	pub url: String,
+	/// DN with which Garage binds to the server
+	pub bind_dn: String,
+	/// Password with which Garage binds to the server
+	pub bind_password: Option<String>,
+	/// File to read the bind password from
+	pub bind_password_file: Option<String>,
+	/// DN under which users are searched
+	pub base_dn: String,
+	/// DN of the group whose members are given an access key
+	pub group_dn: String,
+	/// Attribute of users that identifies them and names their keys
+	/// (default: uid)
+	#[serde(default = "default_ldap_user_attribute")]
+	pub user_attribute: String,
+	/// Interval between two synchronizations, in seconds (default: 300)
+	#[serde(default = "default_ldap_sync_interval_secs")]
+	pub interval_secs: u64,
+}
+
+/// Configuration for the monitoring of data disk usage of storage nodes,
+/// which suggests changes to the capacity of nodes when their usage diverges
+#[derive(Deserialize, Debug, Clone)]
+pub struct RebalanceConfig {
+	/// Difference between the fraction of the data disk used on a node and
+	/// the average of all storage nodes above which a layout change is
+	/// suggested (default: 0.1)
+	#[serde(default = "default_rebalance_threshold")]
+	pub threshold: f64,
+	/// Maximum relative change of the capacity of a node in a suggested
+	/// layout change (default: 0.2)
+	#[serde(default = "default_rebalance_max_capacity_change")]
+	pub max_capacity_change: f64,
+	/// URL to which new suggestions are sent in a JSON POST request
+	pub webhook_url: Option<String>,
+	/// Apply suggested layout changes automatically
+	#[serde(default)]
+	pub auto_apply: bool,
+	/// Minimum delay between two layout changes applied automatically,
+	/// in seconds (default: one day)
+	#[serde(default = "default_rebalance_auto_apply_interval_secs")]
+	pub auto_apply_interval_secs: u64,
+}
+
+/// Configuration for the alarms raised when buckets cross the thresholds
+/// defined on their size and number of objects
+#[derive(Deserialize, Debug, Clone)]
+pub struct BucketAlarmsConfig {
+	/// Interval between two evaluations of the alarms, in seconds (default: 60)
+	#[serde(default = "default_bucket_alarms_check_interval_secs")]
+	pub check_interval_secs: u64,
+	/// URL to which alarms that start or stop firing are sent in a JSON POST request
+	pub webhook_url: Option<String>,
+}
+
+/// Configuration of the priority of maintenance workers (merkle updaters,
+/// table GC, block resync, scrub and repairs) relative to request handling
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct BackgroundConfig {
+	/// Run maintenance workers on a dedicated runtime with this number of
+	/// threads, instead of on the threads that handle requests (default: not set)
+	pub maintenance_threads: Option<usize>,
+	/// Niceness of the threads of the dedicated runtime, between 0 and 19
+	/// (Linux only, default: 0)
+	#[serde(default)]
+	pub maintenance_nice: i32,
+	/// Put the threads of the dedicated runtime in the idle IO scheduling
+	/// class (Linux only, default: false)
+	#[serde(default)]
+	pub maintenance_io_idle: bool,
+	/// Maximum number of maintenance workers that do work at the same
+	/// time (default: no limit)
+	pub max_concurrent_maintenance: Option<usize>,
+}
+
+/// Thresholds of disk usage above which the node refuses new data blocks,
+/// so that a full disk does not prevent the metadata engine from writing
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct DiskWatermarkConfig {
+	/// Refuse new blocks in a data directory whose filesystem is used above
+	/// this fraction, between 0 and 1 (default: not set)
+	pub data_max_usage: Option<f64>,
+	/// Refuse new blocks in a data directory whose filesystem has less free
+	/// space than this (default: not set)
+	#[serde(deserialize_with = "deserialize_opt_capacity", default)]
+	pub data_min_free: Option<usize>,
+	/// Refuse all new blocks when the filesystem of the metadata directory
+	/// has less free space than this (default: not set)
+	#[serde(deserialize_with = "deserialize_opt_capacity", default)]
+	pub metadata_min_free: Option<usize>,
+}
+
+impl Default for BucketAlarmsConfig {
+	fn default() -> Self {
+		Self {
+			check_interval_secs: default_bucket_alarms_check_interval_secs(),
+			webhook_url: None,
+		}
+	}
+}
+
+impl Default for RebalanceConfig {
+	fn default() -> Self {
+		Self {
+			threshold: default_rebalance_threshold(),
+			max_capacity_change: default_rebalance_max_capacity_change(),
+			webhook_url: None,
+			auto_apply: false,
+			auto_apply_interval_secs: default_rebalance_auto_apply_interval_secs(),
+		}
+	}
 }
 
 #[derive(Deserialize, Debug, Clone, Default)]
@@ -221,6 +612,63 @@ pub struct KubernetesDiscoveryConfig {
 	pub skip_crd: bool,
 }
 
+impl Config {
+	/// Build a configuration for a node that stores its metadata and data
+	/// in the given directories, with default values for all optional settings
+	/// and no S3, K2V, web or admin endpoint. This is mostly useful when
+	/// embedding Garage in another program instead of reading a config file.
+	pub fn new(
+		metadata_dir: PathBuf,
+		data_dir: PathBuf,
+		replication_mode: &str,
+		rpc_bind_addr: SocketAddr,
+		rpc_secret: String,
+	) -> Self {
+		// Settings that are not given here take the same default value as
+		// when they are missing from a configuration file
+		let mut config: Config = toml::from_str(
+			r#"
+			metadata_dir = ""
+			data_dir = ""
+			replication_mode = "none"
+			rpc_bind_addr = "[::]:3901"
+
+			[s3_api]
+			s3_region = "garage"
+			"#,
+		)
+		.expect("default configuration is invalid");
+		config.metadata_dir = metadata_dir;
+		config.data_dir = DataDirEnum::Single(data_dir);
+		config.replication_mode = replication_mode.to_string();
+		config.rpc_bind_addr = rpc_bind_addr;
+		config.rpc_secret = Some(rpc_secret);
+		config
+	}
+
+	/// Copy of this configuration in which the settings listed in
+	/// `RELOADABLE_SETTINGS` take their value from `new`
+	pub fn with_reloadable_settings(&self, new: &Config) -> Config {
+		let mut config = self.clone();
+		config.log_filter = new.log_filter.clone();
+		config.shutdown_grace_period_secs = new.shutdown_grace_period_secs;
+		config.s3_api.limits = new.s3_api.limits.clone();
+		if let (Some(k2v), Some(new_k2v)) = (&mut config.k2v_api, &new.k2v_api) {
+			k2v.limits = new_k2v.limits.clone();
+		}
+		if let (Some(webdav), Some(new_webdav)) = (&mut config.webdav, &new.webdav) {
+			webdav.limits = new_webdav.limits.clone();
+		}
+		config.rebalance = new.rebalance.clone();
+		config.bucket_alarms = new.bucket_alarms.clone();
+		config
+	}
+}
+
+fn default_acme_directory_url() -> String {
+	"https://acme-v02.api.letsencrypt.org/directory".into()
+}
+
 fn default_db_engine() -> String {
 	"lmdb".into()
 }
@@ -234,9 +682,51 @@ fn default_sled_flush_every_ms() -> u64 {
 fn default_block_size() -> usize {
 	1048576
 }
+fn default_min_part_size() -> usize {
+	5 * 1024 * 1024
+}
+fn default_put_blocks_max_parallel() -> usize {
+	3
+}
+fn default_shutdown_grace_period_secs() -> u64 {
+	60
+}
+fn default_ldap_user_attribute() -> String {
+	"uid".into()
+}
+fn default_ldap_sync_interval_secs() -> u64 {
+	300
+}
+fn default_oidc_jwks_refresh_secs() -> u64 {
+	3600
+}
+fn default_oidc_scopes_claim() -> String {
+	"scope".into()
+}
+fn default_rebalance_threshold() -> f64 {
+	0.1
+}
+fn default_rebalance_max_capacity_change() -> f64 {
+	0.2
+}
+fn default_rebalance_auto_apply_interval_secs() -> u64 {
+	24 * 3600
+}
+fn default_bucket_alarms_check_interval_secs() -> u64 {
+	60
+}
 
 /// Read and parse configuration
 pub fn read_config(config_file: PathBuf) -> Result<Config, Error> {
+	read_config_with_unknown_settings(config_file).map(|(config, _)| config)
+}
+
+/// Read and parse configuration, and also return the settings of the
+/// configuration file that are unknown to Garage and thus ignored,
+/// e.g. `s3_api.root_domian`
+pub fn read_config_with_unknown_settings(
+	config_file: PathBuf,
+) -> Result<(Config, Vec<String>), Error> {
 	let mut file = std::fs::OpenOptions::new()
 		.read(true)
 		.open(config_file.as_path())?;
@@ -244,7 +734,11 @@ pub fn read_config(config_file: PathBuf) -> Result<Config, Error> {
 	let mut config = String::new();
 	file.read_to_string(&mut config)?;
 
-	let mut parsed_config: Config = toml::from_str(&config)?;
+	let mut unknown = vec![];
+	let mut parsed_config: Config =
+		serde_ignored::deserialize(toml::Deserializer::new(&config), |path| {
+			unknown.push(path.to_string())
+		})?;
 
 	secret_from_file(
 		&mut parsed_config.rpc_secret,
@@ -261,8 +755,123 @@ pub fn read_config(config_file: PathBuf) -> Result<Config, Error> {
 		&parsed_config.admin.admin_token_file,
 		"admin.admin_token",
 	)?;
+	if let Some(ldap) = &mut parsed_config.ldap_sync {
+		secret_from_file(
+			&mut ldap.bind_password,
+			&ldap.bind_password_file,
+			"ldap_sync.bind_password",
+		)?;
+	}
+
+	Ok((parsed_config, unknown))
+}
+
+/// Settings of the configuration file that are applied when it is reloaded
+/// while Garage is running. Changes to other settings require a restart.
+pub const RELOADABLE_SETTINGS: &[&str] = &[
+	"log_filter",
+	"shutdown_grace_period_secs",
+	"s3_api.limits",
+	"k2v_api.limits",
+	"webdav.limits",
+	"rebalance",
+	"bucket_alarms",
+];
+
+/// Read the configuration file as a TOML document, to compare it with
+/// another version of the file when it is reloaded
+pub fn read_config_document(config_file: PathBuf) -> Result<toml::Value, Error> {
+	let config = std::fs::read_to_string(config_file.as_path())?;
+	Ok(toml::from_str(&config)?)
+}
+
+/// Names of the settings whose values differ between two versions of the
+/// configuration file, e.g. `s3_api.limits.max_concurrent_puts`
+pub fn changed_settings(old: &toml::Value, new: &toml::Value) -> Vec<String> {
+	let mut changed = vec![];
+	diff_settings("", old, new, &mut changed);
+	changed
+}
 
-	Ok(parsed_config)
+fn diff_settings(path: &str, old: &toml::Value, new: &toml::Value, changed: &mut Vec<String>) {
+	match (old, new) {
+		(toml::Value::Table(old), toml::Value::Table(new)) => {
+			let keys = old
+				.keys()
+				.chain(new.keys())
+				.collect::<std::collections::BTreeSet<_>>();
+			for k in keys {
+				let path = if path.is_empty() {
+					k.to_string()
+				} else {
+					format!("{}.{}", path, k)
+				};
+				match (old.get(k), new.get(k)) {
+					(Some(o), Some(n)) => diff_settings(&path, o, n, changed),
+					_ => changed.push(path),
+				}
+			}
+		}
+		(o, n) if o != n => changed.push(path.to_string()),
+		_ => (),
+	}
+}
+
+/// Returns true if a setting is applied when the configuration is reloaded
+pub fn is_reloadable_setting(name: &str) -> bool {
+	RELOADABLE_SETTINGS.iter().any(|s| {
+		name == *s
+			|| name
+				.strip_prefix(s)
+				.map(|rest| rest.starts_with('.'))
+				.unwrap_or(false)
+	})
+}
+
+/// Configuration file Garage was started from, which can be read again
+/// to change the settings in `RELOADABLE_SETTINGS` without restarting
+#[derive(Debug)]
+pub struct ConfigFile {
+	path: PathBuf,
+	document: toml::Value,
+}
+
+/// Settings that changed when the configuration file was reloaded,
+/// compared to when Garage was started
+#[derive(Debug, Clone, Default)]
+pub struct ConfigReload {
+	/// Changed settings that are now in effect
+	pub applied: Vec<String>,
+	/// Changed settings that are only taken into account after a restart
+	pub requires_restart: Vec<String>,
+}
+
+impl ConfigFile {
+	pub fn open(path: PathBuf) -> Result<Self, Error> {
+		let document = read_config_document(path.clone())?;
+		Ok(Self { path, document })
+	}
+
+	pub fn path(&self) -> &PathBuf {
+		&self.path
+	}
+
+	/// Read and validate the configuration file again. Returns the new
+	/// configuration and the settings that changed since Garage was started.
+	pub fn reload(&self) -> Result<(Config, ConfigReload), Error> {
+		let config = read_config(self.path.clone())?;
+		let document = read_config_document(self.path.clone())?;
+		let (applied, requires_restart) = changed_settings(&self.document, &document)
+			.into_iter()
+			.partition(|s| is_reloadable_setting(s));
+		Ok((
+			config,
+			ConfigReload {
+				applied,
+				requires_restart,
+			},
+		))
+	}
 }
 
 fn secret_from_file(
@@ -349,6 +958,13 @@ where
 	deserializer.deserialize_any(OptionVisitor)
 }
 
+fn deserialize_opt_capacity<'de, D>(deserializer: D) -> Result<Option<usize>, D::Error>
+where
+	D: de::Deserializer<'de>,
+{
+	deserialize_capacity(deserializer).map(Some)
+}
+
 fn deserialize_capacity<'de, D>(deserializer: D) -> Result<usize, D::Error>
 where
 	D: de::Deserializer<'de>,
@@ -504,4 +1120,68 @@ mod tests {
 		drop(file_config);
 		Ok(())
 	}
+
+	#[test]
+	fn test_changed_settings() -> Result<(), Error> {
+		let old: toml::Value = toml::from_str(
+			r#"
+			rpc_bind_addr = "[::]:3901"
+			[s3_api.limits]
+			max_concurrent_puts = 10
+			[bucket_alarms]
+			webhook_url = "http://localhost/alarms"
+			"#,
+		)?;
+		let new: toml::Value = toml::from_str(
+			r#"
+			rpc_bind_addr = "[::]:3911"
+			[s3_api.limits]
+			max_concurrent_puts = 20
+			max_concurrent_list_scans = 5
+			"#,
+		)?;
+
+		let changed = super::changed_settings(&old, &new);
+		assert_eq!(
+			changed,
+			vec![
+				"bucket_alarms",
+				"rpc_bind_addr",
+				"s3_api.limits.max_concurrent_list_scans",
+				"s3_api.limits.max_concurrent_puts",
+			]
+		);
+		let reloadable = changed
+			.iter()
+			.filter(|s| super::is_reloadable_setting(s))
+			.collect::<Vec<_>>();
+		assert_eq!(
+			reloadable,
+			vec![
+				"bucket_alarms",
+				"s3_api.limits.max_concurrent_list_scans",
+				"s3_api.limits.max_concurrent_puts",
+			]
+		);
+		assert!(!super::is_reloadable_setting("rebalance_foo"));
+
+		Ok(())
+	}
+
+	#[test]
+	fn test_config_new() {
+		let config = super::Config::new(
+			"/tmp/meta".into(),
+			"/tmp/data".into(),
+			"3",
+			"127.0.0.1:3901".parse().unwrap(),
+			"foo".to_string(),
+		);
+		assert_eq!(config.replication_mode, "3");
+		assert_eq!(config.rpc_secret.as_deref(), Some("foo"));
+		assert_eq!(config.block_size, super::default_block_size());
+		assert_eq!(config.db_engine, super::default_db_engine());
+		assert_eq!(config.s3_api.s3_region, "garage");
+		assert!(config.k2v_api.is_none());
+	}
 }
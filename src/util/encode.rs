@@ -13,6 +13,32 @@ where
 	Ok(wr)
 }
 
+/// Size of the serialization of a value by `nonversioned_encode`,
+/// computed without allocating a buffer
+pub fn nonversioned_encoded_len<T>(val: &T) -> Result<u64, rmp_serde::encode::Error>
+where
+	T: Serialize + ?Sized,
+{
+	let mut wr = ByteCounter(0);
+	let mut se = rmp_serde::Serializer::new(&mut wr).with_struct_map();
+	val.serialize(&mut se)?;
+	Ok(wr.0)
+}
+
+/// Writer that only counts the bytes written to it
+struct ByteCounter(u64);
+
+impl std::io::Write for ByteCounter {
+	fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+		self.0 += buf.len() as u64;
+		Ok(buf.len())
+	}
+
+	fn flush(&mut self) -> std::io::Result<()> {
+		Ok(())
+	}
+}
+
 /// Deserialize from MessagePacki, without versionning
 /// (see garage_util::migrate for functions that manage versionned
 /// data formats)
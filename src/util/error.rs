@@ -1,10 +1,9 @@
 //! Module containing error types used in Garage
-use std::fmt;
 use std::io;
 
 use err_derive::Error;
 
-use serde::{de::Visitor, Deserialize, Deserializer, Serialize, Serializer};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use crate::data::*;
 use crate::encode::debug_serialize;
@@ -61,7 +60,7 @@ pub enum Error {
 		_2,
 		_3
 	)]
-	Quorum(usize, usize, usize, Vec<String>),
+	Quorum(usize, usize, usize, Vec<String>, Vec<ErrorCode>),
 
 	#[error(display = "Unexpected RPC message: {}", _0)]
 	UnexpectedRpcMessage(String),
@@ -69,14 +68,54 @@ pub enum Error {
 	#[error(display = "Corrupt data: does not match hash {:?}", _0)]
 	CorruptData(Hash),
 
+	#[error(display = "Insufficient storage: {}", _0)]
+	InsufficientStorage(String),
+
 	#[error(display = "{}", _0)]
 	Message(String),
 }
 
+/// Kinds of errors that are kept as such when they are sent to another node,
+/// instead of becoming a `RemoteError`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ErrorCode {
+	InsufficientStorage,
+}
+
 impl Error {
 	pub fn unexpected_rpc_message<T: Serialize>(v: T) -> Self {
 		Self::UnexpectedRpcMessage(debug_serialize(&v))
 	}
+
+	/// Build the error returned when a quorum could not be reached,
+	/// from the errors returned by the nodes that were called
+	pub fn quorum(quorum: usize, successes: usize, total: usize, errors: &[Error]) -> Self {
+		Self::Quorum(
+			quorum,
+			successes,
+			total,
+			errors.iter().map(|e| format!("{}", e)).collect(),
+			errors.iter().filter_map(Error::code).collect(),
+		)
+	}
+
+	pub fn code(&self) -> Option<ErrorCode> {
+		match self {
+			Error::InsufficientStorage(_) => Some(ErrorCode::InsufficientStorage),
+			_ => None,
+		}
+	}
+
+	/// Whether this error, or one of the errors that prevented a quorum
+	/// from being reached, is a node refusing new data because its disks
+	/// are full
+	pub fn is_insufficient_storage(&self) -> bool {
+		match self {
+			Error::InsufficientStorage(_) => true,
+			Error::Quorum(_, _, _, _, codes) => codes.contains(&ErrorCode::InsufficientStorage),
+			_ => false,
+		}
+	}
 }
 
 impl From<garage_db::TxError<Error>> for Error {
@@ -159,16 +198,31 @@ impl<T> OkOrMessage for Option<T> {
 }
 
 // Custom serialization for our error type, for use in RPC.
-// Errors are serialized as a string of their Display representation.
-// Upon deserialization, they all become a RemoteError with the
-// given representation.
+// Errors are serialized as their code, if they have one, and a message.
+// Upon deserialization, errors with a known code are rebuilt from their
+// message, and all others become a RemoteError with the given message,
+// which is their Display representation.
+
+#[derive(Serialize, Deserialize)]
+struct SerializedError {
+	code: Option<ErrorCode>,
+	message: String,
+}
 
 impl Serialize for Error {
 	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
 	where
 		S: Serializer,
 	{
-		serializer.serialize_str(&format!("{}", self))
+		let message = match self {
+			Error::InsufficientStorage(msg) => msg.clone(),
+			e => format!("{}", e),
+		};
+		SerializedError {
+			code: self.code(),
+			message,
+		}
+		.serialize(serializer)
 	}
 }
 
@@ -177,24 +231,36 @@ impl<'de> Deserialize<'de> for Error {
 	where
 		D: Deserializer<'de>,
 	{
-		deserializer.deserialize_string(ErrorVisitor)
+		let SerializedError { code, message } = SerializedError::deserialize(deserializer)?;
+		Ok(match code {
+			Some(ErrorCode::InsufficientStorage) => Error::InsufficientStorage(message),
+			None => Error::RemoteError(message),
+		})
 	}
 }
 
-struct ErrorVisitor;
-
-impl<'de> Visitor<'de> for ErrorVisitor {
-	type Value = Error;
-
-	fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-		write!(formatter, "a string that represents an error value")
-	}
-
-	fn visit_str<E>(self, error_msg: &str) -> Result<Self::Value, E> {
-		Ok(Error::RemoteError(error_msg.to_string()))
-	}
-
-	fn visit_string<E>(self, error_msg: String) -> Result<Self::Value, E> {
-		Ok(Error::RemoteError(error_msg))
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_insufficient_storage_over_rpc() {
+		let err = Error::InsufficientStorage("/mnt/data: 1% free".into());
+		let bytes = rmp_serde::to_vec(&err).unwrap();
+		let remote = rmp_serde::from_slice::<Error>(&bytes).unwrap();
+		assert!(matches!(&remote, Error::InsufficientStorage(m) if m == "/mnt/data: 1% free"));
+
+		let quorum = Error::quorum(2, 1, 3, &[remote, Error::Timeout]);
+		assert!(quorum.is_insufficient_storage());
+		assert!(!Error::Timeout.is_insufficient_storage());
+
+		// Other errors, even with a similar message, become remote errors
+		let err = Error::Message("Insufficient storage: not really".into());
+		let bytes = rmp_serde::to_vec(&err).unwrap();
+		let remote = rmp_serde::from_slice::<Error>(&bytes).unwrap();
+		assert!(
+			matches!(&remote, Error::RemoteError(m) if m == "Insufficient storage: not really")
+		);
+		assert!(!Error::quorum(2, 1, 3, &[remote]).is_insufficient_storage());
 	}
 }
@@ -19,6 +19,18 @@ pub fn handle_forwarded_for_headers(headers: &HeaderMap<HeaderValue>) -> Result<
 	Ok(client_ip.to_string())
 }
 
+/// Original host requested by the client, as given by a reverse proxy
+/// in the X-Forwarded-Host header (the first one if there are several)
+pub fn handle_forwarded_host_header(headers: &HeaderMap<HeaderValue>) -> Option<&str> {
+	headers
+		.get("x-forwarded-host")?
+		.to_str()
+		.ok()?
+		.split(',')
+		.map(str::trim)
+		.find(|h| !h.is_empty())
+}
+
 #[cfg(test)]
 mod test {
 	use super::*;
@@ -60,4 +72,19 @@ mod test {
 		let result = handle_forwarded_for_headers(&test_headers);
 		assert!(result.is_err());
 	}
+
+	#[test]
+	fn test_handle_forwarded_host_header() {
+		let mut test_headers = HeaderMap::new();
+		assert_eq!(handle_forwarded_host_header(&test_headers), None);
+
+		test_headers.insert(
+			"X-Forwarded-Host",
+			"bucket.s3.garage.tld, proxy.local".parse().unwrap(),
+		);
+		assert_eq!(
+			handle_forwarded_host_header(&test_headers),
+			Some("bucket.s3.garage.tld")
+		);
+	}
 }
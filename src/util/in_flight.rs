@@ -0,0 +1,135 @@
+//! Tracking of the requests that are being processed by the API and web
+//! servers, so that they can be given time to complete when Garage shuts down
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tokio::sync::Notify;
+
+/// The set of requests currently being processed by this node
+#[derive(Default)]
+pub struct InFlightRequests {
+	next_id: AtomicU64,
+	requests: Mutex<HashMap<u64, InFlightRequest>>,
+	notify: Notify,
+}
+
+/// Information about a request that is being processed
+#[derive(Clone, Debug)]
+pub struct InFlightRequest {
+	/// Name of the API or server handling the request
+	pub api: &'static str,
+	pub method: String,
+	pub uri: String,
+	/// Address of the client that sent the request
+	pub client: String,
+	pub started: Instant,
+}
+
+/// Guard returned when a request starts being processed: the request
+/// is considered completed when it is dropped
+pub struct InFlightGuard {
+	requests: Arc<InFlightRequests>,
+	id: u64,
+}
+
+impl InFlightRequests {
+	pub fn new() -> Arc<Self> {
+		Arc::new(Self::default())
+	}
+
+	/// Register a request that starts being processed
+	pub fn start(
+		self: &Arc<Self>,
+		api: &'static str,
+		method: String,
+		uri: String,
+		client: String,
+	) -> InFlightGuard {
+		let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+		self.requests.lock().unwrap().insert(
+			id,
+			InFlightRequest {
+				api,
+				method,
+				uri,
+				client,
+				started: Instant::now(),
+			},
+		);
+		InFlightGuard {
+			requests: self.clone(),
+			id,
+		}
+	}
+
+	/// Number of requests currently being processed
+	pub fn len(&self) -> usize {
+		self.requests.lock().unwrap().len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// List of the requests currently being processed, oldest first
+	pub fn list(&self) -> Vec<InFlightRequest> {
+		let mut list = self
+			.requests
+			.lock()
+			.unwrap()
+			.values()
+			.cloned()
+			.collect::<Vec<_>>();
+		list.sort_by_key(|r| r.started);
+		list
+	}
+
+	/// Wait until all requests have completed, for at most the given
+	/// duration. Returns true if all requests have completed.
+	pub async fn wait_drained(&self, timeout: Duration) -> bool {
+		let deadline = tokio::time::Instant::now() + timeout;
+		loop {
+			let notified = self.notify.notified();
+			if self.is_empty() {
+				return true;
+			}
+			if tokio::time::timeout_at(deadline, notified).await.is_err() {
+				return self.is_empty();
+			}
+		}
+	}
+}
+
+impl Drop for InFlightGuard {
+	fn drop(&mut self) {
+		self.requests.requests.lock().unwrap().remove(&self.id);
+		self.requests.notify.notify_waiters();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[tokio::test]
+	async fn test_wait_drained() {
+		let requests = InFlightRequests::new();
+		assert!(requests.wait_drained(Duration::from_millis(10)).await);
+
+		let guard = requests.start("S3", "PUT".into(), "/a/b".into(), "client".into());
+		let guard2 = requests.start("S3", "GET".into(), "/a/c".into(), "client".into());
+		assert_eq!(requests.len(), 2);
+		assert_eq!(requests.list()[0].uri, "/a/b");
+		assert!(!requests.wait_drained(Duration::from_millis(10)).await);
+
+		drop(guard2);
+		tokio::spawn(async move {
+			tokio::time::sleep(Duration::from_millis(10)).await;
+			drop(guard);
+		});
+		assert!(requests.wait_drained(Duration::from_secs(10)).await);
+		assert!(requests.is_empty());
+	}
+}
@@ -11,9 +11,12 @@ pub mod data;
 pub mod encode;
 pub mod error;
 pub mod forwarded_headers;
+pub mod in_flight;
+pub mod log_filter;
 pub mod metrics;
 pub mod migrate;
 pub mod persister;
+pub mod platform;
 pub mod socket_address;
 pub mod time;
 pub mod tranquilizer;
@@ -0,0 +1,64 @@
+//! Runtime reconfiguration of the filter that selects which log messages
+//! are emitted (the equivalent of the `RUST_LOG` environment variable)
+use std::sync::Mutex;
+
+use crate::error::Error;
+
+type SetFilterFn = Box<dyn Fn(&str) -> Result<(), String> + Send + Sync>;
+
+struct LogFilter {
+	initial: String,
+	current: String,
+	set_filter: SetFilterFn,
+}
+
+lazy_static::lazy_static! {
+	static ref LOG_FILTER: Mutex<Option<LogFilter>> = Mutex::new(None);
+}
+
+/// Register the function that changes the filter of the logging subscriber,
+/// as well as the filter that is currently in use. Called once when the
+/// logging subscriber is initialized.
+pub fn init_log_filter<F>(current: String, set_filter: F)
+where
+	F: Fn(&str) -> Result<(), String> + Send + Sync + 'static,
+{
+	*LOG_FILTER.lock().unwrap() = Some(LogFilter {
+		initial: current.clone(),
+		current,
+		set_filter: Box::new(set_filter),
+	});
+}
+
+/// Get the log filter currently in use
+pub fn get_log_filter() -> Result<String, Error> {
+	match LOG_FILTER.lock().unwrap().as_ref() {
+		Some(lf) => Ok(lf.current.clone()),
+		None => Err(Error::Message(
+			"Log filter cannot be changed at runtime".into(),
+		)),
+	}
+}
+
+/// Change the log filter, e.g. to `garage=info,garage_block=debug`.
+/// The change is not persisted: the filter is reset to the value of
+/// `RUST_LOG` when Garage restarts.
+pub fn set_log_filter(filter: &str) -> Result<(), Error> {
+	let mut log_filter = LOG_FILTER.lock().unwrap();
+	let lf = log_filter
+		.as_mut()
+		.ok_or_else(|| Error::Message("Log filter cannot be changed at runtime".into()))?;
+	(lf.set_filter)(filter)
+		.map_err(|e| Error::Message(format!("Invalid log filter `{}`: {}", filter, e)))?;
+	lf.current = filter.to_string();
+	Ok(())
+}
+
+/// Change the log filter back to the one in use when Garage was started
+pub fn reset_log_filter() -> Result<(), Error> {
+	let initial = match LOG_FILTER.lock().unwrap().as_ref() {
+		Some(lf) => lf.initial.clone(),
+		None => return Ok(()),
+	};
+	set_log_filter(&initial)
+}
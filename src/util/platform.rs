@@ -0,0 +1,170 @@
+//! Filesystem operations whose semantics differ between the platforms Garage
+//! runs on (Linux and other Unixes, macOS, Windows), so that the block manager
+//! and the disk usage reports work the same on all of them.
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Space of the filesystem a path is on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiskSpace {
+	/// Identifier of the filesystem, to avoid counting it twice when
+	/// several directories are on the same one
+	pub filesystem_id: u64,
+	/// Bytes available to Garage
+	pub available: u64,
+	/// Total size in bytes
+	pub total: u64,
+}
+
+/// Get the available and total space of the filesystem a path is on
+pub fn disk_space(path: &Path) -> io::Result<DiskSpace> {
+	Ok(DiskSpace {
+		filesystem_id: filesystem_id(path)?,
+		available: fs2::available_space(path)?,
+		total: fs2::total_space(path)?,
+	})
+}
+
+#[cfg(unix)]
+fn filesystem_id(path: &Path) -> io::Result<u64> {
+	use std::os::unix::fs::MetadataExt;
+	Ok(std::fs::metadata(path)?.dev())
+}
+
+#[cfg(not(unix))]
+fn filesystem_id(path: &Path) -> io::Result<u64> {
+	use std::hash::{Hash, Hasher};
+
+	// Without a device number, identify volumes by their prefix (e.g. `C:`)
+	let path = std::fs::canonicalize(path)?;
+	let mut hasher = std::collections::hash_map::DefaultHasher::new();
+	path.components().next().hash(&mut hasher);
+	Ok(hasher.finish())
+}
+
+/// Ensure that the entries of a directory (files created, renamed or removed
+/// in it) are persisted to disk. Data of the files themselves is persisted
+/// by calling `sync_all` on them, which on macOS also flushes the disk cache
+/// (`F_FULLFSYNC`).
+#[cfg(unix)]
+pub async fn sync_dir(path: &Path) -> io::Result<()> {
+	// See http://thedjbway.b0llix.net/qmail/syncdir.html
+	let dir = tokio::fs::OpenOptions::new()
+		.read(true)
+		.mode(0)
+		.open(path)
+		.await?;
+	dir.sync_all().await
+}
+
+/// Ensure that the entries of a directory are persisted to disk.
+/// Directories cannot be opened as files on Windows, and NTFS journals
+/// directory changes, so there is nothing to do.
+#[cfg(not(unix))]
+pub async fn sync_dir(_path: &Path) -> io::Result<()> {
+	Ok(())
+}
+
+/// Rename a file, replacing the destination if it exists.
+/// On Windows, this fails while the destination is open, e.g. by a reader of
+/// a block that is being rewritten, so it is retried for a short time.
+pub async fn rename_replace(from: &Path, to: &Path) -> io::Result<()> {
+	#[cfg(windows)]
+	for _ in 0..10 {
+		match tokio::fs::rename(from, to).await {
+			Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
+				tokio::time::sleep(std::time::Duration::from_millis(50)).await
+			}
+			res => return res,
+		}
+	}
+	tokio::fs::rename(from, to).await
+}
+
+/// Make a path usable for all filesystem operations. On Windows, absolute
+/// paths are given the `\\?\` prefix so that they are not limited to 260
+/// characters, which the paths of blocks in deep data directories can exceed.
+pub fn native_path(path: &Path) -> PathBuf {
+	#[cfg(windows)]
+	{
+		use std::path::{Component, Prefix};
+		if let Some(Component::Prefix(p)) = path.components().next() {
+			if let Prefix::Disk(_) = p.kind() {
+				let mut verbatim = std::ffi::OsString::from(r"\\?\");
+				verbatim.push(path.as_os_str());
+				return PathBuf::from(verbatim);
+			}
+		}
+	}
+	path.to_path_buf()
+}
+
+/// Lower the CPU priority (niceness) of the calling thread, and put it in
+/// the idle IO scheduling class if `io_idle` is set. Only supported on Linux,
+/// where priorities are set per thread.
+#[cfg(target_os = "linux")]
+pub fn lower_thread_priority(nice: i32, io_idle: bool) -> io::Result<()> {
+	// On Linux, the thread ID can be given where the man pages say process ID
+	let tid = unsafe { libc::syscall(libc::SYS_gettid) } as libc::id_t;
+	if nice > 0 && unsafe { libc::setpriority(libc::PRIO_PROCESS, tid, nice) } != 0 {
+		return Err(io::Error::last_os_error());
+	}
+	if io_idle {
+		// See ioprio_set(2)
+		const IOPRIO_WHO_PROCESS: libc::c_long = 1;
+		const IOPRIO_CLASS_IDLE: libc::c_long = 3;
+		const IOPRIO_CLASS_SHIFT: libc::c_long = 13;
+		let res = unsafe {
+			libc::syscall(
+				libc::SYS_ioprio_set,
+				IOPRIO_WHO_PROCESS,
+				tid as libc::c_long,
+				IOPRIO_CLASS_IDLE << IOPRIO_CLASS_SHIFT,
+			)
+		};
+		if res != 0 {
+			return Err(io::Error::last_os_error());
+		}
+	}
+	Ok(())
+}
+
+/// Lower the CPU and IO priority of the calling thread: not supported on
+/// this platform.
+#[cfg(not(target_os = "linux"))]
+pub fn lower_thread_priority(nice: i32, io_idle: bool) -> io::Result<()> {
+	if nice > 0 || io_idle {
+		return Err(io::Error::new(
+			io::ErrorKind::Unsupported,
+			"thread priorities can only be set on Linux",
+		));
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[tokio::test]
+	async fn test_block_file_operations() -> io::Result<()> {
+		let dir = mktemp::Temp::new_dir()?;
+		let dir = native_path(&dir);
+
+		let space = disk_space(&dir)?;
+		assert!(space.total > 0);
+		assert!(space.available <= space.total);
+		assert_eq!(space.filesystem_id, disk_space(&dir)?.filesystem_id);
+
+		let tmp = dir.join("block.tmp0123");
+		let tgt = dir.join("block.zst");
+		tokio::fs::write(&tgt, b"old").await?;
+		tokio::fs::write(&tmp, b"new").await?;
+		rename_replace(&tmp, &tgt).await?;
+		sync_dir(&dir).await?;
+
+		assert!(!tmp.exists());
+		assert_eq!(tokio::fs::read(&tgt).await?, b"new");
+		Ok(())
+	}
+}
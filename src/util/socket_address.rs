@@ -5,6 +5,7 @@ use std::str::FromStr;
 
 use serde::de::Error;
 use serde::{Deserialize, Deserializer};
+use socket2::{Domain, Protocol, Socket, Type};
 
 #[derive(Debug, Clone)]
 pub enum UnixOrTCPSocketAddress {
@@ -42,3 +43,19 @@ impl<'de> Deserialize<'de> for UnixOrTCPSocketAddress {
 		}
 	}
 }
+
+/// Bind a TCP socket for listening. A socket bound to the unspecified IPv6
+/// address `[::]` accepts IPv4 connections as well, whatever the default of
+/// the system (e.g. the `net.ipv6.bindv6only` sysctl on Linux), so that
+/// `[::]` always designates all the addresses of both families.
+pub fn bind_tcp_listener(addr: SocketAddr) -> std::io::Result<std::net::TcpListener> {
+	let socket = Socket::new(Domain::for_address(addr), Type::STREAM, Some(Protocol::TCP))?;
+	if addr.is_ipv6() && addr.ip().is_unspecified() {
+		socket.set_only_v6(false)?;
+	}
+	socket.set_reuse_address(true)?;
+	socket.set_nonblocking(true)?;
+	socket.bind(&addr.into())?;
+	socket.listen(1024)?;
+	Ok(socket.into())
+}
@@ -0,0 +1,683 @@
+//! Obtaining TLS certificates for websites with the ACME protocol (RFC 8555),
+//! using HTTP-01 challenges that are answered by the web endpoint.
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use base64::prelude::*;
+use hyper::client::HttpConnector;
+use hyper::header::{HeaderValue, CONTENT_TYPE, LOCATION};
+use hyper::{Body, Client, Method, Request, Response, StatusCode};
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+use ring::rand::SystemRandom;
+use ring::signature::{EcdsaKeyPair, KeyPair, ECDSA_P256_SHA256_FIXED_SIGNING};
+use serde::Deserialize;
+use serde_json::json;
+use tokio::sync::watch;
+
+use garage_model::garage::Garage;
+use garage_model::tls_certificate_table::*;
+use garage_table::*;
+use garage_util::background::*;
+use garage_util::config::AcmeConfig;
+use garage_util::error::Error as GarageError;
+use garage_util::time::now_msec;
+
+use crate::error::Error;
+use crate::tls::{open_private_key, private_key_cipher, seal_private_key};
+
+/// Path prefix of the URLs used by the ACME server to validate HTTP-01 challenges
+pub(crate) const ACME_CHALLENGE_PREFIX: &str = "/.well-known/acme-challenge/";
+
+/// Interval between two checks of the certificates that need to be obtained
+const CHECK_INTERVAL: Duration = Duration::from_secs(600);
+/// Age after which certificates are renewed (certificates issued
+/// by Let's Encrypt are valid for 90 days)
+const RENEW_AFTER: u64 = 60 * 24 * 3600 * 1000;
+/// Delay before retrying to obtain a certificate after an error
+const RETRY_AFTER_ERROR: u64 = 6 * 3600 * 1000;
+/// Duration after which an order that was started by another node
+/// is considered abandoned
+const ORDER_TIMEOUT: u64 = 15 * 60 * 1000;
+/// Name of the file, in the metadata directory, containing the key of the ACME account
+const ACCOUNT_KEY_FILE: &str = "acme_account_key";
+
+/// Answer a validation request of the ACME server, if it is
+/// for a challenge that is currently pending for this host.
+pub(crate) async fn handle_acme_challenge(
+	garage: &Garage,
+	host: &str,
+	token: &str,
+) -> Result<Option<Response<Body>>, Error> {
+	let entry = garage
+		.tls_certificate_table
+		.get(&EmptyKey, &host.to_string())
+		.await?;
+	let key_authorization = entry.and_then(|e| {
+		e.pending_order
+			.get()
+			.as_ref()
+			.and_then(|o| o.challenges.get(token).cloned())
+	});
+
+	let key_authorization = match key_authorization {
+		Some(ka) => ka,
+		None => return Ok(None),
+	};
+	let resp = Response::builder()
+		.status(StatusCode::OK)
+		.header(CONTENT_TYPE, "application/octet-stream")
+		.body(Body::from(key_authorization))?;
+	Ok(Some(resp))
+}
+
+/// Background worker that obtains and renews the certificates for
+/// the domain names of all buckets that have website access enabled
+pub struct AcmeWorker {
+	garage: Arc<Garage>,
+	config: AcmeConfig,
+	root_domain: String,
+	client: Option<AcmeClient>,
+	issued: usize,
+	errors: usize,
+}
+
+impl AcmeWorker {
+	pub fn new(garage: Arc<Garage>, config: AcmeConfig, root_domain: String) -> Self {
+		Self {
+			garage,
+			config,
+			root_domain,
+			client: None,
+			issued: 0,
+			errors: 0,
+		}
+	}
+
+	/// Domain names for which we should have a certificate
+	async fn domains(&self) -> Result<Vec<String>, GarageError> {
+		let root_domain = self.root_domain.trim_start_matches('.');
+		let mut domains = vec![];
+
+		let mut start = None;
+		loop {
+			let buckets = self
+				.garage
+				.bucket_table
+				.get_range(
+					&EmptyKey,
+					start,
+					Some(DeletedFilter::NotDeleted),
+					1000,
+					EnumerationOrder::Forward,
+				)
+				.await?;
+			let n_buckets = buckets.len();
+			start = buckets.last().map(|b| b.id);
+
+			for bucket in buckets {
+				let params = match bucket.params() {
+					Some(p) if p.website_config.get().is_some() => p,
+					_ => continue,
+				};
+				for (alias, _, active) in params.aliases.items() {
+					if !*active {
+						continue;
+					}
+					if !root_domain.is_empty() {
+						domains.push(format!("{}.{}", alias, root_domain));
+					}
+					if self.config.custom_domains && alias.contains('.') {
+						domains.push(alias.clone());
+					}
+				}
+			}
+
+			// The last bucket is returned again by the next call to get_range,
+			// duplicates are removed below
+			if n_buckets < 1000 {
+				break;
+			}
+		}
+
+		domains.sort();
+		domains.dedup();
+		Ok(domains)
+	}
+
+	/// Find a domain whose certificate has to be obtained or renewed
+	async fn next_domain(&self) -> Result<Option<TlsCertificate>, GarageError> {
+		let now = now_msec();
+		let cipher = private_key_cipher(&self.garage)?;
+		for domain in self.domains().await? {
+			let entry = self
+				.garage
+				.tls_certificate_table
+				.get(&EmptyKey, &domain)
+				.await?
+				.unwrap_or_else(|| TlsCertificate::new(domain));
+
+			// Certificates whose key can't be decrypted, e.g. because the
+			// RPC secret has changed, are obtained again
+			let needs_certificate = match entry.certificate.get() {
+				Some(c) => {
+					now > c.issued_at + RENEW_AFTER
+						|| open_private_key(&cipher, entry.domain(), &c.encrypted_private_key)
+							.is_none()
+				}
+				None => true,
+			};
+			let recent_error = matches!(
+				entry.last_error.get(),
+				Some((t, _)) if now < t + RETRY_AFTER_ERROR
+			);
+			let pending = matches!(
+				entry.pending_order.get(),
+				Some(o) if now < o.started_at + ORDER_TIMEOUT
+			);
+			if needs_certificate && !recent_error && !pending {
+				return Ok(Some(entry));
+			}
+		}
+		Ok(None)
+	}
+
+	async fn obtain_certificate(&mut self, mut entry: TlsCertificate) -> Result<(), GarageError> {
+		let domain = entry.domain().to_string();
+		let node = self.garage.system.id;
+
+		// Mark the order as being handled by this node, and check after a while
+		// that no other node has taken it at the same time
+		entry.pending_order.update(Some(PendingOrder {
+			node,
+			started_at: now_msec(),
+			challenges: BTreeMap::new(),
+		}));
+		self.garage.tls_certificate_table.insert(&entry).await?;
+		tokio::time::sleep(Duration::from_secs(5)).await;
+		let mut entry = match owned_order(&self.garage, &domain).await? {
+			Some(entry) => entry,
+			None => {
+				debug!(
+					"Certificate for {} is being obtained by another node",
+					domain
+				);
+				return Ok(());
+			}
+		};
+
+		info!("Obtaining TLS certificate for {}", domain);
+		let mut res = self.issue(&mut entry).await;
+		if let Err(e) = &res {
+			// The error may be caused by another node having taken over the
+			// order, in which case its pending order must be left untouched
+			if owned_order(&self.garage, &domain).await?.is_none() {
+				debug!("Could not obtain TLS certificate for {}: {}", domain, e);
+				res = Ok(None);
+			}
+		}
+
+		entry.pending_order.update(None);
+		match res {
+			Ok(Some(certificate)) => {
+				info!("Obtained TLS certificate for {}", domain);
+				entry.certificate.update(Some(certificate));
+				entry.last_error.update(None);
+				self.issued += 1;
+			}
+			Ok(None) => {
+				// Leave the entry to the node that took over the order
+				info!(
+					"Order for {} was taken over by another node, giving up",
+					domain
+				);
+				return Ok(());
+			}
+			Err(e) => {
+				error!("Could not obtain TLS certificate for {}: {}", domain, e);
+				entry.last_error.update(Some((now_msec(), e.to_string())));
+				self.errors += 1;
+				// Start over with a new client (and a fresh nonce) next time
+				self.client = None;
+			}
+		}
+		self.garage.tls_certificate_table.insert(&entry).await?;
+		Ok(())
+	}
+
+	async fn issue(
+		&mut self,
+		entry: &mut TlsCertificate,
+	) -> Result<Option<CertificateData>, GarageError> {
+		if self.client.is_none() {
+			self.client = Some(AcmeClient::new(&self.garage, &self.config).await?);
+		}
+		self.client
+			.as_mut()
+			.unwrap()
+			.issue(&self.garage, entry)
+			.await
+	}
+}
+
+#[async_trait]
+impl Worker for AcmeWorker {
+	fn name(&self) -> String {
+		"ACME certificate manager".into()
+	}
+
+	fn status(&self) -> WorkerStatus {
+		WorkerStatus {
+			freeform: vec![
+				format!("Certificates obtained: {}", self.issued),
+				format!("Errors: {}", self.errors),
+			],
+			..Default::default()
+		}
+	}
+
+	async fn work(
+		&mut self,
+		_must_exit: &mut watch::Receiver<bool>,
+	) -> Result<WorkerState, GarageError> {
+		match self.next_domain().await? {
+			Some(entry) => {
+				self.obtain_certificate(entry).await?;
+				Ok(WorkerState::Busy)
+			}
+			None => Ok(WorkerState::Idle),
+		}
+	}
+
+	async fn wait_for_work(&mut self) -> WorkerState {
+		tokio::time::sleep(CHECK_INTERVAL).await;
+		WorkerState::Busy
+	}
+}
+
+// ---- ACME client ----
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Directory {
+	new_nonce: String,
+	new_account: String,
+	new_order: String,
+}
+
+#[derive(Deserialize)]
+struct Order {
+	status: String,
+	authorizations: Vec<String>,
+	finalize: String,
+	certificate: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Authorization {
+	status: String,
+	challenges: Vec<Challenge>,
+}
+
+#[derive(Deserialize)]
+struct Challenge {
+	#[serde(rename = "type")]
+	challenge_type: String,
+	url: String,
+	token: String,
+}
+
+struct AcmeClient {
+	http: Client<HttpsConnector<HttpConnector>>,
+	key: EcdsaKeyPair,
+	rng: SystemRandom,
+	directory: Directory,
+	account_url: String,
+	nonce: Option<String>,
+}
+
+impl AcmeClient {
+	async fn new(garage: &Garage, config: &AcmeConfig) -> Result<Self, GarageError> {
+		if !config.agree_terms_of_service {
+			return Err(GarageError::Message(
+				"The terms of service of the ACME server must be agreed to, by setting agree_terms_of_service = true".into(),
+			));
+		}
+
+		let rng = SystemRandom::new();
+		let key_file = garage.config.metadata_dir.join(ACCOUNT_KEY_FILE);
+		let pkcs8 = match std::fs::read(&key_file) {
+			Ok(pkcs8) => pkcs8,
+			Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+				let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &rng)
+					.map_err(|_| {
+						GarageError::Message("Could not generate ACME account key".into())
+					})?;
+				write_account_key(&key_file, pkcs8.as_ref())?;
+				pkcs8.as_ref().to_vec()
+			}
+			Err(e) => return Err(e.into()),
+		};
+		let key = EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &pkcs8)
+			.map_err(|_| GarageError::Message("Invalid ACME account key".into()))?;
+
+		let connector = HttpsConnectorBuilder::new()
+			.with_native_roots()
+			.https_only()
+			.enable_http1()
+			.build();
+		let http = Client::builder().build(connector);
+
+		let directory_url = config
+			.directory_url
+			.parse()
+			.map_err(|_| GarageError::Message("Invalid ACME directory URL".into()))?;
+		let resp = http.get(directory_url).await?;
+		let directory: Directory = serde_json::from_slice(&read_body(resp).await?.2)?;
+
+		let mut client = Self {
+			http,
+			key,
+			rng,
+			directory,
+			account_url: String::new(),
+			nonce: None,
+		};
+
+		let new_account = client.directory.new_account.clone();
+		let payload = json!({
+			"termsOfServiceAgreed": true,
+			"contact": [format!("mailto:{}", config.contact_email)],
+		});
+		let (headers, _) = client.post(&new_account, Some(payload)).await?;
+		client.account_url = location(&headers)?;
+
+		Ok(client)
+	}
+
+	/// Run the whole process of obtaining a certificate for a domain.
+	/// The challenges are stored in the pending order of the table entry.
+	/// Returns `None` if another node took over the order in the meantime.
+	async fn issue(
+		&mut self,
+		garage: &Garage,
+		entry: &mut TlsCertificate,
+	) -> Result<Option<CertificateData>, GarageError> {
+		let domain = entry.domain().to_string();
+		let cipher = private_key_cipher(garage)?;
+
+		// Create order
+		let new_order = self.directory.new_order.clone();
+		let payload = json!({
+			"identifiers": [{ "type": "dns", "value": domain }],
+		});
+		let (headers, body) = self.post(&new_order, Some(payload)).await?;
+		let order_url = location(&headers)?;
+		let order: Order = serde_json::from_slice(&body)?;
+
+		// Publish the key authorizations for all challenges, then notify
+		// the ACME server that they are ready to be validated
+		let mut challenges = vec![];
+		for authz_url in order.authorizations.iter() {
+			let authz: Authorization =
+				serde_json::from_slice(&self.post(authz_url, None).await?.1)?;
+			if authz.status == "valid" {
+				continue;
+			}
+			let challenge = authz
+				.challenges
+				.into_iter()
+				.find(|c| c.challenge_type == "http-01")
+				.ok_or_else(|| GarageError::Message("No HTTP-01 challenge offered".into()))?;
+			challenges.push((authz_url.clone(), challenge));
+		}
+
+		if !challenges.is_empty() {
+			*entry = match owned_order(garage, &domain).await? {
+				Some(entry) => entry,
+				None => return Ok(None),
+			};
+			let thumbprint = self.jwk_thumbprint();
+			let mut pending_order = entry
+				.pending_order
+				.get()
+				.clone()
+				.ok_or_else(|| GarageError::Message("Order is not pending".into()))?;
+			for (_, c) in challenges.iter() {
+				pending_order
+					.challenges
+					.insert(c.token.clone(), format!("{}.{}", c.token, thumbprint));
+			}
+			entry.pending_order.update(Some(pending_order));
+			garage.tls_certificate_table.insert(entry).await?;
+			// Leave some time for the challenges to propagate to all nodes
+			tokio::time::sleep(Duration::from_secs(2)).await;
+
+			for (_, c) in challenges.iter() {
+				self.post(&c.url, Some(json!({}))).await?;
+			}
+			for (authz_url, _) in challenges.iter() {
+				self.poll(authz_url, |body| {
+					let authz: Authorization = serde_json::from_slice(body)?;
+					Ok((authz.status != "pending" && authz.status != "processing")
+						.then_some(authz.status))
+				})
+				.await
+				.and_then(|status| match status.as_str() {
+					"valid" => Ok(()),
+					s => Err(GarageError::Message(format!(
+						"Authorization for {} is {}",
+						domain, s
+					))),
+				})?;
+			}
+		}
+
+		// Check again that the order has not been taken over by another node
+		// while the challenges were validated, before a certificate is issued
+		*entry = match owned_order(garage, &domain).await? {
+			Some(entry) => entry,
+			None => return Ok(None),
+		};
+
+		// Finalize order with a CSR for a new key
+		let mut params = rcgen::CertificateParams::new(vec![domain.clone()]);
+		params.distinguished_name = rcgen::DistinguishedName::new();
+		let cert = rcgen::Certificate::from_params(params)
+			.map_err(|e| GarageError::Message(format!("Could not generate key: {}", e)))?;
+		let csr = cert
+			.serialize_request_der()
+			.map_err(|e| GarageError::Message(format!("Could not generate CSR: {}", e)))?;
+		let payload = json!({ "csr": BASE64_URL_SAFE_NO_PAD.encode(csr) });
+		self.post(&order.finalize, Some(payload)).await?;
+
+		let certificate_url = self
+			.poll(&order_url, |body| {
+				let order: Order = serde_json::from_slice(body)?;
+				match order.status.as_str() {
+					"valid" => Ok(Some(order.certificate)),
+					"invalid" => Err(GarageError::Message(format!(
+						"Order for {} is invalid",
+						domain
+					))),
+					_ => Ok(None),
+				}
+			})
+			.await?
+			.ok_or_else(|| GarageError::Message("No certificate URL in valid order".into()))?;
+
+		let (_, cert_chain) = self.post(&certificate_url, None).await?;
+		Ok(Some(CertificateData {
+			cert_chain_pem: String::from_utf8(cert_chain)
+				.map_err(|_| GarageError::Message("Invalid certificate chain".into()))?,
+			encrypted_private_key: seal_private_key(
+				&cipher,
+				&domain,
+				&cert.serialize_private_key_pem(),
+			)?,
+			issued_at: now_msec(),
+		}))
+	}
+
+	/// Fetch an object with POST-as-GET requests until `f` returns a result
+	async fn poll<T, F>(&mut self, url: &str, f: F) -> Result<T, GarageError>
+	where
+		F: Fn(&[u8]) -> Result<Option<T>, GarageError>,
+	{
+		for _ in 0..30 {
+			let (_, body) = self.post(url, None).await?;
+			if let Some(ret) = f(&body)? {
+				return Ok(ret);
+			}
+			tokio::time::sleep(Duration::from_secs(2)).await;
+		}
+		Err(GarageError::Message(format!(
+			"Timeout waiting for ACME object {}",
+			url
+		)))
+	}
+
+	/// Send a signed request to the ACME server. If `payload` is None,
+	/// this is a POST-as-GET request.
+	async fn post(
+		&mut self,
+		url: &str,
+		payload: Option<serde_json::Value>,
+	) -> Result<(hyper::HeaderMap, Vec<u8>), GarageError> {
+		let mut retry_bad_nonce = true;
+		loop {
+			let nonce = match self.nonce.take() {
+				Some(n) => n,
+				None => self.new_nonce().await?,
+			};
+			let body = self.sign(url, &nonce, payload.as_ref())?;
+			let req = Request::builder()
+				.method(Method::POST)
+				.uri(url)
+				.header(CONTENT_TYPE, "application/jose+json")
+				.body(Body::from(body))?;
+			let (status, headers, body) = read_body(self.http.request(req).await?).await?;
+			self.nonce = replay_nonce(&headers);
+
+			if status.is_success() {
+				return Ok((headers, body));
+			}
+			let error = String::from_utf8_lossy(&body);
+			if retry_bad_nonce && error.contains("urn:ietf:params:acme:error:badNonce") {
+				retry_bad_nonce = false;
+				continue;
+			}
+			return Err(GarageError::Message(format!(
+				"ACME request to {} failed with status {}: {}",
+				url, status, error
+			)));
+		}
+	}
+
+	async fn new_nonce(&self) -> Result<String, GarageError> {
+		let req = Request::builder()
+			.method(Method::HEAD)
+			.uri(&self.directory.new_nonce)
+			.body(Body::empty())?;
+		let resp = self.http.request(req).await?;
+		replay_nonce(resp.headers())
+			.ok_or_else(|| GarageError::Message("No nonce returned by ACME server".into()))
+	}
+
+	/// Build a JWS signed with the account key (RFC 8555 section 6.2)
+	fn sign(
+		&self,
+		url: &str,
+		nonce: &str,
+		payload: Option<&serde_json::Value>,
+	) -> Result<String, GarageError> {
+		let protected = if self.account_url.is_empty() {
+			let jwk: serde_json::Value = serde_json::from_str(&self.jwk())?;
+			json!({ "alg": "ES256", "nonce": nonce, "url": url, "jwk": jwk })
+		} else {
+			json!({ "alg": "ES256", "nonce": nonce, "url": url, "kid": self.account_url })
+		};
+		let protected = BASE64_URL_SAFE_NO_PAD.encode(protected.to_string());
+		let payload = match payload {
+			Some(p) => BASE64_URL_SAFE_NO_PAD.encode(p.to_string()),
+			None => String::new(),
+		};
+		let signature = self
+			.key
+			.sign(&self.rng, format!("{}.{}", protected, payload).as_bytes())
+			.map_err(|_| GarageError::Message("Could not sign ACME request".into()))?;
+		Ok(json!({
+			"protected": protected,
+			"payload": payload,
+			"signature": BASE64_URL_SAFE_NO_PAD.encode(signature.as_ref()),
+		})
+		.to_string())
+	}
+
+	/// Public key of the account, as a JSON Web Key
+	fn jwk(&self) -> String {
+		// The public key is an uncompressed point: 0x04 || x || y
+		let public_key = self.key.public_key().as_ref();
+		// Members are in lexicographic order and without whitespace,
+		// as required to compute the thumbprint
+		format!(
+			r#"{{"crv":"P-256","kty":"EC","x":"{}","y":"{}"}}"#,
+			BASE64_URL_SAFE_NO_PAD.encode(&public_key[1..33]),
+			BASE64_URL_SAFE_NO_PAD.encode(&public_key[33..65]),
+		)
+	}
+
+	/// Thumbprint of the account key (RFC 7638), that is part of key authorizations
+	fn jwk_thumbprint(&self) -> String {
+		let digest = ring::digest::digest(&ring::digest::SHA256, self.jwk().as_bytes());
+		BASE64_URL_SAFE_NO_PAD.encode(digest.as_ref())
+	}
+}
+
+async fn read_body(
+	resp: Response<Body>,
+) -> Result<(StatusCode, hyper::HeaderMap, Vec<u8>), GarageError> {
+	let (parts, body) = resp.into_parts();
+	let body = hyper::body::to_bytes(body).await?;
+	Ok((parts.status, parts.headers, body.to_vec()))
+}
+
+fn replay_nonce(headers: &hyper::HeaderMap) -> Option<String> {
+	headers
+		.get("Replay-Nonce")
+		.and_then(|v: &HeaderValue| v.to_str().ok())
+		.map(str::to_string)
+}
+
+fn location(headers: &hyper::HeaderMap) -> Result<String, GarageError> {
+	Ok(headers
+		.get(LOCATION)
+		.ok_or_else(|| GarageError::Message("No Location header in ACME response".into()))?
+		.to_str()?
+		.to_string())
+}
+
+/// Read the table entry of a domain, if its pending order is handled by this node
+async fn owned_order(garage: &Garage, domain: &str) -> Result<Option<TlsCertificate>, GarageError> {
+	let entry = garage
+		.tls_certificate_table
+		.get(&EmptyKey, &domain.to_string())
+		.await?;
+	Ok(entry.filter(|e| matches!(e.pending_order.get(), Some(o) if o.node == garage.system.id)))
+}
+
+/// Write the key of the ACME account to a file that only its owner can read
+fn write_account_key(path: &Path, pkcs8: &[u8]) -> Result<(), GarageError> {
+	let mut options = std::fs::OpenOptions::new();
+	options.write(true).create_new(true);
+	#[cfg(unix)]
+	{
+		use std::os::unix::fs::OpenOptionsExt;
+		options.mode(0o600);
+	}
+	options.open(path)?.write_all(pkcs8)?;
+	Ok(())
+}
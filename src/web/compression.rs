@@ -0,0 +1,189 @@
+use async_compression::tokio::bufread::{BrotliEncoder, GzipEncoder};
+use futures::TryStreamExt;
+use hyper::{
+	header::{
+		HeaderValue, ACCEPT_ENCODING, ACCEPT_RANGES, CONTENT_ENCODING, CONTENT_LENGTH,
+		CONTENT_TYPE, ETAG, VARY,
+	},
+	Body, Method, Request, Response, StatusCode,
+};
+use tokio_util::io::{ReaderStream, StreamReader};
+
+/// Responses smaller than this are not worth compressing
+const MIN_COMPRESS_SIZE: u64 = 1024;
+/// Responses larger than this are not compressed, to bound the CPU time
+/// spent on a single request
+const MAX_COMPRESS_SIZE: u64 = 64 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Encoding {
+	Brotli,
+	Gzip,
+}
+
+impl Encoding {
+	fn as_str(&self) -> &'static str {
+		match self {
+			Encoding::Brotli => "br",
+			Encoding::Gzip => "gzip",
+		}
+	}
+}
+
+/// Compress the body of a response on the fly if the client supports it
+/// and the content is compressible. The `Vary` header is added to all
+/// responses whose content could have been compressed, so that caches
+/// don't serve a compressed response to a client that doesn't support it.
+pub(crate) fn compress_response(req: &Request<Body>, resp: Response<Body>) -> Response<Body> {
+	if resp.status() != StatusCode::OK
+		|| resp.headers().contains_key(CONTENT_ENCODING)
+		|| !resp
+			.headers()
+			.get(CONTENT_TYPE)
+			.and_then(|v| v.to_str().ok())
+			.map(is_compressible)
+			.unwrap_or(false)
+	{
+		return resp;
+	}
+
+	let (mut parts, body) = resp.into_parts();
+	parts
+		.headers
+		.append(VARY, HeaderValue::from_static("Accept-Encoding"));
+
+	let size = parts
+		.headers
+		.get(CONTENT_LENGTH)
+		.and_then(|v| v.to_str().ok())
+		.and_then(|v| v.parse::<u64>().ok());
+	if matches!(size, Some(s) if !(MIN_COMPRESS_SIZE..=MAX_COMPRESS_SIZE).contains(&s)) {
+		return Response::from_parts(parts, body);
+	}
+
+	let encoding = match req
+		.headers()
+		.get(ACCEPT_ENCODING)
+		.and_then(|v| v.to_str().ok())
+		.and_then(negotiate_encoding)
+	{
+		Some(e) => e,
+		None => return Response::from_parts(parts, body),
+	};
+
+	parts.headers.remove(CONTENT_LENGTH);
+	parts.headers.remove(ACCEPT_RANGES);
+	parts.headers.insert(
+		CONTENT_ENCODING,
+		HeaderValue::from_static(encoding.as_str()),
+	);
+	// The compressed representation is not byte-for-byte identical
+	// to the stored object, so its ETag can only be a weak one
+	if let Some(etag) = parts.headers.get(ETAG).and_then(|v| v.to_str().ok()) {
+		if !etag.starts_with("W/") {
+			if let Ok(weak) = HeaderValue::from_str(&format!("W/{}", etag)) {
+				parts.headers.insert(ETAG, weak);
+			}
+		}
+	}
+
+	if req.method() == Method::HEAD {
+		return Response::from_parts(parts, body);
+	}
+
+	let reader =
+		StreamReader::new(body.map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e)));
+	let body = match encoding {
+		Encoding::Brotli => Body::wrap_stream(ReaderStream::new(BrotliEncoder::new(reader))),
+		Encoding::Gzip => Body::wrap_stream(ReaderStream::new(GzipEncoder::new(reader))),
+	};
+	Response::from_parts(parts, body)
+}
+
+/// Returns true for content types that benefit from compression
+fn is_compressible(content_type: &str) -> bool {
+	let mime = content_type
+		.split(';')
+		.next()
+		.unwrap_or("")
+		.trim()
+		.to_ascii_lowercase();
+	mime.starts_with("text/")
+		|| mime.ends_with("+json")
+		|| mime.ends_with("+xml")
+		|| matches!(
+			mime.as_str(),
+			"application/json"
+				| "application/javascript"
+				| "application/x-javascript"
+				| "application/xml"
+				| "application/wasm"
+				| "font/ttf" | "font/otf"
+				| "image/bmp"
+				| "image/x-icon"
+				| "image/vnd.microsoft.icon"
+		)
+}
+
+/// Select the preferred encoding among those accepted by the client,
+/// according to the quality values of the Accept-Encoding header.
+/// Brotli is preferred over gzip when both have the same quality.
+pub(crate) fn negotiate_encoding(accept_encoding: &str) -> Option<Encoding> {
+	let mut br_q = None;
+	let mut gzip_q = None;
+	let mut star_q = None;
+
+	for item in accept_encoding.split(',') {
+		let mut params = item.split(';');
+		let coding = params.next().unwrap_or("").trim().to_ascii_lowercase();
+		let q = params
+			.filter_map(|p| p.trim().strip_prefix("q="))
+			.next()
+			.map(|q| q.trim().parse::<f32>().unwrap_or(0.0))
+			.unwrap_or(1.0);
+		match coding.as_str() {
+			"br" => br_q = Some(q),
+			"gzip" | "x-gzip" => gzip_q = Some(q),
+			"*" => star_q = Some(q),
+			_ => (),
+		}
+	}
+
+	let br_q = br_q.or(star_q).unwrap_or(0.0);
+	let gzip_q = gzip_q.or(star_q).unwrap_or(0.0);
+	if br_q > 0.0 && br_q >= gzip_q {
+		Some(Encoding::Brotli)
+	} else if gzip_q > 0.0 {
+		Some(Encoding::Gzip)
+	} else {
+		None
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_negotiate_encoding() {
+		assert_eq!(
+			negotiate_encoding("gzip, deflate, br"),
+			Some(Encoding::Brotli)
+		);
+		assert_eq!(negotiate_encoding("gzip"), Some(Encoding::Gzip));
+		assert_eq!(negotiate_encoding("br;q=0.5, gzip"), Some(Encoding::Gzip));
+		assert_eq!(negotiate_encoding("gzip;q=0, br;q=0"), None);
+		assert_eq!(negotiate_encoding("identity"), None);
+		assert_eq!(negotiate_encoding("*"), Some(Encoding::Brotli));
+		assert_eq!(negotiate_encoding("*;q=0.1, br;q=0"), Some(Encoding::Gzip));
+	}
+
+	#[test]
+	fn test_is_compressible() {
+		assert!(is_compressible("text/html; charset=utf-8"));
+		assert!(is_compressible("application/JSON"));
+		assert!(is_compressible("image/svg+xml"));
+		assert!(!is_compressible("image/png"));
+		assert!(!is_compressible("application/octet-stream"));
+	}
+}
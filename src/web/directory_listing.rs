@@ -0,0 +1,256 @@
+use std::fmt::Write;
+
+use hyper::{header::ACCEPT, Body, Request, Response, StatusCode};
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+use serde::Serialize;
+
+use garage_model::garage::Garage;
+use garage_model::helper::object::current_version;
+use garage_model::s3::object_table::*;
+
+use garage_table::*;
+use garage_util::data::Uuid;
+use garage_util::time::msec_to_rfc3339;
+
+use crate::error::*;
+
+/// Number of objects fetched from the object table at once
+const LIST_BATCH_SIZE: usize = 1000;
+/// Maximum number of entries shown in a listing
+const MAX_LISTING_ENTRIES: usize = 10000;
+
+/// Characters to escape when building a link to an object
+pub(crate) const PATH_ENCODE_SET: &AsciiSet = &CONTROLS
+	.add(b' ')
+	.add(b'"')
+	.add(b'#')
+	.add(b'%')
+	.add(b'<')
+	.add(b'>')
+	.add(b'?')
+	.add(b'`')
+	.add(b'{')
+	.add(b'}');
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DirectoryListing {
+	prefix: String,
+	directories: Vec<String>,
+	files: Vec<FileEntry>,
+	truncated: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FileEntry {
+	name: String,
+	size: u64,
+	last_modified: String,
+}
+
+/// Answer with a listing of the objects and sub-directories under `prefix`.
+/// The listing is returned as JSON if the client accepts `application/json`,
+/// and as a HTML page otherwise.
+pub async fn handle_directory_listing(
+	garage: &Garage,
+	req: &Request<Body>,
+	bucket_id: Uuid,
+	prefix: &str,
+) -> Result<Response<Body>, Error> {
+	let listing = list_directory(garage, bucket_id, prefix).await?;
+
+	// An empty directory does not exist
+	if !prefix.is_empty() && listing.directories.is_empty() && listing.files.is_empty() {
+		return Err(Error::NotFound);
+	}
+
+	let wants_json = req
+		.headers()
+		.get(ACCEPT)
+		.and_then(|v| v.to_str().ok())
+		.map(|v| v.contains("application/json"))
+		.unwrap_or(false);
+
+	let (content_type, body) = if wants_json {
+		let body = serde_json::to_string_pretty(&listing)
+			.map_err(|e| Error::BadRequest(format!("Cannot serialize listing: {}", e)))?;
+		("application/json", body)
+	} else {
+		("text/html; charset=utf-8", render_html(&listing))
+	};
+
+	Ok(Response::builder()
+		.status(StatusCode::OK)
+		.header("Content-Type", content_type)
+		.body(Body::from(body))
+		.unwrap())
+}
+
+async fn list_directory(
+	garage: &Garage,
+	bucket_id: Uuid,
+	prefix: &str,
+) -> Result<DirectoryListing, Error> {
+	let mut listing = DirectoryListing {
+		prefix: prefix.to_string(),
+		directories: vec![],
+		files: vec![],
+		truncated: false,
+	};
+
+	let mut cursor = prefix.to_string();
+	'batches: loop {
+		let objects = garage
+			.object_table
+			.get_range(
+				&bucket_id,
+				Some(cursor.clone()),
+				Some(ObjectFilter::IsData),
+				LIST_BATCH_SIZE,
+				EnumerationOrder::Forward,
+			)
+			.await?;
+		let batch_len = objects.len();
+
+		for object in objects {
+			if !object.key.starts_with(prefix) {
+				break 'batches;
+			}
+			if listing.directories.len() + listing.files.len() >= MAX_LISTING_ENTRIES {
+				listing.truncated = true;
+				break 'batches;
+			}
+
+			let name = &object.key[prefix.len()..];
+			match name.find('/') {
+				Some(i) => {
+					// Skip over all the objects of this sub-directory:
+					// '0' is the character that comes right after '/'
+					listing.directories.push(name[..=i].to_string());
+					cursor = format!("{}{}0", prefix, &name[..i]);
+					continue 'batches;
+				}
+				None => {
+					// The object is listed with the size of its last complete
+					// version, unless that version is a delete marker
+					if let Some((v, meta)) = current_version(&object) {
+						listing.files.push(FileEntry {
+							name: name.to_string(),
+							size: meta.size,
+							last_modified: msec_to_rfc3339(v.timestamp),
+						});
+					}
+					// Smallest key that comes after this one
+					cursor = format!("{}\0", object.key);
+				}
+			}
+		}
+
+		if batch_len < LIST_BATCH_SIZE {
+			break;
+		}
+	}
+
+	Ok(listing)
+}
+
+fn render_html(listing: &DirectoryListing) -> String {
+	let title = html_escape(&format!("/{}", listing.prefix));
+
+	let mut html = String::new();
+	writeln!(html, "<!DOCTYPE html>").unwrap();
+	writeln!(html, "<html><head><meta charset=\"utf-8\">").unwrap();
+	writeln!(html, "<title>Index of {}</title></head>", title).unwrap();
+	writeln!(html, "<body><h1>Index of {}</h1>", title).unwrap();
+	writeln!(
+		html,
+		"<table><tr><th>Name</th><th>Size</th><th>Last modified</th></tr>"
+	)
+	.unwrap();
+	if !listing.prefix.is_empty() {
+		writeln!(
+			html,
+			"<tr><td><a href=\"../\">../</a></td><td></td><td></td></tr>"
+		)
+		.unwrap();
+	}
+	for dir in listing.directories.iter() {
+		writeln!(
+			html,
+			"<tr><td><a href=\"{}\">{}</a></td><td>-</td><td></td></tr>",
+			html_escape(&utf8_percent_encode(dir, PATH_ENCODE_SET).to_string()),
+			html_escape(dir)
+		)
+		.unwrap();
+	}
+	for file in listing.files.iter() {
+		writeln!(
+			html,
+			"<tr><td><a href=\"{}\">{}</a></td><td>{}</td><td>{}</td></tr>",
+			html_escape(&utf8_percent_encode(&file.name, PATH_ENCODE_SET).to_string()),
+			html_escape(&file.name),
+			file.size,
+			file.last_modified
+		)
+		.unwrap();
+	}
+	writeln!(html, "</table>").unwrap();
+	if listing.truncated {
+		writeln!(
+			html,
+			"<p>This listing was truncated to {} entries.</p>",
+			MAX_LISTING_ENTRIES
+		)
+		.unwrap();
+	}
+	writeln!(html, "</body></html>").unwrap();
+	html
+}
+
+fn html_escape(s: &str) -> String {
+	let mut ret = String::with_capacity(s.len());
+	for c in s.chars() {
+		match c {
+			'&' => ret.push_str("&amp;"),
+			'<' => ret.push_str("&lt;"),
+			'>' => ret.push_str("&gt;"),
+			'"' => ret.push_str("&quot;"),
+			'\'' => ret.push_str("&#39;"),
+			c => ret.push(c),
+		}
+	}
+	ret
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_html_escape() {
+		assert_eq!(
+			html_escape("<a href=\"x\">Tom & Jerry's</a>"),
+			"&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&#39;s&lt;/a&gt;"
+		);
+	}
+
+	#[test]
+	fn test_render_html() {
+		let listing = DirectoryListing {
+			prefix: "pub/".to_string(),
+			directories: vec!["sub dir/".to_string()],
+			files: vec![FileEntry {
+				name: "a<b>.txt".to_string(),
+				size: 12,
+				last_modified: "2023-01-01T00:00:00.000Z".to_string(),
+			}],
+			truncated: false,
+		};
+		let html = render_html(&listing);
+		assert!(html.contains("<title>Index of /pub/</title>"));
+		assert!(html.contains("<a href=\"../\">../</a>"));
+		assert!(html.contains("<a href=\"sub%20dir/\">sub dir/</a>"));
+		assert!(html.contains("<a href=\"a%3Cb%3E.txt\">a&lt;b&gt;.txt</a>"));
+	}
+}
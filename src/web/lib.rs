@@ -5,5 +5,11 @@ extern crate tracing;
 mod error;
 pub use error::Error;
 
+mod acme;
+mod compression;
+mod directory_listing;
+mod tls;
+
 mod web_server;
+pub use acme::AcmeWorker;
 pub use web_server::WebServer;
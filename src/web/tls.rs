@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305, NONCE_LEN};
+use ring::hkdf;
+use ring::rand::{SecureRandom, SystemRandom};
+use rustls::server::{ClientHello, ResolvesServerCert};
+use rustls::sign::CertifiedKey;
+
+use garage_model::garage::Garage;
+use garage_model::tls_certificate_table::*;
+use garage_table::*;
+use garage_util::error::{Error as GarageError, OkOrMessage};
+
+/// Context of the derivation of the key that encrypts the private keys
+/// of the certificates stored in the TLS certificate table
+const PRIVATE_KEY_ENCRYPTION_INFO: &[u8] = b"garage tls certificate private key";
+
+/// Selects the certificate to present to clients based on the server name
+/// they indicate (SNI), among the certificates of the TLS certificate table.
+pub(crate) struct CertResolver {
+	garage: Arc<Garage>,
+	cipher: LessSafeKey,
+	certs: RwLock<HashMap<String, Arc<CertifiedKey>>>,
+}
+
+impl CertResolver {
+	pub(crate) fn new(garage: Arc<Garage>) -> Result<Self, GarageError> {
+		let cipher = private_key_cipher(&garage)?;
+		Ok(Self {
+			garage,
+			cipher,
+			certs: RwLock::new(HashMap::new()),
+		})
+	}
+
+	/// Load the certificates currently stored in the table
+	pub(crate) async fn reload(&self) -> Result<(), GarageError> {
+		let mut certs = HashMap::new();
+
+		let mut start = None;
+		loop {
+			let entries = self
+				.garage
+				.tls_certificate_table
+				.get_range(
+					&EmptyKey,
+					start.clone(),
+					None,
+					1000,
+					EnumerationOrder::Forward,
+				)
+				.await?;
+			let n_entries = entries.len();
+			start = entries.last().map(|e| e.domain().to_string());
+
+			for entry in entries {
+				if let Some(data) = entry.certificate.get() {
+					match certified_key(&self.cipher, entry.domain(), data) {
+						Some(ck) => {
+							certs.insert(entry.domain().to_string(), Arc::new(ck));
+						}
+						None => warn!("Invalid TLS certificate stored for {}", entry.domain()),
+					}
+				}
+			}
+
+			if n_entries < 1000 {
+				break;
+			}
+		}
+
+		*self.certs.write().unwrap() = certs;
+		Ok(())
+	}
+}
+
+impl ResolvesServerCert for CertResolver {
+	fn resolve(&self, client_hello: ClientHello) -> Option<Arc<CertifiedKey>> {
+		let server_name = client_hello.server_name()?.to_ascii_lowercase();
+		self.certs.read().unwrap().get(&server_name).cloned()
+	}
+}
+
+/// Key used to encrypt the private keys of the certificates, derived
+/// from the RPC secret that is shared by all nodes of the cluster
+pub(crate) fn private_key_cipher(garage: &Garage) -> Result<LessSafeKey, GarageError> {
+	let rpc_secret = garage
+		.config
+		.rpc_secret
+		.as_deref()
+		.ok_or_message("No RPC secret is configured")?;
+	Ok(derive_cipher(rpc_secret))
+}
+
+fn derive_cipher(rpc_secret: &str) -> LessSafeKey {
+	let prk = hkdf::Salt::new(hkdf::HKDF_SHA256, &[]).extract(rpc_secret.as_bytes());
+	let okm = prk
+		.expand(&[PRIVATE_KEY_ENCRYPTION_INFO], &CHACHA20_POLY1305)
+		.expect("valid key length");
+	LessSafeKey::new(UnboundKey::from(okm))
+}
+
+/// Encrypt the private key of the certificate of a domain. The result is
+/// the random nonce followed by the ciphertext, which is authenticated
+/// together with the domain name.
+pub(crate) fn seal_private_key(
+	cipher: &LessSafeKey,
+	domain: &str,
+	private_key_pem: &str,
+) -> Result<Vec<u8>, GarageError> {
+	let mut nonce = [0u8; NONCE_LEN];
+	SystemRandom::new()
+		.fill(&mut nonce)
+		.map_err(|_| GarageError::Message("Could not generate nonce".into()))?;
+
+	let mut in_out = private_key_pem.as_bytes().to_vec();
+	cipher
+		.seal_in_place_append_tag(
+			Nonce::assume_unique_for_key(nonce),
+			Aad::from(domain.as_bytes()),
+			&mut in_out,
+		)
+		.map_err(|_| GarageError::Message("Could not encrypt private key".into()))?;
+
+	let mut ret = nonce.to_vec();
+	ret.extend(in_out);
+	Ok(ret)
+}
+
+/// Decrypt the private key of the certificate of a domain
+pub(crate) fn open_private_key(cipher: &LessSafeKey, domain: &str, data: &[u8]) -> Option<Vec<u8>> {
+	if data.len() < NONCE_LEN {
+		return None;
+	}
+	let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+	let nonce = Nonce::try_assume_unique_for_key(nonce).ok()?;
+	let mut in_out = ciphertext.to_vec();
+	let plaintext = cipher
+		.open_in_place(nonce, Aad::from(domain.as_bytes()), &mut in_out)
+		.ok()?;
+	Some(plaintext.to_vec())
+}
+
+fn certified_key(
+	cipher: &LessSafeKey,
+	domain: &str,
+	data: &CertificateData,
+) -> Option<CertifiedKey> {
+	let certs = rustls_pemfile::certs(&mut data.cert_chain_pem.as_bytes())
+		.ok()?
+		.into_iter()
+		.map(rustls::Certificate)
+		.collect::<Vec<_>>();
+	let private_key_pem = open_private_key(cipher, domain, &data.encrypted_private_key)?;
+	let key = rustls_pemfile::pkcs8_private_keys(&mut &private_key_pem[..])
+		.ok()?
+		.into_iter()
+		.next()?;
+	let key = rustls::sign::any_supported_type(&rustls::PrivateKey(key)).ok()?;
+	if certs.is_empty() {
+		return None;
+	}
+	Some(CertifiedKey::new(certs, key))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_certified_key() {
+		let cipher = derive_cipher("secret");
+		let cert = rcgen::generate_simple_self_signed(vec!["example.com".to_string()]).unwrap();
+		let data = CertificateData {
+			cert_chain_pem: cert.serialize_pem().unwrap(),
+			encrypted_private_key: seal_private_key(
+				&cipher,
+				"example.com",
+				&cert.serialize_private_key_pem(),
+			)
+			.unwrap(),
+			issued_at: 0,
+		};
+		let ck = certified_key(&cipher, "example.com", &data).unwrap();
+		assert_eq!(ck.cert.len(), 1);
+
+		// The key can only be decrypted with the same secret, for the same domain
+		assert!(certified_key(&derive_cipher("other secret"), "example.com", &data).is_none());
+		assert!(certified_key(&cipher, "example.org", &data).is_none());
+
+		let invalid = CertificateData {
+			cert_chain_pem: String::new(),
+			..data
+		};
+		assert!(certified_key(&cipher, "example.com", &invalid).is_none());
+	}
+}
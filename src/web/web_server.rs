@@ -1,11 +1,14 @@
 use std::fs::{self, Permissions};
 use std::os::unix::prelude::PermissionsExt;
+use std::time::{Duration, SystemTime};
 use std::{convert::Infallible, sync::Arc};
 
 use futures::future::Future;
+use futures::TryStreamExt;
 
 use hyper::{
-	header::{HeaderValue, HOST},
+	body::HttpBody,
+	header::{HeaderValue, CACHE_CONTROL, EXPIRES, HOST},
 	server::conn::AddrStream,
 	service::{make_service_fn, service_fn},
 	Body, Method, Request, Response, Server, StatusCode,
@@ -22,28 +25,42 @@ use opentelemetry::{
 	Context, KeyValue,
 };
 
+use crate::acme::{handle_acme_challenge, ACME_CHALLENGE_PREFIX};
+use crate::compression::compress_response;
+use crate::directory_listing::{handle_directory_listing, PATH_ENCODE_SET};
 use crate::error::*;
+use crate::tls::CertResolver;
 
 use garage_api::helpers::{authority_to_host, host_to_bucket};
+use garage_api::https::{load_tls_config, serve_https, set_host_header};
 use garage_api::s3::cors::{add_cors_headers, find_matching_cors_rule, handle_options_for_bucket};
 use garage_api::s3::error::{
 	CommonErrorDerivative, Error as ApiError, OkOrBadRequest, OkOrInternalError,
 };
 use garage_api::s3::get::{handle_get, handle_head};
+use garage_api::s3::website::check_website_url_signature;
 
+use garage_model::bucket_table::{
+	RedirectReplaceKey, RoutingRule, WebsiteConfig, MAX_WEBSITE_EXPIRES_AFTER,
+};
 use garage_model::garage::Garage;
 
 use garage_table::*;
+use garage_util::config::{AcmeConfig, TlsConfig};
 use garage_util::data::Uuid;
 use garage_util::error::Error as GarageError;
 use garage_util::forwarded_headers;
 use garage_util::metrics::{gen_trace_id, RecordDuration};
-use garage_util::socket_address::UnixOrTCPSocketAddress;
+use garage_util::socket_address::{bind_tcp_listener, UnixOrTCPSocketAddress};
+
+/// Interval between two reloads of the TLS certificates from the table
+const CERT_RELOAD_INTERVAL: Duration = Duration::from_secs(60);
 
 struct WebMetrics {
 	request_counter: Counter<u64>,
 	error_counter: Counter<u64>,
 	request_duration: ValueRecorder<f64>,
+	response_bytes: Counter<u64>,
 }
 
 impl WebMetrics {
@@ -62,14 +79,25 @@ impl WebMetrics {
 				.f64_value_recorder("web.request_duration")
 				.with_description("Duration of requests to the web endpoint")
 				.init(),
+			response_bytes: meter
+				.u64_counter("web.response_bytes")
+				.with_description("Bytes sent in the body of responses of the web endpoint")
+				.init(),
 		}
 	}
 }
 
+/// Bucket whose website a request to the web endpoint was routed to
+struct ResolvedBucket {
+	name: String,
+	id: Uuid,
+}
+
 pub struct WebServer {
 	garage: Arc<Garage>,
 	metrics: Arc<WebMetrics>,
 	root_domain: String,
+	compression: bool,
 }
 
 impl WebServer {
@@ -78,14 +106,10 @@ impl WebServer {
 		garage: Arc<Garage>,
 		addr: UnixOrTCPSocketAddress,
 		root_domain: String,
+		compression: bool,
 		shutdown_signal: impl Future<Output = ()>,
 	) -> Result<(), GarageError> {
-		let metrics = Arc::new(WebMetrics::new());
-		let web_server = Arc::new(WebServer {
-			garage,
-			metrics,
-			root_domain,
-		});
+		let web_server = Self::new(garage, root_domain, compression);
 
 		let tcp_service = make_service_fn(|conn: &AddrStream| {
 			let web_server = web_server.clone();
@@ -117,7 +141,7 @@ impl WebServer {
 
 		match addr {
 			UnixOrTCPSocketAddress::TCPSocket(addr) => {
-				Server::bind(&addr)
+				Server::from_tcp(bind_tcp_listener(addr)?)?
 					.serve(tcp_service)
 					.with_graceful_shutdown(shutdown_signal)
 					.await?
@@ -141,11 +165,88 @@ impl WebServer {
 		Ok(())
 	}
 
+	/// Run a web server serving HTTPS, with certificates obtained with ACME
+	pub async fn run_tls(
+		garage: Arc<Garage>,
+		acme_config: AcmeConfig,
+		root_domain: String,
+		compression: bool,
+		shutdown_signal: impl Future<Output = ()>,
+	) -> Result<(), GarageError> {
+		let resolver = Arc::new(CertResolver::new(garage.clone())?);
+		let web_server = Self::new(garage, root_domain, compression);
+
+		let mut tls_config = rustls::ServerConfig::builder()
+			.with_safe_defaults()
+			.with_no_client_auth()
+			.with_cert_resolver(resolver.clone());
+		tls_config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+		info!(
+			"Web server listening on {} (HTTPS)",
+			acme_config.tls_bind_addr
+		);
+
+		let reload_certs = async {
+			let mut reload_interval = tokio::time::interval(CERT_RELOAD_INTERVAL);
+			loop {
+				reload_interval.tick().await;
+				if let Err(e) = resolver.reload().await {
+					warn!("Could not load TLS certificates: {}", e);
+				}
+			}
+		};
+
+		tokio::select! {
+			res = serve_https(
+				acme_config.tls_bind_addr,
+				Arc::new(tls_config),
+				false,
+				move |req, addr| web_server.clone().handle_request(req, addr),
+				shutdown_signal,
+			) => res,
+			_ = reload_certs => unreachable!(),
+		}
+	}
+
+	/// Run a web server serving HTTPS, with a static certificate
+	pub async fn run_https(
+		garage: Arc<Garage>,
+		tls_config: TlsConfig,
+		root_domain: String,
+		compression: bool,
+		shutdown_signal: impl Future<Output = ()>,
+	) -> Result<(), GarageError> {
+		let web_server = Self::new(garage, root_domain, compression);
+		let server_config = Arc::new(load_tls_config(&tls_config)?);
+
+		info!("Web server listening on {} (HTTPS)", tls_config.bind_addr);
+
+		serve_https(
+			tls_config.bind_addr,
+			server_config,
+			tls_config.http3,
+			move |req, addr| web_server.clone().handle_request(req, addr),
+			shutdown_signal,
+		)
+		.await
+	}
+
+	fn new(garage: Arc<Garage>, root_domain: String, compression: bool) -> Arc<Self> {
+		Arc::new(WebServer {
+			garage,
+			metrics: Arc::new(WebMetrics::new()),
+			root_domain,
+			compression,
+		})
+	}
+
 	async fn handle_request(
 		self: Arc<Self>,
-		req: Request<Body>,
+		mut req: Request<Body>,
 		addr: String,
 	) -> Result<Response<Body>, Infallible> {
+		set_host_header(&mut req);
 		if let Ok(forwarded_for_ip_addr) =
 			forwarded_headers::handle_forwarded_for_headers(req.headers())
 		{
@@ -160,6 +261,13 @@ impl WebServer {
 			info!("{} {} {}", addr, req.method(), req.uri());
 		}
 
+		let _in_flight = self.garage.in_flight_requests.start(
+			"Web",
+			req.method().to_string(),
+			req.uri().to_string(),
+			addr,
+		);
+
 		// Lots of instrumentation
 		let tracer = opentelemetry::global::tracer("garage");
 		let span = tracer
@@ -174,23 +282,55 @@ impl WebServer {
 		let metrics_tags = &[KeyValue::new("method", req.method().to_string())];
 
 		// The actual handler
+		let mut resolved = None;
 		let res = self
-			.serve_file(&req)
+			.serve_file(&req, &mut resolved)
 			.with_context(Context::current_with_span(span))
 			.record_duration(&self.metrics.request_duration, &metrics_tags[..])
 			.await;
 
 		// More instrumentation
-		self.metrics.request_counter.add(1, &metrics_tags[..]);
+		let status = match &res {
+			Ok(res) => res.status(),
+			Err(error) => error.http_status_code(),
+		};
+		let status_class = format!("{}xx", status.as_u16() / 100);
+		// Requests whose host is not that of a website are not labelled
+		// with it, so that the number of label values remains bounded
+		let bucket_name = resolved
+			.as_ref()
+			.map(|b| b.name.clone())
+			.unwrap_or_else(|| "-".to_string());
+		self.metrics.request_counter.add(
+			1,
+			&[
+				metrics_tags[0].clone(),
+				KeyValue::new("bucket", bucket_name.clone()),
+				KeyValue::new("status_class", status_class.clone()),
+			],
+		);
+		if let Some(b) = &resolved {
+			let is_error = status.is_client_error() || status.is_server_error();
+			self.garage.web_usage.record(b.id, 1, is_error as u64, 0);
+		}
 
 		// Returning the result
-		match res {
+		let resp = match res {
 			Ok(res) => {
-				debug!("{} {} {}", req.method(), res.status(), req.uri());
-				Ok(res)
+				debug!(
+					bucket = %bucket_name,
+					status_class = %status_class,
+					"{} {} {}",
+					req.method(),
+					res.status(),
+					req.uri()
+				);
+				res
 			}
 			Err(error) => {
 				info!(
+					bucket = %bucket_name,
+					status_class = %status_class,
 					"{} {} {} {}",
 					req.method(),
 					error.http_status_code(),
@@ -201,12 +341,41 @@ impl WebServer {
 					1,
 					&[
 						metrics_tags[0].clone(),
+						KeyValue::new("bucket", bucket_name.clone()),
 						KeyValue::new("status_code", error.http_status_code().to_string()),
 					],
 				);
-				Ok(error_to_res(error))
+				error_to_res(error)
 			}
+		};
+		Ok(self.count_response_bytes(resp, bucket_name, resolved.map(|b| b.id)))
+	}
+
+	/// Account the bytes of the body of a response in the metrics,
+	/// and in the usage counters of the website it is sent for
+	fn count_response_bytes(
+		&self,
+		resp: Response<Body>,
+		bucket_name: String,
+		bucket_id: Option<Uuid>,
+	) -> Response<Body> {
+		let metrics = self.metrics.clone();
+		let garage = self.garage.clone();
+		let tags = [KeyValue::new("bucket", bucket_name)];
+		let record = move |bytes: u64| {
+			metrics.response_bytes.add(bytes, &tags);
+			if let Some(id) = bucket_id {
+				garage.web_usage.record(id, 0, 0, bytes);
+			}
+		};
+
+		if let Some(size) = HttpBody::size_hint(resp.body()).exact() {
+			record(size);
+			return resp;
 		}
+		let (parts, body) = resp.into_parts();
+		let body = body.inspect_ok(move |chunk| record(chunk.len() as u64));
+		Response::from_parts(parts, Body::wrap_stream(body))
 	}
 
 	async fn check_key_exists(self: &Arc<Self>, bucket_id: Uuid, key: &str) -> Result<bool, Error> {
@@ -220,7 +389,11 @@ impl WebServer {
 		Ok(exists)
 	}
 
-	async fn serve_file(self: &Arc<Self>, req: &Request<Body>) -> Result<Response<Body>, Error> {
+	async fn serve_file(
+		self: &Arc<Self>,
+		req: &Request<Body>,
+		resolved: &mut Option<ResolvedBucket>,
+	) -> Result<Response<Body>, Error> {
 		// Get http authority string (eg. [::1]:3902 or garage.tld:80)
 		let authority = req
 			.headers()
@@ -231,6 +404,16 @@ impl WebServer {
 		// Get bucket
 		let host = authority_to_host(authority)?;
 
+		// Answer validation requests of the ACME server
+		if let Some(token) = req.uri().path().strip_prefix(ACME_CHALLENGE_PREFIX) {
+			let acme_enabled = matches!(&self.garage.config.s3_web, Some(c) if c.acme.is_some());
+			if acme_enabled {
+				if let Some(resp) = handle_acme_challenge(&self.garage, &host, token).await? {
+					return Ok(resp);
+				}
+			}
+		}
+
 		let bucket_name = host_to_bucket(&host, &self.root_domain).unwrap_or(&host);
 		let bucket_id = self
 			.garage
@@ -255,21 +438,72 @@ impl WebServer {
 			.get()
 			.as_ref()
 			.ok_or(Error::NotFound)?;
+		*resolved = Some(ResolvedBucket {
+			name: bucket_name.to_string(),
+			id: bucket_id,
+		});
+
+		// Preflight requests are answered from the CORS rules of the bucket
+		// for all paths, they are never redirected
+		if *req.method() == Method::OPTIONS {
+			return Ok(handle_options_for_bucket(req, &bucket)?);
+		}
+		let cors_rule = find_matching_cors_rule(&bucket, req)?;
+		let with_cors = |mut resp: Response<Body>| {
+			if let Some(rule) = &cors_rule {
+				add_cors_headers(&mut resp, rule);
+			}
+			resp
+		};
 
 		// Get path
 		let path = req.uri().path().to_string();
 		let index = &website_config.index_document;
 		let (key, may_redirect) = path_to_keys(&path, index)?;
 
+		// Prefix for which a directory listing can be generated,
+		// if the path is that of a directory
+		let dir_prefix = if path.ends_with('/') {
+			key.strip_suffix(index.as_str())
+		} else {
+			None
+		};
+
 		debug!(
 			"Selected bucket: \"{}\" {:?}, target key: \"{}\", may redirect to: {:?}",
 			bucket_name, bucket_id, key, may_redirect
 		);
 
-		let ret_doc = match *req.method() {
-			Method::OPTIONS => handle_options_for_bucket(req, &bucket),
-			Method::HEAD => handle_head(self.garage.clone(), req, bucket_id, &key, None).await,
-			Method::GET => handle_get(self.garage.clone(), req, bucket_id, &key, None).await,
+		// Routing rules are evaluated on the requested key,
+		// before the index document is appended
+		let req_key = percent_encoding::percent_decode_str(&path)
+			.decode_utf8()?
+			.trim_start_matches('/')
+			.to_string();
+		let routing_rules = &website_config.routing_rules;
+		if let Some(rule) = find_routing_rule(routing_rules, &req_key, None) {
+			return routing_rule_redirect(rule, &req_key, &host).map(with_cors);
+		}
+
+		// Objects that can only be read with a signed URL are not served
+		// if the signature of the request is missing or invalid.
+		// The served key is checked as well, as it can have the index
+		// document appended to the requested key.
+		let signature_check = match bucket.website_signing() {
+			Some(signing) => {
+				check_website_url_signature(&signing, &req_key, &key, req.uri().query())
+			}
+			None => Ok(()),
+		};
+
+		let ret_doc = match (signature_check, req.method()) {
+			(Err(e), _) => Err(e),
+			(Ok(()), &Method::HEAD) => {
+				handle_head(self.garage.clone(), req, &bucket, &key, None).await
+			}
+			(Ok(()), &Method::GET) => {
+				handle_get(self.garage.clone(), req, &bucket, &key, None).await
+			}
 			_ => Err(ApiError::bad_request("HTTP method not supported")),
 		};
 
@@ -287,24 +521,40 @@ impl WebServer {
 			_ => ret_doc,
 		};
 
-		match ret_doc_with_redir.map_err(Error::from) {
+		// Generate a directory listing if there is no index document
+		let ret_doc_with_listing = match (ret_doc_with_redir.map_err(Error::from), dir_prefix) {
+			(Err(Error::ApiError(ApiError::NoSuchKey)), Some(prefix))
+				if website_config.directory_listing
+					&& (*req.method() == Method::GET || *req.method() == Method::HEAD) =>
+			{
+				handle_directory_listing(&self.garage, req, bucket_id, prefix).await
+			}
+			(ret, _) => ret,
+		};
+
+		match ret_doc_with_listing {
 			Err(error) => {
-				// For a HEAD or OPTIONS method, and for non-4xx errors,
-				// we don't return the error document as content,
-				// we return above and just return the error message
-				// by relying on err_to_res that is called when we return an Err.
-				if *req.method() == Method::HEAD
-					|| *req.method() == Method::OPTIONS
-					|| !error.http_status_code().is_client_error()
+				// Apply routing rules conditioned on the returned error code
+				if let Some(rule) =
+					find_routing_rule(routing_rules, &req_key, Some(error.http_status_code()))
 				{
+					return routing_rule_redirect(rule, &req_key, &host).map(with_cors);
+				}
+
+				// For a HEAD method, we don't return the error document
+				// as content, we return above and just return the error message
+				// by relying on err_to_res that is called when we return an Err.
+				if *req.method() == Method::HEAD {
 					return Err(error);
 				}
 
-				// If no error document is set: just return the error directly
-				let error_document = match &website_config.error_document {
-					Some(ed) => ed.trim_start_matches('/').to_owned(),
-					None => return Err(error),
-				};
+				// If no error document is set for this status code:
+				// just return the error directly
+				let error_document =
+					match find_error_document(website_config, error.http_status_code()) {
+						Some(ed) => ed.trim_start_matches('/').to_owned(),
+						None => return Err(error),
+					};
 
 				// We want to return the error document
 				// Create a fake HTTP request with path = the error document
@@ -313,8 +563,7 @@ impl WebServer {
 					.body(Body::empty())
 					.unwrap();
 
-				match handle_get(self.garage.clone(), &req2, bucket_id, &error_document, None).await
-				{
+				match handle_get(self.garage.clone(), &req2, &bucket, &error_document, None).await {
 					Ok(mut error_doc) => {
 						// The error won't be logged back in handle_request,
 						// so log it here
@@ -335,7 +584,7 @@ impl WebServer {
 							}
 						}
 
-						Ok(error_doc)
+						Ok(with_cors(error_doc))
 					}
 					Err(error_doc_error) => {
 						warn!(
@@ -346,11 +595,11 @@ impl WebServer {
 					}
 				}
 			}
-			Ok(mut resp) => {
-				// Maybe add CORS headers
-				if let Some(rule) = find_matching_cors_rule(&bucket, req)? {
-					add_cors_headers(&mut resp, rule)
-						.ok_or_internal_error("Invalid bucket CORS configuration")?;
+			Ok(resp) => {
+				let mut resp = with_cors(resp);
+				add_default_cache_headers(&mut resp, website_config);
+				if self.compression {
+					resp = compress_response(req, resp);
 				}
 				Ok(resp)
 			}
@@ -373,6 +622,100 @@ fn error_to_res(e: Error) -> Response<Body> {
 	http_error
 }
 
+/// Add the bucket's default Cache-Control and Expires headers to a response,
+/// if the object doesn't define its own.
+fn add_default_cache_headers(resp: &mut Response<Body>, website_config: &WebsiteConfig) {
+	if !(resp.status().is_success() || resp.status() == StatusCode::NOT_MODIFIED) {
+		return;
+	}
+	let headers = resp.headers_mut();
+	if let Some(cc) = &website_config.cache_control {
+		if !headers.contains_key(CACHE_CONTROL) {
+			if let Ok(v) = HeaderValue::from_str(cc) {
+				headers.insert(CACHE_CONTROL, v);
+			}
+		}
+	}
+	if let Some(secs) = website_config.expires_after {
+		// Values written before the maximum was enforced are clamped
+		let expires =
+			SystemTime::now().checked_add(Duration::from_secs(secs.min(MAX_WEBSITE_EXPIRES_AFTER)));
+		if let (false, Some(expires)) = (headers.contains_key(EXPIRES), expires) {
+			if let Ok(v) = HeaderValue::from_str(&httpdate::fmt_http_date(expires)) {
+				headers.insert(EXPIRES, v);
+			}
+		}
+	}
+}
+
+/// Find the error document to serve for a given status code: the document
+/// configured for this code if there is one, or else the default error document
+/// (which is served only for 4xx errors).
+fn find_error_document(website_config: &WebsiteConfig, status: StatusCode) -> Option<&str> {
+	match website_config.error_documents.get(&status.as_u16()) {
+		Some(ed) => Some(ed.as_str()),
+		None if status.is_client_error() => website_config.error_document.as_deref(),
+		None => None,
+	}
+}
+
+/// Find the first routing rule that applies to a key. If `status` is None,
+/// only rules that don't have a condition on the returned HTTP code are considered,
+/// otherwise only rules whose condition matches that code are considered.
+fn find_routing_rule<'a>(
+	rules: &'a [RoutingRule],
+	key: &str,
+	status: Option<StatusCode>,
+) -> Option<&'a RoutingRule> {
+	rules.iter().find(|rule| {
+		let prefix_matches = rule
+			.prefix
+			.as_ref()
+			.map(|p| key.starts_with(p.as_str()))
+			.unwrap_or(true);
+		let code_matches = match (rule.http_error_code, status) {
+			(None, None) => true,
+			(Some(code), Some(status)) => code == status.as_u16(),
+			_ => false,
+		};
+		prefix_matches && code_matches
+	})
+}
+
+/// Compute the target of a redirection by a routing rule
+fn routing_rule_location(rule: &RoutingRule, key: &str, host: &str) -> String {
+	let new_key = match &rule.redirect.replace_key {
+		Some(RedirectReplaceKey::Key(k)) => k.clone(),
+		Some(RedirectReplaceKey::Prefix(p)) => {
+			let matched_len = rule.prefix.as_ref().map(|x| x.len()).unwrap_or(0);
+			format!("{}{}", p, &key[matched_len..])
+		}
+		None => key.to_string(),
+	};
+	let new_path = percent_encoding::utf8_percent_encode(&new_key, PATH_ENCODE_SET);
+
+	match (&rule.redirect.protocol, &rule.redirect.hostname) {
+		(Some(proto), Some(hostname)) => format!("{}://{}/{}", proto, hostname, new_path),
+		(None, Some(hostname)) => format!("//{}/{}", hostname, new_path),
+		(Some(proto), None) => format!("{}://{}/{}", proto, host, new_path),
+		(None, None) => format!("/{}", new_path),
+	}
+}
+
+fn routing_rule_redirect(
+	rule: &RoutingRule,
+	key: &str,
+	host: &str,
+) -> Result<Response<Body>, Error> {
+	let status = StatusCode::from_u16(rule.redirect.http_redirect_code.unwrap_or(301))
+		.ok_or_internal_error("Invalid redirect code in routing rule")?;
+	Ok(Response::builder()
+		.status(status)
+		.header("Location", routing_rule_location(rule, key, host))
+		.body(Body::empty())
+		.unwrap())
+}
+
 #[derive(Debug, PartialEq)]
 enum ImplicitRedirect {
 	No,
@@ -418,6 +761,7 @@ fn path_to_keys<'a>(path: &'a str, index: &str) -> Result<(String, ImplicitRedir
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use garage_model::bucket_table::Redirect;
 
 	#[test]
 	fn path_to_keys_test() -> Result<(), Error> {
@@ -453,4 +797,116 @@ mod tests {
 		assert!(path_to_keys("i/am/relative", "index.html").is_err());
 		Ok(())
 	}
+
+	fn rule(
+		prefix: Option<&str>,
+		http_error_code: Option<u16>,
+		hostname: Option<&str>,
+		protocol: Option<&str>,
+		replace_key: Option<RedirectReplaceKey>,
+	) -> RoutingRule {
+		RoutingRule {
+			prefix: prefix.map(str::to_string),
+			http_error_code,
+			redirect: Redirect {
+				hostname: hostname.map(str::to_string),
+				protocol: protocol.map(str::to_string),
+				http_redirect_code: None,
+				replace_key,
+			},
+		}
+	}
+
+	#[test]
+	fn find_error_document_test() {
+		let website_config = WebsiteConfig {
+			index_document: "index.html".into(),
+			error_document: Some("error.html".into()),
+			directory_listing: false,
+			routing_rules: vec![],
+			error_documents: vec![(404, "404.html".to_string()), (503, "503.html".to_string())]
+				.into_iter()
+				.collect(),
+			cache_control: None,
+			expires_after: None,
+		};
+		assert_eq!(
+			find_error_document(&website_config, StatusCode::NOT_FOUND),
+			Some("404.html")
+		);
+		assert_eq!(
+			find_error_document(&website_config, StatusCode::FORBIDDEN),
+			Some("error.html")
+		);
+		assert_eq!(
+			find_error_document(&website_config, StatusCode::SERVICE_UNAVAILABLE),
+			Some("503.html")
+		);
+		assert_eq!(
+			find_error_document(&website_config, StatusCode::INTERNAL_SERVER_ERROR),
+			None
+		);
+	}
+
+	#[test]
+	fn find_routing_rule_test() {
+		let rules = vec![
+			rule(Some("old/"), None, None, None, None),
+			rule(None, Some(404), None, None, None),
+			rule(Some("docs/"), Some(403), None, None, None),
+		];
+		assert_eq!(find_routing_rule(&rules, "old/a", None), Some(&rules[0]));
+		assert_eq!(find_routing_rule(&rules, "new/a", None), None);
+		assert_eq!(
+			find_routing_rule(&rules, "new/a", Some(StatusCode::NOT_FOUND)),
+			Some(&rules[1])
+		);
+		assert_eq!(
+			find_routing_rule(&rules, "docs/a", Some(StatusCode::FORBIDDEN)),
+			Some(&rules[2])
+		);
+		assert_eq!(
+			find_routing_rule(&rules, "new/a", Some(StatusCode::FORBIDDEN)),
+			None
+		);
+	}
+
+	#[test]
+	fn routing_rule_location_test() {
+		let r = rule(
+			Some("docs/"),
+			None,
+			None,
+			None,
+			Some(RedirectReplaceKey::Prefix("documents/".into())),
+		);
+		assert_eq!(
+			routing_rule_location(&r, "docs/a b.html", "example.com"),
+			"/documents/a%20b.html"
+		);
+
+		let r = rule(
+			None,
+			Some(404),
+			Some("other.tld"),
+			Some("https"),
+			Some(RedirectReplaceKey::Key("404.html".into())),
+		);
+		assert_eq!(
+			routing_rule_location(&r, "missing", "example.com"),
+			"https://other.tld/404.html"
+		);
+
+		let r = rule(None, None, Some("other.tld"), None, None);
+		assert_eq!(
+			routing_rule_location(&r, "page", "example.com"),
+			"//other.tld/page"
+		);
+
+		let r = rule(None, None, None, Some("https"), None);
+		assert_eq!(
+			routing_rule_location(&r, "page", "example.com"),
+			"https://example.com/page"
+		);
+	}
 }